@@ -0,0 +1,85 @@
+-- Double-entry ledger journal
+--
+-- Every movement of money is recorded as a single entry that debits one account
+-- and credits another, so the journal is balanced by construction.
+--
+-- Accounts:
+--   node                 - sats held by the Lightning node (asset)
+--   fees                 - withdrawal fees earned by the service
+--   unallocated          - received sats not assigned to any location
+--   pending_withdrawals  - sats reserved for in-flight wallet withdrawals
+--   location:<id>        - a location's donation pool
+--   user:<id>            - a user's custodial wallet
+
+CREATE TABLE ledger_entries (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    debit_account TEXT NOT NULL,
+    credit_account TEXT NOT NULL,
+    msats INTEGER NOT NULL CHECK (msats > 0),
+    reference_id TEXT,  -- donation, claim or withdrawal this entry belongs to
+    created_at TIMESTAMP NOT NULL,
+    CHECK (debit_account != credit_account)
+);
+
+CREATE INDEX idx_ledger_entries_debit ON ledger_entries(debit_account);
+CREATE INDEX idx_ledger_entries_credit ON ledger_entries(credit_account);
+CREATE INDEX idx_ledger_entries_time ON ledger_entries(created_at);
+
+-- Pending withdrawals only stored amount + fee, the split is needed to book fees
+ALTER TABLE pending_withdrawals ADD COLUMN fee_msats INTEGER NOT NULL DEFAULT 0;
+
+-- Backfill the journal from existing data
+
+-- Donations made directly to a location
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'donation-' || id, 'donation', 'node', 'location:' || location_id, amount_msats, id,
+       COALESCE(received_at, created_at)
+FROM donations
+WHERE status = 'received' AND location_id IS NOT NULL AND invoice NOT LIKE '%-split-%'
+  AND amount_msats > 0;
+
+-- Global donations (and donations to since deleted locations) are unallocated
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'donation-' || id, 'donation', 'node', 'unallocated', amount_msats, id,
+       COALESCE(received_at, created_at)
+FROM donations
+WHERE status = 'received' AND location_id IS NULL AND invoice NOT LIKE '%-split-%'
+  AND amount_msats > 0;
+
+-- Shares of global donations assigned to locations
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'donation_split-' || id, 'donation_split', 'unallocated', 'location:' || location_id,
+       amount_msats, id, COALESCE(received_at, created_at)
+FROM donations
+WHERE status = 'received' AND location_id IS NOT NULL AND invoice LIKE '%-split-%'
+  AND amount_msats > 0;
+
+-- Claims collected into a user's wallet
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'claim-' || id, 'claim', 'location:' || location_id, 'user:' || user_id, msats_claimed, id,
+       claimed_at
+FROM claims
+WHERE user_id IS NOT NULL AND msats_claimed > 0;
+
+-- Claims paid out directly from a location over LNURL-withdraw
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'location_withdraw-' || id, 'location_withdraw', 'location:' || location_id, 'node',
+       msats_claimed, id, claimed_at
+FROM claims
+WHERE user_id IS NULL AND msats_claimed > 0;
+
+-- Balance reserved by in-flight wallet withdrawals
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'withdraw_reserve-' || id, 'withdraw_reserve', 'user:' || user_id, 'pending_withdrawals',
+       msats, id, created_at
+FROM pending_withdrawals
+WHERE status = 'pending' AND msats > 0;
+
+-- Completed wallet withdrawals. The fee split was not recorded before this
+-- migration, so the full amount is booked as leaving the node.
+INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at)
+SELECT 'withdraw-' || id, 'withdraw', 'user:' || user_id, 'node', msats, id,
+       COALESCE(completed_at, created_at)
+FROM pending_withdrawals
+WHERE status = 'completed' AND msats > 0;
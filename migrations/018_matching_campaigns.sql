@@ -0,0 +1,38 @@
+-- Donation matching campaigns
+--
+-- A sponsor prepays a budget (by paying the campaign's funding invoice). While the
+-- campaign is active, every received donation to a location inside its region is
+-- matched from that budget, until the budget is spent or the campaign ends.
+--
+-- Status: 'funding' (waiting for the sponsor's payment), 'active', 'ended'
+-- Region: optional bounding box, NULL bounds match every location
+
+CREATE TABLE matching_campaigns (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    sponsor_name TEXT NOT NULL,
+    budget_msats INTEGER NOT NULL CHECK (budget_msats > 0),
+    spent_msats INTEGER NOT NULL DEFAULT 0,
+    match_percent INTEGER NOT NULL DEFAULT 100 CHECK (match_percent > 0),
+    max_match_msats INTEGER,  -- cap per donation, NULL = only limited by the budget
+    min_latitude REAL,
+    max_latitude REAL,
+    min_longitude REAL,
+    max_longitude REAL,
+    ends_at TIMESTAMP NOT NULL,
+    status TEXT NOT NULL DEFAULT 'funding',
+    funding_invoice TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    funded_at TIMESTAMP,
+    ended_at TIMESTAMP
+);
+
+CREATE INDEX idx_matching_campaigns_status ON matching_campaigns(status);
+
+-- Matching donations are regular received donations to a location, tagged with the
+-- campaign that paid for them and the donation they match.
+ALTER TABLE donations ADD COLUMN campaign_id TEXT REFERENCES matching_campaigns(id);
+ALTER TABLE donations ADD COLUMN matched_donation_id TEXT;
+
+CREATE UNIQUE INDEX idx_donations_campaign_match
+    ON donations(campaign_id, matched_donation_id, location_id);
@@ -0,0 +1,32 @@
+-- Recurring donations
+--
+-- A donor connects their wallet over Nostr Wallet Connect (NIP-47). On every due
+-- date the scheduler creates an invoice and asks the wallet to pay it; the payment
+-- then lands in `donations` like any other donation.
+--
+-- Status: 'active', 'cancelled' (by the donor), 'failed' (too many failed payments)
+-- location_id: NULL for global donations (split among all locations)
+
+CREATE TABLE recurring_donations (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    location_id TEXT REFERENCES locations(id) ON DELETE CASCADE,
+    amount_msats INTEGER NOT NULL CHECK (amount_msats > 0),
+    interval_days INTEGER NOT NULL CHECK (interval_days > 0),
+    nwc_uri TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active',
+    next_payment_at TIMESTAMP NOT NULL,
+    last_payment_at TIMESTAMP,
+    failure_count INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL,
+    cancelled_at TIMESTAMP
+);
+
+CREATE INDEX idx_recurring_donations_due ON recurring_donations(status, next_payment_at);
+CREATE INDEX idx_recurring_donations_location ON recurring_donations(location_id);
+CREATE INDEX idx_recurring_donations_user ON recurring_donations(user_id);
+
+-- Donations created by a recurring donation
+ALTER TABLE donations ADD COLUMN recurring_donation_id TEXT
+    REFERENCES recurring_donations(id) ON DELETE SET NULL;
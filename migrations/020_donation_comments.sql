@@ -0,0 +1,6 @@
+-- Comments sent along with LNURL-pay donations (LUD-12)
+--
+-- Split entries of a global donation carry the donor's comment as well, so it
+-- shows up in every location's donation history.
+
+ALTER TABLE donations ADD COLUMN comment TEXT;
@@ -0,0 +1,42 @@
+-- Receiving into user wallets
+--
+-- Registered users get a Lightning address (username@domain). Every invoice
+-- created for it is tracked here until paid, then credited to the user's wallet
+-- as a 'receive' transaction.
+--
+-- Status: 'created' (waiting for payment), 'received'
+
+CREATE TABLE wallet_invoices (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    invoice TEXT NOT NULL UNIQUE,
+    amount_msats INTEGER NOT NULL CHECK (amount_msats > 0),
+    comment TEXT,  -- LUD-12 comment from the payer
+    status TEXT NOT NULL DEFAULT 'created',
+    created_at TIMESTAMP NOT NULL,
+    received_at TIMESTAMP
+);
+
+CREATE INDEX idx_wallet_invoices_status ON wallet_invoices(status);
+CREATE INDEX idx_wallet_invoices_user ON wallet_invoices(user_id);
+
+-- SQLite can't alter a CHECK constraint, so user_transactions is recreated to
+-- allow the 'receive' type
+CREATE TABLE user_transactions_new (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    location_id TEXT,  -- NULL for withdrawals and receives, set for collections
+    msats INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('collect', 'withdraw', 'receive')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO user_transactions_new (id, user_id, location_id, msats, transaction_type, created_at)
+SELECT id, user_id, location_id, msats, transaction_type, created_at FROM user_transactions;
+
+DROP TABLE user_transactions;
+ALTER TABLE user_transactions_new RENAME TO user_transactions;
+
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user ON user_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user_type ON user_transactions(user_id, transaction_type);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_time ON user_transactions(created_at);
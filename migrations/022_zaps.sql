@@ -0,0 +1,11 @@
+-- Zaps (NIP-57)
+--
+-- A donation paid through a zap carries the payer's signed zap request
+-- (kind 9734). Once the donation is received, a zap receipt (kind 9735) is
+-- published to Nostr relays and its event id recorded, so it is published once.
+
+ALTER TABLE donations ADD COLUMN zap_request TEXT;
+ALTER TABLE donations ADD COLUMN zap_receipt_id TEXT;
+
+CREATE INDEX idx_donations_unpublished_zaps
+    ON donations(status) WHERE zap_request IS NOT NULL AND zap_receipt_id IS NULL;
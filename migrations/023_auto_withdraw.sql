@@ -0,0 +1,21 @@
+-- Automatic withdrawals
+--
+-- A user saves a Lightning address and a threshold. Whenever a claim brings their
+-- wallet balance to the threshold or above, the auto-withdraw service withdraws
+-- the whole balance to that address through the pending withdrawal flow.
+--
+-- Failed withdrawals are retried with a backoff (retry_at) and the setting is
+-- disabled after too many failures in a row, until the user saves it again.
+
+CREATE TABLE auto_withdraw_settings (
+    user_id TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    ln_address TEXT NOT NULL,
+    threshold_msats INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    failure_count INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    retry_at TIMESTAMP,
+    last_withdrawal_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
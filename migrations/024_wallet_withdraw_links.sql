@@ -0,0 +1,17 @@
+-- Reusable LNURL-withdraw links for user wallets
+--
+-- Unlike the one-hour links on the wallet page, these links stay valid until the
+-- user revokes them, so wallets can store them, check the balance (LUD-14) and
+-- withdraw any amount up to the balance. A user has at most one active link.
+
+CREATE TABLE wallet_withdraw_links (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    link_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP
+);
+
+CREATE UNIQUE INDEX idx_wallet_withdraw_links_active
+    ON wallet_withdraw_links(user_id) WHERE revoked_at IS NULL;
@@ -0,0 +1,11 @@
+-- Payment hash of the invoice each wallet withdrawal pays
+--
+-- The invoice policy refuses an invoice whose payment hash is already being
+-- paid or was paid, and the unique index backs that up against races. Failed
+-- withdrawals don't count, so the same invoice can be retried. NULL for older
+-- withdrawals.
+
+ALTER TABLE pending_withdrawals ADD COLUMN payment_hash TEXT;
+
+CREATE UNIQUE INDEX idx_pending_withdrawals_payment_hash
+    ON pending_withdrawals(payment_hash) WHERE status != 'failed';
@@ -0,0 +1,3 @@
+-- Part of a withdrawal's reserved fee returned to the wallet once the fee
+-- actually paid was known. pending_withdrawals.msats stays the reserved total.
+ALTER TABLE pending_withdrawals ADD COLUMN fee_refund_msats INTEGER NOT NULL DEFAULT 0;
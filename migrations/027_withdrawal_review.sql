@@ -0,0 +1,36 @@
+-- Manual review of wallet withdrawals
+--
+-- Withdrawals over a configured limit are created as 'held' instead of being
+-- paid. Like 'pending' they reserve the balance, until an admin approves them
+-- (they become 'pending' and are paid) or rejects them (they become 'failed'
+-- and the balance is released).
+--
+-- SQLite can't alter a CHECK constraint, so pending_withdrawals is recreated to
+-- allow the 'held' status
+
+CREATE TABLE pending_withdrawals_new (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    msats INTEGER NOT NULL,
+    invoice TEXT NOT NULL,  -- BOLT11 invoice being paid
+    status TEXT NOT NULL CHECK (status IN ('pending', 'completed', 'failed', 'held')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at TIMESTAMP,
+    fee_msats INTEGER NOT NULL DEFAULT 0,
+    payment_hash TEXT,
+    fee_refund_msats INTEGER NOT NULL DEFAULT 0,
+    hold_reason TEXT,  -- why the withdrawal was held for review
+    reviewed_by TEXT,  -- admin who approved or rejected it
+    reviewed_at TIMESTAMP
+);
+
+INSERT INTO pending_withdrawals_new (id, user_id, msats, invoice, status, created_at, completed_at, fee_msats, payment_hash, fee_refund_msats)
+SELECT id, user_id, msats, invoice, status, created_at, completed_at, fee_msats, payment_hash, fee_refund_msats FROM pending_withdrawals;
+
+DROP TABLE pending_withdrawals;
+ALTER TABLE pending_withdrawals_new RENAME TO pending_withdrawals;
+
+CREATE INDEX IF NOT EXISTS idx_pending_withdrawals_user_status ON pending_withdrawals(user_id, status);
+CREATE INDEX IF NOT EXISTS idx_pending_withdrawals_created ON pending_withdrawals(created_at);
+CREATE UNIQUE INDEX idx_pending_withdrawals_payment_hash
+    ON pending_withdrawals(payment_hash) WHERE status != 'failed';
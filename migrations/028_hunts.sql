@@ -0,0 +1,64 @@
+-- Hunts: trails of locations with a completion bonus
+--
+-- A hunt groups locations into a trail. In an ordered hunt the stops have to be
+-- claimed in sequence. Progress comes from the claimed scans of each user. The
+-- user who claims the last stop gets the completion bonus, paid from the hunt's
+-- own donation pool (donations with hunt_id set) as far as the pool allows.
+
+CREATE TABLE hunts (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    ordered BOOLEAN NOT NULL DEFAULT 1,
+    completion_bonus_msats INTEGER NOT NULL CHECK (completion_bonus_msats > 0),
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE hunt_locations (
+    hunt_id TEXT NOT NULL REFERENCES hunts(id) ON DELETE CASCADE,
+    location_id TEXT NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,  -- 1-based order of the stop on the trail
+    PRIMARY KEY (hunt_id, location_id),
+    UNIQUE (hunt_id, position)
+);
+
+CREATE INDEX idx_hunt_locations_location ON hunt_locations(location_id);
+
+-- One row per user who finished a hunt, bonus_msats can be 0 if the pool was empty
+CREATE TABLE hunt_completions (
+    id TEXT PRIMARY KEY,
+    hunt_id TEXT NOT NULL REFERENCES hunts(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    bonus_msats INTEGER NOT NULL,
+    completed_at TIMESTAMP NOT NULL,
+    UNIQUE (hunt_id, user_id)
+);
+
+CREATE INDEX idx_hunt_completions_hunt_time ON hunt_completions(hunt_id, completed_at);
+
+-- Donations to a hunt's bonus pool, location_id is NULL for these
+ALTER TABLE donations ADD COLUMN hunt_id TEXT REFERENCES hunts(id);
+
+CREATE INDEX idx_donations_hunt ON donations(hunt_id);
+
+-- SQLite can't alter a CHECK constraint, so user_transactions is recreated to
+-- allow the 'hunt_bonus' type
+CREATE TABLE user_transactions_new (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    location_id TEXT,  -- NULL for withdrawals, receives and hunt bonuses, set for collections
+    msats INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('collect', 'withdraw', 'receive', 'hunt_bonus')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO user_transactions_new (id, user_id, location_id, msats, transaction_type, created_at)
+SELECT id, user_id, location_id, msats, transaction_type, created_at FROM user_transactions;
+
+DROP TABLE user_transactions;
+ALTER TABLE user_transactions_new RENAME TO user_transactions;
+
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user ON user_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user_type ON user_transactions(user_id, transaction_type);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_time ON user_transactions(created_at);
@@ -0,0 +1,26 @@
+-- Activation schedules for time-boxed events
+--
+-- A schedule belongs either to a single location or to a hunt, in which case it
+-- applies to all of the hunt's stops. A location with schedules can only be
+-- claimed inside one of their windows. Recurring schedules repeat the window
+-- [starts_at, ends_at) every day or every week.
+--
+-- Outside its windows an active location is switched to status 'scheduled' by
+-- the schedule service, which hides it like a deactivated location, and back to
+-- 'active' when the next window opens.
+
+CREATE TABLE schedules (
+    id TEXT PRIMARY KEY,
+    location_id TEXT REFERENCES locations(id) ON DELETE CASCADE,
+    hunt_id TEXT REFERENCES hunts(id) ON DELETE CASCADE,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    recurrence TEXT NOT NULL DEFAULT 'once' CHECK (recurrence IN ('once', 'daily', 'weekly')),
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    CHECK ((location_id IS NULL) != (hunt_id IS NULL)),
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX idx_schedules_location ON schedules(location_id);
+CREATE INDEX idx_schedules_hunt ON schedules(hunt_id);
@@ -0,0 +1,13 @@
+-- Per-location overrides of the global claim rules
+--
+-- NULL keeps the global rule, 0 turns it off for the location.
+
+CREATE TABLE location_claim_rules (
+    location_id TEXT PRIMARY KEY REFERENCES locations(id) ON DELETE CASCADE,
+    cooldown_days INTEGER CHECK (cooldown_days >= 0),
+    max_claims_per_month INTEGER CHECK (max_claims_per_month >= 0),
+    first_claim_bonus_percent INTEGER CHECK (first_claim_bonus_percent >= 0),
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_claims_user_location ON claims(user_id, location_id);
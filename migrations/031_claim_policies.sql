@@ -0,0 +1,8 @@
+-- Claim policy per location
+--
+-- Decides which scans within the claim window can claim and what share of the
+-- claimable balance they get: 'last_scanner' (the original race), 'first_scanner',
+-- 'split' among everyone who scanned, or 'fixed_fraction' per scan.
+
+ALTER TABLE locations ADD COLUMN claim_policy TEXT NOT NULL DEFAULT 'last_scanner'
+    CHECK (claim_policy IN ('last_scanner', 'first_scanner', 'split', 'fixed_fraction'));
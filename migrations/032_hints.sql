@@ -0,0 +1,56 @@
+-- Hints: progressive clues for finding a location
+--
+-- Each hint is unlocked one of three ways:
+-- - 'time': for everyone, unlock_after_days after the location was created
+-- - 'payment': per user, by paying price_msats from their wallet into the location pool
+-- - 'riddle': per user, by answering the riddle
+--
+-- Per-user unlocks are kept in hint_unlocks. Time hints need no unlock rows.
+
+CREATE TABLE hints (
+    id TEXT PRIMARY KEY,
+    location_id TEXT NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,  -- 1-based order of the hint on the location page
+    text TEXT NOT NULL,
+    unlock_kind TEXT NOT NULL CHECK (unlock_kind IN ('time', 'payment', 'riddle')),
+    unlock_after_days INTEGER CHECK (unlock_after_days >= 0),
+    price_msats INTEGER CHECK (price_msats > 0),
+    riddle TEXT,
+    riddle_answer TEXT,
+    created_at TIMESTAMP NOT NULL,
+    CHECK (unlock_kind != 'time' OR unlock_after_days IS NOT NULL),
+    CHECK (unlock_kind != 'payment' OR price_msats IS NOT NULL),
+    CHECK (unlock_kind != 'riddle' OR (riddle IS NOT NULL AND riddle_answer IS NOT NULL))
+);
+
+CREATE INDEX idx_hints_location ON hints(location_id, position);
+
+CREATE TABLE hint_unlocks (
+    hint_id TEXT NOT NULL REFERENCES hints(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    unlocked_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (hint_id, user_id)
+);
+
+CREATE INDEX idx_hint_unlocks_user ON hint_unlocks(user_id);
+
+-- SQLite can't alter a CHECK constraint, so user_transactions is recreated to
+-- allow the 'hint' type for hints paid from the wallet
+CREATE TABLE user_transactions_new (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    location_id TEXT,  -- NULL for withdrawals, receives and hunt bonuses, set for collections and hints
+    msats INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('collect', 'withdraw', 'receive', 'hunt_bonus', 'hint')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO user_transactions_new (id, user_id, location_id, msats, transaction_type, created_at)
+SELECT id, user_id, location_id, msats, transaction_type, created_at FROM user_transactions;
+
+DROP TABLE user_transactions;
+ALTER TABLE user_transactions_new RENAME TO user_transactions;
+
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user ON user_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user_type ON user_transactions(user_id, transaction_type);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_time ON user_transactions(created_at);
@@ -0,0 +1,12 @@
+-- Disclosure of a location's position per location
+--
+-- 'exact' publishes the coordinates, 'fuzzed' only a search area and 'hidden'
+-- nothing until the hunter unlocked one of the location's hints. The search
+-- area is a circle of search_radius_m around a point randomly offset from the
+-- location, drawn once so it stays put.
+
+ALTER TABLE locations ADD COLUMN disclosure TEXT NOT NULL DEFAULT 'exact'
+    CHECK (disclosure IN ('exact', 'fuzzed', 'hidden'));
+ALTER TABLE locations ADD COLUMN search_radius_m INTEGER CHECK (search_radius_m > 0);
+ALTER TABLE locations ADD COLUMN search_latitude REAL;
+ALTER TABLE locations ADD COLUMN search_longitude REAL;
@@ -0,0 +1,40 @@
+-- Log book per location
+--
+-- Hunters leave logs whether or not they claimed anything: 'found',
+-- 'not_found', 'needs_maintenance' or a free text 'note', with optional photos.
+-- The location owner and admins moderate logs by hiding them.
+
+CREATE TABLE location_logs (
+    id TEXT PRIMARY KEY,
+    location_id TEXT NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('found', 'not_found', 'needs_maintenance', 'note')),
+    text TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL,
+    hidden_at TIMESTAMP,  -- set when a moderator hid the log
+    hidden_by TEXT
+);
+
+CREATE INDEX idx_location_logs_location ON location_logs(location_id, created_at);
+CREATE INDEX idx_location_logs_user ON location_logs(user_id);
+
+CREATE TABLE location_log_photos (
+    id TEXT PRIMARY KEY,
+    log_id TEXT NOT NULL REFERENCES location_logs(id) ON DELETE CASCADE,
+    file_path TEXT NOT NULL,
+    uploaded_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_location_log_photos_log ON location_log_photos(log_id);
+
+-- In-app notifications, e.g. maintenance requests for a location's owner
+CREATE TABLE notifications (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    location_id TEXT REFERENCES locations(id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    read_at TIMESTAMP
+);
+
+CREATE INDEX idx_notifications_user ON notifications(user_id, created_at);
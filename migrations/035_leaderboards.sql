@@ -0,0 +1,38 @@
+-- Leaderboards
+--
+-- Hunters are ranked by sats collected, unique locations found and first-finds,
+-- per week, month and season, globally and per region. Rankings are computed
+-- by the leaderboard service and cached in leaderboard_entries.
+
+-- Named bounding boxes set up by admins for regional leaderboards
+CREATE TABLE regions (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    min_latitude REAL NOT NULL,
+    max_latitude REAL NOT NULL,
+    min_longitude REAL NOT NULL,
+    max_longitude REAL NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- Anonymous users only show up on leaderboards after opting in
+ALTER TABLE users ADD COLUMN leaderboard_opt_in INTEGER NOT NULL DEFAULT 0;
+
+-- Cached rankings, replaced on every refresh
+-- Period: 'week', 'month', 'season'
+-- Metric: 'sats' (value in sats), 'locations', 'first_finds'
+-- Region: '' for the global leaderboard
+CREATE TABLE leaderboard_entries (
+    period TEXT NOT NULL,
+    region_id TEXT NOT NULL DEFAULT '',
+    metric TEXT NOT NULL,
+    rank INTEGER NOT NULL,
+    user_id TEXT NOT NULL,
+    display_name TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    computed_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (period, region_id, metric, user_id)
+);
+
+CREATE INDEX idx_leaderboard_entries_rank ON leaderboard_entries(period, region_id, metric, rank);
+CREATE INDEX idx_leaderboard_entries_user ON leaderboard_entries(user_id);
@@ -0,0 +1,23 @@
+-- Achievements and badges
+--
+-- Badges are awarded by rules over a user's scans, claims, donations and created
+-- locations, and kept once earned.
+
+CREATE TABLE user_badges (
+    user_id TEXT NOT NULL,
+    badge TEXT NOT NULL,
+    earned_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (user_id, badge)
+);
+
+-- When the location last went live, for finds right after activation
+ALTER TABLE locations ADD COLUMN activated_at TIMESTAMP;
+
+-- Donor of donations made by a known user
+ALTER TABLE donations ADD COLUMN user_id TEXT;
+
+CREATE INDEX idx_donations_user ON donations(user_id);
+
+UPDATE donations
+SET user_id = (SELECT r.user_id FROM recurring_donations r WHERE r.id = donations.recurring_donation_id)
+WHERE recurring_donation_id IS NOT NULL;
@@ -0,0 +1,74 @@
+-- Teams and shared team wallets
+--
+-- Users are in at most one team and join it with the team's invite code. When a
+-- team enables its wallet, claims of its members are collected into the team
+-- wallet instead of their own. Owners and treasurers withdraw from the team
+-- wallet into their own wallet, from where it can be withdrawn as usual.
+
+CREATE TABLE teams (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    invite_code TEXT NOT NULL UNIQUE,
+    wallet_enabled BOOLEAN NOT NULL DEFAULT 0,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- Role: 'owner', 'treasurer', 'member'
+CREATE TABLE team_members (
+    team_id TEXT NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL UNIQUE,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'treasurer', 'member')),
+    joined_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (team_id, user_id)
+);
+
+-- Team wallet ledger, kept after the team is deleted
+-- user_id is the member who collected or withdrew
+CREATE TABLE team_transactions (
+    id TEXT PRIMARY KEY,
+    team_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    location_id TEXT,  -- set for collections
+    msats INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('collect', 'withdraw')),
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_team_transactions_team ON team_transactions(team_id, created_at);
+
+-- SQLite can't alter a CHECK constraint, so user_transactions is recreated to
+-- allow the 'team_payout' type for sats withdrawn from a team wallet
+CREATE TABLE user_transactions_new (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    location_id TEXT,  -- NULL for withdrawals, receives, hunt bonuses and team payouts, set for collections and hints
+    msats INTEGER NOT NULL,
+    transaction_type TEXT NOT NULL CHECK (transaction_type IN ('collect', 'withdraw', 'receive', 'hunt_bonus', 'hint', 'team_payout')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO user_transactions_new (id, user_id, location_id, msats, transaction_type, created_at)
+SELECT id, user_id, location_id, msats, transaction_type, created_at FROM user_transactions;
+
+DROP TABLE user_transactions;
+ALTER TABLE user_transactions_new RENAME TO user_transactions;
+
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user ON user_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_user_type ON user_transactions(user_id, transaction_type);
+CREATE INDEX IF NOT EXISTS idx_user_transactions_time ON user_transactions(created_at);
+
+-- Cached team rankings, replaced on every refresh like leaderboard_entries
+CREATE TABLE team_leaderboard_entries (
+    period TEXT NOT NULL,
+    region_id TEXT NOT NULL DEFAULT '',
+    metric TEXT NOT NULL,
+    rank INTEGER NOT NULL,
+    team_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    computed_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (period, region_id, metric, team_id)
+);
+
+CREATE INDEX idx_team_leaderboard_entries_rank ON team_leaderboard_entries(period, region_id, metric, rank);
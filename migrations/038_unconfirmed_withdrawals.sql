@@ -0,0 +1,7 @@
+-- Withdrawals with an unknown payment outcome
+--
+-- When paying a withdrawal fails ambiguously, e.g. with a timeout, the payment
+-- may still go through. Such withdrawals stay pending with their balance reserved
+-- and are marked here, until the Lightning backend reports whether they were paid.
+
+ALTER TABLE pending_withdrawals ADD COLUMN unconfirmed_at TIMESTAMP;
@@ -0,0 +1,26 @@
+-- Proof-of-liabilities snapshots
+--
+-- The solvency service periodically publishes the Merkle-sum root over user
+-- wallet balances. The balances behind each root are kept, so inclusion proofs
+-- are issued against a published root rather than one that moves with every
+-- transaction.
+
+CREATE TABLE liability_snapshots (
+    id TEXT PRIMARY KEY,
+    -- Hex encoded root hash of the Merkle-sum tree
+    root_hash TEXT NOT NULL,
+    -- Sum of all user wallet balances in the tree
+    sum_msats INTEGER NOT NULL,
+    user_count INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_liability_snapshots_created ON liability_snapshots(created_at);
+
+-- Leaves of each snapshot's tree: users with a positive balance
+CREATE TABLE liability_snapshot_balances (
+    snapshot_id TEXT NOT NULL REFERENCES liability_snapshots(id),
+    user_id TEXT NOT NULL,
+    balance_msats INTEGER NOT NULL,
+    PRIMARY KEY (snapshot_id, user_id)
+);
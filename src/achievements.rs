@@ -0,0 +1,405 @@
+//! Achievements and badges.
+//!
+//! This module handles:
+//! - The badges hunters can earn and the rules awarding them
+//! - Replaying a user's history of scans, claims, donations and created
+//!   locations to find the badges it earns
+//! - Checking a user's badges after each of these events, and awarding the
+//!   new ones along with an in-app notification
+//!
+//! A rule is a plain function over the event being replayed and the history up
+//! to and including it, so adding a badge means adding a `Badge` variant and a
+//! `Rule` to `RULES`. Badges are kept once earned, even if a later change to
+//! the history (like a deleted location) would no longer earn them.
+
+use crate::db::Database;
+use crate::models::Donation;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+/// Message to have the AchievementService check a user's badges
+pub struct CheckAchievements {
+    pub user_id: String,
+}
+
+/// Something a user did that can earn a badge
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Scanned someone else's location. `activated_at` is when the location
+    /// last went live.
+    Scan {
+        location_id: String,
+        at: DateTime<Utc>,
+        activated_at: Option<DateTime<Utc>>,
+    },
+    Claim {
+        location_id: String,
+        msats: i64,
+        at: DateTime<Utc>,
+    },
+    /// A received donation
+    Donation { msats: i64, at: DateTime<Utc> },
+    LocationCreated {
+        location_id: String,
+        at: DateTime<Utc>,
+    },
+}
+
+impl Event {
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            Self::Scan { at, .. }
+            | Self::Claim { at, .. }
+            | Self::Donation { at, .. }
+            | Self::LocationCreated { at, .. } => *at,
+        }
+    }
+}
+
+/// What a user did so far
+#[derive(Debug, Default)]
+pub struct History {
+    pub locations_found: HashSet<String>,
+    pub msats_claimed: i64,
+    pub msats_donated: i64,
+    pub locations_created: usize,
+}
+
+impl History {
+    pub fn apply(&mut self, event: &Event) {
+        match event {
+            Event::Scan { location_id, .. } => {
+                self.locations_found.insert(location_id.clone());
+            }
+            Event::Claim { msats, .. } => self.msats_claimed += msats,
+            Event::Donation { msats, .. } => self.msats_donated += msats,
+            Event::LocationCreated { .. } => self.locations_created += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    FirstFind,
+    TenLocations,
+    EarlyBird,
+    Stacker,
+    Patron,
+    Creator,
+}
+
+impl Badge {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FirstFind => "first_find",
+            Self::TenLocations => "ten_locations",
+            Self::EarlyBird => "early_bird",
+            Self::Stacker => "stacker",
+            Self::Patron => "patron",
+            Self::Creator => "creator",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::FirstFind => "FIRST FIND",
+            Self::TenLocations => "EXPLORER",
+            Self::EarlyBird => "EARLY BIRD",
+            Self::Stacker => "STACKER",
+            Self::Patron => "PATRON",
+            Self::Creator => "CREATOR",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::FirstFind => "Found a first location",
+            Self::TenLocations => "Found 10 different locations",
+            Self::EarlyBird => "Found a location within an hour of it going live",
+            Self::Stacker => "Collected 10k sats",
+            Self::Patron => "Donated 10k sats",
+            Self::Creator => "Hid a first location",
+        }
+    }
+
+    /// Font Awesome icon
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::FirstFind => "fa-magnifying-glass-location",
+            Self::TenLocations => "fa-compass",
+            Self::EarlyBird => "fa-stopwatch",
+            Self::Stacker => "fa-bolt",
+            Self::Patron => "fa-hand-holding-heart",
+            Self::Creator => "fa-location-dot",
+        }
+    }
+}
+
+impl std::fmt::Display for Badge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Badge {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first_find" => Ok(Self::FirstFind),
+            "ten_locations" => Ok(Self::TenLocations),
+            "early_bird" => Ok(Self::EarlyBird),
+            "stacker" => Ok(Self::Stacker),
+            "patron" => Ok(Self::Patron),
+            "creator" => Ok(Self::Creator),
+            _ => Err(anyhow::anyhow!("Invalid badge: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Badge {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A badge and when it's earned, given the event being replayed and the
+/// history including it
+pub struct Rule {
+    pub badge: Badge,
+    pub earned: fn(&Event, &History) -> bool,
+}
+
+/// How soon after a location went live a scan earns the early bird badge
+const EARLY_BIRD_MINUTES: i64 = 60;
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        badge: Badge::FirstFind,
+        earned: |_, history| !history.locations_found.is_empty(),
+    },
+    Rule {
+        badge: Badge::TenLocations,
+        earned: |_, history| history.locations_found.len() >= 10,
+    },
+    Rule {
+        badge: Badge::EarlyBird,
+        earned: |event, _| match event {
+            Event::Scan {
+                at,
+                activated_at: Some(activated_at),
+                ..
+            } => {
+                *at >= *activated_at && *at - *activated_at <= Duration::minutes(EARLY_BIRD_MINUTES)
+            }
+            _ => false,
+        },
+    },
+    Rule {
+        badge: Badge::Stacker,
+        earned: |_, history| history.msats_claimed >= 10_000_000,
+    },
+    Rule {
+        badge: Badge::Patron,
+        earned: |_, history| history.msats_donated >= 10_000_000,
+    },
+    Rule {
+        badge: Badge::Creator,
+        earned: |_, history| history.locations_created > 0,
+    },
+];
+
+/// Badges earned by a history of events in the order they happened, each
+/// with the time of the event that earned it
+pub fn replay(events: &[Event]) -> Vec<(Badge, DateTime<Utc>)> {
+    let mut history = History::default();
+    let mut earned: Vec<(Badge, DateTime<Utc>)> = Vec::new();
+    for event in events {
+        history.apply(event);
+        for rule in RULES {
+            if !earned.iter().any(|(badge, _)| *badge == rule.badge)
+                && (rule.earned)(event, &history)
+            {
+                earned.push((rule.badge, event.at()));
+            }
+        }
+    }
+    earned
+}
+
+/// Replay a user's history and award the badges they don't have yet.
+/// Returns the newly awarded badges.
+pub async fn check_user(db: &Database, user_id: &str) -> Result<Vec<Badge>> {
+    let events = db.get_achievement_events(user_id).await?;
+    db.award_badges(user_id, &replay(&events)).await
+}
+
+/// Background service that checks badges after scans, claims, donations and
+/// location creation
+pub struct AchievementService {
+    db: Arc<Database>,
+    /// Sender for badge check requests
+    sender: mpsc::UnboundedSender<CheckAchievements>,
+    /// Receiver for badge check requests (wrapped in Option for take())
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<CheckAchievements>>>,
+}
+
+impl AchievementService {
+    pub fn new(db: Arc<Database>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            db,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    /// Get a sender clone to request badge checks
+    pub fn get_sender(&self) -> mpsc::UnboundedSender<CheckAchievements> {
+        self.sender.clone()
+    }
+
+    /// Start the service - checks a user's badges whenever asked to, and the
+    /// donor's when a donation made by a known user is received
+    pub async fn start(self: Arc<Self>, mut donations: broadcast::Receiver<Donation>) {
+        // Take the receiver (can only be done once)
+        let receiver = {
+            let mut guard = self.receiver.lock().await;
+            guard.take()
+        };
+
+        let Some(mut receiver) = receiver else {
+            tracing::error!("AchievementService receiver already taken");
+            return;
+        };
+
+        loop {
+            let user_id = tokio::select! {
+                Some(check) = receiver.recv() => check.user_id,
+                donation = donations.recv() => match donation {
+                    Ok(Donation { user_id: Some(user_id), .. }) => user_id,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Achievement service missed {} received donations", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+
+            match check_user(&self.db, &user_id).await {
+                Ok(badges) => {
+                    for badge in badges {
+                        tracing::info!("User {} earned the {} badge", user_id, badge);
+                    }
+                }
+                Err(e) => tracing::error!("Failed to check badges of user {}: {}", user_id, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+            + Duration::minutes(minutes)
+    }
+
+    fn scan(location_id: &str, minutes: i64) -> Event {
+        Event::Scan {
+            location_id: location_id.to_string(),
+            at: at(minutes),
+            activated_at: None,
+        }
+    }
+
+    fn badges(events: &[Event]) -> Vec<Badge> {
+        replay(events).into_iter().map(|(badge, _)| badge).collect()
+    }
+
+    #[test]
+    fn test_replay_finds() {
+        // Ten scans of nine locations isn't enough for the explorer badge
+        let mut events: Vec<Event> = (0..9).map(|i| scan(&format!("loc-{}", i), i)).collect();
+        events.push(scan("loc-0", 9));
+        assert_eq!(replay(&events), vec![(Badge::FirstFind, at(0))]);
+
+        events.push(scan("loc-9", 10));
+        assert_eq!(
+            replay(&events),
+            vec![(Badge::FirstFind, at(0)), (Badge::TenLocations, at(10))]
+        );
+    }
+
+    #[test]
+    fn test_replay_early_bird() {
+        let early = |minutes| Event::Scan {
+            location_id: "loc".to_string(),
+            at: at(minutes),
+            activated_at: Some(at(0)),
+        };
+        assert!(!badges(&[early(61)]).contains(&Badge::EarlyBird));
+        assert!(badges(&[early(60)]).contains(&Badge::EarlyBird));
+        // Scans from before the location last went live don't count
+        assert!(!badges(&[early(-5)]).contains(&Badge::EarlyBird));
+    }
+
+    #[test]
+    fn test_replay_sats() {
+        let claim = |msats, minutes| Event::Claim {
+            location_id: "loc".to_string(),
+            msats,
+            at: at(minutes),
+        };
+        let donation = |msats, minutes| Event::Donation {
+            msats,
+            at: at(minutes),
+        };
+
+        // Totals add up over time
+        let events = [
+            claim(6_000_000, 0),
+            donation(9_000_000, 1),
+            claim(4_000_000, 2),
+            donation(1_000_000, 3),
+        ];
+        assert_eq!(
+            replay(&events),
+            vec![(Badge::Stacker, at(2)), (Badge::Patron, at(3))]
+        );
+    }
+
+    #[test]
+    fn test_replay_creator() {
+        let created = |minutes| Event::LocationCreated {
+            location_id: format!("loc-{}", minutes),
+            at: at(minutes),
+        };
+        // Each badge is only earned once
+        assert_eq!(
+            replay(&[created(0), created(1)]),
+            vec![(Badge::Creator, at(0))]
+        );
+        assert!(replay(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        for rule in RULES {
+            assert_eq!(rule.badge.as_str().parse::<Badge>().unwrap(), rule.badge);
+        }
+        assert!("champion".parse::<Badge>().is_err());
+    }
+}
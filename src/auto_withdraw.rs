@@ -0,0 +1,343 @@
+//! Automatic withdrawals to a saved Lightning address.
+//!
+//! This module handles:
+//! - Checking a user's balance against their auto-withdraw threshold after a claim,
+//!   and periodically for everyone with the setting enabled
+//! - Withdrawing the whole balance to the saved Lightning address through the
+//!   pending withdrawal flow, like a withdrawal from the wallet page
+//! - Backing off after failed withdrawals, and disabling the setting after too many
+//!   in a row. The last error is shown to the user on the wallet page and sent as
+//!   a notification.
+//! - Keeping withdrawals whose payment outcome is unknown (e.g. after a timeout)
+//!   reserved, and settling them once the Lightning backend knows if they were paid
+
+use crate::db::Database;
+use crate::fees::FeePolicy;
+use crate::invoice_policy::InvoicePolicy;
+use crate::lightning::{Lightning, PaymentStatus};
+use crate::lnurl;
+use crate::models::AutoWithdrawSetting;
+use crate::recurring::retry_delay;
+use crate::withdraw_limits::WithdrawLimits;
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// How often every enabled setting is checked, which also retries failures
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// Failed withdrawals in a row after which the setting is disabled
+pub const MAX_FAILURES: i64 = 5;
+/// Smallest threshold a user can set, so fees never eat most of a withdrawal
+pub const MIN_THRESHOLD_SATS: i64 = 100;
+
+/// Message to have the AutoWithdrawService check a user's balance right away
+pub struct CheckAutoWithdraw {
+    pub user_id: String,
+}
+
+/// Background service that sweeps wallet balances to saved Lightning addresses
+pub struct AutoWithdrawService {
+    db: Arc<Database>,
+    lightning: Arc<dyn Lightning>,
+    invoice_policy: InvoicePolicy,
+    fee_policy: FeePolicy,
+    withdraw_limits: WithdrawLimits,
+    /// Sender for balance check requests
+    sender: mpsc::UnboundedSender<CheckAutoWithdraw>,
+    /// Receiver for balance check requests (wrapped in Option for take())
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<CheckAutoWithdraw>>>,
+    /// Users with a withdrawal in progress (to prevent concurrent sweeps)
+    active_users: Mutex<HashSet<String>>,
+}
+
+impl AutoWithdrawService {
+    pub fn new(
+        db: Arc<Database>,
+        lightning: Arc<dyn Lightning>,
+        invoice_policy: InvoicePolicy,
+        fee_policy: FeePolicy,
+        withdraw_limits: WithdrawLimits,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            db,
+            lightning,
+            invoice_policy,
+            fee_policy,
+            withdraw_limits,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            active_users: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Get a sender clone to request balance checks
+    pub fn get_sender(&self) -> mpsc::UnboundedSender<CheckAutoWithdraw> {
+        self.sender.clone()
+    }
+
+    /// Start the auto-withdraw service - checks every enabled setting each minute
+    /// (starting right away) and a user's balance whenever asked to
+    pub async fn start(self: Arc<Self>) {
+        // Take the receiver (can only be done once)
+        let receiver = {
+            let mut guard = self.receiver.lock().await;
+            guard.take()
+        };
+
+        let Some(mut receiver) = receiver else {
+            tracing::error!("AutoWithdrawService receiver already taken");
+            return;
+        };
+
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.process_due().await {
+                        tracing::error!("Failed to process auto-withdrawals: {}", e);
+                    }
+                }
+                Some(check) = receiver.recv() => {
+                    let service = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = service.process_user(&check.user_id).await {
+                            tracing::error!(
+                                "Failed to process auto-withdrawal for user {}: {}",
+                                check.user_id,
+                                e
+                            );
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// Settle withdrawals with an unknown outcome, then check every enabled setting
+    /// that isn't backing off. Returns the number of withdrawals made.
+    pub async fn process_due(&self) -> Result<usize> {
+        self.settle_unconfirmed().await?;
+
+        let due = self.db.list_due_auto_withdraw_settings().await?;
+        let mut withdrawn = 0;
+
+        for setting in due {
+            if self.process(&setting).await?.is_some() {
+                withdrawn += 1;
+            }
+        }
+
+        Ok(withdrawn)
+    }
+
+    /// Complete or release withdrawals whose payment outcome was unknown, once the
+    /// Lightning backend knows it. This covers every wallet withdrawal, not just
+    /// automatic ones. Returns the number settled.
+    pub async fn settle_unconfirmed(&self) -> Result<usize> {
+        let mut settled = 0;
+
+        for withdrawal in self.db.list_unconfirmed_withdrawals().await? {
+            let amount_sats = (withdrawal.msats - withdrawal.fee_msats) / 1000;
+            let message = match self.lightning.payment_status(&withdrawal.invoice).await {
+                Ok(PaymentStatus::Succeeded { fee_msats }) => {
+                    self.db
+                        .complete_pending_withdrawal(&withdrawal.id, fee_msats)
+                        .await?;
+                    format!("Withdrawal of {} sats was paid", amount_sats)
+                }
+                Ok(PaymentStatus::Failed) => {
+                    self.db.fail_pending_withdrawal(&withdrawal.id).await?;
+                    format!(
+                        "Withdrawal of {} sats failed, the sats are back in your wallet",
+                        amount_sats
+                    )
+                }
+                Ok(PaymentStatus::Unknown) => continue,
+                Err(e) => {
+                    tracing::warn!("Failed to look up withdrawal {}: {}", withdrawal.id, e);
+                    continue;
+                }
+            };
+            self.db
+                .create_notification(&withdrawal.user_id, &message)
+                .await?;
+            settled += 1;
+        }
+
+        Ok(settled)
+    }
+
+    /// Check one user's balance against their setting. Returns the withdrawn
+    /// amount in msats, if a withdrawal was made.
+    pub async fn process_user(&self, user_id: &str) -> Result<Option<i64>> {
+        match self.db.get_auto_withdraw_setting(user_id).await? {
+            Some(setting) if setting.is_due(Utc::now()) => self.process(&setting).await,
+            _ => Ok(None),
+        }
+    }
+
+    /// Withdraw if the balance reached the threshold and record the outcome
+    async fn process(&self, setting: &AutoWithdrawSetting) -> Result<Option<i64>> {
+        {
+            let mut active = self.active_users.lock().await;
+            if !active.insert(setting.user_id.clone()) {
+                tracing::debug!("Auto-withdrawal already in progress, skipping");
+                return Ok(None);
+            }
+        }
+
+        let result = self.sweep(setting).await;
+
+        self.active_users.lock().await.remove(&setting.user_id);
+
+        match result {
+            Ok(Some(withdrawn_msats)) => {
+                self.db
+                    .mark_auto_withdraw_succeeded(&setting.user_id)
+                    .await?;
+                tracing::info!(
+                    "Auto-withdrew {} sats for user {} to {}",
+                    withdrawn_msats / 1000,
+                    setting.user_id,
+                    setting.ln_address
+                );
+                Ok(Some(withdrawn_msats))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                let retry_at = Utc::now() + retry_delay(setting.failure_count);
+                let updated = self
+                    .db
+                    .mark_auto_withdraw_failed(
+                        &setting.user_id,
+                        &e.to_string(),
+                        retry_at,
+                        MAX_FAILURES,
+                    )
+                    .await?;
+                let message = if updated.enabled {
+                    format!(
+                        "Automatic withdrawal to {} failed and will be retried: {}",
+                        setting.ln_address, e
+                    )
+                } else {
+                    format!(
+                        "Automatic withdrawal to {} was turned off after {} failures: {}",
+                        setting.ln_address, updated.failure_count, e
+                    )
+                };
+                self.db
+                    .create_notification(&setting.user_id, &message)
+                    .await?;
+                if updated.enabled {
+                    tracing::warn!(
+                        "Auto-withdrawal for user {} failed ({}), retrying at {}",
+                        setting.user_id,
+                        e,
+                        retry_at
+                    );
+                } else {
+                    tracing::warn!(
+                        "Auto-withdrawal for user {} disabled after {} failures: {}",
+                        setting.user_id,
+                        updated.failure_count,
+                        e
+                    );
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Withdraw the whole balance to the saved address if it reached the threshold
+    async fn sweep(&self, setting: &AutoWithdrawSetting) -> Result<Option<i64>> {
+        let balance_msats = self.db.get_user_balance(&setting.user_id).await?;
+        if balance_msats < setting.threshold_msats {
+            return Ok(None);
+        }
+
+        // Round down to whole sats for the invoice
+        let (max_withdraw_msats, _fee_msats) = self.fee_policy.max_withdrawable(balance_msats);
+        let withdraw_msats = max_withdraw_msats / 1000 * 1000;
+        if withdraw_msats < 1000 {
+            return Ok(None);
+        }
+
+        let invoice =
+            lnurl::get_invoice_for_ln_address(&setting.ln_address, withdraw_msats).await?;
+        self.invoice_policy
+            .check(&invoice, self.lightning.as_ref(), &self.db)
+            .await?;
+        let fee_msats = self
+            .fee_policy
+            .reserve_fee_msats(self.lightning.as_ref(), &invoice, withdraw_msats)
+            .await;
+
+        // Reserve the balance; None means it changed since we looked, e.g. because
+        // of a manual withdrawal, and the next check will pick up the new balance.
+        // Withdrawals over a limit wait for an admin instead of being paid. The held
+        // withdrawal reserves the balance, so the next check doesn't sweep it again.
+        let Some((withdrawal_id, hold_reason)) = self
+            .db
+            .reserve_wallet_withdrawal(
+                &setting.user_id,
+                withdraw_msats,
+                fee_msats,
+                &invoice,
+                &self.withdraw_limits,
+            )
+            .await?
+        else {
+            return Ok(None);
+        };
+        if let Some(reason) = hold_reason {
+            tracing::info!(
+                "Auto-withdrawal for user {} held for review: {}",
+                setting.user_id,
+                reason
+            );
+            return Ok(None);
+        }
+
+        let paid_fee_msats = match self.lightning.pay_invoice_with_fee(&invoice).await {
+            Ok(fee) => fee,
+            // An error such as a timeout doesn't mean the payment won't go through,
+            // so the reserved balance is only released if it's known to have failed
+            Err(e) => match self.lightning.payment_status(&invoice).await {
+                Ok(PaymentStatus::Succeeded { fee_msats }) => fee_msats,
+                Ok(PaymentStatus::Failed) => {
+                    // Release the reserved balance
+                    if let Err(e) = self.db.fail_pending_withdrawal(&withdrawal_id).await {
+                        tracing::error!("Failed to mark withdrawal as failed: {}", e);
+                    }
+                    return Err(anyhow::anyhow!("Payment failed: {}", e));
+                }
+                Ok(PaymentStatus::Unknown) | Err(_) => {
+                    // Settled by settle_unconfirmed once the outcome is known
+                    if let Err(e) = self.db.mark_withdrawal_unconfirmed(&withdrawal_id).await {
+                        tracing::error!("Failed to mark withdrawal as unconfirmed: {}", e);
+                    }
+                    return Err(anyhow::anyhow!(
+                        "Payment outcome unknown, the sats stay reserved until it is known: {}",
+                        e
+                    ));
+                }
+            },
+        };
+
+        // Refunds the part of the reserved fee that wasn't needed
+        if let Err(e) = self
+            .db
+            .complete_pending_withdrawal(&withdrawal_id, paid_fee_msats)
+            .await
+        {
+            tracing::error!("Failed to complete withdrawal: {}", e);
+            // Payment succeeded but we couldn't record it - this is bad but rare
+        }
+
+        Ok(Some(withdraw_msats))
+    }
+}
@@ -1,4 +1,5 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
 
 /// Configuration for balance calculation
 #[derive(Debug, Clone)]
@@ -57,6 +58,88 @@ pub fn compute_balance_msats(
     (max_fill_msats as f64 * fill_ratio) as i64
 }
 
+/// Fill reference time after a claim took `fraction` of the claimable balance.
+///
+/// Moves the reference forward so the fill ratio drops by that fraction: taking
+/// everything restarts the fill at `now`, taking a third leaves two thirds of
+/// the fill for the next claim.
+pub fn reference_after_claim(
+    last_withdraw_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    fraction: f64,
+    config: &BalanceConfig,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let reference_time = last_withdraw_at.unwrap_or(created_at);
+    let time_to_full = Duration::days(config.time_to_full_days as i64);
+    let elapsed = (now - reference_time).clamp(Duration::zero(), time_to_full);
+    let kept_secs = elapsed.num_seconds() as f64 * (1.0 - fraction.clamp(0.0, 1.0));
+    now - Duration::seconds(kept_secs.round() as i64)
+}
+
+/// Projected claimable balance of a location at a point in time
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceForecastPoint {
+    pub at: DateTime<Utc>,
+    /// Projected pool balance (current pool plus expected donations)
+    pub pool_msats: i64,
+    /// Projected claimable balance
+    pub claimable_msats: i64,
+}
+
+/// Average daily donation income over the last `window_days`, from (received_at, amount) pairs.
+pub fn donation_rate_msats_per_day(
+    donations: &[(DateTime<Utc>, i64)],
+    window_days: u32,
+    now: DateTime<Utc>,
+) -> f64 {
+    if window_days == 0 {
+        return 0.0;
+    }
+    let since = now - Duration::days(window_days as i64);
+    let total_msats: i64 = donations
+        .iter()
+        .filter(|(received_at, _)| *received_at >= since && *received_at <= now)
+        .map(|(_, msats)| msats)
+        .sum();
+    total_msats as f64 / window_days as f64
+}
+
+/// Project the claimable balance for today and each of the next `days` days.
+///
+/// Assumes nobody claims in the meantime and donations keep arriving at
+/// `donation_rate_msats_per_day`. Each point is computed with
+/// `compute_balance_msats` by moving the reference times back by the offset,
+/// which is equivalent to evaluating the formula at that future time.
+pub fn forecast_balance_msats(
+    pool_balance_msats: i64,
+    last_withdraw_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    donation_rate_msats_per_day: f64,
+    days: u32,
+    config: &BalanceConfig,
+) -> Vec<BalanceForecastPoint> {
+    let now = Utc::now();
+
+    (0..=days as i64)
+        .map(|day| {
+            let offset = Duration::days(day);
+            let pool_msats = pool_balance_msats + (donation_rate_msats_per_day * day as f64) as i64;
+            let claimable_msats = compute_balance_msats(
+                pool_msats,
+                last_withdraw_at.map(|t| t - offset),
+                created_at - offset,
+                config,
+            );
+            BalanceForecastPoint {
+                at: now + offset,
+                pool_msats,
+                claimable_msats,
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,4 +282,90 @@ mod tests {
         let expected = (pool_msats as f64 * 0.1) as i64;
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_forecast_starts_at_current_balance() {
+        let config = test_config();
+        let now = Utc::now();
+        let created_at = now - Duration::days(30);
+        let last_withdraw_at = Some(now - Duration::days(7));
+        let pool_msats = 1_000_000_000;
+
+        let forecast =
+            forecast_balance_msats(pool_msats, last_withdraw_at, created_at, 0.0, 14, &config);
+        assert_eq!(forecast.len(), 15);
+
+        let current = compute_balance_msats(pool_msats, last_withdraw_at, created_at, &config);
+        assert!((forecast[0].claimable_msats - current).abs() < 1000);
+    }
+
+    #[test]
+    fn test_forecast_fills_up_and_caps() {
+        let config = test_config();
+        let now = Utc::now();
+        let pool_msats = 1_000_000_000;
+
+        let forecast = forecast_balance_msats(pool_msats, Some(now), now, 0.0, 30, &config);
+        let max_fill = (pool_msats as f64 * config.max_fill_percentage) as i64;
+
+        assert!(forecast
+            .windows(2)
+            .all(|w| w[0].claimable_msats <= w[1].claimable_msats));
+        assert_eq!(forecast[21].claimable_msats, max_fill);
+        assert_eq!(forecast[30].claimable_msats, max_fill);
+    }
+
+    #[test]
+    fn test_forecast_includes_donation_rate() {
+        let config = test_config();
+        let now = Utc::now();
+        let created_at = now - Duration::days(60);
+
+        let forecast = forecast_balance_msats(0, None, created_at, 1_000_000.0, 10, &config);
+
+        assert_eq!(forecast[0].claimable_msats, 0);
+        assert_eq!(forecast[10].pool_msats, 10_000_000);
+        assert_eq!(forecast[10].claimable_msats, 1_000_000);
+    }
+
+    #[test]
+    fn test_reference_after_claim() {
+        let config = test_config();
+        let now = Utc::now();
+        let created_at = now - Duration::days(60);
+
+        // Taking everything restarts the fill
+        assert_eq!(
+            reference_after_claim(None, created_at, 1.0, &config, now),
+            now
+        );
+
+        // Taking a third of a full location leaves two thirds of the fill
+        let reference = reference_after_claim(None, created_at, 1.0 / 3.0, &config, now);
+        assert_eq!(now - reference, Duration::days(14));
+
+        // Taking a quarter of a half full location
+        let reference = reference_after_claim(
+            Some(now - Duration::days(8)),
+            created_at,
+            0.25,
+            &config,
+            now,
+        );
+        assert_eq!(now - reference, Duration::days(6));
+    }
+
+    #[test]
+    fn test_donation_rate() {
+        let now = Utc::now();
+        let donations = vec![
+            (now - Duration::days(1), 3_000_000),
+            (now - Duration::days(29), 3_000_000),
+            (now - Duration::days(45), 50_000_000),
+        ];
+
+        assert_eq!(donation_rate_msats_per_day(&donations, 30, now), 200_000.0);
+        assert_eq!(donation_rate_msats_per_day(&donations, 0, now), 0.0);
+        assert_eq!(donation_rate_msats_per_day(&[], 30, now), 0.0);
+    }
 }
@@ -0,0 +1,229 @@
+//! Donation matching campaigns.
+//!
+//! This module handles:
+//! - Awaiting the sponsor's payment of a campaign's funding invoice, which
+//!   activates the campaign and books its budget, or expires the campaign once
+//!   the invoice expired unpaid
+//! - Listening to donations received by the `DonationService` and matching them
+//!   from the budget of every active campaign covering the donated locations
+//! - Matching the donations it missed, received while the service was down or
+//!   when it fell behind, on startup and whenever it lags
+//! - Ending campaigns once their deadline passed, which donates their unspent
+//!   budget to the locations in their region
+
+use crate::db::Database;
+use crate::lightning::Lightning;
+use crate::models::Donation;
+use lightning_invoice::Bolt11Invoice;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+/// How often campaigns are checked for a passed deadline
+const DEADLINE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Message to notify the CampaignService about a new campaign awaiting funding
+pub struct NewCampaign {
+    pub campaign_id: String,
+    pub funding_invoice: String,
+}
+
+/// Background service that funds campaigns and matches received donations.
+/// Like the `DonationService`, campaigns still waiting for funding are loaded from
+/// the database on startup.
+pub struct CampaignService {
+    db: Arc<Database>,
+    lightning: Arc<dyn Lightning>,
+    /// Sender for new campaign notifications
+    sender: mpsc::UnboundedSender<NewCampaign>,
+    /// Receiver for new campaign notifications (wrapped in Option for take())
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<NewCampaign>>>,
+    /// Set of funding invoices currently being awaited (to prevent duplicate tasks)
+    active_invoices: Mutex<HashSet<String>>,
+}
+
+impl CampaignService {
+    pub fn new(db: Arc<Database>, lightning: Arc<dyn Lightning>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            db,
+            lightning,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            active_invoices: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Get a sender clone to notify about new campaigns
+    pub fn get_sender(&self) -> mpsc::UnboundedSender<NewCampaign> {
+        self.sender.clone()
+    }
+
+    /// Start the campaign service - loads unfunded campaigns and matches the
+    /// donations missed while it was down, then listens for new campaigns and
+    /// received donations
+    pub async fn start(self: Arc<Self>, mut donations: broadcast::Receiver<Donation>) {
+        match self.db.list_funding_matching_campaigns().await {
+            Ok(campaigns) => {
+                tracing::info!(
+                    "Loaded {} campaigns awaiting funding from database",
+                    campaigns.len()
+                );
+                for campaign in campaigns {
+                    if invoice_expired(&campaign.funding_invoice) {
+                        self.expire(&campaign.id).await;
+                    } else {
+                        self.clone()
+                            .spawn_funding_task(campaign.id, campaign.funding_invoice)
+                            .await;
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to load campaigns awaiting funding: {}", e);
+            }
+        }
+
+        self.match_missed_donations().await;
+
+        // Take the receiver (can only be done once)
+        let receiver = {
+            let mut guard = self.receiver.lock().await;
+            guard.take()
+        };
+
+        let Some(mut receiver) = receiver else {
+            tracing::error!("CampaignService receiver already taken");
+            return;
+        };
+
+        let mut interval = tokio::time::interval(DEADLINE_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                Some(campaign) = receiver.recv() => {
+                    self.clone()
+                        .spawn_funding_task(campaign.campaign_id, campaign.funding_invoice)
+                        .await;
+                }
+                result = donations.recv() => match result {
+                    Ok(donation) => self.match_donation(&donation).await,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!(
+                            "Campaign matching missed {} received donations, rescanning",
+                            missed
+                        );
+                        self.match_missed_donations().await;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = interval.tick() => self.end_due_campaigns().await,
+            }
+        }
+    }
+
+    /// Match the received donations no campaign matched yet, then end the
+    /// campaigns whose deadline passed in the meantime
+    async fn match_missed_donations(&self) {
+        match self.db.list_unmatched_donations().await {
+            Ok(donations) => {
+                for donation in &donations {
+                    self.match_donation(donation).await;
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to load unmatched donations: {}", e);
+            }
+        }
+        self.end_due_campaigns().await;
+    }
+
+    /// End the campaigns whose deadline passed
+    async fn end_due_campaigns(&self) {
+        match self.db.end_due_matching_campaigns().await {
+            Ok(0) => {}
+            Ok(ended) => tracing::info!("Ended {} campaigns past their deadline", ended),
+            Err(e) => tracing::error!("Failed to end campaigns past their deadline: {}", e),
+        }
+    }
+
+    /// Match a received donation from all active campaigns
+    async fn match_donation(&self, donation: &Donation) {
+        match self.db.apply_campaign_matches(&donation.id).await {
+            Ok(matches) if !matches.is_empty() => {
+                let total_msats: i64 = matches.iter().map(|d| d.amount_msats).sum();
+                tracing::info!(
+                    "Donation {} matched with {} sats across {} locations",
+                    donation.id,
+                    total_msats / 1000,
+                    matches.len()
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("Failed to match donation {}: {}", donation.id, e);
+            }
+        }
+    }
+
+    /// Stop awaiting a campaign whose funding invoice expired unpaid
+    async fn expire(&self, campaign_id: &str) {
+        match self.db.expire_matching_campaign(campaign_id).await {
+            Ok(true) => {
+                tracing::info!("Campaign {} expired before it was funded", campaign_id);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::error!("Failed to expire campaign {}: {}", campaign_id, e);
+            }
+        }
+    }
+
+    /// Spawn a task to await the sponsor's payment of a funding invoice
+    async fn spawn_funding_task(self: Arc<Self>, campaign_id: String, invoice: String) {
+        {
+            let mut active = self.active_invoices.lock().await;
+            if !active.insert(invoice.clone()) {
+                tracing::debug!("Already tracking funding of campaign {}", campaign_id);
+                return;
+            }
+        }
+
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            tracing::info!("Awaiting funding for campaign {}", campaign_id);
+
+            match service.lightning.await_payment(&invoice).await {
+                Ok(()) => match service.db.mark_campaign_funded(&campaign_id).await {
+                    Ok(campaign) => {
+                        tracing::info!(
+                            "Campaign '{}' funded with {} sats by {}",
+                            campaign.name,
+                            campaign.budget_sats(),
+                            campaign.sponsor_name
+                        );
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to mark campaign {} funded: {}", campaign_id, e);
+                    }
+                },
+                Err(_) if invoice_expired(&invoice) => service.expire(&campaign_id).await,
+                Err(e) => {
+                    tracing::error!("Failed to await campaign funding: {}", e);
+                }
+            }
+
+            let mut active = service.active_invoices.lock().await;
+            active.remove(&invoice);
+        });
+    }
+}
+
+/// Whether an invoice can no longer be paid. Invoices that don't parse can't
+/// be paid either.
+fn invoice_expired(invoice: &str) -> bool {
+    match invoice.parse::<Bolt11Invoice>() {
+        Ok(invoice) => invoice.is_expired(),
+        Err(_) => true,
+    }
+}
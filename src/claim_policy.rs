@@ -0,0 +1,260 @@
+//! Claim policies deciding who gets a location's sats after scanning it.
+//!
+//! Every scan can be claimed within `SCAN_CLAIM_WINDOW`. Each location picks a
+//! policy for which of the scans in that window win and what share of the
+//! claimable balance they get:
+//! - last scanner wins: only the latest scan can claim, and gets everything
+//! - first scanner wins: only the first scan since the last winning one can
+//!   claim, and gets everything
+//! - split: everyone who scanned in the window gets an equal share
+//! - fixed fraction: every scan gets `FIXED_FRACTION` of what is left
+//!
+//! A sticker withdrawal counts as a scan right after the recent ones.
+//!
+//! A claim that takes only part of the balance leaves the rest of the fill for
+//! the next one (see `balance::reference_after_claim`).
+
+use crate::models::{ClaimResult, NfcScan};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// How long a scan can be claimed after scanning
+pub const SCAN_CLAIM_WINDOW: Duration = Duration::hours(1);
+
+/// Share of the claimable balance a scan gets under `ClaimPolicy::FixedFraction`
+pub const FIXED_FRACTION: f64 = 0.1;
+
+/// Who gets a location's sats, selectable per location
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimPolicy {
+    #[default]
+    LastScanner,
+    FirstScanner,
+    Split,
+    FixedFraction,
+}
+
+impl ClaimPolicy {
+    pub const ALL: [ClaimPolicy; 4] = [
+        Self::LastScanner,
+        Self::FirstScanner,
+        Self::Split,
+        Self::FixedFraction,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LastScanner => "last_scanner",
+            Self::FirstScanner => "first_scanner",
+            Self::Split => "split",
+            Self::FixedFraction => "fixed_fraction",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::LastScanner => "LAST SCANNER WINS",
+            Self::FirstScanner => "FIRST SCANNER WINS",
+            Self::Split => "SPLIT AMONG SCANNERS",
+            Self::FixedFraction => "FIXED SHARE PER SCAN",
+        }
+    }
+
+    /// Explanation for the collect page
+    pub fn description(&self) -> String {
+        match self {
+            Self::LastScanner => {
+                "Whoever scanned last can collect everything. Scanning after you takes it over."
+                    .to_string()
+            }
+            Self::FirstScanner => {
+                "The first to scan after the last collection can collect everything.".to_string()
+            }
+            Self::Split => format!(
+                "Everyone who scanned in the last {} minutes gets an equal share.",
+                SCAN_CLAIM_WINDOW.num_minutes()
+            ),
+            Self::FixedFraction => format!(
+                "Every scan collects {}% of what is available.",
+                (FIXED_FRACTION * 100.0) as i64
+            ),
+        }
+    }
+
+    /// Whether a claim always takes the whole claimable balance
+    pub fn takes_all(&self) -> bool {
+        matches!(self, Self::LastScanner | Self::FirstScanner)
+    }
+
+    /// Share of the claimable balance `scan` gets, or why it can't claim.
+    ///
+    /// `recent` are the location's scans within the claim window, claimed or
+    /// not, oldest first and including `scan` itself.
+    pub fn share(&self, scan: &NfcScan, recent: &[NfcScan]) -> Result<f64, ClaimResult> {
+        match self {
+            Self::LastScanner => match recent.last() {
+                Some(last) if last.id == scan.id => Ok(1.0),
+                _ => Err(ClaimResult::NotLastScanner),
+            },
+            Self::FirstScanner => {
+                // The contest starts over after each winning scan
+                let start = recent
+                    .iter()
+                    .rposition(|s| s.claimed_at.is_some())
+                    .map_or(0, |i| i + 1);
+                match recent[start..].first() {
+                    Some(first) if first.id == scan.id => Ok(1.0),
+                    _ => Err(ClaimResult::NotFirstScanner),
+                }
+            }
+            Self::Split => {
+                let claimed: HashSet<&str> = recent
+                    .iter()
+                    .filter(|s| s.claimed_at.is_some())
+                    .map(|s| s.user_id.as_str())
+                    .collect();
+                if claimed.contains(scan.user_id.as_str()) {
+                    return Err(ClaimResult::AlreadyClaimed);
+                }
+                // Scanners still waiting for their share, this one included
+                let waiting: HashSet<&str> = recent
+                    .iter()
+                    .map(|s| s.user_id.as_str())
+                    .chain(std::iter::once(scan.user_id.as_str()))
+                    .filter(|user_id| !claimed.contains(user_id))
+                    .collect();
+                Ok(1.0 / waiting.len() as f64)
+            }
+            Self::FixedFraction => Ok(FIXED_FRACTION),
+        }
+    }
+}
+
+impl std::fmt::Display for ClaimPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for ClaimPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "last_scanner" => Ok(Self::LastScanner),
+            "first_scanner" => Ok(Self::FirstScanner),
+            "split" => Ok(Self::Split),
+            "fixed_fraction" => Ok(Self::FixedFraction),
+            _ => Err(anyhow::anyhow!("Invalid claim policy: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for ClaimPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 7, 10, 0, 0).unwrap()
+    }
+
+    /// Scans of a location by (user, minutes after start), oldest first
+    fn scans(sequence: &[(&str, i64)]) -> Vec<NfcScan> {
+        sequence
+            .iter()
+            .enumerate()
+            .map(|(i, (user_id, minute))| NfcScan {
+                id: format!("scan-{}", i),
+                location_id: "location".to_string(),
+                user_id: user_id.to_string(),
+                counter: i as i64 + 1,
+                scanned_at: start() + Duration::minutes(*minute),
+                claimed_at: None,
+                claim_id: None,
+            })
+            .collect()
+    }
+
+    /// Claim the scans in `order` one after another under `policy`, starting
+    /// from `claimable_msats`. Returns what each claim got, or its refusal.
+    fn simulate(
+        policy: ClaimPolicy,
+        recent: &mut [NfcScan],
+        order: &[usize],
+        mut claimable_msats: i64,
+    ) -> Vec<Result<i64, ClaimResult>> {
+        order
+            .iter()
+            .map(|&i| {
+                let fraction = policy.share(&recent[i], recent)?;
+                let msats = (claimable_msats as f64 * fraction).round() as i64;
+                claimable_msats -= msats;
+                recent[i].claimed_at = Some(start() + Duration::minutes(59));
+                Ok(msats)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_last_scanner_wins() {
+        let mut recent = scans(&[("alice", 0), ("bob", 10), ("carol", 20)]);
+        let results = simulate(ClaimPolicy::LastScanner, &mut recent, &[0, 1, 2], 9_000);
+
+        assert!(matches!(results[0], Err(ClaimResult::NotLastScanner)));
+        assert!(matches!(results[1], Err(ClaimResult::NotLastScanner)));
+        assert!(matches!(results[2], Ok(9_000)));
+    }
+
+    #[test]
+    fn test_first_scanner_wins() {
+        let mut recent = scans(&[("alice", 0), ("bob", 10), ("carol", 20)]);
+        let results = simulate(ClaimPolicy::FirstScanner, &mut recent, &[2, 0, 2], 9_000);
+
+        assert!(matches!(results[0], Err(ClaimResult::NotFirstScanner)));
+        assert!(matches!(results[1], Ok(9_000)));
+        // Bob scanned first after alice's winning scan
+        assert!(matches!(results[2], Err(ClaimResult::NotFirstScanner)));
+        assert!(ClaimPolicy::FirstScanner.share(&recent[1], &recent).is_ok());
+    }
+
+    #[test]
+    fn test_split_among_scanners() {
+        let mut recent = scans(&[("alice", 0), ("bob", 10), ("alice", 15), ("carol", 20)]);
+        let results = simulate(ClaimPolicy::Split, &mut recent, &[3, 0, 1, 2], 9_000);
+
+        assert!(matches!(results[0], Ok(3_000)));
+        assert!(matches!(results[1], Ok(3_000)));
+        assert!(matches!(results[2], Ok(3_000)));
+        // One share per scanner, however often they scanned
+        assert!(matches!(results[3], Err(ClaimResult::AlreadyClaimed)));
+    }
+
+    #[test]
+    fn test_fixed_fraction_per_scan() {
+        let mut recent = scans(&[("alice", 0), ("bob", 10), ("alice", 15)]);
+        let results = simulate(ClaimPolicy::FixedFraction, &mut recent, &[1, 0, 2], 10_000);
+
+        assert!(matches!(results[0], Ok(1_000)));
+        assert!(matches!(results[1], Ok(900)));
+        assert!(matches!(results[2], Ok(810)));
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        for policy in ClaimPolicy::ALL {
+            assert_eq!(policy.as_str().parse::<ClaimPolicy>().unwrap(), policy);
+        }
+        assert!("random".parse::<ClaimPolicy>().is_err());
+    }
+}
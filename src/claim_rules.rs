@@ -0,0 +1,200 @@
+//! Per-user claim rules against farming a location.
+//!
+//! The rules are configured globally and each of them can be overridden per
+//! location, where 0 turns a global rule off:
+//! - cooldown: days a user waits between two claims at the same location
+//! - monthly limit: claims a user can make at the same location in 30 days
+//! - first claim bonus: extra percent of the claimable balance for the very first
+//!   claim of a user, taken from the same pool
+//!
+//! `Database::claim_from_scan` enforces them inside the claim transaction.
+//! Sticker withdrawals have no user, so all of them at a location count as one
+//! claimant.
+
+use crate::models::ClaimResult;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Window the monthly claim limit counts claims in
+pub const MONTH: Duration = Duration::days(30);
+
+/// Claim rules, each off when None
+#[derive(Debug, Clone, Default, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct ClaimRules {
+    /// Days a user has to wait between claims at a location
+    pub cooldown_days: Option<i64>,
+    /// Claims a user can make at a location in 30 days
+    pub max_claims_per_month: Option<i64>,
+    /// Bonus on a user's first claim ever, in percent of the claimable balance
+    pub first_claim_bonus_percent: Option<i64>,
+}
+
+impl ClaimRules {
+    /// Rules of a location: its own overrides where set, these rules otherwise.
+    /// Rules set to 0 are turned off.
+    pub fn with_overrides(&self, location: &ClaimRules) -> ClaimRules {
+        let pick = |own: Option<i64>, global: Option<i64>| own.or(global).filter(|v| *v > 0);
+        ClaimRules {
+            cooldown_days: pick(location.cooldown_days, self.cooldown_days),
+            max_claims_per_month: pick(location.max_claims_per_month, self.max_claims_per_month),
+            first_claim_bonus_percent: pick(
+                location.first_claim_bonus_percent,
+                self.first_claim_bonus_percent,
+            ),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cooldown_days.is_none()
+            && self.max_claims_per_month.is_none()
+            && self.first_claim_bonus_percent.is_none()
+    }
+
+    /// Why a user can't claim at a location at `now`, given the times of their
+    /// earlier claims there, or None if they can
+    pub fn check(&self, claimed_at: &[DateTime<Utc>], now: DateTime<Utc>) -> Option<ClaimResult> {
+        if let (Some(days), Some(last)) = (self.cooldown_days, claimed_at.iter().max()) {
+            let until = *last + Duration::days(days);
+            if now < until {
+                return Some(ClaimResult::Cooldown { until });
+            }
+        }
+
+        if let Some(limit) = self.max_claims_per_month.filter(|limit| *limit > 0) {
+            let mut recent: Vec<DateTime<Utc>> = claimed_at
+                .iter()
+                .copied()
+                .filter(|at| now - *at < MONTH)
+                .collect();
+            if recent.len() as i64 >= limit {
+                // A claim frees up once enough of the recent ones left the window
+                recent.sort();
+                let until = recent[recent.len() - limit as usize] + MONTH;
+                return Some(ClaimResult::MonthlyLimitReached { limit, until });
+            }
+        }
+
+        None
+    }
+
+    /// Bonus on top of `claimable_msats` for a user's first claim, limited to
+    /// what is left in the pool
+    pub fn first_claim_bonus_msats(
+        &self,
+        is_first_claim: bool,
+        claimable_msats: i64,
+        pool_msats: i64,
+    ) -> i64 {
+        match self.first_claim_bonus_percent {
+            Some(percent) if is_first_claim => (claimable_msats * percent / 100)
+                .min(pool_msats - claimable_msats)
+                .max(0),
+            _ => 0,
+        }
+    }
+}
+
+/// Explanation for a claim the rules blocked, None for other claim results
+pub fn blocked_message(result: &ClaimResult) -> Option<String> {
+    match result {
+        ClaimResult::Cooldown { until } => Some(format!(
+            "You claimed here recently. You can claim again from {}.",
+            until.format("%Y-%m-%d %H:%M UTC")
+        )),
+        ClaimResult::MonthlyLimitReached { limit, until } => Some(format!(
+            "You reached the limit of {} claims here in 30 days. You can claim again from {}.",
+            limit,
+            until.format("%Y-%m-%d %H:%M UTC")
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 7, 10, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        let global = ClaimRules {
+            cooldown_days: Some(1),
+            max_claims_per_month: Some(10),
+            first_claim_bonus_percent: None,
+        };
+        let location = ClaimRules {
+            cooldown_days: Some(7),
+            max_claims_per_month: Some(0),
+            first_claim_bonus_percent: Some(50),
+        };
+
+        assert_eq!(
+            global.with_overrides(&location),
+            ClaimRules {
+                cooldown_days: Some(7),
+                max_claims_per_month: None,
+                first_claim_bonus_percent: Some(50),
+            }
+        );
+        assert_eq!(global.with_overrides(&ClaimRules::default()), global);
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let rules = ClaimRules {
+            cooldown_days: Some(2),
+            ..Default::default()
+        };
+        let yesterday = now() - Duration::days(1);
+
+        assert!(rules.check(&[], now()).is_none());
+        assert!(matches!(
+            rules.check(&[yesterday], now()),
+            Some(ClaimResult::Cooldown { until }) if until == yesterday + Duration::days(2)
+        ));
+        assert!(rules.check(&[now() - Duration::days(2)], now()).is_none());
+    }
+
+    #[test]
+    fn test_monthly_limit() {
+        let rules = ClaimRules {
+            max_claims_per_month: Some(2),
+            ..Default::default()
+        };
+        let claims = [
+            now() - Duration::days(40),
+            now() - Duration::days(20),
+            now() - Duration::days(10),
+        ];
+
+        // The claim from 20 days ago leaves the window first
+        assert!(matches!(
+            rules.check(&claims, now()),
+            Some(ClaimResult::MonthlyLimitReached { limit: 2, until })
+                if until == now() + Duration::days(10)
+        ));
+        assert!(rules.check(&claims[..2], now()).is_none());
+    }
+
+    #[test]
+    fn test_first_claim_bonus() {
+        let rules = ClaimRules {
+            first_claim_bonus_percent: Some(50),
+            ..Default::default()
+        };
+
+        assert_eq!(rules.first_claim_bonus_msats(true, 10_000, 100_000), 5_000);
+        assert_eq!(rules.first_claim_bonus_msats(false, 10_000, 100_000), 0);
+        // Never more than the pool holds
+        assert_eq!(rules.first_claim_bonus_msats(true, 10_000, 12_000), 2_000);
+        assert_eq!(
+            ClaimRules::default().first_claim_bonus_msats(true, 10_000, 100_000),
+            0
+        );
+    }
+}
@@ -32,6 +32,63 @@ pub struct Config {
     /// Static files directory
     #[arg(long, env = "SH_STATIC_DIR", default_value = "./static")]
     pub static_dir: PathBuf,
+
+    /// Nostr relays zap receipts are published to, in addition to the relays
+    /// listed in each zap request (comma separated)
+    #[arg(long, env = "SH_NOSTR_RELAYS", value_delimiter = ',')]
+    pub nostr_relays: Vec<String>,
+
+    /// Bitcoin network of the Lightning backend (bitcoin, testnet, signet or regtest).
+    /// Invoices for other networks are not paid.
+    #[arg(long, env = "SH_NETWORK", default_value = "bitcoin")]
+    pub network: String,
+
+    /// Node public keys (hex) whose invoices are never paid, on top of the
+    /// invoices we created ourselves (comma separated)
+    #[arg(long, env = "SH_BLOCKED_PAYEES", value_delimiter = ',')]
+    pub blocked_payees: Vec<String>,
+
+    /// Fixed routing fee reserved for a wallet withdrawal, in msats. Also the margin
+    /// added to fees probed by the Lightning backend.
+    #[arg(long, env = "SH_FEE_BASE_MSATS", default_value = "2000")]
+    pub fee_base_msats: i64,
+
+    /// Proportional routing fee reserved for a wallet withdrawal when the backend
+    /// can't probe the route, in parts per million (default: 5000 = 0.5%)
+    #[arg(long, env = "SH_FEE_RATE_PPM", default_value = "5000")]
+    pub fee_rate_ppm: i64,
+
+    /// Largest wallet withdrawal paid without review, in sats
+    #[arg(long, env = "SH_WITHDRAW_MAX_SATS")]
+    pub withdraw_max_sats: Option<i64>,
+
+    /// Most a user withdraws in 24 hours without review, in sats
+    #[arg(long, env = "SH_WITHDRAW_USER_DAILY_SATS")]
+    pub withdraw_user_daily_sats: Option<i64>,
+
+    /// Most all users together withdraw in 24 hours without review, in sats
+    #[arg(long, env = "SH_WITHDRAW_GLOBAL_DAILY_SATS")]
+    pub withdraw_global_daily_sats: Option<i64>,
+
+    /// Most a new anonymous user withdraws without review, in sats
+    #[arg(long, env = "SH_NEW_ANON_WITHDRAW_SATS")]
+    pub new_anon_withdraw_sats: Option<i64>,
+
+    /// How long an anonymous user counts as new, in minutes (default: 60)
+    #[arg(long, env = "SH_NEW_ANON_WINDOW_MINUTES", default_value = "60")]
+    pub new_anon_window_minutes: i64,
+
+    /// Days a user waits between two claims at the same location
+    #[arg(long, env = "SH_CLAIM_COOLDOWN_DAYS")]
+    pub claim_cooldown_days: Option<i64>,
+
+    /// Most claims a user makes at the same location in 30 days
+    #[arg(long, env = "SH_MAX_CLAIMS_PER_MONTH")]
+    pub max_claims_per_month: Option<i64>,
+
+    /// Bonus on a user's first claim, in percent of the claimable balance
+    #[arg(long, env = "SH_FIRST_CLAIM_BONUS_PERCENT")]
+    pub first_claim_bonus_percent: Option<i64>,
 }
 
 impl Config {
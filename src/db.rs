@@ -1,18 +1,584 @@
-use crate::balance::{compute_balance_msats, BalanceConfig};
+use crate::achievements::{Badge, Event};
+use crate::balance::{compute_balance_msats, reference_after_claim, BalanceConfig};
+use crate::claim_policy::{ClaimPolicy, SCAN_CLAIM_WINDOW};
+use crate::claim_rules::ClaimRules;
+use crate::disclosure::{Disclosure, SearchArea};
+use crate::fees;
+use crate::hunt;
+use crate::invoice_policy;
+use crate::leaderboard::{Metric, Period};
 use crate::models::{
-    AdminScan, AuthMethod, Claim, ClaimResult, DailyScanCount, Donation, Location, NfcCard,
-    NfcScan, Photo, ScanWithLocation, ScanWithUser, Stats, User, UserRole, UserTransaction,
-    WithdrawalStatus,
+    AdminScan, AuthMethod, AutoWithdrawSetting, CampaignStatus, Claim, ClaimResult, DailyScanCount,
+    Donation, DonationMatch, Hint, Hunt, HuntCompletion, HunterStats, LeaderboardEntry,
+    LedgerAccount, LedgerAccountBalance, LedgerDiscrepancy, LedgerEntry, LedgerEntryKind,
+    LedgerReport, LiabilitySnapshot, Location, LocationLog, LocationLogPhoto, LogKind,
+    MatchingCampaign, NewHint, NewMatchingCampaign, NewSchedule, NfcCard, NfcScan, Notification,
+    PendingWithdrawal, Photo, RecurringDonation, RecurringDonationStatus, Region, ScanWithLocation,
+    ScanWithUser, Schedule, Stats, Team, TeamLeaderboardEntry, TeamMember, TeamRole, TeamStats,
+    TeamTransaction, User, UserBadge, UserRole, UserTransaction, WalletInvoice,
+    WalletInvoiceStatus, WalletWithdrawLink, WithdrawalStatus, CREDIT_TRANSACTION_TYPES,
 };
+use crate::nwc::UriCipher;
+use crate::schedule;
+use crate::withdraw_limits::WithdrawLimits;
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::{
-    sqlite::{SqliteConnectOptions, SqliteQueryResult},
+    sqlite::{SqliteConnectOptions, SqliteConnection, SqliteQueryResult},
     SqlitePool,
 };
+use std::collections::{BTreeMap, HashSet};
 use std::str::FromStr;
 use uuid::Uuid;
 
+/// Signed amount of a `user_transactions` row in SQL: credits add to the
+/// balance, everything else takes from it
+fn user_tx_signed_msats() -> String {
+    let credits = CREDIT_TRANSACTION_TYPES
+        .iter()
+        .map(|t| format!("'{}'", t))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "CASE WHEN transaction_type IN ({}) THEN msats ELSE -msats END",
+        credits
+    )
+}
+
+/// Amount withdrawn since `since`, see `Database::get_withdrawn_msats_since`.
+/// Takes a connection so withdrawal limits can be checked inside the transaction
+/// reserving a withdrawal.
+pub(crate) async fn withdrawn_msats_since(
+    conn: &mut SqliteConnection,
+    user_id: Option<&str>,
+    since: DateTime<Utc>,
+) -> Result<i64> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(SUM(msats - fee_msats), 0) FROM pending_withdrawals WHERE (? IS NULL OR user_id = ?) AND status != ? AND created_at >= ?",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(WithdrawalStatus::Failed.as_str())
+    .bind(since)
+    .fetch_one(conn)
+    .await
+    .map_err(Into::into)
+}
+
+/// `Database::get_user_by_id` on a given connection
+pub(crate) async fn find_user(conn: &mut SqliteConnection, id: &str) -> Result<Option<User>> {
+    sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+        .map_err(Into::into)
+}
+
+/// Append a balanced entry to the ledger journal.
+///
+/// Meant to be called inside the same transaction as the change it records,
+/// so the journal can never drift from the tables it mirrors. Zero amounts are
+/// skipped since they move nothing.
+async fn post_ledger_entry(
+    conn: &mut SqliteConnection,
+    kind: LedgerEntryKind,
+    debit: &LedgerAccount,
+    credit: &LedgerAccount,
+    msats: i64,
+    reference_id: Option<&str>,
+) -> Result<()> {
+    if msats == 0 {
+        return Ok(());
+    }
+    anyhow::ensure!(msats > 0, "Ledger entry amount must be positive: {}", msats);
+
+    sqlx::query(
+        "INSERT INTO ledger_entries (id, kind, debit_account, credit_account, msats, reference_id, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(kind.as_str())
+    .bind(debit.to_string())
+    .bind(credit.to_string())
+    .bind(msats)
+    .bind(reference_id)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Balance of a single ledger account, signed by its normal side.
+async fn ledger_account_balance(
+    conn: &mut SqliteConnection,
+    account: &LedgerAccount,
+) -> Result<i64> {
+    let account_str = account.to_string();
+    let (debits, credits): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COALESCE(SUM(CASE WHEN debit_account = ? THEN msats ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN credit_account = ? THEN msats ELSE 0 END), 0)
+        FROM ledger_entries
+        WHERE debit_account = ? OR credit_account = ?
+        "#,
+    )
+    .bind(&account_str)
+    .bind(&account_str)
+    .bind(&account_str)
+    .bind(&account_str)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(if account.is_asset() {
+        debits - credits
+    } else {
+        credits - debits
+    })
+}
+
+/// Balance of a hunt's bonus pool: received donations minus bonuses paid
+async fn hunt_pool_balance(conn: &mut SqliteConnection, hunt_id: &str) -> Result<i64> {
+    let donations: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE hunt_id = ? AND status = 'received'",
+    )
+    .bind(hunt_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let bonuses: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(bonus_msats), 0) FROM hunt_completions WHERE hunt_id = ?",
+    )
+    .bind(hunt_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(donations - bonuses)
+}
+
+/// Schedules that apply to a location: its own and those of the hunts it is a stop of
+async fn location_schedules(
+    conn: &mut SqliteConnection,
+    location_id: &str,
+) -> Result<Vec<Schedule>> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM schedules
+        WHERE location_id = ?
+           OR hunt_id IN (SELECT hunt_id FROM hunt_locations WHERE location_id = ?)
+        ORDER BY starts_at
+        "#,
+    )
+    .bind(location_id)
+    .bind(location_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(Into::into)
+}
+
+/// Why a location can't be claimed from at `now`, if it can't: it is outside
+/// its activation schedule, or isn't active at all
+async fn claim_blocked_by_status(
+    conn: &mut SqliteConnection,
+    location: &Location,
+    now: DateTime<Utc>,
+) -> Result<Option<ClaimResult>> {
+    let schedules = location_schedules(conn, &location.id).await?;
+    if location.is_scheduled() || !schedule::is_open(&schedules, now) {
+        return Ok(Some(ClaimResult::OutsideSchedule));
+    }
+    if !location.is_active() {
+        return Ok(Some(ClaimResult::LocationInactive));
+    }
+    Ok(None)
+}
+
+/// Claim rules a location overrides, default if it has none
+async fn location_claim_rules(
+    conn: &mut SqliteConnection,
+    location_id: &str,
+) -> Result<ClaimRules> {
+    let rules: Option<ClaimRules> = sqlx::query_as(
+        "SELECT cooldown_days, max_claims_per_month, first_claim_bonus_percent FROM location_claim_rules WHERE location_id = ?",
+    )
+    .bind(location_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(rules.unwrap_or_default())
+}
+
+/// When a user claimed at a location before
+async fn user_claim_times(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    location_id: &str,
+) -> Result<Vec<DateTime<Utc>>> {
+    sqlx::query_scalar("SELECT claimed_at FROM claims WHERE user_id = ? AND location_id = ?")
+        .bind(user_id)
+        .bind(location_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Into::into)
+}
+
+/// A sticker tap claimed right away, as a scan that isn't recorded yet.
+/// Withdrawals have no user and pass an empty one.
+fn new_tap(location_id: &str, user_id: &str, counter: i64, now: DateTime<Utc>) -> NfcScan {
+    NfcScan {
+        id: Uuid::new_v4().to_string(),
+        location_id: location_id.to_string(),
+        user_id: user_id.to_string(),
+        counter,
+        scanned_at: now,
+        claimed_at: None,
+        claim_id: None,
+    }
+}
+
+/// Share of a location's claimable balance a sticker tap gets under its claim
+/// policy, or why it can't claim. The tap counts as a scan after the location's
+/// recent ones.
+async fn tap_share(
+    conn: &mut SqliteConnection,
+    location: &Location,
+    tap: &NfcScan,
+) -> Result<Result<f64, ClaimResult>> {
+    let mut recent: Vec<NfcScan> = sqlx::query_as(
+        "SELECT * FROM scans WHERE location_id = ? AND scanned_at > ? ORDER BY scanned_at",
+    )
+    .bind(&location.id)
+    .bind(tap.scanned_at - SCAN_CLAIM_WINDOW)
+    .fetch_all(&mut *conn)
+    .await?;
+    recent.push(tap.clone());
+    Ok(location.claim_policy.share(tap, &recent))
+}
+
+/// Times of the withdrawals at a location that were paid out without a user
+async fn anonymous_claim_times(
+    conn: &mut SqliteConnection,
+    location_id: &str,
+) -> Result<Vec<DateTime<Utc>>> {
+    sqlx::query_scalar("SELECT claimed_at FROM claims WHERE user_id IS NULL AND location_id = ?")
+        .bind(location_id)
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(Into::into)
+}
+
+async fn user_has_claimed(conn: &mut SqliteConnection, user_id: &str) -> Result<bool> {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM claims WHERE user_id = ?)")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(Into::into)
+}
+
+/// A user's claims at the stops of a hunt since it was created, as
+/// (location_id, claimed_at) pairs
+async fn hunt_claims(
+    conn: &mut SqliteConnection,
+    hunt: &Hunt,
+    user_id: &str,
+) -> Result<Vec<(String, chrono::DateTime<Utc>)>> {
+    sqlx::query_as(
+        r#"
+        SELECT location_id, claimed_at FROM scans
+        WHERE user_id = ? AND claimed_at IS NOT NULL AND claimed_at >= ?
+          AND location_id IN (SELECT location_id FROM hunt_locations WHERE hunt_id = ?)
+        "#,
+    )
+    .bind(user_id)
+    .bind(hunt.created_at)
+    .bind(&hunt.id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(Into::into)
+}
+
+/// Record every hunt `user_id` just completed with a claim at `location_id` and
+/// pay its bonus from the hunt's pool, as far as the pool allows.
+///
+/// Meant to be called inside the claim's transaction, after its scan is marked
+/// claimed. Hunts the user already completed are skipped.
+async fn complete_hunts(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    location_id: &str,
+) -> Result<()> {
+    let hunts: Vec<Hunt> = sqlx::query_as(
+        r#"
+        SELECT h.* FROM hunts h
+        JOIN hunt_locations hl ON hl.hunt_id = h.id
+        WHERE hl.location_id = ?
+          AND NOT EXISTS (
+              SELECT 1 FROM hunt_completions c WHERE c.hunt_id = h.id AND c.user_id = ?
+          )
+        "#,
+    )
+    .bind(location_id)
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for hunt in hunts {
+        let stops: Vec<String> = sqlx::query_scalar(
+            "SELECT location_id FROM hunt_locations WHERE hunt_id = ? ORDER BY position",
+        )
+        .bind(&hunt.id)
+        .fetch_all(&mut *conn)
+        .await?;
+        let claims = hunt_claims(conn, &hunt, user_id).await?;
+        if !hunt::is_complete(&stops, &claims, hunt.ordered) {
+            continue;
+        }
+
+        let pool_msats = hunt_pool_balance(conn, &hunt.id).await?;
+        let bonus_msats = hunt.completion_bonus_msats.min(pool_msats.max(0));
+        let now = Utc::now();
+
+        let completion_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO hunt_completions (id, hunt_id, user_id, bonus_msats, completed_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&completion_id)
+        .bind(&hunt.id)
+        .bind(user_id)
+        .bind(bonus_msats)
+        .bind(now)
+        .execute(&mut *conn)
+        .await?;
+
+        if bonus_msats > 0 {
+            sqlx::query(
+                "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, NULL, ?, 'hunt_bonus', ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(bonus_msats)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        post_ledger_entry(
+            conn,
+            LedgerEntryKind::HuntBonus,
+            &LedgerAccount::HuntPool(hunt.id.clone()),
+            &LedgerAccount::UserWallet(user_id.to_string()),
+            bonus_msats,
+            Some(&completion_id),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Mark the scan a user claimed with as claimed and complete the hunts the claim
+/// finished. A tap claimed right away (see `new_tap`) is recorded as a claimed
+/// scan.
+///
+/// Meant to be called inside the claim's transaction, after the claim is
+/// recorded. Sticker withdrawals have no user, so they have no scan to record.
+async fn record_claimed_scan(
+    conn: &mut SqliteConnection,
+    scan: &NfcScan,
+    claim_id: &str,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO scans (id, location_id, user_id, counter, scanned_at, claimed_at, claim_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET claimed_at = excluded.claimed_at, claim_id = excluded.claim_id
+        "#,
+    )
+    .bind(&scan.id)
+    .bind(&scan.location_id)
+    .bind(&scan.user_id)
+    .bind(scan.counter)
+    .bind(scan.scanned_at)
+    .bind(now)
+    .bind(claim_id)
+    .execute(&mut *conn)
+    .await?;
+
+    // This claim may have been the last stop of a hunt
+    complete_hunts(conn, &scan.user_id, &scan.location_id).await
+}
+
+/// Collect a claim into the team wallet if the user's team has it enabled,
+/// otherwise into the user's own wallet.
+///
+/// Meant to be called inside the claim's transaction, after the claim is recorded.
+async fn credit_claim(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+    location_id: &str,
+    msats: i64,
+    claim_id: &str,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let team_id: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT t.id FROM teams t
+        JOIN team_members m ON m.team_id = t.id
+        WHERE m.user_id = ? AND t.wallet_enabled = 1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let wallet = match team_id {
+        Some(team_id) => {
+            sqlx::query(
+                "INSERT INTO team_transactions (id, team_id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, ?, ?, ?, 'collect', ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&team_id)
+            .bind(user_id)
+            .bind(location_id)
+            .bind(msats)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+            LedgerAccount::TeamWallet(team_id)
+        }
+        None => {
+            sqlx::query(
+                "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, ?, ?, 'collect', ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(location_id)
+            .bind(msats)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+            LedgerAccount::UserWallet(user_id.to_string())
+        }
+    };
+
+    post_ledger_entry(
+        conn,
+        LedgerEntryKind::Claim,
+        &LedgerAccount::LocationPool(location_id.to_string()),
+        &wallet,
+        msats,
+        Some(claim_id),
+    )
+    .await
+}
+
+/// Donate the budget an ended campaign has left to the active locations in its
+/// region, split equally like a global donation. The rounding remainder, or
+/// everything if no location is in the region, stays unallocated. The budget
+/// counts as spent afterwards.
+///
+/// Meant to be called inside the transaction that ended the campaign.
+async fn settle_campaign_leftover(
+    conn: &mut SqliteConnection,
+    campaign: &MatchingCampaign,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let leftover_msats = campaign.remaining_msats();
+    if leftover_msats <= 0 {
+        return Ok(());
+    }
+
+    let locations: Vec<Location> =
+        sqlx::query_as("SELECT * FROM locations WHERE status = 'active' ORDER BY created_at ASC")
+            .fetch_all(&mut *conn)
+            .await?;
+    let locations: Vec<&Location> = locations
+        .iter()
+        .filter(|l| campaign.covers(l.latitude, l.longitude))
+        .collect();
+
+    let mut donated_msats = 0;
+    if !locations.is_empty() {
+        let amount_per_location = leftover_msats / locations.len() as i64;
+        if amount_per_location > 0 {
+            for location in &locations {
+                let leftover_id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    r#"
+                    INSERT INTO donations (
+                        id, location_id, invoice, amount_msats, status, created_at, received_at,
+                        campaign_id
+                    )
+                    VALUES (?, ?, ?, ?, 'received', ?, ?, ?)
+                    "#,
+                )
+                .bind(&leftover_id)
+                .bind(&location.id)
+                .bind(format!(
+                    "{}-leftover-{}",
+                    campaign.funding_invoice, location.id
+                ))
+                .bind(amount_per_location)
+                .bind(now)
+                .bind(now)
+                .bind(&campaign.id)
+                .execute(&mut *conn)
+                .await?;
+
+                post_ledger_entry(
+                    conn,
+                    LedgerEntryKind::CampaignLeftover,
+                    &LedgerAccount::Campaign(campaign.id.clone()),
+                    &LedgerAccount::LocationPool(location.id.clone()),
+                    amount_per_location,
+                    Some(&leftover_id),
+                )
+                .await?;
+                donated_msats += amount_per_location;
+            }
+        }
+    }
+
+    post_ledger_entry(
+        conn,
+        LedgerEntryKind::CampaignLeftover,
+        &LedgerAccount::Campaign(campaign.id.clone()),
+        &LedgerAccount::Unallocated,
+        leftover_msats - donated_msats,
+        Some(&campaign.id),
+    )
+    .await?;
+
+    sqlx::query("UPDATE matching_campaigns SET spent_msats = budget_msats WHERE id = ?")
+        .bind(&campaign.id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Random code for a team's invite link
+fn new_invite_code() -> String {
+    use rand::{thread_rng, RngCore};
+    let mut code = [0u8; 16];
+    thread_rng().fill_bytes(&mut code);
+    hex::encode(code)
+}
+
+/// Balance of a team wallet: collections minus withdrawals
+async fn team_wallet_balance(conn: &mut SqliteConnection, team_id: &str) -> Result<i64> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(SUM(CASE WHEN transaction_type = 'collect' THEN msats ELSE -msats END), 0)
+        FROM team_transactions WHERE team_id = ?
+        "#,
+    )
+    .bind(team_id)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(Into::into)
+}
+
 #[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
@@ -203,6 +769,16 @@ impl Database {
         .map_err(Into::into)
     }
 
+    /// Locations hidden because they are outside their activation schedule
+    pub async fn list_scheduled_locations(&self) -> Result<Vec<Location>> {
+        sqlx::query_as::<_, Location>(
+            "SELECT * FROM locations WHERE status = 'scheduled' ORDER BY created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
     pub async fn get_locations_by_user(&self, user_id: &str) -> Result<Vec<Location>> {
         sqlx::query_as::<_, Location>(
             "SELECT * FROM locations WHERE user_id = ? ORDER BY created_at DESC",
@@ -218,21 +794,87 @@ impl Database {
         id: &str,
         status: &str,
     ) -> Result<SqliteQueryResult> {
-        sqlx::query("UPDATE locations SET status = ? WHERE id = ?")
-            .bind(status)
+        // Going live restarts the clock for finds right after activation
+        sqlx::query(
+            "UPDATE locations SET status = ?, activated_at = CASE WHEN ? = 'active' THEN ? ELSE activated_at END WHERE id = ?",
+        )
+        .bind(status)
+        .bind(status)
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn update_location_claim_policy(&self, id: &str, policy: ClaimPolicy) -> Result<()> {
+        sqlx::query("UPDATE locations SET claim_policy = ? WHERE id = ?")
+            .bind(policy.as_str())
             .bind(id)
             .execute(&self.pool)
-            .await
-            .map_err(Into::into)
+            .await?;
+        Ok(())
     }
 
+    /// Set how much of a location's position is shown. A None search area
+    /// keeps the stored one.
+    pub async fn update_location_disclosure(
+        &self,
+        id: &str,
+        disclosure: Disclosure,
+        search_area: Option<&SearchArea>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE locations SET
+                disclosure = ?,
+                search_radius_m = COALESCE(?, search_radius_m),
+                search_latitude = COALESCE(?, search_latitude),
+                search_longitude = COALESCE(?, search_longitude)
+            WHERE id = ?
+            "#,
+        )
+        .bind(disclosure.as_str())
+        .bind(search_area.map(|a| a.radius_m))
+        .bind(search_area.map(|a| a.latitude))
+        .bind(search_area.map(|a| a.longitude))
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a location. Whatever is left in its pool is returned to unallocated.
     pub async fn delete_location(&self, id: &str, user_id: &str) -> Result<SqliteQueryResult> {
-        sqlx::query("DELETE FROM locations WHERE id = ? AND user_id = ? AND status != 'active'")
-            .bind(id)
-            .bind(user_id)
-            .execute(&self.pool)
-            .await
-            .map_err(Into::into)
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "DELETE FROM locations WHERE id = ? AND user_id = ? AND status NOT IN ('active', 'scheduled')",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            let pool = LedgerAccount::LocationPool(id.to_string());
+            let remaining_msats = ledger_account_balance(&mut tx, &pool).await?;
+            if remaining_msats > 0 {
+                post_ledger_entry(
+                    &mut tx,
+                    LedgerEntryKind::LocationClosed,
+                    &pool,
+                    &LedgerAccount::Unallocated,
+                    remaining_msats,
+                    Some(id),
+                )
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(result)
     }
 
     // Photo operations
@@ -287,14 +929,30 @@ impl Database {
         invoice: String,
         amount_msats: i64,
         location_id: Option<&str>,
+    ) -> Result<Donation> {
+        self.create_lnurl_pay_donation(invoice, amount_msats, location_id, None, None)
+            .await
+    }
+
+    /// Create a new donation paid over LNURL-pay, with the donor's comment (LUD-12)
+    /// and zap request (NIP-57) if they sent one
+    pub async fn create_lnurl_pay_donation(
+        &self,
+        invoice: String,
+        amount_msats: i64,
+        location_id: Option<&str>,
+        comment: Option<&str>,
+        zap_request: Option<&str>,
     ) -> Result<Donation> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
         sqlx::query_as::<_, Donation>(
             r#"
-            INSERT INTO donations (id, location_id, invoice, amount_msats, status, created_at)
-            VALUES (?, ?, ?, ?, 'created', ?)
+            INSERT INTO donations (
+                id, location_id, invoice, amount_msats, status, created_at, comment, zap_request
+            )
+            VALUES (?, ?, ?, ?, 'created', ?, ?, ?)
             RETURNING *
             "#,
         )
@@ -303,6 +961,8 @@ impl Database {
         .bind(&invoice)
         .bind(amount_msats)
         .bind(now)
+        .bind(comment)
+        .bind(zap_request)
         .fetch_one(&self.pool)
         .await
         .map_err(Into::into)
@@ -318,30 +978,56 @@ impl Database {
     }
 
     /// Mark a donation as received.
-    /// For global donations (location_id = NULL), splits the amount equally among all active locations.
-    pub async fn mark_donation_received(&self, invoice: &str) -> Result<Donation> {
+    /// For global donations (location_id and hunt_id = NULL), splits the amount equally among all active locations.
+    ///
+    /// Returns `None` if the donation was already received, so it's only booked once.
+    pub async fn mark_donation_received(&self, invoice: &str) -> Result<Option<Donation>> {
+        let mut tx = self.pool.begin().await?;
         let now = Utc::now();
 
         // First, get the donation to check if it's global
-        let donation: Donation = sqlx::query_as(
-            "UPDATE donations SET status = 'received', received_at = ? WHERE invoice = ? RETURNING *",
+        let donation: Option<Donation> = sqlx::query_as(
+            "UPDATE donations SET status = 'received', received_at = ? WHERE invoice = ? AND status = 'created' RETURNING *",
         )
         .bind(now)
         .bind(invoice)
-        .fetch_one(&self.pool)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(donation) = donation else {
+            return Ok(None);
+        };
+
+        let donation_account = match (&donation.location_id, &donation.hunt_id) {
+            (Some(location_id), _) => LedgerAccount::LocationPool(location_id.clone()),
+            (None, Some(hunt_id)) => LedgerAccount::HuntPool(hunt_id.clone()),
+            (None, None) => LedgerAccount::Unallocated,
+        };
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::Donation,
+            &LedgerAccount::Node,
+            &donation_account,
+            donation.amount_msats,
+            Some(&donation.id),
+        )
         .await?;
 
-        // If it's a global donation, split it among all active locations
-        if donation.location_id.is_none() {
-            let locations = self.list_active_locations().await?;
+        // If it's a global donation, split it among all active locations.
+        // Any rounding remainder stays unallocated.
+        if donation.location_id.is_none() && donation.hunt_id.is_none() {
+            let locations: Vec<Location> = sqlx::query_as(
+                "SELECT * FROM locations WHERE status = 'active' ORDER BY created_at DESC",
+            )
+            .fetch_all(&mut *tx)
+            .await?;
             if !locations.is_empty() {
                 let amount_per_location = donation.amount_msats / locations.len() as i64;
                 if amount_per_location > 0 {
                     for location in &locations {
                         let split_id = Uuid::new_v4().to_string();
                         sqlx::query(
-                            "INSERT INTO donations (id, location_id, invoice, amount_msats, status, created_at, received_at) \
-                             VALUES (?, ?, ?, ?, 'received', ?, ?)",
+                            "INSERT INTO donations (id, location_id, invoice, amount_msats, status, created_at, received_at, comment) \
+                             VALUES (?, ?, ?, ?, 'received', ?, ?, ?)",
                         )
                         .bind(&split_id)
                         .bind(&location.id)
@@ -349,14 +1035,27 @@ impl Database {
                         .bind(amount_per_location)
                         .bind(now)
                         .bind(now)
-                        .execute(&self.pool)
+                        .bind(&donation.comment)
+                        .execute(&mut *tx)
+                        .await?;
+
+                        post_ledger_entry(
+                            &mut tx,
+                            LedgerEntryKind::DonationSplit,
+                            &LedgerAccount::Unallocated,
+                            &LedgerAccount::LocationPool(location.id.clone()),
+                            amount_per_location,
+                            Some(&split_id),
+                        )
                         .await?;
                     }
                 }
             }
         }
 
-        Ok(donation)
+        tx.commit().await?;
+
+        Ok(Some(donation))
     }
 
     /// Mark a donation as timed out
@@ -403,6 +1102,49 @@ impl Database {
         Ok(donations.0 - claims.0)
     }
 
+    /// Get the combined balance of all location donation pools
+    pub async fn get_total_location_pool_balance(&self) -> Result<i64> {
+        let donations: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE location_id IS NOT NULL AND status = 'received'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let claims: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(msats_claimed), 0) FROM claims")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(donations - claims)
+    }
+
+    /// List received donations paid through a zap whose receipt isn't published yet
+    pub async fn list_unpublished_zaps(&self) -> Result<Vec<Donation>> {
+        sqlx::query_as::<_, Donation>(
+            r#"
+            SELECT * FROM donations
+            WHERE status = 'received' AND zap_request IS NOT NULL AND zap_receipt_id IS NULL
+            ORDER BY received_at ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Record the zap receipt published for a donation
+    pub async fn mark_zap_receipt_published(
+        &self,
+        donation_id: &str,
+        receipt_id: &str,
+    ) -> Result<()> {
+        sqlx::query("UPDATE donations SET zap_receipt_id = ? WHERE id = ?")
+            .bind(receipt_id)
+            .bind(donation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     /// List all received donations for a location (for display on location page)
     pub async fn list_location_donations(&self, location_id: &str) -> Result<Vec<Donation>> {
         sqlx::query_as::<_, Donation>(
@@ -424,12 +1166,16 @@ impl Database {
     /// We filter these out to show one entry per actual donation:
     /// - Global donations show with their full amount (location_id IS NULL)
     /// - Per-location donations show as-is (no splits created for them)
+    ///
+    /// Matching donations paid by campaigns and donations to hunts are left out as well.
     pub async fn list_all_received_donations(&self, limit: i64) -> Result<Vec<Donation>> {
         sqlx::query_as::<_, Donation>(
             r#"
             SELECT * FROM donations
             WHERE status = 'received'
               AND invoice NOT LIKE '%-split-%'
+              AND campaign_id IS NULL
+              AND hunt_id IS NULL
             ORDER BY received_at DESC
             LIMIT ?
             "#,
@@ -440,541 +1186,3198 @@ impl Database {
         .map_err(Into::into)
     }
 
-    // Claim operations (formerly scan operations)
-    pub async fn record_claim(
+    // =========================================================================
+    // Donation matching campaigns
+    // =========================================================================
+
+    /// Create a campaign that waits for its funding invoice to be paid
+    pub async fn create_matching_campaign(
         &self,
-        location_id: &str,
-        msats_claimed: i64,
-        user_id: Option<&str>,
-    ) -> Result<Claim> {
+        campaign: &NewMatchingCampaign,
+        funding_invoice: &str,
+    ) -> Result<MatchingCampaign> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
 
-        // Update last_withdraw_at on the location
-        sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
-            .bind(now)
-            .bind(location_id)
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query_as::<_, Claim>(
-            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, ?) RETURNING *"
+        sqlx::query_as::<_, MatchingCampaign>(
+            r#"
+            INSERT INTO matching_campaigns (
+                id, name, sponsor_name, budget_msats, match_percent, max_match_msats,
+                min_latitude, max_latitude, min_longitude, max_longitude,
+                ends_at, status, funding_invoice, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
         )
         .bind(&id)
-        .bind(location_id)
-        .bind(msats_claimed)
+        .bind(&campaign.name)
+        .bind(&campaign.sponsor_name)
+        .bind(campaign.budget_msats)
+        .bind(campaign.match_percent)
+        .bind(campaign.max_match_msats)
+        .bind(campaign.min_latitude)
+        .bind(campaign.max_latitude)
+        .bind(campaign.min_longitude)
+        .bind(campaign.max_longitude)
+        .bind(campaign.ends_at)
+        .bind(CampaignStatus::Funding.as_str())
+        .bind(funding_invoice)
         .bind(now)
-        .bind(user_id)
         .fetch_one(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    pub async fn get_claims_for_location(&self, location_id: &str) -> Result<Vec<Claim>> {
-        sqlx::query_as::<_, Claim>(
-            "SELECT * FROM claims WHERE location_id = ? ORDER BY claimed_at DESC",
+    pub async fn get_matching_campaign(&self, id: &str) -> Result<Option<MatchingCampaign>> {
+        sqlx::query_as::<_, MatchingCampaign>("SELECT * FROM matching_campaigns WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// List all campaigns, newest first
+    pub async fn list_matching_campaigns(&self) -> Result<Vec<MatchingCampaign>> {
+        sqlx::query_as::<_, MatchingCampaign>(
+            "SELECT * FROM matching_campaigns ORDER BY created_at DESC",
         )
-        .bind(location_id)
         .fetch_all(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    // NFC Scan operations (recording validated NFC taps before claiming)
-
-    /// Record a validated NFC scan (updates counter atomically)
-    /// Returns the scan record, or None if counter already used
-    pub async fn record_nfc_scan(
-        &self,
-        location_id: &str,
-        user_id: &str,
-        new_counter: i64,
-    ) -> Result<Option<NfcScan>> {
+    /// List campaigns that can currently match donations, oldest first
+    pub async fn list_active_matching_campaigns(&self) -> Result<Vec<MatchingCampaign>> {
+        sqlx::query_as::<_, MatchingCampaign>(
+            r#"
+            SELECT * FROM matching_campaigns
+            WHERE status = ? AND ends_at > ? AND spent_msats < budget_msats
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(CampaignStatus::Active.as_str())
+        .bind(Utc::now())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// List campaigns still waiting for their funding invoice to be paid
+    pub async fn list_funding_matching_campaigns(&self) -> Result<Vec<MatchingCampaign>> {
+        sqlx::query_as::<_, MatchingCampaign>(
+            "SELECT * FROM matching_campaigns WHERE status = ? ORDER BY created_at ASC",
+        )
+        .bind(CampaignStatus::Funding.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Activate a campaign once the sponsor paid its funding invoice.
+    /// The budget is booked from the node into the campaign's ledger account.
+    pub async fn mark_campaign_funded(&self, id: &str) -> Result<MatchingCampaign> {
         let mut tx = self.pool.begin().await?;
 
-        // Verify counter is unused
-        let card: Option<NfcCard> = sqlx::query_as("SELECT * FROM nfc_cards WHERE location_id = ?")
-            .bind(location_id)
-            .fetch_optional(&mut *tx)
-            .await?;
+        let campaign: MatchingCampaign = sqlx::query_as(
+            "UPDATE matching_campaigns SET status = ?, funded_at = ? WHERE id = ? AND status = ? RETURNING *",
+        )
+        .bind(CampaignStatus::Active.as_str())
+        .bind(Utc::now())
+        .bind(id)
+        .bind(CampaignStatus::Funding.as_str())
+        .fetch_one(&mut *tx)
+        .await?;
 
-        let card = match card {
-            Some(c) => c,
-            None => return Ok(None),
-        };
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::CampaignFunding,
+            &LedgerAccount::Node,
+            &LedgerAccount::Campaign(campaign.id.clone()),
+            campaign.budget_msats,
+            Some(&campaign.id),
+        )
+        .await?;
 
-        if new_counter <= card.counter {
-            return Ok(None); // Counter already used (replay)
-        }
+        tx.commit().await?;
 
-        let now = Utc::now();
+        Ok(campaign)
+    }
 
-        // Update the counter
-        sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
-            .bind(new_counter)
-            .bind(now)
-            .bind(location_id)
-            .execute(&mut *tx)
-            .await?;
+    /// Give up on a campaign whose funding invoice expired unpaid, so it's no
+    /// longer awaited. Returns false if the campaign isn't waiting for funding.
+    pub async fn expire_matching_campaign(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE matching_campaigns SET status = ?, ended_at = ? WHERE id = ? AND status = ?",
+        )
+        .bind(CampaignStatus::Expired.as_str())
+        .bind(Utc::now())
+        .bind(id)
+        .bind(CampaignStatus::Funding.as_str())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        // Record the scan
-        let scan_id = Uuid::new_v4().to_string();
-        sqlx::query(
-            "INSERT INTO scans (id, location_id, user_id, counter, scanned_at) VALUES (?, ?, ?, ?, ?)",
+    /// Stop an active campaign early. Its unspent budget is donated to the
+    /// locations in its region (see `settle_campaign_leftover`).
+    ///
+    /// Campaigns still waiting for funding can't be ended, since their invoice may
+    /// still be paid. Returns false if the campaign isn't active.
+    pub async fn end_matching_campaign(&self, id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+
+        let campaign: Option<MatchingCampaign> = sqlx::query_as(
+            "UPDATE matching_campaigns SET status = ?, ended_at = ? WHERE id = ? AND status = ? RETURNING *",
         )
-        .bind(&scan_id)
-        .bind(location_id)
-        .bind(user_id)
-        .bind(new_counter)
+        .bind(CampaignStatus::Ended.as_str())
         .bind(now)
-        .execute(&mut *tx)
+        .bind(id)
+        .bind(CampaignStatus::Active.as_str())
+        .fetch_optional(&mut *tx)
         .await?;
 
-        tx.commit().await?;
+        if let Some(campaign) = &campaign {
+            settle_campaign_leftover(&mut tx, campaign, now).await?;
+        }
 
-        // Fetch the scan we just created
-        let scan = sqlx::query_as::<_, NfcScan>("SELECT * FROM scans WHERE id = ?")
-            .bind(&scan_id)
-            .fetch_one(&self.pool)
-            .await?;
+        tx.commit().await?;
 
-        Ok(Some(scan))
+        Ok(campaign.is_some())
     }
 
-    /// Get the last scan for a location (most recent by scanned_at)
-    pub async fn get_last_scan_for_location(&self, location_id: &str) -> Result<Option<NfcScan>> {
-        sqlx::query_as::<_, NfcScan>(
-            "SELECT * FROM scans WHERE location_id = ? ORDER BY scanned_at DESC LIMIT 1",
+    /// End the active campaigns whose deadline passed, donating their unspent
+    /// budget to the locations in their region. Returns the number ended.
+    ///
+    /// Donations received before the deadline should be matched first.
+    pub async fn end_due_matching_campaigns(&self) -> Result<usize> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+
+        let campaigns: Vec<MatchingCampaign> = sqlx::query_as(
+            "UPDATE matching_campaigns SET status = ?, ended_at = ? WHERE status = ? AND ends_at <= ? RETURNING *",
         )
-        .bind(location_id)
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(Into::into)
+        .bind(CampaignStatus::Ended.as_str())
+        .bind(now)
+        .bind(CampaignStatus::Active.as_str())
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for campaign in &campaigns {
+            settle_campaign_leftover(&mut tx, campaign, now).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(campaigns.len())
     }
 
-    /// Get all scans for a location with user display info
-    pub async fn get_scans_with_user_for_location(
-        &self,
-        location_id: &str,
-    ) -> Result<Vec<ScanWithUser>> {
-        sqlx::query_as::<_, ScanWithUser>(
+    /// Received donations that no campaign matched yet, oldest first. Only
+    /// donations received since the oldest active campaign was funded can still
+    /// be matched.
+    pub async fn list_unmatched_donations(&self) -> Result<Vec<Donation>> {
+        sqlx::query_as::<_, Donation>(
             r#"
-            SELECT
-                s.id,
-                s.location_id,
-                s.user_id,
-                s.scanned_at,
-                s.claimed_at,
-                u.username,
-                c.msats_claimed,
-                (s.scanned_at = (SELECT MAX(scanned_at) FROM scans WHERE location_id = s.location_id)) AS is_latest
-            FROM scans s
-            LEFT JOIN users u ON s.user_id = u.id
-            LEFT JOIN claims c ON s.claim_id = c.id
-            WHERE s.location_id = ?
-            ORDER BY s.scanned_at DESC
+            SELECT * FROM donations d
+            WHERE d.status = 'received'
+              AND d.invoice NOT LIKE '%-split-%'
+              AND d.campaign_id IS NULL
+              AND d.hunt_id IS NULL
+              AND d.received_at >= (SELECT MIN(funded_at) FROM matching_campaigns WHERE status = ?)
+              AND NOT EXISTS (SELECT 1 FROM donations m WHERE m.matched_donation_id = d.id)
+            ORDER BY d.received_at ASC
             "#,
         )
-        .bind(location_id)
+        .bind(CampaignStatus::Active.as_str())
         .fetch_all(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    /// Get recent scans for a user with location info
-    pub async fn get_scans_with_location_for_user(
-        &self,
-        user_id: &str,
-        limit: i32,
-    ) -> Result<Vec<ScanWithLocation>> {
-        sqlx::query_as::<_, ScanWithLocation>(
+    /// Match a received donation from every active campaign covering its locations.
+    ///
+    /// A location donation is matched directly, a global donation through its
+    /// per-location split entries. Each match is stored as a received donation to
+    /// the location, tagged with the campaign and the matched donation, and booked
+    /// from the campaign's budget into the location's pool. Only campaigns that
+    /// were running when the donation was received match it, so a donation can
+    /// still be matched after its campaign's deadline (see
+    /// `end_due_matching_campaigns`). Campaigns whose budget runs out are ended.
+    ///
+    /// Returns the matching donations created. Donations that were already matched
+    /// are skipped, so calling this twice is harmless.
+    pub async fn apply_campaign_matches(&self, donation_id: &str) -> Result<Vec<Donation>> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+
+        let donation: Donation = sqlx::query_as(
+            "SELECT * FROM donations WHERE id = ? AND status = 'received' AND campaign_id IS NULL",
+        )
+        .bind(donation_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let already_matched: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM donations WHERE matched_donation_id = ?")
+                .bind(&donation.id)
+                .fetch_one(&mut *tx)
+                .await?;
+        if already_matched > 0 {
+            tx.commit().await?;
+            return Ok(Vec::new());
+        }
+
+        // (location_id, msats, latitude, longitude) of each share of the donation
+        let allocations: Vec<(String, i64, f64, f64)> = sqlx::query_as(
             r#"
-            SELECT
-                s.id,
-                s.location_id,
-                s.user_id,
-                s.scanned_at,
-                s.claimed_at,
-                c.msats_claimed,
-                (s.scanned_at = (SELECT MAX(scanned_at) FROM scans WHERE location_id = s.location_id)) AS is_latest,
-                l.name AS location_name
-            FROM scans s
-            LEFT JOIN claims c ON s.claim_id = c.id
-            JOIN locations l ON s.location_id = l.id
-            WHERE s.user_id = ?
-            ORDER BY s.scanned_at DESC
-            LIMIT ?
+            SELECT d.location_id, d.amount_msats, l.latitude, l.longitude
+            FROM donations d
+            JOIN locations l ON l.id = d.location_id
+            WHERE d.status = 'received'
+              AND (d.id = ? OR d.invoice = ? || '-split-' || d.location_id)
+            ORDER BY l.created_at ASC
             "#,
         )
-        .bind(user_id)
-        .bind(limit)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(Into::into)
-    }
+        .bind(&donation.id)
+        .bind(&donation.invoice)
+        .fetch_all(&mut *tx)
+        .await?;
 
-    /// Get paginated global scan list for admin page
-    pub async fn get_admin_scans(&self, limit: i64, offset: i64) -> Result<Vec<AdminScan>> {
-        sqlx::query_as::<_, AdminScan>(
+        let received_at = donation.received_at.unwrap_or(now);
+        let campaigns: Vec<MatchingCampaign> = sqlx::query_as(
             r#"
-            SELECT
-                s.id,
-                s.location_id,
-                s.user_id,
-                s.scanned_at,
-                s.claimed_at,
-                c.msats_claimed,
-                l.name AS location_name,
-                creator.username AS creator_username,
-                l.user_id AS creator_user_id,
-                scanner.username AS scanner_username
-            FROM scans s
-            LEFT JOIN claims c ON s.claim_id = c.id
-            JOIN locations l ON s.location_id = l.id
-            LEFT JOIN users creator ON l.user_id = creator.id
-            LEFT JOIN users scanner ON s.user_id = scanner.id
-            ORDER BY s.scanned_at DESC
-            LIMIT ? OFFSET ?
+            SELECT * FROM matching_campaigns
+            WHERE status = ? AND spent_msats < budget_msats AND funded_at <= ? AND ends_at > ?
+            ORDER BY created_at ASC
             "#,
         )
-        .bind(limit)
-        .bind(offset)
-        .fetch_all(&self.pool)
-        .await
-        .map_err(Into::into)
-    }
+        .bind(CampaignStatus::Active.as_str())
+        .bind(received_at)
+        .bind(received_at)
+        .fetch_all(&mut *tx)
+        .await?;
 
-    /// Get total scan count for pagination
-    pub async fn count_scans(&self) -> Result<i64> {
-        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM scans")
-            .fetch_one(&self.pool)
-            .await?;
-        Ok(row.0)
+        let mut matches = Vec::new();
+        for mut campaign in campaigns {
+            let mut matched_msats = 0;
+            for (location_id, msats, latitude, longitude) in &allocations {
+                if !campaign.covers(*latitude, *longitude) {
+                    continue;
+                }
+                let match_msats = campaign.match_msats(*msats, matched_msats);
+                if match_msats <= 0 {
+                    continue;
+                }
+
+                let match_donation: Donation = sqlx::query_as(
+                    r#"
+                    INSERT INTO donations (
+                        id, location_id, invoice, amount_msats, status, created_at, received_at,
+                        campaign_id, matched_donation_id
+                    )
+                    VALUES (?, ?, ?, ?, 'received', ?, ?, ?, ?)
+                    RETURNING *
+                    "#,
+                )
+                .bind(Uuid::new_v4().to_string())
+                .bind(location_id)
+                .bind(format!(
+                    "{}-match-{}-{}",
+                    donation.invoice, campaign.id, location_id
+                ))
+                .bind(match_msats)
+                .bind(now)
+                .bind(now)
+                .bind(&campaign.id)
+                .bind(&donation.id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                post_ledger_entry(
+                    &mut tx,
+                    LedgerEntryKind::CampaignMatch,
+                    &LedgerAccount::Campaign(campaign.id.clone()),
+                    &LedgerAccount::LocationPool(location_id.clone()),
+                    match_msats,
+                    Some(&match_donation.id),
+                )
+                .await?;
+
+                campaign.spent_msats += match_msats;
+                matched_msats += match_msats;
+                matches.push(match_donation);
+            }
+
+            if matched_msats > 0 {
+                let result = sqlx::query(
+                    r#"
+                    UPDATE matching_campaigns
+                    SET spent_msats = spent_msats + ?,
+                        status = CASE WHEN spent_msats + ? >= budget_msats THEN ? ELSE status END,
+                        ended_at = CASE WHEN spent_msats + ? >= budget_msats THEN ? ELSE ended_at END
+                    WHERE id = ? AND spent_msats + ? <= budget_msats
+                    "#,
+                )
+                .bind(matched_msats)
+                .bind(matched_msats)
+                .bind(CampaignStatus::Ended.as_str())
+                .bind(matched_msats)
+                .bind(now)
+                .bind(&campaign.id)
+                .bind(matched_msats)
+                .execute(&mut *tx)
+                .await?;
+                if result.rows_affected() == 0 {
+                    return Err(anyhow::anyhow!(
+                        "Campaign {} budget changed while matching",
+                        campaign.id
+                    ));
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(matches)
     }
 
-    /// Get daily scan counts for a date range (inclusive)
-    pub async fn get_daily_scan_counts(
-        &self,
-        from_date: &str,
-        to_date: &str,
-    ) -> Result<Vec<DailyScanCount>> {
-        sqlx::query_as::<_, DailyScanCount>(
+    /// Sats matched by campaigns, per matched donation and campaign
+    pub async fn list_donation_matches(&self) -> Result<Vec<DonationMatch>> {
+        sqlx::query_as::<_, DonationMatch>(
             r#"
-            SELECT
-                DATE(scanned_at) AS date,
-                COUNT(*) AS count
-            FROM scans
-            WHERE DATE(scanned_at) >= ? AND DATE(scanned_at) <= ?
-            GROUP BY DATE(scanned_at)
-            ORDER BY date ASC
+            SELECT d.matched_donation_id AS donation_id, c.id AS campaign_id,
+                   c.sponsor_name, SUM(d.amount_msats) AS msats
+            FROM donations d
+            JOIN matching_campaigns c ON c.id = d.campaign_id
+            WHERE d.status = 'received' AND d.matched_donation_id IS NOT NULL
+            GROUP BY d.matched_donation_id, c.id
             "#,
         )
-        .bind(from_date)
-        .bind(to_date)
         .fetch_all(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    /// Get the date of the earliest scan (for "all time" graph range)
-    pub async fn get_earliest_scan_date(&self) -> Result<Option<chrono::NaiveDate>> {
-        let date: Option<chrono::NaiveDate> = sqlx::query_scalar(
-            "SELECT DATE(MIN(scanned_at)) FROM scans WHERE scanned_at IS NOT NULL",
+    /// Unspent budget of all funded campaigns
+    pub async fn get_total_campaign_budgets(&self) -> Result<i64> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(SUM(budget_msats - spent_msats), 0) FROM matching_campaigns WHERE status NOT IN (?, ?)",
         )
+        .bind(CampaignStatus::Funding.as_str())
+        .bind(CampaignStatus::Expired.as_str())
         .fetch_one(&self.pool)
-        .await?;
-        Ok(date)
+        .await
+        .map_err(Into::into)
     }
 
-    /// Claim sats from a previous scan
-    /// Returns ClaimResult indicating success or reason for failure
-    pub async fn claim_from_scan(
+    // =========================================================================
+    // Hunts
+    // =========================================================================
+
+    /// Create a hunt over `location_ids`, in trail order
+    pub async fn create_hunt(
         &self,
-        scan_id: &str,
-        user_id: &str,
-        balance_config: &BalanceConfig,
-    ) -> Result<ClaimResult> {
+        name: &str,
+        description: Option<&str>,
+        ordered: bool,
+        completion_bonus_msats: i64,
+        created_by: &str,
+        location_ids: &[String],
+    ) -> Result<Hunt> {
         let mut tx = self.pool.begin().await?;
 
-        // Get the scan
-        let scan: Option<NfcScan> = sqlx::query_as("SELECT * FROM scans WHERE id = ?")
-            .bind(scan_id)
-            .fetch_optional(&mut *tx)
-            .await?;
-
-        let scan = match scan {
-            Some(s) => s,
-            None => return Ok(ClaimResult::ScanNotFound),
-        };
-
-        // Verify user is the scanner
-        if scan.user_id != user_id {
-            return Ok(ClaimResult::NotYourScan);
-        }
-
-        // Check if already claimed
-        if scan.claimed_at.is_some() {
-            return Ok(ClaimResult::AlreadyClaimed);
-        }
-
-        // Check if expired (1 hour)
-        let age = Utc::now().signed_duration_since(scan.scanned_at);
-        if age.num_hours() >= 1 {
-            return Ok(ClaimResult::Expired);
-        }
-
-        // Verify this is still the last scan for the location
-        let last_scan: Option<NfcScan> = sqlx::query_as(
-            "SELECT * FROM scans WHERE location_id = ? ORDER BY scanned_at DESC LIMIT 1",
-        )
-        .bind(&scan.location_id)
-        .fetch_optional(&mut *tx)
-        .await?;
-
-        if last_scan.map(|s| s.id) != Some(scan.id.clone()) {
-            return Ok(ClaimResult::NotLastScanner);
-        }
-
-        // Get location and compute balance
-        let location: Location = sqlx::query_as("SELECT * FROM locations WHERE id = ?")
-            .bind(&scan.location_id)
-            .fetch_one(&mut *tx)
-            .await?;
-
-        // Calculate pool balance
-        let donations: (i64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE location_id = ? AND status = 'received'",
-        )
-        .bind(&scan.location_id)
-        .fetch_one(&mut *tx)
-        .await?;
-
-        let claimed: (i64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(msats_claimed), 0) FROM claims WHERE location_id = ?",
+        let hunt: Hunt = sqlx::query_as(
+            r#"
+            INSERT INTO hunts (id, name, description, ordered, completion_bonus_msats, created_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
         )
-        .bind(&scan.location_id)
+        .bind(Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(description)
+        .bind(ordered)
+        .bind(completion_bonus_msats)
+        .bind(created_by)
+        .bind(Utc::now())
         .fetch_one(&mut *tx)
         .await?;
 
-        let pool_balance_msats = donations.0 - claimed.0;
-
-        let claimable_msats = compute_balance_msats(
-            pool_balance_msats,
-            location.last_withdraw_at,
-            location.created_at,
-            balance_config,
-        );
-
-        if claimable_msats <= 0 {
-            return Ok(ClaimResult::NoBalance);
-        }
-
-        let now = Utc::now();
-
-        // Ensure user exists (lazy creation)
-        let user_exists: bool =
-            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
-                .bind(user_id)
-                .fetch_one(&mut *tx)
-                .await?;
-
-        if !user_exists {
+        for (i, location_id) in location_ids.iter().enumerate() {
             sqlx::query(
-                "INSERT INTO users (id, username, email, auth_method, auth_data, created_at, role) VALUES (?, NULL, NULL, 'anonymous', '{}', ?, 'user')"
+                "INSERT INTO hunt_locations (hunt_id, location_id, position) VALUES (?, ?, ?)",
             )
-            .bind(user_id)
-            .bind(now)
+            .bind(&hunt.id)
+            .bind(location_id)
+            .bind(i as i64 + 1)
             .execute(&mut *tx)
             .await?;
         }
 
-        // Create claim record
-        let claim_id = Uuid::new_v4().to_string();
-        sqlx::query(
-            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, ?)",
-        )
-        .bind(&claim_id)
-        .bind(&scan.location_id)
-        .bind(claimable_msats)
-        .bind(now)
-        .bind(user_id)
-        .execute(&mut *tx)
-        .await?;
+        tx.commit().await?;
 
-        // Update scan as claimed
-        sqlx::query("UPDATE scans SET claimed_at = ?, claim_id = ? WHERE id = ?")
-            .bind(now)
-            .bind(&claim_id)
-            .bind(scan_id)
-            .execute(&mut *tx)
-            .await?;
+        Ok(hunt)
+    }
 
-        // Update location's last_withdraw_at
-        sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
-            .bind(now)
-            .bind(&scan.location_id)
-            .execute(&mut *tx)
-            .await?;
+    pub async fn get_hunt(&self, id: &str) -> Result<Option<Hunt>> {
+        sqlx::query_as::<_, Hunt>("SELECT * FROM hunts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
 
-        // Create user transaction for custodial wallet
-        let tx_id = Uuid::new_v4().to_string();
-        sqlx::query(
-            "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, ?, ?, 'collect', ?)"
-        )
-        .bind(&tx_id)
-        .bind(user_id)
-        .bind(&scan.location_id)
-        .bind(claimable_msats)
-        .bind(now)
-        .execute(&mut *tx)
-        .await?;
+    /// List all hunts, newest first
+    pub async fn list_hunts(&self) -> Result<Vec<Hunt>> {
+        sqlx::query_as::<_, Hunt>("SELECT * FROM hunts ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
 
-        tx.commit().await?;
+    /// Get the stops of a hunt in trail order
+    pub async fn get_hunt_locations(&self, hunt_id: &str) -> Result<Vec<Location>> {
+        sqlx::query_as::<_, Location>(
+            r#"
+            SELECT l.* FROM locations l
+            JOIN hunt_locations hl ON hl.location_id = l.id
+            WHERE hl.hunt_id = ?
+            ORDER BY hl.position
+            "#,
+        )
+        .bind(hunt_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
 
-        Ok(ClaimResult::Success {
-            msats: claimable_msats,
-            claim_id,
-        })
+    /// Get the balance of a hunt's bonus pool
+    pub async fn get_hunt_pool_balance(&self, hunt_id: &str) -> Result<i64> {
+        let mut conn = self.pool.acquire().await?;
+        hunt_pool_balance(&mut conn, hunt_id).await
     }
 
-    // Stats operations
-    pub async fn get_stats(&self) -> Result<Stats> {
-        let total_locations: i64 =
-            sqlx::query_scalar("SELECT COUNT(*) FROM locations WHERE status = 'active'")
+    /// Get the combined balance of all hunt bonus pools
+    pub async fn get_total_hunt_pool_balance(&self) -> Result<i64> {
+        let donations: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE hunt_id IS NOT NULL AND status = 'received'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let bonuses: i64 =
+            sqlx::query_scalar("SELECT COALESCE(SUM(bonus_msats), 0) FROM hunt_completions")
                 .fetch_one(&self.pool)
                 .await?;
 
-        let total_scans: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scans")
-            .fetch_one(&self.pool)
-            .await?;
+        Ok(donations - bonuses)
+    }
 
-        // Total donation pool = sum of all location-specific donations for active locations minus their claims
-        let total_donations: (i64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(d.amount_msats), 0) FROM donations d
-             JOIN locations l ON d.location_id = l.id
-             WHERE l.status = 'active' AND d.status = 'received'",
+    /// Which stops of a hunt a user has done, in trail order
+    pub async fn get_hunt_progress(&self, hunt: &Hunt, user_id: &str) -> Result<Vec<bool>> {
+        let mut conn = self.pool.acquire().await?;
+        let stops: Vec<String> = sqlx::query_scalar(
+            "SELECT location_id FROM hunt_locations WHERE hunt_id = ? ORDER BY position",
         )
-        .fetch_one(&self.pool)
+        .bind(&hunt.id)
+        .fetch_all(&mut *conn)
         .await?;
+        let claims = hunt_claims(&mut conn, hunt, user_id).await?;
+        Ok(hunt::completed_stops(&stops, &claims, hunt.ordered))
+    }
 
-        let total_claimed: (i64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(c.msats_claimed), 0) FROM claims c
-             JOIN locations l ON c.location_id = l.id
-             WHERE l.status = 'active'",
+    /// List the users who completed a hunt, first finisher first
+    pub async fn list_hunt_completions(&self, hunt_id: &str) -> Result<Vec<HuntCompletion>> {
+        sqlx::query_as::<_, HuntCompletion>(
+            r#"
+            SELECT c.id, c.hunt_id, c.user_id, u.username, c.bonus_msats, c.completed_at
+            FROM hunt_completions c
+            LEFT JOIN users u ON u.id = c.user_id
+            WHERE c.hunt_id = ?
+            ORDER BY c.completed_at ASC
+            "#,
         )
-        .fetch_one(&self.pool)
-        .await?;
-
-        let total_pool_msats = total_donations.0 - total_claimed.0;
-
-        Ok(Stats {
-            total_locations,
-            // Total pool balance represents the total sats available across all locations
-            total_sats_available: total_pool_msats.max(0) / 1000,
-            total_scans,
-            donation_pool_sats: total_pool_msats.max(0) / 1000,
-        })
+        .bind(hunt_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
     }
 
-    // NFC card operations
-    pub async fn create_nfc_card(
+    /// Create a new donation to a hunt's bonus pool when an invoice is generated
+    pub async fn create_hunt_donation(
         &self,
-        location_id: String,
-        k0_auth_key: String,
-        k1_decrypt_key: String,
-        k2_cmac_key: String,
-        k3: String,
-        k4: String,
-    ) -> Result<NfcCard> {
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now();
-
-        sqlx::query_as::<_, NfcCard>(
+        invoice: String,
+        amount_msats: i64,
+        hunt_id: &str,
+    ) -> Result<Donation> {
+        sqlx::query_as::<_, Donation>(
             r#"
-            INSERT INTO nfc_cards (
-                id, location_id, k0_auth_key, k1_decrypt_key, k2_cmac_key, k3, k4,
-                counter, version, created_at
-            )
-            VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0, ?)
+            INSERT INTO donations (id, hunt_id, invoice, amount_msats, status, created_at)
+            VALUES (?, ?, ?, ?, 'created', ?)
             RETURNING *
             "#,
         )
-        .bind(&id)
-        .bind(&location_id)
-        .bind(&k0_auth_key)
-        .bind(&k1_decrypt_key)
-        .bind(&k2_cmac_key)
-        .bind(&k3)
-        .bind(&k4)
-        .bind(now)
+        .bind(Uuid::new_v4().to_string())
+        .bind(hunt_id)
+        .bind(&invoice)
+        .bind(amount_msats)
+        .bind(Utc::now())
         .fetch_one(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    pub async fn get_nfc_card_by_location(&self, location_id: &str) -> Result<Option<NfcCard>> {
-        sqlx::query_as::<_, NfcCard>("SELECT * FROM nfc_cards WHERE location_id = ?")
-            .bind(location_id)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(Into::into)
+    // =========================================================================
+    // Schedules
+    // =========================================================================
+
+    pub async fn create_schedule(&self, schedule: &NewSchedule) -> Result<Schedule> {
+        sqlx::query_as::<_, Schedule>(
+            r#"
+            INSERT INTO schedules (id, location_id, hunt_id, starts_at, ends_at, recurrence, created_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&schedule.location_id)
+        .bind(&schedule.hunt_id)
+        .bind(schedule.starts_at)
+        .bind(schedule.ends_at)
+        .bind(schedule.recurrence.as_str())
+        .bind(&schedule.created_by)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
     }
 
-    /// Get NFC card by UID - will be used for payment verification with NFC taps
-    #[allow(dead_code)]
-    pub async fn get_nfc_card_by_uid(&self, uid: &str) -> Result<Option<NfcCard>> {
-        sqlx::query_as::<_, NfcCard>("SELECT * FROM nfc_cards WHERE uid = ?")
-            .bind(uid)
+    pub async fn get_schedule(&self, id: &str) -> Result<Option<Schedule>> {
+        sqlx::query_as::<_, Schedule>("SELECT * FROM schedules WHERE id = ?")
+            .bind(id)
             .fetch_optional(&self.pool)
             .await
             .map_err(Into::into)
     }
 
-    pub async fn update_nfc_card_uid_and_mark_programmed(
-        &self,
-        location_id: &str,
-        uid: &str,
-    ) -> Result<SqliteQueryResult> {
-        sqlx::query("UPDATE nfc_cards SET uid = ?, programmed_at = ? WHERE location_id = ?")
-            .bind(uid)
-            .bind(Utc::now())
-            .bind(location_id)
+    pub async fn delete_schedule(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM schedules WHERE id = ?")
+            .bind(id)
             .execute(&self.pool)
-            .await
-            .map_err(Into::into)
+            .await?;
+        Ok(result.rows_affected() > 0)
     }
 
-    pub async fn increment_nfc_card_version(&self, location_id: &str) -> Result<SqliteQueryResult> {
-        sqlx::query("UPDATE nfc_cards SET version = version + 1 WHERE location_id = ?")
-            .bind(location_id)
-            .execute(&self.pool)
-            .await
-            .map_err(Into::into)
+    /// Schedules that apply to a location, including those of its hunts
+    pub async fn get_location_schedules(&self, location_id: &str) -> Result<Vec<Schedule>> {
+        let mut conn = self.pool.acquire().await?;
+        location_schedules(&mut conn, location_id).await
     }
 
-    pub async fn reset_nfc_card_counter(&self, location_id: &str) -> Result<SqliteQueryResult> {
-        sqlx::query("UPDATE nfc_cards SET counter = 0 WHERE location_id = ?")
-            .bind(location_id)
-            .execute(&self.pool)
-            .await
-            .map_err(Into::into)
+    pub async fn get_hunt_schedules(&self, hunt_id: &str) -> Result<Vec<Schedule>> {
+        sqlx::query_as::<_, Schedule>(
+            "SELECT * FROM schedules WHERE hunt_id = ? ORDER BY starts_at",
+        )
+        .bind(hunt_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
     }
 
-    /// Atomically claim a withdrawal by updating the counter and recording the scan.
-    ///
-    /// This prevents double-spending by checking the counter hasn't been used yet
-    /// and updating it in a single transaction. The balance is computed based on
-    /// time since last withdrawal and the donation pool balance. Recording the scan
+    /// Schedules that apply to each location with any, keyed by location id
+    pub async fn get_all_location_schedules(&self) -> Result<BTreeMap<String, Vec<Schedule>>> {
+        let schedules: Vec<Schedule> = sqlx::query_as("SELECT * FROM schedules ORDER BY starts_at")
+            .fetch_all(&self.pool)
+            .await?;
+        let stops: Vec<(String, String)> =
+            sqlx::query_as("SELECT hunt_id, location_id FROM hunt_locations")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut by_location: BTreeMap<String, Vec<Schedule>> = BTreeMap::new();
+        for schedule in schedules {
+            if let Some(location_id) = &schedule.location_id {
+                by_location
+                    .entry(location_id.clone())
+                    .or_default()
+                    .push(schedule.clone());
+            }
+            if let Some(hunt_id) = &schedule.hunt_id {
+                for (_, location_id) in stops.iter().filter(|(h, _)| h == hunt_id) {
+                    by_location
+                        .entry(location_id.clone())
+                        .or_default()
+                        .push(schedule.clone());
+                }
+            }
+        }
+        Ok(by_location)
+    }
+
+    /// Switch active locations outside their schedule windows to 'scheduled', and
+    /// back to 'active' once a window opens (or their schedules were removed).
+    /// Returns how many locations were (opened, closed).
+    pub async fn apply_schedules(&self, now: chrono::DateTime<Utc>) -> Result<(u64, u64)> {
+        let schedules = self.get_all_location_schedules().await?;
+        let locations: Vec<Location> =
+            sqlx::query_as("SELECT * FROM locations WHERE status IN ('active', 'scheduled')")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut opened = 0;
+        let mut closed = 0;
+        for location in locations {
+            let open = schedule::is_open(
+                schedules
+                    .get(&location.id)
+                    .map(Vec::as_slice)
+                    .unwrap_or(&[]),
+                now,
+            );
+            // Only flip from the status we read, so a concurrent deactivation wins
+            let (from, to) = match (location.is_active(), open) {
+                (true, false) => ("active", "scheduled"),
+                (false, true) => ("scheduled", "active"),
+                _ => continue,
+            };
+            let result = sqlx::query(
+                "UPDATE locations SET status = ?, activated_at = CASE WHEN ? THEN ? ELSE activated_at END WHERE id = ? AND status = ?",
+            )
+            .bind(to)
+            .bind(open)
+            .bind(now)
+            .bind(&location.id)
+            .bind(from)
+            .execute(&self.pool)
+            .await?;
+            if open {
+                opened += result.rows_affected();
+            } else {
+                closed += result.rows_affected();
+            }
+        }
+
+        Ok((opened, closed))
+    }
+
+    // =========================================================================
+    // Claim rules
+    // =========================================================================
+
+    /// Claim rules a location overrides, without the global ones
+    pub async fn get_location_claim_rules(&self, location_id: &str) -> Result<ClaimRules> {
+        let mut conn = self.pool.acquire().await?;
+        location_claim_rules(&mut conn, location_id).await
+    }
+
+    /// Replace the claim rules a location overrides. Rules left None fall back
+    /// to the global ones.
+    pub async fn set_location_claim_rules(
+        &self,
+        location_id: &str,
+        rules: &ClaimRules,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO location_claim_rules (location_id, cooldown_days, max_claims_per_month, first_claim_bonus_percent, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(location_id) DO UPDATE SET
+                cooldown_days = excluded.cooldown_days,
+                max_claims_per_month = excluded.max_claims_per_month,
+                first_claim_bonus_percent = excluded.first_claim_bonus_percent,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(location_id)
+        .bind(rules.cooldown_days)
+        .bind(rules.max_claims_per_month)
+        .bind(rules.first_claim_bonus_percent)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// When a user claimed at a location before
+    pub async fn get_user_claim_times(
+        &self,
+        user_id: &str,
+        location_id: &str,
+    ) -> Result<Vec<DateTime<Utc>>> {
+        let mut conn = self.pool.acquire().await?;
+        user_claim_times(&mut conn, user_id, location_id).await
+    }
+
+    /// Share of a location's claimable balance a sticker withdrawal would get
+    /// right now, or why it can't claim
+    pub async fn get_withdrawal_share(
+        &self,
+        location: &Location,
+    ) -> Result<Result<f64, ClaimResult>> {
+        let mut conn = self.pool.acquire().await?;
+        let tap = new_tap(&location.id, "", 0, Utc::now());
+        tap_share(&mut conn, location, &tap).await
+    }
+
+    /// Whether a user ever claimed anywhere
+    pub async fn has_user_claimed(&self, user_id: &str) -> Result<bool> {
+        let mut conn = self.pool.acquire().await?;
+        user_has_claimed(&mut conn, user_id).await
+    }
+
+    // =========================================================================
+    // Hints
+    // =========================================================================
+
+    /// Add a hint after the location's existing ones
+    pub async fn create_hint(&self, hint: &NewHint) -> Result<Hint> {
+        sqlx::query_as::<_, Hint>(
+            r#"
+            INSERT INTO hints (
+                id, location_id, position, text, unlock_kind, unlock_after_days, price_msats,
+                riddle, riddle_answer, created_at
+            )
+            VALUES (
+                ?, ?, (SELECT COALESCE(MAX(position), 0) + 1 FROM hints WHERE location_id = ?),
+                ?, ?, ?, ?, ?, ?, ?
+            )
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&hint.location_id)
+        .bind(&hint.location_id)
+        .bind(&hint.text)
+        .bind(hint.unlock_kind.as_str())
+        .bind(hint.unlock_after_days)
+        .bind(hint.price_msats)
+        .bind(&hint.riddle)
+        .bind(&hint.riddle_answer)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_hint(&self, id: &str) -> Result<Option<Hint>> {
+        sqlx::query_as::<_, Hint>("SELECT * FROM hints WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn list_location_hints(&self, location_id: &str) -> Result<Vec<Hint>> {
+        sqlx::query_as::<_, Hint>("SELECT * FROM hints WHERE location_id = ? ORDER BY position")
+            .bind(location_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn delete_hint(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM hints WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// IDs of the hints at a location a user unlocked by paying or answering
+    pub async fn list_unlocked_hint_ids(
+        &self,
+        user_id: &str,
+        location_id: &str,
+    ) -> Result<Vec<String>> {
+        sqlx::query_scalar(
+            r#"
+            SELECT hu.hint_id FROM hint_unlocks hu
+            JOIN hints h ON h.id = hu.hint_id
+            WHERE hu.user_id = ? AND h.location_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .bind(location_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// IDs of the locations where a user can read at least one hint, either
+    /// unlocked by them or unlocked for everyone by time
+    pub async fn list_hint_unlocked_location_ids(&self, user_id: &str) -> Result<HashSet<String>> {
+        let mut location_ids: HashSet<String> = sqlx::query_scalar(
+            "SELECT DISTINCT h.location_id FROM hint_unlocks hu JOIN hints h ON h.id = hu.hint_id WHERE hu.user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let timed: Vec<(String, i64, DateTime<Utc>)> = sqlx::query_as(
+            r#"
+            SELECT h.location_id, h.unlock_after_days, l.created_at FROM hints h
+            JOIN locations l ON l.id = h.location_id
+            WHERE h.unlock_kind = 'time'
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let now = Utc::now();
+        location_ids.extend(
+            timed
+                .into_iter()
+                .filter(|(_, days, created_at)| *created_at + chrono::Duration::days(*days) <= now)
+                .map(|(location_id, _, _)| location_id),
+        );
+
+        Ok(location_ids)
+    }
+
+    /// Record that a user unlocked a hint. Unlocking twice is a no-op.
+    pub async fn unlock_hint(&self, hint_id: &str, user_id: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO hint_unlocks (hint_id, user_id, unlocked_at) VALUES (?, ?, ?)",
+        )
+        .bind(hint_id)
+        .bind(user_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Unlock a payment hint for a user, paying its price from their wallet into
+    /// the location pool. Returns false if the balance is too low. A hint the user
+    /// already unlocked isn't charged again.
+    pub async fn buy_hint(&self, hint: &Hint, user_id: &str) -> Result<bool> {
+        let price_msats = hint
+            .price_msats
+            .ok_or_else(|| anyhow::anyhow!("Hint {} has no price", hint.id))?;
+        let mut tx = self.pool.begin().await?;
+
+        let unlocked: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM hint_unlocks WHERE hint_id = ? AND user_id = ?)",
+        )
+        .bind(&hint.id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if unlocked {
+            return Ok(true);
+        }
+
+        let tx_balance: i64 = sqlx::query_scalar(&format!(
+            r#"
+            SELECT COALESCE(
+                SUM({}),
+                0
+            ) FROM user_transactions WHERE user_id = ?
+            "#,
+            user_tx_signed_msats()
+        ))
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let pending: i64 = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE user_id = ? AND status IN (?, ?)",
+        )
+        .bind(user_id)
+        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_one(&mut *tx)
+        .await?;
+        if tx_balance - pending < price_msats {
+            return Ok(false);
+        }
+
+        let now = Utc::now();
+        sqlx::query("INSERT INTO hint_unlocks (hint_id, user_id, unlocked_at) VALUES (?, ?, ?)")
+            .bind(&hint.id)
+            .bind(user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, ?, ?, 'hint', ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(&hint.location_id)
+        .bind(price_msats)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        // The price goes into the pool like a donation
+        let donation_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO donations (id, location_id, invoice, amount_msats, status, created_at, received_at) \
+             VALUES (?, ?, ?, ?, 'received', ?, ?)",
+        )
+        .bind(&donation_id)
+        .bind(&hint.location_id)
+        .bind(format!("hint-{}-{}", hint.id, user_id))
+        .bind(price_msats)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::HintPurchase,
+            &LedgerAccount::UserWallet(user_id.to_string()),
+            &LedgerAccount::LocationPool(hint.location_id.clone()),
+            price_msats,
+            Some(&donation_id),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    // =========================================================================
+    // Log book
+    // =========================================================================
+
+    /// Add a log to a location's log book, with the photos already saved to the
+    /// upload directory. Maintenance requests notify the location's owner.
+    pub async fn create_location_log(
+        &self,
+        location: &Location,
+        user_id: &str,
+        kind: LogKind,
+        text: &str,
+        photo_paths: &[String],
+    ) -> Result<LocationLog> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+        let log_id = Uuid::new_v4().to_string();
+
+        sqlx::query(
+            "INSERT INTO location_logs (id, location_id, user_id, kind, text, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&log_id)
+        .bind(&location.id)
+        .bind(user_id)
+        .bind(kind.as_str())
+        .bind(text)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        for file_path in photo_paths {
+            sqlx::query(
+                "INSERT INTO location_log_photos (id, log_id, file_path, uploaded_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&log_id)
+            .bind(file_path)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if kind == LogKind::NeedsMaintenance && location.user_id != user_id {
+            let message = if text.is_empty() {
+                format!("{} needs maintenance", location.name)
+            } else {
+                format!("{} needs maintenance: {}", location.name, text)
+            };
+            sqlx::query(
+                "INSERT INTO notifications (id, user_id, location_id, message, created_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&location.user_id)
+            .bind(&location.id)
+            .bind(message)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let log = sqlx::query_as::<_, LocationLog>(
+            r#"
+            SELECT ll.*, u.username FROM location_logs ll
+            LEFT JOIN users u ON u.id = ll.user_id
+            WHERE ll.id = ?
+            "#,
+        )
+        .bind(&log_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(log)
+    }
+
+    pub async fn get_location_log(&self, id: &str) -> Result<Option<LocationLog>> {
+        sqlx::query_as::<_, LocationLog>(
+            r#"
+            SELECT ll.*, u.username FROM location_logs ll
+            LEFT JOIN users u ON u.id = ll.user_id
+            WHERE ll.id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// A page of a location's logs, newest first. Hidden logs are only
+    /// included for moderators.
+    pub async fn list_location_logs(
+        &self,
+        location_id: &str,
+        include_hidden: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<LocationLog>> {
+        sqlx::query_as::<_, LocationLog>(
+            r#"
+            SELECT ll.*, u.username FROM location_logs ll
+            LEFT JOIN users u ON u.id = ll.user_id
+            WHERE ll.location_id = ? AND (? OR ll.hidden_at IS NULL)
+            ORDER BY ll.created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(location_id)
+        .bind(include_hidden)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Number of a location's logs, for pagination
+    pub async fn count_location_logs(
+        &self,
+        location_id: &str,
+        include_hidden: bool,
+    ) -> Result<i64> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM location_logs WHERE location_id = ? AND (? OR hidden_at IS NULL)",
+        )
+        .bind(location_id)
+        .bind(include_hidden)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Photos of all logs at a location
+    pub async fn list_location_log_photos(
+        &self,
+        location_id: &str,
+    ) -> Result<Vec<LocationLogPhoto>> {
+        sqlx::query_as::<_, LocationLogPhoto>(
+            r#"
+            SELECT p.* FROM location_log_photos p
+            JOIN location_logs ll ON ll.id = p.log_id
+            WHERE ll.location_id = ?
+            ORDER BY p.uploaded_at
+            "#,
+        )
+        .bind(location_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_log_photos(&self, log_id: &str) -> Result<Vec<LocationLogPhoto>> {
+        sqlx::query_as::<_, LocationLogPhoto>(
+            "SELECT * FROM location_log_photos WHERE log_id = ? ORDER BY uploaded_at",
+        )
+        .bind(log_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Hide a log by a moderator, or show it again with None
+    pub async fn set_location_log_hidden(&self, id: &str, hidden_by: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE location_logs SET hidden_at = ?, hidden_by = ? WHERE id = ?")
+            .bind(hidden_by.map(|_| Utc::now()))
+            .bind(hidden_by)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_location_log(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM location_logs WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // =========================================================================
+    // Notifications
+    // =========================================================================
+
+    /// A user's most recent notifications, newest first
+    pub async fn list_notifications(&self, user_id: &str, limit: i64) -> Result<Vec<Notification>> {
+        sqlx::query_as::<_, Notification>(
+            "SELECT * FROM notifications WHERE user_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Notify a user about something not tied to a location
+    pub async fn create_notification(&self, user_id: &str, message: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO notifications (id, user_id, location_id, message, created_at) VALUES (?, ?, NULL, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(message)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mark_notifications_read(&self, user_id: &str) -> Result<()> {
+        sqlx::query("UPDATE notifications SET read_at = ? WHERE user_id = ? AND read_at IS NULL")
+            .bind(Utc::now())
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // =========================================================================
+    // Leaderboards
+    // =========================================================================
+
+    pub async fn create_region(
+        &self,
+        name: &str,
+        min_latitude: f64,
+        max_latitude: f64,
+        min_longitude: f64,
+        max_longitude: f64,
+    ) -> Result<Region> {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO regions (id, name, min_latitude, max_latitude, min_longitude, max_longitude, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(min_latitude)
+        .bind(max_latitude)
+        .bind(min_longitude)
+        .bind(max_longitude)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_region(&id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Failed to retrieve created region"))
+    }
+
+    pub async fn get_region(&self, id: &str) -> Result<Option<Region>> {
+        sqlx::query_as::<_, Region>("SELECT * FROM regions WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn list_regions(&self) -> Result<Vec<Region>> {
+        sqlx::query_as::<_, Region>("SELECT * FROM regions ORDER BY name")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Delete a region along with its cached leaderboards
+    pub async fn delete_region(&self, id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM leaderboard_entries WHERE region_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM regions WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Finds and claims since `since` of every hunter shown on leaderboards,
+    /// limited to locations inside `region` if given. Scans and claims at a
+    /// hunter's own locations don't count.
+    pub async fn get_hunter_stats(
+        &self,
+        since: DateTime<Utc>,
+        region: Option<&Region>,
+    ) -> Result<Vec<HunterStats>> {
+        let (min_lat, max_lat, min_lng, max_lng) = region
+            .map(|r| {
+                (
+                    r.min_latitude,
+                    r.max_latitude,
+                    r.min_longitude,
+                    r.max_longitude,
+                )
+            })
+            .unwrap_or_default();
+
+        sqlx::query_as::<_, HunterStats>(
+            r#"
+            WITH in_region AS (
+                SELECT id, user_id FROM locations
+                WHERE ? OR (latitude BETWEEN ? AND ? AND longitude BETWEEN ? AND ?)
+            ),
+            finds AS (
+                SELECT s.user_id, s.location_id,
+                    MIN(s.scanned_at) AS found_at,
+                    MAX(s.scanned_at) AS last_found_at
+                FROM scans s
+                JOIN in_region l ON l.id = s.location_id
+                WHERE s.user_id != l.user_id
+                GROUP BY s.user_id, s.location_id
+            ),
+            first_finds AS (
+                SELECT location_id, MIN(found_at) AS found_at FROM finds GROUP BY location_id
+            ),
+            claimed AS (
+                SELECT c.user_id, SUM(c.msats_claimed) AS msats_claimed
+                FROM claims c
+                JOIN in_region l ON l.id = c.location_id
+                WHERE c.claimed_at >= ? AND c.user_id != l.user_id
+                GROUP BY c.user_id
+            )
+            SELECT
+                f.user_id,
+                u.username,
+                COALESCE(cl.msats_claimed, 0) AS msats_claimed,
+                SUM(f.last_found_at >= ?) AS locations_found,
+                SUM(f.found_at >= ? AND f.found_at = ff.found_at) AS first_finds
+            FROM finds f
+            JOIN first_finds ff ON ff.location_id = f.location_id
+            JOIN users u ON u.id = f.user_id
+            LEFT JOIN claimed cl ON cl.user_id = f.user_id
+            WHERE u.auth_method != 'anonymous' OR u.leaderboard_opt_in = 1
+            GROUP BY f.user_id
+            "#,
+        )
+        .bind(region.is_none())
+        .bind(min_lat)
+        .bind(max_lat)
+        .bind(min_lng)
+        .bind(max_lng)
+        .bind(since)
+        .bind(since)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Swap in freshly computed leaderboards
+    pub async fn replace_leaderboard_entries(&self, entries: &[LeaderboardEntry]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM leaderboard_entries")
+            .execute(&mut *tx)
+            .await?;
+        for entry in entries {
+            sqlx::query(
+                r#"
+                INSERT INTO leaderboard_entries
+                    (period, region_id, metric, rank, user_id, display_name, value, computed_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(entry.period.as_str())
+            .bind(&entry.region_id)
+            .bind(entry.metric.as_str())
+            .bind(entry.rank)
+            .bind(&entry.user_id)
+            .bind(&entry.display_name)
+            .bind(entry.value)
+            .bind(entry.computed_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// A cached leaderboard, best first. `region_id` is empty for the global one.
+    pub async fn get_leaderboard(
+        &self,
+        period: Period,
+        region_id: &str,
+        metric: Metric,
+    ) -> Result<Vec<LeaderboardEntry>> {
+        sqlx::query_as::<_, LeaderboardEntry>(
+            r#"
+            SELECT * FROM leaderboard_entries
+            WHERE period = ? AND region_id = ? AND metric = ?
+            ORDER BY rank, display_name
+            "#,
+        )
+        .bind(period.as_str())
+        .bind(region_id)
+        .bind(metric.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Opt an anonymous user in or out of leaderboards. Opting out removes
+    /// the user from the cached leaderboards right away.
+    pub async fn set_leaderboard_opt_in(&self, user_id: &str, opt_in: bool) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("UPDATE users SET leaderboard_opt_in = ? WHERE id = ?")
+            .bind(opt_in)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        if !opt_in {
+            sqlx::query("DELETE FROM leaderboard_entries WHERE user_id = ?")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // =========================================================================
+    // Achievements
+    // =========================================================================
+
+    /// Record who made a donation, for their badges
+    pub async fn set_donation_user(&self, donation_id: &str, user_id: &str) -> Result<()> {
+        sqlx::query("UPDATE donations SET user_id = ? WHERE id = ?")
+            .bind(user_id)
+            .bind(donation_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// A user's scans of other people's locations, claims, received donations
+    /// and created locations, oldest first
+    pub async fn get_achievement_events(&self, user_id: &str) -> Result<Vec<Event>> {
+        let scans: Vec<(String, DateTime<Utc>, Option<DateTime<Utc>>)> = sqlx::query_as(
+            r#"
+            SELECT s.location_id, s.scanned_at, l.activated_at
+            FROM scans s
+            JOIN locations l ON l.id = s.location_id
+            WHERE s.user_id = ? AND l.user_id != s.user_id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let claims: Vec<(String, i64, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT location_id, msats_claimed, claimed_at FROM claims WHERE user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let donations: Vec<(i64, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT amount_msats, received_at FROM donations WHERE user_id = ? AND status = 'received' AND received_at IS NOT NULL",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let locations: Vec<(String, DateTime<Utc>)> =
+            sqlx::query_as("SELECT id, created_at FROM locations WHERE user_id = ?")
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut events: Vec<Event> = scans
+            .into_iter()
+            .map(|(location_id, at, activated_at)| Event::Scan {
+                location_id,
+                at,
+                activated_at,
+            })
+            .chain(
+                claims
+                    .into_iter()
+                    .map(|(location_id, msats, at)| Event::Claim {
+                        location_id,
+                        msats,
+                        at,
+                    }),
+            )
+            .chain(
+                donations
+                    .into_iter()
+                    .map(|(msats, at)| Event::Donation { msats, at }),
+            )
+            .chain(
+                locations
+                    .into_iter()
+                    .map(|(location_id, at)| Event::LocationCreated { location_id, at }),
+            )
+            .collect();
+        events.sort_by_key(Event::at);
+        Ok(events)
+    }
+
+    /// Store earned badges the user doesn't have yet, each with a notification.
+    /// Returns the new ones.
+    pub async fn award_badges(
+        &self,
+        user_id: &str,
+        earned: &[(Badge, DateTime<Utc>)],
+    ) -> Result<Vec<Badge>> {
+        let mut tx = self.pool.begin().await?;
+        let mut awarded = Vec::new();
+        for (badge, earned_at) in earned {
+            let result = sqlx::query(
+                "INSERT OR IGNORE INTO user_badges (user_id, badge, earned_at) VALUES (?, ?, ?)",
+            )
+            .bind(user_id)
+            .bind(badge.as_str())
+            .bind(earned_at)
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() == 0 {
+                continue;
+            }
+
+            sqlx::query(
+                "INSERT INTO notifications (id, user_id, location_id, message, created_at) VALUES (?, ?, NULL, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id)
+            .bind(format!(
+                "Badge earned: {} - {}",
+                badge.label(),
+                badge.description()
+            ))
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+            awarded.push(*badge);
+        }
+        tx.commit().await?;
+        Ok(awarded)
+    }
+
+    pub async fn list_user_badges(&self, user_id: &str) -> Result<Vec<UserBadge>> {
+        sqlx::query_as::<_, UserBadge>(
+            "SELECT * FROM user_badges WHERE user_id = ? ORDER BY earned_at",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    // =========================================================================
+    // Teams
+    // =========================================================================
+
+    /// Create a team with `user_id` as its owner
+    pub async fn create_team(&self, name: &str, user_id: &str) -> Result<Team> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let team = sqlx::query_as::<_, Team>(
+            r#"
+            INSERT INTO teams (id, name, invite_code, wallet_enabled, created_by, created_at)
+            VALUES (?, ?, ?, 0, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(name)
+        .bind(new_invite_code())
+        .bind(user_id)
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO team_members (team_id, user_id, role, joined_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&team.id)
+        .bind(user_id)
+        .bind(TeamRole::Owner.as_str())
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(team)
+    }
+
+    pub async fn get_team(&self, id: &str) -> Result<Option<Team>> {
+        sqlx::query_as::<_, Team>("SELECT * FROM teams WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_team_by_name(&self, name: &str) -> Result<Option<Team>> {
+        sqlx::query_as::<_, Team>("SELECT * FROM teams WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_team_by_invite_code(&self, invite_code: &str) -> Result<Option<Team>> {
+        sqlx::query_as::<_, Team>("SELECT * FROM teams WHERE invite_code = ?")
+            .bind(invite_code)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// The team membership of a user, if they are in a team
+    pub async fn get_team_membership(&self, user_id: &str) -> Result<Option<TeamMember>> {
+        sqlx::query_as::<_, TeamMember>(
+            r#"
+            SELECT m.team_id, m.user_id, u.username, m.role, m.joined_at
+            FROM team_members m
+            LEFT JOIN users u ON u.id = m.user_id
+            WHERE m.user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn list_team_members(&self, team_id: &str) -> Result<Vec<TeamMember>> {
+        sqlx::query_as::<_, TeamMember>(
+            r#"
+            SELECT m.team_id, m.user_id, u.username, m.role, m.joined_at
+            FROM team_members m
+            LEFT JOIN users u ON u.id = m.user_id
+            WHERE m.team_id = ?
+            ORDER BY m.joined_at
+            "#,
+        )
+        .bind(team_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Add a user to a team as a member. Fails if they are already in a team.
+    pub async fn join_team(&self, team_id: &str, user_id: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO team_members (team_id, user_id, role, joined_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(team_id)
+        .bind(user_id)
+        .bind(TeamRole::Member.as_str())
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove_team_member(&self, team_id: &str, user_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM team_members WHERE team_id = ? AND user_id = ?")
+            .bind(team_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_team_member_role(
+        &self,
+        team_id: &str,
+        user_id: &str,
+        role: TeamRole,
+    ) -> Result<bool> {
+        let result =
+            sqlx::query("UPDATE team_members SET role = ? WHERE team_id = ? AND user_id = ?")
+                .bind(role.as_str())
+                .bind(team_id)
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Turn the team wallet on or off. Only later claims are affected.
+    pub async fn set_team_wallet_enabled(&self, team_id: &str, enabled: bool) -> Result<()> {
+        sqlx::query("UPDATE teams SET wallet_enabled = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(team_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Replace a team's invite code, so the old invite link stops working
+    pub async fn reset_team_invite_code(&self, team_id: &str) -> Result<String> {
+        let invite_code = new_invite_code();
+        sqlx::query("UPDATE teams SET invite_code = ? WHERE id = ?")
+            .bind(&invite_code)
+            .bind(team_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(invite_code)
+    }
+
+    /// Delete a team and its memberships. The team wallet has to be empty,
+    /// its transactions are kept.
+    pub async fn delete_team(&self, team_id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let balance_msats = team_wallet_balance(&mut tx, team_id).await?;
+        anyhow::ensure!(
+            balance_msats == 0,
+            "Team wallet still holds {} msats",
+            balance_msats
+        );
+
+        let result = sqlx::query("DELETE FROM teams WHERE id = ?")
+            .bind(team_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_team_balance(&self, team_id: &str) -> Result<i64> {
+        let mut conn = self.pool.acquire().await?;
+        team_wallet_balance(&mut conn, team_id).await
+    }
+
+    /// Total balance of all team wallets
+    pub async fn get_total_team_wallet_balance(&self) -> Result<i64> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(SUM(CASE WHEN transaction_type = 'collect' THEN msats ELSE -msats END), 0) FROM team_transactions",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_team_transactions(
+        &self,
+        team_id: &str,
+        limit: i64,
+    ) -> Result<Vec<TeamTransaction>> {
+        sqlx::query_as::<_, TeamTransaction>(
+            "SELECT * FROM team_transactions WHERE team_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(team_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Move `msats` from a team wallet into a member's own wallet. Returns
+    /// false if the team wallet doesn't hold enough. Checking that the member
+    /// may withdraw is up to the caller.
+    pub async fn withdraw_from_team_wallet(
+        &self,
+        team_id: &str,
+        user_id: &str,
+        msats: i64,
+    ) -> Result<bool> {
+        anyhow::ensure!(msats > 0, "Withdrawal amount must be positive: {}", msats);
+        let mut tx = self.pool.begin().await?;
+
+        if team_wallet_balance(&mut tx, team_id).await? < msats {
+            return Ok(false);
+        }
+
+        let now = Utc::now();
+        let withdrawal_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO team_transactions (id, team_id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, ?, NULL, ?, 'withdraw', ?)",
+        )
+        .bind(&withdrawal_id)
+        .bind(team_id)
+        .bind(user_id)
+        .bind(msats)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, NULL, ?, 'team_payout', ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(msats)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::TeamPayout,
+            &LedgerAccount::TeamWallet(team_id.to_string()),
+            &LedgerAccount::UserWallet(user_id.to_string()),
+            msats,
+            Some(&withdrawal_id),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Finds and claims since `since` of every team's current members, limited
+    /// to locations inside `region` if given. A location counts once per team
+    /// however many members found it, and scans and claims at a member's own
+    /// locations don't count.
+    pub async fn get_team_stats(
+        &self,
+        since: DateTime<Utc>,
+        region: Option<&Region>,
+    ) -> Result<Vec<TeamStats>> {
+        let (min_lat, max_lat, min_lng, max_lng) = region
+            .map(|r| {
+                (
+                    r.min_latitude,
+                    r.max_latitude,
+                    r.min_longitude,
+                    r.max_longitude,
+                )
+            })
+            .unwrap_or_default();
+
+        sqlx::query_as::<_, TeamStats>(
+            r#"
+            WITH in_region AS (
+                SELECT id, user_id FROM locations
+                WHERE ? OR (latitude BETWEEN ? AND ? AND longitude BETWEEN ? AND ?)
+            ),
+            finds AS (
+                SELECT s.user_id, s.location_id,
+                    MIN(s.scanned_at) AS found_at,
+                    MAX(s.scanned_at) AS last_found_at
+                FROM scans s
+                JOIN in_region l ON l.id = s.location_id
+                WHERE s.user_id != l.user_id
+                GROUP BY s.user_id, s.location_id
+            ),
+            first_finds AS (
+                SELECT location_id, MIN(found_at) AS found_at FROM finds GROUP BY location_id
+            ),
+            team_finds AS (
+                SELECT m.team_id, f.location_id,
+                    MAX(f.last_found_at) AS last_found_at,
+                    MAX(f.found_at >= ? AND f.found_at = ff.found_at) AS first_find
+                FROM finds f
+                JOIN team_members m ON m.user_id = f.user_id
+                JOIN first_finds ff ON ff.location_id = f.location_id
+                GROUP BY m.team_id, f.location_id
+            ),
+            claimed AS (
+                SELECT m.team_id, SUM(c.msats_claimed) AS msats_claimed
+                FROM claims c
+                JOIN in_region l ON l.id = c.location_id
+                JOIN team_members m ON m.user_id = c.user_id
+                WHERE c.claimed_at >= ? AND c.user_id != l.user_id
+                GROUP BY m.team_id
+            )
+            SELECT
+                t.id AS team_id,
+                t.name,
+                COALESCE(cl.msats_claimed, 0) AS msats_claimed,
+                SUM(tf.last_found_at >= ?) AS locations_found,
+                SUM(tf.first_find) AS first_finds
+            FROM team_finds tf
+            JOIN teams t ON t.id = tf.team_id
+            LEFT JOIN claimed cl ON cl.team_id = tf.team_id
+            GROUP BY tf.team_id
+            "#,
+        )
+        .bind(region.is_none())
+        .bind(min_lat)
+        .bind(max_lat)
+        .bind(min_lng)
+        .bind(max_lng)
+        .bind(since)
+        .bind(since)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Swap in freshly computed team leaderboards
+    pub async fn replace_team_leaderboard_entries(
+        &self,
+        entries: &[TeamLeaderboardEntry],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM team_leaderboard_entries")
+            .execute(&mut *tx)
+            .await?;
+        for entry in entries {
+            sqlx::query(
+                r#"
+                INSERT INTO team_leaderboard_entries
+                    (period, region_id, metric, rank, team_id, name, value, computed_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(entry.period.as_str())
+            .bind(&entry.region_id)
+            .bind(entry.metric.as_str())
+            .bind(entry.rank)
+            .bind(&entry.team_id)
+            .bind(&entry.name)
+            .bind(entry.value)
+            .bind(entry.computed_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// A cached team leaderboard, best first. `region_id` is empty for the global one.
+    pub async fn get_team_leaderboard(
+        &self,
+        period: Period,
+        region_id: &str,
+        metric: Metric,
+    ) -> Result<Vec<TeamLeaderboardEntry>> {
+        sqlx::query_as::<_, TeamLeaderboardEntry>(
+            r#"
+            SELECT * FROM team_leaderboard_entries
+            WHERE period = ? AND region_id = ? AND metric = ?
+            ORDER BY rank, name
+            "#,
+        )
+        .bind(period.as_str())
+        .bind(region_id)
+        .bind(metric.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    // =========================================================================
+    // Recurring donations
+    // =========================================================================
+
+    /// Create a recurring donation. The first payment is due right away.
+    /// `nwc_uri` is the connection URI encrypted with [`UriCipher::encrypt`].
+    pub async fn create_recurring_donation(
+        &self,
+        user_id: &str,
+        location_id: Option<&str>,
+        amount_msats: i64,
+        interval_days: i64,
+        nwc_uri: &str,
+    ) -> Result<RecurringDonation> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query_as::<_, RecurringDonation>(
+            r#"
+            INSERT INTO recurring_donations (
+                id, user_id, location_id, amount_msats, interval_days, nwc_uri, status,
+                next_payment_at, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(location_id)
+        .bind(amount_msats)
+        .bind(interval_days)
+        .bind(nwc_uri)
+        .bind(RecurringDonationStatus::Active.as_str())
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Encrypt connection URIs stored in plaintext before they were encrypted.
+    /// Returns the number of URIs encrypted.
+    pub async fn encrypt_recurring_nwc_uris(&self, cipher: &UriCipher) -> Result<usize> {
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, nwc_uri FROM recurring_donations")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut encrypted = 0;
+        for (id, nwc_uri) in rows {
+            if UriCipher::is_encrypted(&nwc_uri) {
+                continue;
+            }
+            sqlx::query("UPDATE recurring_donations SET nwc_uri = ? WHERE id = ? AND nwc_uri = ?")
+                .bind(cipher.encrypt(&nwc_uri))
+                .bind(&id)
+                .bind(&nwc_uri)
+                .execute(&self.pool)
+                .await?;
+            encrypted += 1;
+        }
+        Ok(encrypted)
+    }
+
+    pub async fn get_recurring_donation(&self, id: &str) -> Result<Option<RecurringDonation>> {
+        sqlx::query_as::<_, RecurringDonation>("SELECT * FROM recurring_donations WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// List active recurring donations whose next payment is due
+    pub async fn list_due_recurring_donations(&self) -> Result<Vec<RecurringDonation>> {
+        sqlx::query_as::<_, RecurringDonation>(
+            r#"
+            SELECT * FROM recurring_donations
+            WHERE status = ? AND next_payment_at <= ?
+            ORDER BY next_payment_at ASC
+            "#,
+        )
+        .bind(RecurringDonationStatus::Active.as_str())
+        .bind(Utc::now())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// List active recurring donations to a location (None = global donations)
+    pub async fn list_active_recurring_donations(
+        &self,
+        location_id: Option<&str>,
+    ) -> Result<Vec<RecurringDonation>> {
+        sqlx::query_as::<_, RecurringDonation>(
+            r#"
+            SELECT * FROM recurring_donations
+            WHERE status = ? AND location_id IS ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(RecurringDonationStatus::Active.as_str())
+        .bind(location_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// List a user's recurring donations, newest first
+    pub async fn list_user_recurring_donations(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<RecurringDonation>> {
+        sqlx::query_as::<_, RecurringDonation>(
+            "SELECT * FROM recurring_donations WHERE user_id = ? ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Create the donation for one payment of a recurring donation
+    pub async fn create_recurring_donation_payment(
+        &self,
+        recurring: &RecurringDonation,
+        invoice: String,
+    ) -> Result<Donation> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query_as::<_, Donation>(
+            r#"
+            INSERT INTO donations (
+                id, location_id, invoice, amount_msats, status, created_at, recurring_donation_id,
+                user_id
+            )
+            VALUES (?, ?, ?, ?, 'created', ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(&recurring.location_id)
+        .bind(&invoice)
+        .bind(recurring.amount_msats)
+        .bind(Utc::now())
+        .bind(&recurring.id)
+        .bind(&recurring.user_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Latest payment attempt of a recurring donation
+    pub async fn get_latest_recurring_donation_payment(
+        &self,
+        recurring_id: &str,
+    ) -> Result<Option<Donation>> {
+        sqlx::query_as::<_, Donation>(
+            r#"
+            SELECT * FROM donations
+            WHERE recurring_donation_id = ?
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(recurring_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Record that the donor's wallet paid, and schedule the next payment
+    pub async fn mark_recurring_payment_sent(
+        &self,
+        recurring: &RecurringDonation,
+    ) -> Result<SqliteQueryResult> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            UPDATE recurring_donations
+            SET last_payment_at = ?, next_payment_at = ?, failure_count = 0, last_error = NULL
+            WHERE id = ?
+            "#,
+        )
+        .bind(now)
+        .bind(now + chrono::Duration::days(recurring.interval_days))
+        .bind(&recurring.id)
+        .execute(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Record a failed payment. Retries at `retry_at`, or stops the recurring
+    /// donation once `max_failures` payments in a row have failed.
+    pub async fn mark_recurring_payment_failed(
+        &self,
+        id: &str,
+        error: &str,
+        retry_at: chrono::DateTime<Utc>,
+        max_failures: i64,
+    ) -> Result<RecurringDonation> {
+        sqlx::query_as::<_, RecurringDonation>(
+            r#"
+            UPDATE recurring_donations
+            SET failure_count = failure_count + 1,
+                last_error = ?,
+                next_payment_at = ?,
+                status = CASE WHEN failure_count + 1 >= ? THEN ? ELSE status END
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(error)
+        .bind(retry_at)
+        .bind(max_failures)
+        .bind(RecurringDonationStatus::Failed.as_str())
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Cancel one of a user's active recurring donations
+    pub async fn cancel_recurring_donation(
+        &self,
+        id: &str,
+        user_id: &str,
+    ) -> Result<SqliteQueryResult> {
+        sqlx::query(
+            "UPDATE recurring_donations SET status = ?, cancelled_at = ? WHERE id = ? AND user_id = ? AND status = ?",
+        )
+        .bind(RecurringDonationStatus::Cancelled.as_str())
+        .bind(Utc::now())
+        .bind(id)
+        .bind(user_id)
+        .bind(RecurringDonationStatus::Active.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    // Claim operations (formerly scan operations)
+    pub async fn record_claim(
+        &self,
+        location_id: &str,
+        msats_claimed: i64,
+        user_id: Option<&str>,
+    ) -> Result<Claim> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        // Update last_withdraw_at on the location
+        sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(location_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let claim = sqlx::query_as::<_, Claim>(
+            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, ?) RETURNING *"
+        )
+        .bind(&id)
+        .bind(location_id)
+        .bind(msats_claimed)
+        .bind(now)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Claims without a user were paid out by the node directly
+        let (kind, credit) = match user_id {
+            Some(user_id) => (
+                LedgerEntryKind::Claim,
+                LedgerAccount::UserWallet(user_id.to_string()),
+            ),
+            None => (LedgerEntryKind::LocationWithdraw, LedgerAccount::Node),
+        };
+        post_ledger_entry(
+            &mut tx,
+            kind,
+            &LedgerAccount::LocationPool(location_id.to_string()),
+            &credit,
+            msats_claimed,
+            Some(&id),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(claim)
+    }
+
+    pub async fn get_claims_for_location(&self, location_id: &str) -> Result<Vec<Claim>> {
+        sqlx::query_as::<_, Claim>(
+            "SELECT * FROM claims WHERE location_id = ? ORDER BY claimed_at DESC",
+        )
+        .bind(location_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    // NFC Scan operations (recording validated NFC taps before claiming)
+
+    /// Record a validated NFC scan (updates counter atomically)
+    /// Returns the scan record, or None if counter already used
+    pub async fn record_nfc_scan(
+        &self,
+        location_id: &str,
+        user_id: &str,
+        new_counter: i64,
+    ) -> Result<Option<NfcScan>> {
+        let mut tx = self.pool.begin().await?;
+
+        // Verify counter is unused
+        let card: Option<NfcCard> = sqlx::query_as("SELECT * FROM nfc_cards WHERE location_id = ?")
+            .bind(location_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let card = match card {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        if new_counter <= card.counter {
+            return Ok(None); // Counter already used (replay)
+        }
+
+        let now = Utc::now();
+
+        // Update the counter
+        sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
+            .bind(new_counter)
+            .bind(now)
+            .bind(location_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Record the scan
+        let scan_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO scans (id, location_id, user_id, counter, scanned_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&scan_id)
+        .bind(location_id)
+        .bind(user_id)
+        .bind(new_counter)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // Fetch the scan we just created
+        let scan = sqlx::query_as::<_, NfcScan>("SELECT * FROM scans WHERE id = ?")
+            .bind(&scan_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(Some(scan))
+    }
+
+    /// Get the last scan for a location (most recent by scanned_at)
+    pub async fn get_last_scan_for_location(&self, location_id: &str) -> Result<Option<NfcScan>> {
+        sqlx::query_as::<_, NfcScan>(
+            "SELECT * FROM scans WHERE location_id = ? ORDER BY scanned_at DESC LIMIT 1",
+        )
+        .bind(location_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get all scans for a location with user display info
+    pub async fn get_scans_with_user_for_location(
+        &self,
+        location_id: &str,
+    ) -> Result<Vec<ScanWithUser>> {
+        sqlx::query_as::<_, ScanWithUser>(
+            r#"
+            SELECT
+                s.id,
+                s.location_id,
+                s.user_id,
+                s.scanned_at,
+                s.claimed_at,
+                u.username,
+                c.msats_claimed,
+                (s.scanned_at = (SELECT MAX(scanned_at) FROM scans WHERE location_id = s.location_id)) AS is_latest
+            FROM scans s
+            LEFT JOIN users u ON s.user_id = u.id
+            LEFT JOIN claims c ON s.claim_id = c.id
+            WHERE s.location_id = ?
+            ORDER BY s.scanned_at DESC
+            "#,
+        )
+        .bind(location_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get recent scans for a user with location info
+    pub async fn get_scans_with_location_for_user(
+        &self,
+        user_id: &str,
+        limit: i32,
+    ) -> Result<Vec<ScanWithLocation>> {
+        sqlx::query_as::<_, ScanWithLocation>(
+            r#"
+            SELECT
+                s.id,
+                s.location_id,
+                s.user_id,
+                s.scanned_at,
+                s.claimed_at,
+                c.msats_claimed,
+                (s.scanned_at = (SELECT MAX(scanned_at) FROM scans WHERE location_id = s.location_id)) AS is_latest,
+                l.name AS location_name
+            FROM scans s
+            LEFT JOIN claims c ON s.claim_id = c.id
+            JOIN locations l ON s.location_id = l.id
+            WHERE s.user_id = ?
+            ORDER BY s.scanned_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get paginated global scan list for admin page
+    pub async fn get_admin_scans(&self, limit: i64, offset: i64) -> Result<Vec<AdminScan>> {
+        sqlx::query_as::<_, AdminScan>(
+            r#"
+            SELECT
+                s.id,
+                s.location_id,
+                s.user_id,
+                s.scanned_at,
+                s.claimed_at,
+                c.msats_claimed,
+                l.name AS location_name,
+                creator.username AS creator_username,
+                l.user_id AS creator_user_id,
+                scanner.username AS scanner_username
+            FROM scans s
+            LEFT JOIN claims c ON s.claim_id = c.id
+            JOIN locations l ON s.location_id = l.id
+            LEFT JOIN users creator ON l.user_id = creator.id
+            LEFT JOIN users scanner ON s.user_id = scanner.id
+            ORDER BY s.scanned_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get total scan count for pagination
+    pub async fn count_scans(&self) -> Result<i64> {
+        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM scans")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(row.0)
+    }
+
+    /// Get daily scan counts for a date range (inclusive)
+    pub async fn get_daily_scan_counts(
+        &self,
+        from_date: &str,
+        to_date: &str,
+    ) -> Result<Vec<DailyScanCount>> {
+        sqlx::query_as::<_, DailyScanCount>(
+            r#"
+            SELECT
+                DATE(scanned_at) AS date,
+                COUNT(*) AS count
+            FROM scans
+            WHERE DATE(scanned_at) >= ? AND DATE(scanned_at) <= ?
+            GROUP BY DATE(scanned_at)
+            ORDER BY date ASC
+            "#,
+        )
+        .bind(from_date)
+        .bind(to_date)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get the date of the earliest scan (for "all time" graph range)
+    pub async fn get_earliest_scan_date(&self) -> Result<Option<chrono::NaiveDate>> {
+        let date: Option<chrono::NaiveDate> = sqlx::query_scalar(
+            "SELECT DATE(MIN(scanned_at)) FROM scans WHERE scanned_at IS NOT NULL",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(date)
+    }
+
+    /// Claim sats from a previous scan
+    /// Returns ClaimResult indicating success or reason for failure
+    /// The location's claim policy decides whether the scan wins and its share
+    /// `claim_rules` are the global rules, which the location can override
+    /// Locations that aren't active or are outside their schedule can't be claimed
+    pub async fn claim_from_scan(
+        &self,
+        scan_id: &str,
+        user_id: &str,
+        balance_config: &BalanceConfig,
+        claim_rules: &ClaimRules,
+    ) -> Result<ClaimResult> {
+        let mut tx = self.pool.begin().await?;
+
+        // Get the scan
+        let scan: Option<NfcScan> = sqlx::query_as("SELECT * FROM scans WHERE id = ?")
+            .bind(scan_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let scan = match scan {
+            Some(s) => s,
+            None => return Ok(ClaimResult::ScanNotFound),
+        };
+
+        // Verify user is the scanner
+        if scan.user_id != user_id {
+            return Ok(ClaimResult::NotYourScan);
+        }
+
+        // Check if already claimed
+        if scan.claimed_at.is_some() {
+            return Ok(ClaimResult::AlreadyClaimed);
+        }
+
+        if scan.is_expired() {
+            return Ok(ClaimResult::Expired);
+        }
+
+        // Get location and compute balance
+        let location: Location = sqlx::query_as("SELECT * FROM locations WHERE id = ?")
+            .bind(&scan.location_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if let Some(result) = claim_blocked_by_status(&mut tx, &location, Utc::now()).await? {
+            return Ok(result);
+        }
+
+        // The location's claim policy decides whether this scan wins and its share
+        let recent: Vec<NfcScan> = sqlx::query_as(
+            "SELECT * FROM scans WHERE location_id = ? AND scanned_at > ? ORDER BY scanned_at",
+        )
+        .bind(&scan.location_id)
+        .bind(Utc::now() - SCAN_CLAIM_WINDOW)
+        .fetch_all(&mut *tx)
+        .await?;
+        let fraction = match location.claim_policy.share(&scan, &recent) {
+            Ok(fraction) => fraction,
+            Err(result) => return Ok(result),
+        };
+
+        let rules =
+            claim_rules.with_overrides(&location_claim_rules(&mut tx, &scan.location_id).await?);
+        let claim_times = user_claim_times(&mut tx, user_id, &scan.location_id).await?;
+        if let Some(result) = rules.check(&claim_times, Utc::now()) {
+            return Ok(result);
+        }
+
+        // Calculate pool balance
+        let donations: (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE location_id = ? AND status = 'received'",
+        )
+        .bind(&scan.location_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let claimed: (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(msats_claimed), 0) FROM claims WHERE location_id = ?",
+        )
+        .bind(&scan.location_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let pool_balance_msats = donations.0 - claimed.0;
+
+        let claimable_msats = (compute_balance_msats(
+            pool_balance_msats,
+            location.last_withdraw_at,
+            location.created_at,
+            balance_config,
+        ) as f64
+            * fraction)
+            .round() as i64;
+
+        if claimable_msats <= 0 {
+            return Ok(ClaimResult::NoBalance);
+        }
+
+        let is_first_claim = !user_has_claimed(&mut tx, user_id).await?;
+        let claimable_msats = claimable_msats
+            + rules.first_claim_bonus_msats(is_first_claim, claimable_msats, pool_balance_msats);
+
+        let now = Utc::now();
+
+        // Ensure user exists (lazy creation)
+        let user_exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        if !user_exists {
+            sqlx::query(
+                "INSERT INTO users (id, username, email, auth_method, auth_data, created_at, role) VALUES (?, NULL, NULL, 'anonymous', '{}', ?, 'user')"
+            )
+            .bind(user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Create claim record
+        let claim_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&claim_id)
+        .bind(&scan.location_id)
+        .bind(claimable_msats)
+        .bind(now)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Update location's last_withdraw_at, keeping the fill a partial claim left
+        let last_withdraw_at = reference_after_claim(
+            location.last_withdraw_at,
+            location.created_at,
+            fraction,
+            balance_config,
+            now,
+        );
+        sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
+            .bind(last_withdraw_at)
+            .bind(&scan.location_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Collect into the custodial wallet (or the team's)
+        credit_claim(
+            &mut tx,
+            user_id,
+            &scan.location_id,
+            claimable_msats,
+            &claim_id,
+            now,
+        )
+        .await?;
+
+        record_claimed_scan(&mut tx, &scan, &claim_id, now).await?;
+
+        tx.commit().await?;
+
+        Ok(ClaimResult::Success {
+            msats: claimable_msats,
+            claim_id,
+        })
+    }
+
+    // Stats operations
+    pub async fn get_stats(&self) -> Result<Stats> {
+        let total_locations: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM locations WHERE status = 'active'")
+                .fetch_one(&self.pool)
+                .await?;
+
+        let total_scans: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scans")
+            .fetch_one(&self.pool)
+            .await?;
+
+        // Total donation pool = sum of all location-specific donations for active locations minus their claims
+        let total_donations: (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(d.amount_msats), 0) FROM donations d
+             JOIN locations l ON d.location_id = l.id
+             WHERE l.status = 'active' AND d.status = 'received'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let total_claimed: (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(c.msats_claimed), 0) FROM claims c
+             JOIN locations l ON c.location_id = l.id
+             WHERE l.status = 'active'",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let total_pool_msats = total_donations.0 - total_claimed.0;
+
+        Ok(Stats {
+            total_locations,
+            // Total pool balance represents the total sats available across all locations
+            total_sats_available: total_pool_msats.max(0) / 1000,
+            total_scans,
+            donation_pool_sats: total_pool_msats.max(0) / 1000,
+        })
+    }
+
+    // NFC card operations
+    pub async fn create_nfc_card(
+        &self,
+        location_id: String,
+        k0_auth_key: String,
+        k1_decrypt_key: String,
+        k2_cmac_key: String,
+        k3: String,
+        k4: String,
+    ) -> Result<NfcCard> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query_as::<_, NfcCard>(
+            r#"
+            INSERT INTO nfc_cards (
+                id, location_id, k0_auth_key, k1_decrypt_key, k2_cmac_key, k3, k4,
+                counter, version, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, 0, 0, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(&location_id)
+        .bind(&k0_auth_key)
+        .bind(&k1_decrypt_key)
+        .bind(&k2_cmac_key)
+        .bind(&k3)
+        .bind(&k4)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_nfc_card_by_location(&self, location_id: &str) -> Result<Option<NfcCard>> {
+        sqlx::query_as::<_, NfcCard>("SELECT * FROM nfc_cards WHERE location_id = ?")
+            .bind(location_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get NFC card by UID - will be used for payment verification with NFC taps
+    #[allow(dead_code)]
+    pub async fn get_nfc_card_by_uid(&self, uid: &str) -> Result<Option<NfcCard>> {
+        sqlx::query_as::<_, NfcCard>("SELECT * FROM nfc_cards WHERE uid = ?")
+            .bind(uid)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn update_nfc_card_uid_and_mark_programmed(
+        &self,
+        location_id: &str,
+        uid: &str,
+    ) -> Result<SqliteQueryResult> {
+        sqlx::query("UPDATE nfc_cards SET uid = ?, programmed_at = ? WHERE location_id = ?")
+            .bind(uid)
+            .bind(Utc::now())
+            .bind(location_id)
+            .execute(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn increment_nfc_card_version(&self, location_id: &str) -> Result<SqliteQueryResult> {
+        sqlx::query("UPDATE nfc_cards SET version = version + 1 WHERE location_id = ?")
+            .bind(location_id)
+            .execute(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn reset_nfc_card_counter(&self, location_id: &str) -> Result<SqliteQueryResult> {
+        sqlx::query("UPDATE nfc_cards SET counter = 0 WHERE location_id = ?")
+            .bind(location_id)
+            .execute(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Atomically claim a withdrawal by updating the counter and recording the scan.
+    ///
+    /// This prevents double-spending by checking the counter hasn't been used yet
+    /// and updating it in a single transaction. The balance is computed based on
+    /// time since last withdrawal and the donation pool balance. Recording the scan
     /// debits from the pool for future balance calculations.
     ///
-    /// Returns the claimed amount in msats if successful, or None if the counter
-    /// was already claimed or no balance is available.
-    pub async fn claim_withdrawal(
+    /// Locations that aren't active or are outside their activation schedule
+    /// can't be withdrawn from. Withdrawals have no user, so the claim rules
+    /// count all of them at a location as one claimant, and they never get the
+    /// first claim bonus.
+    ///
+    /// Claims `amount_msats`, the amount of the invoice being paid, which can be
+    /// at most the share the location's claim policy gives the withdrawal.
+    pub async fn claim_withdrawal(
+        &self,
+        location_id: &str,
+        new_counter: i64,
+        amount_msats: i64,
+        balance_config: &BalanceConfig,
+        claim_rules: &ClaimRules,
+    ) -> Result<ClaimResult> {
+        let mut tx = self.pool.begin().await?;
+
+        // Check if counter is still valid (not already claimed)
+        let card: Option<NfcCard> = sqlx::query_as("SELECT * FROM nfc_cards WHERE location_id = ?")
+            .bind(location_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let card = match card {
+            Some(c) => c,
+            None => return Ok(ClaimResult::ScanNotFound),
+        };
+
+        // If counter has already been claimed, reject
+        if new_counter <= card.counter {
+            return Ok(ClaimResult::AlreadyClaimed);
+        }
+
+        // Get the location
+        let location: Option<Location> = sqlx::query_as("SELECT * FROM locations WHERE id = ?")
+            .bind(location_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let location = match location {
+            Some(l) => l,
+            None => return Ok(ClaimResult::ScanNotFound),
+        };
+
+        if let Some(result) = claim_blocked_by_status(&mut tx, &location, Utc::now()).await? {
+            return Ok(result);
+        }
+
+        let rules = claim_rules.with_overrides(&location_claim_rules(&mut tx, location_id).await?);
+        let claim_times = anonymous_claim_times(&mut tx, location_id).await?;
+        if let Some(result) = rules.check(&claim_times, Utc::now()) {
+            return Ok(result);
+        }
+
+        // Calculate pool balance within transaction
+        let donations: (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE location_id = ? AND status = 'received'",
+        )
+        .bind(location_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let claimed: (i64,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(msats_claimed), 0) FROM claims WHERE location_id = ?",
+        )
+        .bind(location_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let pool_balance_msats = donations.0 - claimed.0;
+
+        // Compute the available balance
+        let balance_msats = compute_balance_msats(
+            pool_balance_msats,
+            location.last_withdraw_at,
+            location.created_at,
+            balance_config,
+        );
+
+        let now = Utc::now();
+
+        // The location's claim policy decides whether this tap wins and its share
+        let tap = new_tap(location_id, "", new_counter, now);
+        let fraction = match tap_share(&mut tx, &location, &tap).await? {
+            Ok(fraction) => fraction,
+            Err(result) => return Ok(result),
+        };
+        let withdrawable_msats = (balance_msats as f64 * fraction).round() as i64;
+
+        if withdrawable_msats <= 0 {
+            return Ok(ClaimResult::NoBalance);
+        }
+        if amount_msats > withdrawable_msats {
+            return Ok(ClaimResult::OverClaimable {
+                claimable_msats: withdrawable_msats,
+            });
+        }
+
+        // Update the counter
+        sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
+            .bind(new_counter)
+            .bind(now)
+            .bind(location_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Update last_withdraw_at, keeping the fill that wasn't withdrawn
+        let last_withdraw_at = reference_after_claim(
+            location.last_withdraw_at,
+            location.created_at,
+            amount_msats as f64 / balance_msats as f64,
+            balance_config,
+            now,
+        );
+        sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
+            .bind(last_withdraw_at)
+            .bind(location_id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Record the claim (debits from pool for future calculations)
+        let claim_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, NULL)"
+        )
+        .bind(&claim_id)
+        .bind(location_id)
+        .bind(amount_msats)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::LocationWithdraw,
+            &LedgerAccount::LocationPool(location_id.to_string()),
+            &LedgerAccount::Node,
+            amount_msats,
+            Some(&claim_id),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(ClaimResult::Success {
+            msats: amount_msats,
+            claim_id,
+        })
+    }
+
+    /// Update NFC card counter (for non-withdrawal scans like activation)
+    pub async fn update_nfc_card_counter(
+        &self,
+        location_id: &str,
+        counter: i64,
+    ) -> Result<SqliteQueryResult> {
+        sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
+            .bind(counter)
+            .bind(Utc::now())
+            .bind(location_id)
+            .execute(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    // ========================================================================
+    // Anonymous User and Custodial Wallet Operations
+    // ========================================================================
+
+    /// Create an anonymous user (lazy creation on first collection)
+    pub async fn create_anonymous_user(&self, id: &str) -> Result<User> {
+        let now = Utc::now();
+        let auth_method = AuthMethod::Anonymous {};
+        let method_type = auth_method.to_type_string();
+        let method_data = auth_method.to_json()?;
+
+        sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (id, username, email, auth_method, auth_data, created_at, role)
+            VALUES (?, NULL, NULL, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(method_type)
+        .bind(&method_data)
+        .bind(now)
+        .bind(UserRole::User.as_str())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get or create an anonymous user - used during collection
+    pub async fn get_or_create_anonymous_user(&self, id: &str) -> Result<User> {
+        // Try to get existing user first
+        if let Some(user) = self.get_user_by_id(id).await? {
+            return Ok(user);
+        }
+
+        // Create new anonymous user
+        self.create_anonymous_user(id).await
+    }
+
+    /// Get user's balance (collections, receives, hunt bonuses and team payouts minus withdrawals)
+    pub async fn get_user_balance(&self, user_id: &str) -> Result<i64> {
+        // Get balance from transactions
+        let tx_balance: Option<i64> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT COALESCE(
+                SUM({}),
+                0
+            ) FROM user_transactions WHERE user_id = ?
+            "#,
+            user_tx_signed_msats()
+        ))
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        // Get pending withdrawals (reserved but not yet completed)
+        let pending: Option<i64> = sqlx::query_scalar(
+            "SELECT COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE user_id = ? AND status IN (?, ?)",
+        )
+        .bind(user_id)
+        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        // Available balance = transactions balance - pending withdrawals
+        Ok(tx_balance.unwrap_or(0) - pending.unwrap_or(0))
+    }
+
+    /// Get the available balance of every user with wallet activity, ordered by user id.
+    ///
+    /// Same calculation as `get_user_balance`, done in one query for reporting.
+    pub async fn list_user_balances(&self) -> Result<Vec<(String, i64)>> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT user_id, COALESCE(SUM(msats), 0) FROM (
+                SELECT user_id,
+                       {} AS msats
+                FROM user_transactions
+                UNION ALL
+                SELECT user_id, -msats AS msats FROM pending_withdrawals WHERE status IN (?, ?)
+            )
+            GROUP BY user_id
+            ORDER BY user_id
+            "#,
+            user_tx_signed_msats()
+        ))
+        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Store a liabilities root along with the balances it was built from
+    pub async fn create_liability_snapshot(
+        &self,
+        root_hash: &str,
+        sum_msats: i64,
+        balances: &[(String, i64)],
+    ) -> Result<LiabilitySnapshot> {
+        let mut tx = self.pool.begin().await?;
+
+        let snapshot: LiabilitySnapshot = sqlx::query_as(
+            r#"
+            INSERT INTO liability_snapshots (id, root_hash, sum_msats, user_count, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(root_hash)
+        .bind(sum_msats)
+        .bind(balances.len() as i64)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (user_id, balance_msats) in balances {
+            sqlx::query(
+                "INSERT INTO liability_snapshot_balances (snapshot_id, user_id, balance_msats) VALUES (?, ?, ?)",
+            )
+            .bind(&snapshot.id)
+            .bind(user_id)
+            .bind(balance_msats)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(snapshot)
+    }
+
+    /// Get a liabilities snapshot by ID
+    pub async fn get_liability_snapshot(&self, id: &str) -> Result<Option<LiabilitySnapshot>> {
+        sqlx::query_as::<_, LiabilitySnapshot>("SELECT * FROM liability_snapshots WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Get the most recent liabilities snapshot
+    pub async fn get_latest_liability_snapshot(&self) -> Result<Option<LiabilitySnapshot>> {
+        sqlx::query_as::<_, LiabilitySnapshot>(
+            "SELECT * FROM liability_snapshots ORDER BY created_at DESC LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// List liabilities snapshots, newest first
+    pub async fn list_liability_snapshots(&self, limit: i64) -> Result<Vec<LiabilitySnapshot>> {
+        sqlx::query_as::<_, LiabilitySnapshot>(
+            "SELECT * FROM liability_snapshots ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Balances a liabilities snapshot was built from, ordered by user id
+    pub async fn list_liability_snapshot_balances(
+        &self,
+        snapshot_id: &str,
+    ) -> Result<Vec<(String, i64)>> {
+        sqlx::query_as(
+            "SELECT user_id, balance_msats FROM liability_snapshot_balances WHERE snapshot_id = ? ORDER BY user_id",
+        )
+        .bind(snapshot_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get the total amount reserved by in-flight and held withdrawals
+    pub async fn get_total_pending_withdrawals(&self) -> Result<i64> {
+        sqlx::query_scalar(
+            "SELECT COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE status IN (?, ?)",
+        )
+        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Get user's transaction history
+    pub async fn get_user_transactions(
+        &self,
+        user_id: &str,
+        limit: i64,
+    ) -> Result<Vec<UserTransaction>> {
+        sqlx::query_as::<_, UserTransaction>(
+            "SELECT * FROM user_transactions WHERE user_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    // =========================================================================
+    // Wallet invoices (payments to users' Lightning addresses)
+    // =========================================================================
+
+    /// Record an invoice created for a payment to a user's Lightning address
+    pub async fn create_wallet_invoice(
+        &self,
+        user_id: &str,
+        invoice: &str,
+        amount_msats: i64,
+        comment: Option<&str>,
+    ) -> Result<WalletInvoice> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query_as::<_, WalletInvoice>(
+            r#"
+            INSERT INTO wallet_invoices (id, user_id, invoice, amount_msats, comment, status, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(invoice)
+        .bind(amount_msats)
+        .bind(comment)
+        .bind(WalletInvoiceStatus::Created.as_str())
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// List wallet invoices still waiting for payment
+    pub async fn list_pending_wallet_invoices(&self) -> Result<Vec<WalletInvoice>> {
+        sqlx::query_as::<_, WalletInvoice>(
+            "SELECT * FROM wallet_invoices WHERE status = ? ORDER BY created_at ASC",
+        )
+        .bind(WalletInvoiceStatus::Created.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Mark a wallet invoice as paid and credit the user's wallet with a 'receive'
+    /// transaction. Returns None if the invoice was already credited.
+    pub async fn mark_wallet_invoice_received(
+        &self,
+        invoice: &str,
+    ) -> Result<Option<WalletInvoice>> {
+        let mut tx = self.pool.begin().await?;
+        let now = Utc::now();
+
+        let wallet_invoice: Option<WalletInvoice> = sqlx::query_as(
+            "UPDATE wallet_invoices SET status = ?, received_at = ? WHERE invoice = ? AND status = ? RETURNING *",
+        )
+        .bind(WalletInvoiceStatus::Received.as_str())
+        .bind(now)
+        .bind(invoice)
+        .bind(WalletInvoiceStatus::Created.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(wallet_invoice) = wallet_invoice else {
+            return Ok(None);
+        };
+
+        let tx_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, NULL, ?, 'receive', ?)"
+        )
+        .bind(&tx_id)
+        .bind(&wallet_invoice.user_id)
+        .bind(wallet_invoice.amount_msats)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::WalletReceive,
+            &LedgerAccount::Node,
+            &LedgerAccount::UserWallet(wallet_invoice.user_id.clone()),
+            wallet_invoice.amount_msats,
+            Some(&wallet_invoice.id),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(wallet_invoice))
+    }
+
+    /// Atomically claim a collection - takes sats from location and credits to user.
+    ///
+    /// This is the core operation for the custodial wallet system. It:
+    /// 1. Verifies the NFC counter hasn't been used (replay protection)
+    /// 2. Creates the anonymous user if they don't exist (lazy creation)
+    /// 3. Computes the available balance from time since last withdrawal
+    /// 4. Records a claim (which debits the pool for future calculations) and a
+    ///    collection transaction for the user
+    /// 5. Records the tap as a claimed scan and completes the hunts it finished
+    ///
+    /// Locations that aren't active or are outside their activation schedule
+    /// can't be claimed, and the claim rules (cooldown, monthly limit, first
+    /// claim bonus) apply. The location's claim policy decides the tap's share,
+    /// like for scans.
+    pub async fn claim_collection(
         &self,
         location_id: &str,
+        user_id: &str,
         new_counter: i64,
         balance_config: &BalanceConfig,
-    ) -> Result<Option<i64>> {
+        claim_rules: &ClaimRules,
+    ) -> Result<ClaimResult> {
         let mut tx = self.pool.begin().await?;
 
         // Check if counter is still valid (not already claimed)
@@ -985,12 +4388,12 @@ impl Database {
 
         let card = match card {
             Some(c) => c,
-            None => return Ok(None),
+            None => return Ok(ClaimResult::ScanNotFound),
         };
 
         // If counter has already been claimed, reject
         if new_counter <= card.counter {
-            return Ok(None);
+            return Ok(ClaimResult::AlreadyClaimed);
         }
 
         // Get the location
@@ -1001,9 +4404,19 @@ impl Database {
 
         let location = match location {
             Some(l) => l,
-            None => return Ok(None),
+            None => return Ok(ClaimResult::ScanNotFound),
         };
 
+        if let Some(result) = claim_blocked_by_status(&mut tx, &location, Utc::now()).await? {
+            return Ok(result);
+        }
+
+        let rules = claim_rules.with_overrides(&location_claim_rules(&mut tx, location_id).await?);
+        let claim_times = user_claim_times(&mut tx, user_id, location_id).await?;
+        if let Some(result) = rules.check(&claim_times, Utc::now()) {
+            return Ok(result);
+        }
+
         // Calculate pool balance within transaction
         let donations: (i64,) = sqlx::query_as(
             "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE location_id = ? AND status = 'received'",
@@ -1021,19 +4434,32 @@ impl Database {
 
         let pool_balance_msats = donations.0 - claimed.0;
 
+        let now = Utc::now();
+
+        // The location's claim policy decides whether this tap wins and its share
+        let tap = new_tap(location_id, user_id, new_counter, now);
+        let fraction = match tap_share(&mut tx, &location, &tap).await? {
+            Ok(fraction) => fraction,
+            Err(result) => return Ok(result),
+        };
+
         // Compute the available balance
-        let withdrawable_msats = compute_balance_msats(
+        let collected_msats = (compute_balance_msats(
             pool_balance_msats,
             location.last_withdraw_at,
             location.created_at,
             balance_config,
-        );
+        ) as f64
+            * fraction)
+            .round() as i64;
 
-        if withdrawable_msats <= 0 {
-            return Ok(None);
+        if collected_msats <= 0 {
+            return Ok(ClaimResult::NoBalance);
         }
 
-        let now = Utc::now();
+        let is_first_claim = !user_has_claimed(&mut tx, user_id).await?;
+        let collected_msats = collected_msats
+            + rules.first_claim_bonus_msats(is_first_claim, collected_msats, pool_balance_msats);
 
         // Update the counter
         sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
@@ -1043,266 +4469,565 @@ impl Database {
             .execute(&mut *tx)
             .await?;
 
-        // Update last_withdraw_at (this resets the fill timer)
+        // Update last_withdraw_at, keeping the fill a partial claim left
+        let last_withdraw_at = reference_after_claim(
+            location.last_withdraw_at,
+            location.created_at,
+            fraction,
+            balance_config,
+            now,
+        );
         sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
-            .bind(now)
+            .bind(last_withdraw_at)
             .bind(location_id)
             .execute(&mut *tx)
             .await?;
 
-        // Record the claim (debits from pool for future calculations)
+        // Ensure user exists (lazy creation)
+        let user_exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
+                .bind(user_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+        if !user_exists {
+            sqlx::query(
+                "INSERT INTO users (id, username, email, auth_method, auth_data, created_at, role) VALUES (?, NULL, NULL, 'anonymous', '{}', ?, 'user')"
+            )
+            .bind(user_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Record the claim with user_id (debits from pool for future calculations)
         let claim_id = Uuid::new_v4().to_string();
         sqlx::query(
-            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, NULL)"
+            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, ?)"
         )
         .bind(&claim_id)
         .bind(location_id)
-        .bind(withdrawable_msats)
+        .bind(collected_msats)
+        .bind(now)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // Collect into the custodial wallet (or the team's)
+        credit_claim(
+            &mut tx,
+            user_id,
+            location_id,
+            collected_msats,
+            &claim_id,
+            now,
+        )
+        .await?;
+
+        record_claimed_scan(&mut tx, &tap, &claim_id, now).await?;
+
+        tx.commit().await?;
+
+        Ok(ClaimResult::Success {
+            msats: collected_msats,
+            claim_id,
+        })
+    }
+
+    // =========================================================================
+    // Reusable wallet withdraw links
+    // =========================================================================
+
+    /// Create a new withdraw link for a user, revoking their current one
+    pub async fn create_wallet_withdraw_link(&self, user_id: &str) -> Result<WalletWithdrawLink> {
+        use rand::{thread_rng, RngCore};
+        let mut key = [0u8; 32];
+        thread_rng().fill_bytes(&mut key);
+
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE wallet_withdraw_links SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+        )
         .bind(now)
+        .bind(user_id)
         .execute(&mut *tx)
         .await?;
 
+        let link = sqlx::query_as::<_, WalletWithdrawLink>(
+            r#"
+            INSERT INTO wallet_withdraw_links (id, user_id, link_key, created_at)
+            VALUES (?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(hex::encode(key))
+        .bind(now)
+        .fetch_one(&mut *tx)
+        .await?;
+
         tx.commit().await?;
+        Ok(link)
+    }
 
-        Ok(Some(withdrawable_msats))
+    /// Get a user's active withdraw link
+    pub async fn get_active_wallet_withdraw_link(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<WalletWithdrawLink>> {
+        sqlx::query_as::<_, WalletWithdrawLink>(
+            "SELECT * FROM wallet_withdraw_links WHERE user_id = ? AND revoked_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
     }
 
-    /// Update NFC card counter (for non-withdrawal scans like activation)
-    pub async fn update_nfc_card_counter(
+    /// Get an active withdraw link by the key in its URL
+    pub async fn get_wallet_withdraw_link_by_key(
         &self,
-        location_id: &str,
-        counter: i64,
-    ) -> Result<SqliteQueryResult> {
-        sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
-            .bind(counter)
+        link_key: &str,
+    ) -> Result<Option<WalletWithdrawLink>> {
+        sqlx::query_as::<_, WalletWithdrawLink>(
+            "SELECT * FROM wallet_withdraw_links WHERE link_key = ? AND revoked_at IS NULL",
+        )
+        .bind(link_key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Revoke a user's active withdraw link
+    pub async fn revoke_wallet_withdraw_link(&self, user_id: &str) -> Result<SqliteQueryResult> {
+        sqlx::query(
+            "UPDATE wallet_withdraw_links SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL",
+        )
+        .bind(Utc::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn mark_wallet_withdraw_link_used(&self, id: &str) -> Result<SqliteQueryResult> {
+        sqlx::query("UPDATE wallet_withdraw_links SET last_used_at = ? WHERE id = ?")
             .bind(Utc::now())
-            .bind(location_id)
+            .bind(id)
             .execute(&self.pool)
             .await
             .map_err(Into::into)
     }
 
-    // ========================================================================
-    // Anonymous User and Custodial Wallet Operations
-    // ========================================================================
+    // =========================================================================
+    // Auto-withdraw settings
+    // =========================================================================
 
-    /// Create an anonymous user (lazy creation on first collection)
-    pub async fn create_anonymous_user(&self, id: &str) -> Result<User> {
-        let now = Utc::now();
-        let auth_method = AuthMethod::Anonymous {};
-        let method_type = auth_method.to_type_string();
-        let method_data = auth_method.to_json()?;
+    pub async fn get_auto_withdraw_setting(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<AutoWithdrawSetting>> {
+        sqlx::query_as::<_, AutoWithdrawSetting>(
+            "SELECT * FROM auto_withdraw_settings WHERE user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
 
-        sqlx::query_as::<_, User>(
+    /// Save a user's auto-withdraw setting. Saving (re-)enables it and clears
+    /// earlier failures.
+    pub async fn save_auto_withdraw_setting(
+        &self,
+        user_id: &str,
+        ln_address: &str,
+        threshold_msats: i64,
+    ) -> Result<AutoWithdrawSetting> {
+        let now = Utc::now();
+        sqlx::query_as::<_, AutoWithdrawSetting>(
             r#"
-            INSERT INTO users (id, username, email, auth_method, auth_data, created_at, role)
-            VALUES (?, NULL, NULL, ?, ?, ?, ?)
+            INSERT INTO auto_withdraw_settings (
+                user_id, ln_address, threshold_msats, enabled, failure_count, created_at, updated_at
+            )
+            VALUES (?, ?, ?, 1, 0, ?, ?)
+            ON CONFLICT(user_id) DO UPDATE SET
+                ln_address = excluded.ln_address,
+                threshold_msats = excluded.threshold_msats,
+                enabled = 1,
+                failure_count = 0,
+                last_error = NULL,
+                retry_at = NULL,
+                updated_at = excluded.updated_at
             RETURNING *
             "#,
         )
-        .bind(id)
-        .bind(method_type)
-        .bind(&method_data)
+        .bind(user_id)
+        .bind(ln_address)
+        .bind(threshold_msats)
+        .bind(now)
         .bind(now)
-        .bind(UserRole::User.as_str())
         .fetch_one(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    /// Get or create an anonymous user - used during collection
-    pub async fn get_or_create_anonymous_user(&self, id: &str) -> Result<User> {
-        // Try to get existing user first
-        if let Some(user) = self.get_user_by_id(id).await? {
-            return Ok(user);
-        }
-
-        // Create new anonymous user
-        self.create_anonymous_user(id).await
+    pub async fn delete_auto_withdraw_setting(&self, user_id: &str) -> Result<SqliteQueryResult> {
+        sqlx::query("DELETE FROM auto_withdraw_settings WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(Into::into)
     }
 
-    /// Get user's balance (sum of collections - sum of withdrawals)
-    pub async fn get_user_balance(&self, user_id: &str) -> Result<i64> {
-        // Get balance from transactions
-        let tx_balance: Option<i64> = sqlx::query_scalar(
+    /// List enabled auto-withdraw settings that may be attempted now
+    pub async fn list_due_auto_withdraw_settings(&self) -> Result<Vec<AutoWithdrawSetting>> {
+        sqlx::query_as::<_, AutoWithdrawSetting>(
             r#"
-            SELECT COALESCE(
-                SUM(CASE WHEN transaction_type = 'collect' THEN msats ELSE -msats END),
-                0
-            ) FROM user_transactions WHERE user_id = ?
+            SELECT * FROM auto_withdraw_settings
+            WHERE enabled = 1 AND (retry_at IS NULL OR retry_at <= ?)
+            ORDER BY updated_at ASC
             "#,
         )
-        .bind(user_id)
-        .fetch_one(&self.pool)
-        .await?;
+        .bind(Utc::now())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
 
-        // Get pending withdrawals (reserved but not yet completed)
-        let pending: Option<i64> = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE user_id = ? AND status = ?",
+    /// Record a successful automatic withdrawal
+    pub async fn mark_auto_withdraw_succeeded(&self, user_id: &str) -> Result<SqliteQueryResult> {
+        sqlx::query(
+            r#"
+            UPDATE auto_withdraw_settings
+            SET last_withdrawal_at = ?, failure_count = 0, last_error = NULL, retry_at = NULL
+            WHERE user_id = ?
+            "#,
         )
+        .bind(Utc::now())
         .bind(user_id)
-        .bind(WithdrawalStatus::Pending.as_str())
-        .fetch_one(&self.pool)
-        .await?;
-
-        // Available balance = transactions balance - pending withdrawals
-        Ok(tx_balance.unwrap_or(0) - pending.unwrap_or(0))
+        .execute(&self.pool)
+        .await
+        .map_err(Into::into)
     }
 
-    /// Get user's transaction history
-    pub async fn get_user_transactions(
+    /// Record a failed automatic withdrawal. Retries at `retry_at`, or disables
+    /// the setting once `max_failures` withdrawals in a row have failed.
+    pub async fn mark_auto_withdraw_failed(
         &self,
         user_id: &str,
-        limit: i64,
-    ) -> Result<Vec<UserTransaction>> {
-        sqlx::query_as::<_, UserTransaction>(
-            "SELECT * FROM user_transactions WHERE user_id = ? ORDER BY created_at DESC LIMIT ?",
+        error: &str,
+        retry_at: chrono::DateTime<Utc>,
+        max_failures: i64,
+    ) -> Result<AutoWithdrawSetting> {
+        sqlx::query_as::<_, AutoWithdrawSetting>(
+            r#"
+            UPDATE auto_withdraw_settings
+            SET failure_count = failure_count + 1,
+                last_error = ?,
+                retry_at = ?,
+                enabled = CASE WHEN failure_count + 1 >= ? THEN 0 ELSE enabled END
+            WHERE user_id = ?
+            RETURNING *
+            "#,
         )
+        .bind(error)
+        .bind(retry_at)
+        .bind(max_failures)
         .bind(user_id)
-        .bind(limit)
-        .fetch_all(&self.pool)
+        .fetch_one(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    /// Atomically claim a collection - takes sats from location and credits to user.
-    ///
-    /// This is the core operation for the custodial wallet system. It:
-    /// 1. Verifies the NFC counter hasn't been used (replay protection)
-    /// 2. Creates the anonymous user if they don't exist (lazy creation)
-    /// 3. Computes the available balance from time since last withdrawal
-    /// 4. Records a collection transaction for the user
-    /// 5. Records a scan (which debits the pool for future calculations)
-    ///
-    /// Returns the collected amount in msats, or None if the counter was already used.
-    pub async fn claim_collection(
-        &self,
-        location_id: &str,
-        user_id: &str,
-        new_counter: i64,
-        balance_config: &BalanceConfig,
-    ) -> Result<Option<i64>> {
-        let mut tx = self.pool.begin().await?;
-
-        // Check if counter is still valid (not already claimed)
-        let card: Option<NfcCard> = sqlx::query_as("SELECT * FROM nfc_cards WHERE location_id = ?")
-            .bind(location_id)
-            .fetch_optional(&mut *tx)
-            .await?;
-
-        let card = match card {
-            Some(c) => c,
-            None => return Ok(None),
-        };
-
-        // If counter has already been claimed, reject
-        if new_counter <= card.counter {
-            return Ok(None);
-        }
-
-        // Get the location
-        let location: Option<Location> = sqlx::query_as("SELECT * FROM locations WHERE id = ?")
-            .bind(location_id)
-            .fetch_optional(&mut *tx)
-            .await?;
-
-        let location = match location {
-            Some(l) => l,
-            None => return Ok(None),
-        };
+    // =========================================================================
+    // Ledger operations
+    // =========================================================================
 
-        // Calculate pool balance within transaction
-        let donations: (i64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(amount_msats), 0) FROM donations WHERE location_id = ? AND status = 'received'",
+    /// List the most recent ledger entries
+    pub async fn list_ledger_entries(&self, limit: i64) -> Result<Vec<LedgerEntry>> {
+        sqlx::query_as::<_, LedgerEntry>(
+            "SELECT * FROM ledger_entries ORDER BY created_at DESC, id LIMIT ?",
         )
-        .bind(location_id)
-        .fetch_one(&mut *tx)
-        .await?;
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
 
-        let claimed: (i64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(msats_claimed), 0) FROM claims WHERE location_id = ?",
+    /// Get the balance of a single ledger account
+    pub async fn get_ledger_balance(&self, account: &LedgerAccount) -> Result<i64> {
+        let mut conn = self.pool.acquire().await?;
+        ledger_account_balance(&mut conn, account).await
+    }
+
+    /// Check the ledger against itself and against the legacy balance queries.
+    ///
+    /// Flags:
+    /// - total debits that don't match total credits
+    /// - accounts that can't be parsed, or hold a negative balance (except equity)
+    /// - location pools that differ from `get_location_donation_pool_balance`
+    /// - wallets that differ from `get_user_balance`
+    /// - reserved withdrawals that differ from the pending_withdrawals table
+    /// - campaign budgets that differ from the matching_campaigns table
+    /// - team wallets that differ from the team_transactions table
+    /// - balances left behind on deleted locations
+    pub async fn verify_ledger(&self) -> Result<LedgerReport> {
+        let mut discrepancies = Vec::new();
+
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT account, COALESCE(SUM(debit), 0), COALESCE(SUM(credit), 0) FROM (
+                SELECT debit_account AS account, msats AS debit, 0 AS credit FROM ledger_entries
+                UNION ALL
+                SELECT credit_account AS account, 0 AS debit, msats AS credit FROM ledger_entries
+            )
+            GROUP BY account
+            ORDER BY account
+            "#,
         )
-        .bind(location_id)
-        .fetch_one(&mut *tx)
+        .fetch_all(&self.pool)
         .await?;
 
-        let pool_balance_msats = donations.0 - claimed.0;
+        // Trial balance: debit and credit totals across all accounts
+        let total_debits_msats: i64 = rows.iter().map(|(_, debits, _)| debits).sum();
+        let total_credits_msats: i64 = rows.iter().map(|(_, _, credits)| credits).sum();
+
+        let mut balances = Vec::new();
+        for (account, debits, credits) in rows {
+            match account.parse::<LedgerAccount>() {
+                Ok(account) => {
+                    let msats = if account.is_asset() {
+                        debits - credits
+                    } else {
+                        credits - debits
+                    };
+                    balances.push(LedgerAccountBalance { account, msats });
+                }
+                Err(_) => discrepancies.push(LedgerDiscrepancy {
+                    account: None,
+                    ledger_msats: credits - debits,
+                    expected_msats: 0,
+                    message: format!("Unknown ledger account '{}'", account),
+                }),
+            }
+        }
 
-        // Compute the available balance
-        let collected_msats = compute_balance_msats(
-            pool_balance_msats,
-            location.last_withdraw_at,
-            location.created_at,
-            balance_config,
-        );
+        let entry_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ledger_entries")
+            .fetch_one(&self.pool)
+            .await?;
 
-        if collected_msats <= 0 {
-            return Ok(None);
+        // Assets must equal liabilities plus fee income and equity
+        let assets: i64 = balances
+            .iter()
+            .filter(|b| b.account.is_asset())
+            .map(|b| b.msats)
+            .sum();
+        let liabilities: i64 = balances
+            .iter()
+            .filter(|b| !b.account.is_asset())
+            .map(|b| b.msats)
+            .sum();
+        if total_debits_msats != total_credits_msats || assets != liabilities {
+            discrepancies.push(LedgerDiscrepancy {
+                account: None,
+                ledger_msats: assets,
+                expected_msats: liabilities,
+                message: "Node balance does not match the sum of all other accounts".to_string(),
+            });
         }
 
-        let now = Utc::now();
-
-        // Update the counter
-        sqlx::query("UPDATE nfc_cards SET counter = ?, last_used_at = ? WHERE location_id = ?")
-            .bind(new_counter)
-            .bind(now)
-            .bind(location_id)
-            .execute(&mut *tx)
-            .await?;
+        for balance in &balances {
+            if balance.msats < 0 && !balance.account.may_be_negative() {
+                discrepancies.push(LedgerDiscrepancy {
+                    account: Some(balance.account.clone()),
+                    ledger_msats: balance.msats,
+                    expected_msats: 0,
+                    message: "Account has a negative balance".to_string(),
+                });
+            }
+        }
 
-        // Update last_withdraw_at (this resets the fill timer)
-        sqlx::query("UPDATE locations SET last_withdraw_at = ? WHERE id = ?")
-            .bind(now)
-            .bind(location_id)
-            .execute(&mut *tx)
-            .await?;
+        let ledger_balances: BTreeMap<&LedgerAccount, i64> =
+            balances.iter().map(|b| (&b.account, b.msats)).collect();
 
-        // Ensure user exists (lazy creation)
-        let user_exists: bool =
-            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE id = ?)")
-                .bind(user_id)
-                .fetch_one(&mut *tx)
+        // Location pools
+        let locations = self.list_locations().await?;
+        for location in &locations {
+            let account = LedgerAccount::LocationPool(location.id.clone());
+            let ledger_msats = ledger_balances.get(&account).copied().unwrap_or(0);
+            let expected_msats = self
+                .get_location_donation_pool_balance(&location.id)
                 .await?;
-
-        if !user_exists {
-            sqlx::query(
-                "INSERT INTO users (id, username, email, auth_method, auth_data, created_at, role) VALUES (?, NULL, NULL, 'anonymous', '{}', ?, 'user')"
-            )
-            .bind(user_id)
-            .bind(now)
-            .execute(&mut *tx)
-            .await?;
+            if ledger_msats != expected_msats {
+                discrepancies.push(LedgerDiscrepancy {
+                    account: Some(account),
+                    ledger_msats,
+                    expected_msats,
+                    message: format!(
+                        "Pool of '{}' does not match donations minus claims",
+                        location.name
+                    ),
+                });
+            }
+        }
+        for balance in &balances {
+            if let LedgerAccount::LocationPool(id) = &balance.account {
+                if balance.msats != 0 && !locations.iter().any(|l| &l.id == id) {
+                    discrepancies.push(LedgerDiscrepancy {
+                        account: Some(balance.account.clone()),
+                        ledger_msats: balance.msats,
+                        expected_msats: 0,
+                        message: "Deleted location still holds a balance".to_string(),
+                    });
+                }
+            }
         }
 
-        // Record the collection transaction
-        let tx_id = Uuid::new_v4().to_string();
-        sqlx::query(
-            "INSERT INTO user_transactions (id, user_id, location_id, msats, transaction_type, created_at) VALUES (?, ?, ?, ?, 'collect', ?)"
+        // User wallets
+        let mut expected_wallets: BTreeMap<String, i64> = BTreeMap::new();
+        let tx_balances: Vec<(String, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT user_id, COALESCE(
+                SUM({}),
+                0
+            ) FROM user_transactions GROUP BY user_id
+            "#,
+            user_tx_signed_msats()
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        for (user_id, msats) in tx_balances {
+            *expected_wallets.entry(user_id).or_default() += msats;
+        }
+        let pending: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT user_id, COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE status IN (?, ?) GROUP BY user_id",
         )
-        .bind(&tx_id)
-        .bind(user_id)
-        .bind(location_id)
-        .bind(collected_msats)
-        .bind(now)
-        .execute(&mut *tx)
+        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_all(&self.pool)
         .await?;
+        let total_pending_msats: i64 = pending.iter().map(|(_, msats)| msats).sum();
+        for (user_id, msats) in pending {
+            *expected_wallets.entry(user_id).or_default() -= msats;
+        }
+        for balance in &balances {
+            if let LedgerAccount::UserWallet(id) = &balance.account {
+                expected_wallets.entry(id.clone()).or_default();
+            }
+        }
+        for (user_id, expected_msats) in expected_wallets {
+            let account = LedgerAccount::UserWallet(user_id);
+            let ledger_msats = ledger_balances.get(&account).copied().unwrap_or(0);
+            if ledger_msats != expected_msats {
+                discrepancies.push(LedgerDiscrepancy {
+                    account: Some(account),
+                    ledger_msats,
+                    expected_msats,
+                    message: "Wallet does not match user transactions".to_string(),
+                });
+            }
+        }
 
-        // Record the claim with user_id (debits from pool for future calculations)
-        let claim_id = Uuid::new_v4().to_string();
-        sqlx::query(
-            "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES (?, ?, ?, ?, ?)"
+        // Reserved withdrawals
+        let ledger_pending_msats = ledger_balances
+            .get(&LedgerAccount::PendingWithdrawals)
+            .copied()
+            .unwrap_or(0);
+        if ledger_pending_msats != total_pending_msats {
+            discrepancies.push(LedgerDiscrepancy {
+                account: Some(LedgerAccount::PendingWithdrawals),
+                ledger_msats: ledger_pending_msats,
+                expected_msats: total_pending_msats,
+                message: "Reserved amount does not match pending withdrawals".to_string(),
+            });
+        }
+
+        // Campaign budgets
+        let campaigns = self.list_matching_campaigns().await?;
+        for campaign in &campaigns {
+            let account = LedgerAccount::Campaign(campaign.id.clone());
+            let ledger_msats = ledger_balances.get(&account).copied().unwrap_or(0);
+            let expected_msats = if matches!(
+                campaign.status,
+                CampaignStatus::Funding | CampaignStatus::Expired
+            ) {
+                0
+            } else {
+                campaign.budget_msats - campaign.spent_msats
+            };
+            if ledger_msats != expected_msats {
+                discrepancies.push(LedgerDiscrepancy {
+                    account: Some(account),
+                    ledger_msats,
+                    expected_msats,
+                    message: format!(
+                        "Budget of campaign '{}' does not match funding minus matches",
+                        campaign.name
+                    ),
+                });
+            }
+        }
+
+        // Hunt bonus pools
+        let hunts = self.list_hunts().await?;
+        for hunt in &hunts {
+            let account = LedgerAccount::HuntPool(hunt.id.clone());
+            let ledger_msats = ledger_balances.get(&account).copied().unwrap_or(0);
+            let expected_msats = self.get_hunt_pool_balance(&hunt.id).await?;
+            if ledger_msats != expected_msats {
+                discrepancies.push(LedgerDiscrepancy {
+                    account: Some(account),
+                    ledger_msats,
+                    expected_msats,
+                    message: format!(
+                        "Pool of hunt '{}' does not match donations minus bonuses",
+                        hunt.name
+                    ),
+                });
+            }
+        }
+
+        // Team wallets, including those of deleted teams
+        let team_balances: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT team_id, COALESCE(
+                SUM(CASE WHEN transaction_type = 'collect' THEN msats ELSE -msats END),
+                0
+            ) FROM team_transactions GROUP BY team_id
+            "#,
         )
-        .bind(&claim_id)
-        .bind(location_id)
-        .bind(collected_msats)
-        .bind(now)
-        .bind(user_id)
-        .execute(&mut *tx)
+        .fetch_all(&self.pool)
         .await?;
+        let mut expected_teams: BTreeMap<String, i64> = team_balances.into_iter().collect();
+        for balance in &balances {
+            if let LedgerAccount::TeamWallet(id) = &balance.account {
+                expected_teams.entry(id.clone()).or_default();
+            }
+        }
+        for (team_id, expected_msats) in expected_teams {
+            let account = LedgerAccount::TeamWallet(team_id);
+            let ledger_msats = ledger_balances.get(&account).copied().unwrap_or(0);
+            if ledger_msats != expected_msats {
+                discrepancies.push(LedgerDiscrepancy {
+                    account: Some(account),
+                    ledger_msats,
+                    expected_msats,
+                    message: "Team wallet does not match team transactions".to_string(),
+                });
+            }
+        }
 
-        tx.commit().await?;
-
-        Ok(Some(collected_msats))
+        Ok(LedgerReport {
+            balances,
+            total_debits_msats,
+            total_credits_msats,
+            entry_count,
+            discrepancies,
+        })
     }
 
     // Settings operations
@@ -1344,28 +5069,92 @@ impl Database {
         fee_msats: i64,
         invoice: &str,
     ) -> Result<Option<String>> {
+        let reserved = self
+            .reserve_withdrawal(user_id, amount_msats, fee_msats, invoice, None, None)
+            .await?;
+        Ok(reserved.map(|(id, _)| id))
+    }
+
+    /// Create a withdrawal held for review, reserving the balance like
+    /// `create_pending_withdrawal`. It isn't paid until an admin approves it.
+    pub async fn create_held_withdrawal(
+        &self,
+        user_id: &str,
+        amount_msats: i64,
+        fee_msats: i64,
+        invoice: &str,
+        hold_reason: &str,
+    ) -> Result<Option<String>> {
+        let reserved = self
+            .reserve_withdrawal(
+                user_id,
+                amount_msats,
+                fee_msats,
+                invoice,
+                Some(hold_reason),
+                None,
+            )
+            .await?;
+        Ok(reserved.map(|(id, _)| id))
+    }
+
+    /// Reserve the balance for a wallet withdrawal, holding it for review if it's
+    /// over one of `limits`. The limits are checked in the transaction that reserves
+    /// the withdrawal, so concurrent withdrawals can't all slip under them.
+    /// Returns the withdrawal ID and the hold reason if it was held, or None if the
+    /// balance is insufficient.
+    pub async fn reserve_wallet_withdrawal(
+        &self,
+        user_id: &str,
+        amount_msats: i64,
+        fee_msats: i64,
+        invoice: &str,
+        limits: &WithdrawLimits,
+    ) -> Result<Option<(String, Option<String>)>> {
+        self.reserve_withdrawal(
+            user_id,
+            amount_msats,
+            fee_msats,
+            invoice,
+            None,
+            Some(limits),
+        )
+        .await
+    }
+
+    async fn reserve_withdrawal(
+        &self,
+        user_id: &str,
+        amount_msats: i64,
+        fee_msats: i64,
+        invoice: &str,
+        hold_reason: Option<&str>,
+        limits: Option<&WithdrawLimits>,
+    ) -> Result<Option<(String, Option<String>)>> {
         let total_msats = amount_msats + fee_msats;
         let mut tx = self.pool.begin().await?;
 
         // Get current balance from transactions
-        let tx_balance: Option<i64> = sqlx::query_scalar(
+        let tx_balance: Option<i64> = sqlx::query_scalar(&format!(
             r#"
             SELECT COALESCE(
-                SUM(CASE WHEN transaction_type = 'collect' THEN msats ELSE -msats END),
+                SUM({}),
                 0
             ) FROM user_transactions WHERE user_id = ?
             "#,
-        )
+            user_tx_signed_msats()
+        ))
         .bind(user_id)
         .fetch_one(&mut *tx)
         .await?;
 
         // Get existing pending withdrawals
         let pending: Option<i64> = sqlx::query_scalar(
-            "SELECT COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE user_id = ? AND status = ?",
+            "SELECT COALESCE(SUM(msats), 0) FROM pending_withdrawals WHERE user_id = ? AND status IN (?, ?)",
         )
         .bind(user_id)
         .bind(WithdrawalStatus::Pending.as_str())
+        .bind(WithdrawalStatus::Held.as_str())
         .fetch_one(&mut *tx)
         .await?;
 
@@ -1376,53 +5165,90 @@ impl Database {
             return Ok(None);
         }
 
+        let hold_reason = match (hold_reason, limits) {
+            (Some(reason), _) => Some(reason.to_string()),
+            (None, Some(limits)) => {
+                limits
+                    .hold_reason_in(&mut tx, user_id, amount_msats)
+                    .await?
+            }
+            (None, None) => None,
+        };
+        let status = match hold_reason {
+            Some(_) => WithdrawalStatus::Held,
+            None => WithdrawalStatus::Pending,
+        };
+
         let now = Utc::now();
         let id = Uuid::new_v4().to_string();
 
         // Create pending withdrawal (reserves amount + fees)
         sqlx::query(
-            "INSERT INTO pending_withdrawals (id, user_id, msats, invoice, status, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+            "INSERT INTO pending_withdrawals (id, user_id, msats, fee_msats, invoice, payment_hash, status, hold_reason, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&id)
         .bind(user_id)
         .bind(total_msats)
+        .bind(fee_msats)
         .bind(invoice)
-        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(invoice_policy::payment_hash(invoice))
+        .bind(status.as_str())
+        .bind(&hold_reason)
         .bind(now)
         .execute(&mut *tx)
         .await?;
 
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::WithdrawReserve,
+            &LedgerAccount::UserWallet(user_id.to_string()),
+            &LedgerAccount::PendingWithdrawals,
+            total_msats,
+            Some(&id),
+        )
+        .await?;
+
         tx.commit().await?;
 
-        Ok(Some(id))
+        Ok(Some((id, hold_reason)))
     }
 
     /// Complete a pending withdrawal, recording the actual transaction.
     ///
     /// This marks the pending withdrawal as completed and records the withdrawal transaction.
-    pub async fn complete_pending_withdrawal(&self, withdrawal_id: &str) -> Result<()> {
+    /// If the routing fee actually paid is known, the unused part of the reserved fee is
+    /// refunded to the wallet.
+    pub async fn complete_pending_withdrawal(
+        &self,
+        withdrawal_id: &str,
+        paid_fee_msats: Option<u64>,
+    ) -> Result<()> {
         let mut tx = self.pool.begin().await?;
         let now = Utc::now();
 
         // Get the pending withdrawal
-        let withdrawal: Option<(String, i64)> = sqlx::query_as(
-            "SELECT user_id, msats FROM pending_withdrawals WHERE id = ? AND status = ?",
+        let withdrawal: Option<(String, i64, i64)> = sqlx::query_as(
+            "SELECT user_id, msats, fee_msats FROM pending_withdrawals WHERE id = ? AND status = ?",
         )
         .bind(withdrawal_id)
         .bind(WithdrawalStatus::Pending.as_str())
         .fetch_optional(&mut *tx)
         .await?;
 
-        let (user_id, msats) = withdrawal
+        let (user_id, msats, fee_msats) = withdrawal
             .ok_or_else(|| anyhow::anyhow!("Pending withdrawal not found or already processed"))?;
+        let refund_msats = fees::fee_refund_msats(fee_msats, paid_fee_msats);
 
         // Mark as completed
-        sqlx::query("UPDATE pending_withdrawals SET status = ?, completed_at = ? WHERE id = ?")
-            .bind(WithdrawalStatus::Completed.as_str())
-            .bind(now)
-            .bind(withdrawal_id)
-            .execute(&mut *tx)
-            .await?;
+        sqlx::query(
+            "UPDATE pending_withdrawals SET status = ?, completed_at = ?, fee_refund_msats = ? WHERE id = ?",
+        )
+        .bind(WithdrawalStatus::Completed.as_str())
+        .bind(now)
+        .bind(refund_msats)
+        .bind(withdrawal_id)
+        .execute(&mut *tx)
+        .await?;
 
         // Record the withdrawal transaction
         let tx_id = Uuid::new_v4().to_string();
@@ -1431,11 +5257,52 @@ impl Database {
         )
         .bind(&tx_id)
         .bind(&user_id)
-        .bind(msats)
+        .bind(msats - refund_msats)
         .bind(now)
         .execute(&mut *tx)
         .await?;
 
+        // The amount and the routing fee leave through the node. An unknown
+        // routing fee is assumed to have used up the whole reserve. If routing
+        // cost more than was reserved, the operator covers the difference.
+        let routing_fee_msats = paid_fee_msats.map_or(fee_msats, |paid| paid as i64);
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::Withdraw,
+            &LedgerAccount::PendingWithdrawals,
+            &LedgerAccount::Node,
+            msats - fee_msats,
+            Some(withdrawal_id),
+        )
+        .await?;
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::RoutingFee,
+            &LedgerAccount::PendingWithdrawals,
+            &LedgerAccount::Node,
+            routing_fee_msats.min(fee_msats),
+            Some(withdrawal_id),
+        )
+        .await?;
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::RoutingFee,
+            &LedgerAccount::Equity,
+            &LedgerAccount::Node,
+            (routing_fee_msats - fee_msats).max(0),
+            Some(withdrawal_id),
+        )
+        .await?;
+        post_ledger_entry(
+            &mut tx,
+            LedgerEntryKind::WithdrawFeeRefund,
+            &LedgerAccount::PendingWithdrawals,
+            &LedgerAccount::UserWallet(user_id),
+            refund_msats,
+            Some(withdrawal_id),
+        )
+        .await?;
+
         tx.commit().await?;
 
         Ok(())
@@ -1445,21 +5312,182 @@ impl Database {
     ///
     /// This marks the pending withdrawal as failed, making the balance available again.
     pub async fn fail_pending_withdrawal(&self, withdrawal_id: &str) -> Result<()> {
+        self.release_withdrawal(withdrawal_id, WithdrawalStatus::Pending, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Reject a held withdrawal, releasing the reserved balance.
+    /// Returns false if it wasn't held (anymore).
+    pub async fn reject_held_withdrawal(
+        &self,
+        withdrawal_id: &str,
+        admin_id: &str,
+    ) -> Result<bool> {
+        self.release_withdrawal(withdrawal_id, WithdrawalStatus::Held, Some(admin_id))
+            .await
+    }
+
+    /// Mark a withdrawal in status `from` as failed and return its reserved balance
+    /// to the wallet. Returns false if it wasn't in that status.
+    async fn release_withdrawal(
+        &self,
+        withdrawal_id: &str,
+        from: WithdrawalStatus,
+        reviewed_by: Option<&str>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
         let now = Utc::now();
 
-        sqlx::query(
-            "UPDATE pending_withdrawals SET status = ?, completed_at = ? WHERE id = ? AND status = ?",
+        let released: Option<(String, i64)> = sqlx::query_as(
+            r#"
+            UPDATE pending_withdrawals
+            SET status = ?, completed_at = ?,
+                reviewed_by = COALESCE(?, reviewed_by),
+                reviewed_at = COALESCE(?, reviewed_at)
+            WHERE id = ? AND status = ?
+            RETURNING user_id, msats
+            "#,
         )
         .bind(WithdrawalStatus::Failed.as_str())
         .bind(now)
+        .bind(reviewed_by)
+        .bind(reviewed_by.map(|_| now))
+        .bind(withdrawal_id)
+        .bind(from.as_str())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let found = released.is_some();
+
+        if let Some((user_id, msats)) = released {
+            post_ledger_entry(
+                &mut tx,
+                LedgerEntryKind::WithdrawRelease,
+                &LedgerAccount::PendingWithdrawals,
+                &LedgerAccount::UserWallet(user_id),
+                msats,
+                Some(withdrawal_id),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(found)
+    }
+
+    /// Approve a held withdrawal. It becomes pending with its balance still reserved,
+    /// and the caller pays it like a new withdrawal. Returns None if it wasn't held
+    /// (anymore).
+    pub async fn approve_held_withdrawal(
+        &self,
+        withdrawal_id: &str,
+        admin_id: &str,
+    ) -> Result<Option<PendingWithdrawal>> {
+        sqlx::query_as::<_, PendingWithdrawal>(
+            "UPDATE pending_withdrawals SET status = ?, reviewed_by = ?, reviewed_at = ? WHERE id = ? AND status = ? RETURNING *",
+        )
+        .bind(WithdrawalStatus::Pending.as_str())
+        .bind(admin_id)
+        .bind(Utc::now())
+        .bind(withdrawal_id)
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_pending_withdrawal(
+        &self,
+        withdrawal_id: &str,
+    ) -> Result<Option<PendingWithdrawal>> {
+        sqlx::query_as::<_, PendingWithdrawal>("SELECT * FROM pending_withdrawals WHERE id = ?")
+            .bind(withdrawal_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Mark a pending withdrawal whose payment outcome is unknown, to be settled
+    /// once the Lightning backend knows it
+    pub async fn mark_withdrawal_unconfirmed(&self, withdrawal_id: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE pending_withdrawals SET unconfirmed_at = ? WHERE id = ? AND status = ?",
+        )
+        .bind(Utc::now())
         .bind(withdrawal_id)
         .bind(WithdrawalStatus::Pending.as_str())
         .execute(&self.pool)
         .await?;
-
         Ok(())
     }
 
+    /// List pending withdrawals whose payment outcome is unknown, oldest first
+    pub async fn list_unconfirmed_withdrawals(&self) -> Result<Vec<PendingWithdrawal>> {
+        sqlx::query_as::<_, PendingWithdrawal>(
+            "SELECT * FROM pending_withdrawals WHERE status = ? AND unconfirmed_at IS NOT NULL ORDER BY created_at",
+        )
+        .bind(WithdrawalStatus::Pending.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// List withdrawals waiting for review, oldest first
+    pub async fn list_held_withdrawals(&self) -> Result<Vec<PendingWithdrawal>> {
+        sqlx::query_as::<_, PendingWithdrawal>(
+            "SELECT * FROM pending_withdrawals WHERE status = ? ORDER BY created_at",
+        )
+        .bind(WithdrawalStatus::Held.as_str())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Amount withdrawn since `since`, without fees, counting withdrawals that are
+    /// in flight or held. Only `user_id`'s withdrawals if given, otherwise everyone's.
+    pub async fn get_withdrawn_msats_since(
+        &self,
+        user_id: Option<&str>,
+        since: chrono::DateTime<Utc>,
+    ) -> Result<i64> {
+        let mut conn = self.pool.acquire().await?;
+        withdrawn_msats_since(&mut conn, user_id, since).await
+    }
+
+    /// Whether a wallet withdrawal is paying or has paid an invoice with this payment hash
+    pub async fn has_withdrawal_for_payment_hash(&self, payment_hash: &str) -> Result<bool> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pending_withdrawals WHERE payment_hash = ? AND status != ?",
+        )
+        .bind(payment_hash)
+        .bind(WithdrawalStatus::Failed.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Get the secret key the instance signs Nostr events with (zap receipts),
+    /// generating it on first use
+    pub async fn get_or_create_nostr_secret(&self) -> Result<[u8; 32]> {
+        const KEY: &str = "nostr_secret";
+
+        if let Some(hex_secret) = self.get_setting(KEY).await? {
+            let secret = hex::decode(&hex_secret)?;
+            return secret
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Stored Nostr secret is not 32 bytes"));
+        }
+
+        use rand::{thread_rng, RngCore};
+        let mut secret = [0u8; 32];
+        thread_rng().fill_bytes(&mut secret);
+        self.set_setting(KEY, &hex::encode(secret)).await?;
+
+        tracing::info!("Generated new Nostr key");
+        Ok(secret)
+    }
+
     /// Get or create the cookie secret for private cookie jar.
     /// Generates a random 64-byte secret on first use (required by axum-extra's Key).
     pub async fn get_or_create_cookie_secret(&self) -> Result<Vec<u8>> {
@@ -0,0 +1,255 @@
+//! How much of a location's position is disclosed to hunters.
+//!
+//! Creators pick a disclosure mode per location:
+//! - exact: the coordinates are public
+//! - fuzzed: only a search area is shown, a circle of `search_radius_m` around
+//!   a point randomly offset from the location
+//! - hidden: nothing is shown until the hunter has unlocked one of the
+//!   location's hints, then the search area
+//!
+//! The circle's center is drawn once and stored with the location, so it stays
+//! put between page loads and can't be averaged out by reloading. Resizing the
+//! circle scales the same offset instead of drawing a new one. Owners and
+//! admins always see the exact position.
+
+use crate::models::Location;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Search radius suggested for new search areas
+pub const DEFAULT_SEARCH_RADIUS_M: i64 = 250;
+
+/// Smallest search radius, below which the offset hides nothing
+pub const MIN_SEARCH_RADIUS_M: i64 = 25;
+
+/// Largest search radius
+pub const MAX_SEARCH_RADIUS_M: i64 = 10_000;
+
+/// Meters per degree of latitude
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// How much of a location's position is shown, selectable per location
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Disclosure {
+    #[default]
+    Exact,
+    Fuzzed,
+    Hidden,
+}
+
+impl Disclosure {
+    pub const ALL: [Disclosure; 3] = [Self::Exact, Self::Fuzzed, Self::Hidden];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact",
+            Self::Fuzzed => "fuzzed",
+            Self::Hidden => "hidden",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Exact => "EXACT POSITION",
+            Self::Fuzzed => "SEARCH AREA",
+            Self::Hidden => "HIDDEN UNTIL A HINT IS UNLOCKED",
+        }
+    }
+
+    /// Whether the mode needs a search area
+    pub fn uses_search_area(&self) -> bool {
+        !matches!(self, Self::Exact)
+    }
+}
+
+impl std::fmt::Display for Disclosure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Disclosure {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(Self::Exact),
+            "fuzzed" => Ok(Self::Fuzzed),
+            "hidden" => Ok(Self::Hidden),
+            _ => Err(anyhow::anyhow!("Invalid disclosure: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Disclosure {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A circle known to contain a location
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchArea {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_m: i64,
+}
+
+impl SearchArea {
+    /// Draw a search area around a position, with the center uniformly random
+    /// within `radius_m` of it
+    pub fn around(latitude: f64, longitude: f64, radius_m: i64, rng: &mut impl Rng) -> Self {
+        let distance = radius_m as f64 * rng.gen::<f64>().sqrt();
+        let bearing = rng.gen::<f64>() * std::f64::consts::TAU;
+        // Longitude degrees shrink towards the poles
+        let meters_per_lng_degree = METERS_PER_DEGREE * latitude.to_radians().cos().max(0.01);
+        Self {
+            latitude: (latitude + distance * bearing.cos() / METERS_PER_DEGREE).clamp(-90.0, 90.0),
+            longitude: longitude + distance * bearing.sin() / meters_per_lng_degree,
+            radius_m,
+        }
+    }
+
+    /// The same area resized to `radius_m` around a position. The center's offset
+    /// from the position scales with the radius, so no new random center is
+    /// revealed.
+    pub fn with_radius(&self, latitude: f64, longitude: f64, radius_m: i64) -> Self {
+        let scale = radius_m as f64 / self.radius_m.max(1) as f64;
+        Self {
+            latitude: (latitude + (self.latitude - latitude) * scale).clamp(-90.0, 90.0),
+            longitude: longitude + (self.longitude - longitude) * scale,
+            radius_m,
+        }
+    }
+
+    /// Approximate distance from the center to a position in meters
+    pub fn distance_m(&self, latitude: f64, longitude: f64) -> f64 {
+        let meters_per_lng_degree = METERS_PER_DEGREE * self.latitude.to_radians().cos();
+        let dy = (latitude - self.latitude) * METERS_PER_DEGREE;
+        let dx = (longitude - self.longitude) * meters_per_lng_degree;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+/// What a viewer gets to see of a location's position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    Exact { latitude: f64, longitude: f64 },
+    Area(SearchArea),
+    Hidden,
+}
+
+impl Position {
+    /// The position of `location` for a viewer. `full_access` is for owners and
+    /// admins, `hint_unlocked` whether the viewer has unlocked any of the
+    /// location's hints.
+    pub fn of(location: &Location, full_access: bool, hint_unlocked: bool) -> Self {
+        if full_access || location.disclosure == Disclosure::Exact {
+            return Self::Exact {
+                latitude: location.latitude,
+                longitude: location.longitude,
+            };
+        }
+        // Without a stored search area nothing can be shown safely
+        match (location.disclosure, location.search_area()) {
+            (Disclosure::Fuzzed, Some(area)) => Self::Area(area),
+            (Disclosure::Hidden, Some(area)) if hint_unlocked => Self::Area(area),
+            _ => Self::Hidden,
+        }
+    }
+
+    /// Point to center a map on, None when hidden
+    pub fn center(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Exact {
+                latitude,
+                longitude,
+            } => Some((*latitude, *longitude)),
+            Self::Area(area) => Some((area.latitude, area.longitude)),
+            Self::Hidden => None,
+        }
+    }
+
+    /// Radius of the search area, None for exact and hidden positions
+    pub fn radius_m(&self) -> Option<i64> {
+        match self {
+            Self::Area(area) => Some(area.radius_m),
+            _ => None,
+        }
+    }
+
+    /// Short text for location cards
+    pub fn label(&self) -> String {
+        match self {
+            Self::Exact {
+                latitude,
+                longitude,
+            } => format!("{:.4}, {:.4}", latitude, longitude),
+            Self::Area(area) => format!(
+                "WITHIN {} M OF {:.4}, {:.4}",
+                area.radius_m, area.latitude, area.longitude
+            ),
+            Self::Hidden => "HIDDEN · UNLOCK A HINT".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_area_contains_location() {
+        let mut rng = rand::thread_rng();
+        for (latitude, longitude) in [(37.7749, -122.4194), (0.0, 0.0), (69.6492, 18.9553)] {
+            for _ in 0..1000 {
+                let area = SearchArea::around(latitude, longitude, 250, &mut rng);
+                // Allow for the flat-earth approximation
+                assert!(area.distance_m(latitude, longitude) <= 251.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_area_is_offset() {
+        let mut rng = rand::thread_rng();
+        let offsets: Vec<f64> = (0..100)
+            .map(|_| {
+                SearchArea::around(37.7749, -122.4194, 250, &mut rng).distance_m(37.7749, -122.4194)
+            })
+            .collect();
+        assert!(offsets.iter().any(|d| *d > 50.0));
+    }
+
+    #[test]
+    fn test_resized_search_area_keeps_offset() {
+        let mut rng = rand::thread_rng();
+        let (latitude, longitude) = (37.7749, -122.4194);
+        for _ in 0..100 {
+            let area = SearchArea::around(latitude, longitude, 250, &mut rng);
+            let larger = area.with_radius(latitude, longitude, 1000);
+            assert_eq!(larger.radius_m, 1000);
+            assert!(larger.distance_m(latitude, longitude) <= 1001.0);
+            // Same direction, four times the distance
+            let offset = area.distance_m(latitude, longitude);
+            assert!((larger.distance_m(latitude, longitude) - 4.0 * offset).abs() < 1.0);
+            let back = larger.with_radius(latitude, longitude, 250);
+            assert!((back.latitude - area.latitude).abs() < 1e-9);
+            assert!((back.longitude - area.longitude).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        for disclosure in Disclosure::ALL {
+            assert_eq!(
+                disclosure.as_str().parse::<Disclosure>().unwrap(),
+                disclosure
+            );
+        }
+        assert!("fuzzy".parse::<Disclosure>().is_err());
+    }
+}
@@ -1,8 +1,12 @@
 use crate::db::Database;
 use crate::lightning::Lightning;
+use crate::models::Donation;
 use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, Mutex};
+
+/// Capacity of the received donations channel before slow subscribers start lagging
+const RECEIVED_CHANNEL_CAPACITY: usize = 64;
 
 /// Message to notify the DonationService about new pending donations
 pub struct NewDonation {
@@ -24,17 +28,21 @@ pub struct DonationService {
     receiver: Mutex<Option<mpsc::UnboundedReceiver<NewDonation>>>,
     /// Set of invoices currently being awaited (to prevent duplicate tasks)
     active_invoices: Mutex<HashSet<String>>,
+    /// Publishes donations once their payment has been received
+    received: broadcast::Sender<Donation>,
 }
 
 impl DonationService {
     pub fn new(db: Arc<Database>, lightning: Arc<dyn Lightning>) -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
+        let (received, _) = broadcast::channel(RECEIVED_CHANNEL_CAPACITY);
         Self {
             db,
             lightning,
             sender,
             receiver: Mutex::new(Some(receiver)),
             active_invoices: Mutex::new(HashSet::new()),
+            received,
         }
     }
 
@@ -43,6 +51,11 @@ impl DonationService {
         self.sender.clone()
     }
 
+    /// Subscribe to donations received from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Donation> {
+        self.received.subscribe()
+    }
+
     /// Start the donation service - loads pending donations and listens for new ones
     pub async fn start(self: Arc<Self>) {
         // Load existing pending donations from database
@@ -125,7 +138,13 @@ impl DonationService {
                     // Mark donation as received in database
                     // This automatically updates the pool balance (calculated from received donations)
                     match service.db.mark_donation_received(&invoice_clone).await {
-                        Ok(donation) => {
+                        Ok(None) => {
+                            tracing::info!("Donation {} was already received", invoice_clone);
+                        }
+                        Ok(Some(donation)) => {
+                            // Nobody listening is fine, so the send error is ignored
+                            let _ = service.received.send(donation.clone());
+
                             if let Some(loc_id) = &location_id {
                                 // Get updated location pool balance
                                 match service.db.get_location_donation_pool_balance(loc_id).await {
@@ -0,0 +1,155 @@
+//! Routing fees reserved for wallet withdrawals.
+//!
+//! A withdrawal reserves its routing fee on top of the amount, so the node never
+//! pays fees out of other users' funds. The fee reserved is:
+//! - the Lightning backend's estimate plus a margin, if it can probe the route
+//! - otherwise the configured `FeePolicy`
+//!
+//! When the backend reports the fee it actually paid, the part of the reserve it
+//! didn't need goes back to the user's wallet (see
+//! `Database::complete_pending_withdrawal`).
+
+use crate::lightning::Lightning;
+
+/// Fallback fee reserved when the route can't be probed: a fixed part plus a
+/// proportional part of the amount
+#[derive(Debug, Clone)]
+pub struct FeePolicy {
+    /// Fixed part of the fee, in msats. Also added to probed estimates as a
+    /// margin for routes changing between probing and paying.
+    pub base_msats: i64,
+    /// Proportional part of the fee, in parts per million of the amount
+    pub rate_ppm: i64,
+}
+
+impl Default for FeePolicy {
+    /// 2 sats + 0.5%
+    fn default() -> Self {
+        Self {
+            base_msats: 2000,
+            rate_ppm: 5000,
+        }
+    }
+}
+
+impl FeePolicy {
+    /// Fee reserved for paying `amount_msats` when the route can't be probed
+    pub fn fee_msats(&self, amount_msats: i64) -> i64 {
+        let proportional = (amount_msats.max(0) * self.rate_ppm + 999_999) / 1_000_000;
+        self.base_msats + proportional
+    }
+
+    /// Most that can be withdrawn from a balance when the amount has to be picked
+    /// before there is an invoice to probe.
+    /// Returns (max_withdrawable_msats, fee_msats).
+    pub fn max_withdrawable(&self, balance_msats: i64) -> (i64, i64) {
+        let fee_msats = self.fee_msats(balance_msats);
+        ((balance_msats - fee_msats).max(0), fee_msats)
+    }
+
+    /// Smallest balance that leaves a whole sat to withdraw after the policy fee,
+    /// or `None` if the fee rate takes the whole balance
+    pub fn min_withdrawable_balance_msats(&self) -> Option<i64> {
+        let kept_ppm = 1_000_000 - self.rate_ppm;
+        if kept_ppm <= 0 {
+            return None;
+        }
+        // Ignoring the rounding of the proportional fee, then stepped past it
+        let mut balance_msats = ((1000 + self.base_msats.max(0)) * 1_000_000).div_ceil(kept_ppm);
+        while self.max_withdrawable(balance_msats).0 < 1000 {
+            balance_msats += 1;
+        }
+        Some(balance_msats)
+    }
+
+    /// Fee to reserve for paying `invoice` for `amount_msats`: the backend's
+    /// estimate plus the base fee as margin, or the policy fee if it can't probe
+    pub async fn reserve_fee_msats(
+        &self,
+        lightning: &dyn Lightning,
+        invoice: &str,
+        amount_msats: i64,
+    ) -> i64 {
+        match lightning.estimate_fee_msats(invoice).await {
+            Ok(Some(estimate_msats)) => estimate_msats as i64 + self.base_msats,
+            Ok(None) => self.fee_msats(amount_msats),
+            Err(e) => {
+                tracing::warn!("Fee probing failed, falling back to fee policy: {}", e);
+                self.fee_msats(amount_msats)
+            }
+        }
+    }
+}
+
+/// Part of a reserved fee to refund once the fee actually paid is known.
+/// Nothing is refunded if the paid fee is unknown.
+pub fn fee_refund_msats(reserved_fee_msats: i64, paid_fee_msats: Option<u64>) -> i64 {
+    match paid_fee_msats {
+        Some(paid) => (reserved_fee_msats - paid as i64).max(0),
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::MockLightning;
+
+    #[test]
+    fn test_default_policy() {
+        let policy = FeePolicy::default();
+        // 2 sats + 0.5% of 100k sats
+        assert_eq!(policy.fee_msats(100_000_000), 502_000);
+        // Rounded up to the next msat
+        assert_eq!(policy.fee_msats(1), 2001);
+        assert_eq!(policy.max_withdrawable(100_000_000), (99_498_000, 502_000));
+        assert_eq!(policy.max_withdrawable(1000).0, 0);
+    }
+
+    #[test]
+    fn test_min_withdrawable_balance() {
+        let policy = FeePolicy::default();
+        let min_msats = policy.min_withdrawable_balance_msats().unwrap();
+        assert_eq!(min_msats, 3016);
+        assert_eq!(policy.max_withdrawable(min_msats).0, 1000);
+        assert!(policy.max_withdrawable(min_msats - 1).0 < 1000);
+
+        let no_fees = FeePolicy {
+            base_msats: 0,
+            rate_ppm: 0,
+        };
+        assert_eq!(no_fees.min_withdrawable_balance_msats(), Some(1000));
+
+        let everything = FeePolicy {
+            base_msats: 0,
+            rate_ppm: 1_000_000,
+        };
+        assert_eq!(everything.min_withdrawable_balance_msats(), None);
+    }
+
+    #[tokio::test]
+    async fn test_reserve_uses_estimate() {
+        let policy = FeePolicy::default();
+
+        let probing = MockLightning::with_fees(1500, 1000);
+        assert_eq!(
+            policy
+                .reserve_fee_msats(&probing, "lnbc", 100_000_000)
+                .await,
+            3500
+        );
+
+        let blind = MockLightning::new();
+        assert_eq!(
+            policy.reserve_fee_msats(&blind, "lnbc", 100_000_000).await,
+            502_000
+        );
+    }
+
+    #[test]
+    fn test_fee_refund() {
+        assert_eq!(fee_refund_msats(3500, Some(1000)), 2500);
+        assert_eq!(fee_refund_msats(3500, Some(5000)), 0);
+        assert_eq!(fee_refund_msats(3500, None), 0);
+    }
+}
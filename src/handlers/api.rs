@@ -1,12 +1,31 @@
 use crate::{
-    auth::{AuthUser, CookieUser, Key, RequireRegistered},
-    balance::BalanceConfig,
+    achievements::CheckAchievements,
+    auth::{AuthUser, CookieUser, Key, RequireRegistered, UserKind},
+    auto_withdraw::{self, CheckAutoWithdraw},
+    balance::{self, BalanceConfig},
+    campaign::NewCampaign,
+    claim_policy::ClaimPolicy,
+    claim_rules::{self, ClaimRules},
     db::Database,
+    disclosure::{
+        Disclosure, SearchArea, DEFAULT_SEARCH_RADIUS_M, MAX_SEARCH_RADIUS_M, MIN_SEARCH_RADIUS_M,
+    },
     donation::NewDonation,
-    lightning::{Lightning, LightningService},
+    fees::FeePolicy,
+    invoice_policy::{CheckedInvoice, InvoicePolicy, InvoicePolicyError},
+    leaderboard::{Metric, Period},
+    lightning::{Lightning, LightningService, PaymentStatus},
     lnurl,
-    models::{ClaimResult, UserRole},
-    ntag424,
+    models::{
+        ClaimResult, HintUnlock, LiabilitySnapshot, Location, LocationLog, LogKind, NewHint,
+        NewMatchingCampaign, NewSchedule, Recurrence, TeamMember, TeamRole, UserRole,
+        WalletWithdrawLink,
+    },
+    ntag424, nwc,
+    receive::NewWalletInvoice,
+    solvency,
+    withdraw_limits::WithdrawLimits,
+    zap,
 };
 use axum::{
     extract::{Multipart, Path, Query, State},
@@ -42,34 +61,45 @@ pub struct AppState {
     pub base_url: String,
     pub balance_config: BalanceConfig,
     pub donation_sender: mpsc::UnboundedSender<NewDonation>,
+    pub campaign_sender: mpsc::UnboundedSender<NewCampaign>,
+    pub receive_sender: mpsc::UnboundedSender<NewWalletInvoice>,
+    pub auto_withdraw_sender: mpsc::UnboundedSender<CheckAutoWithdraw>,
+    pub achievement_sender: mpsc::UnboundedSender<CheckAchievements>,
+    /// Checks invoices before they are paid out
+    pub invoice_policy: InvoicePolicy,
+    /// Routing fees reserved for wallet withdrawals the route can't be probed for
+    pub fee_policy: FeePolicy,
+    /// Wallet withdrawals over these limits are held for review
+    pub withdraw_limits: WithdrawLimits,
+    /// Global claim rules, which locations can override
+    pub claim_rules: ClaimRules,
     /// Key for signing private cookies
     pub cookie_key: Key,
     /// Secret for signing withdrawal tokens (derived from cookie_key)
     pub withdraw_secret: Vec<u8>,
+    /// Nostr key zap receipts are signed with
+    pub nostr_keys: secp256k1::Keypair,
+    /// Encrypts NWC connection URIs before they are stored
+    pub nwc_cipher: nwc::UriCipher,
 }
 
-/// Calculate Lightning network fees for a withdrawal.
-/// Returns (max_withdrawable_msats, fee_msats) given a balance in msats.
-/// Fee structure: 2 sats fixed + 0.5% routing fee
-fn calculate_withdrawal_fees(balance_msats: i64) -> (i64, i64) {
-    let routing_fee_msats = ((balance_msats as f64) * 0.005).ceil() as i64;
-    let fixed_fee_msats = 2000; // 2 sats
-    let total_fee_msats = routing_fee_msats + fixed_fee_msats;
-    let max_withdrawable_msats = (balance_msats - total_fee_msats).max(0);
-    (max_withdrawable_msats, total_fee_msats)
-}
-
-/// Check if an invoice amount plus fees fits within balance.
+/// Reserve the routing fee for paying an invoice and check that the
+/// amount plus fee fits within balance.
 /// Returns Ok(fee_msats) if valid, or Err with error message.
-fn check_invoice_with_fees(invoice_msats: i64, balance_msats: i64) -> Result<i64, String> {
-    // Calculate fee for this specific invoice amount
-    let routing_fee_msats = ((invoice_msats as f64) * 0.005).ceil() as i64;
-    let fixed_fee_msats = 2000; // 2 sats
-    let total_fee_msats = routing_fee_msats + fixed_fee_msats;
+async fn reserve_invoice_fee(
+    state: &AppState,
+    invoice: &str,
+    invoice_msats: i64,
+    balance_msats: i64,
+) -> Result<i64, String> {
+    let total_fee_msats = state
+        .fee_policy
+        .reserve_fee_msats(state.lightning.as_ref(), invoice, invoice_msats)
+        .await;
     let total_required_msats = invoice_msats + total_fee_msats;
 
     if total_required_msats > balance_msats {
-        let max_after_fees = calculate_withdrawal_fees(balance_msats).0 / 1000;
+        let max_after_fees = state.fee_policy.max_withdrawable(balance_msats).0 / 1000;
         Err(format!(
             "Invoice ({} sats) + fees ({} sats) exceeds balance. Max withdrawal: {} sats.",
             invoice_msats / 1000,
@@ -81,6 +111,79 @@ fn check_invoice_with_fees(invoice_msats: i64, balance_msats: i64) -> Result<i64
     }
 }
 
+/// Message for a balance too low to withdraw anything after fees, naming the
+/// smallest balance the fee policy lets the user withdraw from
+fn insufficient_balance_message(fee_policy: &FeePolicy) -> String {
+    match fee_policy.min_withdrawable_balance_msats() {
+        Some(min_msats) => format!(
+            "Insufficient balance to cover fees. You need at least {} sats to withdraw.",
+            (min_msats + 999) / 1000
+        ),
+        None => "Insufficient balance to cover fees.".to_string(),
+    }
+}
+
+/// Reserve the balance for a wallet withdrawal, holding it for review if it's over a
+/// withdrawal limit. Returns the withdrawal ID and whether it was held, or None if
+/// the balance is insufficient.
+async fn reserve_wallet_withdrawal(
+    state: &AppState,
+    user_id: &str,
+    amount_msats: i64,
+    fee_msats: i64,
+    invoice: &str,
+) -> anyhow::Result<Option<(String, bool)>> {
+    let Some((id, hold_reason)) = state
+        .db
+        .reserve_wallet_withdrawal(
+            user_id,
+            amount_msats,
+            fee_msats,
+            invoice,
+            &state.withdraw_limits,
+        )
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    if let Some(reason) = &hold_reason {
+        tracing::info!(
+            "Withdrawal of {} sats by user {} held for review: {}",
+            amount_msats / 1000,
+            user_id,
+            reason
+        );
+    }
+    Ok(Some((id, hold_reason.is_some())))
+}
+
+/// Run an invoice through the invoice policy before paying it out.
+/// On refusal, returns the status and a message to show the user.
+async fn check_invoice_policy(
+    state: &AppState,
+    invoice: &str,
+) -> Result<CheckedInvoice, (StatusCode, String)> {
+    state
+        .invoice_policy
+        .check(invoice, state.lightning.as_ref(), &state.db)
+        .await
+        .map_err(|e| match e {
+            InvoicePolicyError::Internal(e) => {
+                tracing::error!("Failed to check invoice: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to process withdrawal. Please try again.".to_string(),
+                )
+            }
+            InvoicePolicyError::DuplicatePayment => (StatusCode::CONFLICT, e.to_string()),
+            e => {
+                tracing::warn!("Invoice refused by policy: {}", e);
+                (StatusCode::BAD_REQUEST, e.to_string())
+            }
+        })
+}
+
 /// Create a signed withdrawal token for a user.
 /// Format: "{user_id}:{timestamp}:{signature}"
 /// The token is valid for 1 hour.
@@ -158,7 +261,7 @@ pub async fn create_location(
             payload.longitude,
             payload.description,
             lnurlw_secret,
-            auth.user_id,
+            auth.user_id.clone(),
         )
         .await
         .map_err(|e| {
@@ -166,6 +269,8 @@ pub async fn create_location(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    request_achievement_check(&state, &auth.user_id);
+
     Ok(Json(json!({
         "location_id": location.id,
         "write_token": location.write_token
@@ -183,23 +288,118 @@ pub async fn get_stats(
     Ok(Json(json!(stats)))
 }
 
+/// Days of donation history used to estimate a location's donation rate
+const FORECAST_DONATION_WINDOW_DAYS: u32 = 30;
+/// Default and maximum number of days in a balance forecast
+const FORECAST_DEFAULT_DAYS: u32 = 14;
+const FORECAST_MAX_DAYS: u32 = 90;
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    pub days: Option<u32>,
+}
+
+/// Projected balance of a location over the coming days
+#[derive(Debug, Serialize)]
+pub struct LocationForecast {
+    pub location_id: String,
+    pub pool_msats: i64,
+    pub donation_rate_msats_per_day: f64,
+    pub points: Vec<balance::BalanceForecastPoint>,
+}
+
+/// Build a balance forecast for a location from its pool and recent donations
+pub async fn build_location_forecast(
+    state: &AppState,
+    location: &Location,
+    days: u32,
+) -> anyhow::Result<LocationForecast> {
+    let pool_msats = state
+        .db
+        .get_location_donation_pool_balance(&location.id)
+        .await?;
+    let donations: Vec<_> = state
+        .db
+        .list_location_donations(&location.id)
+        .await?
+        .into_iter()
+        .filter_map(|d| d.received_at.map(|at| (at, d.amount_msats)))
+        .collect();
+    let donation_rate_msats_per_day =
+        balance::donation_rate_msats_per_day(&donations, FORECAST_DONATION_WINDOW_DAYS, Utc::now());
+
+    let points = balance::forecast_balance_msats(
+        pool_msats,
+        location.last_withdraw_at,
+        location.created_at,
+        donation_rate_msats_per_day,
+        days.min(FORECAST_MAX_DAYS),
+        &state.balance_config,
+    );
+
+    Ok(LocationForecast {
+        location_id: location.id.clone(),
+        pool_msats,
+        donation_rate_msats_per_day,
+        points,
+    })
+}
+
+/// Project a location's claimable balance over the next days
+///
+/// GET /api/locations/{location_id}/forecast?days=14
+pub async fn get_location_forecast(
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    Query(query): Query<ForecastQuery>,
+) -> Result<Json<LocationForecast>, StatusCode> {
+    let location = state
+        .db
+        .get_location(&location_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let forecast = build_location_forecast(
+        &state,
+        &location,
+        query.days.unwrap_or(FORECAST_DEFAULT_DAYS),
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to build forecast: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(forecast))
+}
+
 #[derive(serde::Deserialize)]
 pub struct DonationInvoiceRequest {
     pub amount: i64,
     /// Optional location ID for direct location donations.
     /// If None, the donation goes to the global pool (split among all locations).
     pub location_id: Option<String>,
+    /// Optional hunt ID for donations to a hunt's completion bonus pool
+    pub hunt_id: Option<String>,
 }
 
 /// Generate a Lightning invoice for donation
 pub async fn create_donation_invoice(
     State(state): State<Arc<AppState>>,
+    user: CookieUser,
     Json(payload): Json<DonationInvoiceRequest>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    if payload.amount <= 0 {
-        tracing::error!("Invalid donation amount: {}", payload.amount);
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    let amount_msats = match request_sats_to_msats(payload.amount) {
+        Some(msats) if msats > 0 => msats,
+        _ => {
+            tracing::error!("Invalid donation amount: {}", payload.amount);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
 
     // If location_id is provided, verify it exists
     let location_name = if let Some(ref loc_id) = payload.location_id {
@@ -220,6 +420,25 @@ pub async fn create_donation_invoice(
         None
     };
 
+    // Donations to a hunt go to its bonus pool instead
+    let hunt = match (&payload.location_id, &payload.hunt_id) {
+        (None, Some(hunt_id)) => Some(
+            state
+                .db
+                .get_hunt(hunt_id)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to get hunt: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .ok_or_else(|| {
+                    tracing::error!("Hunt not found: {}", hunt_id);
+                    StatusCode::NOT_FOUND
+                })?,
+        ),
+        _ => None,
+    };
+
     let description = if let Some(ref name) = location_name {
         tracing::info!(
             "Creating invoice for {} sats donation to location '{}'",
@@ -227,6 +446,16 @@ pub async fn create_donation_invoice(
             name
         );
         format!("SatsHunt donation to '{}': {} sats", name, payload.amount)
+    } else if let Some(ref hunt) = hunt {
+        tracing::info!(
+            "Creating invoice for {} sats donation to hunt '{}'",
+            payload.amount,
+            hunt.name
+        );
+        format!(
+            "SatsHunt donation to hunt '{}': {} sats",
+            hunt.name, payload.amount
+        )
     } else {
         tracing::info!(
             "Creating invoice for {} sats global donation",
@@ -245,21 +474,40 @@ pub async fn create_donation_invoice(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    let amount_msats = payload.amount * 1000;
-
     // Store donation in database for resilient tracking
-    state
-        .db
-        .create_donation(
-            invoice.clone(),
-            amount_msats,
-            payload.location_id.as_deref(),
-        )
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to create donation: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+    let donation = match &hunt {
+        Some(hunt) => {
+            state
+                .db
+                .create_hunt_donation(invoice.clone(), amount_msats, &hunt.id)
+                .await
+        }
+        None => {
+            state
+                .db
+                .create_donation(
+                    invoice.clone(),
+                    amount_msats,
+                    payload.location_id.as_deref(),
+                )
+                .await
+        }
+    };
+    let donation = donation.map_err(|e| {
+        tracing::error!("Failed to create donation: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Known donors get their donations counted towards badges
+    if !matches!(user.kind, UserKind::AnonNew) {
+        if let Err(e) = state
+            .db
+            .set_donation_user(&donation.id, &user.user_id)
+            .await
+        {
+            tracing::error!("Failed to record donor: {}", e);
+        }
+    }
 
     // Notify donation service to start awaiting payment
     if let Err(e) = state.donation_sender.send(NewDonation {
@@ -394,6 +642,27 @@ pub async fn wait_for_donation(
                             if prefix.is_empty() { "" } else { "Location" }
                         )
                     }
+                } else if let Some(ref hunt_id) = donation.hunt_id {
+                    // Donation to a hunt's bonus pool
+                    let pool_balance_msats =
+                        state.db.get_hunt_pool_balance(hunt_id).await.unwrap_or(0);
+
+                    format!(
+                        r#"<div class="p-6" style="background: var(--bg-tertiary); border: 2px solid var(--accent-muted);">
+                            <div class="p-3 flex items-center gap-2" style="background: rgba(107, 155, 107, 0.25); border: 2px solid var(--color-success);">
+                                <i class="fa-solid fa-check-circle" style="color: var(--color-success);"></i>
+                                <span class="text-sm font-bold text-primary">Payment received! Thank you for donating {} sats to this hunt!</span>
+                            </div>
+                            <div class="text-center mt-4">
+                                <p class="text-sm text-muted font-bold">Bonus Pool</p>
+                                <p class="text-3xl font-black text-highlight orange">{} <i class="fa-solid fa-bolt"></i></p>
+                            </div>
+                            <button type="button" onclick="reset{}Donation()" class="btn-brutal mt-4 w-full">Done</button>
+                        </div>"#,
+                        amount,
+                        pool_balance_msats / 1000,
+                        if prefix.is_empty() { "" } else { "Location" }
+                    )
                 } else {
                     // Global donation - was split among all locations
                     let locations = state.db.list_active_locations().await.map_err(|e| {
@@ -467,6 +736,119 @@ pub async fn wait_for_donation(
     Ok(axum::response::Html(html))
 }
 
+/// Form for starting a recurring donation
+#[derive(Debug, Deserialize)]
+pub struct CreateRecurringDonationRequest {
+    /// Location to donate to, empty for global donations
+    pub location_id: Option<String>,
+    pub amount_sats: i64,
+    pub interval_days: i64,
+    /// `nostr+walletconnect://` URI of the donor's wallet
+    pub nwc_uri: String,
+}
+
+/// Longest interval between two recurring payments
+const MAX_RECURRING_INTERVAL_DAYS: i64 = 365;
+/// Largest single recurring payment
+const MAX_RECURRING_AMOUNT_SATS: i64 = 1_000_000;
+
+/// Start a recurring donation paid over Nostr Wallet Connect
+///
+/// POST /api/recurring-donations
+///
+/// The first payment is collected by the scheduler right away.
+pub async fn create_recurring_donation(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Form(payload): Form<CreateRecurringDonationRequest>,
+) -> impl IntoResponse {
+    let valid = (1..=MAX_RECURRING_AMOUNT_SATS).contains(&payload.amount_sats)
+        && (1..=MAX_RECURRING_INTERVAL_DAYS).contains(&payload.interval_days);
+    let Some(amount_msats) = payload.amount_sats.checked_mul(1000).filter(|_| valid) else {
+        tracing::warn!(
+            "Invalid recurring donation: {} sats every {} days",
+            payload.amount_sats,
+            payload.interval_days
+        );
+        return (user.jar, StatusCode::BAD_REQUEST).into_response();
+    };
+
+    if let Err(e) = payload.nwc_uri.parse::<nwc::NwcConnection>() {
+        tracing::warn!("Invalid NWC connection for recurring donation: {}", e);
+        return (user.jar, StatusCode::BAD_REQUEST).into_response();
+    }
+
+    let location_id = payload.location_id.as_deref().filter(|id| !id.is_empty());
+    if let Some(location_id) = location_id {
+        match state.db.get_location(location_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return (user.jar, StatusCode::NOT_FOUND).into_response(),
+            Err(e) => {
+                tracing::error!("Failed to get location: {}", e);
+                return (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+            }
+        }
+    }
+
+    match state
+        .db
+        .create_recurring_donation(
+            &user.user_id,
+            location_id,
+            amount_msats,
+            payload.interval_days,
+            &state.nwc_cipher.encrypt(payload.nwc_uri.trim()),
+        )
+        .await
+    {
+        Ok(recurring) => {
+            tracing::info!(
+                "User {} started recurring donation {}: {} sats every {} days",
+                user.user_id,
+                recurring.id,
+                payload.amount_sats,
+                payload.interval_days
+            );
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to create recurring donation: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+/// Cancel one of the current user's recurring donations
+///
+/// POST /api/recurring-donations/{recurring_id}/cancel
+pub async fn cancel_recurring_donation(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Path(recurring_id): Path<String>,
+) -> impl IntoResponse {
+    match state
+        .db
+        .cancel_recurring_donation(&recurring_id, &user.user_id)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            (user.jar, StatusCode::NOT_FOUND).into_response()
+        }
+        Ok(_) => {
+            tracing::info!(
+                "User {} cancelled recurring donation {}",
+                user.user_id,
+                recurring_id
+            );
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to cancel recurring donation: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
 /// Generate a random 32-character hex string for card keys
 fn generate_card_key() -> String {
     use rand::Rng;
@@ -714,8 +1096,8 @@ pub async fn delete_location(
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Check if active (cannot delete active locations)
-    if location.is_active() {
+    // Check if live (cannot delete active or scheduled locations)
+    if location.is_live() {
         tracing::warn!(
             "User {} attempted to delete active location {}",
             auth.user_id,
@@ -775,6 +1157,55 @@ fn apply_exif_orientation(data: &[u8], img: DynamicImage) -> DynamicImage {
     }
 }
 
+/// Decode an uploaded image, fix its orientation, shrink it to at most 12
+/// megapixels and save it as JPEG in the upload directory. Returns the file name.
+fn save_uploaded_image(upload_dir: &std::path::Path, data: &[u8]) -> Result<String, StatusCode> {
+    // Decode image to validate it's a real image
+    let img = image::load_from_memory(data).map_err(|e| {
+        tracing::error!("Failed to decode image: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    // Apply EXIF orientation to fix rotated images
+    let img = apply_exif_orientation(data, img);
+
+    // Resize if larger than 12 megapixels
+    const MAX_PIXELS: u32 = 12_000_000;
+    let (width, height) = img.dimensions();
+    let total_pixels = width as u64 * height as u64;
+
+    let img = if total_pixels > MAX_PIXELS as u64 {
+        let scale = ((MAX_PIXELS as f64) / (total_pixels as f64)).sqrt();
+        let new_width = (width as f64 * scale) as u32;
+        let new_height = (height as f64 * scale) as u32;
+
+        tracing::info!(
+            "Resizing image from {}x{} to {}x{}",
+            width,
+            height,
+            new_width,
+            new_height
+        );
+
+        img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
+    } else {
+        img
+    };
+
+    // Generate clean UUID filename
+    let filename = format!("{}.jpg", uuid::Uuid::new_v4());
+    let file_path = upload_dir.join(&filename);
+
+    // Encode as JPEG and save
+    img.save_with_format(&file_path, image::ImageFormat::Jpeg)
+        .map_err(|e| {
+            tracing::error!("Failed to save JPEG: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(filename)
+}
+
 /// Upload a photo to a location
 pub async fn upload_photo(
     auth: AuthUser,
@@ -813,8 +1244,8 @@ pub async fn upload_photo(
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Check if location is active (cannot modify photos of active locations)
-    if location.is_active() {
+    // Check if location is live (cannot modify photos of active or scheduled locations)
+    if location.is_live() {
         tracing::warn!(
             "User {} attempted to upload photo to active location {}",
             auth.user_id,
@@ -833,49 +1264,7 @@ pub async fn upload_photo(
                 tracing::error!("Failed to read photo data: {}", e);
                 StatusCode::BAD_REQUEST
             })?;
-
-            // Decode image to validate it's a real image
-            let img = image::load_from_memory(&data).map_err(|e| {
-                tracing::error!("Failed to decode image: {}", e);
-                StatusCode::BAD_REQUEST
-            })?;
-
-            // Apply EXIF orientation to fix rotated images
-            let img = apply_exif_orientation(&data, img);
-
-            // Resize if larger than 12 megapixels
-            const MAX_PIXELS: u32 = 12_000_000;
-            let (width, height) = img.dimensions();
-            let total_pixels = width as u64 * height as u64;
-
-            let img = if total_pixels > MAX_PIXELS as u64 {
-                let scale = ((MAX_PIXELS as f64) / (total_pixels as f64)).sqrt();
-                let new_width = (width as f64 * scale) as u32;
-                let new_height = (height as f64 * scale) as u32;
-
-                tracing::info!(
-                    "Resizing image from {}x{} to {}x{}",
-                    width,
-                    height,
-                    new_width,
-                    new_height
-                );
-
-                img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
-            } else {
-                img
-            };
-
-            // Generate clean UUID filename
-            let filename = format!("{}.jpg", uuid::Uuid::new_v4());
-            let file_path = state.upload_dir.join(&filename);
-
-            // Encode as JPEG and save
-            img.save_with_format(&file_path, image::ImageFormat::Jpeg)
-                .map_err(|e| {
-                    tracing::error!("Failed to save JPEG: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
+            let filename = save_uploaded_image(&state.upload_dir, &data)?;
 
             state
                 .db
@@ -948,8 +1337,8 @@ pub async fn delete_photo(
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Check if location is active (cannot modify photos of active locations)
-    if location.is_active() {
+    // Check if location is live (cannot modify photos of active or scheduled locations)
+    if location.is_live() {
         tracing::warn!(
             "User {} attempted to delete photo from active location {}",
             auth.user_id,
@@ -978,820 +1367,1054 @@ pub async fn delete_photo(
 }
 
 // ============================================================================
-// Withdrawal API Endpoints
+// Log Book API Endpoints
 // ============================================================================
 
-/// Query parameters for SUN message verification
-#[derive(Debug, Deserialize)]
-pub struct SunParams {
-    /// NTAG424 encrypted picc_data (parameter name: p)
-    #[serde(alias = "picc_data")]
-    pub p: String,
-    /// NTAG424 CMAC signature (parameter name: c)
-    #[serde(alias = "cmac")]
-    pub c: String,
-}
+/// Most photos a single log can have
+const MAX_LOG_PHOTOS: usize = 3;
 
-/// Request body for LN address withdrawal
-#[derive(Debug, Deserialize)]
-pub struct LnAddressWithdrawRequest {
-    pub ln_address: String,
-}
+/// Longest log text in characters
+const MAX_LOG_TEXT_CHARS: usize = 2000;
 
-/// Request body for invoice withdrawal
-#[derive(Debug, Deserialize)]
-pub struct InvoiceWithdrawRequest {
-    pub invoice: String,
-}
+/// Logs per page, on the location page and in the API
+pub const LOGS_PER_PAGE: i64 = 10;
 
-/// Response for withdrawal endpoints
-#[derive(Debug, Serialize)]
-pub struct WithdrawResponse {
-    pub success: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub amount_sats: Option<i64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub redirect_url: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
+/// Whether a user moderates a location's log book: its owner and admins
+fn can_moderate_logs(location: &Location, user_id: &str, role: UserRole) -> bool {
+    (location.user_id == user_id && role.has_at_least(UserRole::Creator))
+        || role.has_at_least(UserRole::Admin)
 }
 
-impl WithdrawResponse {
-    fn success(amount_sats: i64, location_id: &str) -> Self {
-        Self {
-            success: true,
-            amount_sats: Some(amount_sats),
-            redirect_url: Some(format!(
-                "/locations/{}?success=withdrawn&amount={}",
-                location_id, amount_sats
-            )),
-            error: None,
-        }
-    }
-
-    fn error(message: impl Into<String>) -> Self {
-        Self {
-            success: false,
-            amount_sats: None,
-            redirect_url: None,
-            error: Some(message.into()),
+/// Write a log for a location
+///
+/// POST /api/locations/{location_id}/logs
+///
+/// Multipart form with `kind`, `text` and up to `MAX_LOG_PHOTOS` `photo`
+/// fields. A note needs text, the other kinds can go without.
+pub async fn create_location_log(
+    auth: AuthUser,
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let location = state
+        .db
+        .get_location(&location_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
+
+    let bad_request = |msg: &str| (StatusCode::BAD_REQUEST, msg.to_string());
+
+    let mut kind = None;
+    let mut text = String::new();
+    let mut photos = Vec::new();
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        tracing::error!("Failed to read multipart field: {}", e);
+        bad_request("Invalid form")
+    })? {
+        match field.name() {
+            Some("kind") => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|_| bad_request("Invalid form"))?;
+                kind = Some(
+                    value
+                        .parse::<LogKind>()
+                        .map_err(|_| bad_request("Invalid log kind"))?,
+                );
+            }
+            Some("text") => {
+                text = field
+                    .text()
+                    .await
+                    .map_err(|_| bad_request("Invalid form"))?
+                    .trim()
+                    .to_string();
+            }
+            Some("photo") => {
+                let data = field.bytes().await.map_err(|e| {
+                    tracing::error!("Failed to read photo data: {}", e);
+                    bad_request("Invalid photo")
+                })?;
+                // Browsers send an empty field when no file was picked
+                if !data.is_empty() {
+                    photos.push(data);
+                }
+            }
+            _ => {}
         }
     }
-}
 
-/// Helper to verify SUN message and prepare for withdrawal
-async fn verify_and_prepare_withdrawal(
-    state: &AppState,
-    location_id: &str,
-    sun_params: &SunParams,
-) -> Result<(crate::models::Location, crate::models::NfcCard, u32, i64), WithdrawResponse> {
-    // Verify the SUN message
-    let verification =
-        ntag424::verify_sun_message(&state.db, location_id, &sun_params.p, &sun_params.c)
-            .await
-            .map_err(|e| match e {
-                ntag424::SunError::ReplayDetected { .. } => WithdrawResponse::error(
-                    "This scan has already been used. Please scan the sticker again.",
-                ),
-                ntag424::SunError::CmacMismatch => {
-                    WithdrawResponse::error("Invalid NFC scan. Please scan the sticker again.")
-                }
-                ntag424::SunError::UidMismatch { .. } => {
-                    WithdrawResponse::error("Invalid NFC card for this location.")
-                }
-                ntag424::SunError::CardNotFound | ntag424::SunError::CardNotProgrammed => {
-                    WithdrawResponse::error("NFC card not configured.")
-                }
-                _ => {
-                    tracing::error!("SUN verification error: {}", e);
-                    WithdrawResponse::error("Verification failed. Please try again.")
-                }
-            })?;
+    let kind = kind.ok_or_else(|| bad_request("Log kind is required"))?;
+    if kind == LogKind::Note && text.is_empty() {
+        return Err(bad_request("A note needs some text"));
+    }
+    if text.chars().count() > MAX_LOG_TEXT_CHARS {
+        return Err(bad_request("Log text is too long"));
+    }
+    if photos.len() > MAX_LOG_PHOTOS {
+        return Err(bad_request("Too many photos"));
+    }
 
-    let location = verification.location;
-    let nfc_card = verification.nfc_card;
-    let counter = verification.counter;
+    let mut photo_paths = Vec::with_capacity(photos.len());
+    for data in &photos {
+        let filename = save_uploaded_image(&state.upload_dir, data)
+            .map_err(|status| (status, "Failed to save photo".to_string()))?;
+        photo_paths.push(filename);
+    }
 
-    // Compute the actual withdrawable balance from pool and time
-    let pool_balance_msats = state
+    let log = state
         .db
-        .get_location_donation_pool_balance(&location.id)
+        .create_location_log(&location, &auth.user_id, kind, &text, &photo_paths)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to get pool balance: {}", e);
-            WithdrawResponse::error("Failed to check balance.")
+            tracing::error!("Failed to create log: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save log".to_string(),
+            )
         })?;
 
-    let withdrawable_msats = crate::balance::compute_balance_msats(
-        pool_balance_msats,
-        location.last_withdraw_at,
-        location.created_at,
-        &state.balance_config,
+    tracing::info!(
+        "User {} logged {} at location {} ({} photos)",
+        auth.user_id,
+        log.kind,
+        location.id,
+        photo_paths.len()
     );
 
-    if withdrawable_msats <= 0 {
-        return Err(WithdrawResponse::error(
-            "No sats available at this location.",
-        ));
-    }
+    Ok(StatusCode::CREATED)
+}
 
-    Ok((location, nfc_card, counter, withdrawable_msats))
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    pub page: Option<i64>,
 }
 
-/// Record a successful withdrawal claim (called after payment succeeds)
-/// Note: Legacy withdrawal methods don't track user_id, so we pass None
-async fn record_withdrawal(state: &AppState, location_id: &str, amount_msats: i64) {
-    // Record the claim - this is best-effort, payment already succeeded
-    if let Err(e) = state.db.record_claim(location_id, amount_msats, None).await {
-        tracing::error!("Failed to record claim: {}", e);
-    }
+#[derive(Debug, Serialize)]
+pub struct LogResponse {
+    #[serde(flatten)]
+    pub log: LocationLog,
+    /// Photo URLs
+    pub photos: Vec<String>,
 }
 
-/// Withdraw via Lightning Address
+/// A page of a location's logs, newest first. Moderators also get hidden logs.
 ///
-/// POST /api/withdraw/{location_id}/ln-address?picc_data={}&cmac={}
-/// Body: { "ln_address": "user@domain.com" }
-pub async fn withdraw_ln_address(
+/// GET /api/locations/{location_id}/logs?page={page}
+pub async fn list_location_logs(
+    user: CookieUser,
     State(state): State<Arc<AppState>>,
     Path(location_id): Path<String>,
-    Query(sun_params): Query<SunParams>,
-    Json(payload): Json<LnAddressWithdrawRequest>,
-) -> Result<Json<WithdrawResponse>, StatusCode> {
-    tracing::info!(
-        "LN address withdrawal request for location {}: {}",
-        location_id,
-        payload.ln_address
-    );
-
-    // Verify SUN and get withdrawal info
-    let (location, _nfc_card, counter, withdrawable_msats) =
-        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
-            Ok(result) => result,
-            Err(response) => return Ok(Json(response)),
-        };
-
-    let withdrawable_sats = withdrawable_msats / 1000;
-
-    // Resolve LN address and get invoice (do this before claiming to avoid
-    // claiming if the LN address is invalid)
-    let invoice =
-        match lnurl::get_invoice_for_ln_address(&payload.ln_address, withdrawable_msats).await {
-            Ok(inv) => inv,
-            Err(lnurl::LnurlError::InvalidFormat(msg)) => {
-                return Ok(Json(WithdrawResponse::error(format!(
-                    "Invalid Lightning address: {}",
-                    msg
-                ))));
-            }
-            Err(lnurl::LnurlError::AmountOutOfRange { min, max, .. }) => {
-                return Ok(Json(WithdrawResponse::error(format!(
-                    "Amount {} sats is outside the allowed range ({}-{} sats)",
-                    withdrawable_sats,
-                    min / 1000,
-                    max / 1000
-                ))));
-            }
-            Err(e) => {
-                tracing::error!("LN address resolution failed: {}", e);
-                return Ok(Json(WithdrawResponse::error(
-                    "Could not resolve Lightning address. Please check and try again.",
-                )));
-            }
-        };
+    Query(query): Query<LogsQuery>,
+) -> impl IntoResponse {
+    let location = match state.db.get_location(&location_id).await {
+        Ok(Some(location)) => location,
+        Ok(None) => return (user.jar, StatusCode::NOT_FOUND).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get location: {}", e);
+            return (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+    let include_hidden = can_moderate_logs(&location, &user.user_id, user.role());
 
-    // Atomically claim the withdrawal (updates counter and zeros balance)
-    // This prevents double-spending even if the same scan is used multiple times
-    let claimed_msats = match state
+    let page = query.page.unwrap_or(1).max(1);
+    let logs = state
         .db
-        .claim_withdrawal(&location_id, counter as i64, &state.balance_config)
-        .await
-    {
-        Ok(Some(msats)) => msats,
-        Ok(None) => {
-            return Ok(Json(WithdrawResponse::error(
-                "This scan has already been used. Please scan the sticker again.",
-            )));
+        .list_location_logs(
+            &location.id,
+            include_hidden,
+            LOGS_PER_PAGE,
+            (page - 1) * LOGS_PER_PAGE,
+        )
+        .await;
+    let total = state
+        .db
+        .count_location_logs(&location.id, include_hidden)
+        .await;
+    let photos = state.db.list_location_log_photos(&location.id).await;
+
+    match (logs, total, photos) {
+        (Ok(logs), Ok(total), Ok(photos)) => {
+            let logs: Vec<LogResponse> = logs
+                .into_iter()
+                .map(|log| LogResponse {
+                    photos: photos
+                        .iter()
+                        .filter(|p| p.log_id == log.id)
+                        .map(|p| format!("{}/uploads/{}", state.base_url, p.file_path))
+                        .collect(),
+                    log,
+                })
+                .collect();
+            let body = json!({
+                "logs": logs,
+                "page": page,
+                "total_pages": (total + LOGS_PER_PAGE - 1) / LOGS_PER_PAGE,
+            });
+            (user.jar, Json(body)).into_response()
         }
-        Err(e) => {
-            tracing::error!("Failed to claim withdrawal: {}", e);
-            return Ok(Json(WithdrawResponse::error(
-                "Failed to process withdrawal. Please try again.",
-            )));
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            tracing::error!("Failed to list logs: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
         }
+    }
+}
+
+/// Load a log and its location for the moderation endpoints
+async fn get_log_with_location(
+    state: &AppState,
+    log_id: &str,
+) -> Result<(LocationLog, Location), (StatusCode, String)> {
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("Failed to get log: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
     };
+    let log = state
+        .db
+        .get_location_log(log_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Log not found".to_string()))?;
+    let location = state
+        .db
+        .get_location(&log.location_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
+    Ok((log, location))
+}
 
-    let claimed_sats = claimed_msats / 1000;
+/// Hide a log from the log book (location owner or admin)
+///
+/// POST /api/logs/{log_id}/hide
+pub async fn hide_location_log(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(log_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_location_log_hidden(&state, &auth, &log_id, true).await
+}
 
-    // Pay the invoice
-    if let Err(e) = state.lightning.pay_invoice(&invoice).await {
-        tracing::error!("Failed to pay invoice: {}", e);
-        // Note: The balance was already claimed, so the user will need to scan again.
-        // This is intentional to prevent double-spending attempts.
-        return Ok(Json(WithdrawResponse::error(
-            "Payment failed. Please scan the sticker again to retry.",
-        )));
+/// Show a hidden log again (location owner or admin)
+///
+/// POST /api/logs/{log_id}/restore
+pub async fn restore_location_log(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(log_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    set_location_log_hidden(&state, &auth, &log_id, false).await
+}
+
+async fn set_location_log_hidden(
+    state: &AppState,
+    auth: &RequireRegistered,
+    log_id: &str,
+    hidden: bool,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (log, location) = get_log_with_location(state, log_id).await?;
+    if !can_moderate_logs(&location, &auth.user_id, auth.role) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the owner can moderate this log book".to_string(),
+        ));
     }
 
-    // Record the successful withdrawal
-    record_withdrawal(&state, &location_id, claimed_msats).await;
+    state
+        .db
+        .set_location_log_hidden(&log.id, hidden.then_some(auth.user_id.as_str()))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to moderate log: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save log".to_string(),
+            )
+        })?;
 
     tracing::info!(
-        "Successful LN address withdrawal from {}: {} sats to {}",
-        location.name,
-        claimed_sats,
-        payload.ln_address
+        "User {} {} log {} at location {}",
+        auth.user_id,
+        if hidden { "hid" } else { "restored" },
+        log.id,
+        location.id
     );
 
-    Ok(Json(WithdrawResponse::success(claimed_sats, &location_id)))
+    Ok(StatusCode::OK)
 }
 
-// ============================================================================
-// LNURL-withdraw Endpoints (LUD-03)
-// ============================================================================
-
-/// LNURL-withdraw response (LUD-03)
-#[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct LnurlWithdrawResponse {
-    pub tag: String,
-    pub callback: String,
-    pub k1: String,
-    pub default_description: String,
-    pub min_withdrawable: i64,
-    pub max_withdrawable: i64,
-}
+/// Delete a log with its photos (its author or an admin)
+///
+/// DELETE /api/logs/{log_id}
+pub async fn delete_location_log(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(log_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (log, _) = get_log_with_location(&state, &log_id).await?;
+    if log.user_id != auth.user_id && !auth.has_role(UserRole::Admin) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the author can delete this log".to_string(),
+        ));
+    }
 
-/// LNURL-withdraw callback response
-#[derive(Debug, Serialize)]
-pub struct LnurlCallbackResponse {
-    pub status: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub reason: Option<String>,
-}
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("Failed to delete log: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to delete log".to_string(),
+        )
+    };
+    let photos = state.db.get_log_photos(&log.id).await.map_err(db_error)?;
+    state
+        .db
+        .delete_location_log(&log.id)
+        .await
+        .map_err(db_error)?;
 
-impl LnurlCallbackResponse {
-    fn ok() -> Self {
-        Self {
-            status: "OK".to_string(),
-            reason: None,
+    for photo in photos {
+        let file_path = state.upload_dir.join(&photo.file_path);
+        if let Err(e) = fs::remove_file(&file_path).await {
+            tracing::warn!("Failed to delete log photo {}: {}", photo.file_path, e);
         }
     }
 
-    fn error(reason: impl Into<String>) -> Self {
-        Self {
-            status: "ERROR".to_string(),
-            reason: Some(reason.into()),
-        }
-    }
+    tracing::info!("User {} deleted log {}", auth.user_id, log.id);
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
-/// LNURL-withdraw initial request (LUD-03)
-///
-/// GET /api/lnurlw/{location_id}?picc_data={}&cmac={}
+/// Mark all of the current user's notifications as read
 ///
-/// Returns the LNURL-withdraw parameters for wallet display.
-pub async fn lnurlw_request(
+/// POST /api/notifications/read
+pub async fn mark_notifications_read(
     State(state): State<Arc<AppState>>,
-    Path(location_id): Path<String>,
-    Query(sun_params): Query<SunParams>,
-) -> Result<Json<LnurlWithdrawResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
-    tracing::info!("LNURL-withdraw request for location {}", location_id);
+    auth: RequireRegistered,
+) -> Result<StatusCode, StatusCode> {
+    state
+        .db
+        .mark_notifications_read(&auth.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notifications read: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(StatusCode::OK)
+}
 
-    // Verify SUN and get withdrawal info
-    let (location, _nfc_card, _counter, withdrawable_msats) =
-        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
-            Ok(result) => result,
-            Err(response) => {
-                let error_msg = response
-                    .error
-                    .unwrap_or_else(|| "Unknown error".to_string());
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(LnurlCallbackResponse::error(error_msg)),
-                ));
-            }
-        };
+// ============================================================================
+// Leaderboard API Endpoints
+// ============================================================================
 
-    // Build callback URL with SUN params preserved
-    let callback = format!(
-        "{}/api/lnurlw/{}/callback?p={}&c={}",
-        state.base_url,
-        location_id,
-        urlencoding::encode(&sun_params.p),
-        urlencoding::encode(&sun_params.c)
-    );
+#[derive(Debug, Default, Deserialize)]
+pub struct LeaderboardQuery {
+    pub period: Option<Period>,
+    pub metric: Option<Metric>,
+    /// Region ID, global leaderboard if empty
+    pub region: Option<String>,
+    /// Rank teams instead of hunters
+    pub teams: Option<bool>,
+}
 
-    // k1 is a unique identifier for this withdraw request
-    // We use a combination of location_id and counter for uniqueness
-    let k1 = format!("{}:{}", location_id, sun_params.p);
+impl LeaderboardQuery {
+    pub fn period(&self) -> Period {
+        self.period.unwrap_or_default()
+    }
 
-    Ok(Json(LnurlWithdrawResponse {
-        tag: "withdrawRequest".to_string(),
-        callback,
-        k1,
-        default_description: format!("Withdraw from SatsHunt location: {}", location.name),
-        min_withdrawable: withdrawable_msats,
-        max_withdrawable: withdrawable_msats,
-    }))
-}
+    pub fn metric(&self) -> Metric {
+        self.metric.unwrap_or_default()
+    }
 
-/// Query parameters for LNURL-withdraw callback
-#[derive(Debug, Deserialize)]
-pub struct LnurlCallbackParams {
-    /// NTAG424 encrypted picc_data
-    pub p: String,
-    /// NTAG424 CMAC signature
-    pub c: String,
-    /// k1 from initial request (for verification)
-    pub k1: String,
-    /// BOLT11 invoice from wallet
-    pub pr: String,
+    pub fn region_id(&self) -> &str {
+        self.region.as_deref().unwrap_or_default()
+    }
+
+    pub fn teams(&self) -> bool {
+        self.teams.unwrap_or_default()
+    }
 }
 
-/// LNURL-withdraw callback (LUD-03)
-///
-/// GET /api/lnurlw/{location_id}/callback?p={}&c={}&k1={}&pr={}
+/// A cached leaderboard, refreshed every few minutes
 ///
-/// Called by wallet with the BOLT11 invoice to pay.
-pub async fn lnurlw_callback(
+/// GET /api/leaderboard?period={week|month|season}&metric={sats|locations|first_finds}&region={region_id}&teams={bool}
+pub async fn get_leaderboard(
     State(state): State<Arc<AppState>>,
-    Path(location_id): Path<String>,
-    Query(params): Query<LnurlCallbackParams>,
-) -> Result<Json<LnurlCallbackResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
-    tracing::info!("LNURL-withdraw callback for location {}", location_id);
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let period = query.period();
+    let entries = if query.teams() {
+        state
+            .db
+            .get_team_leaderboard(period, query.region_id(), query.metric())
+            .await
+            .map(|entries| json!(entries))
+    } else {
+        state
+            .db
+            .get_leaderboard(period, query.region_id(), query.metric())
+            .await
+            .map(|entries| json!(entries))
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to get leaderboard: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    let sun_params = SunParams {
-        p: params.p,
-        c: params.c,
-    };
+    Ok(Json(json!({
+        "period": period,
+        "metric": query.metric(),
+        "region_id": query.region,
+        "teams": query.teams(),
+        "since": period.start(Utc::now()),
+        "entries": entries,
+    })))
+}
 
-    // Verify SUN and get withdrawal info
-    let (location, _nfc_card, counter, _withdrawable_msats) =
-        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
-            Ok(result) => result,
-            Err(response) => {
-                let error_msg = response
-                    .error
-                    .unwrap_or_else(|| "Unknown error".to_string());
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(LnurlCallbackResponse::error(error_msg)),
-                ));
-            }
-        };
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardOptInRequest {
+    pub opt_in: bool,
+}
 
-    // Basic invoice validation
-    let invoice = params.pr.trim();
-    if !invoice.to_lowercase().starts_with("lnbc") {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(LnurlCallbackResponse::error(
-                "Invalid invoice format. Must be a valid Lightning invoice.",
-            )),
-        ));
+/// Show or hide an anonymous user on leaderboards. Registered users are
+/// always ranked.
+///
+/// POST /api/leaderboard/opt-in
+pub async fn set_leaderboard_opt_in(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Form(payload): Form<LeaderboardOptInRequest>,
+) -> impl IntoResponse {
+    if user.is_registered() {
+        return (
+            user.jar,
+            (
+                StatusCode::BAD_REQUEST,
+                "Registered users are always on the leaderboards",
+            ),
+        )
+            .into_response();
     }
 
-    // Atomically claim the withdrawal (updates counter and zeros balance)
-    // This prevents double-spending even if the same scan is used multiple times
-    let claimed_msats = match state
+    match state
         .db
-        .claim_withdrawal(&location_id, counter as i64, &state.balance_config)
+        .set_leaderboard_opt_in(&user.user_id, payload.opt_in)
         .await
     {
-        Ok(Some(msats)) => msats,
-        Ok(None) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(LnurlCallbackResponse::error(
-                    "This scan has already been used. Please scan the sticker again.",
-                )),
-            ));
+        Ok(true) => {
+            tracing::info!(
+                "User {} opted {} leaderboards",
+                user.user_id,
+                if payload.opt_in { "into" } else { "out of" }
+            );
+            (user.jar, StatusCode::OK).into_response()
         }
+        // Anonymous users are only created on their first collection
+        Ok(false) => (user.jar, (StatusCode::NOT_FOUND, "Find a location first")).into_response(),
         Err(e) => {
-            tracing::error!("Failed to claim withdrawal: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LnurlCallbackResponse::error(
-                    "Failed to process withdrawal. Please try again.",
-                )),
-            ));
+            tracing::error!("Failed to set leaderboard opt-in: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
         }
-    };
-
-    let claimed_sats = claimed_msats / 1000;
-
-    // Pay the invoice
-    if let Err(e) = state.lightning.pay_invoice(invoice).await {
-        tracing::error!("Failed to pay invoice: {}", e);
-        // Note: The balance was already claimed, so the user will need to scan again.
-        // This is intentional to prevent double-spending attempts.
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(LnurlCallbackResponse::error(
-                "Payment failed. Please scan the sticker again to retry.",
-            )),
-        ));
     }
+}
 
-    // Record the successful withdrawal
-    record_withdrawal(&state, &location_id, claimed_msats).await;
-
-    tracing::info!(
-        "Successful LNURL-withdraw from {}: {} sats",
-        location.name,
-        claimed_sats
-    );
-
-    Ok(Json(LnurlCallbackResponse::ok()))
+#[derive(Debug, Deserialize)]
+pub struct CreateRegionRequest {
+    pub name: String,
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub min_longitude: f64,
+    pub max_longitude: f64,
 }
 
-/// Withdraw via pasted BOLT11 invoice
+/// Add a region for regional leaderboards (admin only). It gets its
+/// leaderboards on the next refresh.
 ///
-/// POST /api/withdraw/{location_id}/invoice?picc_data={}&cmac={}
-/// Body: { "invoice": "lnbc..." }
-pub async fn withdraw_invoice(
+/// POST /api/admin/regions
+pub async fn create_region(
     State(state): State<Arc<AppState>>,
-    Path(location_id): Path<String>,
-    Query(sun_params): Query<SunParams>,
-    Json(payload): Json<InvoiceWithdrawRequest>,
-) -> Result<Json<WithdrawResponse>, StatusCode> {
-    tracing::info!("Invoice withdrawal request for location {}", location_id);
-
-    // Verify SUN and get withdrawal info
-    let (location, _nfc_card, counter, _withdrawable_msats) =
-        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
-            Ok(result) => result,
-            Err(response) => return Ok(Json(response)),
-        };
+    auth: RequireRegistered,
+    Form(payload): Form<CreateRegionRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| (StatusCode::FORBIDDEN, "Admins only".to_string()))?;
 
-    // Basic invoice validation
-    let invoice = payload.invoice.trim();
-    if !invoice.to_lowercase().starts_with("lnbc") {
-        return Ok(Json(WithdrawResponse::error(
-            "Invalid invoice format. Must be a valid Lightning invoice.",
-        )));
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Name is required".to_string()));
+    }
+    let valid_bounds = (-90.0..=90.0).contains(&payload.min_latitude)
+        && (-90.0..=90.0).contains(&payload.max_latitude)
+        && (-180.0..=180.0).contains(&payload.min_longitude)
+        && (-180.0..=180.0).contains(&payload.max_longitude)
+        && payload.min_latitude < payload.max_latitude
+        && payload.min_longitude < payload.max_longitude;
+    if !valid_bounds {
+        return Err((StatusCode::BAD_REQUEST, "Invalid bounds".to_string()));
     }
 
-    // Atomically claim the withdrawal (updates counter and zeros balance)
-    // This prevents double-spending even if the same scan is used multiple times
-    let claimed_msats = match state
+    let region = state
         .db
-        .claim_withdrawal(&location_id, counter as i64, &state.balance_config)
+        .create_region(
+            name,
+            payload.min_latitude,
+            payload.max_latitude,
+            payload.min_longitude,
+            payload.max_longitude,
+        )
         .await
-    {
-        Ok(Some(msats)) => msats,
-        Ok(None) => {
-            return Ok(Json(WithdrawResponse::error(
-                "This scan has already been used. Please scan the sticker again.",
-            )));
-        }
-        Err(e) => {
-            tracing::error!("Failed to claim withdrawal: {}", e);
-            return Ok(Json(WithdrawResponse::error(
-                "Failed to process withdrawal. Please try again.",
-            )));
-        }
-    };
+        .map_err(|e| {
+            tracing::error!("Failed to create region: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create region".to_string(),
+            )
+        })?;
 
-    let claimed_sats = claimed_msats / 1000;
+    tracing::info!("Admin {} created region {}", auth.user_id, region.name);
 
-    // Pay the invoice
-    if let Err(e) = state.lightning.pay_invoice(invoice).await {
-        tracing::error!("Failed to pay invoice: {}", e);
-        // Note: The balance was already claimed, so the user will need to scan again.
-        // This is intentional to prevent double-spending attempts.
-        return Ok(Json(WithdrawResponse::error(
-            "Payment failed. Please scan the sticker again to retry.",
-        )));
-    }
+    Ok(StatusCode::CREATED)
+}
+
+/// Remove a region and its leaderboards (admin only)
+///
+/// DELETE /api/admin/regions/{region_id}
+pub async fn delete_region(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(region_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| StatusCode::FORBIDDEN)?;
 
-    // Record the successful withdrawal
-    record_withdrawal(&state, &location_id, claimed_msats).await;
+    let deleted = state.db.delete_region(&region_id).await.map_err(|e| {
+        tracing::error!("Failed to delete region: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
 
-    tracing::info!(
-        "Successful invoice withdrawal from {}: {} sats",
-        location.name,
-        claimed_sats
-    );
+    tracing::info!("Admin {} deleted region {}", auth.user_id, region_id);
 
-    Ok(Json(WithdrawResponse::success(claimed_sats, &location_id)))
+    Ok(StatusCode::OK)
 }
 
 // ============================================================================
-// Custodial Wallet Collection Endpoint
+// Team API Endpoints
 // ============================================================================
 
-/// Response for collection endpoint
-#[derive(Debug, Serialize)]
-pub struct CollectResponse {
-    pub success: bool,
-    pub collected_sats: i64,
-    pub new_balance_sats: i64,
-    pub location_name: String,
-    /// User ID to store in localStorage as backup
-    pub user_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
-}
+/// Longest team name, in characters
+const MAX_TEAM_NAME_LENGTH: usize = 40;
 
-impl CollectResponse {
-    fn success(
-        collected_sats: i64,
-        new_balance_sats: i64,
-        location_name: String,
-        user_id: String,
-    ) -> Self {
-        Self {
-            success: true,
-            collected_sats,
-            new_balance_sats,
-            location_name,
-            user_id,
-            error: None,
-        }
+/// The user's membership of `team_id`, or FORBIDDEN if they aren't in it
+async fn require_team_member(
+    state: &AppState,
+    user_id: &str,
+    team_id: &str,
+) -> Result<TeamMember, (StatusCode, String)> {
+    let membership = state.db.get_team_membership(user_id).await.map_err(|e| {
+        tracing::error!("Failed to get team membership: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+    match membership {
+        Some(member) if member.team_id == team_id => Ok(member),
+        _ => Err((
+            StatusCode::FORBIDDEN,
+            "Not a member of this team".to_string(),
+        )),
     }
+}
 
-    fn error(message: impl Into<String>) -> Self {
-        Self {
-            success: false,
-            collected_sats: 0,
-            new_balance_sats: 0,
-            location_name: String::new(),
-            user_id: String::new(),
-            error: Some(message.into()),
-        }
+/// Like `require_team_member`, but also FORBIDDEN for members who can't
+/// manage the team
+async fn require_team_owner(
+    state: &AppState,
+    user_id: &str,
+    team_id: &str,
+) -> Result<TeamMember, (StatusCode, String)> {
+    let member = require_team_member(state, user_id, team_id).await?;
+    if !member.role.can_manage() {
+        return Err((StatusCode::FORBIDDEN, "Team owners only".to_string()));
     }
+    Ok(member)
 }
 
-/// Collect sats from a location into user's custodial balance
-///
-/// POST /api/collect/{location_id}?p={picc_data}&c={cmac}
+/// Number of owners among a team's members
+async fn count_team_owners(state: &AppState, team_id: &str) -> Result<usize, (StatusCode, String)> {
+    let members = state.db.list_team_members(team_id).await.map_err(|e| {
+        tracing::error!("Failed to list team members: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+    Ok(members.iter().filter(|m| m.role == TeamRole::Owner).count())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTeamRequest {
+    pub name: String,
+}
+
+/// Create a team with the current user as its owner
 ///
-/// This is the primary endpoint for the custodial wallet system.
-/// Users collect sats into their balance instead of receiving immediate Lightning payments.
-pub async fn collect_sats(
+/// POST /api/teams
+pub async fn create_team(
     State(state): State<Arc<AppState>>,
-    Path(location_id): Path<String>,
-    Query(sun_params): Query<SunParams>,
-    user: CookieUser,
-) -> impl IntoResponse {
-    tracing::info!(
-        "Collection request for location {} by user {} (kind: {:?})",
-        location_id,
-        user.user_id,
-        user.kind
-    );
+    auth: RequireRegistered,
+    Form(payload): Form<CreateTeamRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let name = payload.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_TEAM_NAME_LENGTH {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Name must be 1 to {} characters", MAX_TEAM_NAME_LENGTH),
+        ));
+    }
 
-    // Helper to build error response with cookie jar
-    let error_response = |jar: PrivateCookieJar, status: StatusCode, msg: &str| {
-        (jar, (status, Json(CollectResponse::error(msg)))).into_response()
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("Failed to create team: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create team".to_string(),
+        )
     };
+    if state
+        .db
+        .get_team_membership(&auth.user_id)
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            "Leave your current team first".to_string(),
+        ));
+    }
+    if state
+        .db
+        .get_team_by_name(name)
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err((StatusCode::CONFLICT, "Team name is taken".to_string()));
+    }
 
-    // Verify the SUN message
-    let verification =
-        match ntag424::verify_sun_message(&state.db, &location_id, &sun_params.p, &sun_params.c)
-            .await
-        {
-            Ok(v) => v,
-            Err(e) => {
-                let msg = match e {
-                    ntag424::SunError::ReplayDetected { .. } => {
-                        "This scan has already been used. Please scan the sticker again."
-                    }
-                    ntag424::SunError::CmacMismatch => {
-                        "Invalid NFC scan. Please scan the sticker again."
-                    }
-                    ntag424::SunError::UidMismatch { .. } => "Invalid NFC card for this location.",
-                    ntag424::SunError::CardNotFound | ntag424::SunError::CardNotProgrammed => {
-                        "NFC card not configured."
-                    }
-                    _ => {
-                        tracing::error!("SUN verification error: {}", e);
-                        "Verification failed. Please try again."
-                    }
-                };
-                return error_response(user.jar, StatusCode::BAD_REQUEST, msg);
-            }
-        };
+    let team = state
+        .db
+        .create_team(name, &auth.user_id)
+        .await
+        .map_err(db_error)?;
 
-    let location = verification.location;
-    let counter = verification.counter;
+    tracing::info!("User {} created team {}", auth.user_id, team.name);
 
-    // Atomically claim collection (creates user if needed, updates counter, computes balance)
-    let collected_msats = match state
-        .db
-        .claim_collection(
-            &location_id,
-            &user.user_id,
-            counter as i64,
-            &state.balance_config,
+    Ok(StatusCode::CREATED)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JoinTeamRequest {
+    pub invite_code: String,
+}
+
+/// Join the team of an invite link
+///
+/// POST /api/teams/join
+pub async fn join_team(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Form(payload): Form<JoinTeamRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("Failed to join team: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to join team".to_string(),
         )
-        .await
-    {
-        Ok(Some(msats)) => msats,
-        Ok(None) => {
-            tracing::warn!("Collection already claimed or no balance");
-            return error_response(
-                user.jar,
-                StatusCode::CONFLICT,
-                "This scan has already been used. Please scan the sticker again.",
-            );
-        }
-        Err(e) => {
-            tracing::error!("Collection failed: {}", e);
-            return error_response(
-                user.jar,
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to process collection. Please try again.",
-            );
-        }
     };
+    let team = state
+        .db
+        .get_team_by_invite_code(payload.invite_code.trim())
+        .await
+        .map_err(db_error)?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            "This invite link is no longer valid".to_string(),
+        ))?;
 
-    let collected_sats = collected_msats / 1000;
+    if state
+        .db
+        .get_team_membership(&auth.user_id)
+        .await
+        .map_err(db_error)?
+        .is_some()
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            "Leave your current team first".to_string(),
+        ));
+    }
 
-    // Get new balance
-    let new_balance_msats = state
+    state
         .db
-        .get_user_balance(&user.user_id)
+        .join_team(&team.id, &auth.user_id)
         .await
-        .unwrap_or(collected_msats);
-    let new_balance_sats = new_balance_msats / 1000;
+        .map_err(db_error)?;
 
-    tracing::info!(
-        "User {} collected {} sats from {}, new balance: {} sats",
-        user.user_id,
-        collected_sats,
-        location.name,
-        new_balance_sats
-    );
+    tracing::info!("User {} joined team {}", auth.user_id, team.name);
 
-    // Return response with the cookie jar (handles setting new cookie if needed)
-    (
-        user.jar,
-        Json(CollectResponse::success(
-            collected_sats,
-            new_balance_sats,
-            location.name,
-            user.user_id,
-        )),
-    )
-        .into_response()
+    Ok(StatusCode::OK)
 }
 
-// ============================================================================
-// Claim Endpoint (new separate scan/claim flow)
-// ============================================================================
-
-/// Claim sats from a previous scan.
-///
-/// POST /api/claim/{scan_id}
+/// Leave a team. The last owner has to hand over the team or delete it.
 ///
-/// The user must be the one who made the scan, and within 1 hour.
-pub async fn claim_sats(
+/// POST /api/teams/{team_id}/leave
+pub async fn leave_team(
     State(state): State<Arc<AppState>>,
-    Path(scan_id): Path<String>,
-    user: CookieUser,
-) -> impl IntoResponse {
-    tracing::info!(
-        "Claim request for scan {} by user {}",
-        scan_id,
-        user.user_id
-    );
+    auth: RequireRegistered,
+    Path(team_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let member = require_team_member(&state, &auth.user_id, &team_id).await?;
+    if member.role == TeamRole::Owner && count_team_owners(&state, &team_id).await? == 1 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Make another member owner first, or delete the team".to_string(),
+        ));
+    }
 
-    let result = state
+    state
         .db
-        .claim_from_scan(&scan_id, &user.user_id, &state.balance_config)
-        .await;
+        .remove_team_member(&team_id, &auth.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to leave team: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to leave team".to_string(),
+            )
+        })?;
 
-    match result {
-        Ok(ClaimResult::Success { msats, claim_id }) => {
-            let collected_sats = msats / 1000;
+    tracing::info!("User {} left team {}", auth.user_id, team_id);
 
-            // Get new balance
-            let new_balance_msats = state
-                .db
-                .get_user_balance(&user.user_id)
-                .await
-                .unwrap_or(msats);
-            let new_balance_sats = new_balance_msats / 1000;
+    Ok(StatusCode::OK)
+}
 
-            tracing::info!(
-                "User {} claimed {} sats (claim_id: {}), new balance: {} sats",
-                user.user_id,
-                collected_sats,
-                claim_id,
-                new_balance_sats
-            );
+#[derive(Debug, Deserialize)]
+pub struct TeamRoleRequest {
+    pub role: TeamRole,
+}
 
+/// Change a member's role (team owners only)
+///
+/// POST /api/teams/{team_id}/members/{user_id}/role
+pub async fn set_team_member_role(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path((team_id, user_id)): Path<(String, String)>,
+    Form(payload): Form<TeamRoleRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_team_owner(&state, &auth.user_id, &team_id).await?;
+    let member = require_team_member(&state, &user_id, &team_id)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Member not found".to_string()))?;
+
+    if member.role == TeamRole::Owner
+        && payload.role != TeamRole::Owner
+        && count_team_owners(&state, &team_id).await? == 1
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "A team needs at least one owner".to_string(),
+        ));
+    }
+
+    state
+        .db
+        .set_team_member_role(&team_id, &user_id, payload.role)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set team role: {}", e);
             (
-                user.jar,
-                Json(CollectResponse::success(
-                    collected_sats,
-                    new_balance_sats,
-                    "this location".to_string(),
-                    user.user_id,
-                )),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to set role".to_string(),
             )
-                .into_response()
-        }
-        Ok(ClaimResult::ScanNotFound) => (
-            user.jar,
-            (
-                StatusCode::NOT_FOUND,
-                Json(CollectResponse::error("Scan not found.")),
-            ),
-        )
-            .into_response(),
-        Ok(ClaimResult::NotYourScan) => (
-            user.jar,
-            (
-                StatusCode::FORBIDDEN,
-                Json(CollectResponse::error("This scan belongs to someone else.")),
-            ),
-        )
-            .into_response(),
-        Ok(ClaimResult::AlreadyClaimed) => (
-            user.jar,
-            (
-                StatusCode::CONFLICT,
-                Json(CollectResponse::error(
-                    "This scan has already been claimed.",
-                )),
-            ),
-        )
-            .into_response(),
-        Ok(ClaimResult::Expired) => (
-            user.jar,
+        })?;
+
+    tracing::info!(
+        "User {} made {} {} of team {}",
+        auth.user_id,
+        user_id,
+        payload.role,
+        team_id
+    );
+
+    Ok(StatusCode::OK)
+}
+
+/// Remove a member from a team (team owners only). Owners leave instead.
+///
+/// DELETE /api/teams/{team_id}/members/{user_id}
+pub async fn remove_team_member(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path((team_id, user_id)): Path<(String, String)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_team_owner(&state, &auth.user_id, &team_id).await?;
+    let member = require_team_member(&state, &user_id, &team_id)
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "Member not found".to_string()))?;
+    if member.role == TeamRole::Owner {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Owners can't be removed".to_string(),
+        ));
+    }
+
+    state
+        .db
+        .remove_team_member(&team_id, &user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to remove team member: {}", e);
             (
-                StatusCode::GONE,
-                Json(CollectResponse::error(
-                    "This scan has expired. Please scan again.",
-                )),
-            ),
-        )
-            .into_response(),
-        Ok(ClaimResult::NotLastScanner) => (
-            user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to remove member".to_string(),
+            )
+        })?;
+
+    tracing::info!(
+        "User {} removed {} from team {}",
+        auth.user_id,
+        user_id,
+        team_id
+    );
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeamWalletRequest {
+    pub enabled: bool,
+}
+
+/// Turn the team wallet on or off (team owners only). While it's on,
+/// members' claims are collected into the team wallet.
+///
+/// POST /api/teams/{team_id}/wallet
+pub async fn set_team_wallet(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(team_id): Path<String>,
+    Form(payload): Form<TeamWalletRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_team_owner(&state, &auth.user_id, &team_id).await?;
+
+    state
+        .db
+        .set_team_wallet_enabled(&team_id, payload.enabled)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set team wallet: {}", e);
             (
-                StatusCode::CONFLICT,
-                Json(CollectResponse::error(
-                    "Someone else scanned after you. Please scan again.",
-                )),
-            ),
-        )
-            .into_response(),
-        Ok(ClaimResult::NoBalance) => (
-            user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update team wallet".to_string(),
+            )
+        })?;
+
+    tracing::info!(
+        "User {} turned the wallet of team {} {}",
+        auth.user_id,
+        team_id,
+        if payload.enabled { "on" } else { "off" }
+    );
+
+    Ok(StatusCode::OK)
+}
+
+/// Replace the invite link of a team (team owners only)
+///
+/// POST /api/teams/{team_id}/invite
+pub async fn reset_team_invite(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(team_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_team_owner(&state, &auth.user_id, &team_id).await?;
+
+    state
+        .db
+        .reset_team_invite_code(&team_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to reset team invite code: {}", e);
             (
-                StatusCode::BAD_REQUEST,
-                Json(CollectResponse::error(
-                    "No sats available at this location.",
-                )),
-            ),
-        )
-            .into_response(),
-        Err(e) => {
-            tracing::error!("Claim failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to reset invite link".to_string(),
+            )
+        })?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeamWithdrawRequest {
+    pub amount_sats: i64,
+}
+
+/// Move sats from the team wallet into the current user's wallet (team
+/// owners and treasurers only)
+///
+/// POST /api/teams/{team_id}/withdraw
+pub async fn team_withdraw(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(team_id): Path<String>,
+    Form(payload): Form<TeamWithdrawRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let member = require_team_member(&state, &auth.user_id, &team_id).await?;
+    if !member.role.can_withdraw() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only owners and treasurers can withdraw".to_string(),
+        ));
+    }
+    let msats = payload
+        .amount_sats
+        .checked_mul(1000)
+        .filter(|msats| *msats > 0)
+        .ok_or((
+            StatusCode::BAD_REQUEST,
+            "Amount must be positive".to_string(),
+        ))?;
+
+    let withdrawn = state
+        .db
+        .withdraw_from_team_wallet(&team_id, &auth.user_id, msats)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to withdraw from team wallet: {}", e);
             (
-                user.jar,
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(CollectResponse::error(
-                        "Failed to process claim. Please try again.",
-                    )),
-                ),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to withdraw".to_string(),
             )
-                .into_response()
-        }
+        })?;
+    if !withdrawn {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Insufficient team balance".to_string(),
+        ));
+    }
+
+    tracing::info!(
+        "User {} withdrew {} sats from team {}",
+        auth.user_id,
+        payload.amount_sats,
+        team_id
+    );
+
+    Ok(StatusCode::OK)
+}
+
+/// Delete a team (team owners only). The team wallet has to be empty.
+///
+/// DELETE /api/teams/{team_id}
+pub async fn delete_team(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(team_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_team_owner(&state, &auth.user_id, &team_id).await?;
+
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("Failed to delete team: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to delete team".to_string(),
+        )
+    };
+    if state
+        .db
+        .get_team_balance(&team_id)
+        .await
+        .map_err(db_error)?
+        != 0
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Withdraw the team wallet first".to_string(),
+        ));
     }
+    state.db.delete_team(&team_id).await.map_err(db_error)?;
+
+    tracing::info!("User {} deleted team {}", auth.user_id, team_id);
+
+    Ok(StatusCode::OK)
 }
 
 // ============================================================================
-// Custodial Wallet Withdrawal Endpoint
+// Withdrawal API Endpoints
 // ============================================================================
 
-/// Request body for wallet withdrawal
+/// Query parameters for SUN message verification
 #[derive(Debug, Deserialize)]
-pub struct WalletWithdrawRequest {
+pub struct SunParams {
+    /// NTAG424 encrypted picc_data (parameter name: p)
+    #[serde(alias = "picc_data")]
+    pub p: String,
+    /// NTAG424 CMAC signature (parameter name: c)
+    #[serde(alias = "cmac")]
+    pub c: String,
+}
+
+/// Request body for LN address withdrawal
+#[derive(Debug, Deserialize)]
+pub struct LnAddressWithdrawRequest {
     pub ln_address: String,
 }
 
-/// Response for wallet withdrawal endpoint
+/// Request body for invoice withdrawal
+#[derive(Debug, Deserialize)]
+pub struct InvoiceWithdrawRequest {
+    pub invoice: String,
+}
+
+/// Response for withdrawal endpoints
 #[derive(Debug, Serialize)]
-pub struct WalletWithdrawResponse {
+pub struct WithdrawResponse {
     pub success: bool,
-    pub withdrawn_sats: i64,
-    pub new_balance_sats: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_sats: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
-impl WalletWithdrawResponse {
-    fn success(withdrawn_sats: i64, new_balance_sats: i64) -> Self {
+impl WithdrawResponse {
+    fn success(amount_sats: i64, location_id: &str) -> Self {
         Self {
             success: true,
-            withdrawn_sats,
-            new_balance_sats,
+            amount_sats: Some(amount_sats),
+            redirect_url: Some(format!(
+                "/locations/{}?success=withdrawn&amount={}",
+                location_id, amount_sats
+            )),
             error: None,
         }
     }
@@ -1799,586 +2422,3260 @@ impl WalletWithdrawResponse {
     fn error(message: impl Into<String>) -> Self {
         Self {
             success: false,
-            withdrawn_sats: 0,
-            new_balance_sats: 0,
+            amount_sats: None,
+            redirect_url: None,
             error: Some(message.into()),
         }
     }
 }
 
-/// Withdraw sats from user's custodial balance via Lightning Address
-///
-/// POST /api/wallet/withdraw
-/// Body: { "ln_address": "user@domain.com" }
-///
-/// Withdraws the user's entire balance to the specified Lightning Address.
-pub async fn wallet_withdraw(
-    State(state): State<Arc<AppState>>,
-    user: CookieUser,
-    Json(payload): Json<WalletWithdrawRequest>,
-) -> impl IntoResponse {
-    tracing::info!(
-        "Wallet withdrawal request from user {} to {}",
-        user.user_id,
-        payload.ln_address
-    );
-
-    // Helper to build error response with cookie jar
-    let error_response = |jar: PrivateCookieJar, status: StatusCode, msg: &str| {
-        (jar, (status, Json(WalletWithdrawResponse::error(msg)))).into_response()
-    };
+/// Helper to verify SUN message and prepare for withdrawal
+async fn verify_and_prepare_withdrawal(
+    state: &AppState,
+    location_id: &str,
+    sun_params: &SunParams,
+) -> Result<(crate::models::Location, crate::models::NfcCard, u32, i64), WithdrawResponse> {
+    // Verify the SUN message
+    let verification =
+        ntag424::verify_sun_message(&state.db, location_id, &sun_params.p, &sun_params.c)
+            .await
+            .map_err(|e| match e {
+                ntag424::SunError::ReplayDetected { .. } => WithdrawResponse::error(
+                    "This scan has already been used. Please scan the sticker again.",
+                ),
+                ntag424::SunError::CmacMismatch => {
+                    WithdrawResponse::error("Invalid NFC scan. Please scan the sticker again.")
+                }
+                ntag424::SunError::UidMismatch { .. } => {
+                    WithdrawResponse::error("Invalid NFC card for this location.")
+                }
+                ntag424::SunError::CardNotFound | ntag424::SunError::CardNotProgrammed => {
+                    WithdrawResponse::error("NFC card not configured.")
+                }
+                _ => {
+                    tracing::error!("SUN verification error: {}", e);
+                    WithdrawResponse::error("Verification failed. Please try again.")
+                }
+            })?;
 
-    // Get user balance
-    let balance_msats = match state.db.get_user_balance(&user.user_id).await {
-        Ok(balance) => balance,
+    let location = verification.location;
+    let nfc_card = verification.nfc_card;
+    let counter = verification.counter;
+
+    // Compute the actual withdrawable balance from pool and time
+    let pool_balance_msats = state
+        .db
+        .get_location_donation_pool_balance(&location.id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get pool balance: {}", e);
+            WithdrawResponse::error("Failed to check balance.")
+        })?;
+
+    let balance_msats = crate::balance::compute_balance_msats(
+        pool_balance_msats,
+        location.last_withdraw_at,
+        location.created_at,
+        &state.balance_config,
+    );
+
+    // The location's claim policy decides what share of it the withdrawal gets
+    let fraction = match state.db.get_withdrawal_share(&location).await {
+        Ok(Ok(fraction)) => fraction,
+        Ok(Err(result)) => return Err(WithdrawResponse::error(withdraw_refused_message(&result))),
         Err(e) => {
-            tracing::error!("Failed to get user balance: {}", e);
-            return error_response(
-                user.jar,
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to get balance. Please try again.",
-            );
+            tracing::error!("Failed to get withdrawal share: {}", e);
+            return Err(WithdrawResponse::error("Failed to check balance."));
         }
     };
+    let withdrawable_msats = (balance_msats as f64 * fraction).round() as i64;
 
-    // Calculate withdrawable amount after fees
-    let (max_withdraw_msats, fee_msats) = calculate_withdrawal_fees(balance_msats);
-
-    // Check minimum withdrawal amount (need enough to cover fees + at least 1 sat)
-    if max_withdraw_msats < 1000 {
-        return error_response(
-            user.jar,
-            StatusCode::BAD_REQUEST,
-            "Insufficient balance to cover fees. You need at least ~3 sats to withdraw.",
-        );
+    if withdrawable_msats <= 0 {
+        return Err(WithdrawResponse::error(
+            "No sats available at this location.",
+        ));
     }
 
-    // Round down to whole sats for the invoice
-    let withdraw_sats = max_withdraw_msats / 1000;
-    let withdraw_msats = withdraw_sats * 1000;
+    Ok((location, nfc_card, counter, withdrawable_msats))
+}
 
-    // Resolve LN address and get invoice (do this before reserving balance)
-    let invoice = match lnurl::get_invoice_for_ln_address(&payload.ln_address, withdraw_msats).await
-    {
-        Ok(inv) => inv,
-        Err(lnurl::LnurlError::InvalidFormat(msg)) => {
-            return error_response(
-                user.jar,
-                StatusCode::BAD_REQUEST,
-                &format!("Invalid Lightning address: {}", msg),
-            );
+/// Shown when a location is claimed outside its activation schedule
+const OUTSIDE_SCHEDULE_MESSAGE: &str =
+    "This location only pays out during its event. Check its page for the next window.";
+
+/// Shown when a location that isn't active is claimed
+const LOCATION_INACTIVE_MESSAGE: &str = "This location isn't active.";
+
+/// Explanation for a withdrawal `claim_withdrawal` refused
+fn withdraw_refused_message(result: &ClaimResult) -> String {
+    match result {
+        ClaimResult::OutsideSchedule => OUTSIDE_SCHEDULE_MESSAGE.to_string(),
+        ClaimResult::LocationInactive => LOCATION_INACTIVE_MESSAGE.to_string(),
+        ClaimResult::NoBalance => "No sats available at this location.".to_string(),
+        ClaimResult::OverClaimable { claimable_msats } => format!(
+            "You can withdraw at most {} sats from this location.",
+            claimable_msats / 1000
+        ),
+        ClaimResult::NotFirstScanner => {
+            "Someone else scanned first. Please try again after they collected.".to_string()
         }
-        Err(lnurl::LnurlError::AmountOutOfRange { min, max, .. }) => {
-            return error_response(
-                user.jar,
-                StatusCode::BAD_REQUEST,
-                &format!(
+        ClaimResult::Cooldown { until } => format!(
+            "Someone withdrew from this sticker recently. You can withdraw again from {}.",
+            until.format("%Y-%m-%d %H:%M UTC")
+        ),
+        ClaimResult::MonthlyLimitReached { limit, until } => format!(
+            "This sticker reached its limit of {} withdrawals in 30 days. You can withdraw again from {}.",
+            limit,
+            until.format("%Y-%m-%d %H:%M UTC")
+        ),
+        _ => "This scan has already been used. Please scan the sticker again.".to_string(),
+    }
+}
+
+/// Withdraw via Lightning Address
+///
+/// POST /api/withdraw/{location_id}/ln-address?picc_data={}&cmac={}
+/// Body: { "ln_address": "user@domain.com" }
+pub async fn withdraw_ln_address(
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    Query(sun_params): Query<SunParams>,
+    Json(payload): Json<LnAddressWithdrawRequest>,
+) -> Result<Json<WithdrawResponse>, StatusCode> {
+    tracing::info!(
+        "LN address withdrawal request for location {}: {}",
+        location_id,
+        payload.ln_address
+    );
+
+    // Verify SUN and get withdrawal info
+    let (location, _nfc_card, counter, withdrawable_msats) =
+        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
+            Ok(result) => result,
+            Err(response) => return Ok(Json(response)),
+        };
+
+    let withdrawable_sats = withdrawable_msats / 1000;
+
+    // Resolve LN address and get invoice (do this before claiming to avoid
+    // claiming if the LN address is invalid)
+    let invoice =
+        match lnurl::get_invoice_for_ln_address(&payload.ln_address, withdrawable_msats).await {
+            Ok(inv) => inv,
+            Err(lnurl::LnurlError::InvalidFormat(msg)) => {
+                return Ok(Json(WithdrawResponse::error(format!(
+                    "Invalid Lightning address: {}",
+                    msg
+                ))));
+            }
+            Err(lnurl::LnurlError::AmountOutOfRange { min, max, .. }) => {
+                return Ok(Json(WithdrawResponse::error(format!(
                     "Amount {} sats is outside the allowed range ({}-{} sats)",
-                    withdraw_sats,
+                    withdrawable_sats,
                     min / 1000,
                     max / 1000
-                ),
-            );
-        }
-        Err(e) => {
-            tracing::error!("LN address resolution failed: {}", e);
-            return error_response(
-                user.jar,
-                StatusCode::BAD_REQUEST,
-                "Could not resolve Lightning address. Please check and try again.",
-            );
-        }
+                ))));
+            }
+            Err(e) => {
+                tracing::error!("LN address resolution failed: {}", e);
+                return Ok(Json(WithdrawResponse::error(
+                    "Could not resolve Lightning address. Please check and try again.",
+                )));
+            }
+        };
+
+    // Check the invoice before claiming, so a refused invoice doesn't use up the scan
+    let checked = match check_invoice_policy(&state, &invoice).await {
+        Ok(checked) => checked,
+        Err((_, msg)) => return Ok(Json(WithdrawResponse::error(msg))),
     };
 
-    // Create pending withdrawal to reserve the balance (including fees)
-    let withdrawal_id = match state
+    // Atomically claim the invoice amount (updates counter and takes it from the balance)
+    // This prevents double-spending even if the same scan is used multiple times
+    let claimed_msats = match state
         .db
-        .create_pending_withdrawal(&user.user_id, withdraw_msats, fee_msats, &invoice)
+        .claim_withdrawal(
+            &location_id,
+            counter as i64,
+            checked.amount_msats,
+            &state.balance_config,
+            &state.claim_rules,
+        )
         .await
     {
-        Ok(Some(id)) => id,
-        Ok(None) => {
-            return error_response(
-                user.jar,
-                StatusCode::CONFLICT,
-                "Insufficient balance. Please try again.",
-            );
+        Ok(ClaimResult::Success { msats, .. }) => msats,
+        Ok(result) => {
+            return Ok(Json(WithdrawResponse::error(withdraw_refused_message(
+                &result,
+            ))));
         }
         Err(e) => {
-            tracing::error!("Failed to create pending withdrawal: {}", e);
-            return error_response(
-                user.jar,
-                StatusCode::INTERNAL_SERVER_ERROR,
+            tracing::error!("Failed to claim withdrawal: {}", e);
+            return Ok(Json(WithdrawResponse::error(
                 "Failed to process withdrawal. Please try again.",
-            );
+            )));
         }
     };
 
-    let withdrawn_sats = withdraw_msats / 1000;
+    let claimed_sats = claimed_msats / 1000;
+
+    // Pay the invoice
+    if let Err(e) = state.lightning.pay_invoice(&invoice).await {
+        tracing::error!("Failed to pay invoice: {}", e);
+        // Note: The balance was already claimed, so the user will need to scan again.
+        // This is intentional to prevent double-spending attempts.
+        return Ok(Json(WithdrawResponse::error(
+            "Payment failed. Please scan the sticker again to retry.",
+        )));
+    }
+
+    tracing::info!(
+        "Successful LN address withdrawal from {}: {} sats to {}",
+        location.name,
+        claimed_sats,
+        payload.ln_address
+    );
+
+    Ok(Json(WithdrawResponse::success(claimed_sats, &location_id)))
+}
+
+// ============================================================================
+// LNURL-withdraw Endpoints (LUD-03)
+// ============================================================================
+
+/// LNURL-withdraw response (LUD-03)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LnurlWithdrawResponse {
+    pub tag: String,
+    pub callback: String,
+    pub k1: String,
+    pub default_description: String,
+    pub min_withdrawable: i64,
+    pub max_withdrawable: i64,
+    /// URL wallets can poll for the current withdrawable amount (LUD-14)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance_check: Option<String>,
+    /// LNURL-pay link for topping up what's being withdrawn from (LUD-19)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pay_link: Option<String>,
+}
+
+/// LNURL-withdraw callback response
+#[derive(Debug, Serialize)]
+pub struct LnurlCallbackResponse {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl LnurlCallbackResponse {
+    fn ok() -> Self {
+        Self {
+            status: "OK".to_string(),
+            reason: None,
+        }
+    }
+
+    fn error(reason: impl Into<String>) -> Self {
+        Self {
+            status: "ERROR".to_string(),
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// LNURL-withdraw initial request (LUD-03)
+///
+/// GET /api/lnurlw/{location_id}?picc_data={}&cmac={}
+///
+/// Returns the LNURL-withdraw parameters for wallet display.
+pub async fn lnurlw_request(
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    Query(sun_params): Query<SunParams>,
+) -> Result<Json<LnurlWithdrawResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
+    tracing::info!("LNURL-withdraw request for location {}", location_id);
+
+    // Verify SUN and get withdrawal info
+    let (location, _nfc_card, _counter, withdrawable_msats) =
+        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
+            Ok(result) => result,
+            Err(response) => {
+                let error_msg = response
+                    .error
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(LnurlCallbackResponse::error(error_msg)),
+                ));
+            }
+        };
+
+    // Build callback URL with SUN params preserved
+    let callback = format!(
+        "{}/api/lnurlw/{}/callback?p={}&c={}",
+        state.base_url,
+        location_id,
+        urlencoding::encode(&sun_params.p),
+        urlencoding::encode(&sun_params.c)
+    );
+
+    // k1 is a unique identifier for this withdraw request
+    // We use a combination of location_id and counter for uniqueness
+    let k1 = format!("{}:{}", location_id, sun_params.p);
+
+    Ok(Json(LnurlWithdrawResponse {
+        tag: "withdrawRequest".to_string(),
+        callback,
+        k1,
+        default_description: format!("Withdraw from SatsHunt location: {}", location.name),
+        min_withdrawable: withdrawable_msats,
+        max_withdrawable: withdrawable_msats,
+        balance_check: None,
+        pay_link: None,
+    }))
+}
+
+/// Query parameters for LNURL-withdraw callback
+#[derive(Debug, Deserialize)]
+pub struct LnurlCallbackParams {
+    /// NTAG424 encrypted picc_data
+    pub p: String,
+    /// NTAG424 CMAC signature
+    pub c: String,
+    /// k1 from initial request (for verification)
+    pub k1: String,
+    /// BOLT11 invoice from wallet
+    pub pr: String,
+}
+
+/// LNURL-withdraw callback (LUD-03)
+///
+/// GET /api/lnurlw/{location_id}/callback?p={}&c={}&k1={}&pr={}
+///
+/// Called by wallet with the BOLT11 invoice to pay.
+pub async fn lnurlw_callback(
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    Query(params): Query<LnurlCallbackParams>,
+) -> Result<Json<LnurlCallbackResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
+    tracing::info!("LNURL-withdraw callback for location {}", location_id);
+
+    let sun_params = SunParams {
+        p: params.p,
+        c: params.c,
+    };
+
+    // Verify SUN and get withdrawal info
+    let (location, _nfc_card, counter, _withdrawable_msats) =
+        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
+            Ok(result) => result,
+            Err(response) => {
+                let error_msg = response
+                    .error
+                    .unwrap_or_else(|| "Unknown error".to_string());
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(LnurlCallbackResponse::error(error_msg)),
+                ));
+            }
+        };
+
+    // Check the invoice before claiming
+    let invoice = params.pr.trim();
+    let checked = match check_invoice_policy(&state, invoice).await {
+        Ok(checked) => checked,
+        Err((status, msg)) => return Err((status, Json(LnurlCallbackResponse::error(msg)))),
+    };
+
+    // Atomically claim the invoice amount (updates counter and takes it from the balance)
+    // This prevents double-spending even if the same scan is used multiple times
+    let claimed_msats = match state
+        .db
+        .claim_withdrawal(
+            &location_id,
+            counter as i64,
+            checked.amount_msats,
+            &state.balance_config,
+            &state.claim_rules,
+        )
+        .await
+    {
+        Ok(ClaimResult::Success { msats, .. }) => msats,
+        Ok(result) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(LnurlCallbackResponse::error(withdraw_refused_message(
+                    &result,
+                ))),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to claim withdrawal: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(LnurlCallbackResponse::error(
+                    "Failed to process withdrawal. Please try again.",
+                )),
+            ));
+        }
+    };
+
+    let claimed_sats = claimed_msats / 1000;
+
+    // Pay the invoice
+    if let Err(e) = state.lightning.pay_invoice(invoice).await {
+        tracing::error!("Failed to pay invoice: {}", e);
+        // Note: The balance was already claimed, so the user will need to scan again.
+        // This is intentional to prevent double-spending attempts.
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(LnurlCallbackResponse::error(
+                "Payment failed. Please scan the sticker again to retry.",
+            )),
+        ));
+    }
+
+    tracing::info!(
+        "Successful LNURL-withdraw from {}: {} sats",
+        location.name,
+        claimed_sats
+    );
+
+    Ok(Json(LnurlCallbackResponse::ok()))
+}
+
+/// Smallest donation accepted over LNURL-pay (invoices are in whole sats)
+const LNURLP_MIN_SENDABLE_MSATS: i64 = 1_000;
+/// Largest donation accepted over LNURL-pay (1 BTC)
+const LNURLP_MAX_SENDABLE_MSATS: i64 = 100_000_000_000;
+/// Longest donor comment accepted with a payment (LUD-12)
+const LNURLP_COMMENT_ALLOWED: usize = 255;
+
+type LnurlPayError = (StatusCode, Json<LnurlCallbackResponse>);
+
+fn lnurl_error(status: StatusCode, reason: impl Into<String>) -> LnurlPayError {
+    (status, Json(LnurlCallbackResponse::error(reason)))
+}
+
+/// Where a payment to one of our Lightning addresses is booked
+enum PayRecipient {
+    /// Donation to a location's pool, or to all locations (None)
+    Donation { location_id: Option<String> },
+    /// Credit to a registered user's wallet
+    Wallet { user_id: String },
+}
+
+/// Resolve a Lightning address user to where its payments go, the text shown in
+/// the payer's wallet and the pay request metadata (LUD-06) carrying it
+async fn resolve_pay_target(
+    state: &AppState,
+    username: &str,
+) -> Result<(lnurl::PayTarget, PayRecipient, String, String), LnurlPayError> {
+    let unknown = || lnurl_error(StatusCode::NOT_FOUND, "Unknown Lightning address");
+    let internal = |e: anyhow::Error| {
+        tracing::error!("Failed to resolve Lightning address: {}", e);
+        lnurl_error(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+    };
+
+    let target = lnurl::PayTarget::from_user(username).ok_or_else(unknown)?;
+
+    let (recipient, description) = match &target {
+        lnurl::PayTarget::Global => (
+            PayRecipient::Donation { location_id: None },
+            "SatsHunt donation to all locations".to_string(),
+        ),
+        lnurl::PayTarget::Location(location_id) => {
+            let location = state
+                .db
+                .get_location(location_id)
+                .await
+                .map_err(internal)?
+                .ok_or_else(unknown)?;
+            (
+                PayRecipient::Donation {
+                    location_id: Some(location.id),
+                },
+                format!("SatsHunt donation to '{}'", location.name),
+            )
+        }
+        lnurl::PayTarget::User(name) => {
+            let user = state
+                .db
+                .get_user_by_username(name)
+                .await
+                .map_err(internal)?
+                .ok_or_else(unknown)?;
+            (
+                PayRecipient::Wallet { user_id: user.id },
+                format!("Payment to {} on SatsHunt", name),
+            )
+        }
+    };
+
+    let ln_address = target
+        .ln_address(&state.base_url)
+        .unwrap_or_else(|| target.user());
+    let metadata = lnurl::pay_metadata(&description, &ln_address);
+
+    Ok((target, recipient, description, metadata))
+}
+
+/// LNURL-pay request for one of our Lightning addresses (LUD-06, LUD-16)
+///
+/// GET /.well-known/lnurlp/{username}
+///
+/// `donate` donates to all locations, `loc-<location id>` to a single location and
+/// any other name pays into that registered user's wallet.
+pub async fn lnurlp_request(
+    State(state): State<Arc<AppState>>,
+    Path(username): Path<String>,
+) -> Result<Json<lnurl::LnurlPayResponse>, LnurlPayError> {
+    let (target, recipient, _, metadata) = resolve_pay_target(&state, &username).await?;
+
+    // Donations can be zapped; wallet payments aren't announced publicly. Zap
+    // invoices must commit to the zap request, so the backend has to support
+    // description hashes.
+    let accepts_zaps = matches!(recipient, PayRecipient::Donation { .. })
+        && state.lightning.supports_description_hash();
+
+    Ok(Json(lnurl::LnurlPayResponse {
+        callback: format!("{}/api/lnurlp/{}/callback", state.base_url, target.user()),
+        min_sendable: LNURLP_MIN_SENDABLE_MSATS,
+        max_sendable: LNURLP_MAX_SENDABLE_MSATS,
+        metadata,
+        tag: "payRequest".to_string(),
+        comment_allowed: Some(LNURLP_COMMENT_ALLOWED as i64),
+        allows_nostr: accepts_zaps.then_some(true),
+        nostr_pubkey: accepts_zaps
+            .then(|| hex::encode(state.nostr_keys.x_only_public_key().0.serialize())),
+    }))
+}
+
+/// Query parameters for LNURL-pay callback
+#[derive(Debug, Deserialize)]
+pub struct LnurlPayParams {
+    /// Amount in millisatoshis
+    pub amount: i64,
+    /// Optional payer comment (LUD-12)
+    pub comment: Option<String>,
+    /// Optional zap request (NIP-57)
+    pub nostr: Option<String>,
+}
+
+/// LNURL-pay callback (LUD-06)
+///
+/// GET /api/lnurlp/{username}/callback?amount={msats}&comment={}&nostr={}
+///
+/// Creates the invoice. Donations are then tracked by the `DonationService` like
+/// donations made on the website, wallet payments by the `ReceiveService`. Donations
+/// paid with a zap request get a zap receipt published by the `ZapService`.
+///
+/// The invoice commits to the hash of the pay request's metadata (LUD-06), or
+/// of the zap request for zaps (NIP-57). If the Lightning backend can't create
+/// invoices with a description hash, it carries the metadata's text as its
+/// description instead and zaps aren't accepted.
+pub async fn lnurlp_callback(
+    State(state): State<Arc<AppState>>,
+    Path(username): Path<String>,
+    Query(params): Query<LnurlPayParams>,
+) -> Result<Json<lnurl::LnurlPayCallbackResponse>, LnurlPayError> {
+    let (target, recipient, description, metadata) = resolve_pay_target(&state, &username).await?;
+
+    if !(LNURLP_MIN_SENDABLE_MSATS..=LNURLP_MAX_SENDABLE_MSATS).contains(&params.amount) {
+        return Err(lnurl_error(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Amount must be between {} and {} msats",
+                LNURLP_MIN_SENDABLE_MSATS, LNURLP_MAX_SENDABLE_MSATS
+            ),
+        ));
+    }
+    if params.amount % 1000 != 0 {
+        return Err(lnurl_error(
+            StatusCode::BAD_REQUEST,
+            "Amount must be a whole number of sats",
+        ));
+    }
+
+    let zap_request = match params.nostr.as_deref() {
+        Some(request) => {
+            if !matches!(recipient, PayRecipient::Donation { .. })
+                || !state.lightning.supports_description_hash()
+            {
+                return Err(lnurl_error(
+                    StatusCode::BAD_REQUEST,
+                    "Zaps are not accepted by this address",
+                ));
+            }
+            let event = zap::validate_zap_request(
+                request,
+                params.amount,
+                &state.nostr_keys.x_only_public_key().0,
+            )
+            .map_err(|e| lnurl_error(StatusCode::BAD_REQUEST, e.to_string()))?;
+            Some((request, event))
+        }
+        None => None,
+    };
+
+    // A zap's message doubles as the donation comment
+    let comment = params
+        .comment
+        .as_deref()
+        .or(zap_request
+            .as_ref()
+            .map(|(_, event)| event.content.as_str()))
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    if comment.is_some_and(|c| c.chars().count() > LNURLP_COMMENT_ALLOWED) {
+        return Err(lnurl_error(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Comment is longer than {} characters",
+                LNURLP_COMMENT_ALLOWED
+            ),
+        ));
+    }
+
+    let amount_sats = params.amount / 1000;
+    tracing::info!(
+        "Creating LNURL-pay invoice for {} sats to {}",
+        amount_sats,
+        target.user()
+    );
+
+    // Zap invoices commit to the zap request (NIP-57), others to the metadata
+    let invoice = if state.lightning.supports_description_hash() {
+        let committed_to = zap_request
+            .as_ref()
+            .map_or(metadata.as_str(), |(request, _)| *request);
+        state
+            .lightning
+            .create_invoice_with_description_hash(amount_sats as u64, committed_to)
+            .await
+    } else {
+        state
+            .lightning
+            .create_invoice(
+                amount_sats as u64,
+                &format!("{}: {} sats", description, amount_sats),
+            )
+            .await
+    };
+    let invoice = invoice.map_err(|e| {
+        tracing::error!("Failed to create invoice: {}", e);
+        lnurl_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to create invoice",
+        )
+    })?;
+
+    let message = match recipient {
+        PayRecipient::Donation { location_id } => {
+            state
+                .db
+                .create_lnurl_pay_donation(
+                    invoice.clone(),
+                    params.amount,
+                    location_id.as_deref(),
+                    comment,
+                    zap_request.as_ref().map(|(request, _)| *request),
+                )
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to create donation: {}", e);
+                    lnurl_error(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+                })?;
+
+            // Notify donation service to start awaiting payment
+            if let Err(e) = state.donation_sender.send(NewDonation {
+                invoice: invoice.clone(),
+                amount_msats: params.amount,
+                location_id,
+            }) {
+                tracing::error!("Failed to notify donation service: {}", e);
+                // Don't fail the request - the donation service will pick it up on next restart
+            }
+
+            "Thank you for your donation!"
+        }
+        PayRecipient::Wallet { user_id } => {
+            state
+                .db
+                .create_wallet_invoice(&user_id, &invoice, params.amount, comment)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to create wallet invoice: {}", e);
+                    lnurl_error(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+                })?;
+
+            // Notify receive service to start awaiting payment
+            if let Err(e) = state.receive_sender.send(NewWalletInvoice {
+                invoice: invoice.clone(),
+                user_id,
+                amount_msats: params.amount,
+            }) {
+                tracing::error!("Failed to notify receive service: {}", e);
+                // Don't fail the request - the receive service will pick it up on next restart
+            }
+
+            "Payment sent to the hunter's wallet!"
+        }
+    };
+
+    Ok(Json(lnurl::LnurlPayCallbackResponse {
+        pr: invoice,
+        routes: Vec::new(),
+        success_action: Some(json!({
+            "tag": "message",
+            "message": message,
+        })),
+    }))
+}
+
+/// Withdraw via pasted BOLT11 invoice
+///
+/// POST /api/withdraw/{location_id}/invoice?picc_data={}&cmac={}
+/// Body: { "invoice": "lnbc..." }
+pub async fn withdraw_invoice(
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    Query(sun_params): Query<SunParams>,
+    Json(payload): Json<InvoiceWithdrawRequest>,
+) -> Result<Json<WithdrawResponse>, StatusCode> {
+    tracing::info!("Invoice withdrawal request for location {}", location_id);
+
+    // Verify SUN and get withdrawal info
+    let (location, _nfc_card, counter, _withdrawable_msats) =
+        match verify_and_prepare_withdrawal(&state, &location_id, &sun_params).await {
+            Ok(result) => result,
+            Err(response) => return Ok(Json(response)),
+        };
+
+    // Basic invoice validation
+    let invoice = payload.invoice.trim();
+    let checked = match check_invoice_policy(&state, invoice).await {
+        Ok(checked) => checked,
+        Err((_, msg)) => return Ok(Json(WithdrawResponse::error(msg))),
+    };
+
+    // Atomically claim the invoice amount (updates counter and takes it from the balance)
+    // This prevents double-spending even if the same scan is used multiple times
+    let claimed_msats = match state
+        .db
+        .claim_withdrawal(
+            &location_id,
+            counter as i64,
+            checked.amount_msats,
+            &state.balance_config,
+            &state.claim_rules,
+        )
+        .await
+    {
+        Ok(ClaimResult::Success { msats, .. }) => msats,
+        Ok(result) => {
+            return Ok(Json(WithdrawResponse::error(withdraw_refused_message(
+                &result,
+            ))));
+        }
+        Err(e) => {
+            tracing::error!("Failed to claim withdrawal: {}", e);
+            return Ok(Json(WithdrawResponse::error(
+                "Failed to process withdrawal. Please try again.",
+            )));
+        }
+    };
+
+    let claimed_sats = claimed_msats / 1000;
+
+    // Pay the invoice
+    if let Err(e) = state.lightning.pay_invoice(invoice).await {
+        tracing::error!("Failed to pay invoice: {}", e);
+        // Note: The balance was already claimed, so the user will need to scan again.
+        // This is intentional to prevent double-spending attempts.
+        return Ok(Json(WithdrawResponse::error(
+            "Payment failed. Please scan the sticker again to retry.",
+        )));
+    }
+
+    tracing::info!(
+        "Successful invoice withdrawal from {}: {} sats",
+        location.name,
+        claimed_sats
+    );
+
+    Ok(Json(WithdrawResponse::success(claimed_sats, &location_id)))
+}
+
+// ============================================================================
+// Custodial Wallet Collection Endpoint
+// ============================================================================
+
+/// Response for collection endpoint
+#[derive(Debug, Serialize)]
+pub struct CollectResponse {
+    pub success: bool,
+    pub collected_sats: i64,
+    pub new_balance_sats: i64,
+    pub location_name: String,
+    /// User ID to store in localStorage as backup
+    pub user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CollectResponse {
+    fn success(
+        collected_sats: i64,
+        new_balance_sats: i64,
+        location_name: String,
+        user_id: String,
+    ) -> Self {
+        Self {
+            success: true,
+            collected_sats,
+            new_balance_sats,
+            location_name,
+            user_id,
+            error: None,
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            collected_sats: 0,
+            new_balance_sats: 0,
+            location_name: String::new(),
+            user_id: String::new(),
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// Collect sats from a location into user's custodial balance
+///
+/// POST /api/collect/{location_id}?p={picc_data}&c={cmac}
+///
+/// This is the primary endpoint for the custodial wallet system.
+/// Users collect sats into their balance instead of receiving immediate Lightning payments.
+pub async fn collect_sats(
+    State(state): State<Arc<AppState>>,
+    Path(location_id): Path<String>,
+    Query(sun_params): Query<SunParams>,
+    user: CookieUser,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Collection request for location {} by user {} (kind: {:?})",
+        location_id,
+        user.user_id,
+        user.kind
+    );
+
+    // Helper to build error response with cookie jar
+    let error_response = |jar: PrivateCookieJar, status: StatusCode, msg: &str| {
+        (jar, (status, Json(CollectResponse::error(msg)))).into_response()
+    };
+
+    // Verify the SUN message
+    let verification =
+        match ntag424::verify_sun_message(&state.db, &location_id, &sun_params.p, &sun_params.c)
+            .await
+        {
+            Ok(v) => v,
+            Err(e) => {
+                let msg = match e {
+                    ntag424::SunError::ReplayDetected { .. } => {
+                        "This scan has already been used. Please scan the sticker again."
+                    }
+                    ntag424::SunError::CmacMismatch => {
+                        "Invalid NFC scan. Please scan the sticker again."
+                    }
+                    ntag424::SunError::UidMismatch { .. } => "Invalid NFC card for this location.",
+                    ntag424::SunError::CardNotFound | ntag424::SunError::CardNotProgrammed => {
+                        "NFC card not configured."
+                    }
+                    _ => {
+                        tracing::error!("SUN verification error: {}", e);
+                        "Verification failed. Please try again."
+                    }
+                };
+                return error_response(user.jar, StatusCode::BAD_REQUEST, msg);
+            }
+        };
+
+    let location = verification.location;
+    let counter = verification.counter;
+
+    // Atomically claim collection (creates user if needed, updates counter, computes balance)
+    let collected_msats = match state
+        .db
+        .claim_collection(
+            &location_id,
+            &user.user_id,
+            counter as i64,
+            &state.balance_config,
+            &state.claim_rules,
+        )
+        .await
+    {
+        Ok(ClaimResult::Success { msats, .. }) => msats,
+        Ok(ClaimResult::OutsideSchedule) => {
+            return error_response(user.jar, StatusCode::FORBIDDEN, OUTSIDE_SCHEDULE_MESSAGE);
+        }
+        Ok(ClaimResult::LocationInactive) => {
+            return error_response(user.jar, StatusCode::FORBIDDEN, LOCATION_INACTIVE_MESSAGE);
+        }
+        Ok(result @ (ClaimResult::Cooldown { .. } | ClaimResult::MonthlyLimitReached { .. })) => {
+            let msg = claim_rules::blocked_message(&result).unwrap_or_default();
+            return error_response(user.jar, StatusCode::TOO_MANY_REQUESTS, &msg);
+        }
+        Ok(ClaimResult::NotFirstScanner) => {
+            return error_response(
+                user.jar,
+                StatusCode::CONFLICT,
+                "Someone else scanned first. Please try again after they collected.",
+            );
+        }
+        Ok(ClaimResult::NoBalance) => {
+            return error_response(
+                user.jar,
+                StatusCode::BAD_REQUEST,
+                "No sats available at this location.",
+            );
+        }
+        Ok(_) => {
+            tracing::warn!("Collection already claimed or no balance");
+            return error_response(
+                user.jar,
+                StatusCode::CONFLICT,
+                "This scan has already been used. Please scan the sticker again.",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Collection failed: {}", e);
+            return error_response(
+                user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to process collection. Please try again.",
+            );
+        }
+    };
+
+    let collected_sats = collected_msats / 1000;
+
+    // Get new balance
+    let new_balance_msats = state
+        .db
+        .get_user_balance(&user.user_id)
+        .await
+        .unwrap_or(collected_msats);
+    let new_balance_sats = new_balance_msats / 1000;
+
+    tracing::info!(
+        "User {} collected {} sats from {}, new balance: {} sats",
+        user.user_id,
+        collected_sats,
+        location.name,
+        new_balance_sats
+    );
+
+    request_auto_withdraw_check(&state, &user.user_id);
+    request_achievement_check(&state, &user.user_id);
+
+    // Return response with the cookie jar (handles setting new cookie if needed)
+    (
+        user.jar,
+        Json(CollectResponse::success(
+            collected_sats,
+            new_balance_sats,
+            location.name,
+            user.user_id,
+        )),
+    )
+        .into_response()
+}
+
+// ============================================================================
+// Claim Endpoint (new separate scan/claim flow)
+// ============================================================================
+
+/// Claim sats from a previous scan.
+///
+/// POST /api/claim/{scan_id}
+///
+/// The user must be the one who made the scan, within the claim window. The
+/// location's claim policy decides whether the scan wins and what share it gets.
+pub async fn claim_sats(
+    State(state): State<Arc<AppState>>,
+    Path(scan_id): Path<String>,
+    user: CookieUser,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Claim request for scan {} by user {}",
+        scan_id,
+        user.user_id
+    );
+
+    let result = state
+        .db
+        .claim_from_scan(
+            &scan_id,
+            &user.user_id,
+            &state.balance_config,
+            &state.claim_rules,
+        )
+        .await;
+
+    match result {
+        Ok(ClaimResult::Success { msats, claim_id }) => {
+            let collected_sats = msats / 1000;
+
+            // Get new balance
+            let new_balance_msats = state
+                .db
+                .get_user_balance(&user.user_id)
+                .await
+                .unwrap_or(msats);
+            let new_balance_sats = new_balance_msats / 1000;
+
+            tracing::info!(
+                "User {} claimed {} sats (claim_id: {}), new balance: {} sats",
+                user.user_id,
+                collected_sats,
+                claim_id,
+                new_balance_sats
+            );
+
+            request_auto_withdraw_check(&state, &user.user_id);
+            request_achievement_check(&state, &user.user_id);
+
+            (
+                user.jar,
+                Json(CollectResponse::success(
+                    collected_sats,
+                    new_balance_sats,
+                    "this location".to_string(),
+                    user.user_id,
+                )),
+            )
+                .into_response()
+        }
+        Ok(ClaimResult::ScanNotFound) => (
+            user.jar,
+            (
+                StatusCode::NOT_FOUND,
+                Json(CollectResponse::error("Scan not found.")),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::NotYourScan) => (
+            user.jar,
+            (
+                StatusCode::FORBIDDEN,
+                Json(CollectResponse::error("This scan belongs to someone else.")),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::AlreadyClaimed) => (
+            user.jar,
+            (
+                StatusCode::CONFLICT,
+                Json(CollectResponse::error(
+                    "This scan has already been claimed.",
+                )),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::Expired) => (
+            user.jar,
+            (
+                StatusCode::GONE,
+                Json(CollectResponse::error(
+                    "This scan has expired. Please scan again.",
+                )),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::NotLastScanner) => (
+            user.jar,
+            (
+                StatusCode::CONFLICT,
+                Json(CollectResponse::error(
+                    "Someone else scanned after you. Please scan again.",
+                )),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::NotFirstScanner) => (
+            user.jar,
+            (
+                StatusCode::CONFLICT,
+                Json(CollectResponse::error(
+                    "Someone else scanned first. Please try again after they collected.",
+                )),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::NoBalance | ClaimResult::OverClaimable { .. }) => (
+            user.jar,
+            (
+                StatusCode::BAD_REQUEST,
+                Json(CollectResponse::error(
+                    "No sats available at this location.",
+                )),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::OutsideSchedule) => (
+            user.jar,
+            (
+                StatusCode::FORBIDDEN,
+                Json(CollectResponse::error(OUTSIDE_SCHEDULE_MESSAGE)),
+            ),
+        )
+            .into_response(),
+        Ok(ClaimResult::LocationInactive) => (
+            user.jar,
+            (
+                StatusCode::FORBIDDEN,
+                Json(CollectResponse::error(LOCATION_INACTIVE_MESSAGE)),
+            ),
+        )
+            .into_response(),
+        Ok(result @ (ClaimResult::Cooldown { .. } | ClaimResult::MonthlyLimitReached { .. })) => (
+            user.jar,
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(CollectResponse::error(
+                    claim_rules::blocked_message(&result).unwrap_or_default(),
+                )),
+            ),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Claim failed: {}", e);
+            (
+                user.jar,
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(CollectResponse::error(
+                        "Failed to process claim. Please try again.",
+                    )),
+                ),
+            )
+                .into_response()
+        }
+    }
+}
+
+// ============================================================================
+// Custodial Wallet Withdrawal Endpoint
+// ============================================================================
+
+/// Request body for wallet withdrawal
+#[derive(Debug, Deserialize)]
+pub struct WalletWithdrawRequest {
+    pub ln_address: String,
+}
+
+/// Response for wallet withdrawal endpoint
+#[derive(Debug, Serialize)]
+pub struct WalletWithdrawResponse {
+    pub success: bool,
+    pub withdrawn_sats: i64,
+    pub new_balance_sats: i64,
+    /// The withdrawal is over a limit and waits for review instead of being paid
+    pub held: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl WalletWithdrawResponse {
+    fn success(withdrawn_sats: i64, new_balance_sats: i64) -> Self {
+        Self {
+            success: true,
+            withdrawn_sats,
+            new_balance_sats,
+            held: false,
+            error: None,
+        }
+    }
+
+    fn held(withdrawn_sats: i64, new_balance_sats: i64) -> Self {
+        Self {
+            held: true,
+            ..Self::success(withdrawn_sats, new_balance_sats)
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            withdrawn_sats: 0,
+            new_balance_sats: 0,
+            held: false,
+            error: Some(message.into()),
+        }
+    }
+}
+
+/// Withdraw sats from user's custodial balance via Lightning Address
+///
+/// POST /api/wallet/withdraw
+/// Body: { "ln_address": "user@domain.com" }
+///
+/// Withdraws the user's entire balance to the specified Lightning Address.
+pub async fn wallet_withdraw(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Json(payload): Json<WalletWithdrawRequest>,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Wallet withdrawal request from user {} to {}",
+        user.user_id,
+        payload.ln_address
+    );
+
+    // Helper to build error response with cookie jar
+    let error_response = |jar: PrivateCookieJar, status: StatusCode, msg: &str| {
+        (jar, (status, Json(WalletWithdrawResponse::error(msg)))).into_response()
+    };
+
+    // Get user balance
+    let balance_msats = match state.db.get_user_balance(&user.user_id).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            tracing::error!("Failed to get user balance: {}", e);
+            return error_response(
+                user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get balance. Please try again.",
+            );
+        }
+    };
+
+    // Calculate withdrawable amount after fees
+    let (max_withdraw_msats, _fee_msats) = state.fee_policy.max_withdrawable(balance_msats);
+
+    // Check minimum withdrawal amount (need enough to cover fees + at least 1 sat)
+    if max_withdraw_msats < 1000 {
+        return error_response(
+            user.jar,
+            StatusCode::BAD_REQUEST,
+            &insufficient_balance_message(&state.fee_policy),
+        );
+    }
+
+    // Round down to whole sats for the invoice
+    let withdraw_sats = max_withdraw_msats / 1000;
+    let withdraw_msats = withdraw_sats * 1000;
+
+    // Resolve LN address and get invoice (do this before reserving balance)
+    let invoice = match lnurl::get_invoice_for_ln_address(&payload.ln_address, withdraw_msats).await
+    {
+        Ok(inv) => inv,
+        Err(lnurl::LnurlError::InvalidFormat(msg)) => {
+            return error_response(
+                user.jar,
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid Lightning address: {}", msg),
+            );
+        }
+        Err(lnurl::LnurlError::AmountOutOfRange { min, max, .. }) => {
+            return error_response(
+                user.jar,
+                StatusCode::BAD_REQUEST,
+                &format!(
+                    "Amount {} sats is outside the allowed range ({}-{} sats)",
+                    withdraw_sats,
+                    min / 1000,
+                    max / 1000
+                ),
+            );
+        }
+        Err(e) => {
+            tracing::error!("LN address resolution failed: {}", e);
+            return error_response(
+                user.jar,
+                StatusCode::BAD_REQUEST,
+                "Could not resolve Lightning address. Please check and try again.",
+            );
+        }
+    };
+
+    if let Err((status, msg)) = check_invoice_policy(&state, &invoice).await {
+        return error_response(user.jar, status, &msg);
+    }
+
+    // Now that there is an invoice, the fee can be probed
+    let fee_msats = state
+        .fee_policy
+        .reserve_fee_msats(state.lightning.as_ref(), &invoice, withdraw_msats)
+        .await;
+
+    // Create pending withdrawal to reserve the balance (including fees)
+    let withdrawal_id =
+        match reserve_wallet_withdrawal(&state, &user.user_id, withdraw_msats, fee_msats, &invoice)
+            .await
+        {
+            Ok(Some((id, false))) => id,
+            Ok(Some((_, true))) => {
+                let new_balance_msats = state.db.get_user_balance(&user.user_id).await.unwrap_or(0);
+                return (
+                    user.jar,
+                    Json(WalletWithdrawResponse::held(
+                        withdraw_msats / 1000,
+                        new_balance_msats / 1000,
+                    )),
+                )
+                    .into_response();
+            }
+            Ok(None) => {
+                return error_response(
+                    user.jar,
+                    StatusCode::CONFLICT,
+                    "Insufficient balance. Please try again.",
+                );
+            }
+            Err(e) => {
+                tracing::error!("Failed to create pending withdrawal: {}", e);
+                return error_response(
+                    user.jar,
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to process withdrawal. Please try again.",
+                );
+            }
+        };
+
+    let withdrawn_sats = withdraw_msats / 1000;
+
+    // Pay the invoice
+    match pay_reserved_withdrawal(&state, &withdrawal_id, &invoice).await {
+        WithdrawalPayment::Paid => {}
+        WithdrawalPayment::Failed(_) => {
+            return error_response(
+                user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Payment failed. Please try again.",
+            );
+        }
+        WithdrawalPayment::Unconfirmed(_) => {
+            return error_response(user.jar, StatusCode::ACCEPTED, PAYMENT_UNCONFIRMED_MESSAGE);
+        }
+    }
+
+    // Get new balance
+    let new_balance_msats = state.db.get_user_balance(&user.user_id).await.unwrap_or(0);
+    let new_balance_sats = new_balance_msats / 1000;
+
+    tracing::info!(
+        "User {} withdrew {} sats to {}, new balance: {} sats",
+        user.user_id,
+        withdrawn_sats,
+        payload.ln_address,
+        new_balance_sats
+    );
+
+    (
+        user.jar,
+        Json(WalletWithdrawResponse::success(
+            withdrawn_sats,
+            new_balance_sats,
+        )),
+    )
+        .into_response()
+}
+
+/// Request body for wallet invoice withdrawal
+#[derive(Debug, Deserialize)]
+pub struct WalletWithdrawInvoiceRequest {
+    pub invoice: String,
+}
+
+/// Withdraw sats from user's custodial balance via pasted BOLT11 invoice
+///
+/// POST /api/wallet/withdraw/invoice
+/// Body: { "invoice": "lnbc..." }
+///
+/// Pays the provided invoice from the user's balance. The invoice amount
+/// must be less than or equal to the user's balance.
+pub async fn wallet_withdraw_invoice(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Json(payload): Json<WalletWithdrawInvoiceRequest>,
+) -> impl IntoResponse {
+    tracing::info!(
+        "Wallet invoice withdrawal request from user {}",
+        user.user_id
+    );
+
+    // Helper to build error response with cookie jar
+    let error_response = |jar: PrivateCookieJar, status: StatusCode, msg: &str| {
+        (jar, (status, Json(WalletWithdrawResponse::error(msg)))).into_response()
+    };
+
+    // Get user balance
+    let balance_msats = match state.db.get_user_balance(&user.user_id).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            tracing::error!("Failed to get user balance: {}", e);
+            return error_response(
+                user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get balance. Please try again.",
+            );
+        }
+    };
+
+    // Parse and validate the invoice
+    let invoice_str = payload.invoice.trim();
+    let invoice_msats = match check_invoice_policy(&state, invoice_str).await {
+        Ok(checked) => checked.amount_msats,
+        Err((status, msg)) => return error_response(user.jar, status, &msg),
+    };
+
+    if invoice_msats < 1000 {
+        return error_response(
+            user.jar,
+            StatusCode::BAD_REQUEST,
+            "Invoice amount must be at least 1 sat.",
+        );
+    }
+
+    // Check if user has enough balance for the invoice amount + fees
+    let fee_msats =
+        match reserve_invoice_fee(&state, invoice_str, invoice_msats, balance_msats).await {
+            Ok(fee) => fee,
+            Err(msg) => return error_response(user.jar, StatusCode::BAD_REQUEST, &msg),
+        };
+
+    // Create pending withdrawal to reserve the balance (including fees)
+    let withdrawal_id = match reserve_wallet_withdrawal(
+        &state,
+        &user.user_id,
+        invoice_msats,
+        fee_msats,
+        invoice_str,
+    )
+    .await
+    {
+        Ok(Some((id, false))) => id,
+        Ok(Some((_, true))) => {
+            let new_balance_msats = state.db.get_user_balance(&user.user_id).await.unwrap_or(0);
+            return (
+                user.jar,
+                Json(WalletWithdrawResponse::held(
+                    invoice_msats / 1000,
+                    new_balance_msats / 1000,
+                )),
+            )
+                .into_response();
+        }
+        Ok(None) => {
+            return error_response(
+                user.jar,
+                StatusCode::CONFLICT,
+                "Insufficient balance. Please try again.",
+            );
+        }
+        Err(e) => {
+            tracing::error!("Failed to create pending withdrawal: {}", e);
+            return error_response(
+                user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to process withdrawal. Please try again.",
+            );
+        }
+    };
+
+    let withdrawn_sats = invoice_msats / 1000;
+
+    // Pay the invoice
+    match pay_reserved_withdrawal(&state, &withdrawal_id, invoice_str).await {
+        WithdrawalPayment::Paid => {}
+        WithdrawalPayment::Failed(_) => {
+            return error_response(
+                user.jar,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Payment failed. Please try again.",
+            );
+        }
+        WithdrawalPayment::Unconfirmed(_) => {
+            return error_response(user.jar, StatusCode::ACCEPTED, PAYMENT_UNCONFIRMED_MESSAGE);
+        }
+    }
+
+    // Get new balance
+    let new_balance_msats = state.db.get_user_balance(&user.user_id).await.unwrap_or(0);
+    let new_balance_sats = new_balance_msats / 1000;
+
+    tracing::info!(
+        "User {} withdrew {} sats via invoice, new balance: {} sats",
+        user.user_id,
+        withdrawn_sats,
+        new_balance_sats
+    );
+
+    (
+        user.jar,
+        Json(WalletWithdrawResponse::success(
+            withdrawn_sats,
+            new_balance_sats,
+        )),
+    )
+        .into_response()
+}
+
+/// Shown when a withdrawal's payment outcome isn't known yet
+const PAYMENT_UNCONFIRMED_MESSAGE: &str = "The payment is taking longer than expected. Your sats stay reserved and go back to your balance if it fails.";
+
+/// How paying a reserved wallet withdrawal ended
+enum WithdrawalPayment {
+    /// Paid, and the withdrawal completed
+    Paid,
+    /// Known to have failed, and the reserved balance released
+    Failed(anyhow::Error),
+    /// Outcome unknown, the balance stays reserved until it is known
+    Unconfirmed(anyhow::Error),
+}
+
+/// Pay a reserved wallet withdrawal and record how it ended.
+///
+/// An error such as a timeout doesn't mean the payment won't go through, so the
+/// reserved balance is only released if the Lightning backend knows it failed.
+/// Otherwise the withdrawal is marked unconfirmed, to be settled by the
+/// auto-withdraw service once the outcome is known.
+async fn pay_reserved_withdrawal(
+    state: &AppState,
+    withdrawal_id: &str,
+    invoice: &str,
+) -> WithdrawalPayment {
+    let paid_fee_msats = match state.lightning.pay_invoice_with_fee(invoice).await {
+        Ok(fee) => fee,
+        Err(e) => {
+            tracing::error!("Failed to pay invoice: {}", e);
+            match state.lightning.payment_status(invoice).await {
+                Ok(PaymentStatus::Succeeded { fee_msats }) => fee_msats,
+                Ok(PaymentStatus::Failed) => {
+                    if let Err(e) = state.db.fail_pending_withdrawal(withdrawal_id).await {
+                        tracing::error!("Failed to mark withdrawal as failed: {}", e);
+                    }
+                    return WithdrawalPayment::Failed(e);
+                }
+                Ok(PaymentStatus::Unknown) | Err(_) => {
+                    if let Err(e) = state.db.mark_withdrawal_unconfirmed(withdrawal_id).await {
+                        tracing::error!("Failed to mark withdrawal as unconfirmed: {}", e);
+                    }
+                    return WithdrawalPayment::Unconfirmed(e);
+                }
+            }
+        }
+    };
+
+    // Refunds the part of the reserved fee that wasn't needed
+    if let Err(e) = state
+        .db
+        .complete_pending_withdrawal(withdrawal_id, paid_fee_msats)
+        .await
+    {
+        tracing::error!("Failed to complete withdrawal: {}", e);
+        // Payment succeeded but we couldn't record it - this is bad but rare
+    }
+
+    WithdrawalPayment::Paid
+}
+
+/// Ask the auto-withdraw service to check a user's balance after it grew
+fn request_auto_withdraw_check(state: &AppState, user_id: &str) {
+    if let Err(e) = state.auto_withdraw_sender.send(CheckAutoWithdraw {
+        user_id: user_id.to_string(),
+    }) {
+        tracing::error!("Failed to notify auto-withdraw service: {}", e);
+        // Don't fail the request - the service checks all settings every minute
+    }
+}
+
+/// Ask the achievement service to check a user's badges after a scan, claim
+/// or new location
+pub fn request_achievement_check(state: &AppState, user_id: &str) {
+    if let Err(e) = state.achievement_sender.send(CheckAchievements {
+        user_id: user_id.to_string(),
+    }) {
+        tracing::error!("Failed to notify achievement service: {}", e);
+        // Don't fail the request - the next check replays the whole history
+    }
+}
+
+/// Form for saving the auto-withdraw setting
+#[derive(Debug, Deserialize)]
+pub struct SaveAutoWithdrawRequest {
+    pub ln_address: String,
+    pub threshold_sats: i64,
+}
+
+/// Save the current user's auto-withdraw setting
+///
+/// POST /api/wallet/auto-withdraw
+///
+/// Saving re-enables a setting disabled after failed withdrawals. The balance is
+/// checked right away, so a balance already over the threshold is withdrawn.
+pub async fn save_auto_withdraw(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Form(payload): Form<SaveAutoWithdrawRequest>,
+) -> impl IntoResponse {
+    let threshold_msats = match request_sats_to_msats(payload.threshold_sats) {
+        Some(msats) if payload.threshold_sats >= auto_withdraw::MIN_THRESHOLD_SATS => msats,
+        _ => {
+            tracing::warn!(
+                "Auto-withdraw threshold out of range: {} sats",
+                payload.threshold_sats
+            );
+            return (user.jar, StatusCode::BAD_REQUEST).into_response();
+        }
+    };
+
+    let ln_address = payload.ln_address.trim().to_lowercase();
+    let domain = match lnurl::parse_ln_address(&ln_address) {
+        Ok((_, domain)) => domain,
+        Err(e) => {
+            tracing::warn!("Invalid auto-withdraw address: {}", e);
+            return (user.jar, StatusCode::BAD_REQUEST).into_response();
+        }
+    };
+
+    // Withdrawing to one of our own addresses would pay right back into a wallet
+    if lnurl::ln_address_domain(&state.base_url).as_deref() == Some(domain.as_str()) {
+        tracing::warn!("Auto-withdraw address {} is on this instance", ln_address);
+        return (user.jar, StatusCode::BAD_REQUEST).into_response();
+    }
+
+    match state
+        .db
+        .save_auto_withdraw_setting(&user.user_id, &ln_address, threshold_msats)
+        .await
+    {
+        Ok(setting) => {
+            tracing::info!(
+                "User {} set auto-withdraw to {} at {} sats",
+                user.user_id,
+                setting.ln_address,
+                setting.threshold_sats()
+            );
+            request_auto_withdraw_check(&state, &user.user_id);
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to save auto-withdraw setting: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+/// Remove the current user's auto-withdraw setting
+///
+/// DELETE /api/wallet/auto-withdraw
+pub async fn delete_auto_withdraw(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+) -> impl IntoResponse {
+    match state.db.delete_auto_withdraw_setting(&user.user_id).await {
+        Ok(result) if result.rows_affected() == 0 => {
+            (user.jar, StatusCode::NOT_FOUND).into_response()
+        }
+        Ok(_) => {
+            tracing::info!("User {} turned off auto-withdraw", user.user_id);
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete auto-withdraw setting: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+// ============================================================================
+// Wallet LNURL-withdraw Endpoints (LUD-03)
+// ============================================================================
+
+/// Query parameters for wallet LNURL-withdraw initial request
+#[derive(Debug, Deserialize)]
+pub struct WalletLnurlwParams {
+    /// Signed withdrawal token (format: "user_id:timestamp:signature")
+    pub token: String,
+}
+
+/// LNURL-withdraw initial request for wallet balance
+///
+/// GET /api/wallet/lnurlw?token={signed_token}
+///
+/// Returns LNURL-withdraw parameters for the user's wallet balance.
+/// This allows users to withdraw by scanning a QR code with their Lightning wallet.
+/// The token is signed with HMAC to authenticate the user without cookies.
+pub async fn wallet_lnurlw_request(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WalletLnurlwParams>,
+) -> Result<Json<LnurlWithdrawResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
+    tracing::info!("Wallet LNURL-withdraw request with token");
+
+    // Verify the signed token and extract user_id
+    let user_id =
+        verify_withdraw_token(&state.withdraw_secret, &params.token).ok_or_else(|| {
+            tracing::warn!("Invalid or expired withdraw token");
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(LnurlCallbackResponse::error(
+                    "Invalid or expired withdrawal link. Please refresh and try again.",
+                )),
+            )
+        })?;
+
+    tracing::info!("Wallet LNURL-withdraw request from user {}", user_id);
+
+    // Get user balance
+    let balance_msats = match state.db.get_user_balance(&user_id).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            tracing::error!("Failed to get user balance: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(LnurlCallbackResponse::error("Failed to get balance.")),
+            ));
+        }
+    };
+
+    // Calculate withdrawable amount after fees
+    let (max_withdraw_msats, _fee_msats) = state.fee_policy.max_withdrawable(balance_msats);
+
+    // Check minimum withdrawal amount (need enough to cover fees + at least 1 sat)
+    if max_withdraw_msats < 1000 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(LnurlCallbackResponse::error(insufficient_balance_message(
+                &state.fee_policy,
+            ))),
+        ));
+    }
+
+    // Round down to whole sats
+    let withdraw_sats = max_withdraw_msats / 1000;
+    let withdraw_msats = withdraw_sats * 1000;
+
+    // Build callback URL - pass the token through for the callback to verify
+    let callback = format!(
+        "{}/api/wallet/lnurlw/callback?token={}",
+        state.base_url,
+        urlencoding::encode(&params.token)
+    );
+
+    // k1 can be anything unique - we use user_id for logging purposes
+    let k1 = user_id.clone();
+
+    Ok(Json(LnurlWithdrawResponse {
+        tag: "withdrawRequest".to_string(),
+        callback,
+        k1,
+        default_description: format!("Withdraw {} sats from SatsHunt wallet", withdraw_sats),
+        min_withdrawable: withdraw_msats,
+        max_withdrawable: withdraw_msats,
+        balance_check: None,
+        pay_link: None,
+    }))
+}
+
+/// Query parameters for wallet LNURL-withdraw callback
+#[derive(Debug, Deserialize)]
+pub struct WalletLnurlCallbackParams {
+    /// Signed withdrawal token (format: "user_id:timestamp:signature")
+    pub token: String,
+    /// k1 from initial request (for logging only)
+    pub k1: String,
+    /// BOLT11 invoice from wallet
+    pub pr: String,
+}
+
+/// LNURL-withdraw callback for wallet balance
+///
+/// GET /api/wallet/lnurlw/callback?token={signed_token}&k1={user_id}&pr={invoice}
+///
+/// Called by the user's Lightning wallet with the invoice to pay.
+pub async fn wallet_lnurlw_callback(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<WalletLnurlCallbackParams>,
+) -> Result<Json<LnurlCallbackResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
+    tracing::info!("Wallet LNURL-withdraw callback for k1 {}", params.k1);
+
+    // Verify the signed token and extract user_id
+    let user_id =
+        verify_withdraw_token(&state.withdraw_secret, &params.token).ok_or_else(|| {
+            tracing::warn!("Invalid or expired withdraw token in callback");
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(LnurlCallbackResponse::error(
+                    "Invalid or expired withdrawal link. Please refresh and try again.",
+                )),
+            )
+        })?;
+
+    tracing::info!("Wallet LNURL-withdraw callback for user {}", user_id);
+    let withdrawn_msats = pay_wallet_lnurlw_invoice(&state, &user_id, &params.pr).await?;
+
+    tracing::info!(
+        "Successful wallet LNURL-withdraw for user {}: {} sats",
+        user_id,
+        withdrawn_msats / 1000
+    );
+
+    Ok(Json(LnurlCallbackResponse::ok()))
+}
+
+/// Pay an invoice handed to a wallet LNURL-withdraw callback from the user's
+/// balance. Returns the withdrawn amount in msats.
+async fn pay_wallet_lnurlw_invoice(
+    state: &AppState,
+    user_id: &str,
+    invoice: &str,
+) -> Result<i64, (StatusCode, Json<LnurlCallbackResponse>)> {
+    let invoice = invoice.trim();
+
+    // Check the invoice and get the amount (we don't support amountless invoices)
+    let invoice_msats = check_invoice_policy(state, invoice)
+        .await
+        .map_err(|(status, msg)| (status, Json(LnurlCallbackResponse::error(msg))))?
+        .amount_msats;
+
+    // Get user balance (already accounts for pending withdrawals)
+    let balance_msats = state.db.get_user_balance(user_id).await.map_err(|e| {
+        tracing::error!("Failed to get user balance: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(LnurlCallbackResponse::error("Failed to get balance.")),
+        )
+    })?;
+
+    // Check if user has enough balance for invoice + fees
+    let fee_msats = reserve_invoice_fee(state, invoice, invoice_msats, balance_msats)
+        .await
+        .map_err(|msg| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(LnurlCallbackResponse::error(&msg)),
+            )
+        })?;
+
+    // Create pending withdrawal to reserve the balance (including fees)
+    let (withdrawal_id, held) =
+        reserve_wallet_withdrawal(state, user_id, invoice_msats, fee_msats, invoice)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to create pending withdrawal: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(LnurlCallbackResponse::error(
+                        "Failed to process withdrawal. Please try again.",
+                    )),
+                )
+            })?
+            .ok_or_else(|| {
+                (
+                    StatusCode::CONFLICT,
+                    Json(LnurlCallbackResponse::error(
+                        "Insufficient balance. Please try again.",
+                    )),
+                )
+            })?;
+
+    // The wallet is told about the review instead of waiting for a payment that
+    // may take a while. The invoice is still paid once approved.
+    if held {
+        return Err((
+            StatusCode::OK,
+            Json(LnurlCallbackResponse::error(format!(
+                "Your withdrawal of {} sats is held for review. This invoice is paid once an admin approves it, otherwise the sats go back to your balance.",
+                invoice_msats / 1000
+            ))),
+        ));
+    }
+
+    // Pay the invoice
+    match pay_reserved_withdrawal(state, &withdrawal_id, invoice).await {
+        WithdrawalPayment::Paid => Ok(invoice_msats),
+        WithdrawalPayment::Failed(_) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(LnurlCallbackResponse::error(
+                "Payment failed. Please try again.",
+            )),
+        )),
+        // The wallet may still receive the payment, but can't be told here
+        WithdrawalPayment::Unconfirmed(_) => Err((
+            StatusCode::OK,
+            Json(LnurlCallbackResponse::error(PAYMENT_UNCONFIRMED_MESSAGE)),
+        )),
+    }
+}
+
+/// Look up an active reusable withdraw link by its key
+async fn get_withdraw_link(
+    state: &AppState,
+    link_key: &str,
+) -> Result<WalletWithdrawLink, (StatusCode, Json<LnurlCallbackResponse>)> {
+    state
+        .db
+        .get_wallet_withdraw_link_by_key(link_key)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get withdraw link: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(LnurlCallbackResponse::error("Internal error")),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(LnurlCallbackResponse::error(
+                    "This withdrawal link was revoked.",
+                )),
+            )
+        })
+}
+
+/// LNURL-withdraw request for a reusable wallet withdraw link
+///
+/// GET /api/wallet/lnurlw/link/{link_key}
+///
+/// Any amount up to the balance after fees can be withdrawn. The response points
+/// back at this URL as `balanceCheck` (LUD-14), so wallets can store the link and
+/// poll it, and at the user's Lightning address as `payLink` (LUD-19) if they
+/// have one.
+pub async fn wallet_withdraw_link_request(
+    State(state): State<Arc<AppState>>,
+    Path(link_key): Path<String>,
+) -> Result<Json<LnurlWithdrawResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
+    let link = get_withdraw_link(&state, &link_key).await?;
+
+    let balance_msats = state
+        .db
+        .get_user_balance(&link.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get user balance: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(LnurlCallbackResponse::error("Failed to get balance.")),
+            )
+        })?;
+
+    // Round down to whole sats. A balance too low to withdraw is still reported,
+    // so wallets polling the balance see it.
+    let (max_withdraw_msats, _fee_msats) = state.fee_policy.max_withdrawable(balance_msats);
+    let max_withdraw_msats = max_withdraw_msats / 1000 * 1000;
+
+    let pay_link = state
+        .db
+        .get_user_by_id(&link.user_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|u| u.username)
+        .filter(|name| lnurl::is_valid_address_user(name) && !lnurl::PayTarget::is_reserved(name))
+        .and_then(|name| lnurl::PayTarget::User(name).lnurlp_link(&state.base_url));
+
+    let link_url = format!("{}/api/wallet/lnurlw/link/{}", state.base_url, link_key);
+
+    Ok(Json(LnurlWithdrawResponse {
+        tag: "withdrawRequest".to_string(),
+        callback: format!("{}/callback", link_url),
+        k1: link.id,
+        default_description: "Withdraw from SatsHunt wallet".to_string(),
+        min_withdrawable: max_withdraw_msats.min(1000),
+        max_withdrawable: max_withdraw_msats,
+        balance_check: Some(link_url),
+        pay_link,
+    }))
+}
+
+/// Query parameters for the reusable withdraw link callback
+#[derive(Debug, Deserialize)]
+pub struct WalletWithdrawLinkCallbackParams {
+    /// k1 from the withdraw request (the link's id)
+    pub k1: String,
+    /// BOLT11 invoice from wallet
+    pub pr: String,
+}
+
+/// LNURL-withdraw callback for a reusable wallet withdraw link
+///
+/// GET /api/wallet/lnurlw/link/{link_key}/callback?k1={link_id}&pr={invoice}
+///
+/// A `balanceNotify` URL (LUD-14) sent by the wallet is ignored; wallets fall back
+/// to polling `balanceCheck`.
+pub async fn wallet_withdraw_link_callback(
+    State(state): State<Arc<AppState>>,
+    Path(link_key): Path<String>,
+    Query(params): Query<WalletWithdrawLinkCallbackParams>,
+) -> Result<Json<LnurlCallbackResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
+    let link = get_withdraw_link(&state, &link_key).await?;
+    if params.k1 != link.id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(LnurlCallbackResponse::error("Invalid k1.")),
+        ));
+    }
+
+    tracing::info!(
+        "Withdraw link callback for user {} (link {})",
+        link.user_id,
+        link.id
+    );
+    let withdrawn_msats = pay_wallet_lnurlw_invoice(&state, &link.user_id, &params.pr).await?;
+
+    if let Err(e) = state.db.mark_wallet_withdraw_link_used(&link.id).await {
+        tracing::error!("Failed to mark withdraw link as used: {}", e);
+    }
+
+    tracing::info!(
+        "Successful withdraw link withdrawal for user {}: {} sats",
+        link.user_id,
+        withdrawn_msats / 1000
+    );
+
+    Ok(Json(LnurlCallbackResponse::ok()))
+}
+
+/// Create a reusable withdraw link for the current user, revoking their old one
+///
+/// POST /api/wallet/withdraw-link
+pub async fn create_withdraw_link(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+) -> impl IntoResponse {
+    match state.db.create_wallet_withdraw_link(&user.user_id).await {
+        Ok(link) => {
+            tracing::info!("User {} created withdraw link {}", user.user_id, link.id);
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to create withdraw link: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+/// Revoke the current user's reusable withdraw link
+///
+/// DELETE /api/wallet/withdraw-link
+pub async fn revoke_withdraw_link(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+) -> impl IntoResponse {
+    match state.db.revoke_wallet_withdraw_link(&user.user_id).await {
+        Ok(result) if result.rows_affected() == 0 => {
+            (user.jar, StatusCode::NOT_FOUND).into_response()
+        }
+        Ok(_) => {
+            tracing::info!("User {} revoked their withdraw link", user.user_id);
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Err(e) => {
+            tracing::error!("Failed to revoke withdraw link: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+// ============================================================================
+// Admin Endpoints
+// ============================================================================
+
+/// Request body for updating user role
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRoleRequest {
+    pub role: String,
+}
+
+/// Update a user's role (admin only)
+///
+/// POST /api/admin/users/{user_id}/role
+/// Body: { "role": "user" | "creator" | "admin" }
+pub async fn update_user_role(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(user_id): Path<String>,
+    Form(payload): Form<UpdateUserRoleRequest>,
+) -> Result<StatusCode, StatusCode> {
+    // Require admin role
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+
+    tracing::info!(
+        "Admin {} updating role for user {} to {}",
+        auth.user_id,
+        user_id,
+        payload.role
+    );
+
+    // Parse the role
+    let new_role: UserRole = payload.role.parse().map_err(|_| {
+        tracing::warn!("Invalid role: {}", payload.role);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    // Update the user's role
+    let result = state
+        .db
+        .update_user_role(&user_id, new_role)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update user role: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if result.rows_affected() == 0 {
+        tracing::warn!("User not found: {}", user_id);
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    tracing::info!(
+        "Admin {} updated user {} role to {}",
+        auth.user_id,
+        user_id,
+        new_role
+    );
+
+    Ok(StatusCode::OK)
+}
+
+/// Export the solvency report as JSON (admin only)
+///
+/// GET /api/admin/solvency
+pub async fn admin_solvency_report(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+) -> Result<Json<solvency::SolvencyReport>, StatusCode> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+
+    let report = solvency::build_report(&state.db, state.lightning.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build solvency report: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LiabilityProofQuery {
+    /// Snapshot to prove inclusion in (default: the latest)
+    pub snapshot: Option<String>,
+}
+
+/// Merkle-sum inclusion proof for the current user's wallet balance in a
+/// published liabilities snapshot
+///
+/// GET /api/wallet/liability-proof?snapshot={snapshot_id}
+///
+/// Returns 404 if there is no such snapshot or the user held no balance in it.
+pub async fn wallet_liability_proof(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Query(query): Query<LiabilityProofQuery>,
+) -> impl IntoResponse {
+    let snapshot = match &query.snapshot {
+        Some(id) => state.db.get_liability_snapshot(id).await,
+        None => state.db.get_latest_liability_snapshot().await,
+    };
+    let snapshot = match snapshot {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => return (user.jar, StatusCode::NOT_FOUND).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get liabilities snapshot: {}", e);
+            return (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    match solvency::snapshot_proof(&state.db, snapshot, &user.user_id).await {
+        Ok(Some(proof)) => (user.jar, Json(proof)).into_response(),
+        Ok(None) => (user.jar, StatusCode::NOT_FOUND).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to build liability proof: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+/// Most liabilities snapshots listed by the history endpoint
+const LIABILITY_SNAPSHOT_HISTORY_LIMIT: i64 = 365;
+
+/// Published liabilities roots, newest first
+///
+/// GET /api/solvency/snapshots
+pub async fn list_liability_snapshots(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<LiabilitySnapshot>>, StatusCode> {
+    state
+        .db
+        .list_liability_snapshots(LIABILITY_SNAPSHOT_HISTORY_LIMIT)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to list liabilities snapshots: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Form for creating a donation matching campaign.
+/// Optional numbers arrive as empty strings when left blank.
+#[derive(Debug, Deserialize)]
+pub struct CreateCampaignRequest {
+    pub name: String,
+    pub sponsor_name: String,
+    pub budget_sats: i64,
+    pub match_percent: i64,
+    pub max_match_sats: Option<String>,
+    pub min_latitude: Option<String>,
+    pub max_latitude: Option<String>,
+    pub min_longitude: Option<String>,
+    pub max_longitude: Option<String>,
+    /// Last day of the campaign (YYYY-MM-DD, inclusive)
+    pub ends_on: String,
+}
+
+/// Highest match ratio a campaign can be created with (10:1)
+const MAX_CAMPAIGN_MATCH_PERCENT: i64 = 1000;
+
+fn parse_optional_field<T: std::str::FromStr>(value: &Option<String>) -> Result<Option<T>, ()> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(v) => v.parse().map(Some).map_err(|_| ()),
+    }
+}
+
+/// Largest amount a request can set in sats (1 BTC)
+const MAX_REQUEST_SATS: i64 = 100_000_000;
+
+/// Convert an amount in sats from a request to msats, None if it is over
+/// `MAX_REQUEST_SATS`
+fn request_sats_to_msats(sats: i64) -> Option<i64> {
+    sats.checked_mul(1000).filter(|_| sats <= MAX_REQUEST_SATS)
+}
+
+impl CreateCampaignRequest {
+    fn into_new_campaign(self) -> Result<NewMatchingCampaign, &'static str> {
+        let name = self.name.trim().to_string();
+        let sponsor_name = self.sponsor_name.trim().to_string();
+        if name.is_empty() || sponsor_name.is_empty() {
+            return Err("name and sponsor are required");
+        }
+        if self.budget_sats <= 0 {
+            return Err("budget must be positive");
+        }
+        let budget_msats = request_sats_to_msats(self.budget_sats).ok_or("budget is too large")?;
+        if !(1..=MAX_CAMPAIGN_MATCH_PERCENT).contains(&self.match_percent) {
+            return Err("match percent out of range");
+        }
+
+        let max_match_sats: Option<i64> =
+            parse_optional_field(&self.max_match_sats).map_err(|_| "invalid max match")?;
+        if max_match_sats.is_some_and(|sats| sats <= 0) {
+            return Err("max match must be positive");
+        }
+        let max_match_msats = max_match_sats
+            .map(|sats| request_sats_to_msats(sats).ok_or("max match is too large"))
+            .transpose()?;
+        let min_latitude: Option<f64> =
+            parse_optional_field(&self.min_latitude).map_err(|_| "invalid latitude")?;
+        let max_latitude: Option<f64> =
+            parse_optional_field(&self.max_latitude).map_err(|_| "invalid latitude")?;
+        let min_longitude: Option<f64> =
+            parse_optional_field(&self.min_longitude).map_err(|_| "invalid longitude")?;
+        let max_longitude: Option<f64> =
+            parse_optional_field(&self.max_longitude).map_err(|_| "invalid longitude")?;
+        if let (Some(min), Some(max)) = (min_latitude, max_latitude) {
+            if min > max {
+                return Err("min latitude above max latitude");
+            }
+        }
+        if let (Some(min), Some(max)) = (min_longitude, max_longitude) {
+            if min > max {
+                return Err("min longitude above max longitude");
+            }
+        }
+
+        // The campaign runs through the whole last day
+        let ends_on = chrono::NaiveDate::parse_from_str(self.ends_on.trim(), "%Y-%m-%d")
+            .map_err(|_| "invalid end date")?;
+        let ends_at = ends_on
+            .succ_opt()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .ok_or("invalid end date")?
+            .and_utc();
+        if ends_at <= Utc::now() {
+            return Err("end date is in the past");
+        }
+
+        Ok(NewMatchingCampaign {
+            name,
+            sponsor_name,
+            budget_msats,
+            match_percent: self.match_percent,
+            max_match_msats,
+            min_latitude,
+            max_latitude,
+            min_longitude,
+            max_longitude,
+            ends_at,
+        })
+    }
+}
+
+/// Create a donation matching campaign (admin only)
+///
+/// POST /api/admin/campaigns
+///
+/// Creates a funding invoice for the sponsor's budget. The campaign starts
+/// matching donations once that invoice is paid.
+pub async fn create_campaign(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Form(payload): Form<CreateCampaignRequest>,
+) -> Result<StatusCode, StatusCode> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+
+    let campaign = payload.into_new_campaign().map_err(|e| {
+        tracing::warn!("Invalid campaign: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let description = format!(
+        "SatsHunt matching campaign '{}' by {}: {} sats",
+        campaign.name,
+        campaign.sponsor_name,
+        campaign.budget_msats / 1000
+    );
+    let invoice = state
+        .lightning
+        .create_invoice((campaign.budget_msats / 1000) as u64, &description)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create campaign funding invoice: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let campaign = state
+        .db
+        .create_matching_campaign(&campaign, &invoice)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create campaign: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if let Err(e) = state.campaign_sender.send(NewCampaign {
+        campaign_id: campaign.id.clone(),
+        funding_invoice: invoice,
+    }) {
+        tracing::error!("Failed to notify campaign service: {}", e);
+        // The campaign service picks up unfunded campaigns on the next restart
+    }
+
+    tracing::info!(
+        "Admin {} created campaign '{}' for {} with {} sats budget",
+        auth.user_id,
+        campaign.name,
+        campaign.sponsor_name,
+        campaign.budget_sats()
+    );
+
+    Ok(StatusCode::OK)
+}
+
+/// End a donation matching campaign early (admin only)
+///
+/// POST /api/admin/campaigns/{campaign_id}/end
+///
+/// Its unspent budget is donated to the locations in its region.
+pub async fn end_campaign(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(campaign_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| StatusCode::FORBIDDEN)?;
+
+    let ended = state
+        .db
+        .end_matching_campaign(&campaign_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to end campaign: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if !ended {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    tracing::info!("Admin {} ended campaign {}", auth.user_id, campaign_id);
+
+    Ok(StatusCode::OK)
+}
+
+/// Form for creating a hunt
+#[derive(Debug, Deserialize)]
+pub struct CreateHuntRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Checkbox, present when the stops have to be claimed in order
+    pub ordered: Option<String>,
+    pub bonus_sats: i64,
+    /// Comma separated location ids in trail order
+    pub location_ids: String,
+}
+
+/// Fewest stops a hunt can have
+const MIN_HUNT_STOPS: usize = 2;
+
+/// Create a hunt over existing locations (admin only)
+///
+/// POST /api/admin/hunts
+pub async fn create_hunt(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Form(payload): Form<CreateHuntRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| (StatusCode::FORBIDDEN, "Admins only".to_string()))?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Name is required".to_string()));
+    }
+    if payload.bonus_sats <= 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Bonus must be positive".to_string(),
+        ));
+    }
+    let bonus_msats = request_sats_to_msats(payload.bonus_sats)
+        .ok_or((StatusCode::BAD_REQUEST, "Bonus is too large".to_string()))?;
+    let description = payload
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|d| !d.is_empty());
+
+    let mut location_ids: Vec<String> = Vec::new();
+    for id in payload.location_ids.split(',').map(str::trim) {
+        if !id.is_empty() && !location_ids.iter().any(|l| l == id) {
+            location_ids.push(id.to_string());
+        }
+    }
+    if location_ids.len() < MIN_HUNT_STOPS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("A hunt needs at least {} locations", MIN_HUNT_STOPS),
+        ));
+    }
+    for id in &location_ids {
+        let location = state.db.get_location(id).await.map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+        if location.is_none() {
+            return Err((StatusCode::BAD_REQUEST, format!("Unknown location {}", id)));
+        }
+    }
+
+    let hunt = state
+        .db
+        .create_hunt(
+            name,
+            description,
+            payload.ordered.is_some(),
+            bonus_msats,
+            &auth.user_id,
+            &location_ids,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create hunt: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?;
+
+    tracing::info!(
+        "Admin {} created hunt '{}' with {} stops",
+        auth.user_id,
+        hunt.name,
+        location_ids.len()
+    );
+
+    Ok(StatusCode::OK)
+}
+
+/// Form for adding an activation schedule to a location or hunt
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleRequest {
+    /// Start of the first window in the browser's local time (YYYY-MM-DDTHH:MM)
+    pub starts_at: String,
+    /// End of the first window in the browser's local time (YYYY-MM-DDTHH:MM)
+    pub ends_at: String,
+    /// "once", "daily" or "weekly"
+    pub recurrence: String,
+    /// Minutes to add to local time to get UTC, as given by `Date.getTimezoneOffset()`
+    pub tz_offset: Option<i64>,
+}
+
+impl CreateScheduleRequest {
+    fn into_new_schedule(
+        self,
+        location_id: Option<String>,
+        hunt_id: Option<String>,
+        created_by: &str,
+    ) -> Result<NewSchedule, &'static str> {
+        let offset = chrono::Duration::minutes(self.tz_offset.unwrap_or(0));
+        let parse = |value: &str| {
+            chrono::NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%dT%H:%M")
+                .map(|local| local.and_utc() + offset)
+        };
+        let starts_at = parse(&self.starts_at).map_err(|_| "invalid start")?;
+        let ends_at = parse(&self.ends_at).map_err(|_| "invalid end")?;
+        let recurrence: Recurrence = self.recurrence.parse().map_err(|_| "invalid recurrence")?;
+
+        if ends_at <= starts_at {
+            return Err("the window has to end after it starts");
+        }
+        match recurrence.period() {
+            Some(period) if ends_at - starts_at > period => {
+                return Err("the window is longer than its repeat interval");
+            }
+            None if ends_at <= Utc::now() => return Err("the window is in the past"),
+            _ => {}
+        }
+
+        Ok(NewSchedule {
+            location_id,
+            hunt_id,
+            starts_at,
+            ends_at,
+            recurrence,
+            created_by: created_by.to_string(),
+        })
+    }
+}
+
+/// Store a schedule and hide or show the affected locations right away
+async fn save_schedule(
+    state: &AppState,
+    new_schedule: NewSchedule,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let schedule = state.db.create_schedule(&new_schedule).await.map_err(|e| {
+        tracing::error!("Failed to create schedule: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Database error".to_string(),
+        )
+    })?;
+
+    tracing::info!(
+        "User {} added {} schedule {} from {} to {}",
+        schedule.created_by,
+        schedule.recurrence,
+        schedule.id,
+        schedule.starts_at,
+        schedule.ends_at
+    );
+
+    if let Err(e) = state.db.apply_schedules(Utc::now()).await {
+        tracing::error!("Failed to apply schedules: {}", e);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Add an activation schedule to a location (owner or admin)
+///
+/// POST /api/locations/{location_id}/schedules
+pub async fn create_location_schedule(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(location_id): Path<String>,
+    Form(payload): Form<CreateScheduleRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let location = state
+        .db
+        .get_location(&location_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
+
+    let is_owner = location.user_id == auth.user_id && auth.has_role(UserRole::Creator);
+    if !is_owner && !auth.has_role(UserRole::Admin) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the owner can schedule this location".to_string(),
+        ));
+    }
+
+    let new_schedule = payload
+        .into_new_schedule(Some(location.id), None, &auth.user_id)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid schedule: {}", e)))?;
+
+    save_schedule(&state, new_schedule).await
+}
+
+/// Add an activation schedule to every stop of a hunt (admin only)
+///
+/// POST /api/admin/hunts/{hunt_id}/schedules
+pub async fn create_hunt_schedule(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(hunt_id): Path<String>,
+    Form(payload): Form<CreateScheduleRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| (StatusCode::FORBIDDEN, "Admins only".to_string()))?;
+
+    let hunt = state
+        .db
+        .get_hunt(&hunt_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get hunt: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Hunt not found".to_string()))?;
+
+    let new_schedule = payload
+        .into_new_schedule(None, Some(hunt.id), &auth.user_id)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid schedule: {}", e)))?;
+
+    save_schedule(&state, new_schedule).await
+}
 
-    // Pay the invoice
-    if let Err(e) = state.lightning.pay_invoice(&invoice).await {
-        tracing::error!("Failed to pay invoice: {}", e);
-        // Mark withdrawal as failed - balance will be released
-        if let Err(e) = state.db.fail_pending_withdrawal(&withdrawal_id).await {
-            tracing::error!("Failed to mark withdrawal as failed: {}", e);
+/// Remove an activation schedule (admin, or the owner of a scheduled location)
+///
+/// DELETE /api/schedules/{schedule_id}
+pub async fn delete_schedule(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(schedule_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let schedule = state
+        .db
+        .get_schedule(&schedule_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get schedule: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if !auth.has_role(UserRole::Admin) {
+        let location_id = schedule
+            .location_id
+            .as_deref()
+            .ok_or(StatusCode::FORBIDDEN)?;
+        let location = state
+            .db
+            .get_location(location_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to get location: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        if location.user_id != auth.user_id {
+            return Err(StatusCode::FORBIDDEN);
         }
-        return error_response(
-            user.jar,
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Payment failed. Please try again.",
-        );
     }
 
-    // Mark withdrawal as completed
-    if let Err(e) = state.db.complete_pending_withdrawal(&withdrawal_id).await {
-        tracing::error!("Failed to complete withdrawal: {}", e);
-        // Payment succeeded but we couldn't record it - this is bad but rare
-    }
+    state.db.delete_schedule(&schedule_id).await.map_err(|e| {
+        tracing::error!("Failed to delete schedule: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    // Get new balance
-    let new_balance_msats = state.db.get_user_balance(&user.user_id).await.unwrap_or(0);
-    let new_balance_sats = new_balance_msats / 1000;
+    tracing::info!("User {} removed schedule {}", auth.user_id, schedule_id);
 
-    tracing::info!(
-        "User {} withdrew {} sats to {}, new balance: {} sats",
-        user.user_id,
-        withdrawn_sats,
-        payload.ln_address,
-        new_balance_sats
-    );
+    if let Err(e) = state.db.apply_schedules(Utc::now()).await {
+        tracing::error!("Failed to apply schedules: {}", e);
+    }
 
-    (
-        user.jar,
-        Json(WalletWithdrawResponse::success(
-            withdrawn_sats,
-            new_balance_sats,
-        )),
-    )
-        .into_response()
+    Ok(StatusCode::OK)
 }
 
-/// Request body for wallet invoice withdrawal
+/// Form for a location's claim policy and rules.
+/// Blank rules keep the global rule, 0 turns it off for the location.
 #[derive(Debug, Deserialize)]
-pub struct WalletWithdrawInvoiceRequest {
-    pub invoice: String,
+pub struct UpdateClaimRulesRequest {
+    pub claim_policy: Option<String>,
+    pub cooldown_days: Option<String>,
+    pub max_claims_per_month: Option<String>,
+    pub first_claim_bonus_percent: Option<String>,
 }
 
-/// Withdraw sats from user's custodial balance via pasted BOLT11 invoice
-///
-/// POST /api/wallet/withdraw/invoice
-/// Body: { "invoice": "lnbc..." }
-///
-/// Pays the provided invoice from the user's balance. The invoice amount
-/// must be less than or equal to the user's balance.
-pub async fn wallet_withdraw_invoice(
-    State(state): State<Arc<AppState>>,
-    user: CookieUser,
-    Json(payload): Json<WalletWithdrawInvoiceRequest>,
-) -> impl IntoResponse {
-    tracing::info!(
-        "Wallet invoice withdrawal request from user {}",
-        user.user_id
-    );
-
-    // Helper to build error response with cookie jar
-    let error_response = |jar: PrivateCookieJar, status: StatusCode, msg: &str| {
-        (jar, (status, Json(WalletWithdrawResponse::error(msg)))).into_response()
-    };
-
-    // Parse and validate the invoice
-    let invoice_str = payload.invoice.trim();
-    let invoice: lightning_invoice::Bolt11Invoice = match invoice_str.parse() {
-        Ok(inv) => inv,
-        Err(e) => {
-            tracing::warn!("Invalid invoice format: {}", e);
-            return error_response(
-                user.jar,
-                StatusCode::BAD_REQUEST,
-                "Invalid invoice format. Please paste a valid Lightning invoice.",
-            );
+/// Highest first claim bonus a location can set, doubling the first claim
+const MAX_FIRST_CLAIM_BONUS_PERCENT: i64 = 100;
+
+impl UpdateClaimRulesRequest {
+    fn into_claim_rules(self) -> Result<ClaimRules, &'static str> {
+        let rules = ClaimRules {
+            cooldown_days: parse_optional_field(&self.cooldown_days)
+                .map_err(|_| "invalid cooldown")?,
+            max_claims_per_month: parse_optional_field(&self.max_claims_per_month)
+                .map_err(|_| "invalid monthly limit")?,
+            first_claim_bonus_percent: parse_optional_field(&self.first_claim_bonus_percent)
+                .map_err(|_| "invalid first claim bonus")?,
+        };
+        if [
+            rules.cooldown_days,
+            rules.max_claims_per_month,
+            rules.first_claim_bonus_percent,
+        ]
+        .iter()
+        .flatten()
+        .any(|v| *v < 0)
+        {
+            return Err("rules can't be negative");
         }
-    };
-
-    // Get invoice amount in msats
-    let invoice_msats = match invoice.amount_milli_satoshis() {
-        Some(msats) => msats as i64,
-        None => {
-            return error_response(
-                user.jar,
-                StatusCode::BAD_REQUEST,
-                "Invoice must specify an amount. Please create an invoice with a specific amount.",
-            );
+        if rules
+            .first_claim_bonus_percent
+            .is_some_and(|percent| percent > MAX_FIRST_CLAIM_BONUS_PERCENT)
+        {
+            return Err("first claim bonus out of range");
         }
-    };
-
-    if invoice_msats < 1000 {
-        return error_response(
-            user.jar,
-            StatusCode::BAD_REQUEST,
-            "Invoice amount must be at least 1 sat.",
-        );
+        Ok(rules)
     }
+}
 
-    // Get user balance
-    let balance_msats = match state.db.get_user_balance(&user.user_id).await {
-        Ok(balance) => balance,
-        Err(e) => {
-            tracing::error!("Failed to get user balance: {}", e);
-            return error_response(
-                user.jar,
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to get balance. Please try again.",
-            );
-        }
-    };
-
-    // Check if user has enough balance for the invoice amount + fees
-    let fee_msats = match check_invoice_with_fees(invoice_msats, balance_msats) {
-        Ok(fee) => fee,
-        Err(msg) => return error_response(user.jar, StatusCode::BAD_REQUEST, &msg),
-    };
-
-    // Create pending withdrawal to reserve the balance (including fees)
-    let withdrawal_id = match state
+/// Set the claim policy and rules of a location (owner or admin)
+///
+/// POST /api/locations/{location_id}/claim-rules
+pub async fn update_location_claim_rules(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(location_id): Path<String>,
+    Form(payload): Form<UpdateClaimRulesRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let location = state
         .db
-        .create_pending_withdrawal(&user.user_id, invoice_msats, fee_msats, invoice_str)
+        .get_location(&location_id)
         .await
-    {
-        Ok(Some(id)) => id,
-        Ok(None) => {
-            return error_response(
-                user.jar,
-                StatusCode::CONFLICT,
-                "Insufficient balance. Please try again.",
-            );
-        }
-        Err(e) => {
-            tracing::error!("Failed to create pending withdrawal: {}", e);
-            return error_response(
-                user.jar,
+        .map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to process withdrawal. Please try again.",
-            );
-        }
-    };
-
-    let withdrawn_sats = invoice_msats / 1000;
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
 
-    // Pay the invoice
-    if let Err(e) = state.lightning.pay_invoice(invoice_str).await {
-        tracing::error!("Failed to pay invoice: {}", e);
-        // Mark withdrawal as failed - balance will be released
-        if let Err(e) = state.db.fail_pending_withdrawal(&withdrawal_id).await {
-            tracing::error!("Failed to mark withdrawal as failed: {}", e);
-        }
-        return error_response(
-            user.jar,
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Payment failed. Please try again.",
-        );
+    let is_owner = location.user_id == auth.user_id && auth.has_role(UserRole::Creator);
+    if !is_owner && !auth.has_role(UserRole::Admin) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the owner can change the claim rules of this location".to_string(),
+        ));
     }
 
-    // Mark withdrawal as completed
-    if let Err(e) = state.db.complete_pending_withdrawal(&withdrawal_id).await {
-        tracing::error!("Failed to complete withdrawal: {}", e);
-        // Payment succeeded but we couldn't record it - this is bad but rare
+    let policy: Option<ClaimPolicy> = parse_optional_field(&payload.claim_policy)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid claim policy".to_string()))?;
+    let rules = payload.into_claim_rules().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid claim rules: {}", e),
+        )
+    })?;
+
+    if let Some(policy) = policy {
+        state
+            .db
+            .update_location_claim_policy(&location.id, policy)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to set claim policy: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to save claim policy".to_string(),
+                )
+            })?;
     }
 
-    // Get new balance
-    let new_balance_msats = state.db.get_user_balance(&user.user_id).await.unwrap_or(0);
-    let new_balance_sats = new_balance_msats / 1000;
+    state
+        .db
+        .set_location_claim_rules(&location.id, &rules)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set claim rules: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save claim rules".to_string(),
+            )
+        })?;
 
     tracing::info!(
-        "User {} withdrew {} sats via invoice, new balance: {} sats",
-        user.user_id,
-        withdrawn_sats,
-        new_balance_sats
+        "User {} set claim policy {:?} and rules {:?} of location {}",
+        auth.user_id,
+        policy,
+        rules,
+        location.id
     );
 
-    (
-        user.jar,
-        Json(WalletWithdrawResponse::success(
-            withdrawn_sats,
-            new_balance_sats,
-        )),
-    )
-        .into_response()
+    Ok(StatusCode::OK)
 }
 
-// ============================================================================
-// Wallet LNURL-withdraw Endpoints (LUD-03)
-// ============================================================================
-
-/// Query parameters for wallet LNURL-withdraw initial request
+/// Form for how much of a location's position is shown.
+/// A blank radius keeps the current search area.
 #[derive(Debug, Deserialize)]
-pub struct WalletLnurlwParams {
-    /// Signed withdrawal token (format: "user_id:timestamp:signature")
-    pub token: String,
+pub struct UpdateDisclosureRequest {
+    pub disclosure: String,
+    pub search_radius_m: Option<String>,
 }
 
-/// LNURL-withdraw initial request for wallet balance
+/// Set the disclosure mode of a location (owner or admin)
 ///
-/// GET /api/wallet/lnurlw?token={signed_token}
+/// POST /api/locations/{location_id}/disclosure
 ///
-/// Returns LNURL-withdraw parameters for the user's wallet balance.
-/// This allows users to withdraw by scanning a QR code with their Lightning wallet.
-/// The token is signed with HMAC to authenticate the user without cookies.
-pub async fn wallet_lnurlw_request(
+/// A search area is drawn when there is none yet, and a new radius scales the
+/// existing one. Never drawing a second center stops hunters from narrowing the
+/// location down by overlapping areas.
+pub async fn update_location_disclosure(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<WalletLnurlwParams>,
-) -> Result<Json<LnurlWithdrawResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
-    tracing::info!("Wallet LNURL-withdraw request with token");
-
-    // Verify the signed token and extract user_id
-    let user_id =
-        verify_withdraw_token(&state.withdraw_secret, &params.token).ok_or_else(|| {
-            tracing::warn!("Invalid or expired withdraw token");
+    auth: RequireRegistered,
+    Path(location_id): Path<String>,
+    Form(payload): Form<UpdateDisclosureRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let location = state
+        .db
+        .get_location(&location_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
             (
-                StatusCode::UNAUTHORIZED,
-                Json(LnurlCallbackResponse::error(
-                    "Invalid or expired withdrawal link. Please refresh and try again.",
-                )),
-            )
-        })?;
-
-    tracing::info!("Wallet LNURL-withdraw request from user {}", user_id);
-
-    // Get user balance
-    let balance_msats = match state.db.get_user_balance(&user_id).await {
-        Ok(balance) => balance,
-        Err(e) => {
-            tracing::error!("Failed to get user balance: {}", e);
-            return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LnurlCallbackResponse::error("Failed to get balance.")),
-            ));
-        }
-    };
+                "Database error".to_string(),
+            )
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
 
-    // Calculate withdrawable amount after fees
-    let (max_withdraw_msats, _fee_msats) = calculate_withdrawal_fees(balance_msats);
+    let is_owner = location.user_id == auth.user_id && auth.has_role(UserRole::Creator);
+    if !is_owner && !auth.has_role(UserRole::Admin) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the owner can change how this location is shown".to_string(),
+        ));
+    }
 
-    // Check minimum withdrawal amount (need enough to cover fees + at least 1 sat)
-    if max_withdraw_msats < 1000 {
+    let disclosure: Disclosure = payload
+        .disclosure
+        .parse()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid disclosure".to_string()))?;
+    let radius_m: Option<i64> = parse_optional_field(&payload.search_radius_m)
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid search radius".to_string()))?;
+    if radius_m.is_some_and(|r| !(MIN_SEARCH_RADIUS_M..=MAX_SEARCH_RADIUS_M).contains(&r)) {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(LnurlCallbackResponse::error(
-                "Insufficient balance to cover fees. You need at least ~3 sats to withdraw.",
-            )),
+            format!(
+                "Search radius must be between {} and {} m",
+                MIN_SEARCH_RADIUS_M, MAX_SEARCH_RADIUS_M
+            ),
         ));
     }
 
-    // Round down to whole sats
-    let withdraw_sats = max_withdraw_msats / 1000;
-    let withdraw_msats = withdraw_sats * 1000;
+    let current = location.search_area();
+    let search_area = if disclosure.uses_search_area() {
+        let radius_m = radius_m
+            .or(current.map(|area| area.radius_m))
+            .unwrap_or(DEFAULT_SEARCH_RADIUS_M);
+        match current {
+            Some(area) if area.radius_m == radius_m => None,
+            Some(area) => Some(area.with_radius(location.latitude, location.longitude, radius_m)),
+            None => Some(SearchArea::around(
+                location.latitude,
+                location.longitude,
+                radius_m,
+                &mut rand::thread_rng(),
+            )),
+        }
+    } else {
+        None
+    };
 
-    // Build callback URL - pass the token through for the callback to verify
-    let callback = format!(
-        "{}/api/wallet/lnurlw/callback?token={}",
-        state.base_url,
-        urlencoding::encode(&params.token)
-    );
+    state
+        .db
+        .update_location_disclosure(&location.id, disclosure, search_area.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to set disclosure: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save disclosure".to_string(),
+            )
+        })?;
 
-    // k1 can be anything unique - we use user_id for logging purposes
-    let k1 = user_id.clone();
+    tracing::info!(
+        "User {} set disclosure of location {} to {} (new search area: {})",
+        auth.user_id,
+        location.id,
+        disclosure,
+        search_area.is_some()
+    );
 
-    Ok(Json(LnurlWithdrawResponse {
-        tag: "withdrawRequest".to_string(),
-        callback,
-        k1,
-        default_description: format!("Withdraw {} sats from SatsHunt wallet", withdraw_sats),
-        min_withdrawable: withdraw_msats,
-        max_withdrawable: withdraw_msats,
-    }))
+    Ok(StatusCode::OK)
 }
 
-/// Query parameters for wallet LNURL-withdraw callback
+/// Form for a new hint. Which of the unlock fields are needed depends on the
+/// unlock kind.
 #[derive(Debug, Deserialize)]
-pub struct WalletLnurlCallbackParams {
-    /// Signed withdrawal token (format: "user_id:timestamp:signature")
-    pub token: String,
-    /// k1 from initial request (for logging only)
-    pub k1: String,
-    /// BOLT11 invoice from wallet
-    pub pr: String,
+pub struct CreateHintRequest {
+    pub text: String,
+    pub unlock_kind: String,
+    pub unlock_after_days: Option<String>,
+    pub price_sats: Option<String>,
+    pub riddle: Option<String>,
+    pub riddle_answer: Option<String>,
 }
 
-/// LNURL-withdraw callback for wallet balance
-///
-/// GET /api/wallet/lnurlw/callback?token={signed_token}&k1={user_id}&pr={invoice}
+impl CreateHintRequest {
+    fn into_new_hint(self, location_id: &str) -> Result<NewHint, &'static str> {
+        let text = self.text.trim().to_string();
+        if text.is_empty() {
+            return Err("hint text is required");
+        }
+        let unlock_kind: HintUnlock = self.unlock_kind.parse().map_err(|_| "invalid unlock")?;
+        let mut hint = NewHint {
+            location_id: location_id.to_string(),
+            text,
+            unlock_kind,
+            unlock_after_days: None,
+            price_msats: None,
+            riddle: None,
+            riddle_answer: None,
+        };
+        match unlock_kind {
+            HintUnlock::Time => {
+                let days: i64 = parse_optional_field(&self.unlock_after_days)
+                    .map_err(|_| "invalid number of days")?
+                    .ok_or("number of days is required")?;
+                if days < 0 {
+                    return Err("number of days can't be negative");
+                }
+                hint.unlock_after_days = Some(days);
+            }
+            HintUnlock::Payment => {
+                let sats: i64 = parse_optional_field(&self.price_sats)
+                    .map_err(|_| "invalid price")?
+                    .ok_or("price is required")?;
+                if sats <= 0 {
+                    return Err("price must be positive");
+                }
+                hint.price_msats = Some(request_sats_to_msats(sats).ok_or("price is too large")?);
+            }
+            HintUnlock::Riddle => {
+                let riddle = self.riddle.as_deref().map(str::trim).unwrap_or_default();
+                let answer = self
+                    .riddle_answer
+                    .as_deref()
+                    .map(str::trim)
+                    .unwrap_or_default();
+                if riddle.is_empty() || answer.is_empty() {
+                    return Err("riddle and answer are required");
+                }
+                hint.riddle = Some(riddle.to_string());
+                hint.riddle_answer = Some(answer.to_string());
+            }
+        }
+        Ok(hint)
+    }
+}
+
+/// Add a hint to a location (owner or admin)
 ///
-/// Called by the user's Lightning wallet with the invoice to pay.
-pub async fn wallet_lnurlw_callback(
+/// POST /api/locations/{location_id}/hints
+pub async fn create_location_hint(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<WalletLnurlCallbackParams>,
-) -> Result<Json<LnurlCallbackResponse>, (StatusCode, Json<LnurlCallbackResponse>)> {
-    tracing::info!("Wallet LNURL-withdraw callback for k1 {}", params.k1);
-
-    // Verify the signed token and extract user_id
-    let user_id =
-        verify_withdraw_token(&state.withdraw_secret, &params.token).ok_or_else(|| {
-            tracing::warn!("Invalid or expired withdraw token in callback");
+    auth: RequireRegistered,
+    Path(location_id): Path<String>,
+    Form(payload): Form<CreateHintRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let location = state
+        .db
+        .get_location(&location_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get location: {}", e);
             (
-                StatusCode::UNAUTHORIZED,
-                Json(LnurlCallbackResponse::error(
-                    "Invalid or expired withdrawal link. Please refresh and try again.",
-                )),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error".to_string(),
             )
-        })?;
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
 
-    tracing::info!("Wallet LNURL-withdraw callback for user {}", user_id);
-    let invoice = params.pr.trim();
+    let is_owner = location.user_id == auth.user_id && auth.has_role(UserRole::Creator);
+    if !is_owner && !auth.has_role(UserRole::Admin) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "Only the owner can add hints to this location".to_string(),
+        ));
+    }
+
+    let new_hint = payload
+        .into_new_hint(&location.id)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid hint: {}", e)))?;
 
-    // Parse the invoice to get the amount
-    let parsed_invoice: lightning_invoice::Bolt11Invoice = invoice.parse().map_err(|e| {
-        tracing::error!("Failed to parse invoice: {}", e);
+    let hint = state.db.create_hint(&new_hint).await.map_err(|e| {
+        tracing::error!("Failed to create hint: {}", e);
         (
-            StatusCode::BAD_REQUEST,
-            Json(LnurlCallbackResponse::error(
-                "Invalid invoice format. Must be a valid Lightning invoice.",
-            )),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to save hint".to_string(),
         )
     })?;
 
-    // Get the invoice amount (we don't support amountless invoices)
-    let invoice_msats = parsed_invoice.amount_milli_satoshis().ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(LnurlCallbackResponse::error(
-                "Invoice must specify an amount.",
-            )),
-        )
-    })? as i64;
+    tracing::info!(
+        "User {} added {} hint {} to location {}",
+        auth.user_id,
+        hint.unlock_kind,
+        hint.id,
+        location.id
+    );
 
-    // Get user balance (already accounts for pending withdrawals)
-    let balance_msats = state.db.get_user_balance(&user_id).await.map_err(|e| {
-        tracing::error!("Failed to get user balance: {}", e);
+    Ok(StatusCode::CREATED)
+}
+
+/// Remove a hint from a location (owner or admin)
+///
+/// DELETE /api/hints/{hint_id}
+pub async fn delete_hint(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(hint_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let db_error = |e: anyhow::Error| {
+        tracing::error!("Failed to delete hint: {}", e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(LnurlCallbackResponse::error("Failed to get balance.")),
+            "Database error".to_string(),
         )
-    })?;
+    };
 
-    // Check if user has enough balance for invoice + fees
-    let fee_msats = check_invoice_with_fees(invoice_msats, balance_msats).map_err(|msg| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(LnurlCallbackResponse::error(&msg)),
+    let hint = state
+        .db
+        .get_hint(&hint_id)
+        .await
+        .map_err(db_error)?
+        .ok_or((StatusCode::NOT_FOUND, "Hint not found".to_string()))?;
+
+    if !auth.has_role(UserRole::Admin) {
+        let location = state
+            .db
+            .get_location(&hint.location_id)
+            .await
+            .map_err(db_error)?
+            .ok_or((StatusCode::NOT_FOUND, "Location not found".to_string()))?;
+        if location.user_id != auth.user_id || !auth.has_role(UserRole::Creator) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Only the owner can remove hints from this location".to_string(),
+            ));
+        }
+    }
+
+    state.db.delete_hint(&hint.id).await.map_err(db_error)?;
+
+    tracing::info!("User {} removed hint {}", auth.user_id, hint.id);
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnlockHintRequest {
+    pub answer: Option<String>,
+}
+
+/// Unlock a hint for the current user, by paying for it from their wallet or
+/// answering its riddle
+///
+/// POST /api/hints/{hint_id}/unlock
+pub async fn unlock_hint(
+    State(state): State<Arc<AppState>>,
+    user: CookieUser,
+    Path(hint_id): Path<String>,
+    Form(payload): Form<UnlockHintRequest>,
+) -> impl IntoResponse {
+    let hint = match state.db.get_hint(&hint_id).await {
+        Ok(Some(hint)) => hint,
+        Ok(None) => return (user.jar, StatusCode::NOT_FOUND).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to get hint: {}", e);
+            return (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let unlocked = match hint.unlock_kind {
+        HintUnlock::Time => {
+            return (
+                user.jar,
+                (
+                    StatusCode::BAD_REQUEST,
+                    "This hint unlocks by itself over time",
+                ),
+            )
+                .into_response();
+        }
+        HintUnlock::Payment => state.db.buy_hint(&hint, &user.user_id).await,
+        HintUnlock::Riddle => {
+            if !hint.is_answer(payload.answer.as_deref().unwrap_or_default()) {
+                return (user.jar, (StatusCode::BAD_REQUEST, "Wrong answer")).into_response();
+            }
+            state
+                .db
+                .unlock_hint(&hint.id, &user.user_id)
+                .await
+                .map(|_| true)
+        }
+    };
+
+    match unlocked {
+        Ok(true) => {
+            tracing::info!(
+                "User {} unlocked {} hint {}",
+                user.user_id,
+                hint.unlock_kind,
+                hint.id
+            );
+            (user.jar, StatusCode::OK).into_response()
+        }
+        Ok(false) => (
+            user.jar,
+            (StatusCode::BAD_REQUEST, "Not enough sats in your wallet"),
         )
-    })?;
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to unlock hint: {}", e);
+            (user.jar, StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
 
-    // Create pending withdrawal to reserve the balance (including fees)
-    let withdrawal_id = state
+/// Approve a held wallet withdrawal and pay it (admin only)
+///
+/// POST /api/admin/withdrawals/{withdrawal_id}/approve
+///
+/// If the payment fails, e.g. because the invoice expired while the withdrawal
+/// waited for review, the balance is returned to the user. If its outcome is
+/// unknown, the balance stays reserved until it is known.
+pub async fn approve_withdrawal(
+    State(state): State<Arc<AppState>>,
+    auth: RequireRegistered,
+    Path(withdrawal_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    auth.ensure_role(UserRole::Admin)
+        .map_err(|_| (StatusCode::FORBIDDEN, "Admins only".to_string()))?;
+
+    let withdrawal = state
         .db
-        .create_pending_withdrawal(&user_id, invoice_msats, fee_msats, invoice)
+        .approve_held_withdrawal(&withdrawal_id, &auth.user_id)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to create pending withdrawal: {}", e);
+            tracing::error!("Failed to approve withdrawal: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LnurlCallbackResponse::error(
-                    "Failed to process withdrawal. Please try again.",
-                )),
+                "Failed to approve withdrawal".to_string(),
             )
         })?
         .ok_or_else(|| {
             (
-                StatusCode::CONFLICT,
-                Json(LnurlCallbackResponse::error(
-                    "Insufficient balance. Please try again.",
-                )),
+                StatusCode::NOT_FOUND,
+                "Withdrawal is not waiting for review".to_string(),
             )
         })?;
 
-    // Pay the invoice
-    if let Err(e) = state.lightning.pay_invoice(invoice).await {
-        tracing::error!("Failed to pay invoice: {}", e);
-        // Mark withdrawal as failed to release the reserved balance
+    tracing::info!(
+        "Admin {} approved withdrawal {}",
+        auth.user_id,
+        withdrawal_id
+    );
+
+    let payment_failed = |e: &dyn std::fmt::Display| {
+        (
+            StatusCode::BAD_GATEWAY,
+            format!(
+                "Payment failed, the balance was returned to the user: {}",
+                e
+            ),
+        )
+    };
+
+    // Checked again, the invoice may have expired in the meantime
+    if let Err(e) = state
+        .invoice_policy
+        .check_payable(&withdrawal.invoice, state.lightning.as_ref())
+        .await
+    {
+        tracing::error!("Approved withdrawal {} can't be paid: {}", withdrawal_id, e);
         if let Err(e) = state.db.fail_pending_withdrawal(&withdrawal_id).await {
             tracing::error!("Failed to mark withdrawal as failed: {}", e);
         }
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(LnurlCallbackResponse::error(
-                "Payment failed. Please try again.",
-            )),
-        ));
+        return Err(payment_failed(&e));
     }
 
-    // Mark withdrawal as completed
-    if let Err(e) = state.db.complete_pending_withdrawal(&withdrawal_id).await {
-        tracing::error!("Failed to mark withdrawal as completed: {}", e);
-        // Payment succeeded but we couldn't update the status - log but don't fail
+    match pay_reserved_withdrawal(&state, &withdrawal_id, &withdrawal.invoice).await {
+        WithdrawalPayment::Paid => Ok(StatusCode::OK),
+        WithdrawalPayment::Failed(e) => Err(payment_failed(&e)),
+        // Settled by the auto-withdraw service once the outcome is known
+        WithdrawalPayment::Unconfirmed(_) => Ok(StatusCode::ACCEPTED),
     }
-
-    let withdrawn_sats = invoice_msats / 1000;
-    tracing::info!(
-        "Successful wallet LNURL-withdraw for user {}: {} sats",
-        user_id,
-        withdrawn_sats
-    );
-
-    Ok(Json(LnurlCallbackResponse::ok()))
-}
-
-// ============================================================================
-// Admin Endpoints
-// ============================================================================
-
-/// Request body for updating user role
-#[derive(Debug, Deserialize)]
-pub struct UpdateUserRoleRequest {
-    pub role: String,
 }
 
-/// Update a user's role (admin only)
+/// Reject a held wallet withdrawal, returning the balance to the user (admin only)
 ///
-/// POST /api/admin/users/{user_id}/role
-/// Body: { "role": "user" | "creator" | "admin" }
-pub async fn update_user_role(
+/// POST /api/admin/withdrawals/{withdrawal_id}/reject
+pub async fn reject_withdrawal(
     State(state): State<Arc<AppState>>,
     auth: RequireRegistered,
-    Path(user_id): Path<String>,
-    Form(payload): Form<UpdateUserRoleRequest>,
+    Path(withdrawal_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    // Require admin role
     auth.ensure_role(UserRole::Admin)
         .map_err(|_| StatusCode::FORBIDDEN)?;
 
-    tracing::info!(
-        "Admin {} updating role for user {} to {}",
-        auth.user_id,
-        user_id,
-        payload.role
-    );
-
-    // Parse the role
-    let new_role: UserRole = payload.role.parse().map_err(|_| {
-        tracing::warn!("Invalid role: {}", payload.role);
-        StatusCode::BAD_REQUEST
-    })?;
-
-    // Update the user's role
-    let result = state
+    let rejected = state
         .db
-        .update_user_role(&user_id, new_role)
+        .reject_held_withdrawal(&withdrawal_id, &auth.user_id)
         .await
         .map_err(|e| {
-            tracing::error!("Failed to update user role: {}", e);
+            tracing::error!("Failed to reject withdrawal: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    if result.rows_affected() == 0 {
-        tracing::warn!("User not found: {}", user_id);
+    if !rejected {
         return Err(StatusCode::NOT_FOUND);
     }
 
     tracing::info!(
-        "Admin {} updated user {} role to {}",
+        "Admin {} rejected withdrawal {}",
         auth.user_id,
-        user_id,
-        new_role
+        withdrawal_id
     );
 
     Ok(StatusCode::OK)
@@ -2415,8 +5712,8 @@ pub async fn deactivate_location(
             StatusCode::NOT_FOUND
         })?;
 
-    // Check if location is active (only active locations can be deactivated)
-    if !location.is_active() {
+    // Check if location is live (only active or scheduled locations can be deactivated)
+    if !location.is_live() {
         tracing::warn!("Location {} is not active", location_id);
         return Err(StatusCode::BAD_REQUEST);
     }
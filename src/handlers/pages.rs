@@ -4,9 +4,16 @@ use crate::{
         LoginRequest, RegisterRequest, UserKind,
     },
     balance::compute_balance_msats,
-    handlers::api::{create_withdraw_token, AppState},
-    models::{AuthMethod, UserRole},
-    ntag424, templates,
+    db::Database,
+    disclosure::Position,
+    handlers::api::{
+        build_location_forecast, create_withdraw_token, request_achievement_check, AppState,
+        LeaderboardQuery, LOGS_PER_PAGE,
+    },
+    lnurl,
+    models::{AuthMethod, Hunt, Location, UserRole},
+    ntag424, schedule, solvency,
+    templates::{self, components::LogBook},
 };
 use axum::{
     extract::{Path, Query, State},
@@ -14,7 +21,9 @@ use axum::{
     response::{Html, IntoResponse, Redirect, Response},
     Form,
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 
 #[derive(Deserialize)]
@@ -27,6 +36,7 @@ pub struct LocationDetailQuery {
     pub error: Option<String>,
     pub success: Option<String>,
     pub amount: Option<i64>,
+    pub logs_page: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -100,8 +110,44 @@ pub async fn map_page(
         location_balances.push((location, balance_msats / 1000, pool_msats / 1000));
     }
 
+    // Countdowns for locations with activation schedules: when the open window
+    // of an active location closes, and when hidden ones open next
+    let schedules = state
+        .db
+        .get_all_location_schedules()
+        .await
+        .unwrap_or_default();
+    let now = Utc::now();
+    let window_of = |location: &Location| {
+        schedules
+            .get(&location.id)
+            .and_then(|s| schedule::current_or_next_window(s, now))
+    };
+    let event_ends: BTreeMap<String, DateTime<Utc>> = locations
+        .iter()
+        .filter_map(|location| {
+            window_of(location)
+                .filter(|window| window.contains(now))
+                .map(|window| (location.id.clone(), window.ends_at))
+        })
+        .collect();
+    let mut upcoming: Vec<(Location, DateTime<Utc>)> = Vec::new();
+    for location in state
+        .db
+        .list_scheduled_locations()
+        .await
+        .unwrap_or_default()
+    {
+        if let Some(window) = window_of(&location) {
+            upcoming.push((location, window.starts_at));
+        }
+    }
+    upcoming.sort_by_key(|(_, starts_at)| *starts_at);
+
+    let positions = visible_positions(&state.db, &user, &locations).await;
+
     let display_name = get_navbar_display_name(&user);
-    let content = templates::map(&location_balances);
+    let content = templates::map(&location_balances, &event_ends, &upcoming, &positions);
     let page = templates::base_with_user(
         "Map",
         content,
@@ -113,6 +159,210 @@ pub async fn map_page(
     Ok(Html(page.into_string()))
 }
 
+pub async fn hunts_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, StatusCode> {
+    let hunts = hunt_summaries(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to list hunts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let display_name = get_navbar_display_name(&user);
+    let content = templates::hunts(&hunts);
+    let page = templates::base_with_user(
+        "Hunts",
+        content,
+        &display_name,
+        user.role(),
+        user.is_registered(),
+    );
+
+    Ok(Html(page.into_string()))
+}
+
+pub async fn leaderboard_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Html<String>, StatusCode> {
+    let regions = state.db.list_regions().await.map_err(|e| {
+        tracing::error!("Failed to list regions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let leaderboard_error = |e: anyhow::Error| {
+        tracing::error!("Failed to get leaderboard: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    let (entries, team_entries) = if query.teams() {
+        let team_entries = state
+            .db
+            .get_team_leaderboard(query.period(), query.region_id(), query.metric())
+            .await
+            .map_err(leaderboard_error)?;
+        (Vec::new(), team_entries)
+    } else {
+        let entries = state
+            .db
+            .get_leaderboard(query.period(), query.region_id(), query.metric())
+            .await
+            .map_err(leaderboard_error)?;
+        (entries, Vec::new())
+    };
+    let current_team_id = state
+        .db
+        .get_team_membership(&user.user_id)
+        .await
+        .unwrap_or(None)
+        .map(|m| m.team_id);
+
+    // Anonymous hunters choose whether they are ranked
+    let opt_in = if user.is_registered() {
+        None
+    } else {
+        state
+            .db
+            .get_user_by_id(&user.user_id)
+            .await
+            .unwrap_or(None)
+            .map(|u| u.leaderboard_opt_in)
+    };
+
+    let selection = templates::LeaderboardSelection {
+        period: query.period(),
+        metric: query.metric(),
+        region_id: query.region_id(),
+        teams: query.teams(),
+    };
+    let display_name = get_navbar_display_name(&user);
+    let content = templates::leaderboard(
+        &selection,
+        &entries,
+        &team_entries,
+        &regions,
+        query.period().start(Utc::now()),
+        &user.user_id,
+        current_team_id.as_deref(),
+        opt_in,
+        user.has_role(UserRole::Admin),
+    );
+    let page = templates::base_with_user(
+        "Leaderboard",
+        content,
+        &display_name,
+        user.role(),
+        user.is_registered(),
+    );
+
+    Ok(Html(page.into_string()))
+}
+
+pub async fn hunt_detail_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Html<String>, StatusCode> {
+    let hunt = state
+        .db
+        .get_hunt(&id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get hunt: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let stops = state.db.get_hunt_locations(&id).await.map_err(|e| {
+        tracing::error!("Failed to get hunt locations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let progress = state
+        .db
+        .get_hunt_progress(&hunt, &user.user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get hunt progress: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let pool_msats = state.db.get_hunt_pool_balance(&id).await.unwrap_or(0);
+
+    let completions = state.db.list_hunt_completions(&id).await.map_err(|e| {
+        tracing::error!("Failed to list hunt completions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let schedules = state.db.get_hunt_schedules(&id).await.unwrap_or_default();
+
+    let positions = visible_positions(&state.db, &user, &stops).await;
+    let positions: Vec<Position> = stops
+        .iter()
+        .map(|stop| positions.get(&stop.id).copied().unwrap_or(Position::Hidden))
+        .collect();
+
+    let display_name = get_navbar_display_name(&user);
+    let content = templates::hunt_detail(
+        &hunt,
+        &stops,
+        &positions,
+        &progress,
+        pool_msats / 1000,
+        &completions,
+        &user.user_id,
+        &schedules,
+    );
+    let page = templates::base_with_user(
+        &hunt.name,
+        content,
+        &display_name,
+        user.role(),
+        user.is_registered(),
+    );
+
+    Ok(Html(page.into_string()))
+}
+
+/// What the user may see of each location's position. Owners and admins see
+/// the exact position.
+async fn visible_positions(
+    db: &Database,
+    user: &CookieUser,
+    locations: &[Location],
+) -> BTreeMap<String, Position> {
+    let hint_unlocked = db
+        .list_hint_unlocked_location_ids(&user.user_id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to list unlocked hints: {}", e);
+            HashSet::new()
+        });
+    let is_admin = user.role() == UserRole::Admin;
+    locations
+        .iter()
+        .map(|location| {
+            let position = Position::of(
+                location,
+                is_admin || location.user_id == user.user_id,
+                hint_unlocked.contains(&location.id),
+            );
+            (location.id.clone(), position)
+        })
+        .collect()
+}
+
+/// Pair each hunt with its number of stops and its bonus pool in sats
+async fn hunt_summaries(db: &Database) -> anyhow::Result<Vec<(Hunt, usize, i64)>> {
+    let hunts = db.list_hunts().await?;
+    let mut summaries = Vec::with_capacity(hunts.len());
+    for hunt in hunts {
+        let stops = db.get_hunt_locations(&hunt.id).await?.len();
+        let pool_msats = db.get_hunt_pool_balance(&hunt.id).await.unwrap_or(0);
+        summaries.push((hunt, stops, pool_msats / 1000));
+    }
+    Ok(summaries)
+}
+
 pub async fn new_location_page(user: CookieUser) -> Result<Html<String>, Response> {
     let username = user.ensure_registered_with_role(UserRole::Creator)?;
     let content = templates::new_location();
@@ -173,6 +423,87 @@ pub async fn location_detail_page(
     // Get NFC card for wipe QR code (for owner/admin)
     let nfc_card = state.db.get_nfc_card_by_location(&id).await.unwrap_or(None);
 
+    // Balance forecast and claim history for the charts
+    let forecast_points = build_location_forecast(&state, &location, 14)
+        .await
+        .map(|f| f.points)
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to build forecast: {}", e);
+            Vec::new()
+        });
+    let claims = state
+        .db
+        .get_claims_for_location(&id)
+        .await
+        .unwrap_or_default();
+
+    let recurring = state
+        .db
+        .list_active_recurring_donations(Some(&id))
+        .await
+        .unwrap_or_default();
+
+    let schedules = state
+        .db
+        .get_location_schedules(&id)
+        .await
+        .unwrap_or_default();
+
+    let claim_rules = state
+        .db
+        .get_location_claim_rules(&id)
+        .await
+        .unwrap_or_default();
+
+    let hints = state.db.list_location_hints(&id).await.unwrap_or_default();
+    let unlocked_hint_ids = state
+        .db
+        .list_unlocked_hint_ids(&user.user_id, &id)
+        .await
+        .unwrap_or_default();
+
+    // Log book page, with hidden logs for the owner and admins
+    let can_moderate_logs = (location.user_id == user.user_id && user.has_role(UserRole::Creator))
+        || user.has_role(UserRole::Admin);
+    let total_logs = state
+        .db
+        .count_location_logs(&id, can_moderate_logs)
+        .await
+        .unwrap_or(0);
+    let logs_total_pages = (total_logs + LOGS_PER_PAGE - 1) / LOGS_PER_PAGE;
+    let logs_page = params
+        .logs_page
+        .unwrap_or(1)
+        .max(1)
+        .min(logs_total_pages.max(1));
+    let logs = state
+        .db
+        .list_location_logs(
+            &id,
+            can_moderate_logs,
+            LOGS_PER_PAGE,
+            (logs_page - 1) * LOGS_PER_PAGE,
+        )
+        .await
+        .unwrap_or_default();
+    let log_photos = state
+        .db
+        .list_location_log_photos(&id)
+        .await
+        .unwrap_or_default();
+
+    let now = Utc::now();
+    let hint_unlocked = !unlocked_hint_ids.is_empty()
+        || hints.iter().any(|h| {
+            h.unlocks_at(location.created_at)
+                .is_some_and(|at| at <= now)
+        });
+    let position = Position::of(
+        &location,
+        location.user_id == user.user_id || user.role() == UserRole::Admin,
+        hint_unlocked,
+    );
+
     let current_user_id = Some(user.user_id.as_str());
     let current_user_role = user.role();
     let display_name = get_navbar_display_name(&user);
@@ -191,6 +522,22 @@ pub async fn location_detail_page(
         &state.base_url,
         &donations,
         nfc_card.as_ref(),
+        &forecast_points,
+        &claims,
+        &recurring,
+        &schedules,
+        &claim_rules,
+        &state.claim_rules,
+        &hints,
+        &unlocked_hint_ids,
+        position,
+        &LogBook {
+            logs: &logs,
+            photos: &log_photos,
+            page: logs_page,
+            total_pages: logs_total_pages,
+            can_write: user.is_registered(),
+        },
     );
     let page = templates::base_with_user(
         &location.name,
@@ -255,11 +602,41 @@ pub async fn donate_page(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    let campaigns = state
+        .db
+        .list_active_matching_campaigns()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list campaigns: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let matches = state.db.list_donation_matches().await.map_err(|e| {
+        tracing::error!("Failed to list donation matches: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let recurring = state
+        .db
+        .list_active_recurring_donations(None)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list recurring donations: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let ln_address = lnurl::PayTarget::Global.ln_address(&state.base_url);
+
     let display_name = get_navbar_display_name(&user);
     let content = templates::donate(
         total_pool_msats / 1000,
         locations.len(),
         &received_donations,
+        &campaigns,
+        &matches,
+        &recurring,
+        &user.user_id,
+        ln_address.as_deref(),
     );
     let page = templates::base_with_user(
         "Donate",
@@ -361,6 +738,15 @@ pub async fn register(
             .into_response();
     }
 
+    // Donation Lightning addresses share the namespace with usernames
+    if lnurl::PayTarget::is_reserved(register_req.username.trim()) {
+        return (
+            user.jar,
+            Redirect::to("/register?error=Username%20is%20reserved"),
+        )
+            .into_response();
+    }
+
     // Validate password is not empty
     if register_req.password.is_empty() {
         return (
@@ -489,7 +875,19 @@ pub async fn profile_page(
         location_balances.push((location, balance_msats / 1000, pool_msats / 1000));
     }
 
-    let content = templates::profile(&db_user, &location_balances);
+    let notifications = state
+        .db
+        .list_notifications(&user.user_id, 20)
+        .await
+        .unwrap_or_default();
+
+    let badges = state
+        .db
+        .list_user_badges(&user.user_id)
+        .await
+        .unwrap_or_default();
+
+    let content = templates::profile(&db_user, &location_balances, &notifications, &badges);
     let display_name = db_user.display_name();
     let page = templates::base_with_user(
         "Profile",
@@ -502,6 +900,116 @@ pub async fn profile_page(
     Ok(Html(page.into_string()))
 }
 
+/// Team page - the user's team with members and team wallet, or a form to
+/// create a team when the user is not in one.
+pub async fn team_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered()?;
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("Failed to load team page: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    let membership = state
+        .db
+        .get_team_membership(&user.user_id)
+        .await
+        .map_err(internal_error)?;
+    let team = match &membership {
+        Some(m) => state
+            .db
+            .get_team(&m.team_id)
+            .await
+            .map_err(internal_error)?,
+        None => None,
+    };
+
+    let content = match (membership, team) {
+        (Some(me), Some(team)) => {
+            let members = state
+                .db
+                .list_team_members(&team.id)
+                .await
+                .map_err(internal_error)?;
+            let balance_msats = state
+                .db
+                .get_team_balance(&team.id)
+                .await
+                .map_err(internal_error)?;
+            let transactions = state
+                .db
+                .get_team_transactions(&team.id, 50)
+                .await
+                .map_err(internal_error)?;
+            let invite_url = format!("{}/teams/join/{}", state.base_url, team.invite_code);
+            templates::team(
+                &team,
+                &members,
+                &me,
+                balance_msats / 1000,
+                &transactions,
+                &invite_url,
+            )
+        }
+        _ => templates::no_team(),
+    };
+
+    let page =
+        templates::base_with_user("Team", content, username, user.role(), user.is_registered());
+    Ok(Html(page.into_string()))
+}
+
+/// Invite link landing page for joining a team.
+pub async fn team_join_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+    Path(invite_code): Path<String>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered()?;
+    let internal_error = |e: anyhow::Error| {
+        tracing::error!("Failed to load team invite: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    let team = state
+        .db
+        .get_team_by_invite_code(&invite_code)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let member_count = state
+        .db
+        .list_team_members(&team.id)
+        .await
+        .map_err(internal_error)?
+        .len();
+    let current_team = match state
+        .db
+        .get_team_membership(&user.user_id)
+        .await
+        .map_err(internal_error)?
+    {
+        Some(m) => state
+            .db
+            .get_team(&m.team_id)
+            .await
+            .map_err(internal_error)?,
+        None => None,
+    };
+
+    let content = templates::team_invite(&team, member_count, current_team.as_ref());
+    let page = templates::base_with_user(
+        "Join Team",
+        content,
+        username,
+        user.role(),
+        user.is_registered(),
+    );
+    Ok(Html(page.into_string()))
+}
+
 /// Withdraw/Collection page - displays the collection UI for the custodial wallet.
 ///
 /// The URL should contain picc_data and cmac query parameters from the NFC chip
@@ -544,6 +1052,27 @@ pub async fn withdraw_page(
     );
     let available_sats = available_msats / 1000;
 
+    // Claim rules of the location and whether they keep this user from claiming
+    let location_rules = state
+        .db
+        .get_location_claim_rules(&location_id)
+        .await
+        .unwrap_or_default();
+    let claim_rules = state.claim_rules.with_overrides(&location_rules);
+    let claim_times = state
+        .db
+        .get_user_claim_times(&user.user_id, &location_id)
+        .await
+        .unwrap_or_default();
+    let claim_block = claim_rules.check(&claim_times, Utc::now());
+    let is_first_claim = !state
+        .db
+        .has_user_claimed(&user.user_id)
+        .await
+        .unwrap_or(true);
+    let first_claim_bonus_sats =
+        claim_rules.first_claim_bonus_msats(is_first_claim, available_msats, pool_msats) / 1000;
+
     // Get user info from DB for template
     let db_user = state.db.get_user_by_id(&user.user_id).await.ok().flatten();
 
@@ -563,6 +1092,9 @@ pub async fn withdraw_page(
                 error: Some("Invalid NFC scan. Please scan the sticker again."),
                 is_new_user,
                 user: db_user.as_ref(),
+                claim_rules: &claim_rules,
+                claim_block: claim_block.as_ref(),
+                first_claim_bonus_sats,
             });
             let page = templates::base_with_user(
                 "Collect Sats",
@@ -629,6 +1161,8 @@ pub async fn withdraw_page(
                 .await
             {
                 Ok(Some(scan)) => {
+                    request_achievement_check(&state, &user.user_id);
+
                     // Success - show claim page with scan info
                     let content = templates::collect(templates::CollectParams {
                         location: &location,
@@ -638,6 +1172,9 @@ pub async fn withdraw_page(
                         error: params.error.as_deref(),
                         is_new_user,
                         user: db_user.as_ref(),
+                        claim_rules: &claim_rules,
+                        claim_block: claim_block.as_ref(),
+                        first_claim_bonus_sats,
                     });
                     let page = templates::base_with_user(
                         "Collect Sats",
@@ -660,6 +1197,9 @@ pub async fn withdraw_page(
                         ),
                         is_new_user,
                         user: db_user.as_ref(),
+                        claim_rules: &claim_rules,
+                        claim_block: claim_block.as_ref(),
+                        first_claim_bonus_sats,
                     });
                     let page = templates::base_with_user(
                         "Collect Sats",
@@ -702,6 +1242,9 @@ pub async fn withdraw_page(
                 error,
                 is_new_user,
                 user: db_user.as_ref(),
+                claim_rules: &claim_rules,
+                claim_block: claim_block.as_ref(),
+                first_claim_bonus_sats,
             });
             let page = templates::base_with_user(
                 "Collect Sats",
@@ -736,6 +1279,9 @@ pub async fn withdraw_page(
                 error: Some(error_message),
                 is_new_user,
                 user: db_user.as_ref(),
+                claim_rules: &claim_rules,
+                claim_block: claim_block.as_ref(),
+                first_claim_bonus_sats,
             });
             let page = templates::base_with_user(
                 "Collect Sats",
@@ -790,6 +1336,13 @@ pub async fn wallet_page(
             .map(|u| (&u.id, &u.username, &u.auth_method))
     );
 
+    // Balance after the fee reserved for withdrawing all of it
+    let withdrawable_sats = state.fee_policy.max_withdrawable(balance_msats).0 / 1000;
+    let min_withdraw_sats = state
+        .fee_policy
+        .min_withdrawable_balance_msats()
+        .map(|msats| (msats + 999) / 1000);
+
     // Generate LNURL-withdraw string if user has balance
     let lnurlw_string = if balance_sats > 0 {
         // Create a signed token for authentication (valid for 1 hour)
@@ -804,15 +1357,68 @@ pub async fn wallet_page(
         None
     };
 
+    // Registered users with an address-compatible username can receive payments
+    let ln_address = db_user
+        .as_ref()
+        .and_then(|u| u.username.as_deref())
+        .filter(|name| lnurl::is_valid_address_user(name) && !lnurl::PayTarget::is_reserved(name))
+        .and_then(|name| lnurl::PayTarget::User(name.to_string()).ln_address(&state.base_url));
+
+    let auto_withdraw = state
+        .db
+        .get_auto_withdraw_setting(&user.user_id)
+        .await
+        .ok()
+        .flatten();
+
+    // Reusable LNURL-withdraw link, if the user created one
+    let withdraw_link = state
+        .db
+        .get_active_wallet_withdraw_link(&user.user_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|link| {
+            let url = format!(
+                "{}/api/wallet/lnurlw/link/{}",
+                state.base_url, link.link_key
+            );
+            crate::lnurl::encode_lnurl(&url).ok()
+        });
+
     // Build content
+    let badges = state
+        .db
+        .list_user_badges(&user.user_id)
+        .await
+        .unwrap_or_default();
+
+    let team_wallet = match state
+        .db
+        .get_team_membership(&user.user_id)
+        .await
+        .unwrap_or(None)
+    {
+        Some(m) => state.db.get_team(&m.team_id).await.unwrap_or(None),
+        None => None,
+    }
+    .filter(|team| team.wallet_enabled);
+
     let content = templates::wallet(
         balance_sats,
+        withdrawable_sats,
+        min_withdraw_sats,
         &transactions,
         db_user.as_ref(),
         params.success.as_deref(),
         params.amount,
         params.location.as_deref(),
         lnurlw_string.as_deref(),
+        ln_address.as_deref(),
+        auto_withdraw.as_ref(),
+        withdraw_link.as_deref(),
+        &badges,
+        team_wallet.as_ref(),
     );
     let display_name = get_navbar_display_name(&user);
     let page = templates::base_with_user(
@@ -962,3 +1568,141 @@ pub async fn admin_scans_page(
 
     Ok(Html(page_html.into_string()))
 }
+
+/// Admin ledger page - verifies the double-entry ledger and lists recent entries
+pub async fn admin_ledger_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered_with_role(UserRole::Admin)?;
+
+    let report = state.db.verify_ledger().await.map_err(|e| {
+        tracing::error!("Failed to verify ledger: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    if !report.is_balanced() {
+        tracing::warn!(
+            "Ledger verification found {} discrepancies",
+            report.discrepancies.len()
+        );
+    }
+
+    let entries = state.db.list_ledger_entries(100).await.map_err(|e| {
+        tracing::error!("Failed to list ledger entries: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let content = templates::admin_ledger(&report, &entries);
+    let page = templates::base_with_user("Ledger", content, username, user.role(), true);
+
+    Ok(Html(page.into_string()))
+}
+
+/// Admin campaigns page - create donation matching campaigns and follow their budgets
+pub async fn admin_campaigns_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered_with_role(UserRole::Admin)?;
+
+    let campaigns = state.db.list_matching_campaigns().await.map_err(|e| {
+        tracing::error!("Failed to list campaigns: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let content = templates::admin_campaigns(&campaigns);
+    let page = templates::base_with_user("Campaigns", content, username, user.role(), true);
+
+    Ok(Html(page.into_string()))
+}
+
+/// Admin hunts page - create hunts and see their bonus pools
+pub async fn admin_hunts_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered_with_role(UserRole::Admin)?;
+
+    let hunts = hunt_summaries(&state.db).await.map_err(|e| {
+        tracing::error!("Failed to list hunts: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let locations = state.db.list_active_locations().await.map_err(|e| {
+        tracing::error!("Failed to get active locations: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let mut schedules = BTreeMap::new();
+    for (hunt, _, _) in &hunts {
+        let hunt_schedules = state
+            .db
+            .get_hunt_schedules(&hunt.id)
+            .await
+            .unwrap_or_default();
+        schedules.insert(hunt.id.clone(), hunt_schedules);
+    }
+
+    let content = templates::admin_hunts(&hunts, &locations, &schedules);
+    let page = templates::base_with_user("Hunts", content, username, user.role(), true);
+
+    Ok(Html(page.into_string()))
+}
+
+/// Admin withdrawals page - held wallet withdrawals waiting for review
+pub async fn admin_withdrawals_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered_with_role(UserRole::Admin)?;
+
+    let withdrawals = state.db.list_held_withdrawals().await.map_err(|e| {
+        tracing::error!("Failed to list held withdrawals: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    // Pair each withdrawal with who requested it
+    let mut held = Vec::with_capacity(withdrawals.len());
+    for withdrawal in withdrawals {
+        let user_name = state
+            .db
+            .get_user_by_id(&withdrawal.user_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|u| u.display_name())
+            .unwrap_or_else(|| withdrawal.user_id.clone());
+        held.push((withdrawal, user_name));
+    }
+
+    let content = templates::admin_withdrawals(&held);
+    let page = templates::base_with_user("Withdrawals", content, username, user.role(), true);
+
+    Ok(Html(page.into_string()))
+}
+
+/// Admin solvency page - compares the node balance with all liabilities
+pub async fn admin_solvency_page(
+    user: CookieUser,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    let username = user.ensure_registered_with_role(UserRole::Admin)?;
+
+    let report = solvency::build_report(&state.db, state.lightning.as_ref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to build solvency report: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+    if !report.is_solvent() {
+        tracing::warn!(
+            "Node balance is {} msats short of liabilities",
+            -report.surplus_msats
+        );
+    }
+
+    let content = templates::admin_solvency(&report);
+    let page = templates::base_with_user("Solvency", content, username, user.role(), true);
+
+    Ok(Html(page.into_string()))
+}
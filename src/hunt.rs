@@ -0,0 +1,110 @@
+//! Hunts: trails of locations with a completion bonus.
+//!
+//! Progress on a hunt is derived from the claimed scans of a user, only counting
+//! claims made after the hunt was created:
+//! - In an unordered hunt a stop is done once the user claimed it
+//! - In an ordered hunt a stop is done once the user claimed it after the
+//!   previous stop was done, so claiming the stops out of order doesn't count
+//!
+//! When a claim completes a hunt, `Database::claim_from_scan` pays the completion
+//! bonus from the hunt's pool in the same transaction.
+
+use chrono::{DateTime, Utc};
+
+/// Which stops of a hunt a user has done.
+///
+/// `stops` are the hunt's location ids in trail order, `claims` are the user's
+/// (location_id, claimed_at) pairs in any order.
+pub fn completed_stops(
+    stops: &[String],
+    claims: &[(String, DateTime<Utc>)],
+    ordered: bool,
+) -> Vec<bool> {
+    if !ordered {
+        return stops
+            .iter()
+            .map(|stop| claims.iter().any(|(location_id, _)| location_id == stop))
+            .collect();
+    }
+
+    let mut done = vec![false; stops.len()];
+    let mut previous_at: Option<DateTime<Utc>> = None;
+    for (i, stop) in stops.iter().enumerate() {
+        let claimed_at = claims
+            .iter()
+            .filter(|(location_id, at)| {
+                location_id == stop && previous_at.is_none_or(|previous| *at > previous)
+            })
+            .map(|(_, at)| *at)
+            .min();
+        match claimed_at {
+            Some(at) => {
+                done[i] = true;
+                previous_at = Some(at);
+            }
+            None => break,
+        }
+    }
+    done
+}
+
+/// Whether every stop of a hunt is done
+pub fn is_complete(stops: &[String], claims: &[(String, DateTime<Utc>)], ordered: bool) -> bool {
+    !stops.is_empty()
+        && completed_stops(stops, claims, ordered)
+            .iter()
+            .all(|done| *done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn stops() -> Vec<String> {
+        vec!["a".to_string(), "b".to_string(), "c".to_string()]
+    }
+
+    fn claim(location_id: &str, minutes: i64) -> (String, DateTime<Utc>) {
+        let start = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        (location_id.to_string(), start + Duration::minutes(minutes))
+    }
+
+    #[test]
+    fn test_unordered_hunt() {
+        let claims = vec![claim("c", 1), claim("a", 2)];
+        assert_eq!(
+            completed_stops(&stops(), &claims, false),
+            vec![true, false, true]
+        );
+        assert!(!is_complete(&stops(), &claims, false));
+
+        let claims = vec![claim("c", 1), claim("a", 2), claim("b", 3)];
+        assert!(is_complete(&stops(), &claims, false));
+    }
+
+    #[test]
+    fn test_ordered_hunt_requires_sequence() {
+        // b was claimed before a, so it doesn't count
+        let claims = vec![claim("b", 1), claim("a", 2), claim("c", 3)];
+        assert_eq!(
+            completed_stops(&stops(), &claims, true),
+            vec![true, false, false]
+        );
+        assert!(!is_complete(&stops(), &claims, true));
+
+        // Claiming b again after a continues the trail
+        let mut claims = claims;
+        claims.push(claim("b", 4));
+        claims.push(claim("c", 5));
+        assert!(is_complete(&stops(), &claims, true));
+    }
+
+    #[test]
+    fn test_empty_hunt_is_never_complete() {
+        assert!(!is_complete(&[], &[claim("a", 1)], false));
+        assert!(completed_stops(&stops(), &[], true).iter().all(|d| !d));
+    }
+}
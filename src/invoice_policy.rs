@@ -0,0 +1,212 @@
+//! Checks on BOLT11 invoices before they are paid.
+//!
+//! Every payout of user or location funds runs the invoice through
+//! `InvoicePolicy::check` before `Lightning::pay_invoice`. It rejects invoices:
+//! - for another network than the Lightning backend's
+//! - that have expired or don't specify an amount
+//! - created by ourselves (found by payment hash in the Lightning backend), which
+//!   would loop funds back into the service, or paying a blocked payee
+//! - with an unreasonably long description
+//! - whose payment hash is already being paid or was paid by a wallet withdrawal
+
+use crate::db::Database;
+use crate::lightning::Lightning;
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescriptionRef, Currency};
+use secp256k1::PublicKey;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Longest invoice description we pay, in characters
+pub const MAX_DESCRIPTION_CHARS: usize = 256;
+
+/// Reasons an invoice is refused. The messages are shown to users.
+#[derive(Debug, Error)]
+pub enum InvoicePolicyError {
+    #[error("Invalid invoice format: {0}")]
+    InvalidFormat(String),
+
+    #[error("Invoice is for {found}, but this server pays on {expected}.")]
+    WrongNetwork {
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[error("Invoice has expired. Please create a new one.")]
+    Expired,
+
+    #[error("Invoice must specify an amount.")]
+    MissingAmount,
+
+    #[error("Invoice pays a blocked node and can't be used for a withdrawal.")]
+    BlockedPayee,
+
+    #[error("Invoice was created by this server and can't be used for a withdrawal.")]
+    OwnInvoice,
+
+    #[error(
+        "Invoice description is longer than {} characters.",
+        MAX_DESCRIPTION_CHARS
+    )]
+    DescriptionTooLong,
+
+    #[error("This invoice has already been paid or is being paid.")]
+    DuplicatePayment,
+
+    #[error("Failed to check invoice: {0}")]
+    Internal(#[from] anyhow::Error),
+}
+
+/// An invoice that passed the policy
+#[derive(Debug, Clone)]
+pub struct CheckedInvoice {
+    pub amount_msats: i64,
+    /// Payment hash, hex encoded
+    pub payment_hash: String,
+}
+
+/// Which invoices we are willing to pay
+#[derive(Debug, Clone, Default)]
+pub struct InvoicePolicy {
+    /// Node keys whose invoices are refused
+    blocked_payees: HashSet<PublicKey>,
+}
+
+impl InvoicePolicy {
+    pub fn new(blocked_payees: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self {
+            blocked_payees: blocked_payees.into_iter().collect(),
+        }
+    }
+
+    /// Check an invoice against everything, including the backend's network and
+    /// own invoices and the wallet withdrawals in the database
+    pub async fn check(
+        &self,
+        invoice: &str,
+        lightning: &dyn Lightning,
+        db: &Database,
+    ) -> Result<CheckedInvoice, InvoicePolicyError> {
+        let checked = self.check_payable(invoice, lightning).await?;
+
+        if db
+            .has_withdrawal_for_payment_hash(&checked.payment_hash)
+            .await?
+        {
+            return Err(InvoicePolicyError::DuplicatePayment);
+        }
+
+        Ok(checked)
+    }
+
+    /// Check an invoice against the backend's network and own invoices, without
+    /// looking at earlier withdrawals
+    pub async fn check_payable(
+        &self,
+        invoice: &str,
+        lightning: &dyn Lightning,
+    ) -> Result<CheckedInvoice, InvoicePolicyError> {
+        let checked = self.check_invoice(invoice, lightning.network())?;
+
+        // Each invoice is signed by its own key, so our invoices are recognized by
+        // payment hash rather than payee
+        if lightning.is_own_invoice(invoice).await? {
+            return Err(InvoicePolicyError::OwnInvoice);
+        }
+
+        Ok(checked)
+    }
+
+    /// Check an invoice on its own, without asking the backend or looking at
+    /// earlier withdrawals
+    pub fn check_invoice(
+        &self,
+        invoice: &str,
+        network: Currency,
+    ) -> Result<CheckedInvoice, InvoicePolicyError> {
+        let parsed: Bolt11Invoice = invoice
+            .trim()
+            .parse()
+            .map_err(|e| InvoicePolicyError::InvalidFormat(e.to_string()))?;
+
+        if parsed.currency() != network {
+            return Err(InvoicePolicyError::WrongNetwork {
+                expected: network_name(network),
+                found: network_name(parsed.currency()),
+            });
+        }
+
+        if parsed.is_expired() {
+            return Err(InvoicePolicyError::Expired);
+        }
+
+        let amount_msats = match parsed.amount_milli_satoshis() {
+            Some(msats) if msats > 0 => msats as i64,
+            _ => return Err(InvoicePolicyError::MissingAmount),
+        };
+
+        let payee = parsed.recover_payee_pub_key();
+        if self.blocked_payees.contains(&payee) {
+            return Err(InvoicePolicyError::BlockedPayee);
+        }
+
+        if let Bolt11InvoiceDescriptionRef::Direct(description) = parsed.description() {
+            let description = description.clone().into_inner().to_string();
+            if description.chars().count() > MAX_DESCRIPTION_CHARS {
+                return Err(InvoicePolicyError::DescriptionTooLong);
+            }
+        }
+
+        Ok(CheckedInvoice {
+            amount_msats,
+            payment_hash: parsed.payment_hash().to_string(),
+        })
+    }
+}
+
+/// Payment hash of a BOLT11 invoice, hex encoded. None if it doesn't parse.
+pub fn payment_hash(invoice: &str) -> Option<String> {
+    let parsed: Bolt11Invoice = invoice.trim().parse().ok()?;
+    Some(parsed.payment_hash().to_string())
+}
+
+/// Parse a network name as used in configuration
+pub fn parse_network(name: &str) -> Option<Currency> {
+    match name.to_lowercase().as_str() {
+        "bitcoin" | "mainnet" => Some(Currency::Bitcoin),
+        "testnet" => Some(Currency::BitcoinTestnet),
+        "signet" => Some(Currency::Signet),
+        "regtest" => Some(Currency::Regtest),
+        "simnet" => Some(Currency::Simnet),
+        _ => None,
+    }
+}
+
+fn network_name(network: Currency) -> &'static str {
+    match network {
+        Currency::Bitcoin => "mainnet",
+        Currency::BitcoinTestnet => "testnet",
+        Currency::Signet => "signet",
+        Currency::Regtest => "regtest",
+        Currency::Simnet => "simnet",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network() {
+        assert_eq!(parse_network("bitcoin"), Some(Currency::Bitcoin));
+        assert_eq!(parse_network("Mainnet"), Some(Currency::Bitcoin));
+        assert_eq!(parse_network("regtest"), Some(Currency::Regtest));
+        assert_eq!(parse_network("litecoin"), None);
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        let result = InvoicePolicy::default().check_invoice("lnbc1000n1fake", Currency::Bitcoin);
+        assert!(matches!(result, Err(InvoicePolicyError::InvalidFormat(_))));
+        assert_eq!(payment_hash("lnbc1000n1fake"), None);
+    }
+}
@@ -0,0 +1,398 @@
+//! Leaderboards and seasonal rankings.
+//!
+//! This module handles:
+//! - The periods hunters are ranked over: the current week (from Monday), month
+//!   and season (calendar quarter), all in UTC
+//! - Ranking hunters and teams by sats collected, unique locations found and
+//!   first-finds
+//! - Periodically recomputing every leaderboard, globally and per region, into
+//!   the `leaderboard_entries` and `team_leaderboard_entries` caches the pages
+//!   read from
+//!
+//! A location counts as found when a hunter scanned it, whether or not there
+//! was anything to claim. A first-find is the first scan of a location by
+//! anyone. Owners scanning their own locations don't count. Registered users
+//! are ranked by default, anonymous users only once they opted in. Teams are
+//! ranked by what their current members found, each location counting once.
+
+use crate::db::Database;
+use crate::models::{HunterStats, LeaderboardEntry, Region, TeamLeaderboardEntry, TeamStats};
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// How often the leaderboards are recomputed
+const REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Hunters kept per leaderboard
+pub const LEADERBOARD_SIZE: usize = 50;
+
+/// Time span a leaderboard covers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    #[default]
+    Week,
+    Month,
+    Season,
+}
+
+impl Period {
+    pub const ALL: [Period; 3] = [Self::Week, Self::Month, Self::Season];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Week => "week",
+            Self::Month => "month",
+            Self::Season => "season",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Week => "THIS WEEK",
+            Self::Month => "THIS MONTH",
+            Self::Season => "THIS SEASON",
+        }
+    }
+
+    /// Start of the period containing `now`
+    pub fn start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.date_naive();
+        let first_day = match self {
+            Self::Week => today - Duration::days(today.weekday().num_days_from_monday() as i64),
+            Self::Month => today.with_day(1).unwrap_or(today),
+            Self::Season => {
+                let month = (today.month0() / 3) * 3 + 1;
+                NaiveDate::from_ymd_opt(today.year(), month, 1).unwrap_or(today)
+            }
+        };
+        first_day.and_time(chrono::NaiveTime::MIN).and_utc()
+    }
+}
+
+impl std::fmt::Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Period {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            "season" => Ok(Self::Season),
+            _ => Err(anyhow::anyhow!("Invalid period: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Period {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// What hunters are ranked by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    #[default]
+    Sats,
+    Locations,
+    FirstFinds,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Self::Sats, Self::Locations, Self::FirstFinds];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sats => "sats",
+            Self::Locations => "locations",
+            Self::FirstFinds => "first_finds",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Sats => "SATS COLLECTED",
+            Self::Locations => "LOCATIONS FOUND",
+            Self::FirstFinds => "FIRST FINDS",
+        }
+    }
+
+    /// A hunter's or team's score, sats for `Sats`
+    pub fn value(&self, stats: &impl Ranked) -> i64 {
+        match self {
+            Self::Sats => stats.msats_claimed() / 1000,
+            Self::Locations => stats.locations_found(),
+            Self::FirstFinds => stats.first_finds(),
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Metric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sats" => Ok(Self::Sats),
+            "locations" => Ok(Self::Locations),
+            "first_finds" => Ok(Self::FirstFinds),
+            _ => Err(anyhow::anyhow!("Invalid metric: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Metric {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Totals of a hunter or team over a leaderboard period
+pub trait Ranked {
+    fn display_name(&self) -> String;
+    fn msats_claimed(&self) -> i64;
+    fn locations_found(&self) -> i64;
+    fn first_finds(&self) -> i64;
+}
+
+impl Ranked for HunterStats {
+    fn display_name(&self) -> String {
+        HunterStats::display_name(self)
+    }
+
+    fn msats_claimed(&self) -> i64 {
+        self.msats_claimed
+    }
+
+    fn locations_found(&self) -> i64 {
+        self.locations_found
+    }
+
+    fn first_finds(&self) -> i64 {
+        self.first_finds
+    }
+}
+
+impl Ranked for TeamStats {
+    fn display_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn msats_claimed(&self) -> i64 {
+        self.msats_claimed
+    }
+
+    fn locations_found(&self) -> i64 {
+        self.locations_found
+    }
+
+    fn first_finds(&self) -> i64 {
+        self.first_finds
+    }
+}
+
+/// Hunters or teams ranked by `metric`, best first, at most `limit` of them.
+/// Those scoring zero are left out and ties share a rank (1, 2, 2, 4).
+pub fn rank<T: Ranked>(stats: &[T], metric: Metric, limit: usize) -> Vec<(i64, &T)> {
+    let mut sorted: Vec<&T> = stats.iter().filter(|s| metric.value(*s) > 0).collect();
+    sorted.sort_by(|a, b| {
+        metric
+            .value(*b)
+            .cmp(&metric.value(*a))
+            .then_with(|| a.display_name().cmp(&b.display_name()))
+    });
+
+    let mut ranked: Vec<(i64, &T)> = Vec::with_capacity(sorted.len().min(limit));
+    for (i, hunter) in sorted.into_iter().take(limit).enumerate() {
+        let rank = match ranked.last() {
+            Some((prev_rank, prev)) if metric.value(*prev) == metric.value(hunter) => *prev_rank,
+            _ => i as i64 + 1,
+        };
+        ranked.push((rank, hunter));
+    }
+    ranked
+}
+
+/// Recompute every hunter and team leaderboard at `now`. Returns the number
+/// of entries.
+pub async fn refresh(db: &Database, now: DateTime<Utc>) -> Result<usize> {
+    let regions = db.list_regions().await?;
+    let scopes: Vec<Option<&Region>> = std::iter::once(None)
+        .chain(regions.iter().map(Some))
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut team_entries = Vec::new();
+    for period in Period::ALL {
+        let since = period.start(now);
+        for region in &scopes {
+            let region_id = region.map(|r| r.id.clone()).unwrap_or_default();
+            let stats = db.get_hunter_stats(since, *region).await?;
+            let team_stats = db.get_team_stats(since, *region).await?;
+            for metric in Metric::ALL {
+                for (rank, hunter) in rank(&stats, metric, LEADERBOARD_SIZE) {
+                    entries.push(LeaderboardEntry {
+                        period,
+                        region_id: region_id.clone(),
+                        metric,
+                        rank,
+                        user_id: hunter.user_id.clone(),
+                        display_name: hunter.display_name(),
+                        value: metric.value(hunter),
+                        computed_at: now,
+                    });
+                }
+                for (rank, team) in rank(&team_stats, metric, LEADERBOARD_SIZE) {
+                    team_entries.push(TeamLeaderboardEntry {
+                        period,
+                        region_id: region_id.clone(),
+                        metric,
+                        rank,
+                        team_id: team.team_id.clone(),
+                        name: team.name.clone(),
+                        value: metric.value(team),
+                        computed_at: now,
+                    });
+                }
+            }
+        }
+    }
+
+    db.replace_leaderboard_entries(&entries).await?;
+    db.replace_team_leaderboard_entries(&team_entries).await?;
+    Ok(entries.len() + team_entries.len())
+}
+
+/// Background service that keeps the cached leaderboards fresh
+pub struct LeaderboardService {
+    db: Arc<Database>,
+}
+
+impl LeaderboardService {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// Start the service - recomputes the leaderboards right away, then every
+    /// ten minutes
+    pub async fn start(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            match refresh(&self.db, Utc::now()).await {
+                Ok(entries) => tracing::debug!("Leaderboards refreshed ({} entries)", entries),
+                Err(e) => tracing::error!("Failed to refresh leaderboards: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn hunter(name: &str, msats_claimed: i64, locations_found: i64) -> HunterStats {
+        HunterStats {
+            user_id: format!("{}-id", name),
+            username: Some(name.to_string()),
+            msats_claimed,
+            locations_found,
+            first_finds: 0,
+        }
+    }
+
+    #[test]
+    fn test_period_start() {
+        // A Sunday in November
+        let now = at("2026-11-15T18:30:00Z");
+        assert_eq!(Period::Week.start(now), at("2026-11-09T00:00:00Z"));
+        assert_eq!(Period::Month.start(now), at("2026-11-01T00:00:00Z"));
+        assert_eq!(Period::Season.start(now), at("2026-10-01T00:00:00Z"));
+
+        // Periods start at their own first instant
+        let monday = at("2026-01-05T00:00:00Z");
+        assert_eq!(Period::Week.start(monday), monday);
+        assert_eq!(
+            Period::Season.start(at("2026-03-31T23:59:59Z")),
+            at("2026-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_rank() {
+        let stats = vec![
+            hunter("carol", 5_000, 1),
+            hunter("alice", 20_000, 3),
+            hunter("bob", 5_000, 2),
+            hunter("dave", 500, 0),
+            hunter("erin", 1_000, 4),
+        ];
+
+        let ranked: Vec<(i64, &str)> = rank(&stats, Metric::Sats, 10)
+            .into_iter()
+            .map(|(rank, s)| (rank, s.username.as_deref().unwrap()))
+            .collect();
+        // Dave's 500 msats round down to 0 sats
+        assert_eq!(
+            ranked,
+            vec![(1, "alice"), (2, "bob"), (2, "carol"), (4, "erin")]
+        );
+
+        let ranked = rank(&stats, Metric::Locations, 2);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].1.user_id, "erin-id");
+
+        assert!(rank(&stats, Metric::FirstFinds, 10).is_empty());
+    }
+
+    #[test]
+    fn test_rank_teams() {
+        let team = |name: &str, first_finds| TeamStats {
+            team_id: format!("{}-id", name),
+            name: name.to_string(),
+            msats_claimed: 0,
+            locations_found: first_finds,
+            first_finds,
+        };
+        let stats = vec![team("owls", 2), team("foxes", 3), team("bees", 2)];
+
+        let ranked: Vec<(i64, &str)> = rank(&stats, Metric::FirstFinds, 10)
+            .into_iter()
+            .map(|(rank, t)| (rank, t.name.as_str()))
+            .collect();
+        assert_eq!(ranked, vec![(1, "foxes"), (2, "bees"), (2, "owls")]);
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        for period in Period::ALL {
+            assert_eq!(period.as_str().parse::<Period>().unwrap(), period);
+        }
+        for metric in Metric::ALL {
+            assert_eq!(metric.as_str().parse::<Metric>().unwrap(), metric);
+        }
+        assert!("year".parse::<Period>().is_err());
+    }
+}
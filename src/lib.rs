@@ -1,12 +1,30 @@
 // Library exports for integration tests
+pub mod achievements;
 pub mod auth;
+pub mod auto_withdraw;
 pub mod balance;
+pub mod campaign;
+pub mod claim_policy;
+pub mod claim_rules;
 pub mod config;
 pub mod db;
+pub mod disclosure;
 pub mod donation;
+pub mod fees;
 pub mod handlers;
+pub mod hunt;
+pub mod invoice_policy;
+pub mod leaderboard;
 pub mod lightning;
 pub mod lnurl;
 pub mod models;
+pub mod nostr;
 pub mod ntag424;
+pub mod nwc;
+pub mod receive;
+pub mod recurring;
+pub mod schedule;
+pub mod solvency;
 pub mod templates;
+pub mod withdraw_limits;
+pub mod zap;
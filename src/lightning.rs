@@ -1,7 +1,10 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use blitzi::{Amount, Blitzi, BlitziBuilder};
+use lightning_invoice::{Bolt11Invoice, Currency};
+use sha2::{Digest, Sha256};
 use std::path::Path;
+use std::time::Duration;
 
 /// Trait for Lightning Network operations
 /// Allows mocking in tests where Blitzi (which requires live funds) cannot be used
@@ -10,26 +13,113 @@ pub trait Lightning: Send + Sync {
     /// Create a Lightning invoice for receiving payment
     async fn create_invoice(&self, amount_sats: u64, description: &str) -> Result<String>;
 
+    /// Whether the backend can create invoices with a description hash. The
+    /// method below fails when this is false.
+    fn supports_description_hash(&self) -> bool {
+        false
+    }
+
+    /// Create an invoice that commits to the SHA256 of `description` instead of
+    /// carrying it, as LNURL-pay (LUD-06) and zaps (NIP-57) require
+    async fn create_invoice_with_description_hash(
+        &self,
+        _amount_sats: u64,
+        _description: &str,
+    ) -> Result<String> {
+        anyhow::bail!("Description hash invoices are not supported by this Lightning backend")
+    }
+
     /// Pay an invoice (send sats to user)
     async fn pay_invoice(&self, invoice: &str) -> Result<()>;
 
+    /// Probe the route to an invoice's payee and estimate the routing fee in msats.
+    /// None if the backend can't probe routes.
+    async fn estimate_fee_msats(&self, _invoice: &str) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Pay an invoice and report the routing fee paid in msats, if the backend
+    /// knows it
+    async fn pay_invoice_with_fee(&self, invoice: &str) -> Result<Option<u64>> {
+        self.pay_invoice(invoice).await?;
+        Ok(None)
+    }
+
+    /// Look up how an earlier attempt to pay an invoice ended, e.g. after
+    /// `pay_invoice` failed with a timeout and the payment may still go through
+    async fn payment_status(&self, _invoice: &str) -> Result<PaymentStatus> {
+        Ok(PaymentStatus::Unknown)
+    }
+
     /// Wait for an invoice to be paid
     async fn await_payment(&self, invoice: &str) -> Result<()>;
+
+    /// Total funds currently held by the node, in msats
+    async fn node_balance_msats(&self) -> Result<u64>;
+
+    /// Network the backend pays and receives on
+    fn network(&self) -> Currency {
+        Currency::Bitcoin
+    }
+
+    /// Whether an invoice was created by this backend. Paying it would send
+    /// funds back to ourselves.
+    async fn is_own_invoice(&self, _invoice: &str) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// Outcome of an earlier payment attempt, from `Lightning::payment_status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentStatus {
+    /// Paid, with the routing fee in msats if the backend knows it
+    Succeeded { fee_msats: Option<u64> },
+    /// Never sent, or failed with the funds returned
+    Failed,
+    /// Still in flight, or the backend can't tell
+    Unknown,
+}
+
+/// How long `payment_status` waits for an earlier payment to reach a final state
+const PAYMENT_STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long `is_own_invoice` waits for Blitzi to look up an invoice
+const OWN_INVOICE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Final payment states in which Blitzi's payment errors report that the funds
+/// were returned (see `Blitzi::pay`)
+const FAILED_PAYMENT_STATES: [&str; 5] = [
+    "Canceled",
+    "Refunded",
+    "WaitingForRefund",
+    "RefundSuccess",
+    "FundingFailed",
+];
+
+/// Whether a payment error from Blitzi reports a payment that failed for good,
+/// as opposed to one that may still go through
+fn is_failed_payment(error: &anyhow::Error) -> bool {
+    error
+        .to_string()
+        .strip_prefix("Payment failed: ")
+        .is_some_and(|state| FAILED_PAYMENT_STATES.iter().any(|s| state.starts_with(s)))
 }
 
 /// Lightning service for managing payments (production implementation using Blitzi)
 pub struct LightningService {
     client: Blitzi,
+    /// Network of the federation Blitzi is connected to
+    network: Currency,
 }
 
 impl LightningService {
-    pub async fn new(data_dir: &Path) -> Result<Self> {
+    pub async fn new(data_dir: &Path, network: Currency) -> Result<Self> {
         let client = BlitziBuilder::default().datadir(data_dir).build().await?;
         tracing::info!(
             "Blitzi Lightning client initialized with data dir: {}",
             data_dir.display()
         );
-        Ok(Self { client })
+        Ok(Self { client, network })
     }
 
     /// Generate a unique secret for a location's LNURL-w
@@ -42,13 +132,21 @@ impl LightningService {
 impl Lightning for LightningService {
     async fn create_invoice(&self, amount_sats: u64, description: &str) -> Result<String> {
         let amount = Amount::from_sats(amount_sats);
-        let invoice = self.client.lightning_invoice(amount, description).await?;
+        let invoice = self
+            .client
+            .lightning_invoice(amount, description)
+            .await?
+            .to_string();
         tracing::info!("Created invoice for {} sats: {}", amount_sats, description);
-        Ok(invoice.to_string())
+        Ok(invoice)
     }
 
+    // Blitzi only creates invoices with a plain description, so the description
+    // hash method keeps its unsupported default.
+
     async fn pay_invoice(&self, invoice: &str) -> Result<()> {
         let bolt11 = invoice
+            .trim()
             .parse()
             .map_err(|e| anyhow::anyhow!("Invalid invoice format: {}", e))?;
 
@@ -61,8 +159,31 @@ impl Lightning for LightningService {
         Ok(())
     }
 
+    async fn payment_status(&self, invoice: &str) -> Result<PaymentStatus> {
+        let bolt11 = invoice
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid invoice format: {}", e))?;
+
+        // Blitzi resumes an earlier payment of the same invoice instead of paying
+        // it again, so paying again reports how that payment ended. If the earlier
+        // attempt never got to start a payment, this starts it.
+        let status =
+            match tokio::time::timeout(PAYMENT_STATUS_TIMEOUT, self.client.pay(&bolt11)).await {
+                Ok(Ok(_)) => PaymentStatus::Succeeded { fee_msats: None },
+                Ok(Err(e)) if is_failed_payment(&e) => PaymentStatus::Failed,
+                Ok(Err(e)) => {
+                    tracing::warn!("Payment status of invoice unknown: {}", e);
+                    PaymentStatus::Unknown
+                }
+                Err(_) => PaymentStatus::Unknown,
+            };
+        Ok(status)
+    }
+
     async fn await_payment(&self, invoice: &str) -> Result<()> {
         let invoice_obj = invoice
+            .trim()
             .parse()
             .map_err(|e| anyhow::anyhow!("Invalid invoice format: {}", e))?;
 
@@ -70,6 +191,42 @@ impl Lightning for LightningService {
         tracing::info!("Payment received for invoice");
         Ok(())
     }
+
+    async fn node_balance_msats(&self) -> Result<u64> {
+        Ok(self.client.balance().await.msats)
+    }
+
+    fn network(&self) -> Currency {
+        self.network
+    }
+
+    async fn is_own_invoice(&self, invoice: &str) -> Result<bool> {
+        let bolt11: Bolt11Invoice = invoice
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid invoice format: {}", e))?;
+
+        // Blitzi tracks the invoices it created by payment hash and refuses right
+        // away to wait for any other invoice. Waiting on one of ours returns once
+        // it is paid or canceled, or keeps waiting while it is open.
+        match tokio::time::timeout(
+            OWN_INVOICE_TIMEOUT,
+            self.client
+                .await_incoming_payment_by_hash(bolt11.payment_hash()),
+        )
+        .await
+        {
+            Ok(Err(e)) => {
+                let message = e.to_string();
+                Ok(!(message.starts_with("No operation found")
+                    || message.starts_with("Operation associated")))
+            }
+            Ok(Ok(())) | Err(_) => Ok(true),
+        }
+    }
+
+    // Blitzi doesn't report gateway fees, so withdrawals reserve fees by the fee
+    // policy.
 }
 
 /// Mock Lightning service for testing (does not require Blitzi or live funds)
@@ -79,6 +236,17 @@ pub struct MockLightning {
     pub pay_error: Option<String>,
     /// If set, await_payment will return this error
     pub await_error: Option<String>,
+    /// Balance reported by node_balance_msats
+    pub balance_msats: u64,
+    /// Routing fee reported by estimate_fee_msats (None: can't probe)
+    pub fee_estimate_msats: Option<u64>,
+    /// Routing fee reported by pay_invoice_with_fee (None: unknown)
+    pub paid_fee_msats: Option<u64>,
+    /// Invoices reported as created by this backend by is_own_invoice
+    pub own_invoices: Vec<String>,
+    /// Reported by payment_status. If unset, failed payments report Failed and
+    /// the others Succeeded.
+    pub payment_status: Option<PaymentStatus>,
 }
 
 impl MockLightning {
@@ -91,7 +259,26 @@ impl MockLightning {
     pub fn with_pay_error(error: impl Into<String>) -> Self {
         Self {
             pay_error: Some(error.into()),
-            await_error: None,
+            ..Self::default()
+        }
+    }
+
+    /// Create a MockLightning that reports the given node balance
+    #[allow(dead_code)]
+    pub fn with_balance(balance_msats: u64) -> Self {
+        Self {
+            balance_msats,
+            ..Self::default()
+        }
+    }
+
+    /// Create a MockLightning that estimates and pays the given routing fees
+    #[allow(dead_code)]
+    pub fn with_fees(estimate_msats: u64, paid_msats: u64) -> Self {
+        Self {
+            fee_estimate_msats: Some(estimate_msats),
+            paid_fee_msats: Some(paid_msats),
+            ..Self::default()
         }
     }
 }
@@ -103,6 +290,21 @@ impl Lightning for MockLightning {
         Ok(format!("lnbc{}n1mock{}", amount_sats, description.len()))
     }
 
+    fn supports_description_hash(&self) -> bool {
+        true
+    }
+
+    async fn create_invoice_with_description_hash(
+        &self,
+        amount_sats: u64,
+        description: &str,
+    ) -> Result<String> {
+        // Fake invoice carrying the start of the description hash instead
+        let hash = Sha256::digest(description.as_bytes());
+        let hash = hex::encode(&hash[..4]);
+        Ok(format!("lnbc{}n1mockh{}", amount_sats, hash))
+    }
+
     async fn pay_invoice(&self, invoice: &str) -> Result<()> {
         if let Some(ref err) = self.pay_error {
             return Err(anyhow::anyhow!("{}", err));
@@ -111,6 +313,28 @@ impl Lightning for MockLightning {
         Ok(())
     }
 
+    async fn estimate_fee_msats(&self, _invoice: &str) -> Result<Option<u64>> {
+        Ok(self.fee_estimate_msats)
+    }
+
+    async fn pay_invoice_with_fee(&self, invoice: &str) -> Result<Option<u64>> {
+        self.pay_invoice(invoice).await?;
+        Ok(self.paid_fee_msats)
+    }
+
+    async fn is_own_invoice(&self, invoice: &str) -> Result<bool> {
+        Ok(self.own_invoices.iter().any(|own| own == invoice.trim()))
+    }
+
+    async fn payment_status(&self, _invoice: &str) -> Result<PaymentStatus> {
+        Ok(self.payment_status.unwrap_or(match self.pay_error {
+            Some(_) => PaymentStatus::Failed,
+            None => PaymentStatus::Succeeded {
+                fee_msats: self.paid_fee_msats,
+            },
+        }))
+    }
+
     async fn await_payment(&self, invoice: &str) -> Result<()> {
         if let Some(ref err) = self.await_error {
             return Err(anyhow::anyhow!("{}", err));
@@ -121,6 +345,10 @@ impl Lightning for MockLightning {
         );
         Ok(())
     }
+
+    async fn node_balance_msats(&self) -> Result<u64> {
+        Ok(self.balance_msats)
+    }
 }
 
 #[cfg(test)]
@@ -149,6 +377,22 @@ mod tests {
         assert!(invoice.contains("1000"));
     }
 
+    #[tokio::test]
+    async fn test_mock_lightning_description_hash_invoice() {
+        let mock = MockLightning::new();
+        let a = mock
+            .create_invoice_with_description_hash(1000, "metadata a")
+            .await
+            .unwrap();
+        let b = mock
+            .create_invoice_with_description_hash(1000, "metadata b")
+            .await
+            .unwrap();
+
+        assert!(a.starts_with("lnbc1000n1"));
+        assert_ne!(a, b);
+    }
+
     #[tokio::test]
     async fn test_mock_lightning_pay_invoice_success() {
         let mock = MockLightning::new();
@@ -173,4 +417,31 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_mock_lightning_node_balance() {
+        assert_eq!(MockLightning::new().node_balance_msats().await.unwrap(), 0);
+
+        let mock = MockLightning::with_balance(42_000);
+        assert_eq!(mock.node_balance_msats().await.unwrap(), 42_000);
+    }
+
+    #[test]
+    fn test_is_failed_payment() {
+        assert!(is_failed_payment(&anyhow::anyhow!(
+            "Payment failed: Canceled"
+        )));
+        assert!(is_failed_payment(&anyhow::anyhow!(
+            "Payment failed: Refunded {{ gateway_error: None }}"
+        )));
+        assert!(is_failed_payment(&anyhow::anyhow!(
+            "Payment failed: FundingFailed {{ error: Timeout }}"
+        )));
+        assert!(!is_failed_payment(&anyhow::anyhow!(
+            "Payment failed: UnexpectedError {{ message: \"\" }}"
+        )));
+        assert!(!is_failed_payment(&anyhow::anyhow!(
+            "No LN gateway available"
+        )));
+    }
 }
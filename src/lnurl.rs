@@ -1,12 +1,22 @@
 //! LNURL protocol implementation.
 //!
 //! This module handles:
-//! - Resolving Lightning Addresses (user@domain.com format) to BOLT11 invoices (LUD-16)
+//! - Resolving Lightning Addresses (user@domain.com format) to BOLT11 invoices (LUD-16),
+//!   with a client hardened against user-supplied addresses (SSRF, oversized or
+//!   mismatching responses)
 //! - Encoding URLs to LNURL bech32 format (LUD-01)
+//! - Naming and describing our own Lightning addresses for donations and user
+//!   wallets (LUD-06, LUD-16)
 
 use bech32::{Bech32, Hrp};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use thiserror::Error;
+use tokio::net::lookup_host;
 
 /// Errors that can occur during LN address resolution
 #[derive(Debug, Error)]
@@ -23,6 +33,9 @@ pub enum LnurlError {
     #[error("Invalid LNURL-pay response: {0}")]
     InvalidResponse(String),
 
+    #[error("Refusing to contact non-public host {0}")]
+    ForbiddenHost(String),
+
     #[error("HTTP request failed: {0}")]
     HttpError(#[from] reqwest::Error),
 }
@@ -50,6 +63,22 @@ pub struct LnurlPayResponse {
     /// Optional comment allowed length
     #[serde(rename = "commentAllowed", default)]
     pub comment_allowed: Option<i64>,
+
+    /// Whether zap requests are accepted (NIP-57)
+    #[serde(
+        rename = "allowsNostr",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub allows_nostr: Option<bool>,
+
+    /// Public key zap receipts are signed with (NIP-57)
+    #[serde(
+        rename = "nostrPubkey",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub nostr_pubkey: Option<String>,
 }
 
 /// Response from LNURL-pay callback with invoice
@@ -77,7 +106,7 @@ pub struct LnurlErrorResponse {
 /// Parse a Lightning Address into user and domain parts.
 ///
 /// Lightning addresses follow the format: user@domain.com
-fn parse_ln_address(address: &str) -> Result<(String, String), LnurlError> {
+pub fn parse_ln_address(address: &str) -> Result<(String, String), LnurlError> {
     let address = address.trim().to_lowercase();
     let parts: Vec<&str> = address.split('@').collect();
 
@@ -103,115 +132,395 @@ fn parse_ln_address(address: &str) -> Result<(String, String), LnurlError> {
     Ok((user.to_string(), domain.to_string()))
 }
 
-/// Resolve a Lightning Address to its LNURL-pay metadata.
+/// Limits for the HTTP requests of an [`LnurlClient`]
+#[derive(Debug, Clone)]
+pub struct LnurlClientConfig {
+    /// Allow servers on private, loopback and link-local addresses (tests only)
+    pub allow_private_ips: bool,
+    /// Contact Lightning address servers over plain HTTP instead of HTTPS (tests only)
+    pub allow_http: bool,
+    /// Timeout of each request, including reading the response
+    pub timeout: Duration,
+    /// Responses larger than this many bytes are rejected
+    pub max_response_bytes: usize,
+    /// How long LNURL-pay metadata of an address is reused
+    pub cache_ttl: Duration,
+}
+
+impl Default for LnurlClientConfig {
+    fn default() -> Self {
+        Self {
+            allow_private_ips: false,
+            allow_http: false,
+            timeout: Duration::from_secs(10),
+            max_response_bytes: 64 * 1024,
+            cache_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Client for paying Lightning addresses given by users.
 ///
-/// This fetches the LNURL-pay endpoint at:
-/// `https://{domain}/.well-known/lnurlp/{user}`
-pub async fn resolve_ln_address(address: &str) -> Result<LnurlPayResponse, LnurlError> {
-    let (user, domain) = parse_ln_address(address)?;
+/// Addresses are arbitrary user input, so the client only talks to public IPs
+/// (pinning the addresses it checked, so a second DNS lookup can't swap them),
+/// doesn't follow redirects, limits response size and time, and checks that
+/// the invoice it gets matches the amount and metadata it asked for.
+pub struct LnurlClient {
+    config: LnurlClientConfig,
+    /// LNURL-pay metadata by Lightning address, with the time it was fetched
+    cache: Mutex<HashMap<String, (Instant, LnurlPayResponse)>>,
+}
 
-    let url = format!("https://{}/.well-known/lnurlp/{}", domain, user);
+impl LnurlClient {
+    pub fn new(config: LnurlClientConfig) -> Self {
+        Self {
+            config,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
 
-    tracing::info!("Resolving LN address {}@{} via {}", user, domain, url);
+    /// Resolve a Lightning Address to its LNURL-pay metadata.
+    ///
+    /// This fetches the LNURL-pay endpoint at:
+    /// `https://{domain}/.well-known/lnurlp/{user}`
+    pub async fn resolve_ln_address(&self, address: &str) -> Result<LnurlPayResponse, LnurlError> {
+        let (user, domain) = parse_ln_address(address)?;
+        let key = format!("{}@{}", user, domain);
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
-        .await?;
+        if let Some(cached) = self.cached(&key) {
+            return Ok(cached);
+        }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        return Err(LnurlError::ResolutionFailed(format!(
-            "HTTP {}: {}",
-            status, body
-        )));
+        let scheme = if self.config.allow_http {
+            "http"
+        } else {
+            "https"
+        };
+        let url = format!("{}://{}/.well-known/lnurlp/{}", scheme, domain, user);
+
+        tracing::info!("Resolving LN address {}@{} via {}", user, domain, url);
+
+        let body = self.fetch(&url).await?;
+        let lnurl_pay: LnurlPayResponse = serde_json::from_slice(&body)
+            .map_err(|e| LnurlError::InvalidResponse(format!("failed to parse response: {}", e)))?;
+        self.validate_pay_response(&lnurl_pay)?;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < self.config.cache_ttl);
+        cache.insert(key, (Instant::now(), lnurl_pay.clone()));
+
+        Ok(lnurl_pay)
     }
 
-    let lnurl_pay: LnurlPayResponse = response
-        .json()
-        .await
-        .map_err(|e| LnurlError::InvalidResponse(format!("failed to parse response: {}", e)))?;
+    /// Get a BOLT11 invoice from the LNURL-pay callback.
+    ///
+    /// Calls the callback URL with the specified amount to receive an invoice, and
+    /// checks the invoice is for that amount and commits to the metadata (LUD-06).
+    pub async fn get_invoice(
+        &self,
+        lnurl_pay: &LnurlPayResponse,
+        amount_msats: i64,
+    ) -> Result<String, LnurlError> {
+        let mut url = url::Url::parse(&lnurl_pay.callback)
+            .map_err(|e| LnurlError::InvalidResponse(format!("invalid callback URL: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("amount", &amount_msats.to_string());
+
+        tracing::info!("Requesting invoice from callback: {}", url);
+
+        let body = self.fetch(url.as_str()).await?;
+
+        // Errors may come with any HTTP status
+        if let Ok(error_response) = serde_json::from_slice::<LnurlErrorResponse>(&body) {
+            if error_response.status.eq_ignore_ascii_case("ERROR") {
+                return Err(LnurlError::ResolutionFailed(error_response.reason));
+            }
+        }
 
-    if lnurl_pay.tag != "payRequest" {
-        return Err(LnurlError::InvalidResponse(format!(
-            "expected tag 'payRequest', got '{}'",
-            lnurl_pay.tag
-        )));
+        let callback_response: LnurlPayCallbackResponse =
+            serde_json::from_slice(&body).map_err(|e| {
+                LnurlError::InvalidResponse(format!("failed to parse callback response: {}", e))
+            })?;
+
+        if callback_response.pr.is_empty() {
+            return Err(LnurlError::InvalidResponse(
+                "empty payment request in response".to_string(),
+            ));
+        }
+
+        check_invoice(&callback_response.pr, amount_msats, &lnurl_pay.metadata)?;
+
+        Ok(callback_response.pr)
     }
 
-    Ok(lnurl_pay)
-}
+    /// Resolve a Lightning Address and get an invoice for the specified amount.
+    ///
+    /// This is a convenience function that combines `resolve_ln_address` and `get_invoice`.
+    pub async fn get_invoice_for_ln_address(
+        &self,
+        address: &str,
+        amount_msats: i64,
+    ) -> Result<String, LnurlError> {
+        let lnurl_pay = self.resolve_ln_address(address).await?;
+
+        // Validate amount is within range
+        if amount_msats < lnurl_pay.min_sendable || amount_msats > lnurl_pay.max_sendable {
+            return Err(LnurlError::AmountOutOfRange {
+                amount: amount_msats,
+                min: lnurl_pay.min_sendable,
+                max: lnurl_pay.max_sendable,
+            });
+        }
+
+        self.get_invoice(&lnurl_pay, amount_msats).await
+    }
+
+    fn cached(&self, key: &str) -> Option<LnurlPayResponse> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(key)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.config.cache_ttl)
+            .map(|(_, lnurl_pay)| lnurl_pay.clone())
+    }
+
+    /// Check the metadata response is a usable pay request (LUD-06)
+    fn validate_pay_response(&self, lnurl_pay: &LnurlPayResponse) -> Result<(), LnurlError> {
+        if lnurl_pay.tag != "payRequest" {
+            return Err(LnurlError::InvalidResponse(format!(
+                "expected tag 'payRequest', got '{}'",
+                lnurl_pay.tag
+            )));
+        }
+
+        let callback = url::Url::parse(&lnurl_pay.callback)
+            .map_err(|e| LnurlError::InvalidResponse(format!("invalid callback URL: {}", e)))?;
+        if callback.scheme() != "https" && !(self.config.allow_http && callback.scheme() == "http")
+        {
+            return Err(LnurlError::InvalidResponse(format!(
+                "callback must use https, got '{}'",
+                callback.scheme()
+            )));
+        }
+
+        if lnurl_pay.min_sendable < 1 || lnurl_pay.min_sendable > lnurl_pay.max_sendable {
+            return Err(LnurlError::InvalidResponse(format!(
+                "invalid amount range {}-{} msats",
+                lnurl_pay.min_sendable, lnurl_pay.max_sendable
+            )));
+        }
+
+        let metadata: Vec<Vec<serde_json::Value>> = serde_json::from_str(&lnurl_pay.metadata)
+            .map_err(|e| LnurlError::InvalidResponse(format!("invalid metadata: {}", e)))?;
+        if !metadata
+            .iter()
+            .any(|entry| entry.len() == 2 && entry[0] == "text/plain" && entry[1].is_string())
+        {
+            return Err(LnurlError::InvalidResponse(
+                "metadata has no text/plain description".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// GET a URL on a public host and return the response body
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, LnurlError> {
+        let parsed = url::Url::parse(url).map_err(|e| LnurlError::InvalidFormat(e.to_string()))?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| LnurlError::InvalidFormat("URL has no host".to_string()))?
+            .to_string();
+        let port = parsed
+            .port_or_known_default()
+            .ok_or_else(|| LnurlError::InvalidFormat("URL has no port".to_string()))?;
+
+        let mut builder = reqwest::Client::builder()
+            .timeout(self.config.timeout)
+            .connect_timeout(self.config.timeout)
+            .redirect(reqwest::redirect::Policy::none());
+
+        match parsed.host() {
+            Some(url::Host::Ipv4(ip)) => self.check_ip(&host, ip.into())?,
+            Some(url::Host::Ipv6(ip)) => self.check_ip(&host, ip.into())?,
+            _ => {
+                // Resolve once, check every address and connect only to those
+                let addrs: Vec<SocketAddr> =
+                    tokio::time::timeout(self.config.timeout, lookup_host((host.as_str(), port)))
+                        .await
+                        .map_err(|_| {
+                            LnurlError::ResolutionFailed(format!(
+                                "DNS lookup of {} timed out",
+                                host
+                            ))
+                        })?
+                        .map_err(|e| {
+                            LnurlError::ResolutionFailed(format!(
+                                "DNS lookup of {} failed: {}",
+                                host, e
+                            ))
+                        })?
+                        .collect();
+                if addrs.is_empty() {
+                    return Err(LnurlError::ResolutionFailed(format!(
+                        "{} has no addresses",
+                        host
+                    )));
+                }
+                for addr in &addrs {
+                    self.check_ip(&host, addr.ip())?;
+                }
+                builder = builder.resolve_to_addrs(&host, &addrs);
+            }
+        }
+
+        let client = builder.build()?;
+        let mut response = client
+            .get(parsed)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
 
-/// Get a BOLT11 invoice from the LNURL-pay callback.
-///
-/// Calls the callback URL with the specified amount to receive an invoice.
-pub async fn get_invoice(callback_url: &str, amount_msats: i64) -> Result<String, LnurlError> {
-    // Parse the callback URL and add the amount parameter
-    let url = if callback_url.contains('?') {
-        format!("{}&amount={}", callback_url, amount_msats)
-    } else {
-        format!("{}?amount={}", callback_url, amount_msats)
-    };
-
-    tracing::info!("Requesting invoice from callback: {}", url);
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
         let status = response.status();
-        let body = response.text().await.unwrap_or_default();
+        if response
+            .content_length()
+            .is_some_and(|len| len > self.config.max_response_bytes as u64)
+        {
+            return Err(self.too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.config.max_response_bytes {
+                return Err(self.too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
 
-        // Try to parse as LNURL error response
-        if let Ok(error_response) = serde_json::from_str::<LnurlErrorResponse>(&body) {
-            return Err(LnurlError::ResolutionFailed(error_response.reason));
+        if !status.is_success() {
+            // Try to parse as LNURL error response
+            if let Ok(error_response) = serde_json::from_slice::<LnurlErrorResponse>(&body) {
+                return Err(LnurlError::ResolutionFailed(error_response.reason));
+            }
+            return Err(LnurlError::ResolutionFailed(format!(
+                "HTTP {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            )));
         }
 
-        return Err(LnurlError::ResolutionFailed(format!(
-            "HTTP {}: {}",
-            status, body
+        Ok(body)
+    }
+
+    fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), LnurlError> {
+        if self.config.allow_private_ips || is_public_ip(ip) {
+            Ok(())
+        } else {
+            Err(LnurlError::ForbiddenHost(format!("{} ({})", host, ip)))
+        }
+    }
+
+    fn too_large(&self) -> LnurlError {
+        LnurlError::InvalidResponse(format!(
+            "response larger than {} bytes",
+            self.config.max_response_bytes
+        ))
+    }
+}
+
+/// Check an invoice from an LNURL-pay callback is for the amount we asked for,
+/// commits to the pay request's metadata (LUD-06) and hasn't expired
+pub fn check_invoice(invoice: &str, amount_msats: i64, metadata: &str) -> Result<(), LnurlError> {
+    let parsed: lightning_invoice::Bolt11Invoice = invoice
+        .parse()
+        .map_err(|e| LnurlError::InvalidResponse(format!("invalid invoice: {}", e)))?;
+
+    if parsed.amount_milli_satoshis() != Some(amount_msats as u64) {
+        return Err(LnurlError::InvalidResponse(format!(
+            "invoice amount {:?} msats doesn't match the requested {} msats",
+            parsed.amount_milli_satoshis(),
+            amount_msats
         )));
     }
 
-    let callback_response: LnurlPayCallbackResponse = response.json().await.map_err(|e| {
-        LnurlError::InvalidResponse(format!("failed to parse callback response: {}", e))
-    })?;
+    let expected_hash = hex::encode(Sha256::digest(metadata.as_bytes()));
+    match parsed.description() {
+        lightning_invoice::Bolt11InvoiceDescriptionRef::Hash(hash)
+            if hash.0.to_string() == expected_hash => {}
+        _ => {
+            return Err(LnurlError::InvalidResponse(
+                "invoice description hash doesn't match the metadata".to_string(),
+            ));
+        }
+    }
 
-    if callback_response.pr.is_empty() {
+    if parsed.is_expired() {
         return Err(LnurlError::InvalidResponse(
-            "empty payment request in response".to_string(),
+            "invoice has already expired".to_string(),
         ));
     }
 
-    Ok(callback_response.pr)
+    Ok(())
 }
 
-/// Resolve a Lightning Address and get an invoice for the specified amount.
-///
-/// This is a convenience function that combines `resolve_ln_address` and `get_invoice`.
+/// Whether an IP is publicly routable, i.e. not private, loopback, link-local
+/// or otherwise reserved
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Shared address space (100.64.0.0/10)
+                || (a == 100 && (64..128).contains(&b))
+                // Benchmarking (198.18.0.0/15)
+                || (a == 198 && (18..20).contains(&b))
+                // Reserved (240.0.0.0/4)
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local (fc00::/7)
+                || (first & 0xfe00) == 0xfc00
+                // Link-local (fe80::/10)
+                || (first & 0xffc0) == 0xfe80
+                // Documentation (2001:db8::/32)
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+        }
+    }
+}
+
+/// Client shared by the free functions below, with the default limits
+fn default_client() -> &'static LnurlClient {
+    static CLIENT: OnceLock<LnurlClient> = OnceLock::new();
+    CLIENT.get_or_init(|| LnurlClient::new(LnurlClientConfig::default()))
+}
+
+/// Resolve a Lightning Address to its LNURL-pay metadata, with the default client
+pub async fn resolve_ln_address(address: &str) -> Result<LnurlPayResponse, LnurlError> {
+    default_client().resolve_ln_address(address).await
+}
+
+/// Resolve a Lightning Address and get an invoice for the specified amount, with
+/// the default client
 pub async fn get_invoice_for_ln_address(
     address: &str,
     amount_msats: i64,
 ) -> Result<String, LnurlError> {
-    let lnurl_pay = resolve_ln_address(address).await?;
-
-    // Validate amount is within range
-    if amount_msats < lnurl_pay.min_sendable || amount_msats > lnurl_pay.max_sendable {
-        return Err(LnurlError::AmountOutOfRange {
-            amount: amount_msats,
-            min: lnurl_pay.min_sendable,
-            max: lnurl_pay.max_sendable,
-        });
-    }
-
-    get_invoice(&lnurl_pay.callback, amount_msats).await
+    default_client()
+        .get_invoice_for_ln_address(address, amount_msats)
+        .await
 }
 
 /// Encode a URL as an LNURL bech32 string (LUD-01).
@@ -225,6 +534,91 @@ pub fn encode_lnurl(url: &str) -> Result<String, LnurlError> {
     Ok(encoded.to_uppercase())
 }
 
+/// Lightning address user for donations to all locations (`donate@domain`)
+pub const GLOBAL_DONATION_USER: &str = "donate";
+
+/// Prefix of a location's Lightning address user (`loc-<location id>@domain`)
+pub const LOCATION_USER_PREFIX: &str = "loc-";
+
+/// Where payments to one of our Lightning addresses go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayTarget {
+    /// Donated and split among all active locations
+    Global,
+    /// Donated to a single location's pool
+    Location(String),
+    /// Credited to a registered user's wallet, addressed by username
+    User(String),
+}
+
+impl PayTarget {
+    /// Parse the user part of one of our Lightning addresses.
+    /// The donation addresses take precedence over usernames.
+    pub fn from_user(user: &str) -> Option<Self> {
+        let user = user.to_lowercase();
+        if user == GLOBAL_DONATION_USER {
+            return Some(Self::Global);
+        }
+        if let Some(id) = user.strip_prefix(LOCATION_USER_PREFIX) {
+            return (!id.is_empty()).then(|| Self::Location(id.to_string()));
+        }
+        is_valid_address_user(&user).then_some(Self::User(user))
+    }
+
+    /// Whether a username would be taken for a donation address
+    pub fn is_reserved(username: &str) -> bool {
+        !matches!(Self::from_user(username), None | Some(Self::User(_)))
+    }
+
+    /// User part of the Lightning address
+    pub fn user(&self) -> String {
+        match self {
+            Self::Global => GLOBAL_DONATION_USER.to_string(),
+            Self::Location(id) => format!("{}{}", LOCATION_USER_PREFIX, id),
+            Self::User(username) => username.clone(),
+        }
+    }
+
+    /// Full Lightning address on the instance served at `base_url`
+    pub fn ln_address(&self, base_url: &str) -> Option<String> {
+        Some(format!("{}@{}", self.user(), ln_address_domain(base_url)?))
+    }
+
+    /// LNURL-pay link of the address (LUD-17 `lnurlp://` scheme), as used for
+    /// LUD-19 pay links
+    pub fn lnurlp_link(&self, base_url: &str) -> Option<String> {
+        Some(format!(
+            "lnurlp://{}/.well-known/lnurlp/{}",
+            ln_address_domain(base_url)?,
+            self.user()
+        ))
+    }
+}
+
+/// Domain part of the Lightning addresses of the instance served at `base_url`
+pub fn ln_address_domain(base_url: &str) -> Option<String> {
+    let url = url::Url::parse(base_url).ok()?;
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+/// Metadata of a pay request (LUD-06), identified by its Lightning address (LUD-16)
+pub fn pay_metadata(description: &str, ln_address: &str) -> String {
+    serde_json::json!([["text/plain", description], ["text/identifier", ln_address]]).to_string()
+}
+
+/// Whether a name can be the user part of a Lightning address (LUD-16 allows
+/// `a-z0-9-_.`)
+pub fn is_valid_address_user(user: &str) -> bool {
+    !user.is_empty()
+        && user
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,4 +692,88 @@ mod tests {
         let decoded_url = String::from_utf8(decoded_data).expect("should be valid utf8");
         assert_eq!(decoded_url, url);
     }
+
+    #[test]
+    fn test_pay_target_from_user() {
+        assert_eq!(PayTarget::from_user("donate"), Some(PayTarget::Global));
+        assert_eq!(
+            PayTarget::from_user("LOC-abc-123"),
+            Some(PayTarget::Location("abc-123".to_string()))
+        );
+        assert_eq!(PayTarget::from_user("loc-"), None);
+        assert_eq!(
+            PayTarget::from_user("Satoshi"),
+            Some(PayTarget::User("satoshi".to_string()))
+        );
+        assert_eq!(PayTarget::from_user("sat oshi"), None);
+        assert_eq!(PayTarget::from_user(""), None);
+    }
+
+    #[test]
+    fn test_pay_target_reserved_usernames() {
+        assert!(PayTarget::is_reserved("donate"));
+        assert!(PayTarget::is_reserved("Loc-123"));
+        assert!(!PayTarget::is_reserved("satoshi"));
+        assert!(!PayTarget::is_reserved("Hal Finney"));
+    }
+
+    #[test]
+    fn test_pay_target_ln_address() {
+        let target = PayTarget::Location("abc".to_string());
+        assert_eq!(
+            target.ln_address("https://satshunt.xyz").as_deref(),
+            Some("loc-abc@satshunt.xyz")
+        );
+        assert_eq!(
+            PayTarget::Global
+                .ln_address("http://localhost:3000")
+                .as_deref(),
+            Some("donate@localhost:3000")
+        );
+        assert_eq!(
+            PayTarget::User("alice".to_string())
+                .lnurlp_link("https://satshunt.xyz")
+                .as_deref(),
+            Some("lnurlp://satshunt.xyz/.well-known/lnurlp/alice")
+        );
+        assert_eq!(PayTarget::from_user(&target.user()), Some(target));
+    }
+
+    #[test]
+    fn test_pay_metadata() {
+        let metadata = pay_metadata("Donation to \"Park\"", "donate@satshunt.xyz");
+        let parsed: Vec<Vec<String>> = serde_json::from_str(&metadata).unwrap();
+        assert_eq!(parsed[0], vec!["text/plain", "Donation to \"Park\""]);
+        assert_eq!(parsed[1], vec!["text/identifier", "donate@satshunt.xyz"]);
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        let public = ["1.1.1.1", "93.184.216.34", "2606:4700:4700::1111"];
+        let private = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ];
+        for ip in public {
+            assert!(is_public_ip(ip.parse().unwrap()), "{} is public", ip);
+        }
+        for ip in private {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{} is not public", ip);
+        }
+    }
+
+    #[test]
+    fn test_check_invoice_rejects_garbage() {
+        let result = check_invoice("lnbc1000n1fake", 100_000, "[]");
+        assert!(matches!(result, Err(LnurlError::InvalidResponse(_))));
+    }
 }
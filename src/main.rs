@@ -7,7 +7,12 @@ use axum::{
 use clap::Parser;
 use config::Config;
 use handlers::api::AppState;
-use satshunt::{auth::auth, balance::BalanceConfig, config, db, donation, handlers, lightning};
+use satshunt::{
+    achievements, auth::auth, auto_withdraw, balance::BalanceConfig, campaign,
+    claim_rules::ClaimRules, config, db, donation, fees::FeePolicy, handlers, invoice_policy,
+    leaderboard, lightning, nostr, nwc, receive, recurring, schedule, solvency,
+    withdraw_limits::WithdrawLimits, zap,
+};
 use std::sync::Arc;
 use tower_http::{services::ServeDir, trace::TraceLayer};
 use tower_sessions::SessionManagerLayer;
@@ -50,16 +55,52 @@ async fn main() -> Result<()> {
     tracing::info!("💾 Database initialized: {}", database_url);
 
     // Initialize Lightning service
+    let network = invoice_policy::parse_network(&config.network)
+        .ok_or_else(|| anyhow::anyhow!("Unknown network: {}", config.network))?;
     let lightning: Arc<dyn lightning::Lightning> =
-        Arc::new(lightning::LightningService::new(&blitzi_dir).await?);
+        Arc::new(lightning::LightningService::new(&blitzi_dir, network).await?);
     tracing::info!("Lightning service initialized");
 
+    // Policy every invoice is checked against before paying it out
+    let blocked_payees = config
+        .blocked_payees
+        .iter()
+        .map(|key| key.trim().parse::<secp256k1::PublicKey>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let invoice_policy = invoice_policy::InvoicePolicy::new(blocked_payees);
+
+    // Routing fees reserved for withdrawals when the route can't be probed
+    let fee_policy = FeePolicy {
+        base_msats: config.fee_base_msats,
+        rate_ppm: config.fee_rate_ppm,
+    };
+
+    // Withdrawals over these limits are held for an admin to review
+    let withdraw_limits = WithdrawLimits {
+        max_withdrawal_msats: config.withdraw_max_sats.map(|sats| sats * 1000),
+        user_daily_msats: config.withdraw_user_daily_sats.map(|sats| sats * 1000),
+        global_daily_msats: config.withdraw_global_daily_sats.map(|sats| sats * 1000),
+        new_anon_msats: config.new_anon_withdraw_sats.map(|sats| sats * 1000),
+        new_anon_window: chrono::Duration::minutes(config.new_anon_window_minutes),
+    };
+
+    // Rules against farming locations, which each location can override
+    let claim_rules = ClaimRules {
+        cooldown_days: config.claim_cooldown_days,
+        max_claims_per_month: config.max_claims_per_month,
+        first_claim_bonus_percent: config.first_claim_bonus_percent,
+    };
+
     // Start donation service for resilient donation tracking
     let donation_service = Arc::new(donation::DonationService::new(
         db.clone(),
         lightning.clone(),
     ));
     let donation_sender = donation_service.get_sender();
+    // Subscribe before the service starts so no received donation is missed
+    let received_donations = donation_service.subscribe();
+    let zapped_donations = donation_service.subscribe();
+    let donor_donations = donation_service.subscribe();
 
     tokio::spawn({
         let donation_service = donation_service.clone();
@@ -70,8 +111,129 @@ async fn main() -> Result<()> {
 
     tracing::info!("Donation service started");
 
-    // Get cookie key for signing private cookies (stored in DB, generated on first use)
+    // Start campaign service for campaign funding and donation matching
+    let campaign_service = Arc::new(campaign::CampaignService::new(
+        db.clone(),
+        lightning.clone(),
+    ));
+    let campaign_sender = campaign_service.get_sender();
+
+    tokio::spawn({
+        let campaign_service = campaign_service.clone();
+        async move {
+            campaign_service.start(received_donations).await;
+        }
+    });
+
+    tracing::info!("Campaign service started");
+
+    // Get cookie secret for signing private cookies (stored in DB, generated on first use)
     let cookie_secret = db.get_or_create_cookie_secret().await?;
+
+    // Key for the stored NWC connection URIs, derived from the cookie secret
+    let nwc_cipher = nwc::UriCipher::new(&cookie_secret);
+    let encrypted = db.encrypt_recurring_nwc_uris(&nwc_cipher).await?;
+    if encrypted > 0 {
+        tracing::info!("Encrypted {} stored NWC connection URIs", encrypted);
+    }
+
+    // Start recurring donation scheduler
+    let recurring_service = Arc::new(recurring::RecurringDonationService::new(
+        db.clone(),
+        lightning.clone(),
+        donation_sender.clone(),
+        nwc_cipher.clone(),
+        nostr::RelayAccess::default(),
+    ));
+
+    tokio::spawn(async move {
+        recurring_service.start().await;
+    });
+
+    tracing::info!("Recurring donation scheduler started");
+
+    // Start schedule service for hiding locations outside their activation windows
+    let schedule_service = Arc::new(schedule::ScheduleService::new(db.clone()));
+
+    tokio::spawn(async move {
+        schedule_service.start().await;
+    });
+
+    tracing::info!("Schedule service started");
+
+    // Start leaderboard service for recomputing the cached rankings
+    let leaderboard_service = Arc::new(leaderboard::LeaderboardService::new(db.clone()));
+
+    tokio::spawn(async move {
+        leaderboard_service.start().await;
+    });
+
+    tracing::info!("Leaderboard service started");
+
+    // Start solvency service for publishing liabilities snapshots
+    let solvency_service = Arc::new(solvency::SolvencyService::new(db.clone()));
+
+    tokio::spawn(async move {
+        solvency_service.start().await;
+    });
+
+    tracing::info!("Solvency service started");
+
+    // Start achievement service for awarding badges
+    let achievement_service = Arc::new(achievements::AchievementService::new(db.clone()));
+    let achievement_sender = achievement_service.get_sender();
+
+    tokio::spawn(async move {
+        achievement_service.start(donor_donations).await;
+    });
+
+    tracing::info!("Achievement service started");
+
+    // Start receive service for payments to users' Lightning addresses
+    let receive_service = Arc::new(receive::ReceiveService::new(db.clone(), lightning.clone()));
+    let receive_sender = receive_service.get_sender();
+
+    tokio::spawn(async move {
+        receive_service.start().await;
+    });
+
+    tracing::info!("Receive service started");
+
+    // Start auto-withdraw service for sweeping balances to saved Lightning addresses
+    let auto_withdraw_service = Arc::new(auto_withdraw::AutoWithdrawService::new(
+        db.clone(),
+        lightning.clone(),
+        invoice_policy.clone(),
+        fee_policy.clone(),
+        withdraw_limits.clone(),
+    ));
+    let auto_withdraw_sender = auto_withdraw_service.get_sender();
+
+    tokio::spawn(async move {
+        auto_withdraw_service.start().await;
+    });
+
+    tracing::info!("Auto-withdraw service started");
+
+    // Nostr key for signing zap receipts (stored in DB, generated on first use)
+    let nostr_secret = db.get_or_create_nostr_secret().await?;
+    let nostr_keys =
+        secp256k1::Keypair::from_seckey_slice(&secp256k1::Secp256k1::new(), &nostr_secret)?;
+
+    // Start zap service for publishing zap receipts
+    let zap_service = Arc::new(zap::ZapService::new(
+        db.clone(),
+        nostr_keys,
+        config.nostr_relays.clone(),
+        nostr::RelayAccess::default(),
+    ));
+
+    tokio::spawn(async move {
+        zap_service.start(zapped_donations).await;
+    });
+
+    tracing::info!("Zap service started");
+
     let cookie_key = satshunt::auth::Key::from(&cookie_secret);
 
     // Derive withdraw secret from cookie secret (use first 32 bytes for HMAC-SHA256)
@@ -91,8 +253,18 @@ async fn main() -> Result<()> {
         base_url: base_url.clone(),
         balance_config: balance_config.clone(),
         donation_sender,
+        campaign_sender,
+        receive_sender,
+        auto_withdraw_sender,
+        achievement_sender,
+        invoice_policy,
+        fee_policy,
+        withdraw_limits,
+        claim_rules,
         cookie_key,
         withdraw_secret,
+        nostr_keys,
+        nwc_cipher,
     });
 
     // Set up session store
@@ -106,12 +278,20 @@ async fn main() -> Result<()> {
         // Page routes
         .route("/", get(auth(handlers::home_page)))
         .route("/map", get(auth(handlers::map_page)))
+        .route("/hunts", get(auth(handlers::hunts_page)))
+        .route("/hunts/:id", get(auth(handlers::hunt_detail_page)))
+        .route("/leaderboard", get(auth(handlers::leaderboard_page)))
         .route("/locations/new", get(auth(handlers::new_location_page)))
         .route("/locations/:id", get(auth(handlers::location_detail_page)))
         .route("/setup/:write_token", get(auth(handlers::nfc_setup_page)))
         .route("/donate", get(auth(handlers::donate_page)))
         .route("/withdraw/:location_id", get(auth(handlers::withdraw_page)))
         .route("/wallet", get(auth(handlers::wallet_page)))
+        .route("/team", get(auth(handlers::team_page)))
+        .route(
+            "/teams/join/:invite_code",
+            get(auth(handlers::team_join_page)),
+        )
         .route(
             "/login",
             get(auth(handlers::login_page)).post(handlers::login),
@@ -129,6 +309,17 @@ async fn main() -> Result<()> {
             get(auth(handlers::admin_locations_page)),
         )
         .route("/admin/scans", get(auth(handlers::admin_scans_page)))
+        .route("/admin/ledger", get(auth(handlers::admin_ledger_page)))
+        .route("/admin/solvency", get(auth(handlers::admin_solvency_page)))
+        .route(
+            "/admin/withdrawals",
+            get(auth(handlers::admin_withdrawals_page)),
+        )
+        .route(
+            "/admin/campaigns",
+            get(auth(handlers::admin_campaigns_page)),
+        )
+        .route("/admin/hunts", get(auth(handlers::admin_hunts_page)))
         // API routes
         .route("/api/locations", post(handlers::create_location))
         .route(
@@ -136,7 +327,63 @@ async fn main() -> Result<()> {
             post(handlers::upload_photo).layer(DefaultBodyLimit::max(20 * 1024 * 1024)), // 20MB limit for photos
         )
         .route("/api/photos/:photo_id", delete(handlers::delete_photo))
+        // Log book endpoints
+        .route(
+            "/api/locations/:location_id/logs",
+            get(handlers::list_location_logs)
+                .post(handlers::create_location_log)
+                .layer(
+                    DefaultBodyLimit::max(3 * 20 * 1024 * 1024), // Up to 3 photos of 20MB
+                ),
+        )
+        .route("/api/logs/:log_id", delete(handlers::delete_location_log))
+        .route("/api/logs/:log_id/hide", post(handlers::hide_location_log))
+        .route(
+            "/api/logs/:log_id/restore",
+            post(handlers::restore_location_log),
+        )
+        .route(
+            "/api/notifications/read",
+            post(handlers::mark_notifications_read),
+        )
         .route("/api/stats", get(handlers::get_stats))
+        .route("/api/leaderboard", get(handlers::get_leaderboard))
+        .route(
+            "/api/solvency/snapshots",
+            get(handlers::list_liability_snapshots),
+        )
+        .route(
+            "/api/leaderboard/opt-in",
+            post(handlers::set_leaderboard_opt_in),
+        )
+        .route("/api/teams", post(handlers::create_team))
+        .route("/api/teams/join", post(handlers::join_team))
+        .route("/api/teams/:team_id", delete(handlers::delete_team))
+        .route("/api/teams/:team_id/leave", post(handlers::leave_team))
+        .route(
+            "/api/teams/:team_id/wallet",
+            post(handlers::set_team_wallet),
+        )
+        .route(
+            "/api/teams/:team_id/invite",
+            post(handlers::reset_team_invite),
+        )
+        .route(
+            "/api/teams/:team_id/withdraw",
+            post(handlers::team_withdraw),
+        )
+        .route(
+            "/api/teams/:team_id/members/:user_id",
+            delete(handlers::remove_team_member),
+        )
+        .route(
+            "/api/teams/:team_id/members/:user_id/role",
+            post(handlers::set_team_member_role),
+        )
+        .route(
+            "/api/locations/:location_id/forecast",
+            get(handlers::get_location_forecast),
+        )
         .route(
             "/api/donate/invoice",
             post(handlers::create_donation_invoice),
@@ -150,17 +397,45 @@ async fn main() -> Result<()> {
         // New claim endpoint (claims from a pre-validated scan)
         .route("/api/claim/:scan_id", post(handlers::claim_sats))
         // Custodial wallet withdrawal endpoints
+        .route(
+            "/api/recurring-donations",
+            post(handlers::create_recurring_donation),
+        )
+        .route(
+            "/api/recurring-donations/:recurring_id/cancel",
+            post(handlers::cancel_recurring_donation),
+        )
         .route("/api/wallet/withdraw", post(handlers::wallet_withdraw))
         .route(
             "/api/wallet/withdraw/invoice",
             post(handlers::wallet_withdraw_invoice),
         )
+        .route(
+            "/api/wallet/auto-withdraw",
+            post(handlers::save_auto_withdraw).delete(handlers::delete_auto_withdraw),
+        )
+        .route(
+            "/api/wallet/liability-proof",
+            get(handlers::wallet_liability_proof),
+        )
         // Wallet LNURL-withdraw endpoints
         .route("/api/wallet/lnurlw", get(handlers::wallet_lnurlw_request))
         .route(
             "/api/wallet/lnurlw/callback",
             get(handlers::wallet_lnurlw_callback),
         )
+        .route(
+            "/api/wallet/lnurlw/link/:link_key",
+            get(handlers::wallet_withdraw_link_request),
+        )
+        .route(
+            "/api/wallet/lnurlw/link/:link_key/callback",
+            get(handlers::wallet_withdraw_link_callback),
+        )
+        .route(
+            "/api/wallet/withdraw-link",
+            post(handlers::create_withdraw_link).delete(handlers::revoke_withdraw_link),
+        )
         // Withdrawal API endpoints (legacy Lightning)
         .route(
             "/api/withdraw/:location_id/ln-address",
@@ -176,6 +451,15 @@ async fn main() -> Result<()> {
             "/api/lnurlw/:location_id/callback",
             get(handlers::lnurlw_callback),
         )
+        // LNURL-pay donation endpoints (LUD-06, LUD-16)
+        .route(
+            "/.well-known/lnurlp/:username",
+            get(handlers::lnurlp_request),
+        )
+        .route(
+            "/api/lnurlp/:username/callback",
+            get(handlers::lnurlp_callback),
+        )
         // Boltcard NFC programming endpoint
         .route("/api/boltcard/:write_token", post(handlers::boltcard_keys))
         // Delete location endpoint (non-active only)
@@ -192,11 +476,59 @@ async fn main() -> Result<()> {
             "/api/locations/:location_id/reactivate",
             post(handlers::reactivate_location),
         )
+        // Activation schedule endpoints
+        .route(
+            "/api/locations/:location_id/schedules",
+            post(handlers::create_location_schedule),
+        )
+        .route(
+            "/api/schedules/:schedule_id",
+            delete(handlers::delete_schedule),
+        )
+        .route(
+            "/api/locations/:location_id/claim-rules",
+            post(handlers::update_location_claim_rules),
+        )
+        .route(
+            "/api/locations/:location_id/disclosure",
+            post(handlers::update_location_disclosure),
+        )
+        // Hint endpoints
+        .route(
+            "/api/locations/:location_id/hints",
+            post(handlers::create_location_hint),
+        )
+        .route("/api/hints/:hint_id", delete(handlers::delete_hint))
+        .route("/api/hints/:hint_id/unlock", post(handlers::unlock_hint))
         // Admin API endpoints
         .route(
             "/api/admin/users/:user_id/role",
             post(handlers::update_user_role),
         )
+        .route("/api/admin/solvency", get(handlers::admin_solvency_report))
+        .route("/api/admin/campaigns", post(handlers::create_campaign))
+        .route(
+            "/api/admin/campaigns/:campaign_id/end",
+            post(handlers::end_campaign),
+        )
+        .route("/api/admin/hunts", post(handlers::create_hunt))
+        .route("/api/admin/regions", post(handlers::create_region))
+        .route(
+            "/api/admin/regions/:region_id",
+            delete(handlers::delete_region),
+        )
+        .route(
+            "/api/admin/hunts/:hunt_id/schedules",
+            post(handlers::create_hunt_schedule),
+        )
+        .route(
+            "/api/admin/withdrawals/:withdrawal_id/approve",
+            post(handlers::approve_withdrawal),
+        )
+        .route(
+            "/api/admin/withdrawals/:withdrawal_id/reject",
+            post(handlers::reject_withdrawal),
+        )
         // Static files
         .nest_service("/uploads", ServeDir::new(&uploads_dir))
         .nest_service("/static", ServeDir::new(&config.static_dir))
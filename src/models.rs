@@ -1,3 +1,7 @@
+use crate::achievements::Badge;
+use crate::claim_policy::{ClaimPolicy, SCAN_CLAIM_WINDOW};
+use crate::disclosure::{Disclosure, SearchArea};
+use crate::leaderboard::{Metric, Period};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -136,6 +140,8 @@ pub struct User {
     /// User role for access control
     #[sqlx(try_from = "String")]
     pub role: UserRole,
+    /// Whether an anonymous user chose to show up on leaderboards
+    pub leaderboard_opt_in: bool,
 }
 
 impl User {
@@ -148,6 +154,12 @@ impl User {
         self.auth_method == "anonymous"
     }
 
+    /// Whether the user is ranked on leaderboards - registered users always,
+    /// anonymous users only after opting in
+    pub fn is_on_leaderboards(&self) -> bool {
+        !self.is_anonymous() || self.leaderboard_opt_in
+    }
+
     /// Get display name - username for registered users, truncated ID for anonymous
     pub fn display_name(&self) -> String {
         self.username
@@ -187,10 +199,38 @@ pub struct Location {
     pub write_token_used: bool,
     pub write_token_created_at: Option<DateTime<Utc>>,
     pub user_id: String,
-    pub status: String, // 'created', 'programmed', 'active'
+    pub status: String, // 'created', 'programmed', 'active', 'scheduled', 'deactivated', 'admin_deactivated'
+    /// Who gets the sats after scanning
+    #[sqlx(try_from = "String")]
+    pub claim_policy: ClaimPolicy,
+    /// How much of the position hunters get to see
+    #[sqlx(try_from = "String")]
+    pub disclosure: Disclosure,
+    pub search_radius_m: Option<i64>,
+    /// Randomly offset center of the search area
+    pub search_latitude: Option<f64>,
+    pub search_longitude: Option<f64>,
+    /// When the location last went live
+    pub activated_at: Option<DateTime<Utc>>,
 }
 
 impl Location {
+    /// The stored search area, if one was drawn
+    pub fn search_area(&self) -> Option<SearchArea> {
+        match (
+            self.search_latitude,
+            self.search_longitude,
+            self.search_radius_m,
+        ) {
+            (Some(latitude), Some(longitude), Some(radius_m)) => Some(SearchArea {
+                latitude,
+                longitude,
+                radius_m,
+            }),
+            _ => None,
+        }
+    }
+
     pub fn is_created(&self) -> bool {
         self.status == "created"
     }
@@ -211,6 +251,16 @@ impl Location {
         self.status == "admin_deactivated"
     }
 
+    /// Active, but hidden because it is outside its activation schedule
+    pub fn is_scheduled(&self) -> bool {
+        self.status == "scheduled"
+    }
+
+    /// Set up and in play, whether or not it is inside its schedule right now
+    pub fn is_live(&self) -> bool {
+        self.is_active() || self.is_scheduled()
+    }
+
     /// Check if this location is visible to regular users (active and not deactivated)
     pub fn is_visible(&self) -> bool {
         self.is_active()
@@ -295,6 +345,16 @@ pub struct Donation {
     pub status: DonationStatus,
     pub created_at: DateTime<Utc>,
     pub received_at: Option<DateTime<Utc>>,
+    /// Donor's comment, sent with LNURL-pay donations (LUD-12)
+    pub comment: Option<String>,
+    /// Signed zap request (NIP-57 kind 9734) for donations paid through a zap
+    pub zap_request: Option<String>,
+    /// Id of the published zap receipt (kind 9735)
+    pub zap_receipt_id: Option<String>,
+    /// Hunt whose bonus pool the donation goes to (location_id is None then)
+    pub hunt_id: Option<String>,
+    /// Donor, for donations made by a known user
+    pub user_id: Option<String>,
 }
 
 impl Donation {
@@ -345,19 +405,14 @@ pub struct NfcScan {
 }
 
 impl NfcScan {
-    /// Check if this scan is still claimable (within 1 hour, not yet claimed)
+    /// Check if this scan is still claimable (within the claim window, not yet claimed)
     pub fn is_claimable(&self) -> bool {
-        if self.claimed_at.is_some() {
-            return false;
-        }
-        let age = Utc::now().signed_duration_since(self.scanned_at);
-        age.num_hours() < 1
+        self.claimed_at.is_none() && !self.is_expired()
     }
 
-    /// Check if this scan has expired
+    /// Check if this scan is past the claim window
     pub fn is_expired(&self) -> bool {
-        let age = Utc::now().signed_duration_since(self.scanned_at);
-        age.num_hours() >= 1
+        Utc::now().signed_duration_since(self.scanned_at) >= SCAN_CLAIM_WINDOW
     }
 }
 
@@ -390,13 +445,12 @@ impl ScanWithUser {
         self.claimed_at.is_some()
     }
 
-    /// Check if this scan is still claimable (latest, not claimed, within 1 hour)
+    /// Check if this scan is still claimable (latest, not claimed, within the claim window)
     pub fn is_claimable(&self) -> bool {
         if self.claimed_at.is_some() || !self.is_latest {
             return false;
         }
-        let age = Utc::now().signed_duration_since(self.scanned_at);
-        age.num_hours() < 1
+        Utc::now().signed_duration_since(self.scanned_at) < SCAN_CLAIM_WINDOW
     }
 
     /// Get claimed amount in sats (0 if not claimed)
@@ -446,13 +500,12 @@ impl ScanWithLocation {
         self.claimed_at.is_some()
     }
 
-    /// Check if this scan is still claimable (latest, not claimed, within 1 hour)
+    /// Check if this scan is still claimable (latest, not claimed, within the claim window)
     pub fn is_claimable(&self) -> bool {
         if self.claimed_at.is_some() || !self.is_latest {
             return false;
         }
-        let age = Utc::now().signed_duration_since(self.scanned_at);
-        age.num_hours() < 1
+        Utc::now().signed_duration_since(self.scanned_at) < SCAN_CLAIM_WINDOW
     }
 
     /// Get claimed amount in sats (0 if not claimed)
@@ -520,12 +573,24 @@ pub enum ClaimResult {
     NotYourScan,
     /// Scan was already claimed
     AlreadyClaimed,
-    /// Scan has expired (>1 hour)
+    /// Scan is past the claim window
     Expired,
     /// Someone else scanned after this user
     NotLastScanner,
+    /// Someone else scanned first
+    NotFirstScanner,
     /// No balance available to claim
     NoBalance,
+    /// The location is outside its activation schedule
+    OutsideSchedule,
+    /// The location isn't active
+    LocationInactive,
+    /// More was requested than the claim can take
+    OverClaimable { claimable_msats: i64 },
+    /// The user claimed this location too recently
+    Cooldown { until: DateTime<Utc> },
+    /// The user reached the most claims per 30 days at this location
+    MonthlyLimitReached { limit: i64, until: DateTime<Utc> },
 }
 
 /// User transaction for tracking sat collections and withdrawals in the custodial wallet
@@ -533,14 +598,20 @@ pub enum ClaimResult {
 pub struct UserTransaction {
     pub id: String,
     pub user_id: String,
-    /// Location where sats were collected from (None for withdrawals)
+    /// Location where sats were collected from or a hint was bought at
+    /// (None for withdrawals, receives, hunt bonuses and team payouts)
     pub location_id: Option<String>,
     pub msats: i64,
-    /// Transaction type: 'collect' or 'withdraw'
+    /// Transaction type: 'collect', 'withdraw', 'receive', 'hunt_bonus', 'hint'
+    /// or 'team_payout'
     pub transaction_type: String,
     pub created_at: DateTime<Utc>,
 }
 
+/// Types of user transactions that add to the wallet balance. Every other type
+/// (withdrawals, hint purchases) takes from it.
+pub const CREDIT_TRANSACTION_TYPES: [&str; 4] = ["collect", "receive", "hunt_bonus", "team_payout"];
+
 impl UserTransaction {
     /// Get amount in sats for display
     pub fn sats(&self) -> i64 {
@@ -554,6 +625,31 @@ impl UserTransaction {
     pub fn is_withdraw(&self) -> bool {
         self.transaction_type == "withdraw"
     }
+
+    /// Paid to the user's Lightning address
+    pub fn is_receive(&self) -> bool {
+        self.transaction_type == "receive"
+    }
+
+    /// Completion bonus of a hunt
+    pub fn is_hunt_bonus(&self) -> bool {
+        self.transaction_type == "hunt_bonus"
+    }
+
+    /// Hint bought from the wallet
+    pub fn is_hint(&self) -> bool {
+        self.transaction_type == "hint"
+    }
+
+    /// Withdrawn from a team wallet into this wallet
+    pub fn is_team_payout(&self) -> bool {
+        self.transaction_type == "team_payout"
+    }
+
+    /// Whether the transaction adds to the balance
+    pub fn is_credit(&self) -> bool {
+        CREDIT_TRANSACTION_TYPES.contains(&self.transaction_type.as_str())
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -607,6 +703,8 @@ pub enum WithdrawalStatus {
     Pending,
     Completed,
     Failed,
+    /// Over a withdrawal limit, waiting for an admin to approve or reject it
+    Held,
 }
 
 impl WithdrawalStatus {
@@ -615,6 +713,7 @@ impl WithdrawalStatus {
             Self::Pending => "pending",
             Self::Completed => "completed",
             Self::Failed => "failed",
+            Self::Held => "held",
         }
     }
 }
@@ -633,6 +732,7 @@ impl std::str::FromStr for WithdrawalStatus {
             "pending" => Ok(Self::Pending),
             "completed" => Ok(Self::Completed),
             "failed" => Ok(Self::Failed),
+            "held" => Ok(Self::Held),
             _ => Err(anyhow::anyhow!("Invalid withdrawal status: {}", s)),
         }
     }
@@ -652,12 +752,20 @@ impl TryFrom<String> for WithdrawalStatus {
 pub struct PendingWithdrawal {
     pub id: String,
     pub user_id: String,
+    /// Amount reserved, including the fee
     pub msats: i64,
+    /// BOLT11 invoice being paid
     pub invoice: String,
     #[sqlx(try_from = "String")]
     pub status: WithdrawalStatus,
     pub created_at: DateTime<Utc>,
     pub completed_at: Option<DateTime<Utc>>,
+    pub fee_msats: i64,
+    /// Why the withdrawal was held for review
+    pub hold_reason: Option<String>,
+    /// Admin who approved or rejected a held withdrawal
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
 }
 
 impl PendingWithdrawal {
@@ -665,6 +773,15 @@ impl PendingWithdrawal {
         self.status == WithdrawalStatus::Pending
     }
 
+    pub fn is_held(&self) -> bool {
+        self.status == WithdrawalStatus::Held
+    }
+
+    /// Amount paid to the destination, without the fee
+    pub fn amount_msats(&self) -> i64 {
+        self.msats - self.fee_msats
+    }
+
     pub fn is_completed(&self) -> bool {
         self.status == WithdrawalStatus::Completed
     }
@@ -679,165 +796,1387 @@ impl PendingWithdrawal {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Status of a recurring donation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurringDonationStatus {
+    Active,
+    /// Cancelled by the donor
+    Cancelled,
+    /// Stopped after too many failed payments
+    Failed,
+}
 
-    fn make_test_location(current_msats: i64) -> Location {
-        let now = Utc::now();
-        Location {
-            id: "test-id".to_string(),
-            name: "Test Location".to_string(),
-            latitude: 0.0,
-            longitude: 0.0,
-            description: None,
-            created_at: now,
-            current_msats,
-            lnurlw_secret: "secret".to_string(),
-            last_refill_at: now,
-            last_withdraw_at: None,
-            write_token: None,
-            write_token_used: false,
-            write_token_created_at: None,
-            user_id: "user-id".to_string(),
-            status: "active".to_string(),
+impl RecurringDonationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Cancelled => "cancelled",
+            Self::Failed => "failed",
         }
     }
+}
 
-    // Note: tests for withdrawable_msats, withdrawable_sats, current_sats, last_activity_at removed
-    // Balance is now computed on-demand via compute_balance_msats()
+impl std::fmt::Display for RecurringDonationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 
-    #[test]
-    fn test_location_status_helpers() {
-        let mut location = make_test_location(1000);
+impl std::str::FromStr for RecurringDonationStatus {
+    type Err = anyhow::Error;
 
-        location.status = "created".to_string();
-        assert!(location.is_created());
-        assert!(!location.is_programmed());
-        assert!(!location.is_active());
-        assert!(!location.is_deactivated());
-        assert!(!location.is_admin_deactivated());
-        assert!(!location.is_visible());
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(Self::Active),
+            "cancelled" => Ok(Self::Cancelled),
+            "failed" => Ok(Self::Failed),
+            _ => Err(anyhow::anyhow!("Invalid recurring donation status: {}", s)),
+        }
+    }
+}
 
-        location.status = "programmed".to_string();
-        assert!(!location.is_created());
-        assert!(location.is_programmed());
-        assert!(!location.is_active());
-        assert!(!location.is_deactivated());
-        assert!(!location.is_admin_deactivated());
-        assert!(!location.is_visible());
+impl TryFrom<String> for RecurringDonationStatus {
+    type Error = anyhow::Error;
 
-        location.status = "active".to_string();
-        assert!(!location.is_created());
-        assert!(!location.is_programmed());
-        assert!(location.is_active());
-        assert!(!location.is_deactivated());
-        assert!(!location.is_admin_deactivated());
-        assert!(location.is_visible());
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
 
-        location.status = "deactivated".to_string();
-        assert!(!location.is_created());
-        assert!(!location.is_programmed());
-        assert!(!location.is_active());
-        assert!(location.is_deactivated());
-        assert!(!location.is_admin_deactivated());
-        assert!(!location.is_visible());
-        assert!(location.can_creator_reactivate());
+/// Donation subscription paid from the donor's wallet over Nostr Wallet Connect
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RecurringDonation {
+    pub id: String,
+    /// Donor (registered or anonymous)
+    pub user_id: String,
+    /// Location ID for location donations (None = global donation)
+    pub location_id: Option<String>,
+    pub amount_msats: i64,
+    pub interval_days: i64,
+    /// Wallet connection, including its secret, encrypted with
+    /// [`crate::nwc::UriCipher`]. Never sent to clients.
+    #[serde(skip_serializing)]
+    pub nwc_uri: String,
+    #[sqlx(try_from = "String")]
+    pub status: RecurringDonationStatus,
+    pub next_payment_at: DateTime<Utc>,
+    pub last_payment_at: Option<DateTime<Utc>>,
+    /// Failed payments since the last successful one
+    pub failure_count: i64,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
 
-        location.status = "admin_deactivated".to_string();
-        assert!(!location.is_created());
-        assert!(!location.is_programmed());
-        assert!(!location.is_active());
-        assert!(!location.is_deactivated());
-        assert!(location.is_admin_deactivated());
-        assert!(!location.is_visible());
-        assert!(!location.can_creator_reactivate());
+impl RecurringDonation {
+    pub fn is_active(&self) -> bool {
+        self.status == RecurringDonationStatus::Active
     }
 
-    #[test]
-    fn test_auth_method_password_roundtrip() {
-        let auth = AuthMethod::Password {
-            password_hash: "argon2hash123".to_string(),
-        };
-
-        let json = auth.to_json().unwrap();
-        let parsed = AuthMethod::from_json("password", &json).unwrap();
+    pub fn amount_sats(&self) -> i64 {
+        self.amount_msats / 1000
+    }
 
-        match parsed {
-            AuthMethod::Password { password_hash } => {
-                assert_eq!(password_hash, "argon2hash123");
-            }
-            _ => panic!("Expected Password variant"),
+    /// Human readable interval, e.g. "week" or "3 days"
+    pub fn interval_label(&self) -> String {
+        match self.interval_days {
+            1 => "day".to_string(),
+            7 => "week".to_string(),
+            30 => "month".to_string(),
+            n if n % 7 == 0 => format!("{} weeks", n / 7),
+            n => format!("{} days", n),
         }
+    }
+}
 
-        assert_eq!(auth.to_type_string(), "password");
+/// Status of an invoice for a user's Lightning address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletInvoiceStatus {
+    /// Waiting for payment
+    Created,
+    /// Paid and credited to the user's wallet
+    Received,
+}
+
+impl WalletInvoiceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Received => "received",
+        }
     }
+}
 
-    #[test]
-    fn test_auth_method_oauth_google_roundtrip() {
-        let auth = AuthMethod::OAuthGoogle {
-            google_id: "google123".to_string(),
-        };
+impl std::fmt::Display for WalletInvoiceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 
-        let json = auth.to_json().unwrap();
-        let parsed = AuthMethod::from_json("oauth_google", &json).unwrap();
+impl std::str::FromStr for WalletInvoiceStatus {
+    type Err = anyhow::Error;
 
-        match parsed {
-            AuthMethod::OAuthGoogle { google_id } => {
-                assert_eq!(google_id, "google123");
-            }
-            _ => panic!("Expected OAuthGoogle variant"),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(Self::Created),
+            "received" => Ok(Self::Received),
+            _ => Err(anyhow::anyhow!("Invalid wallet invoice status: {}", s)),
         }
+    }
+}
 
-        assert_eq!(auth.to_type_string(), "oauth_google");
+impl TryFrom<String> for WalletInvoiceStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
     }
+}
 
-    #[test]
-    fn test_auth_method_oauth_github_roundtrip() {
-        let auth = AuthMethod::OAuthGithub {
-            github_id: "github456".to_string(),
-        };
+/// Invoice created for a payment to a user's Lightning address
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WalletInvoice {
+    pub id: String,
+    pub user_id: String,
+    pub invoice: String,
+    pub amount_msats: i64,
+    /// Payer's comment (LUD-12)
+    pub comment: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub status: WalletInvoiceStatus,
+    pub created_at: DateTime<Utc>,
+    pub received_at: Option<DateTime<Utc>>,
+}
 
-        let json = auth.to_json().unwrap();
-        let parsed = AuthMethod::from_json("oauth_github", &json).unwrap();
+impl WalletInvoice {
+    pub fn amount_sats(&self) -> i64 {
+        self.amount_msats / 1000
+    }
+}
 
-        match parsed {
-            AuthMethod::OAuthGithub { github_id } => {
-                assert_eq!(github_id, "github456");
-            }
-            _ => panic!("Expected OAuthGithub variant"),
-        }
+/// Reusable LNURL-withdraw link for a user's wallet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WalletWithdrawLink {
+    pub id: String,
+    pub user_id: String,
+    /// Secret part of the link's URL. Never sent to other users.
+    #[serde(skip_serializing)]
+    pub link_key: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
 
-        assert_eq!(auth.to_type_string(), "oauth_github");
+/// User's setting for withdrawing their wallet balance automatically
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AutoWithdrawSetting {
+    pub user_id: String,
+    /// Lightning address the balance is withdrawn to
+    pub ln_address: String,
+    /// Balance at which the wallet is swept
+    pub threshold_msats: i64,
+    /// Cleared after too many failed withdrawals in a row
+    pub enabled: bool,
+    /// Failed withdrawals since the last successful one
+    pub failure_count: i64,
+    pub last_error: Option<String>,
+    /// No withdrawal is attempted before this time after a failure
+    pub retry_at: Option<DateTime<Utc>>,
+    pub last_withdrawal_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AutoWithdrawSetting {
+    pub fn threshold_sats(&self) -> i64 {
+        self.threshold_msats / 1000
     }
 
-    #[test]
-    fn test_auth_method_anonymous_roundtrip() {
-        let auth = AuthMethod::Anonymous {};
+    /// Whether a withdrawal may be attempted at `now`
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.retry_at.is_none_or(|at| at <= now)
+    }
+}
 
-        let json = auth.to_json().unwrap();
-        let parsed = AuthMethod::from_json("anonymous", &json).unwrap();
+/// Status of a donation matching campaign
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CampaignStatus {
+    /// Waiting for the sponsor to pay the funding invoice
+    Funding,
+    Active,
+    /// Budget spent, deadline passed or stopped by an admin
+    Ended,
+    /// Funding invoice expired before the sponsor paid it
+    Expired,
+}
 
-        match parsed {
-            AuthMethod::Anonymous {} => {}
-            _ => panic!("Expected Anonymous variant"),
+impl CampaignStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Funding => "funding",
+            Self::Active => "active",
+            Self::Ended => "ended",
+            Self::Expired => "expired",
         }
-
-        assert_eq!(auth.to_type_string(), "anonymous");
     }
+}
 
-    #[test]
-    fn test_auth_method_from_json_unknown_type() {
-        let result = AuthMethod::from_json("unknown", "{}");
-        assert!(result.is_err());
+impl std::fmt::Display for CampaignStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
     }
+}
 
-    #[test]
-    fn test_user_display_name() {
-        let now = Utc::now();
+impl std::str::FromStr for CampaignStatus {
+    type Err = anyhow::Error;
 
-        // Registered user with username
-        let registered_user = User {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "funding" => Ok(Self::Funding),
+            "active" => Ok(Self::Active),
+            "ended" => Ok(Self::Ended),
+            "expired" => Ok(Self::Expired),
+            _ => Err(anyhow::anyhow!("Invalid campaign status: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for CampaignStatus {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Sponsor campaign that matches donations from a prepaid budget
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct MatchingCampaign {
+    pub id: String,
+    pub name: String,
+    pub sponsor_name: String,
+    pub budget_msats: i64,
+    pub spent_msats: i64,
+    /// Matched sats per donated sat, in percent (100 = 1:1)
+    pub match_percent: i64,
+    /// Cap on the match for a single donation
+    pub max_match_msats: Option<i64>,
+    pub min_latitude: Option<f64>,
+    pub max_latitude: Option<f64>,
+    pub min_longitude: Option<f64>,
+    pub max_longitude: Option<f64>,
+    pub ends_at: DateTime<Utc>,
+    #[sqlx(try_from = "String")]
+    pub status: CampaignStatus,
+    pub funding_invoice: String,
+    pub created_at: DateTime<Utc>,
+    pub funded_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl MatchingCampaign {
+    pub fn remaining_msats(&self) -> i64 {
+        (self.budget_msats - self.spent_msats).max(0)
+    }
+
+    /// Whether the campaign can match donations at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.status == CampaignStatus::Active && now < self.ends_at && self.remaining_msats() > 0
+    }
+
+    /// Whether a location lies inside the campaign's region
+    pub fn covers(&self, latitude: f64, longitude: f64) -> bool {
+        self.min_latitude.is_none_or(|min| latitude >= min)
+            && self.max_latitude.is_none_or(|max| latitude <= max)
+            && self.min_longitude.is_none_or(|min| longitude >= min)
+            && self.max_longitude.is_none_or(|max| longitude <= max)
+    }
+
+    pub fn has_region(&self) -> bool {
+        self.min_latitude.is_some()
+            || self.max_latitude.is_some()
+            || self.min_longitude.is_some()
+            || self.max_longitude.is_some()
+    }
+
+    /// Match for a donation of `donation_msats`, given how much of the per-donation
+    /// cap and of the budget is still available
+    pub fn match_msats(&self, donation_msats: i64, matched_so_far_msats: i64) -> i64 {
+        let wanted = donation_msats.max(0) * self.match_percent / 100;
+        let cap_left = self
+            .max_match_msats
+            .map(|cap| (cap - matched_so_far_msats).max(0))
+            .unwrap_or(i64::MAX);
+        wanted.min(cap_left).min(self.remaining_msats())
+    }
+
+    pub fn budget_sats(&self) -> i64 {
+        self.budget_msats / 1000
+    }
+
+    pub fn remaining_sats(&self) -> i64 {
+        self.remaining_msats() / 1000
+    }
+}
+
+/// Campaign settings chosen by an admin, before a funding invoice exists
+#[derive(Debug, Clone)]
+pub struct NewMatchingCampaign {
+    pub name: String,
+    pub sponsor_name: String,
+    pub budget_msats: i64,
+    pub match_percent: i64,
+    pub max_match_msats: Option<i64>,
+    pub min_latitude: Option<f64>,
+    pub max_latitude: Option<f64>,
+    pub min_longitude: Option<f64>,
+    pub max_longitude: Option<f64>,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Sats a campaign added on top of a donation
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DonationMatch {
+    /// The donation that was matched
+    pub donation_id: String,
+    pub campaign_id: String,
+    pub sponsor_name: String,
+    pub msats: i64,
+}
+
+impl DonationMatch {
+    pub fn sats(&self) -> i64 {
+        self.msats / 1000
+    }
+}
+
+/// A trail of locations with a bonus for claiming all of them
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Hunt {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Whether the stops have to be claimed in order
+    pub ordered: bool,
+    /// Bonus paid to each user who completes the hunt, while the pool lasts
+    pub completion_bonus_msats: i64,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Hunt {
+    pub fn completion_bonus_sats(&self) -> i64 {
+        self.completion_bonus_msats / 1000
+    }
+}
+
+/// A user who completed a hunt, with their display information for the leaderboard
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct HuntCompletion {
+    pub id: String,
+    pub hunt_id: String,
+    pub user_id: String,
+    /// User's username (None for anonymous users)
+    pub username: Option<String>,
+    /// Bonus actually paid, less than the hunt's bonus if the pool ran low
+    pub bonus_msats: i64,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl HuntCompletion {
+    pub fn display_name(&self) -> String {
+        self.username
+            .clone()
+            .unwrap_or_else(|| format!("anon_{}", &self.user_id[..8.min(self.user_id.len())]))
+    }
+
+    pub fn bonus_sats(&self) -> i64 {
+        self.bonus_msats / 1000
+    }
+}
+
+/// How often a schedule's window repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Once,
+    Daily,
+    Weekly,
+}
+
+impl Recurrence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Once => "once",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// Time between the starts of two windows (None for one-off schedules)
+    pub fn period(&self) -> Option<chrono::Duration> {
+        match self {
+            Self::Once => None,
+            Self::Daily => Some(chrono::Duration::days(1)),
+            Self::Weekly => Some(chrono::Duration::weeks(1)),
+        }
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "once" => Ok(Self::Once),
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            _ => Err(anyhow::anyhow!("Invalid recurrence: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Activation window of a location, or of every stop of a hunt
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    /// Set for a schedule of a single location
+    pub location_id: Option<String>,
+    /// Set for a schedule of all stops of a hunt
+    pub hunt_id: Option<String>,
+    /// Start of the first window
+    pub starts_at: DateTime<Utc>,
+    /// End of the first window
+    pub ends_at: DateTime<Utc>,
+    #[sqlx(try_from = "String")]
+    pub recurrence: Recurrence,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Data needed to create a schedule
+#[derive(Debug, Clone)]
+pub struct NewSchedule {
+    pub location_id: Option<String>,
+    pub hunt_id: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub recurrence: Recurrence,
+    pub created_by: String,
+}
+
+/// How a hint is unlocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HintUnlock {
+    /// For everyone, some days after the location was created
+    Time,
+    /// Per user, by paying sats into the location pool
+    Payment,
+    /// Per user, by answering a riddle
+    Riddle,
+}
+
+impl HintUnlock {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Time => "time",
+            Self::Payment => "payment",
+            Self::Riddle => "riddle",
+        }
+    }
+}
+
+impl std::fmt::Display for HintUnlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for HintUnlock {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "time" => Ok(Self::Time),
+            "payment" => Ok(Self::Payment),
+            "riddle" => Ok(Self::Riddle),
+            _ => Err(anyhow::anyhow!("Invalid hint unlock: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for HintUnlock {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A clue for finding a location, revealed once unlocked
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Hint {
+    pub id: String,
+    pub location_id: String,
+    /// 1-based order of the hint at its location
+    pub position: i64,
+    pub text: String,
+    #[sqlx(try_from = "String")]
+    pub unlock_kind: HintUnlock,
+    /// Days after the location's creation a time hint unlocks
+    pub unlock_after_days: Option<i64>,
+    /// Price of a payment hint
+    pub price_msats: Option<i64>,
+    /// Question of a riddle hint, shown before it is unlocked
+    pub riddle: Option<String>,
+    pub riddle_answer: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Hint {
+    pub fn price_sats(&self) -> i64 {
+        self.price_msats.unwrap_or(0) / 1000
+    }
+
+    /// When a time hint unlocks for everyone, None for other hints
+    pub fn unlocks_at(&self, location_created_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.unlock_kind {
+            HintUnlock::Time => self
+                .unlock_after_days
+                .map(|days| location_created_at + chrono::Duration::days(days)),
+            _ => None,
+        }
+    }
+
+    /// Whether `answer` solves the riddle, ignoring case and extra whitespace
+    pub fn is_answer(&self, answer: &str) -> bool {
+        let normalize = |s: &str| {
+            s.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        match (&self.unlock_kind, &self.riddle_answer) {
+            (HintUnlock::Riddle, Some(expected)) => {
+                !answer.trim().is_empty() && normalize(answer) == normalize(expected)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Data needed to create a hint
+#[derive(Debug, Clone)]
+pub struct NewHint {
+    pub location_id: String,
+    pub text: String,
+    pub unlock_kind: HintUnlock,
+    pub unlock_after_days: Option<i64>,
+    pub price_msats: Option<i64>,
+    pub riddle: Option<String>,
+    pub riddle_answer: Option<String>,
+}
+
+/// Kind of entry in a location's log book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogKind {
+    Found,
+    NotFound,
+    NeedsMaintenance,
+    Note,
+}
+
+impl LogKind {
+    pub const ALL: [LogKind; 4] = [
+        Self::Found,
+        Self::NotFound,
+        Self::NeedsMaintenance,
+        Self::Note,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Found => "found",
+            Self::NotFound => "not_found",
+            Self::NeedsMaintenance => "needs_maintenance",
+            Self::Note => "note",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Found => "FOUND IT",
+            Self::NotFound => "DIDN'T FIND IT",
+            Self::NeedsMaintenance => "NEEDS MAINTENANCE",
+            Self::Note => "NOTE",
+        }
+    }
+
+    /// Font Awesome icon name
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Found => "fa-face-smile",
+            Self::NotFound => "fa-face-frown",
+            Self::NeedsMaintenance => "fa-wrench",
+            Self::Note => "fa-pen",
+        }
+    }
+}
+
+impl std::fmt::Display for LogKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for LogKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "found" => Ok(Self::Found),
+            "not_found" => Ok(Self::NotFound),
+            "needs_maintenance" => Ok(Self::NeedsMaintenance),
+            "note" => Ok(Self::Note),
+            _ => Err(anyhow::anyhow!("Invalid log kind: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for LogKind {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// An entry in a location's log book, with its author's username
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LocationLog {
+    pub id: String,
+    pub location_id: String,
+    pub user_id: String,
+    #[sqlx(try_from = "String")]
+    pub kind: LogKind,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    /// Set when a moderator hid the log
+    pub hidden_at: Option<DateTime<Utc>>,
+    pub hidden_by: Option<String>,
+    pub username: Option<String>,
+}
+
+impl LocationLog {
+    pub fn is_hidden(&self) -> bool {
+        self.hidden_at.is_some()
+    }
+}
+
+/// A photo attached to a log
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LocationLogPhoto {
+    pub id: String,
+    pub log_id: String,
+    pub file_path: String,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// An in-app notification
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub user_id: String,
+    pub location_id: Option<String>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+impl Notification {
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+/// A badge a user earned
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct UserBadge {
+    pub user_id: String,
+    #[sqlx(try_from = "String")]
+    pub badge: Badge,
+    pub earned_at: DateTime<Utc>,
+}
+
+/// A named bounding box for regional leaderboards
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Region {
+    pub id: String,
+    pub name: String,
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub min_longitude: f64,
+    pub max_longitude: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A hunter's finds and claims over a leaderboard period
+#[derive(Debug, Clone, FromRow)]
+pub struct HunterStats {
+    pub user_id: String,
+    pub username: Option<String>,
+    pub msats_claimed: i64,
+    pub locations_found: i64,
+    pub first_finds: i64,
+}
+
+impl HunterStats {
+    pub fn display_name(&self) -> String {
+        self.username
+            .clone()
+            .unwrap_or_else(|| format!("anon_{}", &self.user_id[..8.min(self.user_id.len())]))
+    }
+}
+
+/// A hunter's place on a cached leaderboard
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    #[sqlx(try_from = "String")]
+    pub period: Period,
+    /// Empty for the global leaderboard
+    pub region_id: String,
+    #[sqlx(try_from = "String")]
+    pub metric: Metric,
+    pub rank: i64,
+    pub user_id: String,
+    pub display_name: String,
+    /// Score in the metric's unit (sats for sats collected)
+    pub value: i64,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Role of a team member
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamRole {
+    /// Manages members, the invite link and the wallet, and can withdraw
+    Owner,
+    /// Can withdraw from the team wallet
+    Treasurer,
+    Member,
+}
+
+impl TeamRole {
+    pub const ALL: [TeamRole; 3] = [Self::Owner, Self::Treasurer, Self::Member];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Treasurer => "treasurer",
+            Self::Member => "member",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Owner => "OWNER",
+            Self::Treasurer => "TREASURER",
+            Self::Member => "MEMBER",
+        }
+    }
+
+    /// Whether the member can withdraw from the team wallet
+    pub fn can_withdraw(&self) -> bool {
+        matches!(self, Self::Owner | Self::Treasurer)
+    }
+
+    /// Whether the member can manage the team
+    pub fn can_manage(&self) -> bool {
+        matches!(self, Self::Owner)
+    }
+}
+
+impl std::fmt::Display for TeamRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for TeamRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(Self::Owner),
+            "treasurer" => Ok(Self::Treasurer),
+            "member" => Ok(Self::Member),
+            _ => Err(anyhow::anyhow!("Invalid team role: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for TeamRole {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A group of hunters, optionally sharing a wallet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Team {
+    pub id: String,
+    pub name: String,
+    /// Code in the invite link, anyone with it can join
+    pub invite_code: String,
+    /// Whether members' claims go into the team wallet
+    pub wallet_enabled: bool,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A member of a team, with their username
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct TeamMember {
+    pub team_id: String,
+    pub user_id: String,
+    pub username: Option<String>,
+    #[sqlx(try_from = "String")]
+    pub role: TeamRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// Collection into or withdrawal from a team wallet
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct TeamTransaction {
+    pub id: String,
+    pub team_id: String,
+    /// Member who collected or withdrew
+    pub user_id: String,
+    /// Location the sats were collected from (None for withdrawals)
+    pub location_id: Option<String>,
+    pub msats: i64,
+    /// Transaction type: 'collect' or 'withdraw'
+    pub transaction_type: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TeamTransaction {
+    /// Get amount in sats for display
+    pub fn sats(&self) -> i64 {
+        self.msats / 1000
+    }
+
+    pub fn is_collect(&self) -> bool {
+        self.transaction_type == "collect"
+    }
+}
+
+/// A team's finds and claims over a leaderboard period
+#[derive(Debug, Clone, FromRow)]
+pub struct TeamStats {
+    pub team_id: String,
+    pub name: String,
+    pub msats_claimed: i64,
+    pub locations_found: i64,
+    pub first_finds: i64,
+}
+
+/// A team's place on a cached leaderboard
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct TeamLeaderboardEntry {
+    #[sqlx(try_from = "String")]
+    pub period: Period,
+    /// Empty for the global leaderboard
+    pub region_id: String,
+    #[sqlx(try_from = "String")]
+    pub metric: Metric,
+    pub rank: i64,
+    pub team_id: String,
+    pub name: String,
+    /// Score in the metric's unit (sats for sats collected)
+    pub value: i64,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// An account in the double-entry ledger
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum LedgerAccount {
+    /// Sats held by the Lightning node (the only asset account)
+    Node,
+    /// Withdrawal fees earned by the service
+    Fees,
+    /// The operator's stake in the service. Routing fees the service covers
+    /// beyond a withdrawal's reserve are drawn from it, so it may be negative.
+    Equity,
+    /// Received sats not assigned to any location (e.g. split remainders)
+    Unallocated,
+    /// Sats reserved for in-flight wallet withdrawals
+    PendingWithdrawals,
+    /// A location's donation pool
+    LocationPool(String),
+    /// A user's custodial wallet
+    UserWallet(String),
+    /// Unspent prepaid budget of a matching campaign
+    Campaign(String),
+    /// A hunt's completion bonus pool
+    HuntPool(String),
+    /// A team's shared wallet
+    TeamWallet(String),
+}
+
+impl LedgerAccount {
+    /// Whether this is an asset account (balance grows with debits).
+    /// All other accounts are liabilities or income and grow with credits.
+    pub fn is_asset(&self) -> bool {
+        matches!(self, Self::Node)
+    }
+
+    /// Whether the account may hold a negative balance
+    pub fn may_be_negative(&self) -> bool {
+        matches!(self, Self::Equity)
+    }
+}
+
+impl std::fmt::Display for LedgerAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Node => write!(f, "node"),
+            Self::Fees => write!(f, "fees"),
+            Self::Equity => write!(f, "equity"),
+            Self::Unallocated => write!(f, "unallocated"),
+            Self::PendingWithdrawals => write!(f, "pending_withdrawals"),
+            Self::LocationPool(id) => write!(f, "location:{}", id),
+            Self::UserWallet(id) => write!(f, "user:{}", id),
+            Self::Campaign(id) => write!(f, "campaign:{}", id),
+            Self::HuntPool(id) => write!(f, "hunt:{}", id),
+            Self::TeamWallet(id) => write!(f, "team:{}", id),
+        }
+    }
+}
+
+impl std::str::FromStr for LedgerAccount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "node" => Ok(Self::Node),
+            "fees" => Ok(Self::Fees),
+            "equity" => Ok(Self::Equity),
+            "unallocated" => Ok(Self::Unallocated),
+            "pending_withdrawals" => Ok(Self::PendingWithdrawals),
+            _ => match s.split_once(':') {
+                Some(("location", id)) if !id.is_empty() => Ok(Self::LocationPool(id.to_string())),
+                Some(("user", id)) if !id.is_empty() => Ok(Self::UserWallet(id.to_string())),
+                Some(("campaign", id)) if !id.is_empty() => Ok(Self::Campaign(id.to_string())),
+                Some(("hunt", id)) if !id.is_empty() => Ok(Self::HuntPool(id.to_string())),
+                Some(("team", id)) if !id.is_empty() => Ok(Self::TeamWallet(id.to_string())),
+                _ => Err(anyhow::anyhow!("Invalid ledger account: {}", s)),
+            },
+        }
+    }
+}
+
+impl TryFrom<String> for LedgerAccount {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<LedgerAccount> for String {
+    fn from(account: LedgerAccount) -> Self {
+        account.to_string()
+    }
+}
+
+/// What caused a ledger entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryKind {
+    /// Donation received by the node
+    Donation,
+    /// Share of a global donation assigned to a location
+    DonationSplit,
+    /// Sats collected from a location into a user's wallet
+    Claim,
+    /// Sats paid out directly from a location (LNURL-withdraw)
+    LocationWithdraw,
+    /// Wallet balance reserved for an in-flight withdrawal
+    WithdrawReserve,
+    /// Reserved balance paid out by the node
+    Withdraw,
+    /// Fee charged on a wallet withdrawal
+    WithdrawFee,
+    /// Reserved balance returned after a failed withdrawal
+    WithdrawRelease,
+    /// Reserved fee returned after a withdrawal paid less in routing fees
+    WithdrawFeeRefund,
+    /// Routing fee paid by the node for a withdrawal
+    RoutingFee,
+    /// Remaining pool of a deleted location returned to unallocated
+    LocationClosed,
+    /// Sponsor payment funding a matching campaign's budget
+    CampaignFunding,
+    /// Campaign budget matching a donation to a location
+    CampaignMatch,
+    /// Budget left when a campaign ended, donated to the locations in its region
+    CampaignLeftover,
+    /// Payment to a user's Lightning address received by the node
+    WalletReceive,
+    /// Hunt completion bonus paid from the hunt's pool into a user's wallet
+    HuntBonus,
+    /// Hint paid from a user's wallet into the location's pool
+    HintPurchase,
+    /// Team wallet balance withdrawn into a member's wallet
+    TeamPayout,
+}
+
+impl LedgerEntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Donation => "donation",
+            Self::DonationSplit => "donation_split",
+            Self::Claim => "claim",
+            Self::LocationWithdraw => "location_withdraw",
+            Self::WithdrawReserve => "withdraw_reserve",
+            Self::Withdraw => "withdraw",
+            Self::WithdrawFee => "withdraw_fee",
+            Self::WithdrawRelease => "withdraw_release",
+            Self::WithdrawFeeRefund => "withdraw_fee_refund",
+            Self::RoutingFee => "routing_fee",
+            Self::LocationClosed => "location_closed",
+            Self::CampaignFunding => "campaign_funding",
+            Self::CampaignMatch => "campaign_match",
+            Self::CampaignLeftover => "campaign_leftover",
+            Self::WalletReceive => "wallet_receive",
+            Self::HuntBonus => "hunt_bonus",
+            Self::HintPurchase => "hint_purchase",
+            Self::TeamPayout => "team_payout",
+        }
+    }
+}
+
+impl std::fmt::Display for LedgerEntryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for LedgerEntryKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "donation" => Ok(Self::Donation),
+            "donation_split" => Ok(Self::DonationSplit),
+            "claim" => Ok(Self::Claim),
+            "location_withdraw" => Ok(Self::LocationWithdraw),
+            "withdraw_reserve" => Ok(Self::WithdrawReserve),
+            "withdraw" => Ok(Self::Withdraw),
+            "withdraw_fee" => Ok(Self::WithdrawFee),
+            "withdraw_release" => Ok(Self::WithdrawRelease),
+            "withdraw_fee_refund" => Ok(Self::WithdrawFeeRefund),
+            "routing_fee" => Ok(Self::RoutingFee),
+            "location_closed" => Ok(Self::LocationClosed),
+            "campaign_funding" => Ok(Self::CampaignFunding),
+            "campaign_match" => Ok(Self::CampaignMatch),
+            "campaign_leftover" => Ok(Self::CampaignLeftover),
+            "wallet_receive" => Ok(Self::WalletReceive),
+            "hunt_bonus" => Ok(Self::HuntBonus),
+            "hint_purchase" => Ok(Self::HintPurchase),
+            "team_payout" => Ok(Self::TeamPayout),
+            _ => Err(anyhow::anyhow!("Invalid ledger entry kind: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for LedgerEntryKind {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A single balanced journal entry: `msats` move from `credit_account` into `debit_account`
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub id: String,
+    #[sqlx(try_from = "String")]
+    pub kind: LedgerEntryKind,
+    #[sqlx(try_from = "String")]
+    pub debit_account: LedgerAccount,
+    #[sqlx(try_from = "String")]
+    pub credit_account: LedgerAccount,
+    pub msats: i64,
+    pub reference_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LedgerEntry {
+    /// Get amount in sats for display
+    pub fn sats(&self) -> i64 {
+        self.msats / 1000
+    }
+}
+
+/// Balance of a single ledger account, signed by its normal side
+/// (debits for assets, credits for everything else)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerAccountBalance {
+    pub account: LedgerAccount,
+    pub msats: i64,
+}
+
+/// A problem found while verifying the ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerDiscrepancy {
+    pub account: Option<LedgerAccount>,
+    /// Balance according to the ledger
+    pub ledger_msats: i64,
+    /// Balance according to the legacy tables (or the expected value)
+    pub expected_msats: i64,
+    pub message: String,
+}
+
+/// Result of `Database::verify_ledger`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerReport {
+    pub balances: Vec<LedgerAccountBalance>,
+    pub total_debits_msats: i64,
+    pub total_credits_msats: i64,
+    pub entry_count: i64,
+    pub discrepancies: Vec<LedgerDiscrepancy>,
+}
+
+impl LedgerReport {
+    pub fn is_balanced(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// Balance of the node account, i.e. what the node should be holding
+    pub fn node_msats(&self) -> i64 {
+        self.balance_of(&LedgerAccount::Node)
+    }
+
+    pub fn balance_of(&self, account: &LedgerAccount) -> i64 {
+        self.balances
+            .iter()
+            .find(|b| &b.account == account)
+            .map(|b| b.msats)
+            .unwrap_or(0)
+    }
+}
+
+/// Published Merkle-sum root over user wallet balances (see `solvency`)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct LiabilitySnapshot {
+    pub id: String,
+    /// Hex encoded root hash
+    pub root_hash: String,
+    /// Sum of all user wallet balances in the tree
+    pub sum_msats: i64,
+    /// Number of users holding a balance
+    pub user_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_test_location(current_msats: i64) -> Location {
+        let now = Utc::now();
+        Location {
+            id: "test-id".to_string(),
+            name: "Test Location".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+            description: None,
+            created_at: now,
+            current_msats,
+            lnurlw_secret: "secret".to_string(),
+            last_refill_at: now,
+            last_withdraw_at: None,
+            write_token: None,
+            write_token_used: false,
+            write_token_created_at: None,
+            user_id: "user-id".to_string(),
+            status: "active".to_string(),
+            claim_policy: ClaimPolicy::LastScanner,
+            disclosure: Disclosure::Exact,
+            search_radius_m: None,
+            search_latitude: None,
+            search_longitude: None,
+            activated_at: None,
+        }
+    }
+
+    // Note: tests for withdrawable_msats, withdrawable_sats, current_sats, last_activity_at removed
+    // Balance is now computed on-demand via compute_balance_msats()
+
+    #[test]
+    fn test_location_status_helpers() {
+        let mut location = make_test_location(1000);
+
+        location.status = "created".to_string();
+        assert!(location.is_created());
+        assert!(!location.is_programmed());
+        assert!(!location.is_active());
+        assert!(!location.is_deactivated());
+        assert!(!location.is_admin_deactivated());
+        assert!(!location.is_visible());
+
+        location.status = "programmed".to_string();
+        assert!(!location.is_created());
+        assert!(location.is_programmed());
+        assert!(!location.is_active());
+        assert!(!location.is_deactivated());
+        assert!(!location.is_admin_deactivated());
+        assert!(!location.is_visible());
+
+        location.status = "active".to_string();
+        assert!(!location.is_created());
+        assert!(!location.is_programmed());
+        assert!(location.is_active());
+        assert!(!location.is_deactivated());
+        assert!(!location.is_admin_deactivated());
+        assert!(location.is_visible());
+        assert!(location.is_live());
+
+        location.status = "scheduled".to_string();
+        assert!(!location.is_active());
+        assert!(location.is_scheduled());
+        assert!(!location.is_visible());
+        assert!(location.is_live());
+        assert!(!location.can_creator_reactivate());
+
+        location.status = "deactivated".to_string();
+        assert!(!location.is_created());
+        assert!(!location.is_programmed());
+        assert!(!location.is_active());
+        assert!(location.is_deactivated());
+        assert!(!location.is_admin_deactivated());
+        assert!(!location.is_visible());
+        assert!(location.can_creator_reactivate());
+
+        location.status = "admin_deactivated".to_string();
+        assert!(!location.is_created());
+        assert!(!location.is_programmed());
+        assert!(!location.is_active());
+        assert!(!location.is_deactivated());
+        assert!(location.is_admin_deactivated());
+        assert!(!location.is_visible());
+        assert!(!location.can_creator_reactivate());
+    }
+
+    #[test]
+    fn test_auth_method_password_roundtrip() {
+        let auth = AuthMethod::Password {
+            password_hash: "argon2hash123".to_string(),
+        };
+
+        let json = auth.to_json().unwrap();
+        let parsed = AuthMethod::from_json("password", &json).unwrap();
+
+        match parsed {
+            AuthMethod::Password { password_hash } => {
+                assert_eq!(password_hash, "argon2hash123");
+            }
+            _ => panic!("Expected Password variant"),
+        }
+
+        assert_eq!(auth.to_type_string(), "password");
+    }
+
+    #[test]
+    fn test_auth_method_oauth_google_roundtrip() {
+        let auth = AuthMethod::OAuthGoogle {
+            google_id: "google123".to_string(),
+        };
+
+        let json = auth.to_json().unwrap();
+        let parsed = AuthMethod::from_json("oauth_google", &json).unwrap();
+
+        match parsed {
+            AuthMethod::OAuthGoogle { google_id } => {
+                assert_eq!(google_id, "google123");
+            }
+            _ => panic!("Expected OAuthGoogle variant"),
+        }
+
+        assert_eq!(auth.to_type_string(), "oauth_google");
+    }
+
+    #[test]
+    fn test_auth_method_oauth_github_roundtrip() {
+        let auth = AuthMethod::OAuthGithub {
+            github_id: "github456".to_string(),
+        };
+
+        let json = auth.to_json().unwrap();
+        let parsed = AuthMethod::from_json("oauth_github", &json).unwrap();
+
+        match parsed {
+            AuthMethod::OAuthGithub { github_id } => {
+                assert_eq!(github_id, "github456");
+            }
+            _ => panic!("Expected OAuthGithub variant"),
+        }
+
+        assert_eq!(auth.to_type_string(), "oauth_github");
+    }
+
+    #[test]
+    fn test_auth_method_anonymous_roundtrip() {
+        let auth = AuthMethod::Anonymous {};
+
+        let json = auth.to_json().unwrap();
+        let parsed = AuthMethod::from_json("anonymous", &json).unwrap();
+
+        match parsed {
+            AuthMethod::Anonymous {} => {}
+            _ => panic!("Expected Anonymous variant"),
+        }
+
+        assert_eq!(auth.to_type_string(), "anonymous");
+    }
+
+    #[test]
+    fn test_auth_method_from_json_unknown_type() {
+        let result = AuthMethod::from_json("unknown", "{}");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_user_display_name() {
+        let now = Utc::now();
+
+        // Registered user with username
+        let registered_user = User {
             id: "550e8400-e29b-41d4-a716-446655440000".to_string(),
             username: Some("testuser".to_string()),
             email: None,
@@ -846,6 +2185,7 @@ mod tests {
             created_at: now,
             last_login_at: None,
             role: UserRole::User,
+            leaderboard_opt_in: false,
         };
         assert_eq!(registered_user.display_name(), "testuser");
         assert!(!registered_user.is_anonymous());
@@ -860,6 +2200,7 @@ mod tests {
             created_at: now,
             last_login_at: None,
             role: UserRole::User,
+            leaderboard_opt_in: false,
         };
         assert_eq!(anon_user.display_name(), "anon_550e8400");
         assert!(anon_user.is_anonymous());
@@ -891,6 +2232,7 @@ mod tests {
             created_at: now,
             last_login_at: None,
             role: UserRole::Admin,
+            leaderboard_opt_in: false,
         };
         assert!(admin_user.is_admin());
         assert!(admin_user.is_creator());
@@ -905,6 +2247,7 @@ mod tests {
             created_at: now,
             last_login_at: None,
             role: UserRole::Creator,
+            leaderboard_opt_in: false,
         };
         assert!(!creator_user.is_admin());
         assert!(creator_user.is_creator());
@@ -937,7 +2280,42 @@ mod tests {
         };
         assert!(!withdraw_tx.is_collect());
         assert!(withdraw_tx.is_withdraw());
+        assert!(!withdraw_tx.is_credit());
         assert_eq!(withdraw_tx.sats(), 3);
+
+        let receive_tx = UserTransaction {
+            id: "tx-3".to_string(),
+            user_id: "user-1".to_string(),
+            location_id: None,
+            msats: 21000,
+            transaction_type: "receive".to_string(),
+            created_at: now,
+        };
+        assert!(receive_tx.is_receive());
+        assert!(receive_tx.is_credit());
+        assert!(!receive_tx.is_withdraw());
+
+        let payout_tx = UserTransaction {
+            id: "tx-4".to_string(),
+            user_id: "user-1".to_string(),
+            location_id: None,
+            msats: 8000,
+            transaction_type: "team_payout".to_string(),
+            created_at: now,
+        };
+        assert!(payout_tx.is_team_payout());
+        assert!(payout_tx.is_credit());
+    }
+
+    #[test]
+    fn test_team_roles() {
+        for role in TeamRole::ALL {
+            assert_eq!(role.as_str().parse::<TeamRole>().unwrap(), role);
+        }
+        assert!("captain".parse::<TeamRole>().is_err());
+        assert!(TeamRole::Owner.can_withdraw() && TeamRole::Owner.can_manage());
+        assert!(TeamRole::Treasurer.can_withdraw() && !TeamRole::Treasurer.can_manage());
+        assert!(!TeamRole::Member.can_withdraw() && !TeamRole::Member.can_manage());
     }
 
     #[test]
@@ -950,6 +2328,11 @@ mod tests {
             status: DonationStatus::Received,
             created_at: Utc::now(),
             received_at: Some(Utc::now()),
+            comment: None,
+            zap_request: None,
+            zap_receipt_id: None,
+            hunt_id: None,
+            user_id: None,
         };
         assert_eq!(donation.amount_sats(), 123);
         assert!(donation.is_received());
@@ -967,5 +2350,216 @@ mod tests {
         assert_eq!(claim.sats_claimed(), 5);
     }
 
+    #[test]
+    fn test_ledger_account_roundtrip() {
+        let accounts = [
+            LedgerAccount::Node,
+            LedgerAccount::Fees,
+            LedgerAccount::Equity,
+            LedgerAccount::Unallocated,
+            LedgerAccount::PendingWithdrawals,
+            LedgerAccount::LocationPool("loc-1".to_string()),
+            LedgerAccount::UserWallet("user-1".to_string()),
+            LedgerAccount::Campaign("campaign-1".to_string()),
+            LedgerAccount::HuntPool("hunt-1".to_string()),
+            LedgerAccount::TeamWallet("team-1".to_string()),
+        ];
+        for account in accounts {
+            let parsed: LedgerAccount = account.to_string().parse().unwrap();
+            assert_eq!(parsed, account);
+        }
+        assert_eq!(
+            LedgerAccount::LocationPool("abc".to_string()).to_string(),
+            "location:abc"
+        );
+        assert!("location:".parse::<LedgerAccount>().is_err());
+        assert!("bogus".parse::<LedgerAccount>().is_err());
+        assert!(LedgerAccount::Node.is_asset());
+        assert!(!LedgerAccount::Fees.is_asset());
+        assert!(LedgerAccount::Equity.may_be_negative());
+        assert!(!LedgerAccount::Fees.may_be_negative());
+    }
+
+    fn make_test_campaign() -> MatchingCampaign {
+        MatchingCampaign {
+            id: "campaign-id".to_string(),
+            name: "Spring match".to_string(),
+            sponsor_name: "Sponsor".to_string(),
+            budget_msats: 10_000_000,
+            spent_msats: 0,
+            match_percent: 100,
+            max_match_msats: None,
+            min_latitude: Some(47.0),
+            max_latitude: Some(48.0),
+            min_longitude: Some(8.0),
+            max_longitude: Some(9.0),
+            ends_at: Utc::now() + chrono::Duration::days(7),
+            status: CampaignStatus::Active,
+            funding_invoice: "lnbc1".to_string(),
+            created_at: Utc::now(),
+            funded_at: Some(Utc::now()),
+            ended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_recurring_donation_interval_label() {
+        let mut recurring = RecurringDonation {
+            id: "recurring-id".to_string(),
+            user_id: "user-id".to_string(),
+            location_id: None,
+            amount_msats: 100_000,
+            interval_days: 7,
+            nwc_uri: "nostr+walletconnect://secret".to_string(),
+            status: RecurringDonationStatus::Active,
+            next_payment_at: Utc::now(),
+            last_payment_at: None,
+            failure_count: 0,
+            last_error: None,
+            created_at: Utc::now(),
+            cancelled_at: None,
+        };
+        assert_eq!(recurring.amount_sats(), 100);
+        assert_eq!(recurring.interval_label(), "week");
+        recurring.interval_days = 14;
+        assert_eq!(recurring.interval_label(), "2 weeks");
+        recurring.interval_days = 3;
+        assert_eq!(recurring.interval_label(), "3 days");
+
+        // The wallet secret never leaves the server
+        let json = serde_json::to_string(&recurring).unwrap();
+        assert!(!json.contains("walletconnect"));
+    }
+
+    #[test]
+    fn test_campaign_region() {
+        let mut campaign = make_test_campaign();
+        assert!(campaign.has_region());
+        assert!(campaign.covers(47.5, 8.5));
+        assert!(campaign.covers(47.0, 9.0));
+        assert!(!campaign.covers(46.9, 8.5));
+        assert!(!campaign.covers(47.5, 9.1));
+
+        campaign.min_latitude = None;
+        campaign.max_latitude = None;
+        assert!(campaign.covers(-30.0, 8.5));
+
+        campaign.min_longitude = None;
+        campaign.max_longitude = None;
+        assert!(!campaign.has_region());
+        assert!(campaign.covers(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_campaign_match_msats() {
+        let mut campaign = make_test_campaign();
+        assert_eq!(campaign.match_msats(2_000_000, 0), 2_000_000);
+
+        campaign.match_percent = 50;
+        assert_eq!(campaign.match_msats(2_000_000, 0), 1_000_000);
+
+        // Per-donation cap, partly used by an earlier share of the same donation
+        campaign.max_match_msats = Some(1_500_000);
+        campaign.match_percent = 100;
+        assert_eq!(campaign.match_msats(2_000_000, 0), 1_500_000);
+        assert_eq!(campaign.match_msats(2_000_000, 1_000_000), 500_000);
+        assert_eq!(campaign.match_msats(2_000_000, 2_000_000), 0);
+
+        // Never more than the remaining budget
+        campaign.max_match_msats = None;
+        campaign.spent_msats = 9_500_000;
+        assert_eq!(campaign.match_msats(2_000_000, 0), 500_000);
+    }
+
+    #[test]
+    fn test_campaign_is_active() {
+        let now = Utc::now();
+        let mut campaign = make_test_campaign();
+        assert!(campaign.is_active(now));
+        assert!(!campaign.is_active(campaign.ends_at));
+
+        campaign.spent_msats = campaign.budget_msats;
+        assert!(!campaign.is_active(now));
+
+        campaign.spent_msats = 0;
+        campaign.status = CampaignStatus::Funding;
+        assert!(!campaign.is_active(now));
+    }
+
+    fn make_test_hint(unlock_kind: HintUnlock) -> Hint {
+        Hint {
+            id: "hint-id".to_string(),
+            location_id: "location-id".to_string(),
+            position: 1,
+            text: "Under the bench".to_string(),
+            unlock_kind,
+            unlock_after_days: Some(7),
+            price_msats: Some(100_000),
+            riddle: Some("What has keys but can't open locks?".to_string()),
+            riddle_answer: Some("A Piano".to_string()),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_hint_riddle_answer() {
+        let hint = make_test_hint(HintUnlock::Riddle);
+        assert!(hint.is_answer("a piano"));
+        assert!(hint.is_answer("  A   PIANO "));
+        assert!(!hint.is_answer("piano"));
+        assert!(!hint.is_answer(""));
+
+        // Only riddles are unlocked by answering
+        assert!(!make_test_hint(HintUnlock::Payment).is_answer("a piano"));
+    }
+
+    #[test]
+    fn test_hint_unlocks_at() {
+        let created_at = Utc::now();
+        assert_eq!(
+            make_test_hint(HintUnlock::Time).unlocks_at(created_at),
+            Some(created_at + chrono::Duration::days(7))
+        );
+        assert_eq!(
+            make_test_hint(HintUnlock::Riddle).unlocks_at(created_at),
+            None
+        );
+        assert_eq!(make_test_hint(HintUnlock::Payment).price_sats(), 100);
+    }
+
+    #[test]
+    fn test_location_position_disclosure() {
+        use crate::disclosure::Position;
+
+        let mut location = make_test_location(0);
+        location.latitude = 37.7749;
+        location.longitude = -122.4194;
+        let exact = Position::Exact {
+            latitude: 37.7749,
+            longitude: -122.4194,
+        };
+        assert_eq!(Position::of(&location, false, false), exact);
+
+        // Without a search area a fuzzed location stays hidden
+        location.disclosure = Disclosure::Fuzzed;
+        assert_eq!(Position::of(&location, false, false), Position::Hidden);
+
+        let area = SearchArea {
+            latitude: 37.7760,
+            longitude: -122.4180,
+            radius_m: 250,
+        };
+        location.search_latitude = Some(area.latitude);
+        location.search_longitude = Some(area.longitude);
+        location.search_radius_m = Some(area.radius_m);
+        assert_eq!(Position::of(&location, false, false), Position::Area(area));
+        assert_eq!(Position::of(&location, true, false), exact);
+
+        location.disclosure = Disclosure::Hidden;
+        assert_eq!(Position::of(&location, false, false), Position::Hidden);
+        assert_eq!(Position::of(&location, false, true), Position::Area(area));
+        assert_eq!(Position::of(&location, true, false), exact);
+    }
+
     // Note: test_refill_display_methods removed - Refill struct removed
 }
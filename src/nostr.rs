@@ -0,0 +1,289 @@
+//! Nostr events and relays.
+//!
+//! This module handles:
+//! - Signing and verifying Nostr events (NIP-01)
+//! - Publishing events to relays
+//! - Connecting only to relays on public addresses
+//!
+//! Used by the Nostr Wallet Connect client and for zap receipts.
+
+use crate::lnurl::is_public_ip;
+use futures_util::{SinkExt, StreamExt};
+use secp256k1::{schnorr::Signature, Keypair, Message, Secp256k1, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::{lookup_host, TcpStream};
+use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
+
+/// An open connection to a relay
+pub type RelayStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Errors that can occur while publishing to a relay
+#[derive(Debug, Error)]
+pub enum RelayError {
+    #[error("Relay rejected event: {0}")]
+    Rejected(String),
+
+    #[error("Relay closed the connection")]
+    Closed,
+
+    #[error("Timed out waiting for the relay")]
+    Timeout,
+
+    #[error("Relay not allowed: {0}")]
+    Forbidden(String),
+
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tungstenite::Error>),
+}
+
+impl From<tungstenite::Error> for RelayError {
+    fn from(e: tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(e))
+    }
+}
+
+/// Which relays the server may connect to
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RelayAccess {
+    /// Allow relays on private, loopback and link-local addresses (tests only)
+    pub allow_private_ips: bool,
+    /// Allow plain `ws://` relays instead of only `wss://` (tests only)
+    pub allow_ws: bool,
+}
+
+/// Connect to a relay given by a user.
+///
+/// Relay URLs come from connection URIs and zap requests, so only `wss://`
+/// relays on public IPs are accepted. The host is resolved once, every address
+/// is checked and the connection goes to those addresses, so a second DNS
+/// lookup can't swap them.
+pub async fn connect(relay: &str, access: RelayAccess) -> Result<RelayStream, RelayError> {
+    let forbidden = RelayError::Forbidden;
+
+    let url = url::Url::parse(relay).map_err(|_| forbidden(format!("invalid URL {}", relay)))?;
+    match url.scheme() {
+        "wss" => {}
+        "ws" if access.allow_ws => {}
+        _ => return Err(forbidden(format!("{} is not a wss:// URL", relay))),
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| forbidden(format!("{} has no host", relay)))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| forbidden(format!("{} has no port", relay)))?;
+
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(url::Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(url::Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        _ => lookup_host((host, port))
+            .await
+            .map_err(|e| forbidden(format!("DNS lookup of {} failed: {}", host, e)))?
+            .collect(),
+    };
+    if addrs.is_empty() {
+        return Err(forbidden(format!("{} has no addresses", host)));
+    }
+    for addr in &addrs {
+        if !access.allow_private_ips && !is_public_ip(addr.ip()) {
+            return Err(forbidden(format!("{} ({})", host, addr.ip())));
+        }
+    }
+
+    let stream = TcpStream::connect(addrs.as_slice())
+        .await
+        .map_err(tungstenite::Error::from)?;
+    let (ws, _) = tokio_tungstenite::client_async_tls(relay, stream).await?;
+    Ok(ws)
+}
+
+/// A signed Nostr event (NIP-01)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NostrEvent {
+    pub id: String,
+    pub pubkey: String,
+    pub created_at: u64,
+    pub kind: u64,
+    pub tags: Vec<Vec<String>>,
+    pub content: String,
+    pub sig: String,
+}
+
+impl NostrEvent {
+    /// Create and sign an event
+    pub fn sign(
+        keypair: &Keypair,
+        kind: u64,
+        tags: Vec<Vec<String>>,
+        content: String,
+        created_at: u64,
+    ) -> Self {
+        let pubkey = hex::encode(keypair.x_only_public_key().0.serialize());
+        let id = event_id(&pubkey, created_at, kind, &tags, &content);
+        let sig = Secp256k1::new()
+            .sign_schnorr_no_aux_rand(&Message::from_digest(id), keypair)
+            .serialize();
+
+        Self {
+            id: hex::encode(id),
+            pubkey,
+            created_at,
+            kind,
+            tags,
+            content,
+            sig: hex::encode(sig),
+        }
+    }
+
+    /// Check that the id matches the content and the signature matches the pubkey
+    pub fn verify(&self) -> bool {
+        let id = event_id(
+            &self.pubkey,
+            self.created_at,
+            self.kind,
+            &self.tags,
+            &self.content,
+        );
+        if hex::encode(id) != self.id {
+            return false;
+        }
+        let Ok(pubkey) = XOnlyPublicKey::from_str(&self.pubkey) else {
+            return false;
+        };
+        let Ok(sig) = Signature::from_str(&self.sig) else {
+            return false;
+        };
+        Secp256k1::verification_only()
+            .verify_schnorr(&sig, &Message::from_digest(id), &pubkey)
+            .is_ok()
+    }
+
+    /// Value of the first tag with the given name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|t| t.first().map(String::as_str) == Some(name))
+            .and_then(|t| t.get(1))
+            .map(String::as_str)
+    }
+}
+
+fn event_id(
+    pubkey: &str,
+    created_at: u64,
+    kind: u64,
+    tags: &[Vec<String>],
+    content: &str,
+) -> [u8; 32] {
+    let serialized = json!([0, pubkey, created_at, kind, tags, content]).to_string();
+    Sha256::digest(serialized.as_bytes()).into()
+}
+
+/// Publish an event to a relay and wait for it to be accepted (NIP-20 `OK`)
+pub async fn publish(
+    relay: &str,
+    event: &NostrEvent,
+    access: RelayAccess,
+    timeout: Duration,
+) -> Result<(), RelayError> {
+    tokio::time::timeout(timeout, publish_inner(relay, event, access))
+        .await
+        .map_err(|_| RelayError::Timeout)?
+}
+
+async fn publish_inner(
+    relay: &str,
+    event: &NostrEvent,
+    access: RelayAccess,
+) -> Result<(), RelayError> {
+    let mut ws = connect(relay, access).await?;
+    ws.send(tungstenite::Message::text(
+        json!(["EVENT", event]).to_string(),
+    ))
+    .await?;
+
+    while let Some(message) = ws.next().await {
+        let text = match message? {
+            tungstenite::Message::Text(text) => text,
+            tungstenite::Message::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(serde_json::Value::Array(frame)) = serde_json::from_str(&text) else {
+            continue;
+        };
+
+        // ["OK", <event id>, <accepted>, <message>]
+        if frame.first().and_then(|v| v.as_str()) == Some("OK")
+            && frame.get(1).and_then(|v| v.as_str()) == Some(event.id.as_str())
+        {
+            let _ = ws.close(None).await;
+            return match frame.get(2).and_then(|v| v.as_bool()) {
+                Some(true) => Ok(()),
+                _ => {
+                    let reason = frame.get(3).and_then(|v| v.as_str()).unwrap_or_default();
+                    Err(RelayError::Rejected(reason.to_string()))
+                }
+            };
+        }
+    }
+
+    Err(RelayError::Closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::SecretKey;
+
+    fn keypair(byte: u8) -> Keypair {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        Keypair::from_secret_key(&Secp256k1::new(), &secret)
+    }
+
+    #[tokio::test]
+    async fn test_connect_refuses_private_and_plain_relays() {
+        for relay in [
+            "ws://relay.example.com",
+            "https://relay.example.com",
+            "wss://127.0.0.1:7000",
+            "wss://[::1]:7000",
+            "wss://10.1.2.3",
+            "wss://localhost:7000",
+        ] {
+            let result = connect(relay, RelayAccess::default()).await;
+            assert!(
+                matches!(result, Err(RelayError::Forbidden(_))),
+                "connected to {}",
+                relay
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_sign_and_verify() {
+        let keys = keypair(1);
+        let event = NostrEvent::sign(
+            &keys,
+            1,
+            vec![vec!["p".to_string(), "abc".to_string()]],
+            "hello \"nostr\"\n".to_string(),
+            1_700_000_000,
+        );
+        assert!(event.verify());
+        assert_eq!(event.tag("p"), Some("abc"));
+
+        let mut tampered = event.clone();
+        tampered.content = "bye".to_string();
+        assert!(!tampered.verify());
+
+        let mut wrong_key = event;
+        wrong_key.pubkey = hex::encode(keypair(2).x_only_public_key().0.serialize());
+        assert!(!wrong_key.verify());
+    }
+}
@@ -0,0 +1,538 @@
+//! Nostr Wallet Connect (NIP-47) client.
+//!
+//! This module handles:
+//! - Parsing `nostr+walletconnect://` connection URIs
+//! - Encrypting request and response content (NIP-04)
+//! - Asking a donor's wallet to pay an invoice over a relay (`pay_invoice`)
+//! - Asking whether it paid an invoice it didn't answer for (`lookup_invoice`)
+//! - Encrypting connection URIs for storage
+//!
+//! A request is a kind 23194 event from the connection's secret key to the wallet
+//! service, the response a kind 23195 event from the wallet tagging the request id.
+
+use crate::invoice_policy;
+use crate::nostr::{self, NostrEvent, RelayAccess};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use secp256k1::{ecdh, Keypair, Parity, PublicKey, Secp256k1, SecretKey, XOnlyPublicKey};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+use tokio_tungstenite::tungstenite;
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// Event kind of wallet requests
+pub const REQUEST_KIND: u64 = 23194;
+/// Event kind of wallet responses
+pub const RESPONSE_KIND: u64 = 23195;
+
+const URI_SCHEME: &str = "nostr+walletconnect";
+/// Prefix of encrypted connection URIs
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// Errors that can occur while talking to a wallet over NWC
+#[derive(Debug, Error)]
+pub enum NwcError {
+    #[error("Invalid NWC connection URI: {0}")]
+    InvalidUri(String),
+
+    #[error("Relay error: {0}")]
+    Relay(String),
+
+    #[error("Wallet returned {code}: {message}")]
+    Wallet { code: String, message: String },
+
+    #[error("Invalid wallet response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Timed out waiting for the wallet")]
+    Timeout,
+
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tungstenite::Error>),
+}
+
+impl From<tungstenite::Error> for NwcError {
+    fn from(e: tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(e))
+    }
+}
+
+fn shared_key(secret: &SecretKey, peer: &XOnlyPublicKey) -> [u8; 32] {
+    let peer = PublicKey::from_x_only_public_key(*peer, Parity::Even);
+    let point = ecdh::shared_secret_point(&peer, secret);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&point[..32]);
+    key
+}
+
+/// Encrypt a message for `peer` (NIP-04): `base64(ciphertext)?iv=base64(iv)`
+pub fn nip04_encrypt(secret: &SecretKey, peer: &XOnlyPublicKey, plaintext: &str) -> String {
+    let key = shared_key(secret, peer);
+    let iv: [u8; 16] = rand::random();
+
+    let mut buf = vec![0u8; plaintext.len() + 16];
+    buf[..plaintext.len()].copy_from_slice(plaintext.as_bytes());
+    let ciphertext = Aes256CbcEnc::new(&key.into(), &iv.into())
+        .encrypt_padded_mut::<Pkcs7>(&mut buf, plaintext.len())
+        .expect("buffer has room for a full padding block");
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    format!("{}?iv={}", b64.encode(ciphertext), b64.encode(iv))
+}
+
+/// Decrypt a NIP-04 message from `peer`
+pub fn nip04_decrypt(
+    secret: &SecretKey,
+    peer: &XOnlyPublicKey,
+    payload: &str,
+) -> Result<String, NwcError> {
+    let invalid = |msg: &str| NwcError::InvalidResponse(msg.to_string());
+
+    let (ciphertext, iv) = payload
+        .split_once("?iv=")
+        .ok_or_else(|| invalid("missing iv"))?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let mut buf = b64
+        .decode(ciphertext)
+        .map_err(|_| invalid("invalid ciphertext encoding"))?;
+    let iv: [u8; 16] = b64
+        .decode(iv)
+        .ok()
+        .and_then(|iv| iv.try_into().ok())
+        .ok_or_else(|| invalid("invalid iv"))?;
+
+    let key = shared_key(secret, peer);
+    let plaintext = Aes256CbcDec::new(&key.into(), &iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .map_err(|_| invalid("decryption failed"))?;
+
+    String::from_utf8(plaintext.to_vec()).map_err(|_| invalid("plaintext is not UTF-8"))
+}
+
+/// Encrypts connection URIs for storage.
+///
+/// A connection URI holds the secret that lets us spend from the donor's
+/// wallet, so only `enc1:base64(iv || AES-256-CBC ciphertext || HMAC-SHA256)`
+/// is stored, with keys derived from the server secret.
+#[derive(Clone)]
+pub struct UriCipher {
+    enc_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl UriCipher {
+    pub fn new(server_secret: &[u8]) -> Self {
+        let derive = |label: &[u8]| -> [u8; 32] {
+            let mut mac =
+                HmacSha256::new_from_slice(server_secret).expect("HMAC can take key of any size");
+            mac.update(label);
+            mac.finalize().into_bytes().into()
+        };
+        Self {
+            enc_key: derive(b"satshunt nwc uri encryption"),
+            mac_key: derive(b"satshunt nwc uri authentication"),
+        }
+    }
+
+    /// Whether a stored URI is already encrypted
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    pub fn encrypt(&self, uri: &str) -> String {
+        let iv: [u8; 16] = rand::random();
+        let mut buf = vec![0u8; uri.len() + 16];
+        buf[..uri.len()].copy_from_slice(uri.as_bytes());
+        let ciphertext = Aes256CbcEnc::new(&self.enc_key.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buf, uri.len())
+            .expect("buffer has room for a full padding block");
+
+        let mut sealed = iv.to_vec();
+        sealed.extend_from_slice(ciphertext);
+        let tag = self.tag(&sealed);
+        sealed.extend_from_slice(&tag);
+
+        let b64 = base64::engine::general_purpose::STANDARD;
+        format!("{}{}", ENCRYPTED_PREFIX, b64.encode(sealed))
+    }
+
+    pub fn decrypt(&self, stored: &str) -> Result<String, NwcError> {
+        let invalid = |msg: &str| NwcError::InvalidUri(msg.to_string());
+
+        let encoded = stored
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| invalid("stored URI is not encrypted"))?;
+        let sealed = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| invalid("invalid encrypted URI encoding"))?;
+        if sealed.len() < 16 + 32 {
+            return Err(invalid("encrypted URI too short"));
+        }
+        let (data, tag) = sealed.split_at(sealed.len() - 32);
+
+        let mut mac =
+            HmacSha256::new_from_slice(&self.mac_key).expect("HMAC can take key of any size");
+        mac.update(data);
+        mac.verify_slice(tag)
+            .map_err(|_| invalid("encrypted URI failed authentication"))?;
+
+        let (iv, ciphertext) = data.split_at(16);
+        let iv: [u8; 16] = iv.try_into().expect("split at 16 bytes");
+        let mut buf = ciphertext.to_vec();
+        let plaintext = Aes256CbcDec::new(&self.enc_key.into(), &iv.into())
+            .decrypt_padded_mut::<Pkcs7>(&mut buf)
+            .map_err(|_| invalid("decryption failed"))?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| invalid("URI is not UTF-8"))
+    }
+
+    fn tag(&self, data: &[u8]) -> [u8; 32] {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.mac_key).expect("HMAC can take key of any size");
+        mac.update(data);
+        mac.finalize().into_bytes().into()
+    }
+}
+
+/// Response to a NIP-47 request
+#[derive(Debug, Deserialize)]
+struct WalletResponse {
+    result_type: String,
+    error: Option<WalletError>,
+    result: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletError {
+    code: String,
+    message: String,
+}
+
+/// A parsed `nostr+walletconnect://<wallet pubkey>?relay=<url>&secret=<hex>` URI
+#[derive(Clone)]
+pub struct NwcConnection {
+    pub wallet_pubkey: XOnlyPublicKey,
+    pub relay: String,
+    keypair: Keypair,
+    access: RelayAccess,
+}
+
+impl std::fmt::Debug for NwcConnection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the secret
+        f.debug_struct("NwcConnection")
+            .field("wallet_pubkey", &self.wallet_pubkey)
+            .field("relay", &self.relay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for NwcConnection {
+    type Err = NwcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, RelayAccess::default())
+    }
+}
+
+impl NwcConnection {
+    /// Parse a connection URI, allowing the relays `access` allows. Only
+    /// `wss://` relays are accepted by default.
+    pub fn parse_with(s: &str, access: RelayAccess) -> Result<Self, NwcError> {
+        let invalid = |msg: &str| NwcError::InvalidUri(msg.to_string());
+
+        let url = url::Url::parse(s.trim()).map_err(|_| invalid("not a URI"))?;
+        if url.scheme() != URI_SCHEME {
+            return Err(invalid("expected nostr+walletconnect:// scheme"));
+        }
+        // The pubkey is the host for `scheme://pubkey`, the path for `scheme:pubkey`
+        let pubkey = url
+            .host_str()
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| url.path().trim_start_matches('/'));
+        let wallet_pubkey =
+            XOnlyPublicKey::from_str(pubkey).map_err(|_| invalid("invalid wallet pubkey"))?;
+
+        let mut relay = None;
+        let mut secret = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                // Only the first relay is used
+                "relay" if relay.is_none() => relay = Some(value.into_owned()),
+                "secret" => secret = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        let relay = relay.ok_or_else(|| invalid("missing relay"))?;
+        if !(relay.starts_with("wss://") || (access.allow_ws && relay.starts_with("ws://"))) {
+            return Err(invalid("relay must be a wss:// URL"));
+        }
+        let secret = SecretKey::from_str(&secret.ok_or_else(|| invalid("missing secret"))?)
+            .map_err(|_| invalid("invalid secret"))?;
+
+        Ok(Self {
+            wallet_pubkey,
+            relay,
+            keypair: Keypair::from_secret_key(&Secp256k1::new(), &secret),
+            access,
+        })
+    }
+
+    /// Public key the wallet sees requests coming from
+    pub fn client_pubkey(&self) -> XOnlyPublicKey {
+        self.keypair.x_only_public_key().0
+    }
+
+    /// Ask the wallet to pay a BOLT11 invoice. Returns the payment preimage.
+    pub async fn pay_invoice(&self, invoice: &str, timeout: Duration) -> Result<String, NwcError> {
+        let request = json!({
+            "method": "pay_invoice",
+            "params": { "invoice": invoice },
+        });
+        let result = self.request(request, timeout).await?;
+
+        result
+            .get("preimage")
+            .and_then(|p| p.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| NwcError::InvalidResponse("missing preimage".to_string()))
+    }
+
+    /// Ask the wallet whether it paid an invoice, looked up by its payment
+    /// hash. An invoice the wallet doesn't know was not paid.
+    pub async fn lookup_invoice(&self, invoice: &str, timeout: Duration) -> Result<bool, NwcError> {
+        let mut params = json!({ "invoice": invoice });
+        if let Some(payment_hash) = invoice_policy::payment_hash(invoice) {
+            params["payment_hash"] = json!(payment_hash);
+        }
+        let request = json!({
+            "method": "lookup_invoice",
+            "params": params,
+        });
+        let result = match self.request(request, timeout).await {
+            Ok(result) => result,
+            Err(NwcError::Wallet { code, .. }) if code == "NOT_FOUND" => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        // Wallets report a settled payment with its preimage and settle time,
+        // newer ones also with its state
+        let preimage = result.get("preimage").and_then(|p| p.as_str());
+        Ok(
+            result.get("state").and_then(|s| s.as_str()) == Some("settled")
+                || result.get("settled_at").is_some_and(|t| !t.is_null())
+                || preimage.is_some_and(|p| !p.is_empty()),
+        )
+    }
+
+    /// Send a request to the wallet and wait for its result
+    async fn request(
+        &self,
+        request: serde_json::Value,
+        timeout: Duration,
+    ) -> Result<serde_json::Value, NwcError> {
+        let secret = self.keypair.secret_key();
+        let content = nip04_encrypt(&secret, &self.wallet_pubkey, &request.to_string());
+        let created_at = chrono::Utc::now().timestamp().max(0) as u64;
+        let event = NostrEvent::sign(
+            &self.keypair,
+            REQUEST_KIND,
+            vec![vec![
+                "p".to_string(),
+                hex::encode(self.wallet_pubkey.serialize()),
+            ]],
+            content,
+            created_at,
+        );
+
+        tokio::time::timeout(timeout, self.exchange(&event))
+            .await
+            .map_err(|_| NwcError::Timeout)?
+    }
+
+    async fn exchange(&self, event: &NostrEvent) -> Result<serde_json::Value, NwcError> {
+        let mut ws = nostr::connect(&self.relay, self.access)
+            .await
+            .map_err(|e| NwcError::Relay(e.to_string()))?;
+
+        // Subscribe to the response before publishing, so it can't be missed
+        let subscription = format!("satshunt-{}", &event.id[..16]);
+        let filter = json!({
+            "kinds": [RESPONSE_KIND],
+            "authors": [hex::encode(self.wallet_pubkey.serialize())],
+            "#e": [event.id],
+        });
+        ws.send(tungstenite::Message::text(
+            json!(["REQ", subscription, filter]).to_string(),
+        ))
+        .await?;
+        ws.send(tungstenite::Message::text(
+            json!(["EVENT", event]).to_string(),
+        ))
+        .await?;
+
+        let secret = self.keypair.secret_key();
+        while let Some(message) = ws.next().await {
+            let text = match message? {
+                tungstenite::Message::Text(text) => text,
+                tungstenite::Message::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(serde_json::Value::Array(frame)) = serde_json::from_str(&text) else {
+                continue;
+            };
+
+            match frame.first().and_then(|v| v.as_str()) {
+                // ["OK", <event id>, <accepted>, <message>]
+                Some("OK") if frame.get(2).and_then(|v| v.as_bool()) == Some(false) => {
+                    let reason = frame.get(3).and_then(|v| v.as_str()).unwrap_or_default();
+                    return Err(NwcError::Relay(format!("request rejected: {}", reason)));
+                }
+                Some("EVENT") => {
+                    let Some(response) = frame
+                        .get(2)
+                        .cloned()
+                        .and_then(|v| serde_json::from_value::<NostrEvent>(v).ok())
+                    else {
+                        continue;
+                    };
+                    // Relays are untrusted: only accept signed responses from the wallet
+                    // to this request
+                    if response.kind != RESPONSE_KIND
+                        || response.pubkey != hex::encode(self.wallet_pubkey.serialize())
+                        || response.tag("e") != Some(event.id.as_str())
+                        || !response.verify()
+                    {
+                        continue;
+                    }
+
+                    let _ = ws.close(None).await;
+
+                    let plaintext = nip04_decrypt(&secret, &self.wallet_pubkey, &response.content)?;
+                    let response: WalletResponse = serde_json::from_str(&plaintext)
+                        .map_err(|e| NwcError::InvalidResponse(e.to_string()))?;
+                    if let Some(error) = response.error {
+                        return Err(NwcError::Wallet {
+                            code: error.code,
+                            message: error.message,
+                        });
+                    }
+                    tracing::debug!("NWC {} response received", response.result_type);
+                    return response
+                        .result
+                        .ok_or_else(|| NwcError::InvalidResponse("missing result".to_string()));
+                }
+                Some("CLOSED") | Some("NOTICE") => {
+                    let reason = frame.last().and_then(|v| v.as_str()).unwrap_or_default();
+                    tracing::warn!("NWC relay {}: {}", self.relay, reason);
+                }
+                _ => {}
+            }
+        }
+
+        Err(NwcError::Relay("connection closed".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair(byte: u8) -> Keypair {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        Keypair::from_secret_key(&Secp256k1::new(), &secret)
+    }
+
+    #[test]
+    fn test_nip04_roundtrip() {
+        let alice = keypair(3);
+        let bob = keypair(4);
+        let message = r#"{"method":"pay_invoice","params":{"invoice":"lnbc1"}}"#;
+
+        let encrypted = nip04_encrypt(&alice.secret_key(), &bob.x_only_public_key().0, message);
+        assert!(encrypted.contains("?iv="));
+
+        let decrypted =
+            nip04_decrypt(&bob.secret_key(), &alice.x_only_public_key().0, &encrypted).unwrap();
+        assert_eq!(decrypted, message);
+
+        // A third party can't read it
+        let eve = keypair(5);
+        let result = nip04_decrypt(&eve.secret_key(), &alice.x_only_public_key().0, &encrypted);
+        assert!(result.map(|m| m != message).unwrap_or(true));
+    }
+
+    #[test]
+    fn test_uri_cipher_roundtrip() {
+        let cipher = UriCipher::new(&[1u8; 64]);
+        let uri = "nostr+walletconnect://abc?relay=wss://r.example&secret=0707";
+
+        let stored = cipher.encrypt(uri);
+        assert!(UriCipher::is_encrypted(&stored));
+        assert!(!stored.contains("secret"));
+        assert_ne!(stored, cipher.encrypt(uri));
+        assert_eq!(cipher.decrypt(&stored).unwrap(), uri);
+
+        // Another server secret can't read it, and tampering is detected
+        assert!(UriCipher::new(&[2u8; 64]).decrypt(&stored).is_err());
+        let mut tampered = stored.clone().into_bytes();
+        let last = tampered.len() - 5;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert!(cipher
+            .decrypt(&String::from_utf8(tampered).unwrap())
+            .is_err());
+        assert!(cipher.decrypt(uri).is_err());
+    }
+
+    #[test]
+    fn test_parse_connection_uri() {
+        let wallet = hex::encode(keypair(6).x_only_public_key().0.serialize());
+        let secret = hex::encode([7u8; 32]);
+        let uri = format!(
+            "nostr+walletconnect://{}?relay=wss%3A%2F%2Frelay.example.com&secret={}&lud16=a%40b.c",
+            wallet, secret
+        );
+
+        let connection: NwcConnection = uri.parse().unwrap();
+        assert_eq!(connection.relay, "wss://relay.example.com");
+        assert_eq!(hex::encode(connection.wallet_pubkey.serialize()), wallet);
+        assert_eq!(connection.client_pubkey(), keypair(7).x_only_public_key().0);
+        assert!(!format!("{:?}", connection).contains(&secret));
+    }
+
+    #[test]
+    fn test_parse_connection_uri_errors() {
+        let wallet = hex::encode(keypair(6).x_only_public_key().0.serialize());
+        let secret = hex::encode([7u8; 32]);
+
+        for uri in [
+            "https://example.com".to_string(),
+            format!("nostr+walletconnect://{}?secret={}", wallet, secret),
+            format!("nostr+walletconnect://{}?relay=wss://r.example", wallet),
+            format!(
+                "nostr+walletconnect://{}?relay=https://r.example&secret={}",
+                wallet, secret
+            ),
+            format!(
+                "nostr+walletconnect://{}?relay=ws://r.example&secret={}",
+                wallet, secret
+            ),
+            format!(
+                "nostr+walletconnect://nothex?relay=wss://r.example&secret={}",
+                secret
+            ),
+        ] {
+            assert!(uri.parse::<NwcConnection>().is_err(), "accepted {}", uri);
+        }
+    }
+}
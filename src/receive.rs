@@ -0,0 +1,136 @@
+//! Receiving payments into user wallets.
+//!
+//! This module handles:
+//! - Awaiting payment of invoices created for users' Lightning addresses
+//! - Crediting the user's wallet once an invoice is paid
+//!
+//! Like the `DonationService`, invoices still waiting for payment are loaded from
+//! the database on startup.
+
+use crate::db::Database;
+use crate::lightning::Lightning;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// Message to notify the ReceiveService about a new wallet invoice
+pub struct NewWalletInvoice {
+    pub invoice: String,
+    pub user_id: String,
+    pub amount_msats: i64,
+}
+
+/// Background service that credits user wallets when their invoices are paid
+pub struct ReceiveService {
+    db: Arc<Database>,
+    lightning: Arc<dyn Lightning>,
+    /// Sender for new wallet invoice notifications
+    sender: mpsc::UnboundedSender<NewWalletInvoice>,
+    /// Receiver for new wallet invoice notifications (wrapped in Option for take())
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<NewWalletInvoice>>>,
+    /// Set of invoices currently being awaited (to prevent duplicate tasks)
+    active_invoices: Mutex<HashSet<String>>,
+}
+
+impl ReceiveService {
+    pub fn new(db: Arc<Database>, lightning: Arc<dyn Lightning>) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            db,
+            lightning,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            active_invoices: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Get a sender clone to notify about new wallet invoices
+    pub fn get_sender(&self) -> mpsc::UnboundedSender<NewWalletInvoice> {
+        self.sender.clone()
+    }
+
+    /// Start the receive service - loads unpaid invoices and listens for new ones
+    pub async fn start(self: Arc<Self>) {
+        match self.db.list_pending_wallet_invoices().await {
+            Ok(pending) => {
+                tracing::info!(
+                    "Loaded {} pending wallet invoices from database",
+                    pending.len()
+                );
+                for invoice in pending {
+                    self.clone()
+                        .spawn_await_task(NewWalletInvoice {
+                            invoice: invoice.invoice,
+                            user_id: invoice.user_id,
+                            amount_msats: invoice.amount_msats,
+                        })
+                        .await;
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to load pending wallet invoices: {}", e);
+            }
+        }
+
+        // Take the receiver (can only be done once)
+        let receiver = {
+            let mut guard = self.receiver.lock().await;
+            guard.take()
+        };
+
+        let Some(mut receiver) = receiver else {
+            tracing::error!("ReceiveService receiver already taken");
+            return;
+        };
+
+        while let Some(invoice) = receiver.recv().await {
+            self.clone().spawn_await_task(invoice).await;
+        }
+    }
+
+    /// Spawn a task to await payment of a wallet invoice
+    async fn spawn_await_task(self: Arc<Self>, new_invoice: NewWalletInvoice) {
+        {
+            let mut active = self.active_invoices.lock().await;
+            if !active.insert(new_invoice.invoice.clone()) {
+                tracing::debug!("Already tracking wallet invoice, skipping");
+                return;
+            }
+        }
+
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let invoice = new_invoice.invoice;
+            tracing::info!(
+                "Awaiting payment of {} sats to wallet of user {}",
+                new_invoice.amount_msats / 1000,
+                new_invoice.user_id
+            );
+
+            match service.lightning.await_payment(&invoice).await {
+                Ok(()) => match service.db.mark_wallet_invoice_received(&invoice).await {
+                    Ok(Some(received)) => {
+                        tracing::info!(
+                            "Credited {} sats to wallet of user {}",
+                            received.amount_sats(),
+                            received.user_id
+                        );
+                    }
+                    Ok(None) => {
+                        tracing::debug!("Wallet invoice was already credited");
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to credit wallet invoice: {}", e);
+                    }
+                },
+                Err(e) => {
+                    tracing::error!("Failed to await wallet invoice payment: {}", e);
+                }
+            }
+
+            let mut active = service.active_invoices.lock().await;
+            active.remove(&invoice);
+        });
+    }
+}
@@ -0,0 +1,231 @@
+//! Recurring donations.
+//!
+//! This module handles:
+//! - Periodically looking for recurring donations that are due
+//! - Creating an invoice and a pending donation for each payment, tracked by the
+//!   `DonationService` like any other donation
+//! - Asking the donor's wallet to pay that invoice over Nostr Wallet Connect
+//! - Checking whether an unanswered payment went through before paying again
+//! - Backing off after failed payments, and stopping after too many in a row
+
+use crate::db::Database;
+use crate::donation::NewDonation;
+use crate::lightning::Lightning;
+use crate::models::{DonationStatus, RecurringDonation};
+use crate::nostr::RelayAccess;
+use crate::nwc::{NwcConnection, UriCipher};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// How often the scheduler looks for due payments
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+/// How long to wait for the donor's wallet to answer a payment request
+const WALLET_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+/// Failed payments in a row after which a recurring donation is stopped
+pub const MAX_FAILURES: i64 = 5;
+
+/// Delay before retrying after the `failure_count`-th failed payment
+/// (1h, 2h, 4h, ... capped at one day)
+pub fn retry_delay(failure_count: i64) -> Duration {
+    let hours = 1i64 << failure_count.clamp(0, 5);
+    Duration::hours(hours.min(24))
+}
+
+/// Background scheduler that collects recurring donations
+pub struct RecurringDonationService {
+    db: Arc<Database>,
+    lightning: Arc<dyn Lightning>,
+    donation_sender: mpsc::UnboundedSender<NewDonation>,
+    /// Decrypts the stored connection URIs
+    cipher: UriCipher,
+    relay_access: RelayAccess,
+}
+
+impl RecurringDonationService {
+    pub fn new(
+        db: Arc<Database>,
+        lightning: Arc<dyn Lightning>,
+        donation_sender: mpsc::UnboundedSender<NewDonation>,
+        cipher: UriCipher,
+        relay_access: RelayAccess,
+    ) -> Self {
+        Self {
+            db,
+            lightning,
+            donation_sender,
+            cipher,
+            relay_access,
+        }
+    }
+
+    /// Start the scheduler - collects due payments every minute
+    pub async fn start(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.process_due().await {
+                tracing::error!("Failed to process recurring donations: {}", e);
+            }
+        }
+    }
+
+    /// Collect every due payment. Returns the number of payments the donors'
+    /// wallets accepted.
+    pub async fn process_due(&self) -> Result<usize> {
+        let due = self.db.list_due_recurring_donations().await?;
+        let mut paid = 0;
+
+        for recurring in due {
+            match self.collect(&recurring).await {
+                Ok(()) => {
+                    self.db.mark_recurring_payment_sent(&recurring).await?;
+                    paid += 1;
+                    tracing::info!(
+                        "Recurring donation {} paid {} sats",
+                        recurring.id,
+                        recurring.amount_sats()
+                    );
+                }
+                Err(e) => {
+                    let retry_at = Utc::now() + retry_delay(recurring.failure_count);
+                    let updated = self
+                        .db
+                        .mark_recurring_payment_failed(
+                            &recurring.id,
+                            &e.to_string(),
+                            retry_at,
+                            MAX_FAILURES,
+                        )
+                        .await?;
+                    if updated.is_active() {
+                        tracing::warn!(
+                            "Recurring donation {} failed ({}), retrying at {}",
+                            recurring.id,
+                            e,
+                            retry_at
+                        );
+                    } else {
+                        tracing::warn!(
+                            "Recurring donation {} stopped after {} failed payments: {}",
+                            recurring.id,
+                            updated.failure_count,
+                            e
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(paid)
+    }
+
+    /// Create an invoice for one payment and have the donor's wallet pay it
+    async fn collect(&self, recurring: &RecurringDonation) -> Result<()> {
+        let uri = self.cipher.decrypt(&recurring.nwc_uri)?;
+        let connection = NwcConnection::parse_with(&uri, self.relay_access)?;
+
+        if self.previous_attempt_paid(recurring, &connection).await? {
+            tracing::info!(
+                "Recurring donation {}: previous payment went through after all",
+                recurring.id
+            );
+            return Ok(());
+        }
+
+        let description = match &recurring.location_id {
+            Some(location_id) => {
+                let name = self
+                    .db
+                    .get_location(location_id)
+                    .await?
+                    .map(|l| l.name)
+                    .unwrap_or_else(|| "a location".to_string());
+                format!(
+                    "SatsHunt recurring donation to '{}': {} sats",
+                    name,
+                    recurring.amount_sats()
+                )
+            }
+            None => format!(
+                "SatsHunt recurring donation: {} sats",
+                recurring.amount_sats()
+            ),
+        };
+
+        let invoice = self
+            .lightning
+            .create_invoice(recurring.amount_sats() as u64, &description)
+            .await?;
+        self.db
+            .create_recurring_donation_payment(recurring, invoice.clone())
+            .await?;
+
+        if let Err(e) = self.donation_sender.send(NewDonation {
+            invoice: invoice.clone(),
+            amount_msats: recurring.amount_msats,
+            location_id: recurring.location_id.clone(),
+        }) {
+            tracing::error!("Failed to notify donation service: {}", e);
+            // The donation service picks up pending donations on the next restart
+        }
+
+        connection.pay_invoice(&invoice, WALLET_TIMEOUT).await?;
+
+        Ok(())
+    }
+
+    /// Whether the last failed attempt was paid anyway. A wallet that timed
+    /// out may still have paid, so paying a new invoice could charge the donor
+    /// twice. Errors if the outcome can't be told yet.
+    async fn previous_attempt_paid(
+        &self,
+        recurring: &RecurringDonation,
+        connection: &NwcConnection,
+    ) -> Result<bool> {
+        let Some(previous) = self
+            .db
+            .get_latest_recurring_donation_payment(&recurring.id)
+            .await?
+        else {
+            return Ok(false);
+        };
+        // Attempts up to the last successful payment are settled
+        if recurring
+            .last_payment_at
+            .is_some_and(|paid_at| previous.created_at <= paid_at)
+        {
+            return Ok(false);
+        }
+
+        match previous.status {
+            DonationStatus::Received => Ok(true),
+            // Our invoice expired unpaid
+            DonationStatus::TimedOut => Ok(false),
+            DonationStatus::Created => connection
+                .lookup_invoice(&previous.invoice, WALLET_TIMEOUT)
+                .await
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "can't tell whether the previous payment went through: {}",
+                        e
+                    )
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(0), Duration::hours(1));
+        assert_eq!(retry_delay(1), Duration::hours(2));
+        assert_eq!(retry_delay(3), Duration::hours(8));
+        assert_eq!(retry_delay(5), Duration::hours(24));
+        assert_eq!(retry_delay(50), Duration::hours(24));
+    }
+}
@@ -0,0 +1,209 @@
+//! Activation schedules for time-boxed events.
+//!
+//! This module handles:
+//! - Working out whether a location is inside one of its schedule windows, and
+//!   when the current window closes or the next one opens
+//! - Periodically switching locations between 'active' and 'scheduled' as their
+//!   windows open and close, so locations outside their windows are hidden
+//!
+//! A location without schedules is always open. Claims check the schedules
+//! themselves, so a location can't be claimed outside its windows even before
+//! the service got around to hiding it.
+
+use crate::db::Database;
+use crate::models::Schedule;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// How often the service looks for windows that opened or closed
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// A single activation window, `ends_at` exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl Window {
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
+
+/// Window of `schedule` that is open at `now`, or else the next one to open.
+/// None once a one-off schedule is over.
+pub fn window_at_or_after(schedule: &Schedule, now: DateTime<Utc>) -> Option<Window> {
+    let duration = schedule.ends_at - schedule.starts_at;
+    let first = Window {
+        starts_at: schedule.starts_at,
+        ends_at: schedule.ends_at,
+    };
+
+    let Some(period) = schedule.recurrence.period() else {
+        return (now < first.ends_at).then_some(first);
+    };
+    if now < first.starts_at {
+        return Some(first);
+    }
+
+    // Latest window that started at or before now
+    let periods = (now - schedule.starts_at).num_seconds() / period.num_seconds();
+    let starts_at = schedule.starts_at + period * periods as i32;
+    let window = Window {
+        starts_at,
+        ends_at: starts_at + duration,
+    };
+    if now < window.ends_at {
+        Some(window)
+    } else {
+        Some(Window {
+            starts_at: starts_at + period,
+            ends_at: starts_at + period + duration,
+        })
+    }
+}
+
+/// Whether a location with `schedules` can be claimed at `now`
+pub fn is_open(schedules: &[Schedule], now: DateTime<Utc>) -> bool {
+    schedules.is_empty()
+        || schedules
+            .iter()
+            .filter_map(|schedule| window_at_or_after(schedule, now))
+            .any(|window| window.contains(now))
+}
+
+/// The window to count down to: the open window that closes last, or else the
+/// window that opens next. None if no window is open or coming up.
+pub fn current_or_next_window(schedules: &[Schedule], now: DateTime<Utc>) -> Option<Window> {
+    let windows: Vec<Window> = schedules
+        .iter()
+        .filter_map(|schedule| window_at_or_after(schedule, now))
+        .collect();
+
+    windows
+        .iter()
+        .filter(|window| window.contains(now))
+        .max_by_key(|window| window.ends_at)
+        .or_else(|| windows.iter().min_by_key(|window| window.starts_at))
+        .copied()
+}
+
+/// Background service that hides locations outside their schedule windows
+pub struct ScheduleService {
+    db: Arc<Database>,
+}
+
+impl ScheduleService {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// Start the service - applies the schedules every minute
+    pub async fn start(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match self.db.apply_schedules(Utc::now()).await {
+                Ok((opened, closed)) => {
+                    if opened > 0 || closed > 0 {
+                        tracing::info!(
+                            "Schedules opened {} and closed {} locations",
+                            opened,
+                            closed
+                        );
+                    }
+                }
+                Err(e) => tracing::error!("Failed to apply schedules: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Recurrence;
+    use chrono::{Duration, TimeZone};
+
+    fn schedule(starts_at: DateTime<Utc>, hours: i64, recurrence: Recurrence) -> Schedule {
+        Schedule {
+            id: "schedule".to_string(),
+            location_id: Some("location".to_string()),
+            hunt_id: None,
+            starts_at,
+            ends_at: starts_at + Duration::hours(hours),
+            recurrence,
+            created_by: "user".to_string(),
+            created_at: starts_at,
+        }
+    }
+
+    /// Saturday 2026-03-07 10:00 UTC
+    fn saturday_10() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 7, 10, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_no_schedules_is_always_open() {
+        assert!(is_open(&[], saturday_10()));
+        assert_eq!(current_or_next_window(&[], saturday_10()), None);
+    }
+
+    #[test]
+    fn test_one_off_window() {
+        let event = schedule(saturday_10(), 8, Recurrence::Once);
+        let schedules = [event.clone()];
+
+        assert!(!is_open(&schedules, saturday_10() - Duration::minutes(1)));
+        assert!(is_open(&schedules, saturday_10()));
+        assert!(is_open(&schedules, saturday_10() + Duration::hours(7)));
+        assert!(!is_open(&schedules, saturday_10() + Duration::hours(8)));
+
+        // Over for good after the window
+        assert_eq!(
+            window_at_or_after(&event, saturday_10() + Duration::hours(9)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_weekly_window() {
+        let schedules = [schedule(saturday_10(), 8, Recurrence::Weekly)];
+        let next_saturday = saturday_10() + Duration::weeks(1);
+
+        assert!(!is_open(&schedules, saturday_10() + Duration::days(1)));
+        assert!(is_open(&schedules, next_saturday + Duration::hours(2)));
+        assert!(!is_open(&schedules, next_saturday + Duration::hours(8)));
+
+        // On Sunday the countdown is to next Saturday's window
+        assert_eq!(
+            current_or_next_window(&schedules, saturday_10() + Duration::days(1)),
+            Some(Window {
+                starts_at: next_saturday,
+                ends_at: next_saturday + Duration::hours(8),
+            })
+        );
+    }
+
+    #[test]
+    fn test_overlapping_windows() {
+        let morning = schedule(saturday_10(), 4, Recurrence::Daily);
+        let afternoon = schedule(saturday_10() + Duration::hours(2), 6, Recurrence::Once);
+        let schedules = [morning, afternoon];
+
+        // While both are open, count down to the later close
+        let window = current_or_next_window(&schedules, saturday_10() + Duration::hours(3));
+        assert_eq!(
+            window.map(|w| w.ends_at),
+            Some(saturday_10() + Duration::hours(8))
+        );
+
+        // Once both closed, the daily window opens next
+        let window = current_or_next_window(&schedules, saturday_10() + Duration::hours(9));
+        assert_eq!(
+            window.map(|w| w.starts_at),
+            Some(saturday_10() + Duration::days(1))
+        );
+    }
+}
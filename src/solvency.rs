@@ -0,0 +1,471 @@
+//! Solvency and proof-of-liabilities reporting.
+//!
+//! This module handles:
+//! - Comparing the node balance with everything the service owes (user and team
+//!   wallets, in-flight withdrawals, location and hunt donation pools and unspent
+//!   campaign budgets)
+//! - Building a Merkle-sum tree over user wallet balances, so each user can check
+//!   that their balance is included in the published total without learning
+//!   anyone else's balance
+//!
+//! Tree construction:
+//! - Leaves are users with a positive balance, ordered by user id.
+//!   `hash = SHA256("satshunt-leaf" || user_id || 0x00 || balance_msats as u64 BE)`
+//! - Inner nodes combine their children.
+//!   `hash = SHA256("satshunt-node" || left_hash || left_sum BE || right_hash || right_sum BE)`,
+//!   `sum = left_sum + right_sum`
+//! - A node without a sibling is carried up to the next level unchanged.
+//!
+//! Snapshots:
+//! - The root is published as a snapshot once a day, and the balances it was
+//!   built from are stored with it
+//! - Inclusion proofs are issued against a snapshot, so every proof for the same
+//!   snapshot checks against the same published root
+
+use crate::db::Database;
+use crate::lightning::Lightning;
+use crate::models::LiabilitySnapshot;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+const LEAF_TAG: &[u8] = b"satshunt-leaf";
+const NODE_TAG: &[u8] = b"satshunt-node";
+
+/// How often a new liabilities snapshot is published
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often the service checks whether a snapshot is due
+const SNAPSHOT_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Node balance compared against all liabilities
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolvencyReport {
+    pub generated_at: DateTime<Utc>,
+    /// Funds held by the Lightning node
+    pub node_balance_msats: i64,
+    /// Sum of all positive user wallet balances (excluding reserved withdrawals)
+    pub user_wallets_msats: i64,
+    /// Balance reserved by in-flight withdrawals
+    pub pending_withdrawals_msats: i64,
+    /// Sats donated to locations that have not been collected yet
+    pub location_pools_msats: i64,
+    /// Prepaid sponsor budgets not yet spent on matching donations
+    pub campaign_budgets_msats: i64,
+    /// Sats donated to hunts that have not been paid out as completion bonuses
+    pub hunt_pools_msats: i64,
+    /// Sum of all team wallet balances
+    pub team_wallets_msats: i64,
+    pub total_liabilities_msats: i64,
+    /// Node balance minus liabilities (negative means insolvent)
+    pub surplus_msats: i64,
+    /// Number of users holding a balance
+    pub user_count: usize,
+    /// Latest published root of the Merkle-sum tree over user wallets
+    pub liabilities_snapshot: Option<LiabilitySnapshot>,
+}
+
+impl SolvencyReport {
+    pub fn is_solvent(&self) -> bool {
+        self.surplus_msats >= 0
+    }
+
+    /// Reserves as a percentage of liabilities (100% = exactly covered)
+    pub fn coverage_percent(&self) -> f64 {
+        if self.total_liabilities_msats <= 0 {
+            return 100.0;
+        }
+        self.node_balance_msats as f64 / self.total_liabilities_msats as f64 * 100.0
+    }
+}
+
+/// Hash and sum of a node in the Merkle-sum tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleSumNode {
+    /// Hex encoded SHA256 hash
+    pub hash: String,
+    pub sum_msats: u64,
+}
+
+/// One step from a leaf towards the root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub sibling: MerkleSumNode,
+    /// Whether the sibling is on the left of the node being proven
+    pub sibling_is_left: bool,
+}
+
+/// Inclusion proof for a user's balance in a published snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotProof {
+    pub snapshot: LiabilitySnapshot,
+    pub proof: LiabilityProof,
+}
+
+/// Proof that a user's balance is included in the liabilities root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiabilityProof {
+    pub user_id: String,
+    pub balance_msats: u64,
+    pub path: Vec<ProofStep>,
+    pub root: MerkleSumNode,
+}
+
+impl LiabilityProof {
+    /// Recompute the root from the leaf and path and compare it with `root`
+    pub fn verify(&self) -> bool {
+        let mut hash = leaf_hash(&self.user_id, self.balance_msats);
+        let mut sum = self.balance_msats;
+
+        for step in &self.path {
+            let Ok(sibling_hash) = decode_hash(&step.sibling.hash) else {
+                return false;
+            };
+            let Some(new_sum) = sum.checked_add(step.sibling.sum_msats) else {
+                return false;
+            };
+            hash = if step.sibling_is_left {
+                node_hash(&sibling_hash, step.sibling.sum_msats, &hash, sum)
+            } else {
+                node_hash(&hash, sum, &sibling_hash, step.sibling.sum_msats)
+            };
+            sum = new_sum;
+        }
+
+        hex::encode(hash) == self.root.hash && sum == self.root.sum_msats
+    }
+}
+
+/// Merkle-sum tree over user balances
+pub struct MerkleSumTree {
+    user_ids: Vec<String>,
+    /// levels[0] are the leaves, the last level holds the root
+    levels: Vec<Vec<([u8; 32], u64)>>,
+}
+
+impl MerkleSumTree {
+    /// Build the tree. Users with a zero or negative balance are left out.
+    pub fn build(balances: &[(String, i64)]) -> Result<Self> {
+        let mut entries: Vec<(&str, u64)> = balances
+            .iter()
+            .filter(|(_, msats)| *msats > 0)
+            .map(|(user_id, msats)| (user_id.as_str(), *msats as u64))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let leaves: Vec<([u8; 32], u64)> = entries
+            .iter()
+            .map(|(user_id, msats)| (leaf_hash(user_id, *msats), *msats))
+            .collect();
+        let user_ids = entries.iter().map(|(id, _)| id.to_string()).collect();
+
+        let mut levels = vec![leaves];
+        while levels.last().map(|l| l.len()).unwrap_or(0) > 1 {
+            let level = levels.last().expect("checked above");
+            let mut next = Vec::with_capacity(level.len().div_ceil(2));
+            for pair in level.chunks(2) {
+                match pair {
+                    [(left, left_sum), (right, right_sum)] => {
+                        let sum = left_sum
+                            .checked_add(*right_sum)
+                            .ok_or_else(|| anyhow::anyhow!("Liability sum overflow"))?;
+                        next.push((node_hash(left, *left_sum, right, *right_sum), sum));
+                    }
+                    [single] => next.push(*single),
+                    _ => unreachable!("chunks(2) yields one or two items"),
+                }
+            }
+            levels.push(next);
+        }
+
+        Ok(Self { user_ids, levels })
+    }
+
+    /// Root of the tree. An empty tree has the hash of no data and a zero sum.
+    pub fn root(&self) -> MerkleSumNode {
+        match self.levels.last().and_then(|l| l.first()) {
+            Some((hash, sum)) => MerkleSumNode {
+                hash: hex::encode(hash),
+                sum_msats: *sum,
+            },
+            None => MerkleSumNode {
+                hash: hex::encode(Sha256::digest(NODE_TAG)),
+                sum_msats: 0,
+            },
+        }
+    }
+
+    /// Inclusion proof for a user, or None if they hold no balance
+    pub fn proof(&self, user_id: &str) -> Option<LiabilityProof> {
+        let mut index = self
+            .user_ids
+            .binary_search_by(|id| id.as_str().cmp(user_id))
+            .ok()?;
+        let balance_msats = self.levels[0][index].1;
+
+        let mut path = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling_index = index ^ 1;
+            if let Some((hash, sum)) = level.get(sibling_index) {
+                path.push(ProofStep {
+                    sibling: MerkleSumNode {
+                        hash: hex::encode(hash),
+                        sum_msats: *sum,
+                    },
+                    sibling_is_left: sibling_index < index,
+                });
+            }
+            index /= 2;
+        }
+
+        Some(LiabilityProof {
+            user_id: user_id.to_string(),
+            balance_msats,
+            path,
+            root: self.root(),
+        })
+    }
+}
+
+fn leaf_hash(user_id: &str, msats: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(LEAF_TAG);
+    hasher.update(user_id.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(msats.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], left_sum: u64, right: &[u8; 32], right_sum: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(NODE_TAG);
+    hasher.update(left);
+    hasher.update(left_sum.to_be_bytes());
+    hasher.update(right);
+    hasher.update(right_sum.to_be_bytes());
+    hasher.finalize().into()
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32]> {
+    hex::decode(hash)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Hash must be 32 bytes"))
+}
+
+/// Publish a snapshot of the liabilities root over the current user balances
+pub async fn take_snapshot(db: &Database) -> Result<LiabilitySnapshot> {
+    let balances: Vec<(String, i64)> = db
+        .list_user_balances()
+        .await?
+        .into_iter()
+        .filter(|(_, msats)| *msats > 0)
+        .collect();
+    let root = MerkleSumTree::build(&balances)?.root();
+
+    db.create_liability_snapshot(&root.hash, i64::try_from(root.sum_msats)?, &balances)
+        .await
+}
+
+/// Rebuild the tree of a snapshot from its stored balances
+pub async fn snapshot_tree(db: &Database, snapshot: &LiabilitySnapshot) -> Result<MerkleSumTree> {
+    let balances = db.list_liability_snapshot_balances(&snapshot.id).await?;
+    let tree = MerkleSumTree::build(&balances)?;
+
+    let root = tree.root();
+    if root.hash != snapshot.root_hash || root.sum_msats != snapshot.sum_msats as u64 {
+        anyhow::bail!(
+            "Balances of liabilities snapshot {} don't match its root",
+            snapshot.id
+        );
+    }
+    Ok(tree)
+}
+
+/// Inclusion proof for a user in a snapshot, or None if they held no balance
+pub async fn snapshot_proof(
+    db: &Database,
+    snapshot: LiabilitySnapshot,
+    user_id: &str,
+) -> Result<Option<SnapshotProof>> {
+    let tree = snapshot_tree(db, &snapshot).await?;
+    Ok(tree
+        .proof(user_id)
+        .map(|proof| SnapshotProof { snapshot, proof }))
+}
+
+/// Compare the node balance with everything owed to users and locations
+pub async fn build_report(db: &Database, lightning: &dyn Lightning) -> Result<SolvencyReport> {
+    let node_balance_msats = i64::try_from(lightning.node_balance_msats().await?)?;
+
+    let balances = db.list_user_balances().await?;
+    let (user_count, user_wallets_msats) = balances
+        .iter()
+        .filter(|(_, msats)| *msats > 0)
+        .fold((0, 0), |(count, total), (_, msats)| {
+            (count + 1, total + msats)
+        });
+    let pending_withdrawals_msats = db.get_total_pending_withdrawals().await?;
+    let location_pools_msats = db.get_total_location_pool_balance().await?.max(0);
+    let campaign_budgets_msats = db.get_total_campaign_budgets().await?;
+    let hunt_pools_msats = db.get_total_hunt_pool_balance().await?.max(0);
+    let team_wallets_msats = db.get_total_team_wallet_balance().await?.max(0);
+
+    let total_liabilities_msats = user_wallets_msats
+        + pending_withdrawals_msats
+        + location_pools_msats
+        + campaign_budgets_msats
+        + hunt_pools_msats
+        + team_wallets_msats;
+
+    Ok(SolvencyReport {
+        generated_at: Utc::now(),
+        node_balance_msats,
+        user_wallets_msats,
+        pending_withdrawals_msats,
+        location_pools_msats,
+        campaign_budgets_msats,
+        hunt_pools_msats,
+        team_wallets_msats,
+        total_liabilities_msats,
+        surplus_msats: node_balance_msats - total_liabilities_msats,
+        user_count,
+        liabilities_snapshot: db.get_latest_liability_snapshot().await?,
+    })
+}
+
+/// Background service publishing liabilities snapshots
+pub struct SolvencyService {
+    db: Arc<Database>,
+}
+
+impl SolvencyService {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    /// Start the service - publishes a snapshot whenever the latest one is a day
+    /// old, checking every hour
+    pub async fn start(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(SNAPSHOT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.snapshot_if_due().await {
+                tracing::error!("Failed to publish liabilities snapshot: {}", e);
+            }
+        }
+    }
+
+    async fn snapshot_if_due(&self) -> Result<()> {
+        if let Some(latest) = self.db.get_latest_liability_snapshot().await? {
+            let age = (Utc::now() - latest.created_at)
+                .to_std()
+                .unwrap_or_default();
+            if age < SNAPSHOT_INTERVAL {
+                return Ok(());
+            }
+        }
+
+        let snapshot = take_snapshot(&self.db).await?;
+        tracing::info!(
+            "Published liabilities snapshot {} ({} users, {} msats)",
+            snapshot.id,
+            snapshot.user_count,
+            snapshot.sum_msats
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balances(n: usize) -> Vec<(String, i64)> {
+        (0..n)
+            .map(|i| (format!("user-{:02}", i), (i as i64 + 1) * 1000))
+            .collect()
+    }
+
+    #[test]
+    fn test_root_sum_matches_total() {
+        let tree = MerkleSumTree::build(&balances(5)).unwrap();
+        assert_eq!(tree.root().sum_msats, 15_000);
+    }
+
+    #[test]
+    fn test_every_proof_verifies() {
+        for n in 1..=9 {
+            let tree = MerkleSumTree::build(&balances(n)).unwrap();
+            for (user_id, msats) in balances(n) {
+                let proof = tree.proof(&user_id).unwrap();
+                assert_eq!(proof.balance_msats, msats as u64);
+                assert!(proof.verify(), "proof for {} of {} failed", user_id, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_fails() {
+        let tree = MerkleSumTree::build(&balances(4)).unwrap();
+        let proof = tree.proof("user-02").unwrap();
+
+        let mut lower_balance = proof.clone();
+        lower_balance.balance_msats -= 1;
+        assert!(!lower_balance.verify());
+
+        let mut hidden_sum = proof.clone();
+        hidden_sum.path[0].sibling.sum_msats = 0;
+        assert!(!hidden_sum.verify());
+
+        let mut bad_hash = proof;
+        bad_hash.path[0].sibling.hash = "zz".to_string();
+        assert!(!bad_hash.verify());
+    }
+
+    #[test]
+    fn test_zero_balances_excluded() {
+        let tree = MerkleSumTree::build(&[
+            ("a".to_string(), 5000),
+            ("b".to_string(), 0),
+            ("c".to_string(), -10),
+        ])
+        .unwrap();
+        assert_eq!(tree.root().sum_msats, 5000);
+        assert!(tree.proof("b").is_none());
+        assert!(tree.proof("c").is_none());
+        assert!(tree.proof("a").unwrap().verify());
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = MerkleSumTree::build(&[]).unwrap();
+        assert_eq!(tree.root().sum_msats, 0);
+        assert!(tree.proof("anyone").is_none());
+    }
+
+    #[test]
+    fn test_coverage_percent() {
+        let mut report = SolvencyReport {
+            generated_at: Utc::now(),
+            node_balance_msats: 150,
+            user_wallets_msats: 100,
+            pending_withdrawals_msats: 0,
+            location_pools_msats: 100,
+            campaign_budgets_msats: 0,
+            hunt_pools_msats: 0,
+            team_wallets_msats: 0,
+            total_liabilities_msats: 200,
+            surplus_msats: -50,
+            user_count: 1,
+            liabilities_snapshot: None,
+        };
+        assert!(!report.is_solvent());
+        assert_eq!(report.coverage_percent(), 75.0);
+
+        report.total_liabilities_msats = 0;
+        assert_eq!(report.coverage_percent(), 100.0);
+    }
+}
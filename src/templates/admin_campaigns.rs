@@ -0,0 +1,186 @@
+use crate::models::{CampaignStatus, MatchingCampaign};
+use chrono::Utc;
+use maud::{html, Markup};
+
+/// Admin campaigns page: create matching campaigns and follow their budgets
+pub fn admin_campaigns(campaigns: &[MatchingCampaign]) -> Markup {
+    html! {
+        div class="mb-8" {
+            div class="flex justify-between items-center mb-8" {
+                h1 class="text-4xl font-black text-primary" style="letter-spacing: -0.02em;" {
+                    "MATCHING CAMPAIGNS"
+                }
+            }
+
+            (new_campaign_form())
+
+            @if campaigns.is_empty() {
+                div class="card-brutal-inset text-center" style="padding: 3rem;" {
+                    div class="text-6xl mb-6 text-muted" {
+                        i class="fa-solid fa-handshake" {}
+                    }
+                    h3 class="text-2xl font-black text-primary mb-3" { "NO CAMPAIGNS" }
+                    p class="text-secondary font-bold" {
+                        "CREATE A CAMPAIGN TO MATCH DONATIONS FROM A SPONSOR'S BUDGET."
+                    }
+                }
+            } @else {
+                div class="space-y-4" {
+                    @for campaign in campaigns {
+                        (campaign_card(campaign))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn new_campaign_form() -> Markup {
+    html! {
+        form class="card-brutal mb-8 space-y-4"
+            hx-post="/api/admin/campaigns"
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+            h2 class="text-xl font-black text-primary" { "NEW CAMPAIGN" }
+
+            div class="grid md:grid-cols-2 gap-4" {
+                div {
+                    label for="campaign-name" class="label-brutal" { "NAME" }
+                    input type="text" id="campaign-name" name="name" required
+                        class="input-brutal-box w-full" placeholder="SPRING MATCH";
+                }
+                div {
+                    label for="campaign-sponsor" class="label-brutal" { "SPONSOR" }
+                    input type="text" id="campaign-sponsor" name="sponsor_name" required
+                        class="input-brutal-box w-full" placeholder="ACME COFFEE";
+                }
+            }
+
+            div class="grid md:grid-cols-4 gap-4" {
+                div {
+                    label for="campaign-budget" class="label-brutal" { "BUDGET (SATS)" }
+                    input type="number" id="campaign-budget" name="budget_sats" required min="1"
+                        class="input-brutal-box w-full" placeholder="1000000";
+                }
+                div {
+                    label for="campaign-percent" class="label-brutal" { "MATCH %" }
+                    input type="number" id="campaign-percent" name="match_percent" required
+                        min="1" max="1000" value="100"
+                        class="input-brutal-box w-full";
+                }
+                div {
+                    label for="campaign-max" class="label-brutal" { "MAX PER DONATION (OPTIONAL)" }
+                    input type="number" id="campaign-max" name="max_match_sats" min="1"
+                        class="input-brutal-box w-full" placeholder="10000";
+                }
+                div {
+                    label for="campaign-ends" class="label-brutal" { "LAST DAY" }
+                    input type="date" id="campaign-ends" name="ends_on" required
+                        class="input-brutal-box w-full";
+                }
+            }
+
+            div {
+                p class="label-brutal" { "REGION (OPTIONAL, LEAVE EMPTY FOR ALL LOCATIONS)" }
+                div class="grid md:grid-cols-4 gap-4" {
+                    input type="number" name="min_latitude" step="any" aria-label="Min latitude"
+                        class="input-brutal-box w-full" placeholder="MIN LAT";
+                    input type="number" name="max_latitude" step="any" aria-label="Max latitude"
+                        class="input-brutal-box w-full" placeholder="MAX LAT";
+                    input type="number" name="min_longitude" step="any" aria-label="Min longitude"
+                        class="input-brutal-box w-full" placeholder="MIN LON";
+                    input type="number" name="max_longitude" step="any" aria-label="Max longitude"
+                        class="input-brutal-box w-full" placeholder="MAX LON";
+                }
+            }
+
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-plus mr-2" {}
+                "CREATE & GET FUNDING INVOICE"
+            }
+        }
+    }
+}
+
+fn campaign_card(campaign: &MatchingCampaign) -> Markup {
+    let now = Utc::now();
+    let (badge_class, badge) = match campaign.status {
+        CampaignStatus::Funding => ("badge-brutal", "AWAITING FUNDING"),
+        CampaignStatus::Expired => ("badge-brutal filled", "NOT FUNDED"),
+        CampaignStatus::Active if campaign.is_active(now) => ("badge-brutal orange", "ACTIVE"),
+        CampaignStatus::Active | CampaignStatus::Ended => ("badge-brutal filled", "ENDED"),
+    };
+    let spent_percent = if campaign.budget_msats > 0 {
+        (campaign.spent_msats as f64 / campaign.budget_msats as f64 * 100.0).min(100.0)
+    } else {
+        0.0
+    };
+
+    html! {
+        div class="card-brutal" {
+            div class="flex justify-between items-start gap-4 mb-4" {
+                div {
+                    h3 class="text-xl font-black text-primary mb-1" { (campaign.name) }
+                    div class="text-sm text-muted font-bold" {
+                        "BY " (campaign.sponsor_name)
+                    }
+                }
+                span class=(badge_class) { (badge) }
+            }
+
+            div class="flex flex-wrap gap-6 mb-4 text-sm font-bold mono text-secondary" {
+                span { "MATCH: " (campaign.match_percent) "%" }
+                @if let Some(max) = campaign.max_match_msats {
+                    span { "MAX/DONATION: " (max / 1000) " sats" }
+                }
+                span {
+                    "REGION: "
+                    @if campaign.has_region() {
+                        (bound(campaign.min_latitude)) ".." (bound(campaign.max_latitude))
+                        " / "
+                        (bound(campaign.min_longitude)) ".." (bound(campaign.max_longitude))
+                    } @else {
+                        "ALL LOCATIONS"
+                    }
+                }
+                span { "UNTIL: " (campaign.ends_at.format("%Y-%m-%d %H:%M UTC").to_string()) }
+            }
+
+            // Budget usage
+            div class="mb-2 text-sm font-bold mono" {
+                (campaign.spent_msats / 1000) " / " (campaign.budget_sats()) " sats matched"
+            }
+            div style="height: 12px; background: var(--bg-tertiary); border: 2px solid var(--accent-muted);" {
+                div style={"height: 100%; width: " (format!("{:.1}", spent_percent)) "%; background: var(--highlight);"} {}
+            }
+
+            @if campaign.status == CampaignStatus::Funding {
+                div class="card-brutal-inset mt-4" {
+                    p class="label-brutal" { "FUNDING INVOICE" }
+                    p class="mono text-xs text-secondary" style="word-break: break-all;" {
+                        (campaign.funding_invoice)
+                    }
+                }
+            }
+
+            @if campaign.status == CampaignStatus::Active {
+                div class="pt-4 mt-4" style="border-top: 3px solid var(--accent-muted);" {
+                    button type="button" class="btn-brutal"
+                        hx-post={"/api/admin/campaigns/" (campaign.id) "/end"}
+                        hx-swap="none"
+                        hx-confirm="End this campaign? Unspent budget is donated to the locations in its region."
+                        hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                        i class="fa-solid fa-stop mr-2" {}
+                        "END CAMPAIGN"
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn bound(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.4}", v))
+        .unwrap_or_else(|| "*".to_string())
+}
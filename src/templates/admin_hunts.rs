@@ -0,0 +1,149 @@
+use crate::models::{Hunt, Location, Schedule};
+use crate::templates::components::{schedule_form_markup, schedule_list_markup};
+use maud::{html, Markup, PreEscaped};
+use std::collections::BTreeMap;
+
+/// Admin hunts page: create hunts from existing locations and schedule them
+/// hunts is a slice of (hunt, number_of_stops, pool_sats)
+/// schedules maps hunt ids to the hunt's activation schedules
+pub fn admin_hunts(
+    hunts: &[(Hunt, usize, i64)],
+    locations: &[Location],
+    schedules: &BTreeMap<String, Vec<Schedule>>,
+) -> Markup {
+    html! {
+        div class="mb-8" {
+            div class="flex justify-between items-center mb-8" {
+                h1 class="text-4xl font-black text-primary" style="letter-spacing: -0.02em;" {
+                    "HUNTS"
+                }
+            }
+
+            (new_hunt_form(locations))
+
+            @if hunts.is_empty() {
+                div class="card-brutal-inset text-center" style="padding: 3rem;" {
+                    div class="text-6xl mb-6 text-muted" {
+                        i class="fa-solid fa-flag-checkered" {}
+                    }
+                    h3 class="text-2xl font-black text-primary mb-3" { "NO HUNTS" }
+                    p class="text-secondary font-bold" {
+                        "CREATE A HUNT TO GROUP LOCATIONS INTO A TRAIL WITH A COMPLETION BONUS."
+                    }
+                }
+            } @else {
+                div class="space-y-4" {
+                    @for (hunt, stops, pool_sats) in hunts {
+                        div class="card-brutal" {
+                            div class="flex justify-between items-start gap-4 mb-4" {
+                                div {
+                                    h3 class="text-xl font-black text-primary mb-1" {
+                                        a href={"/hunts/" (hunt.id)} class="hover:text-highlight" { (hunt.name) }
+                                    }
+                                    @if let Some(desc) = &hunt.description {
+                                        div class="text-sm text-muted font-bold" { (desc) }
+                                    }
+                                }
+                                span class="badge-brutal orange" {
+                                    @if hunt.ordered { "ORDERED" } @else { "ANY ORDER" }
+                                }
+                            }
+                            div class="flex flex-wrap gap-6 text-sm font-bold mono text-secondary" {
+                                span { "STOPS: " (stops) }
+                                span { "BONUS: " (hunt.completion_bonus_sats()) " sats" }
+                                span { "POOL: " (pool_sats) " sats" }
+                                span { "CREATED: " (hunt.created_at.format("%Y-%m-%d").to_string()) }
+                            }
+                            details class="mt-4" {
+                                summary class="label-brutal cursor-pointer" {
+                                    "SCHEDULE (" (schedules.get(&hunt.id).map(Vec::len).unwrap_or(0)) ")"
+                                }
+                                div class="mt-4 space-y-4" {
+                                    @if let Some(hunt_schedules) = schedules.get(&hunt.id) {
+                                        (schedule_list_markup(hunt_schedules, |_| true))
+                                    }
+                                    (schedule_form_markup(&format!("/api/admin/hunts/{}/schedules", hunt.id)))
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn new_hunt_form(locations: &[Location]) -> Markup {
+    html! {
+        form class="card-brutal mb-8 space-y-4"
+            hx-post="/api/admin/hunts"
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            h2 class="text-xl font-black text-primary" { "NEW HUNT" }
+
+            div class="grid md:grid-cols-2 gap-4" {
+                div {
+                    label for="hunt-name" class="label-brutal" { "NAME" }
+                    input type="text" id="hunt-name" name="name" required
+                        class="input-brutal-box w-full" placeholder="OLD TOWN TRAIL";
+                }
+                div {
+                    label for="hunt-bonus" class="label-brutal" { "COMPLETION BONUS (SATS)" }
+                    input type="number" id="hunt-bonus" name="bonus_sats" required min="1"
+                        class="input-brutal-box w-full" placeholder="10000";
+                }
+            }
+
+            div {
+                label for="hunt-description" class="label-brutal" { "DESCRIPTION (OPTIONAL)" }
+                input type="text" id="hunt-description" name="description"
+                    class="input-brutal-box w-full";
+            }
+
+            label class="flex items-center gap-2 font-bold text-secondary" {
+                input type="checkbox" name="ordered" value="1" checked;
+                "STOPS HAVE TO BE CLAIMED IN ORDER"
+            }
+
+            div {
+                p class="label-brutal" { "STOPS (CLICK IN TRAIL ORDER)" }
+                input type="hidden" id="hunt-location-ids" name="location_ids";
+                div class="grid md:grid-cols-2 gap-2" {
+                    @for location in locations {
+                        label class="flex items-center gap-2 text-sm font-bold text-secondary" {
+                            input type="checkbox" class="hunt-stop" value=(location.id);
+                            span class="hunt-stop-number mono text-highlight" style="min-width: 2ch;" {}
+                            (location.name)
+                        }
+                    }
+                }
+            }
+
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-plus mr-2" {}
+                "CREATE HUNT"
+            }
+        }
+
+        (PreEscaped(r#"
+        <script>
+            (function() {
+                const order = [];
+                const input = document.getElementById('hunt-location-ids');
+                document.querySelectorAll('.hunt-stop').forEach(box => {
+                    box.addEventListener('change', () => {
+                        const i = order.indexOf(box.value);
+                        if (box.checked && i < 0) order.push(box.value);
+                        if (!box.checked && i >= 0) order.splice(i, 1);
+                        input.value = order.join(',');
+                        document.querySelectorAll('.hunt-stop').forEach(other => {
+                            const n = order.indexOf(other.value);
+                            other.nextElementSibling.textContent = n >= 0 ? (n + 1) + '.' : '';
+                        });
+                    });
+                });
+            })();
+        </script>
+        "#))
+    }
+}
@@ -0,0 +1,152 @@
+use crate::models::{LedgerAccount, LedgerEntry, LedgerReport};
+use maud::{html, Markup};
+
+/// Admin ledger page: verification result, account balances and recent journal entries
+pub fn admin_ledger(report: &LedgerReport, entries: &[LedgerEntry]) -> Markup {
+    html! {
+        div class="mb-8" {
+            div class="flex justify-between items-center mb-8" {
+                h1 class="text-4xl font-black text-primary" style="letter-spacing: -0.02em;" {
+                    "LEDGER"
+                }
+            }
+
+            // Verification status
+            @if report.is_balanced() {
+                div class="alert-brutal green success mb-8" {
+                    "LEDGER IS BALANCED · "
+                    span class="mono" { (report.entry_count) " ENTRIES" }
+                }
+            } @else {
+                div class="alert-brutal orange error mb-8" {
+                    (report.discrepancies.len()) " DISCREPANCIES FOUND"
+                }
+                div class="card-brutal overflow-x-auto mb-8" {
+                    h2 class="text-xl font-black text-primary mb-4" { "DISCREPANCIES" }
+                    table class="w-full text-sm" style="border-collapse: collapse;" {
+                        thead {
+                            tr style="border-bottom: 3px solid var(--accent-muted);" {
+                                th class="text-left py-3 px-3 font-black text-primary" { "ACCOUNT" }
+                                th class="text-left py-3 px-3 font-black text-primary" { "PROBLEM" }
+                                th class="text-right py-3 px-3 font-black text-primary" { "LEDGER MSATS" }
+                                th class="text-right py-3 px-3 font-black text-primary" { "EXPECTED MSATS" }
+                            }
+                        }
+                        tbody {
+                            @for d in &report.discrepancies {
+                                tr style="border-bottom: 1px solid var(--accent-muted);" {
+                                    td class="py-2 px-3 mono" {
+                                        @if let Some(account) = &d.account {
+                                            (account_label(account))
+                                        } @else {
+                                            span class="text-muted" { "-" }
+                                        }
+                                    }
+                                    td class="py-2 px-3 font-bold" { (d.message) }
+                                    td class="py-2 px-3 text-right mono" { (d.ledger_msats) }
+                                    td class="py-2 px-3 text-right mono" { (d.expected_msats) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Trial balance
+            div class="card-brutal overflow-x-auto mb-8" {
+                h2 class="text-xl font-black text-primary mb-4" { "ACCOUNTS" }
+                div class="flex flex-wrap gap-6 mb-4 text-sm font-bold mono text-secondary" {
+                    span { "DEBITS: " (report.total_debits_msats / 1000) " sats" }
+                    span { "CREDITS: " (report.total_credits_msats / 1000) " sats" }
+                }
+                @if report.balances.is_empty() {
+                    p class="text-muted font-bold" { "NO ENTRIES YET." }
+                } @else {
+                    table class="w-full text-sm" style="border-collapse: collapse;" {
+                        thead {
+                            tr style="border-bottom: 3px solid var(--accent-muted);" {
+                                th class="text-left py-3 px-3 font-black text-primary" { "ACCOUNT" }
+                                th class="text-right py-3 px-3 font-black text-primary" { "SATS" }
+                            }
+                        }
+                        tbody {
+                            @for balance in &report.balances {
+                                tr style="border-bottom: 1px solid var(--accent-muted);" {
+                                    td class="py-2 px-3 mono" { (account_label(&balance.account)) }
+                                    td class="py-2 px-3 text-right mono" {
+                                        @if balance.msats < 0 {
+                                            span class="text-highlight orange font-bold" { (balance.msats / 1000) }
+                                        } @else {
+                                            (balance.msats / 1000)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Journal
+            div class="card-brutal overflow-x-auto" {
+                h2 class="text-xl font-black text-primary mb-4" { "RECENT ENTRIES" }
+                @if entries.is_empty() {
+                    p class="text-muted font-bold" { "NO ENTRIES YET." }
+                } @else {
+                    table class="w-full text-sm" style="border-collapse: collapse;" {
+                        thead {
+                            tr style="border-bottom: 3px solid var(--accent-muted);" {
+                                th class="text-left py-3 px-3 font-black text-primary" { "TIMESTAMP" }
+                                th class="text-left py-3 px-3 font-black text-primary" { "KIND" }
+                                th class="text-left py-3 px-3 font-black text-primary" { "DEBIT" }
+                                th class="text-left py-3 px-3 font-black text-primary" { "CREDIT" }
+                                th class="text-right py-3 px-3 font-black text-primary" { "SATS" }
+                            }
+                        }
+                        tbody {
+                            @for entry in entries {
+                                tr style="border-bottom: 1px solid var(--accent-muted);" {
+                                    td class="py-2 px-3 mono text-secondary" style="white-space: nowrap;" {
+                                        (entry.created_at.format("%Y-%m-%dT%H:%M:%S").to_string())
+                                    }
+                                    td class="py-2 px-3 font-bold" { (entry.kind.as_str()) }
+                                    td class="py-2 px-3 mono" { (account_label(&entry.debit_account)) }
+                                    td class="py-2 px-3 mono" { (account_label(&entry.credit_account)) }
+                                    td class="py-2 px-3 text-right mono text-highlight font-bold" {
+                                        (entry.sats())
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn account_label(account: &LedgerAccount) -> Markup {
+    html! {
+        @match account {
+            LedgerAccount::LocationPool(id) => {
+                a href={"/locations/" (id)} class="text-primary hover:text-highlight" {
+                    "location:" (id.get(..8).unwrap_or(id))
+                }
+            }
+            LedgerAccount::HuntPool(id) => {
+                a href={"/hunts/" (id)} class="text-primary hover:text-highlight" {
+                    "hunt:" (id.get(..8).unwrap_or(id))
+                }
+            }
+            LedgerAccount::UserWallet(id) => {
+                "user:" (id.get(..8).unwrap_or(id))
+            }
+            LedgerAccount::TeamWallet(id) => {
+                "team:" (id.get(..8).unwrap_or(id))
+            }
+            _ => {
+                span class="font-bold" { (account.to_string()) }
+            }
+        }
+    }
+}
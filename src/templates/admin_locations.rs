@@ -8,6 +8,10 @@ pub fn admin_locations(location_balances: &[(&Location, i64, i64)]) -> Markup {
         .iter()
         .filter(|(l, _, _)| l.is_active())
         .count();
+    let scheduled_count = location_balances
+        .iter()
+        .filter(|(l, _, _)| l.is_scheduled())
+        .count();
     let deactivated_count = location_balances
         .iter()
         .filter(|(l, _, _)| l.is_deactivated())
@@ -50,6 +54,13 @@ pub fn admin_locations(location_balances: &[(&Location, i64, i64)]) -> Markup {
                     "ACTIVE "
                     span class="mono" { "[" (active_count) "]" }
                 }
+                button type="button"
+                    class="btn-brutal"
+                    id="filter-scheduled"
+                    onclick="filterLocations('scheduled')" {
+                    "SCHEDULED "
+                    span class="mono" { "[" (scheduled_count) "]" }
+                }
                 button type="button"
                     class="btn-brutal"
                     id="filter-deactivated"
@@ -113,7 +124,7 @@ pub fn admin_locations(location_balances: &[(&Location, i64, i64)]) -> Markup {
                     });
 
                     // Update button styles
-                    const filters = ['all', 'active', 'deactivated', 'admin_deactivated', 'programmed', 'created'];
+                    const filters = ['all', 'active', 'scheduled', 'deactivated', 'admin_deactivated', 'programmed', 'created'];
                     filters.forEach(f => {
                         const btn = document.getElementById('filter-' + f);
                         if (f === filter) {
@@ -137,6 +148,8 @@ pub fn admin_locations(location_balances: &[(&Location, i64, i64)]) -> Markup {
 fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Markup {
     let status = if location.is_active() {
         "active"
+    } else if location.is_scheduled() {
+        "scheduled"
     } else if location.is_deactivated() {
         "deactivated"
     } else if location.is_admin_deactivated() {
@@ -149,6 +162,12 @@ fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Ma
 
     let status_badge = match status {
         "active" => html! { span class="badge-brutal filled" { "ACTIVE" } },
+        "scheduled" => html! {
+            span class="badge-brutal grey" {
+                i class="fa-solid fa-clock mr-1" {}
+                "SCHEDULED"
+            }
+        },
         "deactivated" => html! {
             span class="badge-brutal grey" {
                 i class="fa-solid fa-pause mr-1" {}
@@ -191,7 +210,7 @@ fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Ma
 
                     div class="flex items-center gap-2" {
                         // Deactivate/reactivate button
-                        @if location.is_active() {
+                        @if location.is_live() {
                             button
                                 onclick={
                                     "if(confirm('ADMIN DEACTIVATE THIS LOCATION?')) { "
@@ -235,8 +254,8 @@ fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Ma
                     }
                 }
 
-                // Balance (for active/deactivated locations)
-                @if location.is_active() || location.is_deactivated() || location.is_admin_deactivated() {
+                // Balance (for live/deactivated locations)
+                @if location.is_live() || location.is_deactivated() || location.is_admin_deactivated() {
                     div class="pt-4" style="border-top: 3px solid var(--accent-muted);" {
                         div class="flex justify-between items-center mb-3" {
                             div class="label-brutal" { "BALANCE" }
@@ -0,0 +1,126 @@
+use crate::solvency::SolvencyReport;
+use crate::templates::format_sats_si;
+use maud::{html, Markup};
+
+/// Admin solvency dashboard comparing node reserves with liabilities
+pub fn admin_solvency(report: &SolvencyReport) -> Markup {
+    html! {
+        div class="mb-8" {
+            div class="flex justify-between items-center mb-8" {
+                h1 class="text-4xl font-black text-primary" style="letter-spacing: -0.02em;" {
+                    "SOLVENCY"
+                }
+                a href="/api/admin/solvency" class="btn-brutal" download="solvency-report.json" {
+                    i class="fa-solid fa-download mr-2" {}
+                    "EXPORT JSON"
+                }
+            }
+
+            @if report.is_solvent() {
+                div class="alert-brutal green success mb-8" {
+                    "NODE COVERS ALL LIABILITIES · "
+                    span class="mono" { (format!("{:.1}", report.coverage_percent())) "%" }
+                }
+            } @else {
+                div class="alert-brutal orange error mb-8" {
+                    "NODE IS SHORT BY "
+                    span class="mono" { (format_sats_si(-report.surplus_msats / 1000)) " SATS" }
+                    " · "
+                    span class="mono" { (format!("{:.1}", report.coverage_percent())) "%" }
+                }
+            }
+
+            div class="grid grid-cols-1 md:grid-cols-2 gap-6 mb-8" {
+                div class="card-brutal" {
+                    div class="label-brutal mb-2" { "NODE BALANCE" }
+                    div class="text-4xl font-black text-highlight mono" {
+                        (format_sats_si(report.node_balance_msats / 1000))
+                    }
+                    div class="text-sm text-muted font-bold mono" {
+                        (report.node_balance_msats / 1000) " sats"
+                    }
+                }
+                div class="card-brutal" {
+                    div class="label-brutal mb-2" { "TOTAL LIABILITIES" }
+                    div class="text-4xl font-black text-primary mono" {
+                        (format_sats_si(report.total_liabilities_msats / 1000))
+                    }
+                    div class="text-sm text-muted font-bold mono" {
+                        (report.total_liabilities_msats / 1000) " sats"
+                    }
+                }
+            }
+
+            div class="card-brutal overflow-x-auto mb-8" {
+                h2 class="text-xl font-black text-primary mb-4" { "LIABILITIES" }
+                table class="w-full text-sm" style="border-collapse: collapse;" {
+                    tbody {
+                        tr style="border-bottom: 1px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-bold" {
+                                "USER WALLETS "
+                                span class="text-muted mono" { "[" (report.user_count) "]" }
+                            }
+                            td class="py-2 px-3 text-right mono" { (report.user_wallets_msats / 1000) }
+                        }
+                        tr style="border-bottom: 1px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-bold" { "PENDING WITHDRAWALS" }
+                            td class="py-2 px-3 text-right mono" { (report.pending_withdrawals_msats / 1000) }
+                        }
+                        tr style="border-bottom: 1px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-bold" { "LOCATION POOLS" }
+                            td class="py-2 px-3 text-right mono" { (report.location_pools_msats / 1000) }
+                        }
+                        tr style="border-bottom: 1px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-bold" { "CAMPAIGN BUDGETS" }
+                            td class="py-2 px-3 text-right mono" { (report.campaign_budgets_msats / 1000) }
+                        }
+                        tr style="border-bottom: 1px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-bold" { "HUNT POOLS" }
+                            td class="py-2 px-3 text-right mono" { (report.hunt_pools_msats / 1000) }
+                        }
+                        tr style="border-bottom: 1px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-bold" { "TEAM WALLETS" }
+                            td class="py-2 px-3 text-right mono" { (report.team_wallets_msats / 1000) }
+                        }
+                        tr style="border-bottom: 3px solid var(--accent-muted);" {
+                            td class="py-2 px-3 font-black text-primary" { "SURPLUS" }
+                            td class="py-2 px-3 text-right mono font-black text-highlight" {
+                                (report.surplus_msats / 1000)
+                            }
+                        }
+                    }
+                }
+            }
+
+            div class="card-brutal" {
+                h2 class="text-xl font-black text-primary mb-4" { "PROOF OF LIABILITIES" }
+                p class="text-secondary font-bold mb-4" {
+                    "Merkle-sum root over all user wallets, published daily. Users can fetch an inclusion proof for their own balance from "
+                    code class="mono" { "/api/wallet/liability-proof" }
+                    " and check it against the roots listed at "
+                    code class="mono" { "/api/solvency/snapshots" }
+                    "."
+                }
+                @if let Some(snapshot) = &report.liabilities_snapshot {
+                    div class="label-brutal mb-1" { "ROOT HASH" }
+                    div class="mono text-sm text-primary mb-4" style="word-break: break-all;" {
+                        (snapshot.root_hash)
+                    }
+                    div class="label-brutal mb-1" { "ROOT SUM" }
+                    div class="mono text-sm text-primary mb-4" {
+                        (snapshot.sum_msats) " msats"
+                    }
+                    div class="label-brutal mb-1" { "PUBLISHED" }
+                    div class="mono text-sm text-primary mb-4" {
+                        (snapshot.created_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                    }
+                } @else {
+                    p class="text-muted font-bold mb-4" { "No snapshot published yet" }
+                }
+                div class="text-xs text-muted mono" {
+                    "GENERATED " (report.generated_at.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::models::PendingWithdrawal;
+use maud::{html, Markup};
+
+/// Admin withdrawals page: approve or reject withdrawals held over a limit.
+/// Each withdrawal comes with the display name of the user who requested it.
+pub fn admin_withdrawals(withdrawals: &[(PendingWithdrawal, String)]) -> Markup {
+    html! {
+        div class="mb-8" {
+            div class="flex justify-between items-center mb-8" {
+                h1 class="text-4xl font-black text-primary" style="letter-spacing: -0.02em;" {
+                    "HELD WITHDRAWALS"
+                }
+            }
+
+            @if withdrawals.is_empty() {
+                div class="card-brutal-inset text-center" style="padding: 3rem;" {
+                    div class="text-6xl mb-6 text-muted" {
+                        i class="fa-solid fa-hand" {}
+                    }
+                    h3 class="text-2xl font-black text-primary mb-3" { "NOTHING TO REVIEW" }
+                    p class="text-secondary font-bold" {
+                        "WITHDRAWALS OVER A LIMIT SHOW UP HERE UNTIL THEY ARE APPROVED OR REJECTED."
+                    }
+                }
+            } @else {
+                div class="space-y-4" {
+                    @for (withdrawal, user_name) in withdrawals {
+                        (withdrawal_card(withdrawal, user_name))
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn withdrawal_card(withdrawal: &PendingWithdrawal, user_name: &str) -> Markup {
+    html! {
+        div class="card-brutal" {
+            div class="flex justify-between items-start gap-4 mb-4" {
+                div {
+                    h3 class="text-xl font-black text-primary mb-1" {
+                        (withdrawal.amount_msats() / 1000) " sats"
+                    }
+                    div class="text-sm text-muted font-bold" {
+                        "BY " (user_name)
+                    }
+                }
+                span class="badge-brutal orange" { "HELD" }
+            }
+
+            div class="flex flex-wrap gap-6 mb-4 text-sm font-bold mono text-secondary" {
+                span { "FEE RESERVED: " (withdrawal.fee_msats / 1000) " sats" }
+                span { "REQUESTED: " (withdrawal.created_at.format("%Y-%m-%d %H:%M UTC").to_string()) }
+            }
+
+            @if let Some(reason) = &withdrawal.hold_reason {
+                div class="alert-brutal orange mb-4" { (reason) }
+            }
+
+            div class="card-brutal-inset" {
+                p class="label-brutal" { "DESTINATION" }
+                p class="mono text-xs text-secondary" style="word-break: break-all;" {
+                    (withdrawal.invoice)
+                }
+            }
+
+            div class="flex gap-4 pt-4 mt-4" style="border-top: 3px solid var(--accent-muted);" {
+                button type="button" class="btn-brutal-fill"
+                    hx-post={"/api/admin/withdrawals/" (withdrawal.id) "/approve"}
+                    hx-swap="none"
+                    hx-confirm="Approve and pay this withdrawal now?"
+                    hx-on--after-request="if(!event.detail.successful) alert(event.detail.xhr.responseText); window.location.reload()" {
+                    i class="fa-solid fa-check mr-2" {}
+                    "APPROVE & PAY"
+                }
+                button type="button" class="btn-brutal"
+                    hx-post={"/api/admin/withdrawals/" (withdrawal.id) "/reject"}
+                    hx-swap="none"
+                    hx-confirm="Reject this withdrawal? The sats go back to the user's wallet."
+                    hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                    i class="fa-solid fa-xmark mr-2" {}
+                    "REJECT"
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::models::{Location, User};
+use crate::claim_policy::ClaimPolicy;
+use crate::claim_rules::{self, ClaimRules};
+use crate::models::{ClaimResult, Location, User};
 use maud::{html, Markup, PreEscaped};
 
 /// Parameters for the collection page template.
@@ -11,6 +13,12 @@ pub struct CollectParams<'a> {
     pub error: Option<&'a str>,
     pub is_new_user: bool,
     pub user: Option<&'a User>,
+    /// Claim rules in effect at the location
+    pub claim_rules: &'a ClaimRules,
+    /// Why the claim rules keep the user from claiming here right now
+    pub claim_block: Option<&'a ClaimResult>,
+    /// Extra sats the user gets for their first claim
+    pub first_claim_bonus_sats: i64,
 }
 
 /// Render the collection page for the custodial wallet system.
@@ -25,10 +33,13 @@ pub fn collect(params: CollectParams<'_>) -> Markup {
         error,
         is_new_user,
         user,
+        claim_rules,
+        claim_block,
+        first_claim_bonus_sats,
     } = params;
 
-    // Can only claim if we have a valid scan_id
-    let can_claim = scan_id.is_some() && available_sats > 0;
+    // Can only claim if we have a valid scan_id and the claim rules allow it
+    let can_claim = scan_id.is_some() && available_sats > 0 && claim_block.is_none();
 
     html! {
         div class="max-w-2xl mx-auto" {
@@ -75,6 +86,11 @@ pub fn collect(params: CollectParams<'_>) -> Markup {
                         "Check back later - locations refill automatically!"
                     }
                 }
+            } @else if let Some(message) = claim_block.and_then(claim_rules::blocked_message) {
+                // Has sats but the claim rules block this user
+                div class="card-brutal-inset p-6 text-center mb-6" {
+                    p class="text-xl font-bold text-muted" { (message) }
+                }
             } @else if !can_claim {
                 // Has sats but no valid scan
                 div class="card-brutal-inset p-6 text-center mb-6" {
@@ -95,14 +111,29 @@ pub fn collect(params: CollectParams<'_>) -> Markup {
 
                     div class="p-4" style="background: var(--bg-tertiary); border-top: none;" {
                         p class="text-secondary font-bold mb-4 text-center" {
-                            "Tap the button to add " (available_sats) " sats to your SatsHunt wallet."
+                            @if location.claim_policy.takes_all() {
+                                "Tap the button to add " (available_sats) " sats to your SatsHunt wallet."
+                            } @else {
+                                "Tap the button to add your share of the " (available_sats) " sats to your SatsHunt wallet."
+                            }
+                        }
+
+                        @if first_claim_bonus_sats > 0 {
+                            p class="text-highlight font-bold mb-4 text-center" {
+                                i class="fa-solid fa-gift mr-2" {}
+                                "First claim bonus: +" (first_claim_bonus_sats) " sats on top!"
+                            }
                         }
 
                         // Collect button
                         button id="collect-btn" onclick="claimSats()"
                             class="btn-brutal-fill w-full text-xl py-4" style="background: var(--highlight); border-color: var(--highlight);" {
                             i class="fa-solid fa-bolt mr-3" {}
-                            "COLLECT " (available_sats) " SATS"
+                            @if location.claim_policy.takes_all() {
+                                "COLLECT " (available_sats) " SATS"
+                            } @else {
+                                "COLLECT YOUR SHARE"
+                            }
                         }
 
                         // Processing state (hidden by default)
@@ -122,6 +153,8 @@ pub fn collect(params: CollectParams<'_>) -> Markup {
                 }
             }
 
+            (claim_rules_markup(location.claim_policy, claim_rules))
+
             // Current balance card
             div class="card-brutal mb-6" {
                 h2 class="heading-breaker" {
@@ -242,3 +275,39 @@ pub fn collect(params: CollectParams<'_>) -> Markup {
         }
     }
 }
+
+/// Explanation of the claim policy and rules in effect at a location
+fn claim_rules_markup(policy: ClaimPolicy, rules: &ClaimRules) -> Markup {
+    html! {
+        div class="card-brutal mb-6" {
+            h2 class="heading-breaker" {
+                i class="fa-solid fa-scale-balanced mr-2" {}
+                "CLAIM RULES"
+            }
+            ul class="p-4 space-y-2 text-sm font-bold text-secondary" {
+                li {
+                    i class="fa-solid fa-trophy mr-2 text-highlight" {}
+                    (policy.label()) ": " (policy.description())
+                }
+                @if let Some(days) = rules.cooldown_days {
+                    li {
+                        i class="fa-solid fa-hourglass-half mr-2 text-highlight" {}
+                        "You can claim here once every " (days) " day" @if days != 1 { "s" } "."
+                    }
+                }
+                @if let Some(limit) = rules.max_claims_per_month {
+                    li {
+                        i class="fa-solid fa-calendar-days mr-2 text-highlight" {}
+                        "You can claim here at most " (limit) " time" @if limit != 1 { "s" } " in 30 days."
+                    }
+                }
+                @if let Some(percent) = rules.first_claim_bonus_percent {
+                    li {
+                        i class="fa-solid fa-gift mr-2 text-highlight" {}
+                        "Your very first claim on SatsHunt pays " (percent) "% extra."
+                    }
+                }
+            }
+        }
+    }
+}
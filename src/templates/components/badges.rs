@@ -0,0 +1,48 @@
+use crate::achievements::Badge;
+use crate::models::UserBadge;
+use maud::{html, Markup};
+
+/// A user's earned badges, with the ones still to earn greyed out
+pub fn badges_markup(badges: &[UserBadge]) -> Markup {
+    let all = crate::achievements::RULES.iter().map(|rule| rule.badge);
+    let locked: Vec<Badge> = all
+        .filter(|badge| !badges.iter().any(|b| b.badge == *badge))
+        .collect();
+    html! {
+        div class="card-brutal-inset mb-8" {
+            h2 class="text-xl font-black text-primary mb-4" {
+                i class="fa-solid fa-medal mr-2" {}
+                "BADGES "
+                span class="text-muted mono" { "[" (badges.len()) "]" }
+            }
+            div class="grid grid-cols-2 md:grid-cols-3 gap-3" {
+                @for earned in badges {
+                    (badge_markup(earned.badge, Some(&earned.earned_at.format("%Y-%m-%d").to_string())))
+                }
+                @for badge in locked {
+                    (badge_markup(badge, None))
+                }
+            }
+        }
+    }
+}
+
+fn badge_markup(badge: Badge, earned_on: Option<&str>) -> Markup {
+    let style = if earned_on.is_some() {
+        "background: var(--bg-tertiary); border: 2px solid var(--highlight);"
+    } else {
+        "background: var(--bg-tertiary); border: 2px solid var(--accent-muted); opacity: 0.4;"
+    };
+    html! {
+        div class="p-3" style=(style) title=(badge.description()) {
+            div class="font-black text-primary" {
+                i class={"fa-solid " (badge.icon()) " mr-2 text-highlight orange"} {}
+                (badge.label())
+            }
+            p class="text-xs text-secondary font-bold mt-1" { (badge.description()) }
+            @if let Some(earned_on) = earned_on {
+                p class="text-xs text-muted mono mt-1" { "EARNED " (earned_on) }
+            }
+        }
+    }
+}
@@ -6,6 +6,8 @@ pub struct DonationInvoiceConfig<'a> {
     pub id_prefix: &'a str,
     /// Optional location ID for location-specific donations
     pub location_id: Option<&'a str>,
+    /// Optional hunt ID for donations to a hunt's bonus pool
+    pub hunt_id: Option<&'a str>,
     /// Available amount buttons as (value, label) pairs
     pub amounts: &'a [(&'a str, &'a str)],
     /// Optional label shown above the amount buttons
@@ -17,6 +19,7 @@ impl Default for DonationInvoiceConfig<'_> {
         Self {
             id_prefix: "",
             location_id: None,
+            hunt_id: None,
             amounts: &[
                 ("1000", "1K sats"),
                 ("5000", "5K sats"),
@@ -103,6 +106,10 @@ pub fn donation_invoice_script(config: &DonationInvoiceConfig) -> Markup {
         .location_id
         .map(|id| format!("'{}'", id))
         .unwrap_or_else(|| "null".to_string());
+    let hunt_id_js = config
+        .hunt_id
+        .map(|id| format!("'{}'", id))
+        .unwrap_or_else(|| "null".to_string());
 
     PreEscaped(format!(
         r#"
@@ -111,6 +118,7 @@ pub fn donation_invoice_script(config: &DonationInvoiceConfig) -> Markup {
         const prefix = '{prefix}';
         const fnSuffix = '{fn_suffix}';
         const locationId = {location_id_js};
+        const huntId = {hunt_id_js};
 
         // Copy invoice to clipboard
         window['copy' + fnSuffix + 'Invoice'] = function() {{
@@ -146,7 +154,9 @@ pub fn donation_invoice_script(config: &DonationInvoiceConfig) -> Markup {
                 invoiceArea.classList.remove('hidden');
 
                 // Generate invoice
-                const body = locationId ? {{ amount, location_id: locationId }} : {{ amount }};
+                const body = locationId ? {{ amount, location_id: locationId }}
+                    : huntId ? {{ amount, hunt_id: huntId }}
+                    : {{ amount }};
                 const response = await fetch('/api/donate/invoice', {{
                     method: 'POST',
                     headers: {{ 'Content-Type': 'application/json' }},
@@ -278,6 +288,7 @@ pub fn donation_invoice_script(config: &DonationInvoiceConfig) -> Markup {
         prefix = prefix,
         fn_suffix = fn_suffix,
         location_id_js = location_id_js,
+        hunt_id_js = hunt_id_js,
         btn_selector = btn_selector,
     ))
 }
@@ -0,0 +1,160 @@
+use super::super::format_sats_si;
+use super::countdown_markup;
+use crate::models::{Hint, HintUnlock};
+use chrono::{DateTime, Utc};
+use maud::{html, Markup};
+
+/// A location's hints in order. Locked hints show how to unlock them; with
+/// `can_edit` every hint is shown in full along with a remove button.
+pub fn hints_markup(
+    hints: &[Hint],
+    unlocked_ids: &[String],
+    location_created_at: DateTime<Utc>,
+    can_edit: bool,
+) -> Markup {
+    let now = Utc::now();
+    html! {
+        div class="space-y-3" {
+            @for hint in hints {
+                @let unlocks_at = hint.unlocks_at(location_created_at);
+                @let unlocked = unlocked_ids.contains(&hint.id)
+                    || unlocks_at.is_some_and(|at| at <= now);
+                div class="p-3" style="background: var(--bg-tertiary); border: 2px solid var(--accent-muted);" {
+                    div class="flex items-center justify-between gap-4 mb-2" {
+                        div class="font-black text-sm" {
+                            span class="text-highlight orange" { "HINT #" (hint.position) }
+                            span class="badge-brutal grey ml-2" { (unlock_label(hint)) }
+                        }
+                        @if can_edit {
+                            button type="button" class="btn-brutal" style="padding: 0.25rem 0.5rem;"
+                                hx-delete={"/api/hints/" (hint.id)}
+                                hx-confirm="Remove this hint?"
+                                hx-swap="none"
+                                hx-on--after-request="if(event.detail.successful) window.location.reload()"
+                                title="Remove hint" {
+                                i class="fa-solid fa-trash" {}
+                            }
+                        }
+                    }
+                    @if unlocked || can_edit {
+                        p class="text-primary font-bold" { (hint.text) }
+                        @if can_edit {
+                            @if let (Some(riddle), Some(answer)) = (&hint.riddle, &hint.riddle_answer) {
+                                p class="text-xs text-muted font-bold mt-2" {
+                                    "RIDDLE: " (riddle) " · ANSWER: " (answer)
+                                }
+                            }
+                        }
+                    } @else {
+                        (locked_markup(hint, unlocks_at))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How to unlock a hint that is still locked for the current user
+fn locked_markup(hint: &Hint, unlocks_at: Option<DateTime<Utc>>) -> Markup {
+    html! {
+        @match hint.unlock_kind {
+            HintUnlock::Time => {
+                p class="text-muted font-bold" {
+                    i class="fa-solid fa-lock mr-2" {}
+                    "UNLOCKS IN "
+                    @if let Some(at) = unlocks_at {
+                        (countdown_markup(at))
+                    }
+                }
+            }
+            HintUnlock::Payment => {
+                button type="button" class="btn-brutal-orange"
+                    hx-post={"/api/hints/" (hint.id) "/unlock"}
+                    hx-confirm={"Pay " (hint.price_sats()) " sats from your wallet for this hint?"}
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                    i class="fa-solid fa-lock-open mr-2" {}
+                    "UNLOCK FOR " (format_sats_si(hint.price_sats())) " SATS"
+                }
+            }
+            HintUnlock::Riddle => {
+                p class="text-secondary font-bold mb-2" {
+                    i class="fa-solid fa-question mr-2" {}
+                    (hint.riddle.as_deref().unwrap_or_default())
+                }
+                form class="flex gap-2"
+                    hx-post={"/api/hints/" (hint.id) "/unlock"}
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                    input type="text" name="answer" required placeholder="YOUR ANSWER"
+                        class="input-brutal-box w-full";
+                    button type="submit" class="btn-brutal-fill" { "ANSWER" }
+                }
+            }
+        }
+    }
+}
+
+fn unlock_label(hint: &Hint) -> String {
+    match hint.unlock_kind {
+        HintUnlock::Time => format!("AFTER {} DAYS", hint.unlock_after_days.unwrap_or(0)),
+        HintUnlock::Payment => format!("{} SATS", format_sats_si(hint.price_sats())),
+        HintUnlock::Riddle => "RIDDLE".to_string(),
+    }
+}
+
+/// Form adding a hint to a location. Only the fields of the selected unlock
+/// kind are shown.
+pub fn hint_form_markup(location_id: &str) -> Markup {
+    html! {
+        form class="space-y-4"
+            hx-post={"/api/locations/" (location_id) "/hints"}
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            div {
+                label for="hint-text" class="label-brutal" { "HINT" }
+                textarea id="hint-text" name="text" rows="2" required
+                    class="input-brutal-box w-full" {}
+            }
+            div class="grid md:grid-cols-2 gap-4" {
+                div {
+                    label for="hint-unlock" class="label-brutal" { "UNLOCKS" }
+                    select id="hint-unlock" name="unlock_kind" class="input-brutal-box w-full"
+                        onchange="this.form.querySelectorAll('[data-unlock]').forEach(el => el.classList.toggle('hidden', el.dataset.unlock !== this.value))" {
+                        option value="time" selected { "AFTER SOME DAYS" }
+                        option value="payment" { "BY PAYING SATS" }
+                        option value="riddle" { "BY SOLVING A RIDDLE" }
+                    }
+                }
+                div data-unlock="time" {
+                    label for="hint-days" class="label-brutal" { "DAYS AFTER CREATION" }
+                    input type="number" id="hint-days" name="unlock_after_days" min="0" value="7"
+                        class="input-brutal-box w-full";
+                }
+                div data-unlock="payment" class="hidden" {
+                    label for="hint-price" class="label-brutal" { "PRICE (SATS)" }
+                    input type="number" id="hint-price" name="price_sats" min="1" value="100"
+                        class="input-brutal-box w-full";
+                }
+            }
+            div data-unlock="riddle" class="hidden" {
+                div class="grid md:grid-cols-2 gap-4" {
+                    div {
+                        label for="hint-riddle" class="label-brutal" { "RIDDLE" }
+                        input type="text" id="hint-riddle" name="riddle"
+                            class="input-brutal-box w-full";
+                    }
+                    div {
+                        label for="hint-answer" class="label-brutal" { "ANSWER" }
+                        input type="text" id="hint-answer" name="riddle_answer"
+                            class="input-brutal-box w-full";
+                    }
+                }
+            }
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-lightbulb mr-2" {}
+                "ADD HINT"
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use maud::{html, Markup};
+
+/// A donation Lightning address with a copy button
+pub fn lightning_address_markup(address: &str) -> Markup {
+    html! {
+        div class="mt-6 p-4" style="background: var(--bg-tertiary); border: 2px solid var(--accent-muted);" {
+            div class="label-brutal text-xs mb-1" {
+                i class="fa-solid fa-at mr-1" {}
+                "LIGHTNING ADDRESS"
+            }
+            div class="flex items-center justify-between gap-4" {
+                span class="mono font-bold text-primary" style="word-break: break-all;" { (address) }
+                button type="button" class="btn-brutal"
+                    data-address=(address)
+                    onclick="navigator.clipboard.writeText(this.dataset.address); this.textContent = 'COPIED';" {
+                    "COPY"
+                }
+            }
+            div class="text-xs text-muted font-bold mt-2" {
+                "Send any amount from your wallet. Comments are shown with your donation."
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::models::{LocationLog, LocationLogPhoto, LogKind};
+use maud::{html, Markup};
+
+/// One page of a location's log book
+pub struct LogBook<'a> {
+    pub logs: &'a [LocationLog],
+    /// Photos of the logs at the location, matched to logs by `log_id`
+    pub photos: &'a [LocationLogPhoto],
+    pub page: i64,
+    pub total_pages: i64,
+    /// Whether the viewer can write logs (registered users)
+    pub can_write: bool,
+}
+
+/// The log book of a location with the form for a new log. Moderators see
+/// hidden logs with a button to restore them.
+pub fn log_book_markup(
+    location_id: &str,
+    log_book: &LogBook,
+    current_user_id: Option<&str>,
+    can_moderate: bool,
+    is_admin: bool,
+) -> Markup {
+    html! {
+        @if log_book.can_write {
+            details class="mb-6" {
+                summary class="font-black text-primary cursor-pointer select-none hover:text-highlight" {
+                    i class="fa-solid fa-plus mr-2" {}
+                    "WRITE A LOG"
+                }
+                (log_form_markup(location_id))
+            }
+        } @else {
+            p class="text-muted font-bold mb-6" {
+                a href="/login" class="text-highlight orange" { "LOG IN" }
+                " TO WRITE A LOG."
+            }
+        }
+
+        @if log_book.logs.is_empty() {
+            p class="text-muted font-bold" { "NO LOGS YET." }
+        } @else {
+            div class="space-y-3" {
+                @for log in log_book.logs {
+                    @let is_author = current_user_id == Some(log.user_id.as_str());
+                    @let style = if log.is_hidden() {
+                        "background: var(--bg-tertiary); border: 2px solid var(--accent-muted); opacity: 0.5;"
+                    } else {
+                        "background: var(--bg-tertiary); border: 2px solid var(--accent-muted);"
+                    };
+                    div class="p-3" style=(style) {
+                        div class="flex items-center justify-between gap-4 mb-2" {
+                            div class="font-black text-sm" {
+                                span class=(kind_class(log.kind)) {
+                                    i class={"fa-solid " (log.kind.icon()) " mr-2"} {}
+                                    (log.kind.label())
+                                }
+                                span class="text-muted mono ml-2" {
+                                    (log.username.as_deref().unwrap_or("anonymous"))
+                                    " · " (log.created_at.format("%Y-%m-%d %H:%M UTC"))
+                                }
+                                @if log.is_hidden() {
+                                    span class="badge-brutal grey ml-2" { "HIDDEN" }
+                                }
+                            }
+                            div class="flex gap-2" {
+                                @if can_moderate {
+                                    @if log.is_hidden() {
+                                        (moderate_button(&format!("/api/logs/{}/restore", log.id), "fa-eye", "Show this log again?"))
+                                    } @else {
+                                        (moderate_button(&format!("/api/logs/{}/hide", log.id), "fa-eye-slash", "Hide this log?"))
+                                    }
+                                }
+                                @if is_author || is_admin {
+                                    button type="button" class="btn-brutal" style="padding: 0.25rem 0.5rem;"
+                                        hx-delete={"/api/logs/" (log.id)}
+                                        hx-confirm="Delete this log?"
+                                        hx-swap="none"
+                                        hx-on--after-request="if(event.detail.successful) window.location.reload()"
+                                        title="Delete log" {
+                                        i class="fa-solid fa-trash" {}
+                                    }
+                                }
+                            }
+                        }
+                        @if !log.text.is_empty() {
+                            p class="text-secondary font-bold" style="white-space: pre-line;" { (log.text) }
+                        }
+                        @let photos: Vec<&LocationLogPhoto> = log_book.photos.iter().filter(|p| p.log_id == log.id).collect();
+                        @if !photos.is_empty() {
+                            div class="grid grid-cols-3 gap-2 mt-3" {
+                                @for photo in photos {
+                                    img src={"/uploads/" (photo.file_path)}
+                                        alt="Log photo"
+                                        class="w-full h-24 object-cover cursor-pointer hover:opacity-90 transition-opacity"
+                                        style="border: 2px solid var(--accent-muted);"
+                                        onclick={"openPhotoViewer('/uploads/" (photo.file_path) "')"};
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Pagination
+            @if log_book.total_pages > 1 {
+                div class="flex justify-center items-center gap-2 mt-6" {
+                    @if log_book.page > 1 {
+                        a href={"?logs_page=" (log_book.page - 1) "#logbook"} class="btn-brutal" {
+                            i class="fa-solid fa-chevron-left mr-1" {}
+                            "NEWER"
+                        }
+                    }
+                    span class="px-4 py-2 font-bold mono text-secondary" {
+                        (log_book.page) " / " (log_book.total_pages)
+                    }
+                    @if log_book.page < log_book.total_pages {
+                        a href={"?logs_page=" (log_book.page + 1) "#logbook"} class="btn-brutal" {
+                            "OLDER"
+                            i class="fa-solid fa-chevron-right ml-1" {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn kind_class(kind: LogKind) -> &'static str {
+    match kind {
+        LogKind::Found => "text-primary",
+        LogKind::NotFound | LogKind::NeedsMaintenance => "text-highlight orange",
+        LogKind::Note => "text-secondary",
+    }
+}
+
+/// Icon button hiding or restoring a log
+fn moderate_button(url: &str, icon: &str, confirm: &str) -> Markup {
+    html! {
+        button type="button" class="btn-brutal" style="padding: 0.25rem 0.5rem;"
+            hx-post=(url)
+            hx-confirm=(confirm)
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload()"
+            title=(confirm) {
+            i class={"fa-solid " (icon)} {}
+        }
+    }
+}
+
+fn log_form_markup(location_id: &str) -> Markup {
+    html! {
+        form class="mt-4 space-y-4"
+            hx-post={"/api/locations/" (location_id) "/logs"}
+            hx-encoding="multipart/form-data"
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            div {
+                label for="log-kind" class="label-brutal" { "LOG" }
+                select id="log-kind" name="kind" class="input-brutal-box w-full" {
+                    @for kind in LogKind::ALL {
+                        option value=(kind.as_str()) { (kind.label()) }
+                    }
+                }
+            }
+            div {
+                label for="log-text" class="label-brutal" { "MESSAGE" }
+                textarea id="log-text" name="text" rows="3" maxlength="2000"
+                    class="input-brutal-box w-full" {}
+            }
+            div {
+                label for="log-photos" class="label-brutal" { "PHOTOS (UP TO 3)" }
+                input type="file" id="log-photos" name="photo" accept="image/*" multiple
+                    class="input-brutal-box w-full";
+            }
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-book mr-2" {}
+                "SAVE LOG"
+            }
+        }
+    }
+}
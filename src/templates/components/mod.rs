@@ -1,5 +1,20 @@
+mod badges;
 mod donation_invoice;
+mod hints;
+mod lightning_address;
+mod log_book;
+mod recurring_donations;
+mod schedule;
 
+pub use badges::badges_markup;
 pub use donation_invoice::{
     donation_invoice_markup, donation_invoice_script, DonationInvoiceConfig,
 };
+pub use hints::{hint_form_markup, hints_markup};
+pub use lightning_address::lightning_address_markup;
+pub use log_book::{log_book_markup, LogBook};
+pub use recurring_donations::recurring_donations_markup;
+pub use schedule::{
+    countdown_markup, countdown_script, schedule_form_markup, schedule_list_markup,
+    schedule_status_markup,
+};
@@ -0,0 +1,107 @@
+use super::super::format_sats_si;
+use crate::models::RecurringDonation;
+use maud::{html, Markup};
+
+/// Active recurring donations and the form to start one.
+/// `location_id` is None for global donations.
+pub fn recurring_donations_markup(
+    location_id: Option<&str>,
+    recurring: &[RecurringDonation],
+    current_user_id: Option<&str>,
+) -> Markup {
+    html! {
+        div class="card-brutal-inset mb-8" {
+            h2 class="heading-breaker orange" {
+                i class="fa-solid fa-rotate mr-2" {}
+                "RECURRING DONATIONS"
+            }
+
+            div class="mt-8 space-y-3" {
+                @if recurring.is_empty() {
+                    p class="text-muted font-bold" { "No recurring donations yet. Be the first!" }
+                } @else {
+                    @for r in recurring {
+                        @let is_mine = current_user_id == Some(r.user_id.as_str());
+                        div class="flex items-center justify-between gap-4 p-3" style="background: var(--bg-tertiary); border: 2px solid var(--accent-muted);" {
+                            div {
+                                span class="text-highlight orange font-black mono" {
+                                    (format_sats_si(r.amount_sats())) " "
+                                    i class="fa-solid fa-bolt" {}
+                                }
+                                span class="text-secondary font-bold" { " every " (r.interval_label()) }
+                                div class="text-xs text-muted font-bold mono mt-1" {
+                                    "SINCE " (r.created_at.format("%Y-%m-%d"))
+                                    @if is_mine {
+                                        " · NEXT " (r.next_payment_at.format("%Y-%m-%d %H:%M UTC"))
+                                    }
+                                }
+                                @if is_mine {
+                                    @if let Some(error) = &r.last_error {
+                                        div class="text-xs text-highlight orange font-bold mt-1" {
+                                            "LAST PAYMENT FAILED: " (error)
+                                        }
+                                    }
+                                }
+                            }
+                            @if is_mine {
+                                button type="button" class="btn-brutal"
+                                    hx-post={"/api/recurring-donations/" (r.id) "/cancel"}
+                                    hx-swap="none"
+                                    hx-confirm="Cancel this recurring donation?"
+                                    hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                                    i class="fa-solid fa-xmark mr-2" {}
+                                    "CANCEL"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            details class="mt-6" {
+                summary class="font-black text-primary cursor-pointer select-none hover:text-highlight" {
+                    i class="fa-solid fa-plus mr-2" {}
+                    "START A RECURRING DONATION"
+                }
+                form class="mt-4 space-y-4"
+                    hx-post="/api/recurring-donations"
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert('Could not start the recurring donation. Check the amount and wallet connection.')" {
+                    input type="hidden" name="location_id" value=(location_id.unwrap_or_default());
+                    div class="grid md:grid-cols-2 gap-4" {
+                        div {
+                            label class="label-brutal" for={"recurringAmount" (location_id.unwrap_or_default())} { "AMOUNT (SATS)" }
+                            input type="number" id={"recurringAmount" (location_id.unwrap_or_default())}
+                                name="amount_sats" required min="1" max="1000000" value="100"
+                                class="input-brutal-box w-full";
+                        }
+                        div {
+                            label class="label-brutal" for={"recurringInterval" (location_id.unwrap_or_default())} { "EVERY" }
+                            select id={"recurringInterval" (location_id.unwrap_or_default())} name="interval_days"
+                                class="input-brutal-box w-full" {
+                                option value="1" { "Day" }
+                                option value="7" selected { "Week" }
+                                option value="30" { "Month" }
+                            }
+                        }
+                    }
+                    div {
+                        label class="label-brutal" for={"recurringNwc" (location_id.unwrap_or_default())} { "NOSTR WALLET CONNECT URI" }
+                        input type="password" id={"recurringNwc" (location_id.unwrap_or_default())}
+                            name="nwc_uri" required autocomplete="off"
+                            class="input-brutal-box w-full mono"
+                            placeholder="nostr+walletconnect://...";
+                        p class="text-xs text-muted font-bold mt-2" {
+                            "Create a connection with a payment budget in your wallet (Alby, Mutiny, ...). "
+                            "We ask your wallet to pay each donation. Cancel any time."
+                        }
+                    }
+                    button type="submit" class="btn-brutal-fill w-full" {
+                        i class="fa-solid fa-rotate mr-2" {}
+                        "START"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::models::{Recurrence, Schedule};
+use crate::schedule;
+use chrono::{DateTime, Utc};
+use maud::{html, Markup, PreEscaped};
+
+/// Live countdown to `to`, kept up to date by `countdown_script`
+pub fn countdown_markup(to: DateTime<Utc>) -> Markup {
+    html! {
+        span class="mono" data-countdown=(to.to_rfc3339()) {
+            (format_remaining(to - Utc::now()))
+        }
+    }
+}
+
+/// Script that ticks every `data-countdown` element once a second.
+/// `updateCountdowns()` can be called again for elements added later.
+pub fn countdown_script() -> Markup {
+    PreEscaped(
+        r#"
+        <script>
+            function updateCountdowns() {
+                document.querySelectorAll('[data-countdown]').forEach(el => {
+                    const seconds = Math.max(0, Math.floor((new Date(el.dataset.countdown) - Date.now()) / 1000));
+                    const days = Math.floor(seconds / 86400);
+                    const pad = n => String(n).padStart(2, '0');
+                    const clock = pad(Math.floor(seconds % 86400 / 3600)) + ':' + pad(Math.floor(seconds % 3600 / 60)) + ':' + pad(seconds % 60);
+                    el.textContent = days > 0 ? days + 'd ' + clock : clock;
+                });
+            }
+            updateCountdowns();
+            setInterval(updateCountdowns, 1000);
+        </script>
+        "#
+        .to_string(),
+    )
+}
+
+/// Same format as the countdown script, for the first render
+fn format_remaining(remaining: chrono::Duration) -> String {
+    let seconds = remaining.num_seconds().max(0);
+    let clock = format!(
+        "{:02}:{:02}:{:02}",
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    );
+    match seconds / 86400 {
+        0 => clock,
+        days => format!("{}d {}", days, clock),
+    }
+}
+
+/// Whether the schedules are open right now, with a countdown to the next change
+pub fn schedule_status_markup(schedules: &[Schedule]) -> Markup {
+    let now = Utc::now();
+    html! {
+        @match schedule::current_or_next_window(schedules, now) {
+            Some(window) if window.contains(now) => {
+                div class="alert-brutal green success" {
+                    i class="fa-solid fa-door-open mr-2" {}
+                    "OPEN NOW · CLOSES IN " (countdown_markup(window.ends_at))
+                }
+            }
+            Some(window) => {
+                div class="alert-brutal orange" {
+                    i class="fa-solid fa-clock mr-2" {}
+                    "CLOSED · OPENS IN " (countdown_markup(window.starts_at))
+                }
+            }
+            None => {
+                div class="alert-brutal orange" {
+                    i class="fa-solid fa-door-closed mr-2" {}
+                    "CLOSED · NO UPCOMING WINDOWS"
+                }
+            }
+        }
+    }
+}
+
+/// Schedules with their windows, and a remove button where `can_remove` allows it
+pub fn schedule_list_markup(
+    schedules: &[Schedule],
+    can_remove: impl Fn(&Schedule) -> bool,
+) -> Markup {
+    html! {
+        div class="space-y-2" {
+            @for s in schedules {
+                div class="flex justify-between items-center gap-4 text-sm font-bold text-secondary" {
+                    div {
+                        span class="mono" { (window_label(s)) }
+                        span class="badge-brutal grey ml-2" { (recurrence_label(s.recurrence)) }
+                        @if s.hunt_id.is_some() {
+                            span class="badge-brutal grey ml-2" { "HUNT" }
+                        }
+                    }
+                    @if can_remove(s) {
+                        button type="button" class="btn-brutal" style="padding: 0.25rem 0.5rem;"
+                            hx-delete={"/api/schedules/" (s.id)}
+                            hx-confirm="Remove this schedule?"
+                            hx-swap="none"
+                            hx-on--after-request="if(event.detail.successful) window.location.reload()"
+                            title="Remove schedule" {
+                            i class="fa-solid fa-trash" {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Form adding a schedule through `post_url`. Times are entered in the
+/// browser's local time zone, which is sent along as `tz_offset`.
+pub fn schedule_form_markup(post_url: &str) -> Markup {
+    html! {
+        form class="space-y-4"
+            hx-post=(post_url)
+            hx-vals="js:{tz_offset: new Date().getTimezoneOffset()}"
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            div class="grid md:grid-cols-3 gap-4" {
+                div {
+                    label class="label-brutal" { "OPENS" }
+                    input type="datetime-local" name="starts_at" required
+                        class="input-brutal-box w-full";
+                }
+                div {
+                    label class="label-brutal" { "CLOSES" }
+                    input type="datetime-local" name="ends_at" required
+                        class="input-brutal-box w-full";
+                }
+                div {
+                    label class="label-brutal" { "REPEAT" }
+                    select name="recurrence" class="input-brutal-box w-full" {
+                        option value="once" selected { "ONCE" }
+                        option value="daily" { "DAILY" }
+                        option value="weekly" { "WEEKLY" }
+                    }
+                }
+            }
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-calendar-plus mr-2" {}
+                "ADD SCHEDULE"
+            }
+        }
+    }
+}
+
+fn window_label(s: &Schedule) -> String {
+    let starts = match s.recurrence {
+        Recurrence::Once => s.starts_at.format("%a %Y-%m-%d %H:%M"),
+        Recurrence::Daily => s.starts_at.format("%H:%M"),
+        Recurrence::Weekly => s.starts_at.format("%a %H:%M"),
+    };
+    let ends = if s.ends_at.date_naive() == s.starts_at.date_naive() {
+        s.ends_at.format("%H:%M")
+    } else {
+        s.ends_at.format("%a %H:%M")
+    };
+    format!("{} - {} UTC", starts, ends)
+}
+
+fn recurrence_label(recurrence: Recurrence) -> &'static str {
+    match recurrence {
+        Recurrence::Once => "ONCE",
+        Recurrence::Daily => "DAILY",
+        Recurrence::Weekly => "WEEKLY",
+    }
+}
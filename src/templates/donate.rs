@@ -1,21 +1,34 @@
 use super::format_sats_si;
-use crate::models::Donation;
+use crate::models::{Donation, DonationMatch, MatchingCampaign, RecurringDonation};
 use crate::templates::components::{
-    donation_invoice_markup, donation_invoice_script, DonationInvoiceConfig,
+    donation_invoice_markup, donation_invoice_script, lightning_address_markup,
+    recurring_donations_markup, DonationInvoiceConfig,
 };
 use maud::{html, Markup};
 
 /// pool_balance_sats: total pool balance across all locations in sats
 /// num_locations: number of active locations
 /// received_donations: list of received donations for display
+/// campaigns: active matching campaigns
+/// matches: sats campaigns added on top of received donations
+/// recurring: active recurring donations to all locations
+/// current_user_id: the visitor, who can cancel their own recurring donations
+/// ln_address: Lightning address for donations to all locations
+#[allow(clippy::too_many_arguments)] // All parameters are needed for the template
 pub fn donate(
     pool_balance_sats: i64,
     num_locations: usize,
     received_donations: &[Donation],
+    campaigns: &[MatchingCampaign],
+    matches: &[DonationMatch],
+    recurring: &[RecurringDonation],
+    current_user_id: &str,
+    ln_address: Option<&str>,
 ) -> Markup {
     let config = DonationInvoiceConfig {
         id_prefix: "",
         location_id: None,
+        hunt_id: None,
         amounts: &[
             ("1000", "1K sats"),
             ("5000", "5K sats"),
@@ -52,6 +65,18 @@ pub fn donate(
             }
         }
 
+        // Active matching campaigns
+        @if !campaigns.is_empty() {
+            div class="card-brutal-inset mb-8" {
+                h2 class="heading-breaker orange" { "Donations Are Being Matched" }
+                div class="mt-6 space-y-4" {
+                    @for campaign in campaigns {
+                        (campaign_banner(campaign))
+                    }
+                }
+            }
+        }
+
         // Donation form
         div class="card-brutal-inset" {
             h2 class="heading-breaker orange" { "Make a Donation" }
@@ -59,8 +84,14 @@ pub fn donate(
             div id="donationContainer" class="mt-8" {
                 (donation_invoice_markup(&config))
             }
+
+            @if let Some(address) = ln_address {
+                (lightning_address_markup(address))
+            }
         }
 
+        (recurring_donations_markup(None, recurring, Some(current_user_id)))
+
         // How it works
         div class="card-bar mt-8" {
             h2 class="text-2xl font-black mb-6" { "How It Works" }
@@ -86,6 +117,9 @@ pub fn donate(
                             tr class="border-b-2 border-tertiary" {
                                 th class="text-left py-2 px-3 font-black text-muted" { "Time" }
                                 th class="text-right py-2 px-3 font-black text-muted" { "Amount" }
+                                @if !matches.is_empty() {
+                                    th class="text-right py-2 px-3 font-black text-muted" { "Matched" }
+                                }
                             }
                         }
                         tbody {
@@ -95,11 +129,27 @@ pub fn donate(
                                         @if let Some(received_at) = donation.received_at {
                                             (received_at.format("%Y-%m-%d %H:%M UTC"))
                                         }
+                                        @if let Some(comment) = &donation.comment {
+                                            div class="text-sm mt-1" {
+                                                i class="fa-solid fa-comment mr-1 text-muted" {}
+                                                (comment)
+                                            }
+                                        }
                                     }
                                     td class="py-2 px-3 text-right font-bold text-highlight orange" {
                                         (format_sats_si(donation.amount_sats())) " "
                                         i class="fa-solid fa-bolt" {}
                                     }
+                                    @if !matches.is_empty() {
+                                        td class="py-2 px-3 text-right text-secondary font-bold" {
+                                            @for m in matches.iter().filter(|m| m.donation_id == donation.id) {
+                                                div {
+                                                    "+" (format_sats_si(m.sats())) " "
+                                                    span class="text-muted" { "by " (m.sponsor_name) }
+                                                }
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -111,3 +161,37 @@ pub fn donate(
         (donation_invoice_script(&config))
     }
 }
+
+fn campaign_banner(campaign: &MatchingCampaign) -> Markup {
+    html! {
+        div class="card-brutal" {
+            div class="flex justify-between items-start gap-4" {
+                div {
+                    h3 class="text-xl font-black text-primary" { (campaign.name) }
+                    p class="text-secondary font-bold mt-1" {
+                        (campaign.sponsor_name) " matches "
+                        @if campaign.match_percent == 100 {
+                            "every sat"
+                        } @else {
+                            (campaign.match_percent) "% of every donation"
+                        }
+                        @if let Some(max) = campaign.max_match_msats {
+                            " (up to " (format_sats_si(max / 1000)) " sats per donation)"
+                        }
+                        @if campaign.has_region() {
+                            " to locations in the campaign region"
+                        }
+                        " until " (campaign.ends_at.format("%Y-%m-%d %H:%M UTC"))
+                    }
+                }
+                div class="text-right" {
+                    div class="text-2xl font-black text-highlight orange" style="white-space: nowrap;" {
+                        (format_sats_si(campaign.remaining_sats())) " "
+                        i class="fa-solid fa-bolt" {}
+                    }
+                    div class="text-sm text-muted font-bold" { "left to match" }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use super::format_sats_si;
+use crate::disclosure::Position;
+use crate::models::{Hunt, HuntCompletion, Location, Schedule};
+use crate::templates::components::{
+    countdown_script, donation_invoice_markup, donation_invoice_script, schedule_list_markup,
+    schedule_status_markup, DonationInvoiceConfig,
+};
+use maud::{html, Markup, PreEscaped};
+
+/// List of all hunts
+/// hunts is a slice of (hunt, number_of_stops, pool_sats)
+pub fn hunts(hunts: &[(Hunt, usize, i64)]) -> Markup {
+    html! {
+        h1 class="text-4xl font-black mb-8 text-primary" style="letter-spacing: -0.02em;" {
+            i class="fa-solid fa-flag-checkered mr-2" {}
+            "HUNTS"
+        }
+
+        div class="alert-brutal mb-8" {
+            p class="text-sm font-bold" {
+                "CLAIM EVERY LOCATION ON A TRAIL TO WIN ITS COMPLETION BONUS."
+            }
+        }
+
+        @if hunts.is_empty() {
+            div class="card-brutal-inset text-center" style="padding: 3rem;" {
+                div class="text-6xl mb-6 text-muted" {
+                    i class="fa-solid fa-route" {}
+                }
+                h3 class="text-2xl font-black text-primary mb-3" { "NO HUNTS YET" }
+                p class="text-secondary font-bold" {
+                    "CHECK BACK SOON, OR EXPLORE THE "
+                    a href="/map" class="text-highlight orange" { "MAP" }
+                    "."
+                }
+            }
+        } @else {
+            div class="grid gap-4" {
+                @for (hunt, stops, pool_sats) in hunts {
+                    a href={"/hunts/" (hunt.id)} class="block card-brutal transition hover:bg-elevated" {
+                        div class="flex justify-between items-start gap-4" {
+                            div class="flex-1" {
+                                h3 class="text-xl font-black text-primary mb-2" { (hunt.name) }
+                                @if let Some(desc) = &hunt.description {
+                                    p class="text-secondary text-sm mb-2 font-bold" { (desc) }
+                                }
+                                p class="text-muted text-sm mono" {
+                                    i class="fa-solid fa-route mr-1" {}
+                                    (stops) " STOPS"
+                                    @if hunt.ordered { " IN ORDER" }
+                                }
+                            }
+                            div class="text-right" {
+                                div class="text-2xl font-black text-highlight orange" {
+                                    (format_sats_si(hunt.completion_bonus_sats())) " "
+                                    i class="fa-solid fa-bolt" {}
+                                }
+                                div class="text-muted text-sm mono" {
+                                    "POOL: " (format_sats_si(*pool_sats)) " SATS"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Hunt page with the route, the visitor's progress, the bonus pool and the leaderboard
+/// progress: whether the visitor has done each stop, in trail order
+pub fn hunt_detail(
+    hunt: &Hunt,
+    stops: &[Location],
+    positions: &[Position],
+    progress: &[bool],
+    pool_sats: i64,
+    completions: &[HuntCompletion],
+    current_user_id: &str,
+    schedules: &[Schedule],
+) -> Markup {
+    let done_count = progress.iter().filter(|done| **done).count();
+    let completed = completions.iter().any(|c| c.user_id == current_user_id);
+    let config = DonationInvoiceConfig {
+        hunt_id: Some(&hunt.id),
+        amounts: &[
+            ("1000", "1K sats"),
+            ("5000", "5K sats"),
+            ("10000", "10K sats"),
+            ("custom", "Custom"),
+        ],
+        label: Some("Add to the bonus pool"),
+        ..Default::default()
+    };
+
+    html! {
+        h1 class="text-4xl font-black mb-2 text-primary" style="letter-spacing: -0.02em;" {
+            i class="fa-solid fa-flag-checkered mr-2" {}
+            (hunt.name)
+        }
+        @if let Some(desc) = &hunt.description {
+            p class="text-secondary font-bold mb-8" { (desc) }
+        }
+
+        div class="grid md:grid-cols-3 gap-4 mb-8" {
+            div class="stat-brutal" {
+                div class="stat-value orange" {
+                    (format_sats_si(hunt.completion_bonus_sats())) " "
+                    i class="fa-solid fa-bolt" {}
+                }
+                div class="stat-label" { "completion bonus" }
+            }
+            div class="stat-brutal" {
+                div class="stat-value" {
+                    (format_sats_si(pool_sats)) " "
+                    i class="fa-solid fa-bolt" {}
+                }
+                div class="stat-label" { "in the bonus pool" }
+            }
+            div class="stat-brutal" {
+                div class="stat-value" { (done_count) " / " (stops.len()) }
+                div class="stat-label" { "stops done by you" }
+            }
+        }
+
+        @if completed {
+            div class="alert-brutal orange mb-8" {
+                p class="text-sm font-bold" {
+                    i class="fa-solid fa-trophy mr-2" {}
+                    "YOU COMPLETED THIS HUNT!"
+                }
+            }
+        } @else if hunt.ordered {
+            div class="alert-brutal mb-8" {
+                p class="text-sm font-bold" {
+                    "CLAIM THE STOPS IN ORDER. CLAIMS OUT OF ORDER DON'T COUNT."
+                }
+            }
+        }
+
+        @if !schedules.is_empty() {
+            div class="card-brutal-inset mb-8" {
+                h2 class="heading-breaker" { "SCHEDULE" }
+                div class="mt-4 mb-4" {
+                    (schedule_status_markup(schedules))
+                }
+                (schedule_list_markup(schedules, |_| false))
+            }
+            (countdown_script())
+        }
+
+        // Route map
+        div id="map" class="w-full h-96 mb-8" style="border: 3px solid var(--accent-border);" {}
+
+        // Stops
+        div class="card-brutal-inset mb-8" {
+            h2 class="heading-breaker" { "STOPS" }
+            div class="grid gap-4" {
+                @for (i, location) in stops.iter().enumerate() {
+                    a href={"/locations/" (location.id)}
+                        class="block card-brutal transition hover:bg-elevated" {
+                        div class="flex justify-between items-center gap-4" {
+                            div class="flex items-center gap-4" {
+                                span class="text-2xl font-black text-muted mono" { (i + 1) }
+                                div {
+                                    h3 class="text-xl font-black text-primary" { (location.name) }
+                                    p class="text-muted text-sm mono" {
+                                        i class="fa-solid fa-location-dot mr-1" {}
+                                        (positions.get(i).copied().unwrap_or(Position::Hidden).label())
+                                    }
+                                }
+                            }
+                            @if progress.get(i).copied().unwrap_or(false) {
+                                span class="badge-brutal orange" {
+                                    i class="fa-solid fa-check mr-1" {}
+                                    "DONE"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Leaderboard
+        div class="card-brutal-inset mb-8" {
+            h2 class="heading-breaker orange" { "LEADERBOARD" }
+            @if completions.is_empty() {
+                p class="text-center text-muted font-bold py-6" {
+                    "NOBODY HAS COMPLETED THIS HUNT YET. BE THE FIRST!"
+                }
+            } @else {
+                div class="mt-6 overflow-x-auto" {
+                    table class="w-full" {
+                        thead {
+                            tr class="border-b-2 border-tertiary" {
+                                th class="text-left py-2 px-3 font-black text-muted" { "#" }
+                                th class="text-left py-2 px-3 font-black text-muted" { "Hunter" }
+                                th class="text-left py-2 px-3 font-black text-muted" { "Completed" }
+                                th class="text-right py-2 px-3 font-black text-muted" { "Bonus" }
+                            }
+                        }
+                        tbody {
+                            @for (rank, completion) in completions.iter().enumerate() {
+                                tr class="border-b border-tertiary hover:bg-tertiary" {
+                                    td class="py-2 px-3 font-black mono" { (rank + 1) }
+                                    td class="py-2 px-3 font-bold text-primary" {
+                                        (completion.display_name())
+                                        @if completion.user_id == current_user_id {
+                                            span class="text-muted" { " (you)" }
+                                        }
+                                    }
+                                    td class="py-2 px-3 text-secondary" {
+                                        (completion.completed_at.format("%Y-%m-%d %H:%M UTC"))
+                                    }
+                                    td class="py-2 px-3 text-right font-bold text-highlight orange" {
+                                        (format_sats_si(completion.bonus_sats())) " "
+                                        i class="fa-solid fa-bolt" {}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Donation form
+        div class="card-brutal-inset" {
+            h2 class="heading-breaker orange" { "Fund the Bonus" }
+            p class="text-secondary font-bold mt-4" {
+                "Donations to this hunt pay the bonus of everyone who completes it."
+            }
+            div id="donationContainer" class="mt-6" {
+                (donation_invoice_markup(&config))
+            }
+        }
+
+        (donation_invoice_script(&config))
+
+        (route_map_script(stops, positions, progress, hunt.ordered))
+    }
+}
+
+/// Map of the stops, connected in trail order for ordered hunts. Stops with a
+/// search area are drawn as circles and hidden stops are left out.
+fn route_map_script(
+    stops: &[Location],
+    positions: &[Position],
+    progress: &[bool],
+    ordered: bool,
+) -> Markup {
+    let stops_json = serde_json::Value::Array(
+        stops
+            .iter()
+            .enumerate()
+            .filter_map(|(i, location)| {
+                let position = positions.get(i)?;
+                let (latitude, longitude) = position.center()?;
+                Some(serde_json::json!({
+                    "id": location.id,
+                    "name": location.name,
+                    "number": i + 1,
+                    "latitude": latitude,
+                    "longitude": longitude,
+                    "radius_m": position.radius_m(),
+                    "done": progress.get(i).copied().unwrap_or(false),
+                }))
+            })
+            .collect(),
+    );
+
+    PreEscaped(format!(
+        r#"
+        <script>
+            const stops = {stops_json};
+            const ordered = {ordered};
+
+            const map = new maplibregl.Map({{
+                container: 'map',
+                style: 'https://tiles.openfreemap.org/styles/positron',
+                center: [-122.4194, 37.7749],
+                zoom: 12
+            }});
+
+            map.addControl(new maplibregl.NavigationControl());
+
+            const bounds = new maplibregl.LngLatBounds();
+            stops.forEach(stop => bounds.extend([stop.longitude, stop.latitude]));
+
+            function circlePolygon(lng, lat, radiusM) {{
+                const ring = [];
+                for (let i = 0; i <= 64; i++) {{
+                    const angle = i / 64 * 2 * Math.PI;
+                    ring.push([
+                        lng + radiusM * Math.sin(angle) / (111320 * Math.cos(lat * Math.PI / 180)),
+                        lat + radiusM * Math.cos(angle) / 111320
+                    ]);
+                }}
+                return [ring];
+            }}
+
+            map.on('load', () => {{
+                map.addSource('areas', {{
+                    type: 'geojson',
+                    data: {{
+                        type: 'FeatureCollection',
+                        features: stops.filter(stop => stop.radius_m !== null).map(stop => ({{
+                            type: 'Feature',
+                            geometry: {{ type: 'Polygon', coordinates: circlePolygon(stop.longitude, stop.latitude, stop.radius_m) }}
+                        }}))
+                    }}
+                }});
+                map.addLayer({{
+                    id: 'search-areas',
+                    type: 'fill',
+                    source: 'areas',
+                    paint: {{
+                        'fill-color': '#F7931A',
+                        'fill-opacity': 0.2
+                    }}
+                }});
+
+                if (ordered && stops.length > 1) {{
+                    map.addSource('route', {{
+                        type: 'geojson',
+                        data: {{
+                            type: 'Feature',
+                            geometry: {{
+                                type: 'LineString',
+                                coordinates: stops.map(stop => [stop.longitude, stop.latitude])
+                            }}
+                        }}
+                    }});
+                    map.addLayer({{
+                        id: 'route',
+                        type: 'line',
+                        source: 'route',
+                        paint: {{
+                            'line-color': '#F7931A',
+                            'line-width': 3,
+                            'line-dasharray': [2, 2]
+                        }}
+                    }});
+                }}
+
+                stops.forEach(stop => {{
+                    const el = document.createElement('div');
+                    el.textContent = stop.number;
+                    el.style.cssText = 'width: 28px; height: 28px; border-radius: 50%; border: 2px solid #fff; '
+                        + 'display: flex; align-items: center; justify-content: center; font-weight: bold; color: #fff; '
+                        + 'background: ' + (stop.done ? '#6B9B6B' : '#F7931A') + ';';
+
+                    const popup = new maplibregl.Popup({{ offset: 15 }}).setHTML(`
+                        <div style="color: #0f172a; padding: 8px;">
+                            <h3 style="font-weight: bold; margin-bottom: 4px;">${{stop.number}}. ${{stop.name}}</h3>
+                            ${{stop.radius_m !== null ? `<p style="margin: 4px 0; font-size: 0.9em; color: #666;">Somewhere within ${{stop.radius_m}} m</p>` : ''}}
+                            <a href="/locations/${{stop.id}}" style="color: #3b82f6; text-decoration: underline;">View details</a>
+                        </div>
+                    `);
+
+                    new maplibregl.Marker({{ element: el }})
+                        .setLngLat([stop.longitude, stop.latitude])
+                        .setPopup(popup)
+                        .addTo(map);
+                }});
+
+                if (stops.length > 0) {{
+                    map.fitBounds(bounds, {{ padding: 50, animate: false }});
+                }}
+            }});
+        </script>
+        "#,
+        stops_json = stops_json,
+        ordered = ordered,
+    ))
+}
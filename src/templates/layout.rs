@@ -139,6 +139,16 @@ fn navbar(
                                     "MAP"
                                 }
                             }
+                            li {
+                                a href="/hunts" class="text-primary transition hover:text-highlight font-bold" {
+                                    "HUNTS"
+                                }
+                            }
+                            li {
+                                a href="/leaderboard" class="text-primary transition hover:text-highlight font-bold" {
+                                    "LEADERBOARD"
+                                }
+                            }
                             li {
                                 a href="/donate" class="text-highlight transition hover:text-primary font-bold orange" {
                                     i class="fa-solid fa-coins mr-2" {}
@@ -168,6 +178,12 @@ fn navbar(
                                         i class="fa-solid fa-wallet w-4" {}
                                         "WALLET"
                                     }
+                                    @if is_registered {
+                                        a href="/team" class="flex items-center gap-2 px-4 py-2 text-primary text-sm font-bold hover:bg-elevated hover:text-highlight" style="border-bottom: none;" {
+                                            i class="fa-solid fa-users w-4" {}
+                                            "TEAM"
+                                        }
+                                    }
                                     @if can_create_locations {
                                         a href="/locations" class="flex items-center gap-2 px-4 py-2 text-primary text-sm font-bold hover:bg-elevated hover:text-highlight" style="border-bottom: none;" {
                                             i class="fa-solid fa-location-dot w-4" {}
@@ -192,6 +208,26 @@ fn navbar(
                                             i class="fa-solid fa-barcode w-4" {}
                                             "SCAN LOG"
                                         }
+                                        a href="/admin/ledger" class="flex items-center gap-2 px-4 py-2 text-highlight text-sm font-bold hover:bg-elevated orange" style="border-bottom: none;" {
+                                            i class="fa-solid fa-scale-balanced w-4" {}
+                                            "LEDGER"
+                                        }
+                                        a href="/admin/solvency" class="flex items-center gap-2 px-4 py-2 text-highlight text-sm font-bold hover:bg-elevated orange" style="border-bottom: none;" {
+                                            i class="fa-solid fa-vault w-4" {}
+                                            "SOLVENCY"
+                                        }
+                                        a href="/admin/campaigns" class="flex items-center gap-2 px-4 py-2 text-highlight text-sm font-bold hover:bg-elevated orange" style="border-bottom: none;" {
+                                            i class="fa-solid fa-handshake w-4" {}
+                                            "CAMPAIGNS"
+                                        }
+                                        a href="/admin/hunts" class="flex items-center gap-2 px-4 py-2 text-highlight text-sm font-bold hover:bg-elevated orange" style="border-bottom: none;" {
+                                            i class="fa-solid fa-flag-checkered w-4" {}
+                                            "HUNTS"
+                                        }
+                                        a href="/admin/withdrawals" class="flex items-center gap-2 px-4 py-2 text-highlight text-sm font-bold hover:bg-elevated orange" style="border-bottom: none;" {
+                                            i class="fa-solid fa-hand w-4" {}
+                                            "WITHDRAWALS"
+                                        }
                                     }
                                 }
                                 // Separator and auth options
@@ -249,6 +285,16 @@ fn navbar(
                                 "MAP"
                             }
                         }
+                        li {
+                            a href="/hunts" class="block py-3 text-primary font-bold hover:text-highlight" style="border-bottom: none;" {
+                                "HUNTS"
+                            }
+                        }
+                        li {
+                            a href="/leaderboard" class="block py-3 text-primary font-bold hover:text-highlight" style="border-bottom: none;" {
+                                "LEADERBOARD"
+                            }
+                        }
                         li {
                             a href="/donate" class="block py-3 text-highlight font-bold hover:text-primary orange" style="border-bottom: none;" {
                                 i class="fa-solid fa-coins mr-2" {}
@@ -270,6 +316,12 @@ fn navbar(
                                 i class="fa-solid fa-wallet w-5" {}
                                 "WALLET"
                             }
+                            @if is_registered {
+                                a href="/team" class="flex items-center gap-2 py-2 px-3 text-primary font-bold hover:text-highlight hover:bg-tertiary" style="border-bottom: none;" {
+                                    i class="fa-solid fa-users w-5" {}
+                                    "TEAM"
+                                }
+                            }
                             @if can_create_locations {
                                 a href="/locations" class="flex items-center gap-2 py-2 px-3 text-primary font-bold hover:text-highlight hover:bg-tertiary" style="border-bottom: none;" {
                                     i class="fa-solid fa-location-dot w-5" {}
@@ -294,6 +346,26 @@ fn navbar(
                                     i class="fa-solid fa-barcode w-5" {}
                                     "SCAN LOG"
                                 }
+                                a href="/admin/ledger" class="flex items-center gap-2 py-2 px-3 text-highlight font-bold hover:bg-tertiary orange" style="border-bottom: none;" {
+                                    i class="fa-solid fa-scale-balanced w-5" {}
+                                    "LEDGER"
+                                }
+                                a href="/admin/solvency" class="flex items-center gap-2 py-2 px-3 text-highlight font-bold hover:bg-tertiary orange" style="border-bottom: none;" {
+                                    i class="fa-solid fa-vault w-5" {}
+                                    "SOLVENCY"
+                                }
+                                a href="/admin/campaigns" class="flex items-center gap-2 py-2 px-3 text-highlight font-bold hover:bg-tertiary orange" style="border-bottom: none;" {
+                                    i class="fa-solid fa-handshake w-5" {}
+                                    "CAMPAIGNS"
+                                }
+                                a href="/admin/hunts" class="flex items-center gap-2 py-2 px-3 text-highlight font-bold hover:bg-tertiary orange" style="border-bottom: none;" {
+                                    i class="fa-solid fa-flag-checkered w-5" {}
+                                    "HUNTS"
+                                }
+                                a href="/admin/withdrawals" class="flex items-center gap-2 py-2 px-3 text-highlight font-bold hover:bg-tertiary orange" style="border-bottom: none;" {
+                                    i class="fa-solid fa-hand w-5" {}
+                                    "WITHDRAWALS"
+                                }
                             }
                         }
                         // Auth options
@@ -0,0 +1,271 @@
+use super::format_sats_si;
+use crate::leaderboard::{Metric, Period};
+use crate::models::{LeaderboardEntry, Region, TeamLeaderboardEntry};
+use chrono::{DateTime, Utc};
+use maud::{html, Markup};
+
+/// Which leaderboard is shown; `region_id` is empty for the global one
+pub struct LeaderboardSelection<'a> {
+    pub period: Period,
+    pub metric: Metric,
+    pub region_id: &'a str,
+    /// Whether teams are ranked instead of hunters
+    pub teams: bool,
+}
+
+impl LeaderboardSelection<'_> {
+    fn url(&self, period: Period, metric: Metric, region_id: &str) -> String {
+        self.url_for(period, metric, region_id, self.teams)
+    }
+
+    fn url_for(&self, period: Period, metric: Metric, region_id: &str, teams: bool) -> String {
+        let mut url = format!("/leaderboard?period={}&metric={}", period, metric);
+        if !region_id.is_empty() {
+            url.push_str("&region=");
+            url.push_str(&urlencoding::encode(region_id));
+        }
+        if teams {
+            url.push_str("&teams=true");
+        }
+        url
+    }
+}
+
+/// A row of the ranking table
+struct Row<'a> {
+    rank: i64,
+    name: &'a str,
+    value: i64,
+    /// The viewer or the viewer's team
+    is_own: bool,
+}
+
+/// Leaderboard page with hunters/teams, period, metric and region tabs.
+/// Shows `team_entries` if `selection.teams`, otherwise `entries`.
+/// opt_in is whether an anonymous visitor is ranked, None for registered users
+/// and visitors who haven't found anything yet.
+#[allow(clippy::too_many_arguments)] // All parameters are needed for the template
+pub fn leaderboard(
+    selection: &LeaderboardSelection,
+    entries: &[LeaderboardEntry],
+    team_entries: &[TeamLeaderboardEntry],
+    regions: &[Region],
+    since: DateTime<Utc>,
+    current_user_id: &str,
+    current_team_id: Option<&str>,
+    opt_in: Option<bool>,
+    is_admin: bool,
+) -> Markup {
+    let tab = |selected: bool| {
+        if selected {
+            "btn-brutal-fill"
+        } else {
+            "btn-brutal"
+        }
+    };
+    let (rows, computed_at): (Vec<Row>, _) = if selection.teams {
+        let rows = team_entries
+            .iter()
+            .map(|e| Row {
+                rank: e.rank,
+                name: &e.name,
+                value: e.value,
+                is_own: current_team_id == Some(e.team_id.as_str()),
+            })
+            .collect();
+        (rows, team_entries.first().map(|e| e.computed_at))
+    } else {
+        let rows = entries
+            .iter()
+            .map(|e| Row {
+                rank: e.rank,
+                name: &e.display_name,
+                value: e.value,
+                is_own: e.user_id == current_user_id,
+            })
+            .collect();
+        (rows, entries.first().map(|e| e.computed_at))
+    };
+
+    html! {
+        h1 class="text-4xl font-black mb-8 text-primary" style="letter-spacing: -0.02em;" {
+            i class="fa-solid fa-trophy mr-2" {}
+            "LEADERBOARD"
+        }
+
+        @if let Some(opt_in) = opt_in {
+            div class="alert-brutal mb-8 flex justify-between items-center gap-4" {
+                p class="text-sm font-bold" {
+                    @if opt_in {
+                        "YOU ARE RANKED AS AN ANONYMOUS HUNTER."
+                    } @else {
+                        "ANONYMOUS HUNTERS ARE ONLY RANKED IF THEY OPT IN."
+                    }
+                }
+                form hx-post="/api/leaderboard/opt-in"
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                    input type="hidden" name="opt_in" value=(!opt_in);
+                    button type="submit" class="btn-brutal" {
+                        @if opt_in { "LEAVE LEADERBOARDS" } @else { "JOIN LEADERBOARDS" }
+                    }
+                }
+            }
+        }
+
+        // Tabs
+        div class="flex flex-wrap gap-2 mb-4" {
+            a href=(selection.url_for(selection.period, selection.metric, selection.region_id, false))
+                class=(tab(!selection.teams)) {
+                i class="fa-solid fa-user mr-2" {}
+                "HUNTERS"
+            }
+            a href=(selection.url_for(selection.period, selection.metric, selection.region_id, true))
+                class=(tab(selection.teams)) {
+                i class="fa-solid fa-users mr-2" {}
+                "TEAMS"
+            }
+        }
+        div class="flex flex-wrap gap-2 mb-4" {
+            @for period in Period::ALL {
+                a href=(selection.url(period, selection.metric, selection.region_id))
+                    class=(tab(period == selection.period)) { (period.label()) }
+            }
+        }
+        div class="flex flex-wrap gap-2 mb-4" {
+            @for metric in Metric::ALL {
+                a href=(selection.url(selection.period, metric, selection.region_id))
+                    class=(tab(metric == selection.metric)) { (metric.label()) }
+            }
+        }
+        @if !regions.is_empty() {
+            div class="flex flex-wrap gap-2 mb-4" {
+                a href=(selection.url(selection.period, selection.metric, ""))
+                    class=(tab(selection.region_id.is_empty())) {
+                    i class="fa-solid fa-earth-europe mr-2" {}
+                    "GLOBAL"
+                }
+                @for region in regions {
+                    a href=(selection.url(selection.period, selection.metric, &region.id))
+                        class=(tab(selection.region_id == region.id)) { (region.name) }
+                }
+            }
+        }
+
+        div class="card-brutal-inset mb-8" {
+            p class="text-muted text-sm mono mb-4" {
+                "SINCE " (since.format("%Y-%m-%d")) " UTC"
+                @if let Some(at) = computed_at {
+                    " · UPDATED " (at.format("%H:%M UTC"))
+                }
+            }
+            @if rows.is_empty() {
+                p class="text-center text-muted font-bold py-6" {
+                    @if selection.teams { "NO TEAM IS RANKED YET. GO " } @else { "NOBODY IS RANKED YET. GO " }
+                    a href="/map" class="text-highlight orange" { "FIND SOME SATS" }
+                    "!"
+                }
+            } @else {
+                div class="overflow-x-auto" {
+                    table class="w-full" {
+                        thead {
+                            tr class="border-b-2 border-tertiary" {
+                                th class="text-left py-2 px-3 font-black text-muted" { "#" }
+                                th class="text-left py-2 px-3 font-black text-muted" {
+                                    @if selection.teams { "Team" } @else { "Hunter" }
+                                }
+                                th class="text-right py-2 px-3 font-black text-muted" { (selection.metric.label()) }
+                            }
+                        }
+                        tbody {
+                            @for row in &rows {
+                                tr class="border-b border-tertiary hover:bg-tertiary" {
+                                    td class="py-2 px-3 font-black mono" { (row.rank) }
+                                    td class="py-2 px-3 font-bold text-primary" {
+                                        (row.name)
+                                        @if row.is_own {
+                                            span class="text-muted" {
+                                                @if selection.teams { " (your team)" } @else { " (you)" }
+                                            }
+                                        }
+                                    }
+                                    td class="py-2 px-3 text-right font-bold text-highlight orange" {
+                                        @match selection.metric {
+                                            Metric::Sats => {
+                                                (format_sats_si(row.value)) " "
+                                                i class="fa-solid fa-bolt" {}
+                                            }
+                                            Metric::Locations | Metric::FirstFinds => { (row.value) }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        @if is_admin {
+            (regions_admin_markup(regions))
+        }
+    }
+}
+
+/// Region list and form for admins
+fn regions_admin_markup(regions: &[Region]) -> Markup {
+    html! {
+        div class="card-brutal-inset mb-8" {
+            h2 class="heading-breaker orange" { "REGIONS" }
+            div class="mt-6 space-y-2" {
+                @for region in regions {
+                    div class="flex justify-between items-center gap-4" {
+                        div {
+                            span class="font-bold text-primary" { (region.name) }
+                            span class="text-muted text-sm mono ml-2" {
+                                (format!("{:.4}..{:.4}, {:.4}..{:.4}",
+                                    region.min_latitude, region.max_latitude,
+                                    region.min_longitude, region.max_longitude))
+                            }
+                        }
+                        button type="button" class="btn-brutal" style="padding: 0.25rem 0.5rem;"
+                            hx-delete={"/api/admin/regions/" (region.id)}
+                            hx-confirm={"Delete region " (region.name) "?"}
+                            hx-swap="none"
+                            hx-on--after-request="if(event.detail.successful) window.location.href = '/leaderboard'"
+                            title="Delete region" {
+                            i class="fa-solid fa-trash" {}
+                        }
+                    }
+                }
+            }
+            form class="mt-6 space-y-4"
+                hx-post="/api/admin/regions"
+                hx-swap="none"
+                hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                div {
+                    label for="region-name" class="label-brutal" { "NAME" }
+                    input type="text" id="region-name" name="name" required
+                        class="input-brutal-box w-full";
+                }
+                div class="grid md:grid-cols-4 gap-4" {
+                    input type="number" name="min_latitude" step="any" required aria-label="Min latitude"
+                        class="input-brutal-box w-full" placeholder="MIN LAT";
+                    input type="number" name="max_latitude" step="any" required aria-label="Max latitude"
+                        class="input-brutal-box w-full" placeholder="MAX LAT";
+                    input type="number" name="min_longitude" step="any" required aria-label="Min longitude"
+                        class="input-brutal-box w-full" placeholder="MIN LON";
+                    input type="number" name="max_longitude" step="any" required aria-label="Max longitude"
+                        class="input-brutal-box w-full" placeholder="MAX LON";
+                }
+                p class="text-xs text-muted font-bold" {
+                    "NEW REGIONS ARE RANKED ON THE NEXT REFRESH, WITHIN TEN MINUTES."
+                }
+                button type="submit" class="btn-brutal-fill" {
+                    i class="fa-solid fa-plus mr-2" {}
+                    "ADD REGION"
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,20 @@
 use super::format_sats_si;
-use crate::models::{Donation, Location, NfcCard, Photo, ScanWithUser, UserRole};
+use crate::balance::BalanceForecastPoint;
+use crate::claim_policy::ClaimPolicy;
+use crate::claim_rules::ClaimRules;
+use crate::disclosure::{
+    Disclosure, Position, DEFAULT_SEARCH_RADIUS_M, MAX_SEARCH_RADIUS_M, MIN_SEARCH_RADIUS_M,
+};
+use crate::lnurl::PayTarget;
+use crate::models::{
+    Claim, Donation, Hint, Location, NfcCard, Photo, RecurringDonation, ScanWithUser, Schedule,
+    UserRole,
+};
 use crate::templates::components::{
-    donation_invoice_markup, donation_invoice_script, DonationInvoiceConfig,
+    countdown_script, donation_invoice_markup, donation_invoice_script, hint_form_markup,
+    hints_markup, lightning_address_markup, log_book_markup, recurring_donations_markup,
+    schedule_form_markup, schedule_list_markup, schedule_status_markup, DonationInvoiceConfig,
+    LogBook,
 };
 use maud::{html, Markup, PreEscaped};
 
@@ -20,6 +33,16 @@ pub fn location_detail(
     base_url: &str,
     donations: &[Donation],
     nfc_card: Option<&NfcCard>,
+    forecast: &[BalanceForecastPoint],
+    claims: &[Claim],
+    recurring: &[RecurringDonation],
+    schedules: &[Schedule],
+    claim_rules: &ClaimRules,
+    global_claim_rules: &ClaimRules,
+    hints: &[Hint],
+    unlocked_hint_ids: &[String],
+    position: Position,
+    log_book: &LogBook,
 ) -> Markup {
     // Max fill = 10% of pool, fill percentage based on available vs max fill
     let max_fill_sats = (pool_sats as f64 * 0.1) as i64;
@@ -33,7 +56,7 @@ pub fn location_detail(
         .map(|id| id == location.user_id)
         .unwrap_or(false);
     let is_admin = current_user_role == UserRole::Admin;
-    let can_manage_photos = is_owner && !location.is_active();
+    let can_manage_photos = is_owner && !location.is_live();
 
     // Generate Boltcard deep links for NFC programming and reset
     let boltcard_program_deep_link = location.write_token.as_ref().map(|token| {
@@ -172,7 +195,7 @@ pub fn location_detail(
                     // Status badge and deactivate/reactivate controls
                     div class="flex items-center gap-2" {
                         @if is_owner || is_admin {
-                            @if location.is_active() {
+                            @if location.is_live() {
                                 button
                                     onclick={
                                         "if(confirm('DEACTIVATE THIS LOCATION? Users will no longer be able to collect sats.')) { "
@@ -217,6 +240,11 @@ pub fn location_detail(
                         // Status badge
                         @if location.is_active() {
                             span class="badge-brutal filled" { "ACTIVE" }
+                        } @else if location.is_scheduled() {
+                            span class="badge-brutal grey" {
+                                i class="fa-solid fa-clock mr-1" {}
+                                "SCHEDULED"
+                            }
                         } @else if location.is_deactivated() {
                             span class="badge-brutal grey" {
                                 i class="fa-solid fa-pause mr-1" {}
@@ -264,8 +292,20 @@ pub fn location_detail(
                     div class="card-brutal-inset p-4" {
                         div class="label-brutal text-xs mb-2" { "COORDINATES" }
                         div class="text-sm mono text-secondary font-bold" {
-                            (format!("{:.4}", location.latitude)) br;
-                            (format!("{:.4}", location.longitude))
+                            @match position {
+                                Position::Exact { latitude, longitude } => {
+                                    (format!("{:.4}", latitude)) br;
+                                    (format!("{:.4}", longitude))
+                                }
+                                Position::Area(area) => {
+                                    "WITHIN " (area.radius_m) " M" br;
+                                    (format!("{:.4}, {:.4}", area.latitude, area.longitude))
+                                }
+                                Position::Hidden => {
+                                    "HIDDEN" br;
+                                    "UNLOCK A HINT"
+                                }
+                            }
                         }
                     }
                 }
@@ -285,13 +325,92 @@ pub fn location_detail(
 
             }
 
+            // Activation schedule
+            @if !schedules.is_empty() || is_owner || is_admin {
+                div class="card-brutal-inset mb-8" {
+                    h2 class="heading-breaker" { "SCHEDULE" }
+                    @if schedules.is_empty() {
+                        p class="text-muted font-bold mt-4" {
+                            "NO SCHEDULE. THIS LOCATION CAN BE CLAIMED AT ANY TIME."
+                        }
+                    } @else {
+                        div class="mt-4 mb-4" {
+                            (schedule_status_markup(schedules))
+                        }
+                        (schedule_list_markup(schedules, |s| {
+                            is_admin || (is_owner && s.location_id.is_some())
+                        }))
+                    }
+                    @if is_owner || is_admin {
+                        div class="mt-6" {
+                            (schedule_form_markup(&format!("/api/locations/{}/schedules", location.id)))
+                        }
+                    }
+                }
+            }
+
+            // Hints
+            @if !hints.is_empty() || is_owner || is_admin {
+                div class="card-brutal-inset mb-8" {
+                    h2 class="heading-breaker" {
+                        i class="fa-solid fa-lightbulb mr-2" {}
+                        "HINTS"
+                    }
+                    div class="mt-4" {
+                        @if hints.is_empty() {
+                            p class="text-muted font-bold" { "NO HINTS YET." }
+                        } @else {
+                            (hints_markup(hints, unlocked_hint_ids, location.created_at, is_owner || is_admin))
+                        }
+                    }
+                    @if is_owner || is_admin {
+                        div class="mt-6" {
+                            (hint_form_markup(&location.id))
+                        }
+                    }
+                }
+            }
+            @if !schedules.is_empty() || !hints.is_empty() || is_owner || is_admin {
+                (countdown_script())
+            }
+
+            // Claim rules
+            @if is_owner || is_admin {
+                div class="card-brutal-inset mb-8" {
+                    h2 class="heading-breaker" { "CLAIM RULES" }
+                    (claim_rules_form(location, claim_rules, global_claim_rules))
+                }
+            }
+
+            // Balance forecast and claim timeline
+            @if !forecast.is_empty() && (pool_sats > 0 || !claims.is_empty()) {
+                (balance_charts(forecast, claims))
+            }
+
             // Map
             div class="card-brutal-inset mb-8" {
                 h2 class="heading-breaker" {
                     i class="fa-solid fa-map mr-2" {}
                     "LOCATION"
                 }
-                div id="map" class="w-full h-64 mt-8" style="border: 3px solid var(--accent-border);" {}
+                @if position == Position::Hidden {
+                    p class="text-muted font-bold mt-8" {
+                        i class="fa-solid fa-eye-slash mr-2" {}
+                        "THE POSITION IS HIDDEN. UNLOCK A HINT TO SEE THE SEARCH AREA."
+                    }
+                } @else {
+                    @if let Position::Area(area) = position {
+                        p class="text-muted font-bold mt-4" {
+                            "SOMEWHERE WITHIN THE CIRCLE, UP TO " (area.radius_m) " M FROM ITS CENTER."
+                        }
+                    }
+                    div id="map" class="w-full h-64 mt-8" style="border: 3px solid var(--accent-border);" {}
+                }
+                @if is_owner || is_admin {
+                    div class="pt-6 mt-6" style="border-top: 3px solid var(--accent-muted);" {
+                        (disclosure_form(location))
+                    }
+                }
             }
 
             // Photos
@@ -349,6 +468,23 @@ pub fn location_detail(
                 }
             }
 
+            // Log book
+            div id="logbook" class="card-brutal-inset mb-8" {
+                h2 class="heading-breaker" {
+                    i class="fa-solid fa-book mr-2" {}
+                    "LOG BOOK"
+                }
+                div class="mt-8" {
+                    (log_book_markup(
+                        &location.id,
+                        log_book,
+                        current_user_id,
+                        (is_owner && current_user_role.has_at_least(UserRole::Creator)) || is_admin,
+                        is_admin,
+                    ))
+                }
+            }
+
             // Donation Pool Section
             div class="card-brutal-inset mb-8" {
                 h2 class="heading-breaker orange" {
@@ -377,6 +513,7 @@ pub fn location_detail(
                         (donation_invoice_markup(&DonationInvoiceConfig {
                             id_prefix: "location",
                             location_id: Some(&location.id),
+                            hunt_id: None,
                             amounts: &[
                                 ("1000", "1K"),
                                 ("5000", "5K"),
@@ -386,9 +523,15 @@ pub fn location_detail(
                             label: Some("Donate to this location"),
                         }))
                     }
+
+                    @if let Some(address) = PayTarget::Location(location.id.clone()).ln_address(base_url) {
+                        (lightning_address_markup(&address))
+                    }
                 }
             }
 
+            (recurring_donations_markup(Some(&location.id), recurring, current_user_id))
+
             // Recent Donations History (default collapsed)
             @if !donations.is_empty() {
                 div class="card-brutal-inset mb-8" {
@@ -429,6 +572,12 @@ pub fn location_detail(
                                                         "Direct"
                                                     }
                                                 }
+                                                @if let Some(comment) = &donation.comment {
+                                                    div class="text-secondary font-normal mt-1" {
+                                                        i class="fa-solid fa-comment mr-1 text-muted" {}
+                                                        (comment)
+                                                    }
+                                                }
                                             }
                                             td class="py-3 px-4 text-right mono" {
                                                 span class="text-highlight orange font-black" {
@@ -600,31 +749,7 @@ pub fn location_detail(
         }
 
         // Map script
-        (PreEscaped(format!(r#"
-        <script>
-            // Initialize map with MapLibre
-            const map = new maplibregl.Map({{
-                container: 'map',
-                style: 'https://tiles.openfreemap.org/styles/positron',
-                center: [{}, {}],
-                zoom: 15
-            }});
-
-            map.addControl(new maplibregl.NavigationControl());
-
-            // Add marker
-            new maplibregl.Marker()
-                .setLngLat([{}, {}])
-                .setPopup(new maplibregl.Popup({{ offset: 25 }})
-                    .setHTML('<div style="color: #0f172a; padding: 8px;"><b>{}</b><br>{} sats available</div>'))
-                .addTo(map)
-                .togglePopup();
-        </script>
-        "#,
-            location.longitude, location.latitude,
-            location.longitude, location.latitude,
-            location.name, format_sats_si(available_sats)
-        )))
+        (map_script(location, position, available_sats))
 
         // Photo upload script - auto-upload on file selection
         @if can_manage_photos {
@@ -722,6 +847,7 @@ pub fn location_detail(
         (donation_invoice_script(&DonationInvoiceConfig {
             id_prefix: "location",
             location_id: Some(&location.id),
+            hunt_id: None,
             amounts: &[
                 ("1000", "1K"),
                 ("5000", "5K"),
@@ -748,3 +874,253 @@ fn delete_button(location_id: &str) -> Markup {
         }
     }
 }
+
+/// Number of most recent claims shown in the claim timeline
+const CLAIM_TIMELINE_LIMIT: usize = 30;
+
+/// Bar charts of the projected balance and the claim history
+fn balance_charts(forecast: &[BalanceForecastPoint], claims: &[Claim]) -> Markup {
+    let max_forecast = forecast
+        .iter()
+        .map(|p| p.claimable_msats)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let week_ahead = forecast.get(7).or(forecast.last());
+
+    // Claims come newest first, show them oldest to newest
+    let recent_claims: Vec<&Claim> = claims.iter().take(CLAIM_TIMELINE_LIMIT).rev().collect();
+    let max_claim = recent_claims
+        .iter()
+        .map(|c| c.msats_claimed)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    html! {
+        div class="card-brutal-inset mb-8" {
+            h2 class="heading-breaker" {
+                i class="fa-solid fa-chart-column mr-2" {}
+                "BALANCE FORECAST"
+            }
+
+            div class="mt-8" {
+                @if let Some(point) = week_ahead {
+                    p class="text-secondary font-bold mb-4" {
+                        "IF NOBODY CLAIMS, THIS SPOT WILL HOLD "
+                        span class="text-highlight orange font-black" {
+                            (format_sats_si(point.claimable_msats / 1000)) " SATS"
+                        }
+                        " BY " (point.at.format("%Y-%m-%d"))
+                    }
+                }
+                div style="display: flex; align-items: flex-end; height: 140px; gap: 2px;" {
+                    @for point in forecast {
+                        @let bar_height = (point.claimable_msats as f64 / max_forecast as f64 * 100.0).max(1.0);
+                        div
+                            style={"flex: 1; height: " (bar_height as i64) "%; background: var(--highlight);"}
+                            title={(point.at.format("%Y-%m-%d")) ": " (point.claimable_msats / 1000) " sats"} {}
+                    }
+                }
+                @if let Some(last) = forecast.last() {
+                    div class="flex justify-between mono text-muted text-xs mt-1" {
+                        span { "TODAY" }
+                        span { (last.at.format("%Y-%m-%d")) }
+                    }
+                }
+            }
+
+            @if !recent_claims.is_empty() {
+                div class="mt-8" {
+                    div class="label-brutal text-xs mb-2" { "CLAIM HISTORY" }
+                    div style="display: flex; align-items: flex-end; height: 100px; gap: 2px;" {
+                        @for claim in &recent_claims {
+                            @let bar_height = (claim.msats_claimed as f64 / max_claim as f64 * 100.0).max(1.0);
+                            div
+                                style={"flex: 1; max-width: 2rem; height: " (bar_height as i64) "%; background: var(--text-secondary);"}
+                                title={(claim.claimed_at.format("%Y-%m-%d %H:%M")) ": " (claim.sats_claimed()) " sats"} {}
+                        }
+                    }
+                    @if let (Some(first), Some(last)) = (recent_claims.first(), recent_claims.last()) {
+                        div class="flex justify-between mono text-muted text-xs mt-1" {
+                            span { (first.claimed_at.format("%Y-%m-%d")) }
+                            span { (last.claimed_at.format("%Y-%m-%d")) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Map of the location: a marker at the exact position, or a circle for a
+/// search area. Nothing for hidden positions, which have no map.
+fn map_script(location: &Location, position: Position, available_sats: i64) -> Markup {
+    let popup = format!(
+        r#"<div style="color: #0f172a; padding: 8px;"><b>{}</b><br>{} sats available</div>"#,
+        location.name.replace('\'', "&#39;"),
+        format_sats_si(available_sats)
+    );
+    let Some((latitude, longitude)) = position.center() else {
+        return html! {};
+    };
+    let overlay = match position.radius_m() {
+        None => format!(
+            r#"
+            new maplibregl.Marker()
+                .setLngLat([{longitude}, {latitude}])
+                .setPopup(new maplibregl.Popup({{ offset: 25 }}).setHTML('{popup}'))
+                .addTo(map)
+                .togglePopup();
+            "#
+        ),
+        Some(radius_m) => format!(
+            r#"
+            map.on('load', () => {{
+                const ring = [];
+                for (let i = 0; i <= 64; i++) {{
+                    const angle = i / 64 * 2 * Math.PI;
+                    ring.push([
+                        {longitude} + {radius_m} * Math.sin(angle) / (111320 * Math.cos({latitude} * Math.PI / 180)),
+                        {latitude} + {radius_m} * Math.cos(angle) / 111320
+                    ]);
+                }}
+                map.addSource('area', {{
+                    type: 'geojson',
+                    data: {{ type: 'Feature', geometry: {{ type: 'Polygon', coordinates: [ring] }} }}
+                }});
+                map.addLayer({{
+                    id: 'area',
+                    type: 'fill',
+                    source: 'area',
+                    paint: {{ 'fill-color': '#F7931A', 'fill-opacity': 0.2 }}
+                }});
+                map.addLayer({{
+                    id: 'area-outline',
+                    type: 'line',
+                    source: 'area',
+                    paint: {{ 'line-color': '#F7931A', 'line-width': 2, 'line-dasharray': [2, 2] }}
+                }});
+                const bounds = new maplibregl.LngLatBounds();
+                ring.forEach(point => bounds.extend(point));
+                map.fitBounds(bounds, {{ padding: 20, animate: false }});
+                new maplibregl.Popup({{ offset: 0, closeOnClick: false }})
+                    .setLngLat([{longitude}, {latitude}])
+                    .setHTML('{popup}')
+                    .addTo(map);
+            }});
+            "#
+        ),
+    };
+    PreEscaped(format!(
+        r#"
+        <script>
+            // Initialize map with MapLibre
+            const map = new maplibregl.Map({{
+                container: 'map',
+                style: 'https://tiles.openfreemap.org/styles/positron',
+                center: [{longitude}, {latitude}],
+                zoom: 15
+            }});
+
+            map.addControl(new maplibregl.NavigationControl());
+            {overlay}
+        </script>
+        "#
+    ))
+}
+
+/// Form for how much of the location's position hunters see. Owners and
+/// admins always see the exact position.
+fn disclosure_form(location: &Location) -> Markup {
+    let radius_m = location.search_radius_m.unwrap_or(DEFAULT_SEARCH_RADIUS_M);
+    html! {
+        form class="space-y-4"
+            hx-post={"/api/locations/" (location.id) "/disclosure"}
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            div class="grid md:grid-cols-2 gap-4" {
+                div {
+                    label for="disclosure" class="label-brutal" { "SHOW HUNTERS" }
+                    select id="disclosure" name="disclosure" class="input-brutal-box w-full" {
+                        @for disclosure in Disclosure::ALL {
+                            option value=(disclosure.as_str()) selected[disclosure == location.disclosure] {
+                                (disclosure.label())
+                            }
+                        }
+                    }
+                }
+                div {
+                    label for="search-radius" class="label-brutal" { "SEARCH RADIUS (M)" }
+                    input type="number" id="search-radius" name="search_radius_m"
+                        min=(MIN_SEARCH_RADIUS_M) max=(MAX_SEARCH_RADIUS_M) value=(radius_m)
+                        class="input-brutal-box w-full";
+                }
+            }
+            p class="text-sm text-muted font-bold" {
+                "YOU ALWAYS SEE THE EXACT POSITION. CHANGING THE RADIUS MOVES THE SEARCH AREA."
+            }
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-eye-slash mr-2" {}
+                "SAVE"
+            }
+        }
+    }
+}
+
+/// Form for a location's claim policy and its overrides of the global claim
+/// rules. Blank rules keep the global rule, shown as the placeholder.
+fn claim_rules_form(location: &Location, rules: &ClaimRules, global: &ClaimRules) -> Markup {
+    let placeholder = |v: Option<i64>| match v {
+        Some(v) => format!("DEFAULT: {}", v),
+        None => "DEFAULT: OFF".to_string(),
+    };
+    html! {
+        form class="space-y-4 mt-4"
+            hx-post={"/api/locations/" (location.id) "/claim-rules"}
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            div {
+                label for="claim-policy" class="label-brutal" { "WHO GETS THE SATS" }
+                select id="claim-policy" name="claim_policy" class="input-brutal-box w-full" {
+                    @for policy in ClaimPolicy::ALL {
+                        option value=(policy.as_str()) selected[policy == location.claim_policy] {
+                            (policy.label())
+                        }
+                    }
+                }
+                p class="text-sm text-muted font-bold mt-2" { (location.claim_policy.description()) }
+            }
+            p class="text-sm text-muted font-bold" {
+                "LEAVE BLANK FOR THE DEFAULT, 0 TURNS A RULE OFF."
+            }
+            div class="grid md:grid-cols-3 gap-4" {
+                div {
+                    label for="cooldown-days" class="label-brutal" { "COOLDOWN (DAYS)" }
+                    input type="number" id="cooldown-days" name="cooldown_days" min="0"
+                        class="input-brutal-box w-full"
+                        value=[rules.cooldown_days]
+                        placeholder=(placeholder(global.cooldown_days));
+                }
+                div {
+                    label for="max-claims" class="label-brutal" { "CLAIMS PER 30 DAYS" }
+                    input type="number" id="max-claims" name="max_claims_per_month" min="0"
+                        class="input-brutal-box w-full"
+                        value=[rules.max_claims_per_month]
+                        placeholder=(placeholder(global.max_claims_per_month));
+                }
+                div {
+                    label for="first-claim-bonus" class="label-brutal" { "FIRST CLAIM BONUS (%)" }
+                    input type="number" id="first-claim-bonus" name="first_claim_bonus_percent" min="0" max="100"
+                        class="input-brutal-box w-full"
+                        value=[rules.first_claim_bonus_percent]
+                        placeholder=(placeholder(global.first_claim_bonus_percent));
+                }
+            }
+            button type="submit" class="btn-brutal-fill" {
+                i class="fa-solid fa-floppy-disk mr-2" {}
+                "SAVE RULES"
+            }
+        }
+    }
+}
@@ -1,10 +1,23 @@
 use super::format_sats_si;
+use crate::disclosure::Position;
 use crate::models::Location;
+use crate::templates::components::{countdown_markup, countdown_script};
+use chrono::{DateTime, Utc};
 use maud::{html, Markup, PreEscaped};
+use std::collections::BTreeMap;
 
 /// Display the map with locations and their computed balances
 /// location_balances is a slice of (location, available_sats, pool_sats)
-pub fn map(location_balances: &[(&Location, i64, i64)]) -> Markup {
+/// event_ends maps locations inside a schedule window to when the window closes
+/// upcoming is a slice of (location, opens_at) for locations hidden by their schedule
+/// positions maps locations to what the viewer may see of their position,
+/// locations missing from it are treated as hidden
+pub fn map(
+    location_balances: &[(&Location, i64, i64)],
+    event_ends: &BTreeMap<String, DateTime<Utc>>,
+    upcoming: &[(Location, DateTime<Utc>)],
+    positions: &BTreeMap<String, Position>,
+) -> Markup {
     html! {
         h1 class="text-4xl font-black mb-8 text-primary" style="letter-spacing: -0.02em;" {
             i class="fa-solid fa-map mr-2" {}
@@ -20,12 +33,39 @@ pub fn map(location_balances: &[(&Location, i64, i64)]) -> Markup {
         // Map container
         div id="map" class="w-full h-96 mb-8" style="border: 3px solid var(--accent-border);" {}
 
+        // Events that haven't started yet
+        @if !upcoming.is_empty() {
+            div class="card-brutal-inset mb-8" {
+                h2 class="heading-breaker orange" { "UPCOMING EVENTS" }
+                div class="grid gap-4 mt-4" {
+                    @for (location, opens_at) in upcoming {
+                        a href={"/locations/" (location.id)}
+                            class="block card-brutal transition hover:bg-elevated" {
+                            div class="flex justify-between items-center gap-4" {
+                                h3 class="text-xl font-black text-primary" { (location.name) }
+                                div class="text-sm font-bold text-highlight orange" {
+                                    i class="fa-solid fa-clock mr-1" {}
+                                    "OPENS IN " (countdown_markup(*opens_at))
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         // Locations list
         div class="card-brutal-inset" {
             h2 class="heading-breaker" { "ALL LOCATIONS" }
             div class="grid gap-4" {
                 @for (location, available_sats, pool_sats) in location_balances {
-                    (location_card(location, *available_sats, *pool_sats))
+                    (location_card(
+                        location,
+                        positions.get(&location.id).copied().unwrap_or(Position::Hidden),
+                        *available_sats,
+                        *pool_sats,
+                        event_ends.get(&location.id).copied(),
+                    ))
                 }
                 @if location_balances.is_empty() {
                     div class="text-center py-8" {
@@ -54,21 +94,64 @@ pub fn map(location_balances: &[(&Location, i64, i64)]) -> Markup {
 
             map.addControl(new maplibregl.NavigationControl());
 
-            // Location data as GeoJSON
+            // Location data as GeoJSON. Locations with a radius_m only have a
+            // search area, drawn as a circle around its center.
             const locations = {locations_json};
             const geojson = {{
                 type: 'FeatureCollection',
-                features: locations.map(loc => ({{
+                features: locations.filter(loc => loc.radius_m === null).map(loc => ({{
                     type: 'Feature',
                     geometry: {{ type: 'Point', coordinates: [loc.longitude, loc.latitude] }},
                     properties: loc
                 }}))
             }};
 
+            function circlePolygon(lng, lat, radiusM) {{
+                const ring = [];
+                for (let i = 0; i <= 64; i++) {{
+                    const angle = i / 64 * 2 * Math.PI;
+                    ring.push([
+                        lng + radiusM * Math.sin(angle) / (111320 * Math.cos(lat * Math.PI / 180)),
+                        lat + radiusM * Math.cos(angle) / 111320
+                    ]);
+                }}
+                return [ring];
+            }}
+            const areas = {{
+                type: 'FeatureCollection',
+                features: locations.filter(loc => loc.radius_m !== null).map(loc => ({{
+                    type: 'Feature',
+                    geometry: {{ type: 'Polygon', coordinates: circlePolygon(loc.longitude, loc.latitude, loc.radius_m) }},
+                    properties: loc
+                }}))
+            }};
+
             const bounds = new maplibregl.LngLatBounds();
             locations.forEach(loc => bounds.extend([loc.longitude, loc.latitude]));
 
             map.on('load', () => {{
+                // Search areas
+                map.addSource('areas', {{ type: 'geojson', data: areas }});
+                map.addLayer({{
+                    id: 'search-areas',
+                    type: 'fill',
+                    source: 'areas',
+                    paint: {{
+                        'fill-color': '#F7931A',
+                        'fill-opacity': 0.2
+                    }}
+                }});
+                map.addLayer({{
+                    id: 'search-area-outlines',
+                    type: 'line',
+                    source: 'areas',
+                    paint: {{
+                        'line-color': '#F7931A',
+                        'line-width': 2,
+                        'line-dasharray': [2, 2]
+                    }}
+                }});
+
                 // Add clustered source
                 map.addSource('locations', {{
                     type: 'geojson',
@@ -138,27 +221,34 @@ pub fn map(location_balances: &[(&Location, i64, i64)]) -> Markup {
                     }}
                 }});
 
-                // Click on individual point to show popup
-                map.on('click', 'unclustered-point', (e) => {{
+                // Click on individual point or search area to show popup
+                const showPopup = (e, lngLat) => {{
                     const loc = e.features[0].properties;
                     new maplibregl.Popup({{ offset: 15 }})
-                        .setLngLat(e.features[0].geometry.coordinates)
+                        .setLngLat(lngLat)
                         .setHTML(`
                             <div style="color: #0f172a; padding: 8px;">
                                 <h3 style="font-weight: bold; margin-bottom: 4px;">${{loc.name}}</h3>
                                 <p style="margin: 4px 0;"><i class="fa-solid fa-bolt"></i> ${{loc.available_sats_fmt}} sats available</p>
                                 <p style="margin: 4px 0; font-size: 0.9em; color: #666;">Pool: ${{loc.pool_sats_fmt}} sats</p>
+                                ${{loc.radius_m !== null ? `<p style="margin: 4px 0; font-size: 0.9em; color: #666;">Somewhere within ${{loc.radius_m}} m</p>` : ''}}
+                                ${{loc.closes_at ? `<p style="margin: 4px 0; font-weight: bold; color: #F7931A;">Event ends in <span data-countdown="${{loc.closes_at}}"></span></p>` : ''}}
                                 <a href="/locations/${{loc.id}}" style="color: #3b82f6; text-decoration: underline;">View details</a>
                             </div>
                         `)
                         .addTo(map);
-                }});
+                    updateCountdowns();
+                }};
+                map.on('click', 'unclustered-point', (e) => showPopup(e, e.features[0].geometry.coordinates));
+                map.on('click', 'search-areas', (e) => showPopup(e, e.lngLat));
 
                 // Change cursor on hover
                 map.on('mouseenter', 'clusters', () => {{ map.getCanvas().style.cursor = 'pointer'; }});
                 map.on('mouseleave', 'clusters', () => {{ map.getCanvas().style.cursor = ''; }});
                 map.on('mouseenter', 'unclustered-point', () => {{ map.getCanvas().style.cursor = 'pointer'; }});
                 map.on('mouseleave', 'unclustered-point', () => {{ map.getCanvas().style.cursor = ''; }});
+                map.on('mouseenter', 'search-areas', () => {{ map.getCanvas().style.cursor = 'pointer'; }});
+                map.on('mouseleave', 'search-areas', () => {{ map.getCanvas().style.cursor = ''; }});
 
                 // Fit bounds after layers are added
                 if (locations.length > 0) {{
@@ -167,33 +257,59 @@ pub fn map(location_balances: &[(&Location, i64, i64)]) -> Markup {
             }});
         </script>
         "#,
-        locations_json = build_locations_json(location_balances)
+        locations_json = build_locations_json(location_balances, event_ends, positions)
         )))
+
+        (countdown_script())
     }
 }
 
-/// Build JSON array for map markers with computed balances
-fn build_locations_json(location_balances: &[(&Location, i64, i64)]) -> String {
+/// Build JSON array for map markers with computed balances.
+/// Only the position the viewer may see is included: the center of the search
+/// area for fuzzed locations, and nothing at all for hidden ones.
+fn build_locations_json(
+    location_balances: &[(&Location, i64, i64)],
+    event_ends: &BTreeMap<String, DateTime<Utc>>,
+    positions: &BTreeMap<String, Position>,
+) -> String {
     let items: Vec<String> = location_balances
         .iter()
-        .map(|(loc, available_sats, pool_sats)| {
-            format!(
-                r#"{{"id":"{}","name":"{}","latitude":{},"longitude":{},"available_sats":{},"pool_sats":{},"available_sats_fmt":"{}","pool_sats_fmt":"{}"}}"#,
+        .filter_map(|(loc, available_sats, pool_sats)| {
+            let position = positions.get(&loc.id)?;
+            let (latitude, longitude) = position.center()?;
+            let radius_m = position
+                .radius_m()
+                .map(|r| r.to_string())
+                .unwrap_or_else(|| "null".to_string());
+            let closes_at = event_ends
+                .get(&loc.id)
+                .map(|at| format!(r#""{}""#, at.to_rfc3339()))
+                .unwrap_or_else(|| "null".to_string());
+            Some(format!(
+                r#"{{"id":"{}","name":"{}","latitude":{},"longitude":{},"radius_m":{},"available_sats":{},"pool_sats":{},"available_sats_fmt":"{}","pool_sats_fmt":"{}","closes_at":{}}}"#,
                 loc.id,
                 loc.name.replace('"', r#"\""#),
-                loc.latitude,
-                loc.longitude,
+                latitude,
+                longitude,
+                radius_m,
                 available_sats,
                 pool_sats,
                 format_sats_si(*available_sats),
-                format_sats_si(*pool_sats)
-            )
+                format_sats_si(*pool_sats),
+                closes_at
+            ))
         })
         .collect();
     format!("[{}]", items.join(","))
 }
 
-fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Markup {
+fn location_card(
+    location: &Location,
+    position: Position,
+    available_sats: i64,
+    pool_sats: i64,
+    closes_at: Option<DateTime<Utc>>,
+) -> Markup {
     // Color based on how full the location is relative to its pool
     let fill_percent = if pool_sats > 0 {
         ((available_sats as f64 / (pool_sats as f64 * 0.1)) * 100.0) as i32
@@ -212,7 +328,13 @@ fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Ma
                     }
                     p class="text-muted text-sm mono" {
                         i class="fa-solid fa-location-dot mr-1" {}
-                        (format!("{:.4}, {:.4}", location.latitude, location.longitude))
+                        (position.label())
+                    }
+                    @if let Some(closes_at) = closes_at {
+                        p class="text-sm font-bold text-highlight orange mt-2" {
+                            i class="fa-solid fa-clock mr-1" {}
+                            "EVENT ENDS IN " (countdown_markup(closes_at))
+                        }
                     }
                 }
                 div class="text-right" {
@@ -1,17 +1,25 @@
+pub mod admin_campaigns;
+pub mod admin_hunts;
+pub mod admin_ledger;
 pub mod admin_locations;
 pub mod admin_scans;
+pub mod admin_solvency;
 pub mod admin_users;
+pub mod admin_withdrawals;
 pub mod collect;
 pub mod components;
 pub mod donate;
 pub mod home;
+pub mod hunts;
 pub mod layout;
+pub mod leaderboard;
 pub mod location_detail;
 pub mod login;
 pub mod map;
 pub mod new_location;
 pub mod profile;
 pub mod register;
+pub mod team;
 pub mod wallet;
 pub mod withdraw;
 
@@ -48,18 +56,26 @@ pub fn format_sats_si(sats: i64) -> String {
     }
 }
 
+pub use admin_campaigns::admin_campaigns;
+pub use admin_hunts::admin_hunts;
+pub use admin_ledger::admin_ledger;
 pub use admin_locations::admin_locations;
 pub use admin_scans::admin_scans;
+pub use admin_solvency::admin_solvency;
 pub use admin_users::admin_users;
+pub use admin_withdrawals::admin_withdrawals;
 pub use collect::{collect, CollectParams};
 pub use donate::donate;
 pub use home::home;
+pub use hunts::{hunt_detail, hunts};
 pub use layout::{base, base_with_user};
+pub use leaderboard::{leaderboard, LeaderboardSelection};
 pub use location_detail::location_detail;
 pub use login::login;
 pub use map::map;
 pub use new_location::new_location;
 pub use profile::profile;
 pub use register::register;
+pub use team::{no_team, team, team_invite};
 pub use wallet::wallet;
 pub use withdraw::withdraw;
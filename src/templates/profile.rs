@@ -1,10 +1,22 @@
-use crate::models::{Location, User};
+use crate::models::{Location, Notification, User, UserBadge};
+use crate::templates::components::badges_markup;
 use maud::{html, Markup};
 
 /// Profile page showing user's locations with computed balances.
 /// location_balances is a slice of (location, available_sats, pool_sats)
-pub fn profile(_user: &User, location_balances: &[(&Location, i64, i64)]) -> Markup {
+pub fn profile(
+    _user: &User,
+    location_balances: &[(&Location, i64, i64)],
+    notifications: &[Notification],
+    badges: &[UserBadge],
+) -> Markup {
     html! {
+        @if !notifications.is_empty() {
+            (notifications_markup(notifications))
+        }
+
+        (badges_markup(badges))
+
         // Locations section
         div class="mb-8" {
                 div class="flex justify-between items-center mb-8" {
@@ -42,6 +54,52 @@ pub fn profile(_user: &User, location_balances: &[(&Location, i64, i64)]) -> Mar
     }
 }
 
+/// Recent notifications, unread ones highlighted
+fn notifications_markup(notifications: &[Notification]) -> Markup {
+    let unread = notifications.iter().filter(|n| !n.is_read()).count();
+    html! {
+        div class="card-brutal-inset mb-8" {
+            div class="flex justify-between items-center mb-4" {
+                h2 class="text-xl font-black text-primary" {
+                    i class="fa-solid fa-bell mr-2" {}
+                    "NOTIFICATIONS "
+                    span class="text-muted mono" { "[" (unread) "]" }
+                }
+                @if unread > 0 {
+                    button type="button" class="btn-brutal"
+                        hx-post="/api/notifications/read"
+                        hx-swap="none"
+                        hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                        i class="fa-solid fa-check mr-2" {}
+                        "MARK ALL READ"
+                    }
+                }
+            }
+            div class="space-y-2" {
+                @for notification in notifications {
+                    @let style = if notification.is_read() {
+                        "border-left: 4px solid var(--accent-muted); padding-left: 0.75rem;"
+                    } else {
+                        "border-left: 4px solid var(--highlight); padding-left: 0.75rem;"
+                    };
+                    div style=(style) {
+                        @if let Some(location_id) = &notification.location_id {
+                            a href={"/locations/" (location_id)} class="font-bold text-primary hover:text-highlight" {
+                                (notification.message)
+                            }
+                        } @else {
+                            span class="font-bold text-primary" { (notification.message) }
+                        }
+                        div class="text-xs text-muted mono" {
+                            (notification.created_at.format("%Y-%m-%d %H:%M UTC"))
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Markup {
     // Calculate percentage based on available vs max fill (10% of pool)
     let max_fill_sats = (pool_sats as f64 * 0.1) as i64;
@@ -53,7 +111,7 @@ fn location_card(location: &Location, available_sats: i64, pool_sats: i64) -> Ma
 
     html! {
         // Use orange border for inactive locations to draw attention
-        @if location.is_active() {
+        @if location.is_live() {
             div class="card-brutal" {
                 (location_card_content(location, available_sats, pool_sats, sats_percent))
             }
@@ -83,6 +141,11 @@ fn location_card_content(
                 }
                 @if location.is_active() {
                     span class="badge-brutal filled" { "ACTIVE" }
+                } @else if location.is_scheduled() {
+                    span class="badge-brutal grey" {
+                        i class="fa-solid fa-clock mr-1" {}
+                        "SCHEDULED"
+                    }
                 } @else if location.is_deactivated() {
                     span class="badge-brutal grey" {
                         i class="fa-solid fa-pause mr-1" {}
@@ -118,8 +181,8 @@ fn location_card_content(
                 }
             }
 
-            // Stats (show for active and deactivated locations)
-            @if location.is_active() || location.is_deactivated() || location.is_admin_deactivated() {
+            // Stats (show for live and deactivated locations)
+            @if location.is_live() || location.is_deactivated() || location.is_admin_deactivated() {
                 div class="pt-4" style="border-top: 3px solid var(--accent-muted);" {
                     div class="flex justify-between items-center mb-3" {
                         div class="label-brutal" { "BALANCE" }
@@ -143,7 +206,7 @@ fn location_card_content(
 
             // Action button
             div class="pt-4" style="border-top: 3px solid var(--accent-muted);" {
-                @if location.is_active() {
+                @if location.is_live() {
                     div class="flex gap-2" {
                         a href={"/locations/" (location.id)}
                             class="btn-brutal text-center flex-1" {
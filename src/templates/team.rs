@@ -0,0 +1,299 @@
+use crate::models::{Team, TeamMember, TeamRole, TeamTransaction};
+use maud::{html, Markup};
+
+/// Team page for a member: members and roles, the invite link, the team
+/// wallet with its ledger and, for owners, team management
+pub fn team(
+    team: &Team,
+    members: &[TeamMember],
+    me: &TeamMember,
+    balance_sats: i64,
+    transactions: &[TeamTransaction],
+    invite_url: &str,
+) -> Markup {
+    let can_manage = me.role.can_manage();
+    let member_name = |user_id: &str| {
+        members
+            .iter()
+            .find(|m| m.user_id == user_id)
+            .and_then(|m| m.username.clone())
+            .unwrap_or_else(|| "former member".to_string())
+    };
+
+    html! {
+        div class="max-w-2xl mx-auto" {
+            h1 class="text-4xl font-black mb-8 text-primary" style="letter-spacing: -0.02em;" {
+                i class="fa-solid fa-users mr-2" {}
+                (team.name)
+            }
+
+            // Members
+            div class="card-brutal-inset mb-8" {
+                h2 class="text-xl font-black text-primary mb-4" {
+                    "MEMBERS "
+                    span class="text-muted mono" { "[" (members.len()) "]" }
+                }
+                div class="space-y-2" {
+                    @for member in members {
+                        div class="flex justify-between items-center gap-4" {
+                            div {
+                                span class="font-bold text-primary" {
+                                    (member.username.as_deref().unwrap_or("anonymous"))
+                                }
+                                @if member.user_id == me.user_id {
+                                    span class="text-muted" { " (you)" }
+                                }
+                            }
+                            div class="flex gap-2 items-center" {
+                                @if can_manage && member.user_id != me.user_id {
+                                    (role_select(&team.id, member))
+                                    @if member.role != TeamRole::Owner {
+                                        button type="button" class="btn-brutal" style="padding: 0.25rem 0.5rem;"
+                                            hx-delete={"/api/teams/" (team.id) "/members/" (member.user_id)}
+                                            hx-confirm="Remove this member from the team?"
+                                            hx-swap="none"
+                                            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)"
+                                            title="Remove member" {
+                                            i class="fa-solid fa-user-minus" {}
+                                        }
+                                    }
+                                } @else {
+                                    span class="badge-brutal grey" { (member.role.label()) }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div class="mt-6" {
+                    label for="invite-url" class="label-brutal" { "INVITE LINK" }
+                    div class="flex gap-2" {
+                        input type="text" id="invite-url" readonly value=(invite_url)
+                            class="input-brutal-box w-full mono text-sm"
+                            onclick="this.select()";
+                        @if can_manage {
+                            button type="button" class="btn-brutal"
+                                hx-post={"/api/teams/" (team.id) "/invite"}
+                                hx-confirm="Make a new invite link? The current one stops working."
+                                hx-swap="none"
+                                hx-on--after-request="if(event.detail.successful) window.location.reload()"
+                                title="New invite link" {
+                                i class="fa-solid fa-rotate" {}
+                            }
+                        }
+                    }
+                    p class="text-xs text-muted font-bold mt-2" {
+                        "ANYONE WITH THIS LINK CAN JOIN THE TEAM."
+                    }
+                }
+            }
+
+            // Team wallet
+            div class="card-brutal mb-8" {
+                h2 class="heading-breaker" {
+                    i class="fa-solid fa-wallet mr-2" {}
+                    "TEAM WALLET"
+                }
+                div class="p-6" {
+                    @if team.wallet_enabled {
+                        p class="text-sm text-secondary font-bold mb-4" {
+                            "SATS FOUND BY MEMBERS ARE COLLECTED INTO THE TEAM WALLET."
+                        }
+                    } @else {
+                        p class="text-sm text-muted font-bold mb-4" {
+                            "THE TEAM WALLET IS OFF. MEMBERS COLLECT INTO THEIR OWN WALLETS."
+                        }
+                    }
+                    div class="text-center mb-4" {
+                        div class="label-brutal text-xs mb-2" { "TEAM BALANCE" }
+                        div class="text-5xl font-black text-highlight orange" {
+                            (balance_sats) " "
+                            i class="fa-solid fa-bolt" {}
+                        }
+                    }
+
+                    @if me.role.can_withdraw() && balance_sats > 0 {
+                        form class="flex gap-2 mt-6"
+                            hx-post={"/api/teams/" (team.id) "/withdraw"}
+                            hx-swap="none"
+                            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                            input type="number" name="amount_sats" min="1" max=(balance_sats) required
+                                aria-label="Amount in sats" placeholder="SATS"
+                                class="input-brutal-box w-full";
+                            button type="submit" class="btn-brutal-fill" style="white-space: nowrap;" {
+                                i class="fa-solid fa-arrow-up mr-2" {}
+                                "TO MY WALLET"
+                            }
+                        }
+                    }
+
+                    @if can_manage {
+                        form class="mt-6"
+                            hx-post={"/api/teams/" (team.id) "/wallet"}
+                            hx-swap="none"
+                            hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                            input type="hidden" name="enabled" value=(!team.wallet_enabled);
+                            button type="submit" class="btn-brutal w-full" {
+                                @if team.wallet_enabled { "TURN TEAM WALLET OFF" } @else { "TURN TEAM WALLET ON" }
+                            }
+                        }
+                    }
+                }
+
+                // Team ledger
+                @if !transactions.is_empty() {
+                    div class="divide-y" style="border-top: 3px solid var(--accent-muted); border-color: var(--accent-muted);" {
+                        @for tx in transactions {
+                            div class="p-4 flex items-center justify-between" {
+                                div {
+                                    @if tx.is_collect() {
+                                        span class="font-bold" style="color: var(--color-success);" {
+                                            i class="fa-solid fa-arrow-down mr-2" {}
+                                            "Collected by " (member_name(&tx.user_id))
+                                        }
+                                    } @else {
+                                        span class="font-bold" style="color: var(--color-error);" {
+                                            i class="fa-solid fa-arrow-up mr-2" {}
+                                            "Withdrawn by " (member_name(&tx.user_id))
+                                        }
+                                    }
+                                    div class="text-xs text-muted mt-1 font-bold" {
+                                        (tx.created_at.format("%Y-%m-%d %H:%M UTC"))
+                                    }
+                                }
+                                @if tx.is_collect() {
+                                    span class="font-bold text-lg" style="color: var(--color-success);" {
+                                        "+" (tx.sats()) " sats"
+                                    }
+                                } @else {
+                                    span class="font-bold text-lg" style="color: var(--color-error);" {
+                                        "-" (tx.sats()) " sats"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div class="flex flex-wrap gap-2" {
+                a href="/leaderboard?teams=true" class="btn-brutal" {
+                    i class="fa-solid fa-trophy mr-2" {}
+                    "TEAM LEADERBOARD"
+                }
+                button type="button" class="btn-brutal"
+                    hx-post={"/api/teams/" (team.id) "/leave"}
+                    hx-confirm="Leave this team?"
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                    i class="fa-solid fa-right-from-bracket mr-2" {}
+                    "LEAVE TEAM"
+                }
+                @if can_manage {
+                    button type="button" class="btn-brutal"
+                        hx-delete={"/api/teams/" (team.id)}
+                        hx-confirm="Delete this team? This can't be undone."
+                        hx-swap="none"
+                        hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                        i class="fa-solid fa-trash mr-2" {}
+                        "DELETE TEAM"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Role picker of a member, for team owners
+fn role_select(team_id: &str, member: &TeamMember) -> Markup {
+    html! {
+        select name="role" class="input-brutal-box" style="padding: 0.25rem 0.5rem;"
+            aria-label="Role"
+            hx-post={"/api/teams/" (team_id) "/members/" (member.user_id) "/role"}
+            hx-swap="none"
+            hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+            @for role in TeamRole::ALL {
+                option value=(role.as_str()) selected[role == member.role] { (role.label()) }
+            }
+        }
+    }
+}
+
+/// Team page for users not in a team
+pub fn no_team() -> Markup {
+    html! {
+        div class="max-w-2xl mx-auto" {
+            h1 class="text-4xl font-black mb-8 text-primary" style="letter-spacing: -0.02em;" {
+                i class="fa-solid fa-users mr-2" {}
+                "TEAM"
+            }
+            div class="card-brutal-inset mb-8" {
+                p class="text-secondary font-bold mb-6" {
+                    "HUNT TOGETHER WITH FAMILY AND FRIENDS. TEAMS GET THEIR OWN LEADERBOARD AND CAN SHARE A WALLET. "
+                    "TO JOIN A TEAM, ASK ONE OF ITS MEMBERS FOR THE INVITE LINK."
+                }
+                form class="space-y-4"
+                    hx-post="/api/teams"
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert(event.detail.xhr.responseText)" {
+                    div {
+                        label for="team-name" class="label-brutal" { "TEAM NAME" }
+                        input type="text" id="team-name" name="name" required maxlength="40"
+                            class="input-brutal-box w-full";
+                    }
+                    button type="submit" class="btn-brutal-fill" {
+                        i class="fa-solid fa-plus mr-2" {}
+                        "CREATE TEAM"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Invite link landing page. `current_team` is the team the user is already
+/// in, if any.
+pub fn team_invite(team: &Team, member_count: usize, current_team: Option<&Team>) -> Markup {
+    html! {
+        div class="max-w-2xl mx-auto" {
+            div class="card-brutal-inset text-center" style="padding: 3rem;" {
+                p class="label-brutal mb-2" { "YOU ARE INVITED TO JOIN" }
+                h1 class="text-4xl font-black mb-2 text-primary" {
+                    i class="fa-solid fa-users mr-2" {}
+                    (team.name)
+                }
+                p class="text-muted font-bold mb-8" {
+                    (member_count) @if member_count == 1 { " MEMBER" } @else { " MEMBERS" }
+                }
+                @match current_team {
+                    Some(current) if current.id == team.id => {
+                        a href="/team" class="btn-brutal-fill" { "YOU ARE IN THIS TEAM" }
+                    }
+                    Some(current) => {
+                        p class="text-secondary font-bold" {
+                            "YOU ARE IN TEAM " (current.name) ". LEAVE IT ON THE "
+                            a href="/team" class="text-highlight orange" { "TEAM PAGE" }
+                            " TO JOIN THIS ONE."
+                        }
+                    }
+                    None => {
+                        form hx-post="/api/teams/join"
+                            hx-swap="none"
+                            hx-on--after-request="if(event.detail.successful) window.location.href = '/team'; else alert(event.detail.xhr.responseText)" {
+                            input type="hidden" name="invite_code" value=(team.invite_code);
+                            @if team.wallet_enabled {
+                                p class="text-sm text-secondary font-bold mb-4" {
+                                    "THIS TEAM SHARES A WALLET: SATS YOU FIND GO INTO THE TEAM WALLET."
+                                }
+                            }
+                            button type="submit" class="btn-brutal-fill" {
+                                i class="fa-solid fa-user-plus mr-2" {}
+                                "JOIN TEAM"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,27 +1,188 @@
-use crate::models::{User, UserTransaction};
+use crate::auto_withdraw::MIN_THRESHOLD_SATS;
+use crate::models::{AutoWithdrawSetting, Team, User, UserBadge, UserTransaction};
+use crate::templates::components::badges_markup;
 use maud::{html, Markup, PreEscaped};
 
-/// Calculate the withdrawable amount after fees (2 sat fixed + 0.5% routing)
-fn withdrawable_after_fees(balance_sats: i64) -> i64 {
-    let balance_msats = balance_sats * 1000;
-    let routing_fee_msats = ((balance_msats as f64) * 0.005).ceil() as i64;
-    let fixed_fee_msats = 2000; // 2 sats
-    let total_fee_msats = routing_fee_msats + fixed_fee_msats;
-    ((balance_msats - total_fee_msats).max(0)) / 1000
+/// Auto-withdraw setting, with the last failure if there was one
+fn auto_withdraw_section(setting: Option<&AutoWithdrawSetting>) -> Markup {
+    html! {
+        div class="p-6" style="border-top: 3px solid var(--accent-muted);" {
+            div class="label-brutal text-xs mb-2" {
+                i class="fa-solid fa-robot mr-1" {}
+                "AUTO-WITHDRAW"
+            }
+            @if let Some(setting) = setting {
+                @if !setting.enabled {
+                    div class="alert-brutal mb-3" style="background: var(--color-error); border-color: var(--color-error);" {
+                        p class="text-sm font-bold text-white" {
+                            i class="fa-solid fa-exclamation-circle mr-2" {}
+                            "Auto-withdraw was turned off after " (setting.failure_count) " failed withdrawals. "
+                            "Save the setting again to turn it back on."
+                        }
+                        @if let Some(error) = &setting.last_error {
+                            p class="text-xs text-white font-bold mt-1 mono" { (error) }
+                        }
+                    }
+                } @else if let Some(error) = &setting.last_error {
+                    div class="p-3 mb-3" style="background: rgba(181, 152, 107, 0.25); border: 2px solid var(--color-warning);" {
+                        p class="text-sm font-bold text-primary" {
+                            i class="fa-solid fa-triangle-exclamation mr-2" style="color: var(--color-warning);" {}
+                            "Last automatic withdrawal failed"
+                            @if let Some(retry_at) = setting.retry_at {
+                                ", retrying at " (retry_at.format("%Y-%m-%d %H:%M UTC"))
+                            }
+                        }
+                        p class="text-xs text-muted font-bold mt-1 mono" { (error) }
+                    }
+                } @else {
+                    p class="text-sm text-secondary font-bold mb-3" {
+                        "Your balance is sent to "
+                        span class="mono text-primary" { (setting.ln_address) }
+                        " whenever it reaches " (setting.threshold_sats()) " sats."
+                        @if let Some(at) = setting.last_withdrawal_at {
+                            " Last withdrawal: " (at.format("%Y-%m-%d %H:%M UTC")) "."
+                        }
+                    }
+                }
+            } @else {
+                p class="text-sm text-muted font-bold mb-3" {
+                    "Send your balance to your own wallet automatically once it reaches a threshold."
+                }
+            }
+
+            form class="space-y-3"
+                hx-post="/api/wallet/auto-withdraw"
+                hx-swap="none"
+                hx-on--after-request="if(event.detail.successful) window.location.reload(); else alert('Could not save auto-withdraw. Check the Lightning address and threshold.')" {
+                div class="grid md:grid-cols-2 gap-3" {
+                    div {
+                        label class="label-brutal text-xs mb-2 block" for="auto_withdraw_address" { "LIGHTNING ADDRESS" }
+                        input type="text" id="auto_withdraw_address" name="ln_address" required
+                            placeholder="you@wallet.com"
+                            value=(setting.map(|s| s.ln_address.as_str()).unwrap_or_default())
+                            class="input-brutal-box w-full mono";
+                    }
+                    div {
+                        label class="label-brutal text-xs mb-2 block" for="auto_withdraw_threshold" { "THRESHOLD (SATS)" }
+                        input type="number" id="auto_withdraw_threshold" name="threshold_sats" required
+                            min=(MIN_THRESHOLD_SATS)
+                            value=(setting.map(|s| s.threshold_sats()).unwrap_or(1000))
+                            class="input-brutal-box w-full";
+                    }
+                }
+                div class="flex gap-3" {
+                    button type="submit" class="btn-brutal-fill flex-1" {
+                        i class="fa-solid fa-floppy-disk mr-2" {}
+                        "SAVE"
+                    }
+                    @if setting.is_some() {
+                        button type="button" class="btn-brutal"
+                            hx-delete="/api/wallet/auto-withdraw"
+                            hx-swap="none"
+                            hx-confirm="Turn off auto-withdraw?"
+                            hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                            i class="fa-solid fa-xmark mr-2" {}
+                            "TURN OFF"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reusable LNURL-withdraw link, with buttons to create, replace or revoke it
+fn withdraw_link_section(withdraw_link: Option<&str>) -> Markup {
+    html! {
+        div class="p-6" style="border-top: 3px solid var(--accent-muted);" {
+            div class="label-brutal text-xs mb-2" {
+                i class="fa-solid fa-link mr-1" {}
+                "REUSABLE WITHDRAW LINK"
+            }
+            @if let Some(lnurl) = withdraw_link {
+                p class="text-sm text-secondary font-bold mb-3" {
+                    "Add this link to your Lightning wallet to see your balance there and "
+                    "withdraw any amount, any time. Anyone with the link can withdraw your "
+                    "balance, so keep it private."
+                }
+                div class="p-3 mb-3 mono text-xs text-primary" style="background: var(--bg-tertiary); border: 2px solid var(--accent-muted); word-break: break-all;" {
+                    (lnurl)
+                }
+                div class="flex flex-wrap gap-3" {
+                    a href={"lightning:" (lnurl)} class="btn-brutal-fill" {
+                        i class="fa-solid fa-bolt mr-2" {}
+                        "OPEN IN WALLET"
+                    }
+                    button type="button" class="btn-brutal"
+                        data-lnurl=(lnurl)
+                        onclick="navigator.clipboard.writeText(this.dataset.lnurl); this.textContent = 'COPIED';" {
+                        "COPY"
+                    }
+                    button type="button" class="btn-brutal"
+                        hx-post="/api/wallet/withdraw-link"
+                        hx-swap="none"
+                        hx-confirm="Replace this link? The current one stops working."
+                        hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                        i class="fa-solid fa-rotate mr-2" {}
+                        "REGENERATE"
+                    }
+                    button type="button" class="btn-brutal"
+                        hx-delete="/api/wallet/withdraw-link"
+                        hx-swap="none"
+                        hx-confirm="Revoke this link? Wallets using it can no longer withdraw."
+                        hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                        i class="fa-solid fa-xmark mr-2" {}
+                        "REVOKE"
+                    }
+                }
+            } @else {
+                p class="text-sm text-muted font-bold mb-3" {
+                    "Create a link your Lightning wallet can keep, to check your balance and "
+                    "withdraw from it without visiting this page."
+                }
+                button type="button" class="btn-brutal-fill w-full"
+                    hx-post="/api/wallet/withdraw-link"
+                    hx-swap="none"
+                    hx-on--after-request="if(event.detail.successful) window.location.reload()" {
+                    i class="fa-solid fa-link mr-2" {}
+                    "CREATE LINK"
+                }
+            }
+        }
+    }
 }
 
 /// Render the wallet page showing user's balance and transaction history.
+/// `withdrawable_sats` is the balance minus the fee reserved for withdrawing all of it.
+/// `min_withdraw_sats` is the smallest balance that covers the fee, if there is one.
+/// `ln_address` is the user's own Lightning address, if they can receive payments.
+/// `auto_withdraw` is their auto-withdraw setting, if they saved one.
+/// `withdraw_link` is their reusable LNURL-withdraw link, if they created one.
+#[allow(clippy::too_many_arguments)] // All parameters are needed for the template
 pub fn wallet(
     balance_sats: i64,
+    withdrawable_sats: i64,
+    min_withdraw_sats: Option<i64>,
     transactions: &[UserTransaction],
     user: Option<&User>,
     success: Option<&str>,
     amount: Option<i64>,
     location_name: Option<&str>,
     lnurlw_string: Option<&str>,
+    ln_address: Option<&str>,
+    auto_withdraw: Option<&AutoWithdrawSetting>,
+    withdraw_link: Option<&str>,
+    badges: &[UserBadge],
+    team_wallet: Option<&Team>,
 ) -> Markup {
-    let withdrawable_sats = withdrawable_after_fees(balance_sats);
     let fee_sats = balance_sats - withdrawable_sats;
+    let too_low_message = match min_withdraw_sats {
+        Some(min_sats) => format!(
+            "Balance too low to withdraw (minimum {} sats to cover fees)",
+            min_sats
+        ),
+        None => "Balance too low to withdraw".to_string(),
+    };
     html! {
         div class="max-w-2xl mx-auto" {
             // Success message for collection
@@ -31,10 +192,22 @@ pub fn wallet(
                     @if let Some(name) = location_name {
                         " from " (name)
                     }
+                    @if let Some(team) = team_wallet {
+                        " for team " (team.name)
+                    }
                     "!"
                 }
             }
 
+            // Claims go to the team wallet instead of this one
+            @if let Some(team) = team_wallet {
+                div class="alert-brutal mb-6" {
+                    "Sats you find are collected into the wallet of team "
+                    a href="/team" class="text-highlight orange font-bold" { (team.name) }
+                    "."
+                }
+            }
+
             // Success message for withdrawal
             @if let (Some("withdrawn"), Some(amt)) = (success, amount) {
                 div class="alert-brutal green success mb-6" {
@@ -42,6 +215,14 @@ pub fn wallet(
                 }
             }
 
+            // Withdrawal over a limit, waiting for an admin
+            @if let (Some("held"), Some(amt)) = (success, amount) {
+                div class="alert-brutal orange mb-6" {
+                    "Your withdrawal of " (amt) " sats is waiting for review. "
+                    "The sats stay reserved until it's approved, or return to your wallet if it's rejected."
+                }
+            }
+
             // Balance card
             div class="card-brutal mb-6" {
                 h1 class="heading-breaker" {
@@ -130,7 +311,7 @@ pub fn wallet(
                                             (withdrawable_sats) " sats"
                                         }
                                         div class="text-xs text-muted mt-1" {
-                                            "(" (fee_sats) " sats fee: 2 sats + 0.5% routing)"
+                                            "(up to " (fee_sats) " sats routing fee)"
                                         }
                                     }
                                     @if let Some(lnurl) = lnurlw_string {
@@ -146,7 +327,7 @@ pub fn wallet(
                                     }
                                 } @else {
                                     p class="text-muted font-bold" {
-                                        "Balance too low to withdraw (minimum ~3 sats to cover fees)"
+                                        (too_low_message)
                                     }
                                 }
                             }
@@ -175,7 +356,7 @@ pub fn wallet(
                                             span class="text-lg font-black text-highlight orange" { (withdrawable_sats) " sats" }
                                         }
                                         div class="text-xs text-muted mt-1" {
-                                            "(" (fee_sats) " sats fee: 2 sats + 0.5% routing)"
+                                            "(up to " (fee_sats) " sats routing fee)"
                                         }
                                     }
                                     button
@@ -189,7 +370,7 @@ pub fn wallet(
                                 }
                             } @else {
                                 p class="text-muted font-bold text-center" {
-                                    "Balance too low to withdraw (minimum ~3 sats to cover fees)"
+                                    (too_low_message)
                                 }
                             }
                         }
@@ -220,7 +401,7 @@ pub fn wallet(
                                             span class="text-lg font-black text-highlight orange" { (withdrawable_sats) " sats" }
                                         }
                                         div class="text-xs text-muted mt-1" {
-                                            "(" (fee_sats) " sats fee: 2 sats + 0.5% routing)"
+                                            "(up to " (fee_sats) " sats routing fee)"
                                         }
                                     }
                                     button
@@ -234,7 +415,7 @@ pub fn wallet(
                                 }
                             } @else {
                                 p class="text-muted font-bold text-center" {
-                                    "Balance too low to withdraw (minimum ~3 sats to cover fees)"
+                                    (too_low_message)
                                 }
                             }
                         }
@@ -250,8 +431,47 @@ pub fn wallet(
                         }
                     }
                 }
+
+                // Receive section
+                div class="p-6" style="border-top: 3px solid var(--accent-muted);" {
+                    div class="label-brutal text-xs mb-2" {
+                        i class="fa-solid fa-arrow-down mr-1" {}
+                        "RECEIVE"
+                    }
+                    @if let Some(address) = ln_address {
+                        div class="flex items-center justify-between gap-4" {
+                            span class="mono font-bold text-primary" style="word-break: break-all;" { (address) }
+                            button type="button" class="btn-brutal"
+                                data-address=(address)
+                                onclick="navigator.clipboard.writeText(this.dataset.address); this.textContent = 'COPIED';" {
+                                "COPY"
+                            }
+                        }
+                        p class="text-xs text-muted mt-2 font-bold" {
+                            "Payments to your Lightning address land in this wallet."
+                        }
+                    } @else if user.is_some_and(|u| !u.is_anonymous()) {
+                        p class="text-sm text-muted font-bold" {
+                            "Your username can't be used as a Lightning address. "
+                            "Only lowercase letters, digits, '-', '_' and '.' are allowed."
+                        }
+                    } @else {
+                        p class="text-sm text-muted font-bold" {
+                            a href="/register" class="text-highlight orange" { "Register" }
+                            " to get your own Lightning address."
+                        }
+                    }
+                }
+
+                // Auto-withdraw section
+                (auto_withdraw_section(auto_withdraw))
+
+                // Reusable withdraw link section
+                (withdraw_link_section(withdraw_link))
             }
 
+            (badges_markup(badges))
+
             // Transaction history
             div class="card-brutal" {
                 h2 class="heading-breaker" {
@@ -274,6 +494,26 @@ pub fn wallet(
                                             i class="fa-solid fa-arrow-down mr-2" {}
                                             "Collected"
                                         }
+                                    } @else if tx.is_receive() {
+                                        span class="font-bold" style="color: var(--color-success);" {
+                                            i class="fa-solid fa-bolt mr-2" {}
+                                            "Received"
+                                        }
+                                    } @else if tx.is_hunt_bonus() {
+                                        span class="font-bold" style="color: var(--color-success);" {
+                                            i class="fa-solid fa-flag-checkered mr-2" {}
+                                            "Hunt bonus"
+                                        }
+                                    } @else if tx.is_team_payout() {
+                                        span class="font-bold" style="color: var(--color-success);" {
+                                            i class="fa-solid fa-users mr-2" {}
+                                            "From team wallet"
+                                        }
+                                    } @else if tx.is_hint() {
+                                        span class="font-bold" style="color: var(--color-error);" {
+                                            i class="fa-solid fa-lightbulb mr-2" {}
+                                            "Hint"
+                                        }
                                     } @else {
                                         span class="font-bold" style="color: var(--color-error);" {
                                             i class="fa-solid fa-arrow-up mr-2" {}
@@ -285,7 +525,7 @@ pub fn wallet(
                                     }
                                 }
                                 div class="text-right" {
-                                    @if tx.is_collect() {
+                                    @if tx.is_credit() {
                                         span class="font-bold text-lg" style="color: var(--color-success);" {
                                             "+" (tx.sats()) " sats"
                                         }
@@ -367,7 +607,9 @@ pub fn wallet(
 
                         if (data.success) {
                             // Show success message
-                            successText.textContent = 'Withdrew ' + data.withdrawn_sats + ' sats!';
+                            successText.textContent = data.held
+                                ? 'Withdrawal of ' + data.withdrawn_sats + ' sats is waiting for review.'
+                                : 'Withdrew ' + data.withdrawn_sats + ' sats!';
                             successDiv.classList.remove('hidden');
 
                             // Update balance display
@@ -378,7 +620,8 @@ pub fn wallet(
 
                             // Redirect to wallet page with success message after short delay
                             setTimeout(function() {
-                                window.location.href = '/wallet?success=withdrawn&amount=' + data.withdrawn_sats;
+                                const success = data.held ? 'held' : 'withdrawn';
+                                window.location.href = '/wallet?success=' + success + '&amount=' + data.withdrawn_sats;
                             }, 1500);
                         } else {
                             // Show error message
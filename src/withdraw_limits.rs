@@ -0,0 +1,120 @@
+//! Limits on wallet withdrawals.
+//!
+//! Withdrawals over a limit aren't refused. They are held with their balance
+//! reserved until an admin approves or rejects them on the admin withdrawals page
+//! (see `Database::reserve_wallet_withdrawal`, which checks the limits in the same
+//! transaction). The limits, each off unless configured:
+//! - the amount of a single withdrawal
+//! - the amount a user withdraws in 24 hours, and all users together
+//! - the amount an anonymous user withdraws shortly after their account was
+//!   created, which is what farming locations with throwaway accounts looks like
+
+use crate::db::{self, Database};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use sqlx::sqlite::SqliteConnection;
+
+/// Configured withdrawal limits, amounts in msats without fees
+#[derive(Debug, Clone)]
+pub struct WithdrawLimits {
+    /// Largest single withdrawal
+    pub max_withdrawal_msats: Option<i64>,
+    /// Most a user withdraws in 24 hours
+    pub user_daily_msats: Option<i64>,
+    /// Most all users together withdraw in 24 hours
+    pub global_daily_msats: Option<i64>,
+    /// Most an anonymous user withdraws within `new_anon_window` of being created
+    pub new_anon_msats: Option<i64>,
+    pub new_anon_window: Duration,
+}
+
+impl Default for WithdrawLimits {
+    fn default() -> Self {
+        Self {
+            max_withdrawal_msats: None,
+            user_daily_msats: None,
+            global_daily_msats: None,
+            new_anon_msats: None,
+            new_anon_window: Duration::hours(1),
+        }
+    }
+}
+
+impl WithdrawLimits {
+    /// Why a withdrawal of `amount_msats` by `user_id` has to be held for review,
+    /// or None if it can be paid right away
+    pub async fn hold_reason(
+        &self,
+        db: &Database,
+        user_id: &str,
+        amount_msats: i64,
+    ) -> Result<Option<String>> {
+        let mut conn = db.pool().acquire().await?;
+        self.hold_reason_in(&mut conn, user_id, amount_msats).await
+    }
+
+    /// `hold_reason` on a given connection, e.g. inside the transaction that
+    /// reserves the withdrawal
+    pub async fn hold_reason_in(
+        &self,
+        conn: &mut SqliteConnection,
+        user_id: &str,
+        amount_msats: i64,
+    ) -> Result<Option<String>> {
+        if let Some(max_msats) = self.max_withdrawal_msats {
+            if amount_msats > max_msats {
+                return Ok(Some(format!(
+                    "{} sats is over the limit of {} sats per withdrawal",
+                    amount_msats / 1000,
+                    max_msats / 1000
+                )));
+            }
+        }
+
+        let day_ago = Utc::now() - Duration::days(1);
+
+        if let Some(limit_msats) = self.user_daily_msats {
+            let withdrawn_msats =
+                db::withdrawn_msats_since(&mut *conn, Some(user_id), day_ago).await?;
+            if withdrawn_msats + amount_msats > limit_msats {
+                return Ok(Some(format!(
+                    "User would withdraw {} sats in 24 hours, over the limit of {} sats",
+                    (withdrawn_msats + amount_msats) / 1000,
+                    limit_msats / 1000
+                )));
+            }
+        }
+
+        if let Some(limit_msats) = self.global_daily_msats {
+            let withdrawn_msats = db::withdrawn_msats_since(&mut *conn, None, day_ago).await?;
+            if withdrawn_msats + amount_msats > limit_msats {
+                return Ok(Some(format!(
+                    "All users would withdraw {} sats in 24 hours, over the limit of {} sats",
+                    (withdrawn_msats + amount_msats) / 1000,
+                    limit_msats / 1000
+                )));
+            }
+        }
+
+        if let Some(limit_msats) = self.new_anon_msats {
+            let user = db::find_user(&mut *conn, user_id).await?;
+            if let Some(user) = user.filter(|u| u.is_anonymous()) {
+                if Utc::now() - user.created_at < self.new_anon_window {
+                    let withdrawn_msats =
+                        db::withdrawn_msats_since(&mut *conn, Some(user_id), user.created_at)
+                            .await?;
+                    if withdrawn_msats + amount_msats > limit_msats {
+                        return Ok(Some(format!(
+                            "New anonymous user would withdraw {} sats within {} minutes of being created, over the limit of {} sats",
+                            (withdrawn_msats + amount_msats) / 1000,
+                            self.new_anon_window.num_minutes(),
+                            limit_msats / 1000
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,382 @@
+//! Zaps (NIP-57).
+//!
+//! This module handles:
+//! - Validating zap requests (kind 9734) sent to the LNURL-pay callback
+//! - Building zap receipts (kind 9735) for donations paid through a zap
+//! - Publishing the receipts to relays once the `DonationService` reports the
+//!   donation received
+//!
+//! Receipts are signed with the instance's Nostr key, advertised as `nostrPubkey`
+//! by the donation Lightning addresses.
+
+use crate::db::Database;
+use crate::models::Donation;
+use crate::nostr::{self, NostrEvent, RelayAccess};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use secp256k1::{Keypair, XOnlyPublicKey};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+/// Event kind of zap requests
+pub const ZAP_REQUEST_KIND: u64 = 9734;
+/// Event kind of zap receipts
+pub const ZAP_RECEIPT_KIND: u64 = 9735;
+/// Largest zap request accepted, in bytes
+pub const MAX_ZAP_REQUEST_LEN: usize = 8 * 1024;
+/// Most relays a receipt is published to
+const MAX_RELAYS: usize = 10;
+/// How long to wait for each relay to accept a receipt
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reasons a zap request is refused
+#[derive(Debug, Error)]
+pub enum ZapError {
+    #[error("Invalid zap request: {0}")]
+    InvalidRequest(String),
+}
+
+/// Check a zap request against NIP-57 (Appendix D) for a payment of
+/// `amount_msats` to `recipient`, returning the parsed event
+pub fn validate_zap_request(
+    json: &str,
+    amount_msats: i64,
+    recipient: &XOnlyPublicKey,
+) -> Result<NostrEvent, ZapError> {
+    let invalid = |msg: &str| ZapError::InvalidRequest(msg.to_string());
+
+    if json.len() > MAX_ZAP_REQUEST_LEN {
+        return Err(invalid("too large"));
+    }
+    let event: NostrEvent = serde_json::from_str(json).map_err(|_| invalid("not an event"))?;
+
+    if event.kind != ZAP_REQUEST_KIND {
+        return Err(invalid("wrong kind"));
+    }
+    if !event.verify() {
+        return Err(invalid("bad signature"));
+    }
+
+    let tags_named = |name: &str| {
+        event
+            .tags
+            .iter()
+            .filter(|t| t.first().map(String::as_str) == Some(name))
+            .count()
+    };
+    if tags_named("p") != 1 {
+        return Err(invalid("must have exactly one p tag"));
+    }
+    if event.tag("p") != Some(hex::encode(recipient.serialize()).as_str()) {
+        return Err(invalid("p tag is not this recipient"));
+    }
+    if tags_named("e") > 1 {
+        return Err(invalid("must have at most one e tag"));
+    }
+    if let Some(amount) = event.tag("amount") {
+        if amount.parse::<i64>().ok() != Some(amount_msats) {
+            return Err(invalid("amount tag does not match the amount"));
+        }
+    }
+
+    Ok(event)
+}
+
+/// Build the zap receipt for a paid zap request
+pub fn zap_receipt(
+    keys: &Keypair,
+    request: &NostrEvent,
+    request_json: &str,
+    bolt11: &str,
+    paid_at: DateTime<Utc>,
+) -> NostrEvent {
+    let mut tags = Vec::new();
+    for name in ["p", "e", "a"] {
+        if let Some(value) = request.tag(name) {
+            tags.push(vec![name.to_string(), value.to_string()]);
+        }
+    }
+    tags.push(vec!["P".to_string(), request.pubkey.clone()]);
+    tags.push(vec!["bolt11".to_string(), bolt11.to_string()]);
+    tags.push(vec!["description".to_string(), request_json.to_string()]);
+
+    NostrEvent::sign(
+        keys,
+        ZAP_RECEIPT_KIND,
+        tags,
+        String::new(),
+        paid_at.timestamp().max(0) as u64,
+    )
+}
+
+/// Relays to publish a receipt to: the ones listed in the zap request followed by
+/// the configured ones, without duplicates. Only `wss://` relays are used unless
+/// `access` allows plain ones; `nostr::connect` checks their addresses.
+pub fn receipt_relays(
+    request: &NostrEvent,
+    configured: &[String],
+    access: RelayAccess,
+) -> Vec<String> {
+    let requested = request
+        .tags
+        .iter()
+        .filter(|t| t.first().map(String::as_str) == Some("relays"))
+        .flat_map(|t| t.iter().skip(1));
+
+    let mut relays: Vec<String> = Vec::new();
+    for relay in requested.chain(configured) {
+        let relay = relay.trim();
+        let is_websocket =
+            relay.starts_with("wss://") || (access.allow_ws && relay.starts_with("ws://"));
+        if is_websocket && !relays.iter().any(|r| r == relay) {
+            relays.push(relay.to_string());
+        }
+    }
+    relays.truncate(MAX_RELAYS);
+    relays
+}
+
+/// Background service publishing zap receipts for received zap donations
+pub struct ZapService {
+    db: Arc<Database>,
+    keys: Keypair,
+    /// Relays every receipt is published to
+    relays: Vec<String>,
+    /// Relays zap requests can make us connect to
+    relay_access: RelayAccess,
+}
+
+impl ZapService {
+    pub fn new(
+        db: Arc<Database>,
+        keys: Keypair,
+        relays: Vec<String>,
+        relay_access: RelayAccess,
+    ) -> Self {
+        Self {
+            db,
+            keys,
+            relays,
+            relay_access,
+        }
+    }
+
+    /// Start the zap service - publishes receipts left over from before a restart,
+    /// then listens for received donations
+    pub async fn start(self: Arc<Self>, mut donations: broadcast::Receiver<Donation>) {
+        self.publish_pending().await;
+
+        loop {
+            match donations.recv().await {
+                Ok(donation) if donation.zap_request.is_some() => {
+                    if let Err(e) = self.publish_receipt(&donation).await {
+                        tracing::error!(
+                            "Failed to publish zap receipt for donation {}: {}",
+                            donation.id,
+                            e
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Zap service missed {} received donations", missed);
+                    self.publish_pending().await;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Publish the receipts of all received zaps that don't have one yet
+    pub async fn publish_pending(&self) {
+        let pending = match self.db.list_unpublished_zaps().await {
+            Ok(pending) => pending,
+            Err(e) => {
+                tracing::error!("Failed to load unpublished zaps: {}", e);
+                return;
+            }
+        };
+
+        for donation in pending {
+            if let Err(e) = self.publish_receipt(&donation).await {
+                tracing::error!(
+                    "Failed to publish zap receipt for donation {}: {}",
+                    donation.id,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Publish the zap receipt of a received donation. Succeeds if at least one
+    /// relay accepted it.
+    pub async fn publish_receipt(&self, donation: &Donation) -> Result<NostrEvent> {
+        let request_json = donation
+            .zap_request
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Donation was not paid through a zap"))?;
+        let request: NostrEvent = serde_json::from_str(request_json)?;
+        let paid_at = donation.received_at.unwrap_or_else(Utc::now);
+
+        let receipt = zap_receipt(
+            &self.keys,
+            &request,
+            request_json,
+            &donation.invoice,
+            paid_at,
+        );
+
+        let relays = receipt_relays(&request, &self.relays, self.relay_access);
+        anyhow::ensure!(!relays.is_empty(), "No relays to publish to");
+
+        let results = join_all(
+            relays
+                .iter()
+                .map(|relay| nostr::publish(relay, &receipt, self.relay_access, PUBLISH_TIMEOUT)),
+        )
+        .await;
+
+        let mut accepted = 0;
+        for (relay, result) in relays.iter().zip(results) {
+            match result {
+                Ok(()) => accepted += 1,
+                Err(e) => tracing::warn!("Relay {} did not take zap receipt: {}", relay, e),
+            }
+        }
+        anyhow::ensure!(accepted > 0, "No relay accepted the zap receipt");
+
+        self.db
+            .mark_zap_receipt_published(&donation.id, &receipt.id)
+            .await?;
+        tracing::info!(
+            "Published zap receipt {} for donation {} to {} relays",
+            receipt.id,
+            donation.id,
+            accepted
+        );
+
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::{Secp256k1, SecretKey};
+
+    fn keypair(byte: u8) -> Keypair {
+        let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+        Keypair::from_secret_key(&Secp256k1::new(), &secret)
+    }
+
+    fn pubkey_hex(keys: &Keypair) -> String {
+        hex::encode(keys.x_only_public_key().0.serialize())
+    }
+
+    fn zap_request(sender: &Keypair, tags: Vec<Vec<&str>>) -> String {
+        let tags = tags
+            .into_iter()
+            .map(|t| t.into_iter().map(str::to_string).collect())
+            .collect();
+        let event = NostrEvent::sign(
+            sender,
+            ZAP_REQUEST_KIND,
+            tags,
+            "great spot!".to_string(),
+            1_700_000_000,
+        );
+        serde_json::to_string(&event).unwrap()
+    }
+
+    #[test]
+    fn test_validate_zap_request() {
+        let server = keypair(1);
+        let sender = keypair(2);
+        let server_hex = pubkey_hex(&server);
+        let recipient = server.x_only_public_key().0;
+
+        let valid = zap_request(
+            &sender,
+            vec![
+                vec!["p", &server_hex],
+                vec!["amount", "21000"],
+                vec!["relays", "wss://relay.example"],
+            ],
+        );
+        let event = validate_zap_request(&valid, 21_000, &recipient).unwrap();
+        assert_eq!(event.content, "great spot!");
+
+        // Amount must match the amount tag
+        assert!(validate_zap_request(&valid, 1_000, &recipient).is_err());
+
+        // Exactly one p tag, for us
+        let other = pubkey_hex(&keypair(3));
+        for tags in [
+            vec![vec!["amount", "21000"]],
+            vec![vec!["p", other.as_str()]],
+            vec![vec!["p", &server_hex], vec!["p", &server_hex]],
+            vec![vec!["p", &server_hex], vec!["e", "a"], vec!["e", "b"]],
+        ] {
+            let request = zap_request(&sender, tags);
+            assert!(validate_zap_request(&request, 21_000, &recipient).is_err());
+        }
+
+        // Tampered events are refused
+        let tampered = valid.replace("great spot!", "bad spot!");
+        assert!(validate_zap_request(&tampered, 21_000, &recipient).is_err());
+        assert!(validate_zap_request("{}", 21_000, &recipient).is_err());
+    }
+
+    #[test]
+    fn test_zap_receipt_tags() {
+        let server = keypair(1);
+        let sender = keypair(2);
+        let server_hex = pubkey_hex(&server);
+        let request_json = zap_request(&sender, vec![vec!["p", &server_hex], vec!["e", "note-id"]]);
+        let request: NostrEvent = serde_json::from_str(&request_json).unwrap();
+
+        let receipt = zap_receipt(&server, &request, &request_json, "lnbc1", Utc::now());
+        assert!(receipt.verify());
+        assert_eq!(receipt.kind, ZAP_RECEIPT_KIND);
+        assert_eq!(receipt.pubkey, server_hex);
+        assert_eq!(receipt.tag("p"), Some(server_hex.as_str()));
+        assert_eq!(receipt.tag("e"), Some("note-id"));
+        assert_eq!(receipt.tag("P"), Some(pubkey_hex(&sender).as_str()));
+        assert_eq!(receipt.tag("bolt11"), Some("lnbc1"));
+        assert_eq!(receipt.tag("description"), Some(request_json.as_str()));
+    }
+
+    #[test]
+    fn test_receipt_relays() {
+        let sender = keypair(2);
+        let request: NostrEvent = serde_json::from_str(&zap_request(
+            &sender,
+            vec![vec![
+                "relays",
+                "wss://a.example",
+                "https://not-a-relay",
+                "ws://plain.example",
+                "wss://b.example",
+            ]],
+        ))
+        .unwrap();
+
+        let configured = ["wss://b.example".to_string(), "wss://c.example".to_string()];
+        let relays = receipt_relays(&request, &configured, RelayAccess::default());
+        assert_eq!(
+            relays,
+            vec!["wss://a.example", "wss://b.example", "wss://c.example"]
+        );
+
+        // Plain relays only where allowed (tests)
+        let access = RelayAccess {
+            allow_ws: true,
+            ..RelayAccess::default()
+        };
+        assert!(receipt_relays(&request, &configured, access)
+            .contains(&"ws://plain.example".to_string()));
+    }
+}
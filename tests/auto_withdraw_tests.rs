@@ -0,0 +1,197 @@
+//! Auto-withdraw tests. Payments go through `MockLightning`; addresses that fail to
+//! parse stand in for unreachable Lightning addresses.
+
+use satshunt::auto_withdraw::{AutoWithdrawService, MAX_FAILURES};
+use satshunt::db::Database;
+use satshunt::fees::FeePolicy;
+use satshunt::invoice_policy::InvoicePolicy;
+use satshunt::lightning::{MockLightning, PaymentStatus};
+use satshunt::models::AuthMethod;
+use satshunt::withdraw_limits::WithdrawLimits;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+async fn setup_test_db() -> (Arc<Database>, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db_url = format!("sqlite:{}", db_path.display());
+    let db = Database::new(&db_url).await.unwrap();
+    (Arc::new(db), temp_dir)
+}
+
+/// Create a user with `balance_msats` in their wallet
+async fn create_user_with_balance(db: &Database, balance_msats: i64) -> String {
+    let user = db
+        .create_user(
+            "hunter".to_string(),
+            None,
+            AuthMethod::Password {
+                password_hash: "hash".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    db.create_wallet_invoice(&user.id, "lnbc-funding", balance_msats, None)
+        .await
+        .unwrap();
+    db.mark_wallet_invoice_received("lnbc-funding")
+        .await
+        .unwrap();
+    user.id
+}
+
+#[tokio::test]
+async fn test_balance_below_threshold_is_kept() {
+    let (db, _temp) = setup_test_db().await;
+    let user_id = create_user_with_balance(&db, 50_000).await;
+    db.save_auto_withdraw_setting(&user_id, "not-an-address", 100_000)
+        .await
+        .unwrap();
+
+    let service = AutoWithdrawService::new(
+        db.clone(),
+        Arc::new(MockLightning::new()),
+        InvoicePolicy::default(),
+        FeePolicy::default(),
+        WithdrawLimits::default(),
+    );
+    assert_eq!(service.process_user(&user_id).await.unwrap(), None);
+
+    // Nothing was attempted, so nothing failed
+    let setting = db
+        .get_auto_withdraw_setting(&user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(setting.failure_count, 0);
+    assert!(setting.last_error.is_none());
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 50_000);
+}
+
+#[tokio::test]
+async fn test_failed_withdrawals_back_off_then_disable() {
+    let (db, _temp) = setup_test_db().await;
+    let user_id = create_user_with_balance(&db, 500_000).await;
+    db.save_auto_withdraw_setting(&user_id, "not-an-address", 100_000)
+        .await
+        .unwrap();
+
+    let service = AutoWithdrawService::new(
+        db.clone(),
+        Arc::new(MockLightning::new()),
+        InvoicePolicy::default(),
+        FeePolicy::default(),
+        WithdrawLimits::default(),
+    );
+    assert_eq!(service.process_user(&user_id).await.unwrap(), None);
+
+    // The failure is recorded for the wallet page and retried later
+    let setting = db
+        .get_auto_withdraw_setting(&user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(setting.enabled);
+    assert_eq!(setting.failure_count, 1);
+    assert!(setting.last_error.is_some());
+    assert!(setting.retry_at.is_some_and(|at| at > chrono::Utc::now()));
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 500_000);
+    let notifications = db.list_notifications(&user_id, 10).await.unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert!(notifications[0].message.contains("not-an-address"));
+
+    // Nothing is attempted while backing off
+    assert_eq!(service.process_due().await.unwrap(), 0);
+    assert!(db
+        .list_due_auto_withdraw_settings()
+        .await
+        .unwrap()
+        .is_empty());
+    let unchanged = db
+        .get_auto_withdraw_setting(&user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unchanged.failure_count, 1);
+
+    // Reaching the limit disables it until the user saves it again
+    let mut setting = setting;
+    while setting.failure_count < MAX_FAILURES {
+        setting = db
+            .mark_auto_withdraw_failed(&user_id, "still failing", chrono::Utc::now(), MAX_FAILURES)
+            .await
+            .unwrap();
+    }
+    assert!(!setting.enabled);
+    assert!(db
+        .list_due_auto_withdraw_settings()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let saved = db
+        .save_auto_withdraw_setting(&user_id, "me@wallet.example", 200_000)
+        .await
+        .unwrap();
+    assert!(saved.enabled);
+    assert_eq!(saved.failure_count, 0);
+    assert!(saved.last_error.is_none());
+    assert_eq!(saved.threshold_sats(), 200);
+    assert_eq!(db.list_due_auto_withdraw_settings().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_unconfirmed_withdrawals_are_settled_once_known() {
+    let (db, _temp) = setup_test_db().await;
+    let user_id = create_user_with_balance(&db, 500_000).await;
+
+    let service_reporting = |status| {
+        AutoWithdrawService::new(
+            db.clone(),
+            Arc::new(MockLightning {
+                payment_status: Some(status),
+                ..MockLightning::default()
+            }),
+            InvoicePolicy::default(),
+            FeePolicy::default(),
+            WithdrawLimits::default(),
+        )
+    };
+
+    let paid = db
+        .create_pending_withdrawal(&user_id, 100_000, 2_000, "lnbc-paid")
+        .await
+        .unwrap()
+        .unwrap();
+    db.mark_withdrawal_unconfirmed(&paid).await.unwrap();
+
+    // While the outcome is unknown the balance stays reserved
+    let unknown = service_reporting(PaymentStatus::Unknown);
+    assert_eq!(unknown.settle_unconfirmed().await.unwrap(), 0);
+    assert_eq!(db.list_unconfirmed_withdrawals().await.unwrap().len(), 1);
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 398_000);
+
+    // Once paid, the unused part of the fee reserve is refunded
+    let succeeded = service_reporting(PaymentStatus::Succeeded {
+        fee_msats: Some(500),
+    });
+    assert_eq!(succeeded.settle_unconfirmed().await.unwrap(), 1);
+    let withdrawal = db.get_pending_withdrawal(&paid).await.unwrap().unwrap();
+    assert!(!withdrawal.is_pending());
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 399_500);
+    assert!(db.list_unconfirmed_withdrawals().await.unwrap().is_empty());
+
+    // A failed payment releases the reserved balance
+    let failed = db
+        .create_pending_withdrawal(&user_id, 100_000, 2_000, "lnbc-failed")
+        .await
+        .unwrap()
+        .unwrap();
+    db.mark_withdrawal_unconfirmed(&failed).await.unwrap();
+    let failing = service_reporting(PaymentStatus::Failed);
+    assert_eq!(failing.settle_unconfirmed().await.unwrap(), 1);
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 399_500);
+
+    // The user is told about both outcomes
+    assert_eq!(db.list_notifications(&user_id, 10).await.unwrap().len(), 2);
+}
@@ -1,5 +1,17 @@
+use chrono::Utc;
+use satshunt::achievements::{self, Badge};
+use satshunt::balance::BalanceConfig;
+use satshunt::claim_policy::ClaimPolicy;
+use satshunt::claim_rules::ClaimRules;
 use satshunt::db::Database;
-use satshunt::models::AuthMethod;
+use satshunt::disclosure::{Disclosure, SearchArea};
+use satshunt::leaderboard::{self, Metric, Period};
+use satshunt::lightning::MockLightning;
+use satshunt::models::{
+    AuthMethod, CampaignStatus, ClaimResult, HintUnlock, LedgerAccount, LogKind, NewHint,
+    NewMatchingCampaign, NewSchedule, Recurrence, TeamRole,
+};
+use satshunt::solvency;
 use sqlx::Executor as _;
 use tempfile::TempDir;
 
@@ -479,3 +491,1947 @@ async fn test_admin_scans_with_data() {
         Some(chrono::NaiveDate::from_ymd_opt(2026, 3, 10).unwrap())
     );
 }
+
+/// Helper to create a user and an active location whose fill timer is already full
+async fn setup_ledger_location(db: &Database, username: &str) -> (String, String) {
+    let auth = AuthMethod::Password {
+        password_hash: "hash".to_string(),
+    };
+    let user = db
+        .create_user(username.to_string(), None, auth)
+        .await
+        .unwrap();
+    let location = db
+        .create_location(
+            format!("{} location", username),
+            0.0,
+            0.0,
+            None,
+            format!("{}-secret", username),
+            user.id.clone(),
+        )
+        .await
+        .unwrap();
+    db.update_location_status(&location.id, "active")
+        .await
+        .unwrap();
+    db.pool()
+        .execute(
+            sqlx::query("UPDATE locations SET created_at = '2020-01-01T00:00:00Z' WHERE id = ?")
+                .bind(&location.id),
+        )
+        .await
+        .unwrap();
+    (user.id, location.id)
+}
+
+#[tokio::test]
+async fn test_ledger_balanced_after_money_flows() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+
+    let (user_id, location_a) = setup_ledger_location(&db, "alice").await;
+    let (_, location_b) = setup_ledger_location(&db, "bob").await;
+
+    // Direct donation plus a global donation with a 1 msat remainder
+    db.create_donation("lnbc-direct".to_string(), 100_000, Some(&location_a))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-direct").await.unwrap();
+    db.create_donation("lnbc-global".to_string(), 10_001, None)
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-global").await.unwrap();
+
+    // Collect into the wallet
+    let now = Utc::now().to_rfc3339();
+    insert_test_scan(&db, &location_a, &user_id, &now).await;
+    let scan = db
+        .get_last_scan_for_location(&location_a)
+        .await
+        .unwrap()
+        .unwrap();
+    let collected = match db
+        .claim_from_scan(&scan.id, &user_id, &config, &ClaimRules::default())
+        .await
+        .unwrap()
+    {
+        ClaimResult::Success { msats, .. } => msats,
+        other => panic!("unexpected claim result: {:?}", other),
+    };
+    assert_eq!(collected, 52_500);
+
+    // One completed and one failed withdrawal
+    let completed = db
+        .create_pending_withdrawal(&user_id, 20_000, 2_100, "lnbc-out-1")
+        .await
+        .unwrap()
+        .unwrap();
+    let failed = db
+        .create_pending_withdrawal(&user_id, 10_000, 2_050, "lnbc-out-2")
+        .await
+        .unwrap()
+        .unwrap();
+    db.complete_pending_withdrawal(&completed, None)
+        .await
+        .unwrap();
+    db.fail_pending_withdrawal(&failed).await.unwrap();
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(report.total_debits_msats, report.total_credits_msats);
+
+    let wallet = LedgerAccount::UserWallet(user_id.clone());
+    assert_eq!(
+        report.balance_of(&wallet),
+        db.get_user_balance(&user_id).await.unwrap()
+    );
+    assert_eq!(report.balance_of(&wallet), 52_500 - 22_100);
+    assert_eq!(report.balance_of(&LedgerAccount::Fees), 0);
+    assert_eq!(report.balance_of(&LedgerAccount::Unallocated), 1);
+    assert_eq!(report.balance_of(&LedgerAccount::PendingWithdrawals), 0);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::LocationPool(location_b.clone())),
+        5_000
+    );
+    // The fee of unknown size is assumed spent on routing
+    assert_eq!(report.node_msats(), 110_001 - 22_100);
+}
+
+#[tokio::test]
+async fn test_withdrawal_fee_refund() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, _) = setup_ledger_location(&db, "dave").await;
+    db.create_wallet_invoice(&user_id, "lnbc-dave", 100_000, None)
+        .await
+        .unwrap();
+    db.mark_wallet_invoice_received("lnbc-dave").await.unwrap();
+
+    // 3 sats reserved, 1 sat paid: 2 sats go back to the wallet
+    let refunded = db
+        .create_pending_withdrawal(&user_id, 20_000, 3_000, "lnbc-out-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 77_000);
+    db.complete_pending_withdrawal(&refunded, Some(1_000))
+        .await
+        .unwrap();
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 79_000);
+
+    // Paying more than reserved charges the reserve and nothing more
+    let exceeded = db
+        .create_pending_withdrawal(&user_id, 20_000, 3_000, "lnbc-out-2")
+        .await
+        .unwrap()
+        .unwrap();
+    db.complete_pending_withdrawal(&exceeded, Some(5_000))
+        .await
+        .unwrap();
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 56_000);
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    // Routing fees leave the node, the operator covers what exceeded the reserve
+    assert_eq!(report.balance_of(&LedgerAccount::Fees), 0);
+    assert_eq!(report.balance_of(&LedgerAccount::Equity), -2_000);
+    assert_eq!(report.node_msats(), 100_000 - 40_000 - 6_000);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::UserWallet(user_id)),
+        56_000
+    );
+}
+
+#[tokio::test]
+async fn test_held_withdrawals() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, _) = setup_ledger_location(&db, "erin").await;
+    let (admin_id, _) = setup_ledger_location(&db, "admin").await;
+    db.create_wallet_invoice(&user_id, "lnbc-erin", 100_000, None)
+        .await
+        .unwrap();
+    db.mark_wallet_invoice_received("lnbc-erin").await.unwrap();
+
+    // Held withdrawals reserve the balance like pending ones
+    let rejected = db
+        .create_held_withdrawal(&user_id, 40_000, 2_000, "lnbc-out-1", "too much")
+        .await
+        .unwrap()
+        .unwrap();
+    let approved = db
+        .create_held_withdrawal(&user_id, 30_000, 2_000, "lnbc-out-2", "too much")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 26_000);
+    assert!(db
+        .create_pending_withdrawal(&user_id, 30_000, 2_000, "lnbc-out-3")
+        .await
+        .unwrap()
+        .is_none());
+
+    let held = db.list_held_withdrawals().await.unwrap();
+    assert_eq!(held.len(), 2);
+    assert_eq!(held[0].hold_reason.as_deref(), Some("too much"));
+    assert_eq!(held[0].amount_msats(), 40_000);
+
+    // They count towards withdrawal limits
+    let since = Utc::now() - chrono::Duration::days(1);
+    assert_eq!(
+        db.get_withdrawn_msats_since(Some(&user_id), since)
+            .await
+            .unwrap(),
+        70_000
+    );
+    assert_eq!(
+        db.get_withdrawn_msats_since(None, since).await.unwrap(),
+        70_000
+    );
+
+    // Rejecting returns the sats
+    assert!(db
+        .reject_held_withdrawal(&rejected, &admin_id)
+        .await
+        .unwrap());
+    assert!(!db
+        .reject_held_withdrawal(&rejected, &admin_id)
+        .await
+        .unwrap());
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 68_000);
+
+    // Approving makes it pending, to be paid by the caller
+    let withdrawal = db
+        .approve_held_withdrawal(&approved, &admin_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(withdrawal.is_pending());
+    assert_eq!(withdrawal.reviewed_by.as_deref(), Some(admin_id.as_str()));
+    assert!(db
+        .approve_held_withdrawal(&approved, &admin_id)
+        .await
+        .unwrap()
+        .is_none());
+    db.complete_pending_withdrawal(&approved, None)
+        .await
+        .unwrap();
+
+    assert!(db.list_held_withdrawals().await.unwrap().is_empty());
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 68_000);
+    assert!(db.verify_ledger().await.unwrap().is_balanced());
+}
+
+#[tokio::test]
+async fn test_donation_received_once() {
+    let (db, _temp) = setup_test_db().await;
+    let (_, location_id) = setup_ledger_location(&db, "ivan").await;
+
+    db.create_donation("lnbc-ivan".to_string(), 10_000, None)
+        .await
+        .unwrap();
+    assert!(db
+        .mark_donation_received("lnbc-ivan")
+        .await
+        .unwrap()
+        .is_some());
+    // A second payment notification for the same invoice books nothing
+    assert!(db
+        .mark_donation_received("lnbc-ivan")
+        .await
+        .unwrap()
+        .is_none());
+
+    assert_eq!(
+        db.get_location_donation_pool_balance(&location_id)
+            .await
+            .unwrap(),
+        10_000
+    );
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::LocationPool(location_id)),
+        10_000
+    );
+}
+
+#[tokio::test]
+async fn test_verify_ledger_flags_imbalance() {
+    let (db, _temp) = setup_test_db().await;
+    let (_, location_id) = setup_ledger_location(&db, "carol").await;
+
+    db.create_donation("lnbc-carol".to_string(), 50_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-carol").await.unwrap();
+    assert!(db.verify_ledger().await.unwrap().is_balanced());
+
+    // A claim written behind the ledger's back
+    db.pool()
+        .execute(
+            sqlx::query(
+                "INSERT INTO claims (id, location_id, msats_claimed, claimed_at, user_id) VALUES ('rogue', ?, 1000, CURRENT_TIMESTAMP, NULL)",
+            )
+            .bind(&location_id),
+        )
+        .await
+        .unwrap();
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(!report.is_balanced());
+    let discrepancy = &report.discrepancies[0];
+    assert_eq!(
+        discrepancy.account,
+        Some(LedgerAccount::LocationPool(location_id))
+    );
+    assert_eq!(discrepancy.ledger_msats, 50_000);
+    assert_eq!(discrepancy.expected_msats, 49_000);
+}
+
+#[tokio::test]
+async fn test_delete_location_returns_pool_to_unallocated() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, location_id) = setup_ledger_location(&db, "dave").await;
+
+    db.create_donation("lnbc-dave".to_string(), 30_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-dave").await.unwrap();
+    db.update_location_status(&location_id, "deactivated")
+        .await
+        .unwrap();
+    db.delete_location(&location_id, &user_id).await.unwrap();
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::LocationPool(location_id)),
+        0
+    );
+    assert_eq!(report.balance_of(&LedgerAccount::Unallocated), 30_000);
+}
+
+#[tokio::test]
+async fn test_solvency_report() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (user_id, location_id) = setup_ledger_location(&db, "erin").await;
+
+    db.create_donation("lnbc-erin".to_string(), 80_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-erin").await.unwrap();
+
+    let now = Utc::now().to_rfc3339();
+    insert_test_scan(&db, &location_id, &user_id, &now).await;
+    let scan = db
+        .get_last_scan_for_location(&location_id)
+        .await
+        .unwrap()
+        .unwrap();
+    db.claim_from_scan(&scan.id, &user_id, &config, &ClaimRules::default())
+        .await
+        .unwrap();
+    db.create_pending_withdrawal(&user_id, 10_000, 2_050, "lnbc-erin-out")
+        .await
+        .unwrap()
+        .unwrap();
+
+    let balances = db.list_user_balances().await.unwrap();
+    assert_eq!(
+        balances,
+        vec![(
+            user_id.clone(),
+            db.get_user_balance(&user_id).await.unwrap()
+        )]
+    );
+    assert_eq!(db.get_total_pending_withdrawals().await.unwrap(), 12_050);
+    assert_eq!(db.get_total_location_pool_balance().await.unwrap(), 40_000);
+
+    // Liabilities: 27_950 wallet + 12_050 pending + 40_000 pool
+    let lightning = MockLightning::with_balance(75_000);
+    let report = solvency::build_report(&db, &lightning).await.unwrap();
+    assert_eq!(report.total_liabilities_msats, 80_000);
+    assert_eq!(report.surplus_msats, -5_000);
+    assert!(!report.is_solvent());
+    assert_eq!(report.user_count, 1);
+    assert!(report.liabilities_snapshot.is_none());
+
+    let snapshot = solvency::take_snapshot(&db).await.unwrap();
+    assert_eq!(snapshot.sum_msats, 27_950);
+    assert_eq!(snapshot.user_count, 1);
+    let report = solvency::build_report(&db, &lightning).await.unwrap();
+    assert_eq!(report.liabilities_snapshot.unwrap().id, snapshot.id);
+
+    // Balances moving after the snapshot don't change its proofs
+    db.create_pending_withdrawal(&user_id, 5_000, 1_000, "lnbc-erin-out-2")
+        .await
+        .unwrap()
+        .unwrap();
+    let proof = solvency::snapshot_proof(&db, snapshot.clone(), &user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(proof.proof.verify());
+    assert_eq!(proof.proof.root.hash, snapshot.root_hash);
+    assert_eq!(proof.proof.balance_msats, 27_950);
+    assert!(solvency::snapshot_proof(&db, snapshot.clone(), "nobody")
+        .await
+        .unwrap()
+        .is_none());
+
+    let later = solvency::take_snapshot(&db).await.unwrap();
+    assert_eq!(later.sum_msats, 21_950);
+    assert_ne!(later.root_hash, snapshot.root_hash);
+    let history = db.list_liability_snapshots(10).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].id, later.id);
+    assert_eq!(history[1].id, snapshot.id);
+}
+
+fn new_campaign(budget_msats: i64, max_match_msats: Option<i64>) -> NewMatchingCampaign {
+    // Region around (0, 0), where setup_ledger_location puts its locations
+    NewMatchingCampaign {
+        name: "Test match".to_string(),
+        sponsor_name: "Sponsor".to_string(),
+        budget_msats,
+        match_percent: 100,
+        max_match_msats,
+        min_latitude: Some(-1.0),
+        max_latitude: Some(1.0),
+        min_longitude: Some(-1.0),
+        max_longitude: Some(1.0),
+        ends_at: Utc::now() + chrono::Duration::days(7),
+    }
+}
+
+#[tokio::test]
+async fn test_campaign_matches_donations_in_region() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, inside) = setup_ledger_location(&db, "erin").await;
+    let outside = db
+        .create_location(
+            "far away".to_string(),
+            47.0,
+            8.0,
+            None,
+            "far-secret".to_string(),
+            user_id,
+        )
+        .await
+        .unwrap()
+        .id;
+    db.update_location_status(&outside, "active").await.unwrap();
+
+    let campaign = db
+        .create_matching_campaign(&new_campaign(30_000, Some(20_000)), "lnbc-fund")
+        .await
+        .unwrap();
+    assert_eq!(campaign.status, CampaignStatus::Funding);
+
+    // Nothing is matched before the sponsor paid
+    db.create_donation("lnbc-early".to_string(), 5_000, Some(&inside))
+        .await
+        .unwrap();
+    let early = db
+        .mark_donation_received("lnbc-early")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(db
+        .apply_campaign_matches(&early.id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let campaign = db.mark_campaign_funded(&campaign.id).await.unwrap();
+    assert_eq!(campaign.status, CampaignStatus::Active);
+    assert!(db.verify_ledger().await.unwrap().is_balanced());
+
+    // Location donation inside the region is matched 1:1
+    db.create_donation("lnbc-inside".to_string(), 8_000, Some(&inside))
+        .await
+        .unwrap();
+    let donation = db
+        .mark_donation_received("lnbc-inside")
+        .await
+        .unwrap()
+        .unwrap();
+    let matches = db.apply_campaign_matches(&donation.id).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].amount_msats, 8_000);
+    assert_eq!(
+        db.get_location_donation_pool_balance(&inside)
+            .await
+            .unwrap(),
+        5_000 + 8_000 + 8_000
+    );
+
+    // Matching twice does nothing
+    assert!(db
+        .apply_campaign_matches(&donation.id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Donations outside the region are not matched
+    db.create_donation("lnbc-outside".to_string(), 8_000, Some(&outside))
+        .await
+        .unwrap();
+    let donation = db
+        .mark_donation_received("lnbc-outside")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(db
+        .apply_campaign_matches(&donation.id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Global donation: only the share of the location inside the region is matched,
+    // capped per donation
+    db.create_donation("lnbc-global".to_string(), 60_000, None)
+        .await
+        .unwrap();
+    let donation = db
+        .mark_donation_received("lnbc-global")
+        .await
+        .unwrap()
+        .unwrap();
+    let matches = db.apply_campaign_matches(&donation.id).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].location_id.as_deref(), Some(inside.as_str()));
+    assert_eq!(matches[0].amount_msats, 20_000);
+
+    // Budget exhausted: 30_000 - 8_000 - 20_000 = 2_000 left for the next donation
+    db.create_donation("lnbc-last".to_string(), 9_000, Some(&inside))
+        .await
+        .unwrap();
+    let donation = db
+        .mark_donation_received("lnbc-last")
+        .await
+        .unwrap()
+        .unwrap();
+    let matches = db.apply_campaign_matches(&donation.id).await.unwrap();
+    assert_eq!(matches[0].amount_msats, 2_000);
+
+    let campaign = db
+        .get_matching_campaign(&campaign.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(campaign.spent_msats, 30_000);
+    assert_eq!(campaign.status, CampaignStatus::Ended);
+    assert!(db
+        .list_active_matching_campaigns()
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(db.get_total_campaign_budgets().await.unwrap(), 0);
+
+    // Matching donations are listed per matched donation, not as donations of their own
+    let listed = db.list_all_received_donations(50).await.unwrap();
+    assert_eq!(listed.len(), 5);
+    let matched: i64 = db
+        .list_donation_matches()
+        .await
+        .unwrap()
+        .iter()
+        .map(|m| m.msats)
+        .sum();
+    assert_eq!(matched, 30_000);
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::Campaign(campaign.id.clone())),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_ended_campaign_donates_unspent_budget() {
+    let (db, _temp) = setup_test_db().await;
+    let (_, location_id) = setup_ledger_location(&db, "frank").await;
+
+    let campaign = db
+        .create_matching_campaign(&new_campaign(50_000, None), "lnbc-fund-frank")
+        .await
+        .unwrap();
+    // Unfunded campaigns can't be ended
+    assert!(!db.end_matching_campaign(&campaign.id).await.unwrap());
+
+    db.mark_campaign_funded(&campaign.id).await.unwrap();
+    assert!(db.end_matching_campaign(&campaign.id).await.unwrap());
+
+    // The unspent budget went to the only location in the region
+    assert_eq!(
+        db.get_location_donation_pool_balance(&location_id)
+            .await
+            .unwrap(),
+        50_000
+    );
+
+    db.create_donation("lnbc-frank".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    let donation = db
+        .mark_donation_received("lnbc-frank")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(db
+        .apply_campaign_matches(&donation.id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(db.get_total_campaign_budgets().await.unwrap(), 0);
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(report.balance_of(&LedgerAccount::Campaign(campaign.id)), 0);
+}
+
+#[tokio::test]
+async fn test_missed_donations_matched_after_deadline() {
+    let (db, _temp) = setup_test_db().await;
+    let (_, location_id) = setup_ledger_location(&db, "hank").await;
+
+    db.create_donation("lnbc-hank-early".to_string(), 5_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-hank-early").await.unwrap();
+
+    let campaign = db
+        .create_matching_campaign(&new_campaign(50_000, None), "lnbc-fund-hank")
+        .await
+        .unwrap();
+    db.mark_campaign_funded(&campaign.id).await.unwrap();
+
+    // Received while the campaign service wasn't listening
+    db.create_donation("lnbc-hank".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    let missed = db
+        .mark_donation_received("lnbc-hank")
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The deadline passes before the donation is matched
+    db.pool()
+        .execute(
+            sqlx::query("UPDATE matching_campaigns SET ends_at = ? WHERE id = ?")
+                .bind(Utc::now())
+                .bind(&campaign.id),
+        )
+        .await
+        .unwrap();
+
+    // Only the donation received while the campaign ran is still to be matched
+    let unmatched = db.list_unmatched_donations().await.unwrap();
+    assert_eq!(unmatched.len(), 1);
+    assert_eq!(unmatched[0].id, missed.id);
+    let matches = db.apply_campaign_matches(&missed.id).await.unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].amount_msats, 10_000);
+    assert!(db.list_unmatched_donations().await.unwrap().is_empty());
+
+    // Ending the campaign donates the rest of its budget
+    assert_eq!(db.end_due_matching_campaigns().await.unwrap(), 1);
+    let campaign = db
+        .get_matching_campaign(&campaign.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(campaign.status, CampaignStatus::Ended);
+    assert_eq!(
+        db.get_location_donation_pool_balance(&location_id)
+            .await
+            .unwrap(),
+        5_000 + 10_000 + 50_000
+    );
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+}
+
+#[tokio::test]
+async fn test_unfunded_campaign_expires() {
+    let (db, _temp) = setup_test_db().await;
+
+    let campaign = db
+        .create_matching_campaign(&new_campaign(50_000, None), "lnbc-fund-gina")
+        .await
+        .unwrap();
+    assert!(db.expire_matching_campaign(&campaign.id).await.unwrap());
+    assert!(!db.expire_matching_campaign(&campaign.id).await.unwrap());
+
+    // Expired campaigns are no longer awaited and can't be funded anymore
+    assert!(db
+        .list_funding_matching_campaigns()
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db.mark_campaign_funded(&campaign.id).await.is_err());
+    let campaign = db
+        .get_matching_campaign(&campaign.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(campaign.status, CampaignStatus::Expired);
+    assert_eq!(db.get_total_campaign_budgets().await.unwrap(), 0);
+    assert!(db.verify_ledger().await.unwrap().is_balanced());
+}
+
+#[tokio::test]
+async fn test_donation_comment_copied_to_splits() {
+    let (db, _temp) = setup_test_db().await;
+
+    let auth = AuthMethod::Password {
+        password_hash: "hash".to_string(),
+    };
+    let user = db
+        .create_user("owner".to_string(), None, auth)
+        .await
+        .unwrap();
+    let location = db
+        .create_location(
+            "Comment Test".to_string(),
+            0.0,
+            0.0,
+            None,
+            "secret".to_string(),
+            user.id,
+        )
+        .await
+        .unwrap();
+    db.update_location_status(&location.id, "active")
+        .await
+        .unwrap();
+
+    let donation = db
+        .create_lnurl_pay_donation(
+            "lnbc21k1".to_string(),
+            21000,
+            None,
+            Some("keep hunting!"),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(donation.comment.as_deref(), Some("keep hunting!"));
+
+    db.mark_donation_received("lnbc21k1").await.unwrap();
+
+    // The location's share of the global donation carries the comment
+    let donations = db.list_location_donations(&location.id).await.unwrap();
+    assert_eq!(donations.len(), 1);
+    assert_eq!(donations[0].amount_msats, 21000);
+    assert_eq!(donations[0].comment.as_deref(), Some("keep hunting!"));
+
+    // Donations made without a comment have none
+    let plain = db
+        .create_donation("lnbc1k1".to_string(), 1000, Some(&location.id))
+        .await
+        .unwrap();
+    assert_eq!(plain.comment, None);
+}
+
+#[tokio::test]
+async fn test_wallet_invoice_credits_user_once() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, _) = setup_ledger_location(&db, "dave").await;
+
+    let invoice = db
+        .create_wallet_invoice(&user_id, "lnbc-tip", 21_000, Some("nice find"))
+        .await
+        .unwrap();
+    assert_eq!(invoice.comment.as_deref(), Some("nice find"));
+    assert_eq!(db.list_pending_wallet_invoices().await.unwrap().len(), 1);
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 0);
+
+    let received = db.mark_wallet_invoice_received("lnbc-tip").await.unwrap();
+    assert!(received.is_some());
+    // A second notification for the same payment credits nothing
+    let again = db.mark_wallet_invoice_received("lnbc-tip").await.unwrap();
+    assert!(again.is_none());
+
+    assert!(db.list_pending_wallet_invoices().await.unwrap().is_empty());
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 21_000);
+    let transactions = db.get_user_transactions(&user_id, 10).await.unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert!(transactions[0].is_receive());
+
+    // Received sats can be withdrawn like collected ones
+    let withdrawal = db
+        .create_pending_withdrawal(&user_id, 10_000, 1_000, "lnbc-out")
+        .await
+        .unwrap();
+    assert!(withdrawal.is_some());
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::UserWallet(user_id.clone())),
+        db.get_user_balance(&user_id).await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_wallet_withdraw_link_rotation() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, _) = setup_ledger_location(&db, "erin").await;
+    assert!(db
+        .get_active_wallet_withdraw_link(&user_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let first = db.create_wallet_withdraw_link(&user_id).await.unwrap();
+    let found = db
+        .get_wallet_withdraw_link_by_key(&first.link_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, first.id);
+    assert_eq!(found.user_id, user_id);
+
+    // Regenerating revokes the old link
+    let second = db.create_wallet_withdraw_link(&user_id).await.unwrap();
+    assert_ne!(second.link_key, first.link_key);
+    assert!(db
+        .get_wallet_withdraw_link_by_key(&first.link_key)
+        .await
+        .unwrap()
+        .is_none());
+    let active = db
+        .get_active_wallet_withdraw_link(&user_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(active.id, second.id);
+
+    // Revoking leaves no working link
+    let result = db.revoke_wallet_withdraw_link(&user_id).await.unwrap();
+    assert_eq!(result.rows_affected(), 1);
+    assert!(db
+        .get_wallet_withdraw_link_by_key(&second.link_key)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(db
+        .get_active_wallet_withdraw_link(&user_id)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_hunt_completion_bonus() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (user_id, location_a) = setup_ledger_location(&db, "frank").await;
+    let (_, location_b) = setup_ledger_location(&db, "grace").await;
+    for (invoice, location_id) in [("lnbc-a", &location_a), ("lnbc-b", &location_b)] {
+        db.create_donation(invoice.to_string(), 10_000, Some(location_id.as_str()))
+            .await
+            .unwrap();
+        db.mark_donation_received(invoice).await.unwrap();
+    }
+
+    let hunt = db
+        .create_hunt(
+            "Trail",
+            None,
+            true,
+            20_000,
+            &user_id,
+            &[location_a.clone(), location_b.clone()],
+        )
+        .await
+        .unwrap();
+    let stops = db.get_hunt_locations(&hunt.id).await.unwrap();
+    assert_eq!(stops[0].id, location_a);
+    assert_eq!(stops[1].id, location_b);
+
+    // The pool only holds part of the bonus, which is all that can be paid out
+    db.create_hunt_donation("lnbc-hunt".to_string(), 15_000, &hunt.id)
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-hunt").await.unwrap();
+    assert_eq!(db.get_hunt_pool_balance(&hunt.id).await.unwrap(), 15_000);
+
+    let mut collected = 0;
+    for location_id in [&location_a, &location_b] {
+        let now = Utc::now().to_rfc3339();
+        insert_test_scan(&db, location_id, &user_id, &now).await;
+        let scan = db
+            .get_last_scan_for_location(location_id)
+            .await
+            .unwrap()
+            .unwrap();
+        match db
+            .claim_from_scan(&scan.id, &user_id, &config, &ClaimRules::default())
+            .await
+            .unwrap()
+        {
+            ClaimResult::Success { msats, .. } => collected += msats,
+            other => panic!("unexpected claim result: {:?}", other),
+        }
+        if location_id == &location_a {
+            assert_eq!(
+                db.get_hunt_progress(&hunt, &user_id).await.unwrap(),
+                vec![true, false]
+            );
+            assert!(db.list_hunt_completions(&hunt.id).await.unwrap().is_empty());
+        }
+    }
+
+    assert_eq!(
+        db.get_hunt_progress(&hunt, &user_id).await.unwrap(),
+        vec![true, true]
+    );
+    let completions = db.list_hunt_completions(&hunt.id).await.unwrap();
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].user_id, user_id);
+    assert_eq!(completions[0].bonus_msats, 15_000);
+    assert_eq!(db.get_hunt_pool_balance(&hunt.id).await.unwrap(), 0);
+    assert_eq!(
+        db.get_user_balance(&user_id).await.unwrap(),
+        collected + 15_000
+    );
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::HuntPool(hunt.id.clone())),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_collection_completes_hunt() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (user_id, location_id) = setup_ledger_location(&db, "heidi").await;
+    db.create_nfc_card(
+        location_id.clone(),
+        "k0".to_string(),
+        "k1".to_string(),
+        "k2".to_string(),
+        "k3".to_string(),
+        "k4".to_string(),
+    )
+    .await
+    .unwrap();
+    db.create_donation("lnbc-heidi".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-heidi").await.unwrap();
+
+    let hunt = db
+        .create_hunt(
+            "Single stop",
+            None,
+            false,
+            1_000,
+            &user_id,
+            std::slice::from_ref(&location_id),
+        )
+        .await
+        .unwrap();
+    db.create_hunt_donation("lnbc-heidi-hunt".to_string(), 1_000, &hunt.id)
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-heidi-hunt").await.unwrap();
+
+    assert!(matches!(
+        db.claim_collection(
+            &location_id,
+            "collector",
+            1,
+            &config,
+            &ClaimRules::default()
+        )
+        .await
+        .unwrap(),
+        ClaimResult::Success { .. }
+    ));
+
+    // The tap is recorded as a claimed scan, which completes the hunt
+    let scans = db
+        .get_scans_with_location_for_user("collector", 10)
+        .await
+        .unwrap();
+    assert_eq!(scans.len(), 1);
+    assert!(scans[0].claimed_at.is_some());
+    let completions = db.list_hunt_completions(&hunt.id).await.unwrap();
+    assert_eq!(completions.len(), 1);
+    assert_eq!(completions[0].user_id, "collector");
+    assert_eq!(completions[0].bonus_msats, 1_000);
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+}
+
+#[tokio::test]
+async fn test_location_schedule() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (user_id, location_id) = setup_ledger_location(&db, "heidi").await;
+    db.create_donation("lnbc-heidi".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-heidi").await.unwrap();
+
+    // An event starting in an hour hides the location until then
+    let opens_at = Utc::now() + chrono::Duration::hours(1);
+    let schedule = db
+        .create_schedule(&NewSchedule {
+            location_id: Some(location_id.clone()),
+            hunt_id: None,
+            starts_at: opens_at,
+            ends_at: opens_at + chrono::Duration::hours(8),
+            recurrence: Recurrence::Once,
+            created_by: user_id.clone(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(db.apply_schedules(Utc::now()).await.unwrap(), (0, 1));
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+    assert!(location.is_scheduled());
+    assert_eq!(db.list_scheduled_locations().await.unwrap().len(), 1);
+
+    // Claims are refused outside the window
+    let now = Utc::now().to_rfc3339();
+    insert_test_scan(&db, &location_id, &user_id, &now).await;
+    let scan = db
+        .get_last_scan_for_location(&location_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        db.claim_from_scan(&scan.id, &user_id, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::OutsideSchedule
+    ));
+
+    // The location is shown again once the window opens
+    assert_eq!(
+        db.apply_schedules(opens_at + chrono::Duration::minutes(1))
+            .await
+            .unwrap(),
+        (1, 0)
+    );
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+    assert!(location.is_active());
+
+    // Without the schedule the location can be claimed any time
+    assert!(db.delete_schedule(&schedule.id).await.unwrap());
+    assert!(db
+        .get_location_schedules(&location_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        db.claim_from_scan(&scan.id, &user_id, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::Success { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_withdrawal_checks_schedule_and_status() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (user_id, location_id) = setup_ledger_location(&db, "nina").await;
+    db.create_nfc_card(
+        location_id.clone(),
+        "k0".to_string(),
+        "k1".to_string(),
+        "k2".to_string(),
+        "k3".to_string(),
+        "k4".to_string(),
+    )
+    .await
+    .unwrap();
+    db.create_donation("lnbc-nina".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-nina").await.unwrap();
+
+    // Withdrawing before the event starts is refused, even if the location
+    // wasn't switched to scheduled yet
+    let opens_at = Utc::now() + chrono::Duration::hours(1);
+    let schedule = db
+        .create_schedule(&NewSchedule {
+            location_id: Some(location_id.clone()),
+            hunt_id: None,
+            starts_at: opens_at,
+            ends_at: opens_at + chrono::Duration::hours(8),
+            recurrence: Recurrence::Once,
+            created_by: user_id.clone(),
+        })
+        .await
+        .unwrap();
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 5_000, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::OutsideSchedule
+    ));
+    assert!(db.delete_schedule(&schedule.id).await.unwrap());
+
+    // Deactivated locations can't be withdrawn from, collected or claimed
+    db.update_location_status(&location_id, "deactivated")
+        .await
+        .unwrap();
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 5_000, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::LocationInactive
+    ));
+    assert!(matches!(
+        db.claim_collection(&location_id, &user_id, 1, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::LocationInactive
+    ));
+    insert_test_scan(&db, &location_id, &user_id, &Utc::now().to_rfc3339()).await;
+    let scan = db
+        .get_last_scan_for_location(&location_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        db.claim_from_scan(&scan.id, &user_id, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::LocationInactive
+    ));
+
+    db.update_location_status(&location_id, "active")
+        .await
+        .unwrap();
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 5_000, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::Success { msats: 5_000, .. }
+    ));
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 5_000, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::AlreadyClaimed
+    ));
+}
+
+#[tokio::test]
+async fn test_withdrawal_claim_rules() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let rules = ClaimRules {
+        cooldown_days: Some(1),
+        max_claims_per_month: None,
+        first_claim_bonus_percent: Some(50),
+    };
+    let (_, location_id) = setup_ledger_location(&db, "oscar").await;
+    db.create_nfc_card(
+        location_id.clone(),
+        "k0".to_string(),
+        "k1".to_string(),
+        "k2".to_string(),
+        "k3".to_string(),
+        "k4".to_string(),
+    )
+    .await
+    .unwrap();
+    db.create_donation("lnbc-oscar".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-oscar").await.unwrap();
+
+    // Withdrawals get no first claim bonus
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 5_000, &config, &rules)
+            .await
+            .unwrap(),
+        ClaimResult::Success { msats: 5_000, .. }
+    ));
+
+    // Any further withdrawal waits for the cooldown, whoever taps the sticker
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 2, 5_000, &config, &rules)
+            .await
+            .unwrap(),
+        ClaimResult::Cooldown { .. }
+    ));
+
+    // Only the first withdrawal was taken from the pool
+    assert_eq!(
+        db.get_location_donation_pool_balance(&location_id)
+            .await
+            .unwrap(),
+        5_000
+    );
+}
+
+#[tokio::test]
+async fn test_withdrawal_claim_policy_share() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (_, location_id) = setup_ledger_location(&db, "paula").await;
+    db.create_nfc_card(
+        location_id.clone(),
+        "k0".to_string(),
+        "k1".to_string(),
+        "k2".to_string(),
+        "k3".to_string(),
+        "k4".to_string(),
+    )
+    .await
+    .unwrap();
+    db.create_donation("lnbc-paula".to_string(), 100_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-paula").await.unwrap();
+    db.update_location_claim_policy(&location_id, ClaimPolicy::FixedFraction)
+        .await
+        .unwrap();
+
+    // A withdrawal gets a tenth of the 50_000 msats claimable
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+    assert!(matches!(
+        db.get_withdrawal_share(&location).await.unwrap(),
+        Ok(fraction) if fraction == 0.1
+    ));
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 50_000, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::OverClaimable {
+            claimable_msats: 5_000
+        }
+    ));
+    assert!(matches!(
+        db.claim_withdrawal(&location_id, 1, 5_000, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::Success { msats: 5_000, .. }
+    ));
+
+    // The rest of the fill is kept for the next withdrawal
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+    let pool_msats = db
+        .get_location_donation_pool_balance(&location_id)
+        .await
+        .unwrap();
+    assert_eq!(pool_msats, 95_000);
+    let balance_msats = satshunt::balance::compute_balance_msats(
+        pool_msats,
+        location.last_withdraw_at,
+        location.created_at,
+        &config,
+    );
+    assert!(balance_msats > 40_000, "{}", balance_msats);
+}
+
+#[tokio::test]
+async fn test_collection_claim_policy_share() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (_, location_id) = setup_ledger_location(&db, "quinn").await;
+    db.create_nfc_card(
+        location_id.clone(),
+        "k0".to_string(),
+        "k1".to_string(),
+        "k2".to_string(),
+        "k3".to_string(),
+        "k4".to_string(),
+    )
+    .await
+    .unwrap();
+    db.create_donation("lnbc-quinn".to_string(), 100_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-quinn").await.unwrap();
+    db.update_location_claim_policy(&location_id, ClaimPolicy::FixedFraction)
+        .await
+        .unwrap();
+
+    // A collection gets a tenth of the 50_000 msats claimable, not all of it
+    assert!(matches!(
+        db.claim_collection(
+            &location_id,
+            "collector",
+            1,
+            &config,
+            &ClaimRules::default()
+        )
+        .await
+        .unwrap(),
+        ClaimResult::Success { msats: 5_000, .. }
+    ));
+    assert_eq!(db.get_user_balance("collector").await.unwrap(), 5_000);
+}
+
+/// Scan a location and claim right away
+async fn scan_and_claim(
+    db: &Database,
+    location_id: &str,
+    user_id: &str,
+    config: &BalanceConfig,
+    rules: &ClaimRules,
+) -> ClaimResult {
+    // Let the location refill completely since the last claim
+    db.pool()
+        .execute(
+            sqlx::query("UPDATE locations SET last_withdraw_at = NULL WHERE id = ?")
+                .bind(location_id),
+        )
+        .await
+        .unwrap();
+    insert_test_scan(db, location_id, user_id, &Utc::now().to_rfc3339()).await;
+    let scan = db
+        .get_last_scan_for_location(location_id)
+        .await
+        .unwrap()
+        .unwrap();
+    db.claim_from_scan(&scan.id, user_id, config, rules)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_claim_rules() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let global = ClaimRules {
+        cooldown_days: Some(2),
+        max_claims_per_month: None,
+        first_claim_bonus_percent: Some(50),
+    };
+    let (user_id, location_id) = setup_ledger_location(&db, "ivan").await;
+    db.create_donation("lnbc-ivan".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-ivan").await.unwrap();
+
+    // The first claim gets half of the claimable balance on top
+    assert!(db
+        .get_user_claim_times(&user_id, &location_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(matches!(
+        scan_and_claim(&db, &location_id, &user_id, &config, &global).await,
+        ClaimResult::Success { msats: 7_500, .. }
+    ));
+    assert!(db.has_user_claimed(&user_id).await.unwrap());
+
+    // Claiming again right away is refused by the cooldown
+    assert!(matches!(
+        scan_and_claim(&db, &location_id, &user_id, &config, &global).await,
+        ClaimResult::Cooldown { until } if until > Utc::now() + chrono::Duration::days(1)
+    ));
+
+    // The location turns the cooldown off but allows one claim per month
+    let location_rules = ClaimRules {
+        cooldown_days: Some(0),
+        max_claims_per_month: Some(1),
+        first_claim_bonus_percent: None,
+    };
+    db.set_location_claim_rules(&location_id, &location_rules)
+        .await
+        .unwrap();
+    assert_eq!(
+        db.get_location_claim_rules(&location_id).await.unwrap(),
+        location_rules
+    );
+    assert!(matches!(
+        scan_and_claim(&db, &location_id, &user_id, &config, &global).await,
+        ClaimResult::MonthlyLimitReached { limit: 1, .. }
+    ));
+
+    // With room for a second claim there is no first claim bonus anymore
+    db.set_location_claim_rules(
+        &location_id,
+        &ClaimRules {
+            max_claims_per_month: Some(2),
+            ..location_rules
+        },
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        scan_and_claim(&db, &location_id, &user_id, &config, &global).await,
+        ClaimResult::Success { msats: 1_250, .. }
+    ));
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::UserWallet(user_id.clone())),
+        8_750
+    );
+}
+
+#[tokio::test]
+async fn test_split_claim_policy() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (alice, location_id) = setup_ledger_location(&db, "kate").await;
+    let (bob, _) = setup_ledger_location(&db, "leo").await;
+    db.create_donation("lnbc-kate".to_string(), 10_000, Some(&location_id))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-kate").await.unwrap();
+    db.update_location_claim_policy(&location_id, ClaimPolicy::Split)
+        .await
+        .unwrap();
+
+    let now = Utc::now();
+    insert_test_scan(&db, &location_id, &alice, &now.to_rfc3339()).await;
+    insert_test_scan(
+        &db,
+        &location_id,
+        &bob,
+        &(now + chrono::Duration::seconds(1)).to_rfc3339(),
+    )
+    .await;
+    let scans = db
+        .get_scans_with_user_for_location(&location_id)
+        .await
+        .unwrap();
+    let scan_of = |user_id: &str| {
+        scans
+            .iter()
+            .find(|s| s.user_id == user_id)
+            .map(|s| s.id.clone())
+            .unwrap()
+    };
+
+    // Alice scanned before bob but still gets half instead of losing the race
+    assert!(matches!(
+        db.claim_from_scan(&scan_of(&alice), &alice, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::Success { msats: 2_500, .. }
+    ));
+
+    // Half of the fill is left for bob, from the smaller pool
+    assert!(matches!(
+        db.claim_from_scan(&scan_of(&bob), &bob, &config, &ClaimRules::default())
+            .await
+            .unwrap(),
+        ClaimResult::Success { msats: 1_875, .. }
+    ));
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+}
+
+#[tokio::test]
+async fn test_hints() {
+    let (db, _temp) = setup_test_db().await;
+    let (creator, location_id) = setup_ledger_location(&db, "mia").await;
+    let (finder, _) = setup_ledger_location(&db, "ned").await;
+    db.create_wallet_invoice(&finder, "lnbc-ned", 30_000, None)
+        .await
+        .unwrap();
+    db.mark_wallet_invoice_received("lnbc-ned").await.unwrap();
+
+    let new_hint = |unlock_kind| NewHint {
+        location_id: location_id.clone(),
+        text: "Look under the bench".to_string(),
+        unlock_kind,
+        unlock_after_days: None,
+        price_msats: None,
+        riddle: None,
+        riddle_answer: None,
+    };
+    let timed = db
+        .create_hint(&NewHint {
+            unlock_after_days: Some(7),
+            ..new_hint(HintUnlock::Time)
+        })
+        .await
+        .unwrap();
+    let paid = db
+        .create_hint(&NewHint {
+            price_msats: Some(20_000),
+            ..new_hint(HintUnlock::Payment)
+        })
+        .await
+        .unwrap();
+    let riddle = db
+        .create_hint(&NewHint {
+            riddle: Some("What has legs but can't walk?".to_string()),
+            riddle_answer: Some("A bench".to_string()),
+            ..new_hint(HintUnlock::Riddle)
+        })
+        .await
+        .unwrap();
+    let hints = db.list_location_hints(&location_id).await.unwrap();
+    assert_eq!(
+        hints.iter().map(|h| h.position).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+
+    // Buying twice only charges once
+    assert!(db.buy_hint(&paid, &finder).await.unwrap());
+    assert!(db.buy_hint(&paid, &finder).await.unwrap());
+    assert_eq!(db.get_user_balance(&finder).await.unwrap(), 10_000);
+    assert_eq!(
+        db.get_location_donation_pool_balance(&location_id)
+            .await
+            .unwrap(),
+        20_000
+    );
+
+    // The creator has no sats to pay with
+    assert!(!db.buy_hint(&paid, &creator).await.unwrap());
+
+    assert!(riddle.is_answer("  a   BENCH "));
+    db.unlock_hint(&riddle.id, &finder).await.unwrap();
+    db.unlock_hint(&riddle.id, &finder).await.unwrap();
+
+    let mut unlocked = db
+        .list_unlocked_hint_ids(&finder, &location_id)
+        .await
+        .unwrap();
+    unlocked.sort();
+    let mut expected = vec![paid.id.clone(), riddle.id.clone()];
+    expected.sort();
+    assert_eq!(unlocked, expected);
+    assert!(db
+        .list_unlocked_hint_ids(&creator, &location_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert!(db.delete_hint(&timed.id).await.unwrap());
+    assert!(!db.delete_hint(&timed.id).await.unwrap());
+
+    let report = db.verify_ledger().await.unwrap();
+    assert!(report.is_balanced(), "{:?}", report.discrepancies);
+    assert_eq!(
+        report.balance_of(&LedgerAccount::LocationPool(location_id)),
+        20_000
+    );
+}
+
+#[tokio::test]
+async fn test_location_disclosure() {
+    let (db, _temp) = setup_test_db().await;
+    let (_, location_id) = setup_ledger_location(&db, "olga").await;
+    let (hunter, other_location) = setup_ledger_location(&db, "paul").await;
+
+    let area = SearchArea {
+        latitude: 0.001,
+        longitude: -0.001,
+        radius_m: 250,
+    };
+    db.update_location_disclosure(&location_id, Disclosure::Fuzzed, Some(&area))
+        .await
+        .unwrap();
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+    assert_eq!(location.disclosure, Disclosure::Fuzzed);
+    assert_eq!(location.search_area(), Some(area));
+
+    // Switching modes keeps the drawn search area
+    db.update_location_disclosure(&location_id, Disclosure::Hidden, None)
+        .await
+        .unwrap();
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+    assert_eq!(location.disclosure, Disclosure::Hidden);
+    assert_eq!(location.search_area(), Some(area));
+
+    let hint = |location_id: &str, unlock_kind, unlock_after_days| NewHint {
+        location_id: location_id.to_string(),
+        text: "Near the fountain".to_string(),
+        unlock_kind,
+        unlock_after_days,
+        price_msats: None,
+        riddle: Some("Riddle".to_string()),
+        riddle_answer: Some("Answer".to_string()),
+    };
+    let riddle = db
+        .create_hint(&hint(&location_id, HintUnlock::Riddle, None))
+        .await
+        .unwrap();
+    // Created in 2020, so a 7 day hint is long unlocked for everyone
+    db.create_hint(&hint(&other_location, HintUnlock::Time, Some(7)))
+        .await
+        .unwrap();
+    let unlocked = db.list_hint_unlocked_location_ids(&hunter).await.unwrap();
+    assert!(!unlocked.contains(&location_id));
+    assert!(unlocked.contains(&other_location));
+
+    db.unlock_hint(&riddle.id, &hunter).await.unwrap();
+    let unlocked = db.list_hint_unlocked_location_ids(&hunter).await.unwrap();
+    assert!(unlocked.contains(&location_id));
+}
+
+#[tokio::test]
+async fn test_location_logs() {
+    let (db, _temp) = setup_test_db().await;
+    let (owner, location_id) = setup_ledger_location(&db, "quinn").await;
+    let (hunter, _) = setup_ledger_location(&db, "rosa").await;
+    let location = db.get_location(&location_id).await.unwrap().unwrap();
+
+    let found = db
+        .create_location_log(
+            &location,
+            &hunter,
+            LogKind::Found,
+            "Nice spot",
+            &["log-1.jpg".to_string(), "log-2.jpg".to_string()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(found.kind, LogKind::Found);
+    assert_eq!(found.username.as_deref(), Some("rosa"));
+    assert_eq!(db.get_log_photos(&found.id).await.unwrap().len(), 2);
+
+    // Maintenance logs notify the owner, but not when the owner writes them
+    let broken = db
+        .create_location_log(
+            &location,
+            &hunter,
+            LogKind::NeedsMaintenance,
+            "Tag is wet",
+            &[],
+        )
+        .await
+        .unwrap();
+    db.create_location_log(&location, &owner, LogKind::NeedsMaintenance, "", &[])
+        .await
+        .unwrap();
+    let notifications = db.list_notifications(&owner, 20).await.unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert!(notifications[0].message.contains("Tag is wet"));
+    assert!(!notifications[0].is_read());
+    assert!(db.list_notifications(&hunter, 20).await.unwrap().is_empty());
+
+    db.mark_notifications_read(&owner).await.unwrap();
+    assert!(db.list_notifications(&owner, 20).await.unwrap()[0].is_read());
+
+    // Hidden logs are only listed for moderators
+    db.set_location_log_hidden(&broken.id, Some(&owner))
+        .await
+        .unwrap();
+    assert_eq!(
+        db.count_location_logs(&location_id, false).await.unwrap(),
+        2
+    );
+    assert_eq!(db.count_location_logs(&location_id, true).await.unwrap(), 3);
+    let visible = db
+        .list_location_logs(&location_id, false, 10, 0)
+        .await
+        .unwrap();
+    assert!(visible.iter().all(|log| log.id != broken.id));
+    let page = db
+        .list_location_logs(&location_id, true, 2, 2)
+        .await
+        .unwrap();
+    assert_eq!(page.len(), 1);
+
+    db.set_location_log_hidden(&broken.id, None).await.unwrap();
+    assert_eq!(
+        db.count_location_logs(&location_id, false).await.unwrap(),
+        3
+    );
+
+    // Deleting a log removes its photos
+    assert!(db.delete_location_log(&found.id).await.unwrap());
+    assert!(!db.delete_location_log(&found.id).await.unwrap());
+    assert!(db
+        .list_location_log_photos(&location_id)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_leaderboards() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (_, old_location) = setup_ledger_location(&db, "sven").await;
+    let (owner, new_location) = setup_ledger_location(&db, "uwe").await;
+    let (hunter, _) = setup_ledger_location(&db, "tina").await;
+    let anon = uuid::Uuid::new_v4().to_string();
+    db.create_anonymous_user(&anon).await.unwrap();
+    db.create_donation("lnbc-uwe".to_string(), 10_000, Some(&new_location))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-uwe").await.unwrap();
+
+    // Tina found the old location years ago and is first to find the new one,
+    // the owner testing the tag doesn't count
+    insert_test_scan(&db, &new_location, &owner, "2020-06-01T00:00:00Z").await;
+    insert_test_scan(&db, &old_location, &hunter, "2021-01-01T00:00:00Z").await;
+    let claimed_msats =
+        match scan_and_claim(&db, &new_location, &hunter, &config, &ClaimRules::default()).await {
+            ClaimResult::Success { msats, .. } => msats,
+            other => panic!("claim failed: {:?}", other),
+        };
+    insert_test_scan(&db, &old_location, &anon, &Utc::now().to_rfc3339()).await;
+
+    let now = Utc::now();
+    let week = Period::Week.start(now);
+    let stats = db.get_hunter_stats(week, None).await.unwrap();
+    // The anonymous hunter hasn't opted in
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].user_id, hunter);
+    assert_eq!(stats[0].msats_claimed, claimed_msats);
+    assert_eq!(stats[0].locations_found, 1);
+    assert_eq!(stats[0].first_finds, 1);
+
+    let since_2000 = chrono::DateTime::parse_from_rfc3339("2000-01-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let stats = db.get_hunter_stats(since_2000, None).await.unwrap();
+    assert_eq!(stats[0].locations_found, 2);
+    assert_eq!(stats[0].first_finds, 2);
+
+    assert!(db.set_leaderboard_opt_in(&anon, true).await.unwrap());
+    let stats = db.get_hunter_stats(week, None).await.unwrap();
+    assert_eq!(stats.len(), 2);
+
+    // All locations are at 0, 0
+    let region = db
+        .create_region("North", 10.0, 20.0, 10.0, 20.0)
+        .await
+        .unwrap();
+    assert!(db
+        .get_hunter_stats(week, Some(&region))
+        .await
+        .unwrap()
+        .is_empty());
+
+    leaderboard::refresh(&db, now).await.unwrap();
+    let sats = db
+        .get_leaderboard(Period::Week, "", Metric::Sats)
+        .await
+        .unwrap();
+    assert_eq!(sats.len(), 1);
+    assert_eq!(sats[0].value, claimed_msats / 1000);
+    let found = db
+        .get_leaderboard(Period::Week, "", Metric::Locations)
+        .await
+        .unwrap();
+    assert_eq!(found.iter().map(|e| e.rank).collect::<Vec<_>>(), vec![1, 1]);
+    assert!(db
+        .get_leaderboard(Period::Week, &region.id, Metric::Locations)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Opting out takes effect before the next refresh
+    assert!(db.set_leaderboard_opt_in(&anon, false).await.unwrap());
+    let found = db
+        .get_leaderboard(Period::Week, "", Metric::Locations)
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].user_id, hunter);
+
+    assert!(db.delete_region(&region.id).await.unwrap());
+    assert!(db.list_regions().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_achievements() {
+    let (db, _temp) = setup_test_db().await;
+    let (owner, location) = setup_ledger_location(&db, "vera").await;
+    let hunter = uuid::Uuid::new_v4().to_string();
+    db.create_anonymous_user(&hunter).await.unwrap();
+
+    // Scanning before the location last went live isn't early
+    insert_test_scan(&db, &location, &hunter, "2021-01-01T00:00:00Z").await;
+    let awarded = achievements::check_user(&db, &hunter).await.unwrap();
+    assert_eq!(awarded, vec![Badge::FirstFind]);
+    let notifications = db.list_notifications(&hunter, 10).await.unwrap();
+    assert_eq!(notifications.len(), 1);
+    assert!(notifications[0].message.contains("FIRST FIND"));
+
+    // Badges are only awarded once
+    assert!(achievements::check_user(&db, &hunter)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // The location went live when it was set up
+    insert_test_scan(&db, &location, &hunter, &Utc::now().to_rfc3339()).await;
+    assert_eq!(
+        achievements::check_user(&db, &hunter).await.unwrap(),
+        vec![Badge::EarlyBird]
+    );
+
+    // Only received donations count
+    let donation = db
+        .create_donation("lnbc-vera".to_string(), 10_000_000, Some(&location))
+        .await
+        .unwrap();
+    db.set_donation_user(&donation.id, &hunter).await.unwrap();
+    assert!(achievements::check_user(&db, &hunter)
+        .await
+        .unwrap()
+        .is_empty());
+    db.mark_donation_received("lnbc-vera").await.unwrap();
+    assert_eq!(
+        achievements::check_user(&db, &hunter).await.unwrap(),
+        vec![Badge::Patron]
+    );
+
+    // The owner scanning their own location doesn't count
+    insert_test_scan(&db, &location, &owner, "2021-01-01T00:00:00Z").await;
+    assert_eq!(
+        achievements::check_user(&db, &owner).await.unwrap(),
+        vec![Badge::Creator]
+    );
+
+    let badges = db.list_user_badges(&hunter).await.unwrap();
+    let badges: Vec<Badge> = badges.into_iter().map(|b| b.badge).collect();
+    assert_eq!(
+        badges,
+        vec![Badge::FirstFind, Badge::EarlyBird, Badge::Patron]
+    );
+}
+
+#[tokio::test]
+async fn test_teams() {
+    let (db, _temp) = setup_test_db().await;
+    let config = BalanceConfig {
+        time_to_full_days: 1,
+        max_fill_percentage: 0.5,
+    };
+    let (_owner, location) = setup_ledger_location(&db, "wendy").await;
+    db.create_donation("lnbc-wendy".to_string(), 10_000, Some(&location))
+        .await
+        .unwrap();
+    db.mark_donation_received("lnbc-wendy").await.unwrap();
+
+    let mut hunters = Vec::new();
+    for name in ["xena", "yuri"] {
+        let auth = AuthMethod::Password {
+            password_hash: "hash".to_string(),
+        };
+        let user = db.create_user(name.to_string(), None, auth).await.unwrap();
+        hunters.push(user.id);
+    }
+    let (xena, yuri) = (&hunters[0], &hunters[1]);
+
+    let team = db.create_team("Explorers", xena).await.unwrap();
+    assert!(!team.wallet_enabled);
+    assert_eq!(
+        db.get_team_membership(xena).await.unwrap().unwrap().role,
+        TeamRole::Owner
+    );
+    let invited = db
+        .get_team_by_invite_code(&team.invite_code)
+        .await
+        .unwrap()
+        .unwrap();
+    db.join_team(&invited.id, yuri).await.unwrap();
+    assert_eq!(db.list_team_members(&team.id).await.unwrap().len(), 2);
+
+    // Users are in at most one team
+    assert!(db.join_team(&team.id, yuri).await.is_err());
+    assert!(db.create_team("Others", yuri).await.is_err());
+
+    // With the team wallet on, claims of members land in the team wallet
+    db.set_team_wallet_enabled(&team.id, true).await.unwrap();
+    let ClaimResult::Success { msats, .. } =
+        scan_and_claim(&db, &location, yuri, &config, &ClaimRules::default()).await
+    else {
+        panic!("claim failed");
+    };
+    assert_eq!(db.get_team_balance(&team.id).await.unwrap(), msats);
+    assert_eq!(db.get_user_balance(yuri).await.unwrap(), 0);
+    let transactions = db.get_team_transactions(&team.id, 10).await.unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert!(transactions[0].is_collect());
+    assert_eq!(transactions[0].user_id, *yuri);
+
+    // A team with sats in its wallet can't be deleted
+    assert!(db.delete_team(&team.id).await.is_err());
+
+    // Withdrawals move sats into the member's own wallet
+    assert!(!db
+        .withdraw_from_team_wallet(&team.id, xena, msats + 1_000)
+        .await
+        .unwrap());
+    assert!(db
+        .withdraw_from_team_wallet(&team.id, xena, msats)
+        .await
+        .unwrap());
+    assert_eq!(db.get_team_balance(&team.id).await.unwrap(), 0);
+    assert_eq!(db.get_user_balance(xena).await.unwrap(), msats);
+    assert!(db.verify_ledger().await.unwrap().is_balanced());
+
+    // Team leaderboards
+    let stats = db
+        .get_team_stats(Utc::now() - chrono::Duration::days(1), None)
+        .await
+        .unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].msats_claimed, msats);
+    assert_eq!(stats[0].locations_found, 1);
+    leaderboard::refresh(&db, Utc::now()).await.unwrap();
+    let ranked = db
+        .get_team_leaderboard(Period::Week, "", Metric::Locations)
+        .await
+        .unwrap();
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(ranked[0].name, "Explorers");
+
+    // Leaving and deleting
+    assert!(db.remove_team_member(&team.id, yuri).await.unwrap());
+    assert!(db.get_team_membership(yuri).await.unwrap().is_none());
+    assert!(db.delete_team(&team.id).await.unwrap());
+    assert!(db.get_team_membership(xena).await.unwrap().is_none());
+}
@@ -0,0 +1,224 @@
+//! Invoice policy tests against signed invoices built in the test.
+
+use bitcoin_hashes::{sha256, Hash};
+use lightning_invoice::{Currency, InvoiceBuilder, PaymentSecret};
+use satshunt::db::Database;
+use satshunt::invoice_policy::{InvoicePolicy, InvoicePolicyError};
+use satshunt::lightning::MockLightning;
+use satshunt::models::AuthMethod;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
+
+async fn setup_test_db() -> (Database, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db_url = format!("sqlite:{}", db_path.display());
+    let db = Database::new(&db_url).await.unwrap();
+    (db, temp_dir)
+}
+
+fn payee_key() -> SecretKey {
+    SecretKey::from_slice(&[3u8; 32]).unwrap()
+}
+
+/// Invoice options, each test changes the one it's about
+struct TestInvoice {
+    currency: Currency,
+    amount_msats: Option<u64>,
+    description: String,
+    preimage: &'static [u8],
+    created_secs_ago: u64,
+}
+
+impl Default for TestInvoice {
+    fn default() -> Self {
+        Self {
+            currency: Currency::Bitcoin,
+            amount_msats: Some(10_000),
+            description: "withdrawal".to_string(),
+            preimage: b"preimage",
+            created_secs_ago: 0,
+        }
+    }
+}
+
+impl TestInvoice {
+    fn build(self) -> String {
+        let created = SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+            - Duration::from_secs(self.created_secs_ago);
+        let builder = InvoiceBuilder::new(self.currency)
+            .description(self.description)
+            .payment_hash(sha256::Hash::hash(self.preimage))
+            .payment_secret(PaymentSecret([42u8; 32]))
+            .duration_since_epoch(created)
+            .expiry_time(Duration::from_secs(3600))
+            .min_final_cltv_expiry_delta(144);
+        let builder = match self.amount_msats {
+            Some(msats) => builder.amount_milli_satoshis(msats),
+            None => builder,
+        };
+        builder
+            .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &payee_key()))
+            .unwrap()
+            .to_string()
+    }
+}
+
+fn check(policy: &InvoicePolicy, invoice: &str) -> Result<i64, InvoicePolicyError> {
+    policy
+        .check_invoice(invoice, Currency::Bitcoin)
+        .map(|checked| checked.amount_msats)
+}
+
+#[test]
+fn test_accepts_valid_invoice() {
+    let invoice = TestInvoice::default().build();
+    assert_eq!(check(&InvoicePolicy::default(), &invoice).unwrap(), 10_000);
+}
+
+#[test]
+fn test_rejects_wrong_network() {
+    let invoice = TestInvoice {
+        currency: Currency::Regtest,
+        ..TestInvoice::default()
+    }
+    .build();
+    assert!(matches!(
+        check(&InvoicePolicy::default(), &invoice),
+        Err(InvoicePolicyError::WrongNetwork {
+            expected: "mainnet",
+            found: "regtest"
+        })
+    ));
+}
+
+#[test]
+fn test_rejects_expired_and_amountless() {
+    let expired = TestInvoice {
+        created_secs_ago: 7200,
+        ..TestInvoice::default()
+    }
+    .build();
+    assert!(matches!(
+        check(&InvoicePolicy::default(), &expired),
+        Err(InvoicePolicyError::Expired)
+    ));
+
+    let amountless = TestInvoice {
+        amount_msats: None,
+        ..TestInvoice::default()
+    }
+    .build();
+    assert!(matches!(
+        check(&InvoicePolicy::default(), &amountless),
+        Err(InvoicePolicyError::MissingAmount)
+    ));
+}
+
+#[test]
+fn test_rejects_blocked_payee() {
+    let payee = PublicKey::from_secret_key(&Secp256k1::new(), &payee_key());
+    let invoice = TestInvoice::default().build();
+
+    let blocked = InvoicePolicy::new([payee]);
+    assert!(matches!(
+        check(&blocked, &invoice),
+        Err(InvoicePolicyError::BlockedPayee)
+    ));
+}
+
+#[tokio::test]
+async fn test_rejects_own_invoice() {
+    let (db, _temp) = setup_test_db().await;
+    let invoice = TestInvoice::default().build();
+    let other = TestInvoice {
+        preimage: b"other",
+        ..TestInvoice::default()
+    }
+    .build();
+
+    // Recognized by the backend without configuring its payee
+    let lightning = MockLightning {
+        own_invoices: vec![invoice.clone()],
+        ..MockLightning::default()
+    };
+    let result = InvoicePolicy::default()
+        .check(&invoice, &lightning, &db)
+        .await;
+    assert!(matches!(result, Err(InvoicePolicyError::OwnInvoice)));
+
+    // The same payee is fine on an invoice we didn't create
+    assert!(InvoicePolicy::default()
+        .check(&other, &lightning, &db)
+        .await
+        .is_ok());
+}
+
+#[test]
+fn test_rejects_long_description() {
+    let invoice = TestInvoice {
+        description: "x".repeat(300),
+        ..TestInvoice::default()
+    }
+    .build();
+    assert!(matches!(
+        check(&InvoicePolicy::default(), &invoice),
+        Err(InvoicePolicyError::DescriptionTooLong)
+    ));
+}
+
+#[tokio::test]
+async fn test_rejects_duplicate_payment_hash() {
+    let (db, _temp) = setup_test_db().await;
+    let lightning = MockLightning::new();
+    let policy = InvoicePolicy::default();
+
+    let user = db
+        .create_user(
+            "hunter".to_string(),
+            None,
+            AuthMethod::Password {
+                password_hash: "hash".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    db.create_wallet_invoice(&user.id, "lnbc-funding", 100_000, None)
+        .await
+        .unwrap();
+    db.mark_wallet_invoice_received("lnbc-funding")
+        .await
+        .unwrap();
+
+    let invoice = TestInvoice::default().build();
+    policy.check(&invoice, &lightning, &db).await.unwrap();
+
+    let withdrawal_id = db
+        .create_pending_withdrawal(&user.id, 10_000, 0, &invoice)
+        .await
+        .unwrap()
+        .unwrap();
+    let result = policy.check(&invoice, &lightning, &db).await;
+    assert!(matches!(result, Err(InvoicePolicyError::DuplicatePayment)));
+
+    // A different invoice for the same payment hash is refused too
+    let same_hash = TestInvoice {
+        amount_msats: Some(20_000),
+        ..TestInvoice::default()
+    }
+    .build();
+    let result = policy.check(&same_hash, &lightning, &db).await;
+    assert!(matches!(result, Err(InvoicePolicyError::DuplicatePayment)));
+
+    // Once the withdrawal failed the invoice can be paid again
+    db.fail_pending_withdrawal(&withdrawal_id).await.unwrap();
+    policy.check(&invoice, &lightning, &db).await.unwrap();
+
+    let other = TestInvoice {
+        preimage: b"other",
+        ..TestInvoice::default()
+    }
+    .build();
+    policy.check(&other, &lightning, &db).await.unwrap();
+}
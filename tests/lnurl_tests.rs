@@ -0,0 +1,238 @@
+//! LNURL client tests against a local Lightning address server.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use bitcoin_hashes::{sha256, Hash};
+use lightning_invoice::{Currency, InvoiceBuilder, PaymentSecret};
+use satshunt::lnurl::{self, LnurlClient, LnurlClientConfig, LnurlError};
+use secp256k1::{Secp256k1, SecretKey};
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Local Lightning address server
+struct Server {
+    addr: SocketAddr,
+    /// Number of metadata requests the server answered
+    metadata_requests: AtomicUsize,
+}
+
+fn metadata(user: &str) -> String {
+    lnurl::pay_metadata(&format!("Pay to {}", user), &format!("{}@test", user))
+}
+
+/// Build a signed invoice for `amount_msats` committing to `description`
+fn invoice(amount_msats: u64, description: &str) -> String {
+    let key = SecretKey::from_slice(&[3u8; 32]).unwrap();
+    InvoiceBuilder::new(Currency::Bitcoin)
+        .description_hash(sha256::Hash::hash(description.as_bytes()))
+        .payment_hash(sha256::Hash::hash(b"preimage"))
+        .payment_secret(PaymentSecret([42u8; 32]))
+        .current_timestamp()
+        .min_final_cltv_expiry_delta(144)
+        .amount_milli_satoshis(amount_msats)
+        .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &key))
+        .unwrap()
+        .to_string()
+}
+
+async fn pay_request(State(server): State<Arc<Server>>, Path(user): Path<String>) -> Response {
+    server.metadata_requests.fetch_add(1, Ordering::SeqCst);
+
+    match user.as_str() {
+        "slow" => tokio::time::sleep(Duration::from_secs(2)).await,
+        "redirect" => {
+            return (
+                StatusCode::FOUND,
+                [(header::LOCATION, "/.well-known/lnurlp/alice")],
+            )
+                .into_response()
+        }
+        _ => {}
+    }
+
+    let tag = if user == "badtag" {
+        "withdrawRequest"
+    } else {
+        "payRequest"
+    };
+    let padding = if user == "big" {
+        "x".repeat(10_000)
+    } else {
+        String::new()
+    };
+
+    Json(json!({
+        "tag": tag,
+        "callback": format!("http://{}/callback/{}", server.addr, user),
+        "minSendable": 1000,
+        "maxSendable": 100_000_000,
+        "metadata": metadata(&user),
+        "padding": padding,
+    }))
+    .into_response()
+}
+
+#[derive(Deserialize)]
+struct CallbackParams {
+    amount: u64,
+}
+
+async fn callback(Path(user): Path<String>, Query(params): Query<CallbackParams>) -> Response {
+    let pr = match user.as_str() {
+        "greedy" => invoice(params.amount + 1000, &metadata(&user)),
+        "sloppy" => invoice(params.amount, "something else"),
+        "broke" => {
+            return Json(json!({ "status": "ERROR", "reason": "wallet is full" })).into_response()
+        }
+        _ => invoice(params.amount, &metadata(&user)),
+    };
+    Json(json!({ "pr": pr, "routes": [] })).into_response()
+}
+
+/// Start a Lightning address server on a random local port
+async fn start_server() -> Arc<Server> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server = Arc::new(Server {
+        addr: listener.local_addr().unwrap(),
+        metadata_requests: AtomicUsize::new(0),
+    });
+
+    let app = Router::new()
+        .route("/.well-known/lnurlp/:user", get(pay_request))
+        .route("/callback/:user", get(callback))
+        .with_state(server.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    server
+}
+
+/// Client allowed to talk to the local server
+fn test_client() -> LnurlClient {
+    LnurlClient::new(LnurlClientConfig {
+        allow_private_ips: true,
+        allow_http: true,
+        timeout: Duration::from_millis(500),
+        max_response_bytes: 4096,
+        ..LnurlClientConfig::default()
+    })
+}
+
+fn address(server: &Server, user: &str) -> String {
+    format!("{}@{}", user, server.addr)
+}
+
+#[tokio::test]
+async fn test_get_invoice_for_ln_address() {
+    let server = start_server().await;
+    let client = test_client();
+
+    let pr = client
+        .get_invoice_for_ln_address(&address(&server, "alice"), 21_000)
+        .await
+        .unwrap();
+    let parsed: lightning_invoice::Bolt11Invoice = pr.parse().unwrap();
+    assert_eq!(parsed.amount_milli_satoshis(), Some(21_000));
+}
+
+#[tokio::test]
+async fn test_metadata_is_cached() {
+    let server = start_server().await;
+    let client = test_client();
+
+    for amount in [1000, 2000] {
+        client
+            .get_invoice_for_ln_address(&address(&server, "alice"), amount)
+            .await
+            .unwrap();
+    }
+    assert_eq!(server.metadata_requests.load(Ordering::SeqCst), 1);
+
+    // A client without cache fetches every time
+    let uncached = LnurlClient::new(LnurlClientConfig {
+        allow_private_ips: true,
+        allow_http: true,
+        cache_ttl: Duration::ZERO,
+        ..LnurlClientConfig::default()
+    });
+    for _ in 0..2 {
+        uncached
+            .resolve_ln_address(&address(&server, "alice"))
+            .await
+            .unwrap();
+    }
+    assert_eq!(server.metadata_requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_refuses_private_hosts() {
+    let server = start_server().await;
+    let client = LnurlClient::new(LnurlClientConfig {
+        allow_http: true,
+        ..LnurlClientConfig::default()
+    });
+
+    let result = client.resolve_ln_address(&address(&server, "alice")).await;
+    assert!(matches!(result, Err(LnurlError::ForbiddenHost(_))));
+    assert_eq!(server.metadata_requests.load(Ordering::SeqCst), 0);
+
+    let result = client.resolve_ln_address("alice@10.0.0.1").await;
+    assert!(matches!(result, Err(LnurlError::ForbiddenHost(_))));
+}
+
+#[tokio::test]
+async fn test_rejects_mismatching_invoices() {
+    let server = start_server().await;
+    let client = test_client();
+
+    for user in ["greedy", "sloppy"] {
+        let result = client
+            .get_invoice_for_ln_address(&address(&server, user), 5000)
+            .await;
+        assert!(
+            matches!(result, Err(LnurlError::InvalidResponse(_))),
+            "{}: {:?}",
+            user,
+            result
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_callback_error_reason() {
+    let server = start_server().await;
+    let result = test_client()
+        .get_invoice_for_ln_address(&address(&server, "broke"), 5000)
+        .await;
+    assert!(
+        matches!(result, Err(LnurlError::ResolutionFailed(reason)) if reason == "wallet is full")
+    );
+}
+
+#[tokio::test]
+async fn test_rejects_bad_responses() {
+    let server = start_server().await;
+    let client = test_client();
+
+    // Wrong tag, too large, too slow, and redirects are not followed
+    for user in ["badtag", "big", "slow", "redirect"] {
+        let result = client.resolve_ln_address(&address(&server, user)).await;
+        assert!(result.is_err(), "{} should fail", user);
+    }
+
+    let result = client
+        .get_invoice_for_ln_address(&address(&server, "alice"), 500)
+        .await;
+    assert!(matches!(result, Err(LnurlError::AmountOutOfRange { .. })));
+}
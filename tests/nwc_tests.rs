@@ -0,0 +1,443 @@
+//! Nostr Wallet Connect tests against a local relay with a mock wallet behind it.
+
+use futures_util::{SinkExt, StreamExt};
+use satshunt::db::Database;
+use satshunt::donation::NewDonation;
+use satshunt::lightning::MockLightning;
+use satshunt::models::{AuthMethod, DonationStatus, RecurringDonationStatus};
+use satshunt::nostr::{NostrEvent, RelayAccess};
+use satshunt::nwc::{self, NwcConnection, NwcError, UriCipher};
+use satshunt::recurring::{RecurringDonationService, MAX_FAILURES};
+use secp256k1::{Keypair, Secp256k1, SecretKey, XOnlyPublicKey};
+use serde_json::json;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The test relay is plain ws:// on localhost
+const LOCAL_RELAY: RelayAccess = RelayAccess {
+    allow_private_ips: true,
+    allow_ws: true,
+};
+
+fn cipher() -> UriCipher {
+    UriCipher::new(&[3u8; 64])
+}
+
+const PREIMAGE: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+/// How the mock wallet answers `pay_invoice` requests
+#[derive(Clone, Copy)]
+enum WalletReply {
+    Pay,
+    Refuse,
+    /// Pays, but reports an error; only `lookup_invoice` shows the payment
+    PayAndFail,
+}
+
+fn wallet_keypair() -> Keypair {
+    let secret = SecretKey::from_slice(&[7u8; 32]).unwrap();
+    Keypair::from_secret_key(&Secp256k1::new(), &secret)
+}
+
+/// Start a relay on a random local port, answering requests as the wallet.
+/// Returns the connection URI a donor would paste.
+async fn start_wallet_relay(reply: WalletReply) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let wallet = wallet_keypair();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve_connection(stream, wallet, reply));
+        }
+    });
+
+    format!(
+        "nostr+walletconnect://{}?relay=ws://{}&secret={}",
+        hex::encode(wallet.x_only_public_key().0.serialize()),
+        addr,
+        hex::encode([9u8; 32])
+    )
+}
+
+async fn serve_connection(stream: tokio::net::TcpStream, wallet: Keypair, reply: WalletReply) {
+    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+    let mut subscription = None;
+
+    while let Some(Ok(Message::Text(text))) = ws.next().await {
+        let frame: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+        match frame[0].as_str() {
+            Some("REQ") => subscription = frame[1].as_str().map(str::to_string),
+            Some("EVENT") => {
+                let request: NostrEvent = serde_json::from_value(frame[1].clone()).unwrap();
+                assert!(request.verify());
+                assert_eq!(request.kind, nwc::REQUEST_KIND);
+                ws.send(Message::text(
+                    json!(["OK", request.id, true, ""]).to_string(),
+                ))
+                .await
+                .unwrap();
+
+                let client = XOnlyPublicKey::from_str(&request.pubkey).unwrap();
+                let plaintext =
+                    nwc::nip04_decrypt(&wallet.secret_key(), &client, &request.content).unwrap();
+                let body: serde_json::Value = serde_json::from_str(&plaintext).unwrap();
+                assert!(body["params"]["invoice"].as_str().is_some());
+
+                let result = match (body["method"].as_str().unwrap(), reply) {
+                    ("pay_invoice", WalletReply::Pay) => json!({
+                        "result_type": "pay_invoice",
+                        "result": { "preimage": PREIMAGE },
+                    }),
+                    ("pay_invoice", WalletReply::Refuse) => json!({
+                        "result_type": "pay_invoice",
+                        "error": { "code": "QUOTA_EXCEEDED", "message": "budget used up" },
+                    }),
+                    ("pay_invoice", WalletReply::PayAndFail) => json!({
+                        "result_type": "pay_invoice",
+                        "error": { "code": "INTERNAL", "message": "lost the response" },
+                    }),
+                    ("lookup_invoice", WalletReply::PayAndFail) => json!({
+                        "result_type": "lookup_invoice",
+                        "result": { "preimage": PREIMAGE, "settled_at": 1_700_000_000 },
+                    }),
+                    ("lookup_invoice", _) => json!({
+                        "result_type": "lookup_invoice",
+                        "error": { "code": "NOT_FOUND", "message": "no such invoice" },
+                    }),
+                    (method, _) => panic!("unexpected method {}", method),
+                };
+                let response = NostrEvent::sign(
+                    &wallet,
+                    nwc::RESPONSE_KIND,
+                    vec![
+                        vec!["e".to_string(), request.id.clone()],
+                        vec!["p".to_string(), request.pubkey.clone()],
+                    ],
+                    nwc::nip04_encrypt(&wallet.secret_key(), &client, &result.to_string()),
+                    request.created_at,
+                );
+                ws.send(Message::text(
+                    json!(["EVENT", subscription.clone().unwrap(), response]).to_string(),
+                ))
+                .await
+                .unwrap();
+            }
+            _ => {}
+        }
+    }
+}
+
+async fn setup_test_db() -> (Arc<Database>, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db_url = format!("sqlite:{}", db_path.display());
+    let db = Database::new(&db_url).await.unwrap();
+    (Arc::new(db), temp_dir)
+}
+
+async fn create_location(db: &Database) -> (String, String) {
+    let user = db
+        .create_user(
+            "donor".to_string(),
+            None,
+            AuthMethod::Password {
+                password_hash: "hash".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    let location = db
+        .create_location(
+            "Park".to_string(),
+            51.5,
+            -0.12,
+            None,
+            "secret".to_string(),
+            user.id.clone(),
+        )
+        .await
+        .unwrap();
+    (user.id, location.id)
+}
+
+#[tokio::test]
+async fn test_pay_invoice_returns_preimage() {
+    let uri = start_wallet_relay(WalletReply::Pay).await;
+    let connection = NwcConnection::parse_with(&uri, LOCAL_RELAY).unwrap();
+
+    let preimage = connection
+        .pay_invoice("lnbc10n1test", Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(preimage, PREIMAGE);
+}
+
+#[tokio::test]
+async fn test_local_relay_is_refused_by_default() {
+    let uri = start_wallet_relay(WalletReply::Pay).await;
+    assert!(uri.parse::<NwcConnection>().is_err());
+
+    // Even over wss:// a relay on a private address is never contacted
+    let uri = uri.replace("relay=ws://", "relay=wss://");
+    let connection: NwcConnection = uri.parse().unwrap();
+    let err = connection
+        .pay_invoice("lnbc10n1test", Duration::from_secs(5))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not allowed"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_pay_invoice_surfaces_wallet_error() {
+    let uri = start_wallet_relay(WalletReply::Refuse).await;
+    let connection = NwcConnection::parse_with(&uri, LOCAL_RELAY).unwrap();
+
+    let err = connection
+        .pay_invoice("lnbc10n1test", Duration::from_secs(5))
+        .await
+        .unwrap_err();
+    match err {
+        NwcError::Wallet { code, .. } => assert_eq!(code, "QUOTA_EXCEEDED"),
+        other => panic!("unexpected error: {}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_recurring_donation_is_collected() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, location_id) = create_location(&db).await;
+    let uri = start_wallet_relay(WalletReply::Pay).await;
+
+    let recurring = db
+        .create_recurring_donation(
+            &user_id,
+            Some(&location_id),
+            21_000,
+            7,
+            &cipher().encrypt(&uri),
+        )
+        .await
+        .unwrap();
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<NewDonation>();
+    let service = RecurringDonationService::new(
+        db.clone(),
+        Arc::new(MockLightning::new()),
+        sender,
+        cipher(),
+        LOCAL_RELAY,
+    );
+    assert_eq!(service.process_due().await.unwrap(), 1);
+
+    // The payment is handed to the donation service like any other donation
+    let new_donation = receiver.try_recv().unwrap();
+    assert_eq!(new_donation.amount_msats, 21_000);
+    assert_eq!(
+        new_donation.location_id.as_deref(),
+        Some(location_id.as_str())
+    );
+    let donation = db
+        .get_donation_by_invoice(&new_donation.invoice)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(donation.status, DonationStatus::Created);
+
+    // The next payment is scheduled one interval later and nothing is due now
+    let updated = db
+        .get_recurring_donation(&recurring.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(updated.last_payment_at.is_some());
+    assert!(updated.next_payment_at > recurring.next_payment_at + chrono::Duration::days(6));
+    assert_eq!(service.process_due().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_recurring_donation_stops_after_repeated_failures() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, location_id) = create_location(&db).await;
+    let uri = start_wallet_relay(WalletReply::Refuse).await;
+
+    let recurring = db
+        .create_recurring_donation(
+            &user_id,
+            Some(&location_id),
+            21_000,
+            7,
+            &cipher().encrypt(&uri),
+        )
+        .await
+        .unwrap();
+
+    let (sender, _receiver) = mpsc::unbounded_channel::<NewDonation>();
+    let service = RecurringDonationService::new(
+        db.clone(),
+        Arc::new(MockLightning::new()),
+        sender,
+        cipher(),
+        LOCAL_RELAY,
+    );
+    assert_eq!(service.process_due().await.unwrap(), 0);
+
+    // The first failure backs off instead of stopping the donation
+    let updated = db
+        .get_recurring_donation(&recurring.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.status, RecurringDonationStatus::Active);
+    assert_eq!(updated.failure_count, 1);
+    assert!(updated
+        .last_error
+        .as_deref()
+        .is_some_and(|e| e.contains("budget used up")));
+    assert!(updated.next_payment_at > chrono::Utc::now());
+
+    // Reaching the limit stops it for good
+    let mut updated = updated;
+    for _ in updated.failure_count..MAX_FAILURES {
+        assert_eq!(updated.status, RecurringDonationStatus::Active);
+        updated = db
+            .mark_recurring_payment_failed(
+                &recurring.id,
+                "still failing",
+                chrono::Utc::now(),
+                MAX_FAILURES,
+            )
+            .await
+            .unwrap();
+    }
+    assert_eq!(updated.failure_count, MAX_FAILURES);
+    assert_eq!(updated.status, RecurringDonationStatus::Failed);
+    assert!(db.list_due_recurring_donations().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_cancel_recurring_donation_requires_owner() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, location_id) = create_location(&db).await;
+
+    let recurring = db
+        .create_recurring_donation(
+            &user_id,
+            Some(&location_id),
+            1_000,
+            30,
+            "nostr+walletconnect://unused",
+        )
+        .await
+        .unwrap();
+
+    let result = db
+        .cancel_recurring_donation(&recurring.id, "someone-else")
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 0);
+
+    let result = db
+        .cancel_recurring_donation(&recurring.id, &user_id)
+        .await
+        .unwrap();
+    assert_eq!(result.rows_affected(), 1);
+
+    let cancelled = db
+        .get_recurring_donation(&recurring.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(cancelled.status, RecurringDonationStatus::Cancelled);
+    assert!(db
+        .list_active_recurring_donations(Some(&location_id))
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_plaintext_uris_are_encrypted() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, location_id) = create_location(&db).await;
+    let uri = start_wallet_relay(WalletReply::Pay).await;
+
+    // Stored before URIs were encrypted
+    let recurring = db
+        .create_recurring_donation(&user_id, Some(&location_id), 21_000, 7, &uri)
+        .await
+        .unwrap();
+
+    assert_eq!(db.encrypt_recurring_nwc_uris(&cipher()).await.unwrap(), 1);
+    let stored = db
+        .get_recurring_donation(&recurring.id)
+        .await
+        .unwrap()
+        .unwrap()
+        .nwc_uri;
+    assert!(UriCipher::is_encrypted(&stored));
+    assert_eq!(cipher().decrypt(&stored).unwrap(), uri);
+
+    // Already encrypted URIs are left alone
+    assert_eq!(db.encrypt_recurring_nwc_uris(&cipher()).await.unwrap(), 0);
+
+    // And the scheduler can still pay with it
+    let (sender, _receiver) = mpsc::unbounded_channel::<NewDonation>();
+    let service = RecurringDonationService::new(
+        db.clone(),
+        Arc::new(MockLightning::new()),
+        sender,
+        cipher(),
+        LOCAL_RELAY,
+    );
+    assert_eq!(service.process_due().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_payment_is_looked_up_before_paying_again() {
+    let (db, _temp) = setup_test_db().await;
+    let (user_id, location_id) = create_location(&db).await;
+    let uri = start_wallet_relay(WalletReply::PayAndFail).await;
+
+    let recurring = db
+        .create_recurring_donation(
+            &user_id,
+            Some(&location_id),
+            21_000,
+            7,
+            &cipher().encrypt(&uri),
+        )
+        .await
+        .unwrap();
+
+    let (sender, mut receiver) = mpsc::unbounded_channel::<NewDonation>();
+    let service = RecurringDonationService::new(
+        db.clone(),
+        Arc::new(MockLightning::new()),
+        sender,
+        cipher(),
+        LOCAL_RELAY,
+    );
+    assert_eq!(service.process_due().await.unwrap(), 0);
+    assert!(receiver.try_recv().is_ok());
+
+    // Retry now: the wallet says the first invoice was paid, so no new one is made
+    db.mark_recurring_payment_failed(&recurring.id, "retry", chrono::Utc::now(), MAX_FAILURES)
+        .await
+        .unwrap();
+    assert_eq!(service.process_due().await.unwrap(), 1);
+    assert!(receiver.try_recv().is_err());
+
+    let updated = db
+        .get_recurring_donation(&recurring.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.failure_count, 0);
+    assert!(updated.next_payment_at > chrono::Utc::now());
+}
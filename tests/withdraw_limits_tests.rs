@@ -0,0 +1,184 @@
+//! Withdrawal limit tests. Withdrawals are reserved directly in the database to
+//! stand in for earlier withdrawals.
+
+use satshunt::db::Database;
+use satshunt::models::AuthMethod;
+use satshunt::withdraw_limits::WithdrawLimits;
+use tempfile::TempDir;
+
+async fn setup_test_db() -> (Database, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db_url = format!("sqlite:{}", db_path.display());
+    let db = Database::new(&db_url).await.unwrap();
+    (db, temp_dir)
+}
+
+/// Create a user with `balance_msats` in their wallet
+async fn create_user_with_balance(
+    db: &Database,
+    name: &str,
+    auth: AuthMethod,
+    balance_msats: i64,
+) -> String {
+    let user = db.create_user(name.to_string(), None, auth).await.unwrap();
+    let funding = format!("lnbc-funding-{}", name);
+    db.create_wallet_invoice(&user.id, &funding, balance_msats, None)
+        .await
+        .unwrap();
+    db.mark_wallet_invoice_received(&funding).await.unwrap();
+    user.id
+}
+
+fn password() -> AuthMethod {
+    AuthMethod::Password {
+        password_hash: "hash".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_no_limits_by_default() {
+    let (db, _temp) = setup_test_db().await;
+    let user_id = create_user_with_balance(&db, "hunter", password(), 1_000_000).await;
+
+    let limits = WithdrawLimits::default();
+    assert_eq!(
+        limits.hold_reason(&db, &user_id, 900_000).await.unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_per_withdrawal_and_daily_limits() {
+    let (db, _temp) = setup_test_db().await;
+    let alice = create_user_with_balance(&db, "alice", password(), 1_000_000).await;
+    let bob = create_user_with_balance(&db, "bob", password(), 1_000_000).await;
+
+    let limits = WithdrawLimits {
+        max_withdrawal_msats: Some(100_000),
+        user_daily_msats: Some(150_000),
+        global_daily_msats: Some(250_000),
+        ..WithdrawLimits::default()
+    };
+
+    assert!(limits
+        .hold_reason(&db, &alice, 100_000)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(limits
+        .hold_reason(&db, &alice, 100_001)
+        .await
+        .unwrap()
+        .is_some_and(|reason| reason.contains("per withdrawal")));
+
+    // Alice withdrew 100 sats today, another 60 is over her daily limit
+    db.create_pending_withdrawal(&alice, 100_000, 2_000, "lnbc-out-1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(limits
+        .hold_reason(&db, &alice, 60_000)
+        .await
+        .unwrap()
+        .is_some_and(|reason| reason.contains("User would withdraw 160 sats")));
+
+    // Bob is within his own limit, but not everyone's together
+    db.create_pending_withdrawal(&bob, 100_000, 2_000, "lnbc-out-2")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(limits
+        .hold_reason(&db, &bob, 40_000)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(limits
+        .hold_reason(&db, &bob, 50_001)
+        .await
+        .unwrap()
+        .is_some_and(|reason| reason.contains("All users")));
+
+    // Failed withdrawals don't count
+    let failed = db
+        .create_pending_withdrawal(&bob, 40_000, 2_000, "lnbc-out-3")
+        .await
+        .unwrap()
+        .unwrap();
+    db.fail_pending_withdrawal(&failed).await.unwrap();
+    assert!(limits
+        .hold_reason(&db, &bob, 40_000)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_new_anonymous_users() {
+    let (db, _temp) = setup_test_db().await;
+    let anon = create_user_with_balance(&db, "anon", AuthMethod::Anonymous {}, 1_000_000).await;
+    let registered = create_user_with_balance(&db, "carol", password(), 1_000_000).await;
+
+    let limits = WithdrawLimits {
+        new_anon_msats: Some(50_000),
+        ..WithdrawLimits::default()
+    };
+
+    assert!(limits
+        .hold_reason(&db, &anon, 60_000)
+        .await
+        .unwrap()
+        .is_some_and(|reason| reason.contains("New anonymous user")));
+    assert!(limits
+        .hold_reason(&db, &anon, 50_000)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(limits
+        .hold_reason(&db, &registered, 60_000)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Once the window has passed the limit no longer applies
+    let expired = WithdrawLimits {
+        new_anon_window: chrono::Duration::zero(),
+        ..limits
+    };
+    assert!(expired
+        .hold_reason(&db, &anon, 60_000)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_reserving_holds_withdrawals_over_a_limit() {
+    let (db, _temp) = setup_test_db().await;
+    let user_id = create_user_with_balance(&db, "hunter", password(), 1_000_000).await;
+
+    let limits = WithdrawLimits {
+        user_daily_msats: Some(150_000),
+        ..WithdrawLimits::default()
+    };
+
+    let (_, reason) = db
+        .reserve_wallet_withdrawal(&user_id, 100_000, 2_000, "lnbc-out-1", &limits)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(reason.is_none());
+
+    // The first withdrawal counts towards the limit in the same check that
+    // reserves the second one
+    let (held_id, reason) = db
+        .reserve_wallet_withdrawal(&user_id, 60_000, 2_000, "lnbc-out-2", &limits)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(reason.is_some_and(|reason| reason.contains("User would withdraw 160 sats")));
+    let held = db.list_held_withdrawals().await.unwrap();
+    assert_eq!(held.len(), 1);
+    assert_eq!(held[0].id, held_id);
+    assert_eq!(db.get_user_balance(&user_id).await.unwrap(), 836_000);
+}
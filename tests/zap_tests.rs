@@ -0,0 +1,194 @@
+//! Zap receipt tests against a local relay that records what it is sent.
+
+use futures_util::{SinkExt, StreamExt};
+use satshunt::db::Database;
+use satshunt::models::AuthMethod;
+use satshunt::nostr::{NostrEvent, RelayAccess};
+use satshunt::zap::{self, ZapService};
+use secp256k1::{Keypair, Secp256k1, SecretKey};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The test relay is plain ws:// on localhost
+const LOCAL_RELAY: RelayAccess = RelayAccess {
+    allow_private_ips: true,
+    allow_ws: true,
+};
+
+fn keypair(byte: u8) -> Keypair {
+    let secret = SecretKey::from_slice(&[byte; 32]).unwrap();
+    Keypair::from_secret_key(&Secp256k1::new(), &secret)
+}
+
+fn pubkey_hex(keys: &Keypair) -> String {
+    hex::encode(keys.x_only_public_key().0.serialize())
+}
+
+/// Start a relay on a random local port that accepts every event.
+/// Returns its URL and the events it received.
+async fn start_relay() -> (String, Arc<Mutex<Vec<NostrEvent>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    tokio::spawn({
+        let events = events.clone();
+        async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let events = events.clone();
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(Message::Text(text))) = ws.next().await {
+                        let frame: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+                        if frame[0] == "EVENT" {
+                            let event: NostrEvent =
+                                serde_json::from_value(frame[1].clone()).unwrap();
+                            let reply = json!(["OK", event.id, true, ""]).to_string();
+                            events.lock().unwrap().push(event);
+                            ws.send(Message::text(reply)).await.unwrap();
+                        }
+                    }
+                });
+            }
+        }
+    });
+
+    (format!("ws://{}", addr), events)
+}
+
+async fn setup_test_db() -> (Arc<Database>, TempDir) {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+    let db_url = format!("sqlite:{}", db_path.display());
+    let db = Database::new(&db_url).await.unwrap();
+    (Arc::new(db), temp_dir)
+}
+
+async fn create_location(db: &Database) -> String {
+    let user = db
+        .create_user(
+            "owner".to_string(),
+            None,
+            AuthMethod::Password {
+                password_hash: "hash".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    db.create_location(
+        "Park".to_string(),
+        51.5,
+        -0.12,
+        None,
+        "secret".to_string(),
+        user.id,
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+#[tokio::test]
+async fn test_zap_receipt_is_published_once() {
+    let (db, _temp) = setup_test_db().await;
+    let location_id = create_location(&db).await;
+    let (relay, events) = start_relay().await;
+
+    let server = keypair(1);
+    let sender = keypair(2);
+    let request = NostrEvent::sign(
+        &sender,
+        zap::ZAP_REQUEST_KIND,
+        vec![
+            vec!["p".to_string(), pubkey_hex(&server)],
+            vec!["amount".to_string(), "21000".to_string()],
+            vec!["relays".to_string(), relay.clone()],
+        ],
+        "great spot!".to_string(),
+        1_700_000_000,
+    );
+    let request_json = serde_json::to_string(&request).unwrap();
+    zap::validate_zap_request(&request_json, 21_000, &server.x_only_public_key().0).unwrap();
+
+    db.create_lnurl_pay_donation(
+        "lnbc210n1zap".to_string(),
+        21_000,
+        Some(&location_id),
+        Some("great spot!"),
+        Some(&request_json),
+    )
+    .await
+    .unwrap();
+
+    // Nothing is published before the donation is paid
+    assert!(db.list_unpublished_zaps().await.unwrap().is_empty());
+    let donation = db
+        .mark_donation_received("lnbc210n1zap")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(db.list_unpublished_zaps().await.unwrap().len(), 1);
+
+    // The relay from the zap request is used even with none configured
+    let service = ZapService::new(db.clone(), server, Vec::new(), LOCAL_RELAY);
+    let receipt = service.publish_receipt(&donation).await.unwrap();
+
+    let published = events.lock().unwrap().clone();
+    assert_eq!(published.len(), 1);
+    assert_eq!(published[0].id, receipt.id);
+    assert!(published[0].verify());
+    assert_eq!(published[0].kind, zap::ZAP_RECEIPT_KIND);
+    assert_eq!(published[0].pubkey, pubkey_hex(&server));
+    assert_eq!(published[0].tag("bolt11"), Some("lnbc210n1zap"));
+    assert_eq!(published[0].tag("description"), Some(request_json.as_str()));
+    assert_eq!(published[0].tag("P"), Some(pubkey_hex(&sender).as_str()));
+
+    // Published receipts are not sent again after a restart
+    assert!(db.list_unpublished_zaps().await.unwrap().is_empty());
+    service.publish_pending().await;
+    assert_eq!(events.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_zap_receipt_is_not_sent_to_private_relays() {
+    let (db, _temp) = setup_test_db().await;
+    let location_id = create_location(&db).await;
+    let (relay, events) = start_relay().await;
+
+    let server = keypair(1);
+    let request = NostrEvent::sign(
+        &keypair(2),
+        zap::ZAP_REQUEST_KIND,
+        vec![
+            vec!["p".to_string(), pubkey_hex(&server)],
+            vec!["relays".to_string(), relay.clone()],
+            vec!["relays".to_string(), relay.replace("ws://", "wss://")],
+        ],
+        String::new(),
+        1_700_000_000,
+    );
+    let request_json = serde_json::to_string(&request).unwrap();
+    db.create_lnurl_pay_donation(
+        "lnbc210n1zap".to_string(),
+        21_000,
+        Some(&location_id),
+        None,
+        Some(&request_json),
+    )
+    .await
+    .unwrap();
+    let donation = db
+        .mark_donation_received("lnbc210n1zap")
+        .await
+        .unwrap()
+        .unwrap();
+
+    // A zap request can't make the server connect to its local network
+    let service = ZapService::new(db.clone(), server, Vec::new(), RelayAccess::default());
+    assert!(service.publish_receipt(&donation).await.is_err());
+    assert!(events.lock().unwrap().is_empty());
+    assert_eq!(db.list_unpublished_zaps().await.unwrap().len(), 1);
+}